@@ -153,12 +153,14 @@ fn setup_routes(router: &mut Router) {
       location: ParameterLocation::Query,
       description: Some("Maximum number of users to return (default: 10)".to_string()),
       required: false,
+      kind: Some("integer".to_string()),
     })
     .parameter(OpenApiParameter {
       name: "offset".to_string(),
       location: ParameterLocation::Query,
       description: Some("Number of users to skip (default: 0)".to_string()),
       required: false,
+      kind: Some("integer".to_string()),
     })
     .response(200, "List of users");
 
@@ -174,6 +176,7 @@ fn setup_routes(router: &mut Router) {
       location: ParameterLocation::Path,
       description: Some("Unique user identifier".to_string()),
       required: true,
+      kind: None,
     })
     .response(200, "User found")
     .response(404, "User not found");
@@ -194,16 +197,19 @@ fn setup_routes(router: &mut Router) {
           name: "id".to_string(),
           property_type: "integer".to_string(),
           description: Some("Unique user identifier".to_string()),
+          required: true,
         },
         RequestBodyProperty {
           name: "name".to_string(),
           property_type: "string".to_string(),
           description: Some("User's full name".to_string()),
+          required: true,
         },
         RequestBodyProperty {
           name: "email".to_string(),
           property_type: "string".to_string(),
           description: Some("User's email address".to_string()),
+          required: true,
         },
       ],
     })
@@ -222,6 +228,7 @@ fn setup_routes(router: &mut Router) {
       location: ParameterLocation::Path,
       description: Some("User ID to delete".to_string()),
       required: true,
+      kind: None,
     })
     .response(204, "User deleted successfully")
     .response(404, "User not found")
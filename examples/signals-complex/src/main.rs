@@ -57,7 +57,8 @@ fn init_app_signals() {
     println!("[signals-complex] server.started: {:?}", signal.metadata);
   });
 
-  // Metrics-style listener for completed requests
+  // Log completed requests. For request metrics, mount
+  // `plugins::metrics::HttpMetrics` instead of aggregating these by hand.
   let mut rx = arbiter.subscribe(ids::REQUEST_COMPLETED);
   tokio::spawn(async move {
     while let Ok(signal) = rx.recv().await {
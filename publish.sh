@@ -1,6 +1,6 @@
 #!/usr/bin/env bash
 # Publish all tako sub-crates to crates.io in topological order, then publish
-# the umbrella `tako-rs` crate.
+# the umbrella `tako-rs` crate and the `tako-rs-cli` binary.
 #
 # Usage:
 #   ./publish.sh                 # publish for real (runs gate: fmt + clippy + tests)
@@ -67,7 +67,8 @@ PUBLISH_ORDER=(
   "tako-rs-server-pt"  # tako-rs-core
   "tako-rs-streams"    # tako-rs-core, tako-rs-server
   "tako-rs-plugins"    # tako-rs-core, tako-rs-extractors
-  "tako-rs"            # umbrella — last
+  "tako-rs"            # umbrella
+  "tako-rs-cli"        # standalone binary, no internal deps
 )
 
 local_version() {
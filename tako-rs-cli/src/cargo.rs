@@ -0,0 +1,72 @@
+//! Building the application through `cargo` and locating its executable.
+
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+
+use serde_json::Value;
+
+/// Runs `cargo build` for the package in the current directory.
+///
+/// Compiler output goes to the terminal as usual. Returns the built binary,
+/// or `None` when the build failed.
+pub(crate) fn build(bin: Option<&str>) -> io::Result<Option<PathBuf>> {
+  let mut cmd = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+  cmd
+    .args(["build", "--message-format=json-render-diagnostics"])
+    .stdout(Stdio::piped());
+  if let Some(bin) = bin {
+    cmd.args(["--bin", bin]);
+  }
+  let mut child = cmd.spawn()?;
+  let stdout = child.stdout.take().expect("piped");
+  let mut executables = Vec::new();
+  for line in BufReader::new(stdout).lines() {
+    if let Some(exe) = executable(&line?) {
+      executables.push(exe);
+    }
+  }
+  if !child.wait()?.success() {
+    return Ok(None);
+  }
+  match executables.len() {
+    0 => Err(io::Error::other("the build produced no binary")),
+    1 => Ok(executables.pop()),
+    _ => Err(io::Error::other(
+      "the package has several binaries; pick one with --bin",
+    )),
+  }
+}
+
+/// The `executable` of a `compiler-artifact` message for a `bin` target.
+fn executable(line: &str) -> Option<PathBuf> {
+  let msg: Value = serde_json::from_str(line).ok()?;
+  if msg["reason"] != "compiler-artifact" {
+    return None;
+  }
+  let kinds = msg["target"]["kind"].as_array()?;
+  if !kinds.iter().any(|k| k == "bin") {
+    return None;
+  }
+  msg["executable"].as_str().map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn picks_bin_artifacts_only() {
+    let bin = r#"{"reason":"compiler-artifact","target":{"kind":["bin"],"name":"app"},"executable":"/t/debug/app"}"#;
+    let lib =
+      r#"{"reason":"compiler-artifact","target":{"kind":["lib"],"name":"dep"},"executable":null}"#;
+    let done = r#"{"reason":"build-finished","success":true}"#;
+    assert_eq!(executable(bin), Some(PathBuf::from("/t/debug/app")));
+    assert_eq!(executable(lib), None);
+    assert_eq!(executable(done), None);
+    assert_eq!(executable("Compiling app"), None);
+  }
+}
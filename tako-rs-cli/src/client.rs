@@ -0,0 +1,80 @@
+//! `tako client`: run the application with `TAKO_GENERATE_CLIENT` set and
+//! write the typed API client its transport emits instead of serving.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::routes;
+
+/// Mirrors `tako::codegen::GENERATE_CLIENT_ENV`.
+pub(crate) const GENERATE_CLIENT_ENV: &str = "TAKO_GENERATE_CLIENT";
+/// Mirrors `tako::codegen::CLIENT_LINE_PREFIX`.
+pub(crate) const CLIENT_LINE_PREFIX: &str = "tako-client";
+
+pub(crate) fn run(
+  bin: Option<&str>,
+  lang: &str,
+  out: Option<&Path>,
+  timeout: Duration,
+) -> io::Result<()> {
+  let output = routes::capture(bin, GENERATE_CLIENT_ENV, lang, timeout)?;
+  let Some(client) = parse(&output) else {
+    return Err(io::Error::other(
+      "no client reported; does the application serve a tako Router?",
+    ));
+  };
+  match out {
+    Some(path) => {
+      if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+      }
+      fs::write(path, client)?;
+      eprintln!("tako: wrote {}", path.display());
+    }
+    None => print!("{client}"),
+  }
+  Ok(())
+}
+
+/// The generated source in the application's output; other lines are the
+/// application's own and are ignored.
+fn parse(out: &str) -> Option<String> {
+  let mut client = String::new();
+  for line in out.lines() {
+    if let Some(code) = line
+      .strip_prefix(CLIENT_LINE_PREFIX)
+      .and_then(|rest| rest.strip_prefix('\t'))
+    {
+      client.push_str(code);
+      client.push('\n');
+    }
+  }
+  (!client.is_empty()).then_some(client)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn protocol_matches_the_generator() {
+    assert_eq!(
+      GENERATE_CLIENT_ENV,
+      tako_rs_core::codegen::GENERATE_CLIENT_ENV
+    );
+    assert_eq!(
+      CLIENT_LINE_PREFIX,
+      tako_rs_core::codegen::CLIENT_LINE_PREFIX
+    );
+  }
+
+  #[test]
+  fn collects_client_lines_among_application_output() {
+    let out =
+      "starting up\ntako-client\texport class ApiClient {\ntako-client\t\ntako-client\t}\nbye\n";
+    assert_eq!(parse(out).unwrap(), "export class ApiClient {\n\n}\n");
+    assert_eq!(parse("starting up\n"), None);
+  }
+}
@@ -0,0 +1,127 @@
+//! `tako dev`: build, run, watch, rebuild, restart.
+
+use std::io;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::cargo;
+use crate::watch::Watcher;
+
+/// How long a stopped application gets to drain before it is killed.
+const GRACE: Duration = Duration::from_secs(5);
+
+pub(crate) struct Options<'a> {
+  pub(crate) bin: Option<&'a str>,
+  pub(crate) socket: Option<SocketAddr>,
+  pub(crate) watch: Vec<PathBuf>,
+  pub(crate) poll: Duration,
+  pub(crate) args: Vec<String>,
+}
+
+pub(crate) fn run(opts: &Options<'_>) -> io::Result<()> {
+  if cfg!(not(unix)) && opts.socket.is_some() {
+    return Err(io::Error::new(
+      io::ErrorKind::Unsupported,
+      "--socket is only supported on Unix",
+    ));
+  }
+  let listener = opts.socket.map(TcpListener::bind).transpose()?;
+  if let Some(l) = &listener {
+    eprintln!("tako dev: holding {} across restarts", l.local_addr()?);
+  }
+
+  let mut roots: Vec<PathBuf> = ["src", "Cargo.toml", "build.rs"]
+    .into_iter()
+    .map(PathBuf::from)
+    .collect();
+  roots.extend(opts.watch.iter().cloned());
+  let mut watcher = Watcher::new(roots, opts.poll);
+  let mut app: Option<Child> = None;
+
+  loop {
+    // Build while the previous version keeps serving; only swap on success.
+    match cargo::build(opts.bin)? {
+      Some(exe) => {
+        if let Some(old) = app.take() {
+          stop(old)?;
+        }
+        eprintln!("tako dev: starting {}", exe.display());
+        app = Some(spawn(&exe, &opts.args, listener.as_ref())?);
+      }
+      None => eprintln!("tako dev: build failed, waiting for changes"),
+    }
+    watcher.wait_for_change();
+    eprintln!("tako dev: change detected, rebuilding");
+  }
+}
+
+fn spawn(exe: &PathBuf, args: &[String], listener: Option<&TcpListener>) -> io::Result<Child> {
+  let mut cmd = Command::new(exe);
+  cmd.args(args);
+  #[cfg(unix)]
+  if let Some(listener) = listener {
+    pass_listener(&mut cmd, listener);
+  }
+  #[cfg(not(unix))]
+  let _ = listener;
+  cmd.spawn()
+}
+
+/// Hands `listener` to the child as its first `LISTEN_FDS` socket.
+///
+/// `LISTEN_PID` is left unset (the child's pid is unknown before it forks);
+/// `listenfd` treats that as "meant for this process".
+#[cfg(unix)]
+fn pass_listener(cmd: &mut Command, listener: &TcpListener) {
+  use std::os::fd::AsRawFd;
+  use std::os::unix::process::CommandExt;
+
+  let fd = listener.as_raw_fd();
+  cmd
+    .env("LISTEN_FDS", "1")
+    .env("LISTEN_FDS_FIRST_FD", fd.to_string())
+    .env_remove("LISTEN_PID");
+  // SAFETY: runs in the forked child before exec and only calls `fcntl`,
+  // which is async-signal-safe. Clearing `FD_CLOEXEC` lets the socket (std
+  // opens it close-on-exec) survive into the application; the parent's copy
+  // is untouched.
+  unsafe {
+    cmd.pre_exec(move || {
+      if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+        return Err(io::Error::last_os_error());
+      }
+      Ok(())
+    });
+  }
+}
+
+/// Asks the application to shut down (`SIGTERM` on Unix) and kills it if it
+/// is still running after [`GRACE`].
+fn stop(mut child: Child) -> io::Result<()> {
+  #[cfg(unix)]
+  {
+    use std::thread;
+    use std::time::Instant;
+
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+      // SAFETY: `kill` has no memory-safety preconditions; `pid` is our own
+      // child, which has not been reaped yet.
+      unsafe {
+        libc::kill(pid, libc::SIGTERM);
+      }
+    }
+    let deadline = Instant::now() + GRACE;
+    while Instant::now() < deadline {
+      if child.try_wait()?.is_some() {
+        return Ok(());
+      }
+      thread::sleep(Duration::from_millis(50));
+    }
+  }
+  child.kill()?;
+  child.wait().map(drop)
+}
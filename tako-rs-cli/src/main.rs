@@ -0,0 +1,183 @@
+//! `tako` — command-line companion for tako-rs applications.
+//!
+//! - `tako new <name>` scaffolds a runnable project.
+//! - `tako dev` rebuilds and restarts the application whenever its sources
+//!   change. With `--socket` the listening socket is owned by the CLI and
+//!   handed to each restart through `LISTEN_FDS`, so the port never closes
+//!   between builds.
+//! - `tako routes` prints the application's route table without serving.
+//! - `tako client` generates a typed TypeScript or Rust API client from the
+//!   application's routes without serving.
+
+mod cargo;
+mod client;
+mod dev;
+mod new;
+mod routes;
+mod watch;
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::Arg;
+use clap::ArgAction;
+use clap::ArgMatches;
+use clap::Command;
+use clap::value_parser;
+
+fn cli() -> Command {
+  let bin = Arg::new("bin")
+    .long("bin")
+    .value_name("NAME")
+    .help("Binary target to build and run (required when the package has several)");
+  let timeout = Arg::new("timeout-secs")
+    .long("timeout-secs")
+    .value_name("SECS")
+    .default_value("30")
+    .value_parser(value_parser!(u64))
+    .help("Give up if the application has not reached `serve` in time");
+  Command::new("tako")
+    .about("Scaffold, run, and inspect tako-rs applications")
+    .version(env!("CARGO_PKG_VERSION"))
+    .subcommand_required(true)
+    .arg_required_else_help(true)
+    .subcommand(
+      Command::new("new")
+        .about("Create a new tako-rs project")
+        .arg(
+          Arg::new("name")
+            .required(true)
+            .help("Package and directory name"),
+        )
+        .arg(
+          Arg::new("path")
+            .long("path")
+            .value_name("DIR")
+            .value_parser(value_parser!(PathBuf))
+            .help("Directory to create (default: ./<name>)"),
+        ),
+    )
+    .subcommand(
+      Command::new("dev")
+        .about("Rebuild and restart the application when sources change")
+        .arg(bin.clone())
+        .arg(
+          Arg::new("socket")
+            .long("socket")
+            .value_name("ADDR")
+            .value_parser(value_parser!(SocketAddr))
+            .help("Hold this TCP address open across restarts and pass it via LISTEN_FDS"),
+        )
+        .arg(
+          Arg::new("watch")
+            .long("watch")
+            .short('w')
+            .value_name("PATH")
+            .action(ArgAction::Append)
+            .value_parser(value_parser!(PathBuf))
+            .help("Extra paths to watch (default: src, Cargo.toml, build.rs)"),
+        )
+        .arg(
+          Arg::new("poll-ms")
+            .long("poll-ms")
+            .value_name("MS")
+            .default_value("300")
+            .value_parser(value_parser!(u64))
+            .help("How often to scan watched paths"),
+        )
+        .arg(
+          Arg::new("args")
+            .num_args(0..)
+            .last(true)
+            .help("Arguments passed to the application after `--`"),
+        ),
+    )
+    .subcommand(
+      Command::new("routes")
+        .about("Print the application's route table")
+        .arg(bin.clone())
+        .arg(timeout.clone()),
+    )
+    .subcommand(
+      Command::new("client")
+        .about("Generate a typed API client from the application's routes")
+        .arg(bin)
+        .arg(
+          Arg::new("lang")
+            .long("lang")
+            .value_name("LANG")
+            .default_value("typescript")
+            .value_parser(["typescript", "ts", "rust", "rs"])
+            .help("Target language"),
+        )
+        .arg(
+          Arg::new("out")
+            .long("out")
+            .short('o')
+            .value_name("FILE")
+            .value_parser(value_parser!(PathBuf))
+            .help("Write the client here instead of stdout"),
+        )
+        .arg(timeout),
+    )
+}
+
+fn main() -> ExitCode {
+  let matches = cli().get_matches();
+  let result = match matches.subcommand() {
+    Some(("new", m)) => new::run(
+      m.get_one::<String>("name").expect("required"),
+      m.get_one::<PathBuf>("path").cloned(),
+    ),
+    Some(("dev", m)) => dev::run(&dev_options(m)),
+    Some(("routes", m)) => routes::run(bin(m), timeout(m)),
+    Some(("client", m)) => client::run(
+      bin(m),
+      m.get_one::<String>("lang").expect("defaulted"),
+      m.get_one::<PathBuf>("out").map(PathBuf::as_path),
+      timeout(m),
+    ),
+    _ => unreachable!("subcommand_required"),
+  };
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(e) => {
+      eprintln!("tako: {e}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn bin(m: &ArgMatches) -> Option<&str> {
+  m.get_one::<String>("bin").map(String::as_str)
+}
+
+fn timeout(m: &ArgMatches) -> Duration {
+  Duration::from_secs(*m.get_one::<u64>("timeout-secs").expect("defaulted"))
+}
+
+fn dev_options(m: &ArgMatches) -> dev::Options<'_> {
+  dev::Options {
+    bin: bin(m),
+    socket: m.get_one::<SocketAddr>("socket").copied(),
+    watch: m
+      .get_many::<PathBuf>("watch")
+      .map(|v| v.cloned().collect())
+      .unwrap_or_default(),
+    poll: Duration::from_millis(*m.get_one::<u64>("poll-ms").expect("defaulted")),
+    args: m
+      .get_many::<String>("args")
+      .map(|v| v.cloned().collect())
+      .unwrap_or_default(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn command_definition_is_consistent() {
+    super::cli().debug_assert();
+  }
+}
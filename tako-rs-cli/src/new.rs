@@ -0,0 +1,107 @@
+//! `tako new`: scaffold a runnable project.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const MAIN_RS: &str = r#"use anyhow::Result;
+use tako::Method;
+use tako::responder::Responder;
+use tako::router::Router;
+
+async fn hello() -> impl Responder {
+  "Hello from tako!".into_response()
+}
+
+async fn health() -> impl Responder {
+  "ok".into_response()
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+  // Picks up the socket `tako dev --socket` holds open, otherwise binds.
+  let listener = tako::bind_with_port_fallback("127.0.0.1:8080").await?;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/", hello);
+  router.route(Method::GET, "/health", health);
+
+  tako::serve(listener, router).await;
+  Ok(())
+}
+"#;
+
+pub(crate) fn run(name: &str, path: Option<PathBuf>) -> io::Result<()> {
+  validate_name(name)?;
+  let dir = path.unwrap_or_else(|| PathBuf::from(name));
+  if dir.exists() {
+    return Err(io::Error::new(
+      io::ErrorKind::AlreadyExists,
+      format!("`{}` already exists", dir.display()),
+    ));
+  }
+  fs::create_dir_all(dir.join("src"))?;
+  fs::write(dir.join("Cargo.toml"), manifest(name))?;
+  fs::write(dir.join("src/main.rs"), MAIN_RS)?;
+  fs::write(dir.join(".gitignore"), "/target\n")?;
+  println!("Created `{name}` in {}", dir.display());
+  println!("  cd {} && tako dev --socket 127.0.0.1:8080", dir.display());
+  Ok(())
+}
+
+fn manifest(name: &str) -> String {
+  let major = env!("CARGO_PKG_VERSION_MAJOR");
+  format!(
+    r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2024"
+
+[dependencies]
+anyhow = "1"
+tako-rs = {{ version = "{major}", features = ["socket-activation"] }}
+tokio = {{ version = "1", features = ["full"] }}
+"#
+  )
+}
+
+/// Cargo's package-name rules, minus the reserved-name list.
+fn validate_name(name: &str) -> io::Result<()> {
+  let valid = name
+    .chars()
+    .next()
+    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+    && name
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if valid {
+    Ok(())
+  } else {
+    Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      format!(
+        "`{name}` is not a valid package name (letters, digits, `-`, `_`; must start with a letter)"
+      ),
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn scaffolds_a_project_and_refuses_to_overwrite() {
+    let dir = std::env::temp_dir().join(format!("tako-cli-new-{}", std::process::id()));
+    run("my-app", Some(dir.clone())).unwrap();
+    let manifest = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("name = \"my-app\""));
+    assert!(manifest.contains("tako-rs = { version = \"2\""));
+    assert!(dir.join("src/main.rs").is_file());
+    assert!(run("my-app", Some(dir.clone())).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(validate_name("9lives").is_err());
+    assert!(validate_name("my app").is_err());
+  }
+}
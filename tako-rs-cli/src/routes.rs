@@ -0,0 +1,117 @@
+//! `tako routes`: run the application with `TAKO_PRINT_ROUTES` set and print
+//! the table its transport emits instead of serving. Only debug builds honour
+//! the variable, which is what [`cargo::build`] produces.
+
+use std::io;
+use std::io::Read;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::cargo;
+
+/// Mirrors `tako::router::PRINT_ROUTES_ENV`.
+pub(crate) const PRINT_ROUTES_ENV: &str = "TAKO_PRINT_ROUTES";
+/// Mirrors `tako::router::ROUTE_LINE_PREFIX`.
+pub(crate) const ROUTE_LINE_PREFIX: &str = "tako-route";
+
+pub(crate) fn run(bin: Option<&str>, timeout: Duration) -> io::Result<()> {
+  let out = capture(bin, PRINT_ROUTES_ENV, "1", timeout)?;
+  let routes = parse(&out);
+  if routes.is_empty() {
+    return Err(io::Error::other(
+      "no routes reported; does the application serve a tako Router?",
+    ));
+  }
+  print!("{}", render(&routes));
+  Ok(())
+}
+
+/// Builds the application, runs it with `env` set to `value`, and returns its
+/// stdout once it exits — which a tako transport does right before serving.
+pub(crate) fn capture(
+  bin: Option<&str>,
+  env: &str,
+  value: &str,
+  timeout: Duration,
+) -> io::Result<String> {
+  let Some(exe) = cargo::build(bin)? else {
+    return Err(io::Error::other("build failed"));
+  };
+  let mut child = Command::new(&exe)
+    .env(env, value)
+    .stdout(Stdio::piped())
+    .spawn()?;
+  let mut stdout = child.stdout.take().expect("piped");
+  let reader = thread::spawn(move || {
+    let mut out = String::new();
+    stdout.read_to_string(&mut out).map(|_| out)
+  });
+
+  let deadline = Instant::now() + timeout;
+  while child.try_wait()?.is_none() {
+    if Instant::now() >= deadline {
+      child.kill()?;
+      child.wait()?;
+      return Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "the application did not reach `serve` in time",
+      ));
+    }
+    thread::sleep(Duration::from_millis(50));
+  }
+  reader
+    .join()
+    .map_err(|_| io::Error::other("stdout reader panicked"))?
+}
+
+/// The `(method, path)` pairs in the application's output; other lines are
+/// the application's own and are ignored.
+fn parse(out: &str) -> Vec<(&str, &str)> {
+  out
+    .lines()
+    .filter_map(|line| {
+      let mut parts = line.splitn(3, '\t');
+      (parts.next()? == ROUTE_LINE_PREFIX).then_some(())?;
+      Some((parts.next()?, parts.next()?))
+    })
+    .collect()
+}
+
+fn render(routes: &[(&str, &str)]) -> String {
+  let width = routes
+    .iter()
+    .map(|(m, _)| m.len())
+    .max()
+    .unwrap_or(0)
+    .max("METHOD".len());
+  let mut out = format!("{:width$}  PATH\n", "METHOD");
+  for (method, path) in routes {
+    out.push_str(&format!("{method:width$}  {path}\n"));
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn protocol_matches_the_router() {
+    assert_eq!(PRINT_ROUTES_ENV, tako_rs_core::router::PRINT_ROUTES_ENV);
+    assert_eq!(ROUTE_LINE_PREFIX, tako_rs_core::router::ROUTE_LINE_PREFIX);
+  }
+
+  #[test]
+  fn parses_route_lines_among_application_output() {
+    let out = "starting up\ntako-route\tGET\t/\ntako-route\tDELETE\t/users/{id}\nbye\n";
+    let routes = parse(out);
+    assert_eq!(routes, [("GET", "/"), ("DELETE", "/users/{id}")]);
+    assert_eq!(
+      render(&routes),
+      "METHOD  PATH\nGET     /\nDELETE  /users/{id}\n"
+    );
+  }
+}
@@ -0,0 +1,95 @@
+//! Polling file watcher: no platform notification APIs, just modification
+//! times compared between scans.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+
+type Snapshot = BTreeMap<PathBuf, SystemTime>;
+
+pub(crate) struct Watcher {
+  roots: Vec<PathBuf>,
+  poll: Duration,
+  last: Snapshot,
+}
+
+impl Watcher {
+  pub(crate) fn new(roots: Vec<PathBuf>, poll: Duration) -> Self {
+    let last = snapshot(&roots);
+    Self { roots, poll, last }
+  }
+
+  /// Blocks until a watched file is added, removed, or modified, then waits
+  /// for the tree to settle for one poll interval (editors often write in
+  /// several steps).
+  pub(crate) fn wait_for_change(&mut self) {
+    loop {
+      thread::sleep(self.poll);
+      let now = snapshot(&self.roots);
+      if now != self.last {
+        self.last = now;
+        break;
+      }
+    }
+    loop {
+      thread::sleep(self.poll);
+      let now = snapshot(&self.roots);
+      if now == self.last {
+        return;
+      }
+      self.last = now;
+    }
+  }
+}
+
+fn snapshot(roots: &[PathBuf]) -> Snapshot {
+  let mut out = Snapshot::new();
+  for root in roots {
+    visit(root, &mut out);
+  }
+  out
+}
+
+fn visit(path: &Path, out: &mut Snapshot) {
+  let Ok(meta) = fs::metadata(path) else {
+    return;
+  };
+  if meta.is_dir() {
+    let Ok(entries) = fs::read_dir(path) else {
+      return;
+    };
+    for entry in entries.flatten() {
+      let name = entry.file_name();
+      // Build output and VCS/editor metadata would retrigger every build.
+      if name == "target" || name.to_string_lossy().starts_with('.') {
+        continue;
+      }
+      visit(&entry.path(), out);
+    }
+  } else if let Ok(modified) = meta.modified() {
+    out.insert(path.to_path_buf(), modified);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn snapshots_skip_target_and_hidden_entries() {
+    let root = std::env::temp_dir().join(format!("tako-cli-watch-{}", std::process::id()));
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("target")).unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+    fs::write(root.join("target/app"), "").unwrap();
+    fs::write(root.join(".main.rs.swp"), "").unwrap();
+
+    let snap = snapshot(std::slice::from_ref(&root));
+    assert_eq!(snap.keys().collect::<Vec<_>>(), [&root.join("src/main.rs")]);
+    fs::remove_dir_all(&root).unwrap();
+  }
+}
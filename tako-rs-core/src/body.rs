@@ -6,6 +6,16 @@
 //! for common use cases like creating empty bodies, streaming data, and converting from
 //! different input types with efficient memory management.
 //!
+//! Middleware that must read a body before the handler does (to verify a
+//! signature, or to store it for replay) can use
+//! [`Spooler`](crate::body::Spooler) to buffer it into a replayable
+//! [`SpooledBody`](crate::body::SpooledBody) that spills to a temp file past
+//! a memory threshold.
+//!
+//! [`TeeBody`](crate::body::TeeBody) copies a streaming response into a
+//! [`TeeSink`](crate::body::TeeSink) (a file, object storage, ...) while it
+//! is sent, for archiving generated exports without rendering them twice.
+//!
 //! # Examples
 //!
 //! ```rust
@@ -32,13 +42,21 @@
 
 use std::convert::Infallible;
 use std::fmt::Debug;
+use std::path::Path;
+use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::task::Context;
 use std::task::Poll;
 
 use anyhow::Result;
 use bytes::Bytes;
+use bytes::BytesMut;
 use futures_util::Stream;
+use futures_util::StreamExt;
 use futures_util::TryStream;
 use futures_util::TryStreamExt;
 use http_body::Body;
@@ -48,10 +66,19 @@ use http_body_util::BodyExt;
 use http_body_util::Empty;
 use http_body_util::Full;
 use http_body_util::StreamBody;
+use pin_project_lite::pin_project;
+use tokio::io::AsyncWriteExt;
+use tokio_util::io::ReaderStream;
 
 use crate::types::BoxBody;
 use crate::types::BoxError;
 
+mod tee;
+
+pub use tee::FileSink;
+pub use tee::TeeBody;
+pub use tee::TeeSink;
+
 /// Internal enum to avoid heap-boxing for the most common body kinds.
 /// `Full`, `Empty`, and `Incoming` are stored inline (zero allocations).
 /// Anything else (streams, mapped bodies, etc.) goes through the `Boxed` variant.
@@ -252,3 +279,488 @@ impl Body for TakoBody {
     }
   }
 }
+
+/// Callback invoked once with the total number of data bytes a
+/// [`InspectedBody`] forwarded, when the body ends, fails, or trips its budget.
+pub type BodySizeCallback = Arc<dyn Fn(u64) + Send + Sync + 'static>;
+
+/// Error yielded by [`InspectedBody`] once more than `limit` bytes were seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyBudgetExceeded {
+  /// The configured byte budget.
+  pub limit: u64,
+}
+
+impl std::fmt::Display for BodyBudgetExceeded {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "body exceeded byte budget of {}", self.limit)
+  }
+}
+
+impl std::error::Error for BodyBudgetExceeded {}
+
+pin_project! {
+  /// Body adapter that counts bytes frame-by-frame without buffering.
+  ///
+  /// Optionally enforces a byte budget: the frame that pushes the running
+  /// total past the limit is replaced by a [`BodyBudgetExceeded`] error, which
+  /// aborts a streamed response or surfaces as a read error to whoever is
+  /// consuming a request body. The size callback fires exactly once: at
+  /// end-of-stream, on a stream error, or when the budget trips.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use std::sync::Arc;
+  /// use tako::body::{InspectedBody, TakoBody};
+  ///
+  /// let body = InspectedBody::new(TakoBody::from("hello"))
+  ///     .budget(1024)
+  ///     .on_complete(Arc::new(|bytes| println!("{bytes} bytes")));
+  /// let body = TakoBody::new(body);
+  /// ```
+  pub struct InspectedBody<B> {
+    #[pin]
+    inner: B,
+    seen: u64,
+    budget: Option<u64>,
+    on_complete: Option<BodySizeCallback>,
+    finished: AtomicBool,
+  }
+}
+
+impl<B> InspectedBody<B> {
+  /// Wraps `inner` with byte counting and no budget.
+  pub fn new(inner: B) -> Self {
+    Self {
+      inner,
+      seen: 0,
+      budget: None,
+      on_complete: None,
+      finished: AtomicBool::new(false),
+    }
+  }
+
+  /// Fails the body once more than `limit` data bytes have been forwarded.
+  #[must_use]
+  pub fn budget(mut self, limit: u64) -> Self {
+    self.budget = Some(limit);
+    self
+  }
+
+  /// Reports the forwarded byte total when the body finishes.
+  #[must_use]
+  pub fn on_complete(mut self, f: BodySizeCallback) -> Self {
+    self.on_complete = Some(f);
+    self
+  }
+
+  /// Number of data bytes forwarded so far.
+  pub fn bytes_seen(&self) -> u64 {
+    self.seen
+  }
+}
+
+impl<B> Body for InspectedBody<B>
+where
+  B: Body<Data = Bytes>,
+  B::Error: Into<BoxError>,
+{
+  type Data = Bytes;
+  type Error = BoxError;
+
+  fn poll_frame(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<core::result::Result<Frame<Self::Data>, Self::Error>>> {
+    let mut this = self.project();
+    if this.finished.load(Ordering::Relaxed) {
+      return Poll::Ready(None);
+    }
+    let poll = this.inner.as_mut().poll_frame(cx);
+    let report = |seen: u64| report_size(this.finished, this.on_complete.as_ref(), seen);
+    match poll {
+      Poll::Ready(Some(Ok(frame))) => {
+        if let Some(data) = frame.data_ref() {
+          *this.seen += data.len() as u64;
+          if let Some(limit) = *this.budget
+            && *this.seen > limit
+          {
+            report(*this.seen);
+            return Poll::Ready(Some(Err(Box::new(BodyBudgetExceeded { limit }))));
+          }
+        }
+        // Servers stop polling once the inner body says it is done, so the
+        // final `None` may never arrive.
+        if this.inner.is_end_stream() {
+          report(*this.seen);
+        }
+        Poll::Ready(Some(Ok(frame)))
+      }
+      Poll::Ready(Some(Err(e))) => {
+        report(*this.seen);
+        Poll::Ready(Some(Err(e.into())))
+      }
+      Poll::Ready(None) => {
+        report(*this.seen);
+        Poll::Ready(None)
+      }
+      Poll::Pending => Poll::Pending,
+    }
+  }
+
+  fn is_end_stream(&self) -> bool {
+    if self.finished.load(Ordering::Relaxed) {
+      return true;
+    }
+    // Bodies that are complete before their first poll (empty ones, for
+    // instance) are never polled at all by hyper.
+    let done = self.inner.is_end_stream();
+    if done {
+      report_size(&self.finished, self.on_complete.as_ref(), self.seen);
+    }
+    done
+  }
+
+  fn size_hint(&self) -> SizeHint {
+    self.inner.size_hint()
+  }
+}
+
+/// Marks an [`InspectedBody`] finished and runs its callback the first time.
+fn report_size(finished: &AtomicBool, cb: Option<&BodySizeCallback>, seen: u64) {
+  if !finished.swap(true, Ordering::Relaxed)
+    && let Some(cb) = cb
+  {
+    cb(seen);
+  }
+}
+
+/// Default in-memory threshold for [`Spooler`]: 1 MiB.
+const DEFAULT_SPOOL_THRESHOLD: usize = 1024 * 1024;
+
+/// Distinguishes temp files created by the same process.
+static SPOOL_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Collects bodies into [`SpooledBody`] values, spilling to disk past a
+/// memory threshold.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use tako::body::Spooler;
+///
+/// let spooled = Spooler::new(256 * 1024)
+///     .max_bytes(64 * 1024 * 1024)
+///     .spool(req.into_body())
+///     .await?;
+/// verify(spooled.chunks()).await?;
+/// let body = spooled.body(); // replay for the handler
+/// ```
+#[derive(Debug, Clone)]
+pub struct Spooler {
+  threshold: usize,
+  max_bytes: Option<u64>,
+  dir: Option<PathBuf>,
+}
+
+impl Default for Spooler {
+  fn default() -> Self {
+    Self::new(DEFAULT_SPOOL_THRESHOLD)
+  }
+}
+
+impl Spooler {
+  /// Keeps bodies of up to `threshold` bytes in memory.
+  pub fn new(threshold: usize) -> Self {
+    Self {
+      threshold,
+      max_bytes: None,
+      dir: None,
+    }
+  }
+
+  /// Fails with [`BodyBudgetExceeded`] once a body grows past `limit` bytes.
+  #[must_use]
+  pub fn max_bytes(mut self, limit: u64) -> Self {
+    self.max_bytes = Some(limit);
+    self
+  }
+
+  /// Directory for spill files (default: [`std::env::temp_dir`]).
+  #[must_use]
+  pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.dir = Some(dir.into());
+    self
+  }
+
+  /// Reads `body` to the end, in memory while it fits under the threshold
+  /// and in a temp file after that. Trailers are discarded.
+  pub async fn spool<B>(&self, body: B) -> core::result::Result<SpooledBody, BoxError>
+  where
+    B: Body<Data = Bytes>,
+    B::Error: Into<BoxError>,
+  {
+    let mut body = std::pin::pin!(body);
+    let mut buf = BytesMut::new();
+    let mut file: Option<(tokio::fs::File, SpoolFile)> = None;
+    let mut len = 0u64;
+
+    while let Some(frame) = body.frame().await {
+      let Ok(data) = frame.map_err(Into::into)?.into_data() else {
+        continue;
+      };
+      len += data.len() as u64;
+      if let Some(limit) = self.max_bytes
+        && len > limit
+      {
+        return Err(Box::new(BodyBudgetExceeded { limit }));
+      }
+      match &mut file {
+        Some((f, _)) => f.write_all(&data).await?,
+        None if buf.len() + data.len() > self.threshold => {
+          let (mut f, spool) = self.create_file().await?;
+          f.write_all(&buf).await?;
+          f.write_all(&data).await?;
+          buf = BytesMut::new();
+          file = Some((f, spool));
+        }
+        None => buf.extend_from_slice(&data),
+      }
+    }
+
+    let storage = match file {
+      Some((mut f, spool)) => {
+        f.flush().await?;
+        Storage::File(spool)
+      }
+      None => Storage::Memory(buf.freeze()),
+    };
+    Ok(SpooledBody {
+      inner: Arc::new(storage),
+      len,
+    })
+  }
+
+  async fn create_file(&self) -> std::io::Result<(tokio::fs::File, SpoolFile)> {
+    let dir = self.dir.clone().unwrap_or_else(std::env::temp_dir);
+    let mut opts = tokio::fs::OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    opts.mode(0o600);
+    loop {
+      let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+      let path = dir.join(format!(
+        "tako-spool-{}-{}-{nanos:08x}",
+        std::process::id(),
+        SPOOL_COUNTER.fetch_add(1, Ordering::Relaxed),
+      ));
+      match opts.open(&path).await {
+        Ok(f) => return Ok((f, SpoolFile(path))),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+      }
+    }
+  }
+}
+
+/// Temp file removed when the last [`SpooledBody`] clone referencing it drops.
+#[derive(Debug)]
+struct SpoolFile(PathBuf);
+
+impl Drop for SpoolFile {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_file(&self.0);
+  }
+}
+
+#[derive(Debug)]
+enum Storage {
+  Memory(Bytes),
+  File(SpoolFile),
+}
+
+/// A fully buffered body that can be replayed any number of times.
+///
+/// Small bodies live in memory; bodies past the [`Spooler`] threshold live in
+/// a private temp file that is deleted once the last clone, and the last
+/// stream produced from it, is dropped. Produced by [`Spooler::spool`].
+#[derive(Debug, Clone)]
+pub struct SpooledBody {
+  inner: Arc<Storage>,
+  len: u64,
+}
+
+impl SpooledBody {
+  /// Total number of data bytes.
+  pub fn len(&self) -> u64 {
+    self.len
+  }
+
+  /// Whether the body has no data.
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Whether the body was spilled to disk.
+  pub fn is_spilled(&self) -> bool {
+    matches!(*self.inner, Storage::File(_))
+  }
+
+  /// The bytes, when the body stayed in memory.
+  pub fn as_bytes(&self) -> Option<&Bytes> {
+    match &*self.inner {
+      Storage::Memory(b) => Some(b),
+      Storage::File(_) => None,
+    }
+  }
+
+  /// Path of the spill file, when the body was spilled.
+  pub fn path(&self) -> Option<&Path> {
+    match &*self.inner {
+      Storage::Memory(_) => None,
+      Storage::File(f) => Some(&f.0),
+    }
+  }
+
+  /// Streams the body from the start; each call starts a fresh replay.
+  pub fn chunks(&self) -> impl Stream<Item = std::io::Result<Bytes>> + Send + 'static {
+    let inner = self.inner.clone();
+    futures_util::stream::once(async move {
+      let chunks: Pin<Box<dyn Stream<Item = std::io::Result<Bytes>> + Send>> = match &*inner {
+        Storage::Memory(b) => {
+          let b = b.clone();
+          Box::pin(futures_util::stream::iter((!b.is_empty()).then_some(Ok(b))))
+        }
+        Storage::File(f) => match tokio::fs::File::open(&f.0).await {
+          // Holding `inner` keeps the file alive until the stream is dropped.
+          Ok(file) => Box::pin(ReaderStream::new(file).map_ok(move |b| {
+            let _ = &inner;
+            b
+          })),
+          Err(e) => Box::pin(futures_util::stream::iter([Err(e)])),
+        },
+      };
+      chunks
+    })
+    .flatten()
+  }
+
+  /// Reads the whole body into memory.
+  pub async fn to_bytes(&self) -> std::io::Result<Bytes> {
+    match &*self.inner {
+      Storage::Memory(b) => Ok(b.clone()),
+      Storage::File(f) => tokio::fs::read(&f.0).await.map(Bytes::from),
+    }
+  }
+
+  /// A fresh [`TakoBody`] replaying the content, with an exact size hint.
+  pub fn body(&self) -> TakoBody {
+    match &*self.inner {
+      Storage::Memory(b) => TakoBody::from(b.clone()),
+      Storage::File(_) => TakoBody::new(SizedBody {
+        inner: StreamBody::new(self.chunks().map_ok(Frame::data)),
+        len: self.len,
+      }),
+    }
+  }
+}
+
+pin_project! {
+  /// Stream body that reports a known exact length.
+  struct SizedBody<B> {
+    #[pin]
+    inner: B,
+    len: u64,
+  }
+}
+
+impl<B: Body> Body for SizedBody<B> {
+  type Data = B::Data;
+  type Error = B::Error;
+
+  fn poll_frame(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<core::result::Result<Frame<Self::Data>, Self::Error>>> {
+    self.project().inner.poll_frame(cx)
+  }
+
+  fn size_hint(&self) -> SizeHint {
+    SizeHint::with_exact(self.len)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use http_body_util::BodyExt;
+
+  use super::Spooler;
+  use super::TakoBody;
+
+  #[tokio::test]
+  async fn small_bodies_stay_in_memory() {
+    let spooled = Spooler::new(16)
+      .spool(TakoBody::from("hello"))
+      .await
+      .unwrap();
+    assert!(!spooled.is_spilled());
+    assert_eq!(spooled.as_bytes().unwrap().as_ref(), b"hello");
+  }
+
+  #[tokio::test]
+  async fn large_bodies_spill_and_replay_then_clean_up() {
+    let data = "x".repeat(100);
+    let spooled = Spooler::new(16)
+      .spool(TakoBody::from(data.clone()))
+      .await
+      .unwrap();
+    assert!(spooled.is_spilled());
+    assert_eq!(spooled.len(), 100);
+    let path = spooled.path().unwrap().to_path_buf();
+
+    for _ in 0..2 {
+      let body = spooled.body();
+      assert_eq!(http_body::Body::size_hint(&body).exact(), Some(100));
+      let bytes = body.collect().await.unwrap().to_bytes();
+      assert_eq!(bytes, data.as_bytes());
+    }
+
+    drop(spooled);
+    assert!(!path.exists());
+  }
+
+  #[tokio::test]
+  async fn max_bytes_rejects_oversized_bodies() {
+    let err = Spooler::new(4)
+      .max_bytes(8)
+      .spool(TakoBody::from("0123456789"))
+      .await
+      .unwrap_err();
+    assert!(err.is::<super::BodyBudgetExceeded>());
+  }
+
+  #[tokio::test]
+  async fn inspected_bodies_report_when_the_stream_fails() {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering;
+
+    use bytes::Bytes;
+    use futures_util::stream;
+
+    let seen = Arc::new(AtomicU64::new(u64::MAX));
+    let chunks = vec![
+      Ok(Bytes::from_static(b"abc")),
+      Err(std::io::Error::other("reset")),
+    ];
+    let cb = Arc::clone(&seen);
+    let body = super::InspectedBody::new(TakoBody::from_stream(stream::iter(chunks)))
+      .on_complete(Arc::new(move |n| cb.store(n, Ordering::SeqCst)));
+
+    assert!(body.collect().await.is_err());
+    assert_eq!(seen.load(Ordering::SeqCst), 3);
+  }
+}
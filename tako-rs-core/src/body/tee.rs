@@ -0,0 +1,303 @@
+//! Tee a streaming body into a secondary sink while it is being sent.
+
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+use std::task::ready;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_body::Body;
+use http_body::Frame;
+use http_body::SizeHint;
+use pin_project_lite::pin_project;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio_util::sync::PollSender;
+
+use crate::types::BoxError;
+
+/// Chunks a [`TeeBody`] may run ahead of its sink before it waits.
+const TEE_CHANNEL_CAPACITY: usize = 16;
+
+/// Destination for the copy [`TeeBody`] makes of a body.
+///
+/// Runs on its own task. `write` receives every data chunk in order; `finish`
+/// is called exactly once — with `complete == true` after the last chunk of a
+/// body that ended normally, or `false` when the body failed, the client went
+/// away before the end, or `write` returned an error.
+#[async_trait]
+pub trait TeeSink: Send + 'static {
+  /// Stores the next chunk.
+  async fn write(&mut self, chunk: Bytes) -> io::Result<()>;
+
+  /// Completes (or abandons, when `complete` is `false`) the copy.
+  async fn finish(&mut self, complete: bool) -> io::Result<()>;
+}
+
+/// [`TeeSink`] that writes the copy to a file.
+///
+/// Data goes to `<path>.part` first and is renamed to `path` on completion,
+/// so `path` only ever holds whole bodies; an abandoned copy is deleted.
+#[derive(Debug)]
+pub struct FileSink {
+  path: PathBuf,
+  part: PathBuf,
+  file: Option<tokio::fs::File>,
+}
+
+impl FileSink {
+  /// Archives the body at `path`, replacing an existing file on completion.
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    let path = path.into();
+    let mut part = path.clone().into_os_string();
+    part.push(".part");
+    Self {
+      path,
+      part: part.into(),
+      file: None,
+    }
+  }
+}
+
+#[async_trait]
+impl TeeSink for FileSink {
+  async fn write(&mut self, chunk: Bytes) -> io::Result<()> {
+    let file = match &mut self.file {
+      Some(file) => file,
+      None => self.file.insert(tokio::fs::File::create(&self.part).await?),
+    };
+    file.write_all(&chunk).await
+  }
+
+  async fn finish(&mut self, complete: bool) -> io::Result<()> {
+    let file = match self.file.take() {
+      Some(file) => Some(file),
+      // An empty body still produces an (empty) archive.
+      None if complete => Some(tokio::fs::File::create(&self.part).await?),
+      None => None,
+    };
+    let Some(mut file) = file else {
+      return Ok(());
+    };
+    if !complete {
+      drop(file);
+      return tokio::fs::remove_file(&self.part).await;
+    }
+    file.flush().await?;
+    file.sync_all().await?;
+    drop(file);
+    tokio::fs::rename(&self.part, &self.path).await
+  }
+}
+
+/// Message from a [`TeeBody`] to its sink task; `None` marks a clean end.
+type TeeMessage = Option<Bytes>;
+
+pin_project! {
+  /// Body adapter that forwards `inner` unchanged and hands a copy of every
+  /// data chunk to a [`TeeSink`], so a generated report or export can be
+  /// archived while it streams to the client instead of being produced twice.
+  ///
+  /// Chunks are reference-counted [`Bytes`], so the copy costs no memcpy. The
+  /// sink runs on a spawned task behind a small bounded queue: a sink that
+  /// falls behind slows the response down rather than buffering without
+  /// bound. A failing sink is logged and dropped; the client response
+  /// carries on untouched.
+  ///
+  /// Must be created inside a tokio runtime.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,ignore
+  /// use tako::body::{FileSink, TakoBody, TeeBody};
+  /// use tako::responder::Responder;
+  ///
+  /// async fn export() -> impl Responder {
+  ///     let csv = render_csv_stream(); // a streaming TakoBody
+  ///     csv.map_body(|body| TakoBody::new(TeeBody::new(body, FileSink::new("archive/export.csv"))))
+  /// }
+  /// ```
+  pub struct TeeBody<B> {
+    #[pin]
+    inner: B,
+    sink: Option<PollSender<TeeMessage>>,
+  }
+}
+
+impl<B> TeeBody<B> {
+  /// Wraps `inner`, copying its data into `sink`.
+  pub fn new(inner: B, sink: impl TeeSink) -> Self {
+    let (tx, rx) = mpsc::channel(TEE_CHANNEL_CAPACITY);
+    tokio::spawn(run_sink(rx, sink));
+    Self {
+      inner,
+      sink: Some(PollSender::new(tx)),
+    }
+  }
+}
+
+async fn run_sink(mut rx: mpsc::Receiver<TeeMessage>, mut sink: impl TeeSink) {
+  let complete = loop {
+    match rx.recv().await {
+      Some(Some(chunk)) => {
+        if let Err(err) = sink.write(chunk).await {
+          tracing::warn!("tee sink write failed: {err}; dropping the copy");
+          break false;
+        }
+      }
+      Some(None) => break true,
+      // Body failed or was dropped before the end.
+      None => break false,
+    }
+  };
+  drop(rx);
+  if let Err(err) = sink.finish(complete).await {
+    tracing::warn!("tee sink finish failed: {err}");
+  }
+}
+
+impl<B> Body for TeeBody<B>
+where
+  B: Body<Data = Bytes>,
+  B::Error: Into<BoxError>,
+{
+  type Data = Bytes;
+  type Error = BoxError;
+
+  fn poll_frame(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+    let this = self.project();
+
+    // Reserve queue space before pulling the next frame so the frame can
+    // always be copied once it arrives.
+    if let Some(tx) = this.sink.as_mut()
+      && ready!(tx.poll_reserve(cx)).is_err()
+    {
+      // The sink task gave up; keep serving the client.
+      *this.sink = None;
+    }
+
+    let frame = ready!(this.inner.poll_frame(cx));
+    match &frame {
+      Some(Ok(frame)) => {
+        if let (Some(data), Some(tx)) = (frame.data_ref(), this.sink.as_mut())
+          && tx.send_item(Some(data.clone())).is_err()
+        {
+          *this.sink = None;
+        }
+      }
+      Some(Err(_)) => {
+        // Dropping the sender without the end marker abandons the copy.
+        *this.sink = None;
+      }
+      None => {
+        if let Some(mut tx) = this.sink.take() {
+          let _ = tx.send_item(None);
+        }
+      }
+    }
+    Poll::Ready(frame.map(|res| res.map_err(Into::into)))
+  }
+
+  fn is_end_stream(&self) -> bool {
+    // Report the end only after the end marker went out to the sink.
+    self.sink.is_none() && self.inner.is_end_stream()
+  }
+
+  fn size_hint(&self) -> SizeHint {
+    self.inner.size_hint()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use http_body_util::BodyExt;
+  use tokio::sync::oneshot;
+
+  use super::*;
+  use crate::body::TakoBody;
+
+  struct Recorder {
+    chunks: Vec<Bytes>,
+    done: Option<oneshot::Sender<(Vec<Bytes>, bool)>>,
+  }
+
+  #[async_trait]
+  impl TeeSink for Recorder {
+    async fn write(&mut self, chunk: Bytes) -> io::Result<()> {
+      self.chunks.push(chunk);
+      Ok(())
+    }
+
+    async fn finish(&mut self, complete: bool) -> io::Result<()> {
+      let chunks = std::mem::take(&mut self.chunks);
+      let _ = self.done.take().unwrap().send((chunks, complete));
+      Ok(())
+    }
+  }
+
+  fn recorder() -> (Recorder, oneshot::Receiver<(Vec<Bytes>, bool)>) {
+    let (tx, rx) = oneshot::channel();
+    let sink = Recorder {
+      chunks: Vec::new(),
+      done: Some(tx),
+    };
+    (sink, rx)
+  }
+
+  #[tokio::test]
+  async fn copies_every_chunk_and_reports_completion() {
+    let (sink, done) = recorder();
+    let chunks = (0..40).map(|i| Ok::<_, io::Error>(Bytes::from(format!("{i},"))));
+    let inner = TakoBody::from_stream(futures_util::stream::iter(chunks));
+    let sent = TeeBody::new(inner, sink)
+      .collect()
+      .await
+      .unwrap()
+      .to_bytes();
+
+    let (copied, complete) = done.await.unwrap();
+    assert!(complete);
+    assert_eq!(copied.concat(), sent.to_vec());
+  }
+
+  #[tokio::test]
+  async fn abandons_the_copy_when_the_body_fails_or_is_dropped() {
+    let (sink, done) = recorder();
+    let chunks = [Ok(Bytes::from("a")), Err(io::Error::other("boom"))];
+    let inner = TakoBody::from_stream(futures_util::stream::iter(chunks));
+    assert!(TeeBody::new(inner, sink).collect().await.is_err());
+    assert_eq!(done.await.unwrap(), (vec![Bytes::from("a")], false));
+
+    let (sink, done) = recorder();
+    drop(TeeBody::new(TakoBody::from("never sent"), sink));
+    assert_eq!(done.await.unwrap(), (Vec::new(), false));
+  }
+
+  #[tokio::test]
+  async fn file_sink_renames_complete_copies_only() {
+    let dir = std::env::temp_dir().join(format!("tako-tee-{}", std::process::id()));
+    tokio::fs::create_dir_all(&dir).await.unwrap();
+    let path = dir.join("report.txt");
+
+    let body = TeeBody::new(TakoBody::from("report"), FileSink::new(&path));
+    body.collect().await.unwrap();
+    // The sink finishes on its own task shortly after the body ends.
+    let mut written = None;
+    for _ in 0..100 {
+      if let Ok(data) = tokio::fs::read(&path).await {
+        written = Some(data);
+        break;
+      }
+      tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert_eq!(written.as_deref(), Some(&b"report"[..]));
+    assert!(!dir.join("report.txt.part").exists());
+    tokio::fs::remove_dir_all(&dir).await.unwrap();
+  }
+}
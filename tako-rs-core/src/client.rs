@@ -3,7 +3,11 @@
 //! This module provides HTTP clients for making requests to external services. It includes
 //! `TakoClient` for plain HTTP connections and `TakoTlsClient` for secure HTTPS connections
 //! using rustls. Both clients support HTTP/1.1 protocol and handle connection management
-//! automatically. The clients are generic over body types to support different request
+//! automatically. `V2Client` adds pooling on top and negotiates HTTP/2 via ALPN, multiplexing
+//! concurrent requests to a host over a single connection, `Client` wraps it in a fluent request
+//! builder with per-host limits and redirect following, and `FanOut` scatters a batch of
+//! requests through it under one deadline, and `S3Client` (`s3` feature) talks to
+//! S3-compatible object storage. The clients are generic over body types to support different request
 //! payload formats while maintaining type safety and performance.
 //!
 //! # Examples
@@ -34,12 +38,44 @@
 
 #![cfg_attr(docsrs, doc(cfg(feature = "client")))]
 
+mod connector;
+mod dns_cache;
+mod fanout;
+mod happy_eyeballs;
 mod plain;
 mod pooled;
+mod request;
+#[cfg(feature = "s3")]
+#[cfg_attr(docsrs, doc(cfg(feature = "s3")))]
+pub mod s3;
 mod tls;
 mod trust_store;
 
+pub use dns_cache::DnsCache;
+pub use fanout::CallError;
+pub use fanout::CallOutcome;
+pub use fanout::CallResult;
+pub use fanout::FailurePolicy;
+pub use fanout::FanOut;
+pub use fanout::FanOutError;
+pub use fanout::FanOutResults;
 pub use plain::TakoClient;
 pub use pooled::V2Client;
 pub use pooled::V2ClientBuilder;
+pub use request::Client;
+pub use request::ClientBuilder;
+pub use request::ClientError;
+pub use request::ClientResponse;
+pub use request::RedirectPolicy;
+pub use request::RequestBuilder;
+#[cfg(feature = "s3")]
+pub use s3::CompletedPart;
+#[cfg(feature = "s3")]
+pub use s3::S3Client;
+#[cfg(feature = "s3")]
+pub use s3::S3ClientBuilder;
+#[cfg(feature = "s3")]
+pub use s3::S3Credentials;
+#[cfg(feature = "s3")]
+pub use s3::S3Error;
 pub use tls::TakoTlsClient;
@@ -0,0 +1,223 @@
+//! ALPN-aware connector used by [`V2Client`](super::V2Client).
+//!
+//! Resolves the upstream (through the optional [`DnsCache`]), dials it with
+//! [happy-eyeballs](super::happy_eyeballs) racing and, for `https://` URIs, layers a rustls handshake on top of the TCP
+//! stream. The rustls config is built on the first `https://` dial, so
+//! plaintext clients never touch rustls. The ALPN protocol negotiated
+//! during the handshake is reported back to the pool via
+//! [`Connected::negotiated_h2`], which is what lets the legacy client
+//! multiplex every request to a host over a single HTTP/2 connection instead
+//! of opening one HTTP/1.1 connection per in-flight request.
+
+use std::error::Error;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
+
+use http::Uri;
+use hyper::rt::Read;
+use hyper::rt::ReadBufCursor;
+use hyper::rt::Write;
+use hyper_util::client::legacy::connect::Connected;
+use hyper_util::client::legacy::connect::Connection;
+use hyper_util::rt::TokioIo;
+use rustls::ClientConfig;
+use rustls::RootCertStore;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::ServerName;
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+use tower_service::Service;
+
+use super::dns_cache::DnsCache;
+use super::happy_eyeballs;
+use super::trust_store::load_root_certs;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Connector that speaks plain TCP for `http://` and rustls for `https://`.
+#[derive(Clone)]
+pub(crate) struct TakoConnector {
+  attempt_delay: Duration,
+  dns: Option<DnsCache>,
+  enable_h2: bool,
+  tls: Arc<OnceLock<Result<TlsConnector, rustls::Error>>>,
+}
+
+impl TakoConnector {
+  /// Builds a connector advertising `h2` in ALPN only when `enable_h2` is
+  /// set. `http/1.1` is always offered as the fallback. `attempt_delay` is
+  /// the happy-eyeballs wait before the next resolved address is dialled;
+  /// hosts are resolved through `dns` when one is given.
+  pub(crate) fn new(enable_h2: bool, attempt_delay: Duration, dns: Option<DnsCache>) -> Self {
+    Self {
+      attempt_delay,
+      dns,
+      enable_h2,
+      tls: Arc::new(OnceLock::new()),
+    }
+  }
+
+  fn tls(&self) -> Result<TlsConnector, rustls::Error> {
+    self
+      .tls
+      .get_or_init(|| tls_config(self.enable_h2).map(|c| TlsConnector::from(Arc::new(c))))
+      .clone()
+  }
+}
+
+/// The client TLS config. Uses the process-level rustls provider when one is
+/// installed and `aws-lc-rs` otherwise, so it works when several providers
+/// are compiled in.
+fn tls_config(enable_h2: bool) -> Result<ClientConfig, rustls::Error> {
+  let provider = CryptoProvider::get_default()
+    .cloned()
+    .unwrap_or_else(|| Arc::new(rustls::crypto::aws_lc_rs::default_provider()));
+  let mut roots = RootCertStore::empty();
+  load_root_certs(&mut roots);
+  let mut config = ClientConfig::builder_with_provider(provider)
+    .with_safe_default_protocol_versions()?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+  config.alpn_protocols = if enable_h2 {
+    vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+  } else {
+    vec![b"http/1.1".to_vec()]
+  };
+  Ok(config)
+}
+
+impl Service<Uri> for TakoConnector {
+  type Response = MaybeTlsStream;
+  type Error = BoxError;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+  fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn call(&mut self, uri: Uri) -> Self::Future {
+    let is_https = uri.scheme() == Some(&http::uri::Scheme::HTTPS);
+    let host = uri.host().map(|h| h.trim_matches(['[', ']']).to_string());
+    let port = uri.port_u16().unwrap_or(if is_https { 443 } else { 80 });
+    let attempt_delay = self.attempt_delay;
+    let dns = self.dns.clone();
+    let tls = is_https.then(|| self.tls());
+
+    Box::pin(async move {
+      let host = host.ok_or("URI is missing a host")?;
+      let tcp = match dns {
+        Some(dns) => {
+          happy_eyeballs::connect(&dns.resolve(&host, port).await?, attempt_delay).await?
+        }
+        None => happy_eyeballs::connect_host(&host, port, attempt_delay).await?,
+      };
+      let Some(tls) = tls else {
+        return Ok(MaybeTlsStream::Plain(TokioIo::new(tcp)));
+      };
+      let tls = tls?;
+      let server_name = ServerName::try_from(host)?;
+      let stream = tls.connect(server_name, tcp).await?;
+      Ok(MaybeTlsStream::Tls(Box::new(TokioIo::new(stream))))
+    })
+  }
+}
+
+/// Either a plain TCP stream or a rustls client stream over TCP.
+///
+/// The TLS arm is boxed: the rustls session state is over a kilobyte and
+/// would otherwise inflate every plaintext connection by the same amount.
+pub(crate) enum MaybeTlsStream {
+  Plain(TokioIo<TcpStream>),
+  Tls(Box<TokioIo<TlsStream<TcpStream>>>),
+}
+
+impl Connection for MaybeTlsStream {
+  fn connected(&self) -> Connected {
+    match self {
+      Self::Plain(s) => s.connected(),
+      Self::Tls(s) => {
+        let (tcp, session) = s.inner().get_ref();
+        let connected = tcp.connected();
+        if session.alpn_protocol() == Some(b"h2") {
+          connected.negotiated_h2()
+        } else {
+          connected
+        }
+      }
+    }
+  }
+}
+
+impl Read for MaybeTlsStream {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: ReadBufCursor<'_>,
+  ) -> Poll<io::Result<()>> {
+    match self.get_mut() {
+      Self::Plain(s) => Pin::new(s).poll_read(cx, buf),
+      Self::Tls(s) => Pin::new(s.as_mut()).poll_read(cx, buf),
+    }
+  }
+}
+
+impl Write for MaybeTlsStream {
+  fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+    match self.get_mut() {
+      Self::Plain(s) => Pin::new(s).poll_write(cx, buf),
+      Self::Tls(s) => Pin::new(s.as_mut()).poll_write(cx, buf),
+    }
+  }
+
+  fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    match self.get_mut() {
+      Self::Plain(s) => Pin::new(s).poll_flush(cx),
+      Self::Tls(s) => Pin::new(s.as_mut()).poll_flush(cx),
+    }
+  }
+
+  fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    match self.get_mut() {
+      Self::Plain(s) => Pin::new(s).poll_shutdown(cx),
+      Self::Tls(s) => Pin::new(s.as_mut()).poll_shutdown(cx),
+    }
+  }
+
+  fn is_write_vectored(&self) -> bool {
+    match self {
+      Self::Plain(s) => s.is_write_vectored(),
+      Self::Tls(s) => s.is_write_vectored(),
+    }
+  }
+
+  fn poll_write_vectored(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    bufs: &[io::IoSlice<'_>],
+  ) -> Poll<io::Result<usize>> {
+    match self.get_mut() {
+      Self::Plain(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+      Self::Tls(s) => Pin::new(s.as_mut()).poll_write_vectored(cx, bufs),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn alpn_offers_h2_only_when_enabled() {
+    let h2 = tls_config(true).unwrap();
+    assert_eq!(h2.alpn_protocols, [b"h2".to_vec(), b"http/1.1".to_vec()]);
+    let h1 = tls_config(false).unwrap();
+    assert_eq!(h1.alpn_protocols, [b"http/1.1".to_vec()]);
+  }
+}
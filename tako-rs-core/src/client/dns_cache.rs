@@ -0,0 +1,374 @@
+//! In-process DNS cache for outbound connections.
+//!
+//! Service-to-service traffic resolves the same handful of hostnames over
+//! and over; a [`DnsCache`] shared by the client's connector answers repeat
+//! lookups from memory. Successful answers live for [`DnsCache::ttl`],
+//! failures for [`DnsCache::negative_ttl`] so a missing host does not hammer
+//! the resolver either. Entries that are still being used close to their
+//! expiry are refreshed in the background, so hot hosts never pay resolver
+//! latency on the request path.
+//!
+//! Lookups go through the system resolver (`getaddrinfo`), which does not
+//! report record TTLs; the configured TTL is therefore an upper bound on how
+//! long an answer is trusted and should be kept at or below the TTL of the
+//! records being served. IP literals bypass the cache.
+//!
+//! ```rust,ignore
+//! let dns = DnsCache::new().ttl(Duration::from_secs(30));
+//! let client = V2Client::builder().dns_cache(dns.clone()).build();
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+
+type Lookup = Pin<Box<dyn Future<Output = io::Result<Vec<IpAddr>>> + Send>>;
+type Resolver = Arc<dyn Fn(String) -> Lookup + Send + Sync>;
+
+/// Shared, TTL-bounded cache of resolved host addresses.
+///
+/// Cloning is cheap and clones share the same entries, so one cache can back
+/// several clients.
+#[derive(Clone)]
+pub struct DnsCache {
+  inner: Arc<Inner>,
+}
+
+struct Inner {
+  ttl: Duration,
+  negative_ttl: Duration,
+  refresh_ahead: Duration,
+  max_entries: usize,
+  resolver: Resolver,
+  entries: Mutex<HashMap<String, Entry>>,
+}
+
+struct Entry {
+  answer: Result<Arc<[IpAddr]>, (io::ErrorKind, String)>,
+  expires: Instant,
+  refreshing: bool,
+}
+
+impl Default for DnsCache {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl DnsCache {
+  /// A cache with a 60 s TTL, 5 s negative TTL, 10 s refresh-ahead window
+  /// and room for 1024 hosts.
+  pub fn new() -> Self {
+    Self::with_resolver(Arc::new(|host: String| {
+      Box::pin(async move {
+        Ok(
+          tokio::net::lookup_host((host.as_str(), 0))
+            .await?
+            .map(|a| a.ip())
+            .collect(),
+        )
+      })
+    }))
+  }
+
+  fn with_resolver(resolver: Resolver) -> Self {
+    Self {
+      inner: Arc::new(Inner {
+        ttl: Duration::from_secs(60),
+        negative_ttl: Duration::from_secs(5),
+        refresh_ahead: Duration::from_secs(10),
+        max_entries: 1024,
+        resolver,
+        entries: Mutex::new(HashMap::new()),
+      }),
+    }
+  }
+
+  fn configure(mut self, f: impl FnOnce(&mut Inner)) -> Self {
+    let inner = Arc::get_mut(&mut self.inner).expect("configure the DnsCache before sharing it");
+    f(inner);
+    self
+  }
+
+  /// How long a successful answer is served (default 60 s).
+  ///
+  /// # Panics
+  ///
+  /// Panics if the cache has already been cloned.
+  #[must_use]
+  pub fn ttl(self, ttl: Duration) -> Self {
+    self.configure(|i| i.ttl = ttl)
+  }
+
+  /// How long a failed lookup is remembered (default 5 s). `Duration::ZERO`
+  /// disables negative caching.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the cache has already been cloned.
+  #[must_use]
+  pub fn negative_ttl(self, ttl: Duration) -> Self {
+    self.configure(|i| i.negative_ttl = ttl)
+  }
+
+  /// Window before expiry in which a hit triggers a background refresh
+  /// (default 10 s). `Duration::ZERO` disables refresh-ahead.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the cache has already been cloned.
+  #[must_use]
+  pub fn refresh_ahead(self, window: Duration) -> Self {
+    self.configure(|i| i.refresh_ahead = window)
+  }
+
+  /// Maximum number of cached hosts (default 1024).
+  ///
+  /// # Panics
+  ///
+  /// Panics if the cache has already been cloned.
+  #[must_use]
+  pub fn max_entries(self, n: usize) -> Self {
+    self.configure(|i| i.max_entries = n.max(1))
+  }
+
+  /// Number of cached hosts, including expired entries not yet evicted.
+  pub fn len(&self) -> usize {
+    self.inner.entries.lock().len()
+  }
+
+  /// `true` when nothing is cached.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Drops every cached answer.
+  pub fn clear(&self) {
+    self.inner.entries.lock().clear();
+  }
+
+  /// Resolves `host` to socket addresses on `port`, from the cache when a
+  /// fresh answer is available.
+  pub async fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+    let host = host.trim_matches(['[', ']']);
+    if let Ok(ip) = host.parse::<IpAddr>() {
+      return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    let key = host.to_ascii_lowercase();
+    let ips = match self.cached(&key) {
+      Some(answer) => answer,
+      None => self.lookup(key).await,
+    }?;
+    Ok(ips.iter().map(|ip| SocketAddr::new(*ip, port)).collect())
+  }
+
+  /// Returns a fresh cached answer, kicking off a background refresh when it
+  /// is about to expire.
+  fn cached(&self, key: &str) -> Option<io::Result<Arc<[IpAddr]>>> {
+    let now = Instant::now();
+    let mut entries = self.inner.entries.lock();
+    let entry = entries.get_mut(key)?;
+    if entry.expires <= now {
+      return None;
+    }
+    let answer = match &entry.answer {
+      Ok(ips) => Ok(ips.clone()),
+      Err((kind, msg)) => return Some(Err(io::Error::new(*kind, msg.clone()))),
+    };
+    if !entry.refreshing && entry.expires.saturating_duration_since(now) <= self.inner.refresh_ahead
+    {
+      entry.refreshing = true;
+      let cache = self.clone();
+      let key = key.to_string();
+      tokio::spawn(async move {
+        let _ = cache.lookup(key).await;
+      });
+    }
+    Some(answer)
+  }
+
+  async fn lookup(&self, key: String) -> io::Result<Arc<[IpAddr]>> {
+    let result = (self.inner.resolver)(key.clone()).await;
+    let now = Instant::now();
+    let mut entries = self.inner.entries.lock();
+    match result {
+      Ok(ips) if !ips.is_empty() => {
+        let ips: Arc<[IpAddr]> = ips.into();
+        self.insert(
+          &mut entries,
+          key,
+          Entry {
+            answer: Ok(ips.clone()),
+            expires: now + self.inner.ttl,
+            refreshing: false,
+          },
+        );
+        Ok(ips)
+      }
+      result => {
+        let err = result.err().unwrap_or_else(|| {
+          io::Error::new(io::ErrorKind::NotFound, "host resolved to no addresses")
+        });
+        match entries.get_mut(&key) {
+          // A failed background refresh keeps serving the previous answer
+          // until it expires.
+          Some(entry) if entry.answer.is_ok() && entry.expires > now => entry.refreshing = false,
+          _ if self.inner.negative_ttl.is_zero() => {
+            entries.remove(&key);
+          }
+          _ => self.insert(
+            &mut entries,
+            key,
+            Entry {
+              answer: Err((err.kind(), err.to_string())),
+              expires: now + self.inner.negative_ttl,
+              refreshing: false,
+            },
+          ),
+        }
+        Err(err)
+      }
+    }
+  }
+
+  fn insert(&self, entries: &mut HashMap<String, Entry>, key: String, entry: Entry) {
+    if entries.len() >= self.inner.max_entries && !entries.contains_key(&key) {
+      let now = Instant::now();
+      entries.retain(|_, e| e.expires > now);
+      if entries.len() >= self.inner.max_entries
+        && let Some(oldest) = entries
+          .iter()
+          .min_by_key(|(_, e)| e.expires)
+          .map(|(k, _)| k.clone())
+      {
+        entries.remove(&oldest);
+      }
+    }
+    entries.insert(key, entry);
+  }
+}
+
+impl std::fmt::Debug for DnsCache {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("DnsCache")
+      .field("ttl", &self.inner.ttl)
+      .field("negative_ttl", &self.inner.negative_ttl)
+      .field("refresh_ahead", &self.inner.refresh_ahead)
+      .field("max_entries", &self.inner.max_entries)
+      .field("len", &self.len())
+      .finish_non_exhaustive()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io;
+  use std::net::IpAddr;
+  use std::sync::Arc;
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+  use std::time::Duration;
+
+  use super::DnsCache;
+
+  /// Cache whose resolver answers `ok.test` with a counter-derived address
+  /// and fails everything else.
+  fn counting_cache() -> (DnsCache, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let cache = DnsCache::with_resolver(Arc::new(move |host: String| {
+      let n = counter.fetch_add(1, Ordering::SeqCst);
+      Box::pin(async move {
+        if host == "ok.test" {
+          Ok(vec![IpAddr::from([
+            192,
+            0,
+            2,
+            u8::try_from(n + 1).unwrap(),
+          ])])
+        } else {
+          Err(io::Error::new(io::ErrorKind::NotFound, "no such host"))
+        }
+      })
+    }));
+    (cache, calls)
+  }
+
+  #[tokio::test]
+  async fn serves_repeat_lookups_from_memory_until_the_ttl() {
+    let (cache, calls) = counting_cache();
+    let cache = cache
+      .ttl(Duration::from_millis(100))
+      .refresh_ahead(Duration::ZERO);
+    let first = cache.resolve("OK.test", 443).await.unwrap();
+    assert_eq!(first, ["192.0.2.1:443".parse().unwrap()]);
+    assert_eq!(
+      cache.resolve("ok.test", 80).await.unwrap(),
+      ["192.0.2.1:80".parse().unwrap()]
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(
+      cache.resolve("ok.test", 80).await.unwrap(),
+      ["192.0.2.2:80".parse().unwrap()]
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+  }
+
+  #[tokio::test]
+  async fn remembers_failures_for_the_negative_ttl() {
+    let (cache, calls) = counting_cache();
+    let cache = cache.negative_ttl(Duration::from_secs(60));
+    for _ in 0..3 {
+      let err = cache.resolve("missing.test", 80).await.unwrap_err();
+      assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+  }
+
+  #[tokio::test]
+  async fn refreshes_hot_entries_in_the_background() {
+    let (cache, calls) = counting_cache();
+    let cache = cache
+      .ttl(Duration::from_secs(60))
+      .refresh_ahead(Duration::from_secs(60));
+    cache.resolve("ok.test", 80).await.unwrap();
+    // Inside the refresh window: the stale answer is served immediately and
+    // a refresh runs behind it.
+    assert_eq!(
+      cache.resolve("ok.test", 80).await.unwrap(),
+      ["192.0.2.1:80".parse().unwrap()]
+    );
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(
+      cache.resolve("ok.test", 80).await.unwrap(),
+      ["192.0.2.2:80".parse().unwrap()]
+    );
+  }
+
+  #[tokio::test]
+  async fn bypasses_ip_literals_and_bounds_its_size() {
+    let (cache, calls) = counting_cache();
+    let cache = cache.max_entries(1).negative_ttl(Duration::from_secs(60));
+    assert_eq!(
+      cache.resolve("[2001:db8::1]", 443).await.unwrap(),
+      ["[2001:db8::1]:443".parse().unwrap()]
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+    cache.resolve("ok.test", 80).await.unwrap();
+    let _ = cache.resolve("missing.test", 80).await;
+    assert_eq!(cache.len(), 1);
+    cache.clear();
+    assert!(cache.is_empty());
+  }
+}
@@ -0,0 +1,562 @@
+//! Scatter-gather over [`V2Client`]: issue several requests at once under one
+//! deadline and collect every outcome, with per-call timing.
+//!
+//! Aggregation endpoints ("fetch the user, their orders and their
+//! recommendations, render whatever came back") all need the same plumbing:
+//! start the calls concurrently, stop waiting at a shared deadline, decide
+//! whether one failure sinks the whole response, and know which upstream was
+//! slow. [`FanOut`] packages that.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use bytes::Bytes;
+//! use http::Request;
+//! use http::Response;
+//! use http_body_util::Full;
+//! use tako::client::FailurePolicy;
+//! use tako::client::FanOut;
+//! use tako::client::V2Client;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = V2Client::builder().build();
+//! let get = |uri: &str| Request::get(uri).body(Full::new(Bytes::new())).unwrap();
+//!
+//! let results = FanOut::new(&client)
+//!   .deadline(Duration::from_millis(300))
+//!   .policy(FailurePolicy::BestEffort)
+//!   .call("user", get("http://users.internal/42"))
+//!   .call_or(
+//!     "recommendations",
+//!     get("http://recs.internal/42"),
+//!     Response::new(Bytes::from_static(b"[]")),
+//!   )
+//!   .run()
+//!   .await?;
+//!
+//! for call in &results {
+//!   println!("{} took {:?}", call.name, call.elapsed);
+//! }
+//! let recs = results.get("recommendations").and_then(|c| c.outcome.response());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use http::Request;
+use http::Response;
+use http::StatusCode;
+use http_body_util::BodyExt;
+use http_body_util::Full;
+use tokio::time::Instant;
+
+use super::V2Client;
+
+/// What a [`FanOut`] does when one of its calls fails.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FailurePolicy {
+  /// Cancel the outstanding calls and return [`FanOutError`] as soon as any
+  /// call fails.
+  #[default]
+  FailFast,
+  /// Let every call finish (or hit the deadline). Failed calls resolve to
+  /// their placeholder when one was given, otherwise to
+  /// [`CallOutcome::Failed`]; [`FanOut::run`] always returns `Ok`.
+  BestEffort,
+}
+
+/// Why a single call did not produce a response.
+#[derive(Debug)]
+pub enum CallError {
+  /// The client returned an error (connect, TLS, protocol, retries exhausted).
+  Request(Box<dyn Error + Send + Sync>),
+  /// The upstream answered with a 5xx status (see
+  /// [`FanOut::fail_on_server_error`]).
+  Status(StatusCode),
+  /// The shared deadline passed before the response body was complete.
+  DeadlineExceeded,
+  /// Another call failed first under [`FailurePolicy::FailFast`].
+  Cancelled,
+}
+
+impl fmt::Display for CallError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Request(e) => write!(f, "request failed: {e}"),
+      Self::Status(status) => write!(f, "upstream returned {status}"),
+      Self::DeadlineExceeded => f.write_str("deadline exceeded"),
+      Self::Cancelled => f.write_str("cancelled after another call failed"),
+    }
+  }
+}
+
+impl Error for CallError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      Self::Request(e) => Some(e.as_ref()),
+      _ => None,
+    }
+  }
+}
+
+/// How a single call ended.
+#[derive(Debug)]
+pub enum CallOutcome {
+  /// The upstream responded; the body has been fully read.
+  Response(Response<Bytes>),
+  /// The call failed under [`FailurePolicy::BestEffort`] and its placeholder
+  /// stands in for the response.
+  Placeholder {
+    response: Response<Bytes>,
+    error: CallError,
+  },
+  /// The call failed and had no placeholder.
+  Failed(CallError),
+}
+
+impl CallOutcome {
+  /// The upstream response or the placeholder standing in for it.
+  pub fn response(&self) -> Option<&Response<Bytes>> {
+    match self {
+      Self::Response(r) | Self::Placeholder { response: r, .. } => Some(r),
+      Self::Failed(_) => None,
+    }
+  }
+
+  /// Consumes the outcome, returning the upstream response or placeholder.
+  pub fn into_response(self) -> Option<Response<Bytes>> {
+    match self {
+      Self::Response(r) | Self::Placeholder { response: r, .. } => Some(r),
+      Self::Failed(_) => None,
+    }
+  }
+
+  /// Why the call failed, including calls covered by a placeholder.
+  pub fn error(&self) -> Option<&CallError> {
+    match self {
+      Self::Response(_) => None,
+      Self::Placeholder { error, .. } | Self::Failed(error) => Some(error),
+    }
+  }
+
+  /// `true` when the upstream itself answered.
+  pub fn is_response(&self) -> bool {
+    matches!(self, Self::Response(_))
+  }
+}
+
+/// One call's name, outcome and timing.
+#[derive(Debug)]
+pub struct CallResult {
+  /// Name given to [`FanOut::call`].
+  pub name: String,
+  /// Time from the start of the fan-out until this call finished, failed,
+  /// or was abandoned.
+  pub elapsed: Duration,
+  pub outcome: CallOutcome,
+}
+
+/// Every call's result, in the order the calls were added.
+#[derive(Debug)]
+pub struct FanOutResults {
+  calls: Vec<CallResult>,
+  elapsed: Duration,
+}
+
+impl FanOutResults {
+  /// The result of the call with this name (the first one, if names repeat).
+  pub fn get(&self, name: &str) -> Option<&CallResult> {
+    self.calls.iter().find(|c| c.name == name)
+  }
+
+  /// Removes and returns the result of the call with this name.
+  pub fn take(&mut self, name: &str) -> Option<CallResult> {
+    let idx = self.calls.iter().position(|c| c.name == name)?;
+    Some(self.calls.remove(idx))
+  }
+
+  pub fn iter(&self) -> std::slice::Iter<'_, CallResult> {
+    self.calls.iter()
+  }
+
+  pub fn len(&self) -> usize {
+    self.calls.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.calls.is_empty()
+  }
+
+  /// Wall-clock time of the whole fan-out.
+  pub fn elapsed(&self) -> Duration {
+    self.elapsed
+  }
+
+  /// `true` when every upstream answered (no failures, no placeholders).
+  pub fn all_succeeded(&self) -> bool {
+    self.calls.iter().all(|c| c.outcome.is_response())
+  }
+}
+
+impl IntoIterator for FanOutResults {
+  type Item = CallResult;
+  type IntoIter = std::vec::IntoIter<CallResult>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.calls.into_iter()
+  }
+}
+
+impl<'a> IntoIterator for &'a FanOutResults {
+  type Item = &'a CallResult;
+  type IntoIter = std::slice::Iter<'a, CallResult>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.calls.iter()
+  }
+}
+
+/// Returned by [`FanOut::run`] under [`FailurePolicy::FailFast`].
+///
+/// [`results`](Self::results) holds what was known when the fan-out stopped: the failed call,
+/// calls that had already completed, and the rest as
+/// [`CallError::Cancelled`].
+#[derive(Debug)]
+pub struct FanOutError {
+  failed: usize,
+  results: FanOutResults,
+}
+
+impl FanOutError {
+  /// Name of the call that failed first.
+  pub fn failed(&self) -> &str {
+    &self.results.calls[self.failed].name
+  }
+
+  /// The error of the call that failed first.
+  pub fn error(&self) -> &CallError {
+    match &self.results.calls[self.failed].outcome {
+      CallOutcome::Failed(error) => error,
+      _ => unreachable!("the first failure is recorded as `Failed`"),
+    }
+  }
+
+  pub fn results(&self) -> &FanOutResults {
+    &self.results
+  }
+
+  pub fn into_results(self) -> FanOutResults {
+    self.results
+  }
+}
+
+impl fmt::Display for FanOutError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "fan-out call `{}` failed: {}",
+      self.failed(),
+      self.error()
+    )
+  }
+}
+
+impl Error for FanOutError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    Some(self.error())
+  }
+}
+
+struct Call {
+  name: String,
+  req: Request<Full<Bytes>>,
+  placeholder: Option<Response<Bytes>>,
+}
+
+/// Concurrent scatter-gather of requests through one [`V2Client`].
+///
+/// Each call still goes through the client's own timeout, retry and
+/// `User-Agent` handling; the fan-out deadline caps the whole batch on top
+/// of that, including reading response bodies.
+pub struct FanOut<'c> {
+  client: &'c V2Client,
+  deadline: Option<Duration>,
+  policy: FailurePolicy,
+  fail_on_server_error: bool,
+  calls: Vec<Call>,
+}
+
+impl<'c> FanOut<'c> {
+  /// An empty fan-out: no deadline, [`FailurePolicy::FailFast`], 5xx counted
+  /// as failure.
+  pub fn new(client: &'c V2Client) -> Self {
+    Self {
+      client,
+      deadline: None,
+      policy: FailurePolicy::default(),
+      fail_on_server_error: true,
+      calls: Vec::new(),
+    }
+  }
+
+  /// Shared deadline, measured from [`run`](Self::run). Calls still pending
+  /// when it passes fail with [`CallError::DeadlineExceeded`].
+  pub fn deadline(mut self, d: Duration) -> Self {
+    self.deadline = Some(d);
+    self
+  }
+
+  pub fn policy(mut self, policy: FailurePolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// Treat 5xx responses as failed calls (default `true`). When `false`,
+  /// any response the upstream sends counts as success.
+  pub fn fail_on_server_error(mut self, enabled: bool) -> Self {
+    self.fail_on_server_error = enabled;
+    self
+  }
+
+  /// Add a call with no placeholder.
+  pub fn call(mut self, name: impl Into<String>, req: Request<Full<Bytes>>) -> Self {
+    self.calls.push(Call {
+      name: name.into(),
+      req,
+      placeholder: None,
+    });
+    self
+  }
+
+  /// Add a call whose failure is replaced by `placeholder` under
+  /// [`FailurePolicy::BestEffort`]. Under `FailFast` the placeholder is
+  /// unused.
+  pub fn call_or(
+    mut self,
+    name: impl Into<String>,
+    req: Request<Full<Bytes>>,
+    placeholder: Response<Bytes>,
+  ) -> Self {
+    self.calls.push(Call {
+      name: name.into(),
+      req,
+      placeholder: Some(placeholder),
+    });
+    self
+  }
+
+  /// Issue every call concurrently and wait for all of them, the deadline,
+  /// or (under `FailFast`) the first failure.
+  ///
+  /// # Errors
+  ///
+  /// Only under [`FailurePolicy::FailFast`], when a call fails.
+  pub async fn run(self) -> Result<FanOutResults, FanOutError> {
+    let start = Instant::now();
+    let deadline = self.deadline.map(|d| start + d);
+    let client = self.client;
+    let fail_on_server_error = self.fail_on_server_error;
+
+    let mut names = Vec::with_capacity(self.calls.len());
+    let mut placeholders = Vec::with_capacity(self.calls.len());
+    let mut pending = FuturesUnordered::new();
+    for (idx, call) in self.calls.into_iter().enumerate() {
+      names.push(call.name);
+      placeholders.push(call.placeholder);
+      let req = call.req;
+      pending.push(async move {
+        let exchange = exchange(client, req, fail_on_server_error);
+        let result = match deadline {
+          Some(at) => tokio::time::timeout_at(at, exchange)
+            .await
+            .unwrap_or(Err(CallError::DeadlineExceeded)),
+          None => exchange.await,
+        };
+        (idx, start.elapsed(), result)
+      });
+    }
+
+    let mut slots: Vec<Option<(Duration, CallOutcome)>> = names.iter().map(|_| None).collect();
+    let mut failed = None;
+    while let Some((idx, elapsed, result)) = pending.next().await {
+      let outcome = match result {
+        Ok(resp) => CallOutcome::Response(resp),
+        Err(error) if self.policy == FailurePolicy::FailFast => {
+          failed = Some(idx);
+          CallOutcome::Failed(error)
+        }
+        Err(error) => match placeholders[idx].take() {
+          Some(response) => CallOutcome::Placeholder { response, error },
+          None => CallOutcome::Failed(error),
+        },
+      };
+      slots[idx] = Some((elapsed, outcome));
+      if failed.is_some() {
+        break;
+      }
+    }
+    // Dropping the stream cancels whatever is still in flight.
+    drop(pending);
+
+    let elapsed = start.elapsed();
+    let calls = names
+      .into_iter()
+      .zip(slots)
+      .map(|(name, slot)| {
+        let (elapsed, outcome) =
+          slot.unwrap_or((elapsed, CallOutcome::Failed(CallError::Cancelled)));
+        CallResult {
+          name,
+          elapsed,
+          outcome,
+        }
+      })
+      .collect();
+    let results = FanOutResults { calls, elapsed };
+    match failed {
+      Some(failed) => Err(FanOutError { failed, results }),
+      None => Ok(results),
+    }
+  }
+}
+
+/// Sends `req` and reads the whole body.
+async fn exchange(
+  client: &V2Client,
+  req: Request<Full<Bytes>>,
+  fail_on_server_error: bool,
+) -> Result<Response<Bytes>, CallError> {
+  let resp = client.send(req).await.map_err(CallError::Request)?;
+  if fail_on_server_error && resp.status().is_server_error() {
+    return Err(CallError::Status(resp.status()));
+  }
+  let (parts, body) = resp.into_parts();
+  let body = body
+    .collect()
+    .await
+    .map_err(|e| CallError::Request(Box::new(e)))?
+    .to_bytes();
+  Ok(Response::from_parts(parts, body))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::SocketAddr;
+
+  use tokio::io::AsyncReadExt;
+  use tokio::io::AsyncWriteExt;
+  use tokio::net::TcpListener;
+
+  use super::*;
+
+  /// Minimal HTTP/1.1 upstream: `/slow` stalls, `/boom` answers 500, any
+  /// other path is echoed back with 200.
+  async fn upstream() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+      loop {
+        let (mut sock, _) = listener.accept().await.unwrap();
+        tokio::spawn(async move {
+          let mut buf = vec![0; 1024];
+          let n = sock.read(&mut buf).await.unwrap();
+          let head = String::from_utf8_lossy(&buf[..n]);
+          let path = head.split_whitespace().nth(1).unwrap_or("/").to_owned();
+          let status = match path.as_str() {
+            "/slow" => {
+              tokio::time::sleep(Duration::from_secs(5)).await;
+              "200 OK"
+            }
+            "/boom" => "500 Internal Server Error",
+            _ => "200 OK",
+          };
+          let resp = format!(
+            "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{path}",
+            path.len()
+          );
+          let _ = sock.write_all(resp.as_bytes()).await;
+        });
+      }
+    });
+    addr
+  }
+
+  fn get(addr: SocketAddr, path: &str) -> Request<Full<Bytes>> {
+    Request::get(format!("http://{addr}{path}"))
+      .body(Full::new(Bytes::new()))
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn best_effort_fills_placeholders_and_keeps_order() {
+    let addr = upstream().await;
+    let client = V2Client::builder().build();
+    let results = FanOut::new(&client)
+      .deadline(Duration::from_millis(300))
+      .policy(FailurePolicy::BestEffort)
+      .call_or(
+        "slow",
+        get(addr, "/slow"),
+        Response::new(Bytes::from_static(b"fallback")),
+      )
+      .call("boom", get(addr, "/boom"))
+      .call("ok", get(addr, "/ok"))
+      .run()
+      .await
+      .unwrap();
+
+    let names: Vec<_> = results.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["slow", "boom", "ok"]);
+    assert!(!results.all_succeeded());
+
+    let slow = results.get("slow").unwrap();
+    assert!(matches!(
+      slow.outcome,
+      CallOutcome::Placeholder {
+        error: CallError::DeadlineExceeded,
+        ..
+      }
+    ));
+    assert_eq!(slow.outcome.response().unwrap().body(), "fallback");
+    assert!(slow.elapsed >= Duration::from_millis(300));
+
+    let boom = results.get("boom").unwrap();
+    assert!(matches!(
+      boom.outcome,
+      CallOutcome::Failed(CallError::Status(StatusCode::INTERNAL_SERVER_ERROR))
+    ));
+
+    let ok = results.get("ok").unwrap();
+    assert_eq!(ok.outcome.response().unwrap().body(), "/ok");
+    assert!(ok.elapsed < slow.elapsed);
+  }
+
+  #[tokio::test]
+  async fn fail_fast_cancels_outstanding_calls() {
+    let addr = upstream().await;
+    let client = V2Client::builder().build();
+    let err = FanOut::new(&client)
+      .call("slow", get(addr, "/slow"))
+      .call("boom", get(addr, "/boom"))
+      .run()
+      .await
+      .unwrap_err();
+
+    assert_eq!(err.failed(), "boom");
+    assert!(matches!(err.error(), CallError::Status(_)));
+    let slow = err.results().get("slow").unwrap();
+    assert!(matches!(
+      slow.outcome,
+      CallOutcome::Failed(CallError::Cancelled)
+    ));
+    assert!(err.results().elapsed() < Duration::from_secs(5));
+  }
+}
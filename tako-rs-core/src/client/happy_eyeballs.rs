@@ -0,0 +1,162 @@
+//! Dual-stack TCP connection racing (RFC 8305, "Happy Eyeballs v2").
+//!
+//! Resolved addresses are reordered so the two families alternate, IPv6
+//! first. The first address is dialled immediately; every further address is
+//! started either when the previous attempt fails or after the attempt delay
+//! elapses, whichever comes first. The first socket to connect wins and the
+//! remaining attempts are dropped, so a host whose IPv6 path silently drops
+//! SYNs costs one attempt delay instead of a full TCP timeout.
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use tokio::net::TcpStream;
+
+/// Delay before the next address is tried while an attempt is still pending
+/// (the "Connection Attempt Delay" recommended by RFC 8305 §5).
+pub(crate) const DEFAULT_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Resolves `host:port` and races the results.
+pub(crate) async fn connect_host(
+  host: &str,
+  port: u16,
+  attempt_delay: Duration,
+) -> io::Result<TcpStream> {
+  let host = host.trim_matches(['[', ']']);
+  let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await?.collect();
+  connect(&addrs, attempt_delay).await
+}
+
+/// Races connection attempts to `addrs` in RFC 8305 order.
+pub(crate) async fn connect(
+  addrs: &[SocketAddr],
+  attempt_delay: Duration,
+) -> io::Result<TcpStream> {
+  let mut pending = interleave(addrs).into_iter();
+  let mut attempts = FuturesUnordered::new();
+  let mut last_err = None;
+
+  loop {
+    if attempts.is_empty() {
+      match pending.next() {
+        Some(addr) => attempts.push(attempt(addr)),
+        None => {
+          return Err(last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no addresses to connect to")
+          }));
+        }
+      }
+    }
+
+    let more = pending.len() > 0;
+    tokio::select! {
+      Some(result) = attempts.next() => match result {
+        Ok(stream) => return Ok(stream),
+        Err(e) => {
+          last_err = Some(e);
+          if let Some(addr) = pending.next() {
+            attempts.push(attempt(addr));
+          }
+        }
+      },
+      () = tokio::time::sleep(attempt_delay), if more => {
+        if let Some(addr) = pending.next() {
+          attempts.push(attempt(addr));
+        }
+      }
+    }
+  }
+}
+
+async fn attempt(addr: SocketAddr) -> io::Result<TcpStream> {
+  TcpStream::connect(addr).await
+}
+
+/// Alternates address families, starting with IPv6 when any is present and
+/// otherwise keeping the resolver's order within each family.
+fn interleave(addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+  let (v6, v4): (Vec<SocketAddr>, Vec<SocketAddr>) = addrs.iter().partition(|a| a.is_ipv6());
+  let mut out = Vec::with_capacity(addrs.len());
+  let mut v6 = v6.into_iter();
+  let mut v4 = v4.into_iter();
+  loop {
+    match (v6.next(), v4.next()) {
+      (None, None) => return out,
+      (a, b) => out.extend(a.into_iter().chain(b)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::SocketAddr;
+  use std::time::Duration;
+
+  use tokio::net::TcpListener;
+
+  use super::connect;
+  use super::interleave;
+
+  fn addr(s: &str) -> SocketAddr {
+    s.parse().unwrap()
+  }
+
+  #[test]
+  fn interleaves_families_ipv6_first() {
+    let sorted = interleave(&[
+      addr("192.0.2.1:80"),
+      addr("192.0.2.2:80"),
+      addr("192.0.2.3:80"),
+      addr("[2001:db8::1]:80"),
+      addr("[2001:db8::2]:80"),
+    ]);
+    assert_eq!(
+      sorted,
+      [
+        addr("[2001:db8::1]:80"),
+        addr("192.0.2.1:80"),
+        addr("[2001:db8::2]:80"),
+        addr("192.0.2.2:80"),
+        addr("192.0.2.3:80"),
+      ]
+    );
+  }
+
+  #[tokio::test]
+  async fn falls_back_past_unreachable_and_refused_addresses() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let live = listener.local_addr().unwrap();
+    let refused = {
+      let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+      l.local_addr().unwrap()
+    };
+
+    // TEST-NET-1 either blackholes the SYN or fails fast; both must fall
+    // through to the live listener well within the outer timeout.
+    let addrs = [addr("192.0.2.1:9"), refused, live];
+    let stream = tokio::time::timeout(
+      Duration::from_secs(5),
+      connect(&addrs, Duration::from_millis(50)),
+    )
+    .await
+    .expect("racing must not wait for the TCP timeout")
+    .unwrap();
+    assert_eq!(stream.peer_addr().unwrap(), live);
+  }
+
+  #[tokio::test]
+  async fn reports_the_last_error_when_every_attempt_fails() {
+    let refused = {
+      let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+      l.local_addr().unwrap()
+    };
+    let err = connect(&[refused], Duration::from_millis(50))
+      .await
+      .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+    assert!(connect(&[], Duration::from_millis(50)).await.is_err());
+  }
+}
@@ -9,9 +9,10 @@ use http_body_util::BodyExt;
 use hyper::client::conn::http1::SendRequest;
 use hyper::client::{self};
 use hyper_util::rt::TokioIo;
-use tokio::net::TcpStream;
 use tokio::task::JoinHandle;
 
+use super::happy_eyeballs;
+
 /// Plain HTTP client for unencrypted connections.
 ///
 /// `TakoClient` provides a standard HTTP client that establishes plain TCP connections
@@ -70,8 +71,8 @@ where
     'a: 'static,
   {
     let port = port.unwrap_or(80);
-    let addr = format!("{host}:{port}");
-    let tcp_stream = TcpStream::connect(addr).await?;
+    let tcp_stream =
+      happy_eyeballs::connect_host(host, port, happy_eyeballs::DEFAULT_ATTEMPT_DELAY).await?;
     let io = TokioIo::new(tcp_stream);
 
     // HTTP/1 handshake
@@ -7,15 +7,22 @@ use http::Request;
 use http::Response;
 use http_body_util::Full;
 use hyper_util::client::legacy::Client as HyperClient;
-use hyper_util::client::legacy::connect::HttpConnector;
 use hyper_util::rt::TokioExecutor;
 
+use super::connector::TakoConnector;
+use super::dns_cache::DnsCache;
+use super::happy_eyeballs;
+
 /// v2 high-level client built on `hyper_util::client::legacy::Client`.
 ///
 /// Compared to [`TakoClient`](super::TakoClient) / [`TakoTlsClient`](super::TakoTlsClient) (single-connection,
 /// HTTP/1.1 only) this provides:
 /// - connection pool with idle timeout / per-host caps
-/// - HTTP/1.1 + HTTP/2 negotiation via ALPN (when TLS is present)
+/// - `https://` via rustls with HTTP/1.1 + HTTP/2 negotiation over ALPN;
+///   an `h2` connection is shared by every concurrent request to that host
+/// - optional HTTP/2 prior knowledge (h2c) for plaintext upstreams
+/// - dual-stack happy-eyeballs dialling (IPv6 preferred, fast IPv4 fallback)
+/// - optional in-process DNS cache shared across clients
 /// - per-request timeout
 /// - retry policy with capped attempts and backoff
 /// - W3C `traceparent` header propagation when present in extensions
@@ -23,7 +30,7 @@ use hyper_util::rt::TokioExecutor;
 /// HTTP/3 support is intentionally deferred — the underlying `hyper_util`
 /// legacy client does not yet expose a stable connector for it.
 pub struct V2Client {
-  inner: HyperClient<HttpConnector, Full<bytes::Bytes>>,
+  inner: HyperClient<TakoConnector, Full<bytes::Bytes>>,
   default_timeout: Option<Duration>,
   max_retries: u32,
   retry_backoff: Duration,
@@ -45,6 +52,23 @@ pub struct V2ClientBuilder {
   retry_backoff: Duration,
   user_agent: Option<String>,
   retry_only_idempotent: bool,
+  happy_eyeballs_delay: Duration,
+  dns_cache: Option<DnsCache>,
+  http2: Http2Settings,
+}
+
+/// HTTP/2 knobs collected by [`V2ClientBuilder`] and applied to the pool.
+#[derive(Default)]
+struct Http2Settings {
+  /// Advertise `h2` over ALPN.
+  alpn: bool,
+  /// Skip negotiation and always speak HTTP/2.
+  prior_knowledge: bool,
+  max_concurrent_streams: Option<usize>,
+  adaptive_window: bool,
+  initial_stream_window_size: Option<u32>,
+  initial_connection_window_size: Option<u32>,
+  keep_alive_interval: Option<Duration>,
 }
 
 impl V2ClientBuilder {
@@ -57,6 +81,12 @@ impl V2ClientBuilder {
       retry_backoff: Duration::from_millis(100),
       user_agent: Some(format!("tako/{}", env!("CARGO_PKG_VERSION"))),
       retry_only_idempotent: true,
+      happy_eyeballs_delay: happy_eyeballs::DEFAULT_ATTEMPT_DELAY,
+      dns_cache: None,
+      http2: Http2Settings {
+        alpn: true,
+        ..Http2Settings::default()
+      },
     }
   }
 
@@ -106,10 +136,75 @@ impl V2ClientBuilder {
     self
   }
 
+  /// How long a pending connection attempt gets before the next resolved
+  /// address is dialled in parallel (default 250 ms, per RFC 8305). IPv6
+  /// and IPv4 addresses are tried alternately, IPv6 first.
+  pub fn happy_eyeballs_delay(mut self, d: Duration) -> Self {
+    self.happy_eyeballs_delay = d;
+    self
+  }
+
+  /// Resolve upstream hosts through `cache` instead of asking the system
+  /// resolver on every new connection. Pass a clone of the same cache to
+  /// several builders to share it.
+  pub fn dns_cache(mut self, cache: DnsCache) -> Self {
+    self.dns_cache = Some(cache);
+    self
+  }
+
+  /// Advertise `h2` in the TLS ALPN list (default `true`). When the server
+  /// selects it, every request to that host is multiplexed over one pooled
+  /// connection. Disable to force HTTP/1.1 over TLS.
+  pub fn http2(mut self, enabled: bool) -> Self {
+    self.http2.alpn = enabled;
+    self
+  }
+
+  /// Speak HTTP/2 without negotiation (prior knowledge). Required for
+  /// plaintext h2c upstreams such as gRPC services behind a trusted LB;
+  /// every connection — TLS or not — then uses HTTP/2.
+  pub fn http2_prior_knowledge(mut self, enabled: bool) -> Self {
+    self.http2.prior_knowledge = enabled;
+    self
+  }
+
+  /// Initial cap on concurrent streams opened per HTTP/2 connection before
+  /// the server's `SETTINGS_MAX_CONCURRENT_STREAMS` arrives (the server's
+  /// value wins afterwards).
+  pub fn http2_max_concurrent_streams(mut self, n: usize) -> Self {
+    self.http2.max_concurrent_streams = Some(n);
+    self
+  }
+
+  /// Enable BDP-based adaptive flow-control windows. Overrides the fixed
+  /// window sizes below.
+  pub fn http2_adaptive_window(mut self, enabled: bool) -> Self {
+    self.http2.adaptive_window = enabled;
+    self
+  }
+
+  /// Initial per-stream flow-control window, in bytes.
+  pub fn http2_initial_stream_window_size(mut self, size: u32) -> Self {
+    self.http2.initial_stream_window_size = Some(size);
+    self
+  }
+
+  /// Initial connection-level flow-control window, in bytes.
+  pub fn http2_initial_connection_window_size(mut self, size: u32) -> Self {
+    self.http2.initial_connection_window_size = Some(size);
+    self
+  }
+
+  /// Interval between HTTP/2 `PING` frames used to keep pooled connections
+  /// alive and detect dead peers.
+  pub fn http2_keep_alive_interval(mut self, d: Duration) -> Self {
+    self.http2.keep_alive_interval = Some(d);
+    self
+  }
+
   /// Build a `V2Client`.
   pub fn build(self) -> V2Client {
-    let mut http = HttpConnector::new();
-    http.enforce_http(false);
+    let connector = TakoConnector::new(self.http2.alpn, self.happy_eyeballs_delay, self.dns_cache);
     let mut builder = HyperClient::builder(TokioExecutor::new());
     if let Some(d) = self.pool_idle_timeout {
       builder.pool_idle_timeout(d);
@@ -117,7 +212,15 @@ impl V2ClientBuilder {
     if let Some(n) = self.pool_max_idle_per_host {
       builder.pool_max_idle_per_host(n);
     }
-    let inner = builder.build(http);
+    builder
+      .timer(hyper_util::rt::TokioTimer::new())
+      .http2_only(self.http2.prior_knowledge)
+      .http2_adaptive_window(self.http2.adaptive_window)
+      .http2_initial_stream_window_size(self.http2.initial_stream_window_size)
+      .http2_initial_connection_window_size(self.http2.initial_connection_window_size)
+      .http2_initial_max_send_streams(self.http2.max_concurrent_streams)
+      .http2_keep_alive_interval(self.http2.keep_alive_interval);
+    let inner = builder.build(connector);
     V2Client {
       inner,
       default_timeout: self.default_timeout,
@@ -224,3 +327,44 @@ fn clone_request_full(req: &Request<Full<bytes::Bytes>>) -> Option<Request<Full<
   let body = req.body().clone();
   builder.body(body).ok()
 }
+
+#[cfg(test)]
+mod tests {
+  use std::convert::Infallible;
+
+  use hyper::service::service_fn;
+  use hyper_util::rt::TokioIo;
+  use tokio::net::TcpListener;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn prior_knowledge_speaks_h2c_with_tuned_settings() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+      let (sock, _) = listener.accept().await.unwrap();
+      let service = service_fn(|req: Request<hyper::body::Incoming>| async move {
+        Ok::<_, Infallible>(Response::new(Full::<bytes::Bytes>::from(format!(
+          "{:?}",
+          req.version()
+        ))))
+      });
+      let _ = hyper::server::conn::http2::Builder::new(TokioExecutor::new())
+        .serve_connection(TokioIo::new(sock), service)
+        .await;
+    });
+
+    let client = V2Client::builder()
+      .http2_prior_knowledge(true)
+      .http2_max_concurrent_streams(8)
+      .http2_initial_stream_window_size(1 << 20)
+      .http2_adaptive_window(false)
+      .build();
+    let req = Request::get(format!("http://{addr}/"))
+      .body(Full::default())
+      .unwrap();
+    let resp = client.send(req).await.unwrap();
+    assert_eq!(resp.version(), http::Version::HTTP_2);
+  }
+}
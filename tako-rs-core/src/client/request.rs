@@ -0,0 +1,753 @@
+//! Ergonomic request-builder client on top of [`V2Client`].
+//!
+//! [`Client`] covers the everyday outbound call without pulling in another
+//! HTTP stack: keep-alive pooling and timeouts come from [`V2Client`], and on
+//! top of that it caps concurrent requests per host, follows redirects, and
+//! builds requests fluently:
+//!
+//! ```rust,no_run
+//! use tako::client::Client;
+//!
+//! # async fn example() -> Result<(), tako::client::ClientError> {
+//! let client = Client::new();
+//! let user: serde_json::Value = client
+//!     .post("https://api.example.com/users")
+//!     .header("x-request-id", "42")
+//!     .json(&serde_json::json!({ "name": "alice" }))
+//!     .send()
+//!     .await?
+//!     .error_for_status()?
+//!     .json()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::HeaderMap;
+use http::HeaderName;
+use http::HeaderValue;
+use http::Method;
+use http::Request;
+use http::Response;
+use http::StatusCode;
+use http::Uri;
+use http::header;
+use http_body_util::BodyExt;
+use http_body_util::Full;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+
+use super::V2Client;
+use super::V2ClientBuilder;
+
+/// Redirects followed by default.
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// Errors returned by [`Client`].
+#[derive(Debug)]
+pub enum ClientError {
+  /// The URL, a header, or a body could not be turned into a request.
+  Builder(String),
+  /// Connecting, sending, or reading the response failed.
+  Transport(Box<dyn Error + Send + Sync>),
+  /// The request did not finish within its timeout.
+  Timeout,
+  /// More redirects than the [`RedirectPolicy`] allows.
+  TooManyRedirects(usize),
+  /// [`ClientResponse::error_for_status`] saw a 4xx or 5xx.
+  Status(StatusCode),
+  /// The response body was not the expected JSON.
+  Json(serde_json::Error),
+}
+
+impl fmt::Display for ClientError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Builder(msg) => write!(f, "invalid request: {msg}"),
+      Self::Transport(e) => write!(f, "request failed: {e}"),
+      Self::Timeout => f.write_str("request timed out"),
+      Self::TooManyRedirects(n) => write!(f, "stopped after {n} redirects"),
+      Self::Status(status) => write!(f, "server answered {status}"),
+      Self::Json(e) => write!(f, "invalid JSON body: {e}"),
+    }
+  }
+}
+
+impl Error for ClientError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      Self::Transport(e) => Some(e.as_ref()),
+      Self::Json(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+/// Whether and how far [`Client`] follows `3xx` responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectPolicy {
+  /// Return redirects to the caller as-is.
+  None,
+  /// Follow up to this many redirects, then fail with
+  /// [`ClientError::TooManyRedirects`].
+  Limited(usize),
+}
+
+impl Default for RedirectPolicy {
+  fn default() -> Self {
+    Self::Limited(DEFAULT_MAX_REDIRECTS)
+  }
+}
+
+/// Builder for [`Client`].
+pub struct ClientBuilder {
+  http: V2ClientBuilder,
+  client: Option<V2Client>,
+  max_per_host: Option<usize>,
+  redirect: RedirectPolicy,
+  default_headers: HeaderMap,
+}
+
+impl ClientBuilder {
+  fn new() -> Self {
+    Self {
+      http: V2Client::builder(),
+      client: None,
+      max_per_host: None,
+      redirect: RedirectPolicy::default(),
+      default_headers: HeaderMap::new(),
+    }
+  }
+
+  /// Per-attempt request timeout (default 30 s).
+  #[must_use]
+  pub fn timeout(mut self, d: Duration) -> Self {
+    self.http = self.http.timeout(d);
+    self
+  }
+
+  /// Idle timeout for pooled keep-alive connections (default 90 s).
+  #[must_use]
+  pub fn pool_idle_timeout(mut self, d: Duration) -> Self {
+    self.http = self.http.pool_idle_timeout(d);
+    self
+  }
+
+  /// Maximum idle connections kept per host (default 8).
+  #[must_use]
+  pub fn pool_max_idle_per_host(mut self, n: usize) -> Self {
+    self.http = self.http.pool_max_idle_per_host(n);
+    self
+  }
+
+  /// Maximum requests in flight to one host (scheme and authority) at a
+  /// time; further requests wait. A request counts until its
+  /// [`ClientResponse`] is dropped or its body read, so holding responses
+  /// while sending more to the same host can wait forever. Unlimited by
+  /// default.
+  #[must_use]
+  pub fn max_connections_per_host(mut self, n: usize) -> Self {
+    self.max_per_host = Some(n.max(1));
+    self
+  }
+
+  /// Redirect handling (default: follow up to 10).
+  #[must_use]
+  pub fn redirect(mut self, policy: RedirectPolicy) -> Self {
+    self.redirect = policy;
+    self
+  }
+
+  /// `User-Agent` sent with every request.
+  #[must_use]
+  pub fn user_agent(mut self, ua: impl Into<String>) -> Self {
+    self.http = self.http.user_agent(ua);
+    self
+  }
+
+  /// Header added to every request that does not set it itself.
+  #[must_use]
+  pub fn default_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+    self.default_headers.insert(name, value);
+    self
+  }
+
+  /// Retries on transport errors and `5xx` for idempotent methods.
+  #[must_use]
+  pub fn max_retries(mut self, n: u32) -> Self {
+    self.http = self.http.max_retries(n);
+    self
+  }
+
+  /// Sends through `client` instead of building a [`V2Client`]; the pool
+  /// and timeout settings above are then ignored.
+  #[must_use]
+  pub fn client(mut self, client: V2Client) -> Self {
+    self.client = Some(client);
+    self
+  }
+
+  /// Builds the client.
+  pub fn build(self) -> Client {
+    Client {
+      inner: Arc::new(Inner {
+        http: self.client.unwrap_or_else(|| self.http.build()),
+        max_per_host: self.max_per_host,
+        hosts: Mutex::new(HashMap::new()),
+        redirect: self.redirect,
+        default_headers: self.default_headers,
+      }),
+    }
+  }
+}
+
+struct Inner {
+  http: V2Client,
+  max_per_host: Option<usize>,
+  hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+  redirect: RedirectPolicy,
+  default_headers: HeaderMap,
+}
+
+impl Inner {
+  async fn permit(&self, uri: &Uri) -> Option<OwnedSemaphorePermit> {
+    let max = self.max_per_host?;
+    let semaphore = self
+      .hosts
+      .lock()
+      .expect("host limits lock poisoned")
+      .entry(host_key(uri))
+      .or_insert_with(|| Arc::new(Semaphore::new(max)))
+      .clone();
+    semaphore.acquire_owned().await.ok()
+  }
+}
+
+fn host_key(uri: &Uri) -> String {
+  format!(
+    "{}://{}",
+    uri.scheme_str().unwrap_or("http"),
+    uri.authority().map_or("", |a| a.as_str())
+  )
+}
+
+/// Pooled HTTP client with a fluent request builder. Cheap to clone; clones
+/// share the pool and per-host limits.
+#[derive(Clone)]
+pub struct Client {
+  inner: Arc<Inner>,
+}
+
+impl Default for Client {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Client {
+  /// A client with default settings.
+  pub fn new() -> Self {
+    Self::builder().build()
+  }
+
+  /// Starts configuring a client.
+  pub fn builder() -> ClientBuilder {
+    ClientBuilder::new()
+  }
+
+  /// Starts a request with any method.
+  pub fn request(&self, method: Method, url: impl AsRef<str>) -> RequestBuilder {
+    let uri = url
+      .as_ref()
+      .parse::<Uri>()
+      .map_err(|e| ClientError::Builder(e.to_string()));
+    RequestBuilder {
+      client: self.clone(),
+      method,
+      uri,
+      headers: HeaderMap::new(),
+      body: Bytes::new(),
+      timeout: None,
+    }
+  }
+
+  /// Starts a `GET` request.
+  pub fn get(&self, url: impl AsRef<str>) -> RequestBuilder {
+    self.request(Method::GET, url)
+  }
+
+  /// Starts a `POST` request.
+  pub fn post(&self, url: impl AsRef<str>) -> RequestBuilder {
+    self.request(Method::POST, url)
+  }
+
+  /// Starts a `PUT` request.
+  pub fn put(&self, url: impl AsRef<str>) -> RequestBuilder {
+    self.request(Method::PUT, url)
+  }
+
+  /// Starts a `PATCH` request.
+  pub fn patch(&self, url: impl AsRef<str>) -> RequestBuilder {
+    self.request(Method::PATCH, url)
+  }
+
+  /// Starts a `DELETE` request.
+  pub fn delete(&self, url: impl AsRef<str>) -> RequestBuilder {
+    self.request(Method::DELETE, url)
+  }
+
+  /// Starts a `HEAD` request.
+  pub fn head(&self, url: impl AsRef<str>) -> RequestBuilder {
+    self.request(Method::HEAD, url)
+  }
+}
+
+/// A request being built; finish with [`send`](Self::send). Errors from the
+/// builder methods are reported by `send`.
+#[must_use = "requests do nothing until `send` is awaited"]
+pub struct RequestBuilder {
+  client: Client,
+  method: Method,
+  uri: Result<Uri, ClientError>,
+  headers: HeaderMap,
+  body: Bytes,
+  timeout: Option<Duration>,
+}
+
+impl RequestBuilder {
+  fn fail(mut self, err: impl fmt::Display) -> Self {
+    if self.uri.is_ok() {
+      self.uri = Err(ClientError::Builder(err.to_string()));
+    }
+    self
+  }
+
+  /// Adds a header, replacing earlier values of the same name.
+  pub fn header<K, V>(mut self, name: K, value: V) -> Self
+  where
+    K: TryInto<HeaderName>,
+    K::Error: fmt::Display,
+    V: TryInto<HeaderValue>,
+    V::Error: fmt::Display,
+  {
+    let name = match name.try_into() {
+      Ok(name) => name,
+      Err(e) => return self.fail(e),
+    };
+    match value.try_into() {
+      Ok(value) => {
+        self.headers.insert(name, value);
+        self
+      }
+      Err(e) => self.fail(e),
+    }
+  }
+
+  /// Adds every header in `headers`.
+  pub fn headers(mut self, headers: HeaderMap) -> Self {
+    self.headers.extend(headers);
+    self
+  }
+
+  /// Sets `Authorization: Bearer <token>`.
+  pub fn bearer_auth(self, token: impl fmt::Display) -> Self {
+    self.header(header::AUTHORIZATION, format!("Bearer {token}"))
+  }
+
+  /// Appends `query` (anything `serde_urlencoded` accepts) to the URL.
+  pub fn query<T: Serialize + ?Sized>(mut self, query: &T) -> Self {
+    let encoded = match serde_urlencoded::to_string(query) {
+      Ok(encoded) => encoded,
+      Err(e) => return self.fail(e),
+    };
+    if encoded.is_empty() {
+      return self;
+    }
+    let Ok(uri) = &self.uri else {
+      return self;
+    };
+    let path_and_query = match uri.path_and_query() {
+      Some(pq) if pq.query().is_some() => format!("{pq}&{encoded}"),
+      Some(pq) => format!("{pq}?{encoded}"),
+      None => format!("/?{encoded}"),
+    };
+    let mut parts = uri.clone().into_parts();
+    match path_and_query.parse() {
+      Ok(pq) => {
+        parts.path_and_query = Some(pq);
+        self.uri = Uri::from_parts(parts).map_err(|e| ClientError::Builder(e.to_string()));
+        self
+      }
+      Err(e) => self.fail(e),
+    }
+  }
+
+  /// Sets a raw body.
+  pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+    self.body = body.into();
+    self
+  }
+
+  /// Sends `value` as JSON.
+  pub fn json<T: Serialize + ?Sized>(mut self, value: &T) -> Self {
+    match serde_json::to_vec(value) {
+      Ok(body) => {
+        self.body = body.into();
+        self.header(header::CONTENT_TYPE, "application/json")
+      }
+      Err(e) => self.fail(e),
+    }
+  }
+
+  /// Sends `value` as `application/x-www-form-urlencoded`.
+  pub fn form<T: Serialize + ?Sized>(mut self, value: &T) -> Self {
+    match serde_urlencoded::to_string(value) {
+      Ok(body) => {
+        self.body = body.into();
+        self.header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+      }
+      Err(e) => self.fail(e),
+    }
+  }
+
+  /// Deadline for the whole exchange, redirects and queueing for a per-host
+  /// slot included.
+  pub fn timeout(mut self, d: Duration) -> Self {
+    self.timeout = Some(d);
+    self
+  }
+
+  /// Sends the request, following redirects per the client's policy.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`ClientError`] if the request could not be built or sent, ran
+  /// past its timeout, or redirected too often.
+  pub async fn send(self) -> Result<ClientResponse, ClientError> {
+    match self.timeout {
+      Some(d) => tokio::time::timeout(d, self.exchange())
+        .await
+        .map_err(|_| ClientError::Timeout)?,
+      None => self.exchange().await,
+    }
+  }
+
+  async fn exchange(self) -> Result<ClientResponse, ClientError> {
+    let inner = &self.client.inner;
+    let mut uri = self.uri?;
+    let mut method = self.method;
+    let mut headers = self.headers;
+    let mut body = self.body;
+    for (name, value) in &inner.default_headers {
+      if !headers.contains_key(name) {
+        headers.insert(name.clone(), value.clone());
+      }
+    }
+
+    let mut redirects = 0;
+    loop {
+      let permit = inner.permit(&uri).await;
+      let mut req = Request::builder()
+        .method(method.clone())
+        .uri(uri.clone())
+        .body(Full::new(body.clone()))
+        .map_err(|e| ClientError::Builder(e.to_string()))?;
+      *req.headers_mut() = headers.clone();
+      let resp = inner.http.send(req).await.map_err(ClientError::Transport)?;
+
+      let status = resp.status();
+      let location = resp
+        .headers()
+        .get(header::LOCATION)
+        .and_then(|v| v.to_str().ok());
+      let next = match (inner.redirect, location) {
+        (RedirectPolicy::Limited(max), Some(location)) if status.is_redirection() => {
+          if redirects == max {
+            return Err(ClientError::TooManyRedirects(redirects));
+          }
+          resolve(&uri, location)
+        }
+        _ => None,
+      };
+      let Some(next) = next else {
+        return Ok(ClientResponse {
+          inner: resp,
+          uri,
+          _permit: permit,
+        });
+      };
+
+      redirects += 1;
+      if status == StatusCode::SEE_OTHER
+        || (matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND)
+          && method == Method::POST)
+      {
+        if method != Method::HEAD {
+          method = Method::GET;
+        }
+        body = Bytes::new();
+        headers.remove(header::CONTENT_TYPE);
+        headers.remove(header::CONTENT_LENGTH);
+      }
+      if host_key(&next) != host_key(&uri) {
+        headers.remove(header::AUTHORIZATION);
+        headers.remove(header::COOKIE);
+        headers.remove(header::PROXY_AUTHORIZATION);
+      }
+      uri = next;
+    }
+  }
+}
+
+/// Resolves a `Location` header against the URL that returned it.
+fn resolve(base: &Uri, location: &str) -> Option<Uri> {
+  let base = url::Url::parse(&base.to_string()).ok()?;
+  let next = base.join(location).ok()?;
+  matches!(next.scheme(), "http" | "https")
+    .then(|| next.as_str().parse().ok())
+    .flatten()
+}
+
+/// A response from [`Client`]. Holds the request's per-host slot until it
+/// is dropped or its body is read.
+pub struct ClientResponse {
+  inner: Response<hyper::body::Incoming>,
+  uri: Uri,
+  _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl fmt::Debug for ClientResponse {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("ClientResponse")
+      .field("status", &self.inner.status())
+      .field("uri", &self.uri)
+      .field("headers", self.inner.headers())
+      .finish_non_exhaustive()
+  }
+}
+
+impl ClientResponse {
+  /// Response status.
+  pub fn status(&self) -> StatusCode {
+    self.inner.status()
+  }
+
+  /// Response headers.
+  pub fn headers(&self) -> &HeaderMap {
+    self.inner.headers()
+  }
+
+  /// The URL that produced this response, after redirects.
+  pub fn uri(&self) -> &Uri {
+    &self.uri
+  }
+
+  /// Turns `4xx` / `5xx` responses into [`ClientError::Status`].
+  ///
+  /// # Errors
+  ///
+  /// Returns the status when it is a client or server error.
+  pub fn error_for_status(self) -> Result<Self, ClientError> {
+    let status = self.status();
+    if status.is_client_error() || status.is_server_error() {
+      Err(ClientError::Status(status))
+    } else {
+      Ok(self)
+    }
+  }
+
+  /// Reads the whole body.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`ClientError::Transport`] if the body could not be read.
+  pub async fn bytes(self) -> Result<Bytes, ClientError> {
+    self
+      .inner
+      .into_body()
+      .collect()
+      .await
+      .map(http_body_util::Collected::to_bytes)
+      .map_err(|e| ClientError::Transport(Box::new(e)))
+  }
+
+  /// Reads the body as UTF-8, replacing invalid sequences.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`ClientError::Transport`] if the body could not be read.
+  pub async fn text(self) -> Result<String, ClientError> {
+    let bytes = self.bytes().await?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+  }
+
+  /// Reads the body as JSON.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`ClientError::Transport`] or [`ClientError::Json`].
+  pub async fn json<T: DeserializeOwned>(self) -> Result<T, ClientError> {
+    let bytes = self.bytes().await?;
+    serde_json::from_slice(&bytes).map_err(ClientError::Json)
+  }
+
+  /// The underlying response, streaming body included.
+  pub fn into_inner(self) -> Response<hyper::body::Incoming> {
+    self.inner
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::SocketAddr;
+  use std::time::Instant;
+
+  use tokio::io::AsyncReadExt;
+  use tokio::io::AsyncWriteExt;
+  use tokio::net::TcpListener;
+
+  use super::*;
+
+  /// HTTP/1.1 upstream: `/to-echo` answers 302 to `/echo`, `/loop`
+  /// redirects to itself, `/slow` stalls 100 ms, and anything else echoes
+  /// `<method> <path> <body>` with the request's content type.
+  async fn upstream() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+      loop {
+        let (mut sock, _) = listener.accept().await.unwrap();
+        tokio::spawn(async move {
+          let mut buf = Vec::new();
+          let mut chunk = [0; 1024];
+          let head_end = loop {
+            let n = sock.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+              break i + 4;
+            }
+          };
+          let head = String::from_utf8_lossy(&buf[..head_end]).to_lowercase();
+          let header = |name: &str| {
+            head
+              .lines()
+              .find_map(|l| l.strip_prefix(name))
+              .map(|v| v.trim().to_string())
+          };
+          let len: usize = header("content-length:").map_or(0, |v| v.parse().unwrap());
+          while buf.len() < head_end + len {
+            let n = sock.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+          }
+          let body = String::from_utf8_lossy(&buf[head_end..head_end + len]).to_string();
+          let mut line = head.split_whitespace();
+          let method = line.next().unwrap().to_uppercase();
+          let path = line.next().unwrap().to_string();
+          let route = path.split('?').next().unwrap_or_default();
+          let resp = match route {
+            "/to-echo" => {
+              "HTTP/1.1 302 Found\r\nlocation: /echo\r\ncontent-length: 0\r\n\r\n".to_string()
+            }
+            "/loop" => {
+              "HTTP/1.1 307 Temporary Redirect\r\nlocation: /loop\r\ncontent-length: 0\r\n\r\n"
+                .to_string()
+            }
+            _ => {
+              if route == "/slow" {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+              }
+              let out = format!(
+                "{method} {path} {body} {}",
+                header("content-type:").unwrap_or_default()
+              );
+              format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{out}",
+                out.len()
+              )
+            }
+          };
+          let _ = sock.write_all(resp.as_bytes()).await;
+        });
+      }
+    });
+    addr
+  }
+
+  #[tokio::test]
+  async fn builds_json_requests_and_follows_redirects() {
+    let addr = upstream().await;
+    let client = Client::new();
+
+    let resp = client
+      .post(format!("http://{addr}/to-echo"))
+      .query(&[("page", "2")])
+      .json(&serde_json::json!({ "a": 1 }))
+      .send()
+      .await
+      .unwrap();
+    assert_eq!(resp.uri().path(), "/echo");
+    // 302 after POST turns into a body-less GET.
+    assert_eq!(resp.text().await.unwrap(), "GET /echo  ");
+
+    let text = client
+      .put(format!("http://{addr}/echo?x=1"))
+      .query(&[("y", "2")])
+      .json(&serde_json::json!({ "a": 1 }))
+      .send()
+      .await
+      .unwrap()
+      .error_for_status()
+      .unwrap()
+      .text()
+      .await
+      .unwrap();
+    assert_eq!(text, r#"PUT /echo?x=1&y=2 {"a":1} application/json"#);
+
+    let err = client
+      .get(format!("http://{addr}/loop"))
+      .send()
+      .await
+      .unwrap_err();
+    assert!(matches!(err, ClientError::TooManyRedirects(10)));
+
+    let resp = Client::builder()
+      .redirect(RedirectPolicy::None)
+      .build()
+      .get(format!("http://{addr}/to-echo"))
+      .send()
+      .await
+      .unwrap();
+    assert_eq!(resp.status(), StatusCode::FOUND);
+  }
+
+  #[tokio::test]
+  async fn limits_requests_per_host() {
+    let addr = upstream().await;
+    let client = Client::builder().max_connections_per_host(1).build();
+    let started = Instant::now();
+    let fetch = || async {
+      let resp = client.get(format!("http://{addr}/slow")).send().await;
+      resp.unwrap().bytes().await.unwrap()
+    };
+    tokio::join!(fetch(), fetch());
+    assert!(started.elapsed() >= Duration::from_millis(200));
+
+    let err = client
+      .get(format!("http://{addr}/slow"))
+      .timeout(Duration::from_millis(20))
+      .send()
+      .await
+      .unwrap_err();
+    assert!(matches!(err, ClientError::Timeout));
+  }
+}
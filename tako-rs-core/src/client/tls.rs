@@ -13,10 +13,10 @@ use hyper_util::rt::TokioIo;
 use rustls::ClientConfig;
 use rustls::RootCertStore;
 use rustls::pki_types::ServerName;
-use tokio::net::TcpStream;
 use tokio::task::JoinHandle;
 use tokio_rustls::TlsConnector;
 
+use super::happy_eyeballs;
 use super::trust_store::load_root_certs;
 
 /// HTTPS client with TLS encryption support using rustls.
@@ -78,8 +78,8 @@ where
     'a: 'static,
   {
     let port = port.unwrap_or(443);
-    let addr = format!("{host}:{port}");
-    let tcp_stream = TcpStream::connect(addr).await?;
+    let tcp_stream =
+      happy_eyeballs::connect_host(host, port, happy_eyeballs::DEFAULT_ATTEMPT_DELAY).await?;
 
     let mut root_cert_store = RootCertStore::empty();
     load_root_certs(&mut root_cert_store);
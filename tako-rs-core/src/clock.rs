@@ -0,0 +1,31 @@
+//! Time source for framework-internal deadlines.
+//!
+//! Rate-limit buckets, cache freshness, idempotency expiry and the job queue
+//! read the time through [`now`] and [`system_now`] instead of
+//! `Instant::now()` / `SystemTime::now()`. Outside of tests the two are the
+//! same. When tokio's clock is paused (see [`sim`](crate::sim) or
+//! `#[tokio::test(start_paused = true)]`), they follow the paused clock, so
+//! expiries move only when the test advances time — in step with the
+//! `tokio::time` sleeps and intervals those components already use.
+
+use std::time::Instant;
+use std::time::SystemTime;
+
+/// Current monotonic time.
+#[inline]
+pub fn now() -> Instant {
+  tokio::time::Instant::now().into_std()
+}
+
+/// Current wall-clock time, shifted by however far a paused clock has been
+/// advanced.
+pub fn system_now() -> SystemTime {
+  let real = Instant::now();
+  let virtual_now = now();
+  let wall = SystemTime::now();
+  if virtual_now >= real {
+    wall + (virtual_now - real)
+  } else {
+    wall - (real - virtual_now)
+  }
+}
@@ -0,0 +1,432 @@
+//! Typed API client generation from the route table.
+//!
+//! [`generate_client`] walks every registered route and emits a client with
+//! one method per route: path parameters become arguments, and with `utoipa`
+//! or `vespera` enabled the `OpenAPI` metadata recorded on the route — query
+//! parameters and request body fields, whether described by the handler's
+//! extractors or set explicitly — becomes typed query and body structures.
+//! Without metadata, routes still get a method; bodies are untyped.
+//!
+//! Two targets are supported:
+//!
+//! - **TypeScript** — a dependency-free `ApiClient` class on top of `fetch`;
+//! - **Rust** — an `ApiClient` on top of [`V2Client`](crate::client::V2Client)
+//!   (the generated module needs `tako-rs` with the `client` feature, plus
+//!   `http`, `bytes`, `http-body-util`, `serde` and `serde_json`).
+//!
+//! Generate the file from a test or a small binary that builds the
+//! application's router, or let the CLI do it: `tako client` runs the
+//! application with [`GENERATE_CLIENT_ENV`] set, and the transport prints the
+//! client instead of serving.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use tako::codegen::{ClientLanguage, generate_client};
+//! use tako::router::Router;
+//!
+//! # fn app() -> Router { Router::new() }
+//! let router = app();
+//! std::fs::write("web/src/api.ts", generate_client(&router, ClientLanguage::TypeScript))
+//!     .unwrap();
+//! ```
+
+mod rust;
+mod typescript;
+
+use std::collections::HashSet;
+
+use http::Method;
+
+use crate::router::Router;
+
+/// Environment variable that makes a debug build's transport print a generated
+/// client and exit instead of serving; release builds ignore it. The value
+/// names the language (`typescript` / `ts` or `rust` / `rs`).
+pub const GENERATE_CLIENT_ENV: &str = "TAKO_GENERATE_CLIENT";
+
+/// Prefix of each line printed under [`GENERATE_CLIENT_ENV`], so the client
+/// can be picked out of whatever else the application writes to stdout.
+pub const CLIENT_LINE_PREFIX: &str = "tako-client";
+
+/// Target language of [`generate_client`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientLanguage {
+  /// A `fetch`-based TypeScript class.
+  TypeScript,
+  /// An async Rust client on top of `tako::client::V2Client`.
+  Rust,
+}
+
+impl ClientLanguage {
+  /// Parses `typescript` / `ts` or `rust` / `rs`, case-insensitively.
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name.to_ascii_lowercase().as_str() {
+      "typescript" | "ts" => Some(Self::TypeScript),
+      "rust" | "rs" => Some(Self::Rust),
+      _ => None,
+    }
+  }
+}
+
+/// One route as seen by the generators.
+#[derive(Debug, Clone)]
+pub struct ApiRoute {
+  /// HTTP method.
+  pub method: Method,
+  /// Registered path pattern, e.g. `/users/{id}`.
+  pub path: String,
+  /// `OpenAPI` operation id; used as the method name when set.
+  pub operation_id: Option<String>,
+  /// `OpenAPI` summary; rendered as the method's doc comment.
+  pub summary: Option<String>,
+  /// Whether the operation is marked deprecated.
+  pub deprecated: bool,
+  /// Query parameters.
+  pub query: Vec<ApiField>,
+  /// Request body, when described.
+  pub body: Option<ApiBody>,
+}
+
+/// A query parameter or body field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiField {
+  /// Wire name.
+  pub name: String,
+  /// `OpenAPI` type (`string`, `integer`, `number`, `boolean`, `array`,
+  /// `object`).
+  pub kind: String,
+  /// Whether the field must be present.
+  pub required: bool,
+  /// Doc text.
+  pub description: Option<String>,
+}
+
+/// A described request body.
+#[derive(Debug, Clone)]
+pub struct ApiBody {
+  /// Media type, e.g. `application/json`.
+  pub content_type: String,
+  /// Top-level fields; empty when only the media type is known.
+  pub fields: Vec<ApiField>,
+}
+
+/// The router's routes with their metadata, sorted by path then method.
+pub fn api_routes(router: &Router) -> Vec<ApiRoute> {
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  let openapi = router.collect_openapi_routes();
+
+  router
+    .route_table()
+    .into_iter()
+    .map(|(method, path)| {
+      let route = ApiRoute {
+        method,
+        path,
+        operation_id: None,
+        summary: None,
+        deprecated: false,
+        query: Vec::new(),
+        body: None,
+      };
+      #[cfg(any(feature = "utoipa", feature = "vespera"))]
+      let route = match openapi
+        .iter()
+        .find(|(m, p, _)| *m == route.method && *p == route.path)
+      {
+        Some((_, _, meta)) => with_openapi(route, meta),
+        None => route,
+      };
+      route
+    })
+    .collect()
+}
+
+#[cfg(any(feature = "utoipa", feature = "vespera"))]
+fn with_openapi(mut route: ApiRoute, meta: &crate::openapi::RouteOpenApi) -> ApiRoute {
+  use crate::openapi::ParameterLocation;
+
+  route.operation_id.clone_from(&meta.operation_id);
+  route.summary.clone_from(&meta.summary);
+  route.deprecated = meta.deprecated;
+  route.query = meta
+    .parameters
+    .iter()
+    .filter(|p| p.location == ParameterLocation::Query)
+    .map(|p| ApiField {
+      name: p.name.clone(),
+      kind: p.kind.clone().unwrap_or_else(|| "string".to_string()),
+      required: p.required,
+      description: p.description.clone(),
+    })
+    .collect();
+  route.body = meta.request_body.as_ref().map(|body| ApiBody {
+    content_type: body.content_type.clone(),
+    fields: body
+      .schema_properties
+      .iter()
+      .map(|p| ApiField {
+        name: p.name.clone(),
+        kind: p.property_type.clone(),
+        required: p.required,
+        description: p.description.clone(),
+      })
+      .collect(),
+  });
+  route
+}
+
+/// Generates a client for every route of `router`.
+pub fn generate_client(router: &Router, language: ClientLanguage) -> String {
+  let routes = api_routes(router);
+  let operations = operations(&routes);
+  match language {
+    ClientLanguage::TypeScript => typescript::render(&operations),
+    ClientLanguage::Rust => rust::render(&operations),
+  }
+}
+
+/// Prints the client requested through [`GENERATE_CLIENT_ENV`] and exits.
+///
+/// Release builds ignore the variable and keep serving.
+pub(crate) fn print_client_and_exit_if_requested(router: &Router) {
+  if !cfg!(debug_assertions) {
+    return;
+  }
+  let Some(name) = std::env::var_os(GENERATE_CLIENT_ENV) else {
+    return;
+  };
+  let Some(language) = name.to_str().and_then(ClientLanguage::from_name) else {
+    eprintln!(
+      "{GENERATE_CLIENT_ENV}={}: expected `typescript` or `rust`",
+      name.to_string_lossy()
+    );
+    std::process::exit(2);
+  };
+  for line in generate_client(router, language).lines() {
+    println!("{CLIENT_LINE_PREFIX}\t{line}");
+  }
+  std::process::exit(0);
+}
+
+/// A route with its generated name and parsed path.
+pub(crate) struct Operation<'a> {
+  pub(crate) route: &'a ApiRoute,
+  /// Name words, e.g. `["get", "users", "by", "id"]`; unique per client.
+  pub(crate) words: Vec<String>,
+  pub(crate) path: Vec<PathPart>,
+}
+
+impl Operation<'_> {
+  pub(crate) fn path_params(&self) -> impl Iterator<Item = (&str, bool)> {
+    self.path.iter().filter_map(|part| match part {
+      PathPart::Param { name, catch_all } => Some((name.as_str(), *catch_all)),
+      PathPart::Literal(_) => None,
+    })
+  }
+
+  /// Whether the method takes a body argument.
+  pub(crate) fn takes_body(&self) -> bool {
+    self.route.body.is_some()
+      || matches!(
+        self.route.method,
+        Method::POST | Method::PUT | Method::PATCH
+      )
+  }
+}
+
+/// A segment of a path pattern.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum PathPart {
+  Literal(String),
+  Param { name: String, catch_all: bool },
+}
+
+fn operations(routes: &[ApiRoute]) -> Vec<Operation<'_>> {
+  let mut used = HashSet::new();
+  routes
+    .iter()
+    .map(|route| {
+      let path = parse_path(&route.path);
+      let mut words = match &route.operation_id {
+        Some(id) => words(id),
+        None => default_words(&route.method, &path),
+      };
+      if words.is_empty() {
+        words.push("call".to_string());
+      }
+      let base = words.clone();
+      let mut n = 1;
+      while !used.insert(words.join("_")) {
+        n += 1;
+        words.clone_from(&base);
+        words.push(n.to_string());
+      }
+      Operation { route, words, path }
+    })
+    .collect()
+}
+
+/// `GET /users/{id}/posts` → `get users by id posts`; `/` → `index`.
+fn default_words(method: &Method, path: &[PathPart]) -> Vec<String> {
+  let mut out = words(method.as_str());
+  let start = out.len();
+  for part in path {
+    match part {
+      PathPart::Literal(text) => out.extend(words(text)),
+      PathPart::Param { name, .. } => {
+        out.push("by".to_string());
+        out.extend(words(name));
+      }
+    }
+  }
+  if out.len() == start {
+    out.push("index".to_string());
+  }
+  out
+}
+
+fn parse_path(path: &str) -> Vec<PathPart> {
+  let mut parts = Vec::new();
+  let mut rest = path;
+  while let Some(open) = rest.find('{') {
+    let Some(close) = rest[open..].find('}').map(|i| open + i) else {
+      break;
+    };
+    if open > 0 {
+      parts.push(PathPart::Literal(rest[..open].to_string()));
+    }
+    let inner = &rest[open + 1..close];
+    let (name, catch_all) = match inner.strip_prefix('*') {
+      Some(name) => (name, true),
+      None => (inner, false),
+    };
+    parts.push(PathPart::Param {
+      name: name.to_string(),
+      catch_all,
+    });
+    rest = &rest[close + 1..];
+  }
+  if !rest.is_empty() {
+    parts.push(PathPart::Literal(rest.to_string()));
+  }
+  parts
+}
+
+/// Lowercase words of `text`, split on non-alphanumerics and camel humps.
+pub(crate) fn words(text: &str) -> Vec<String> {
+  let mut out = Vec::new();
+  let mut current = String::new();
+  let mut prev_lower = false;
+  for c in text.chars() {
+    if !c.is_ascii_alphanumeric() {
+      if !current.is_empty() {
+        out.push(std::mem::take(&mut current));
+      }
+      prev_lower = false;
+      continue;
+    }
+    if c.is_ascii_uppercase() && prev_lower && !current.is_empty() {
+      out.push(std::mem::take(&mut current));
+    }
+    prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+    current.push(c.to_ascii_lowercase());
+  }
+  if !current.is_empty() {
+    out.push(current);
+  }
+  out
+}
+
+pub(crate) fn camel(words: &[String]) -> String {
+  let mut out = String::new();
+  for (i, word) in words.iter().enumerate() {
+    if i == 0 {
+      out.push_str(word);
+    } else {
+      out.push_str(&capitalize(word));
+    }
+  }
+  leading_digit_safe(out)
+}
+
+pub(crate) fn pascal(words: &[String]) -> String {
+  leading_digit_safe(words.iter().map(|w| capitalize(w)).collect())
+}
+
+pub(crate) fn snake(words: &[String]) -> String {
+  leading_digit_safe(words.join("_"))
+}
+
+fn capitalize(word: &str) -> String {
+  let mut chars = word.chars();
+  chars.next().map_or_else(String::new, |first| {
+    first.to_ascii_uppercase().to_string() + chars.as_str()
+  })
+}
+
+fn leading_digit_safe(ident: String) -> String {
+  if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+    format!("_{ident}")
+  } else {
+    ident
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use http::Method;
+
+  use super::*;
+
+  fn route(method: Method, path: &str) -> ApiRoute {
+    ApiRoute {
+      method,
+      path: path.to_string(),
+      operation_id: None,
+      summary: None,
+      deprecated: false,
+      query: Vec::new(),
+      body: None,
+    }
+  }
+
+  #[test]
+  fn names_operations_uniquely() {
+    let mut named = route(Method::GET, "/users");
+    named.operation_id = Some("listUsers".into());
+    let routes = [
+      route(Method::GET, "/"),
+      route(Method::GET, "/users/{user_id}/posts"),
+      route(Method::DELETE, "/files/{*path}"),
+      named,
+      route(Method::GET, "/list-users"),
+    ];
+    let names: Vec<_> = operations(&routes)
+      .iter()
+      .map(|op| camel(&op.words))
+      .collect();
+    assert_eq!(
+      names,
+      [
+        "getIndex",
+        "getUsersByUserIdPosts",
+        "deleteFilesByPath",
+        "listUsers",
+        "getListUsers",
+      ]
+    );
+    assert_eq!(
+      parse_path("/files/{*path}"),
+      [
+        PathPart::Literal("/files/".into()),
+        PathPart::Param {
+          name: "path".into(),
+          catch_all: true
+        },
+      ]
+    );
+    assert_eq!(words("HTTPServer2go"), ["httpserver2go"]);
+    assert_eq!(pascal(&words("create_user")), "CreateUser");
+  }
+}
@@ -0,0 +1,352 @@
+//! Rust target: an async `ApiClient` over `tako::client::V2Client`.
+
+use std::fmt::Write;
+
+use super::ApiField;
+use super::Operation;
+use super::PathPart;
+use super::pascal;
+use super::snake;
+use super::words;
+
+const PRELUDE: &str = r"//! Generated by tako from the application's route table. Do not edit.
+//!
+//! Needs `tako-rs` (feature `client`), `http`, `bytes`, `http-body-util`,
+//! `serde` and `serde_json`.
+
+#![allow(dead_code, clippy::all)]
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use http_body_util::Full;
+use serde::Serialize;
+use tako::client::V2Client;
+
+/// Error returned by [`ApiClient`] calls; non-2xx responses included.
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+";
+
+const CLIENT: &str = r#"
+/// Typed client for the application's routes.
+pub struct ApiClient {
+  base_url: String,
+  http: V2Client,
+}
+
+impl ApiClient {
+  /// Creates a client for `base_url`, e.g. `http://localhost:8080`.
+  pub fn new(base_url: impl Into<String>) -> Self {
+    Self::with_client(base_url, V2Client::builder().build())
+  }
+
+  /// Creates a client that sends through a preconfigured `V2Client`.
+  pub fn with_client(base_url: impl Into<String>, http: V2Client) -> Self {
+    let base_url = base_url.into().trim_end_matches('/').to_string();
+    Self { base_url, http }
+  }
+
+  async fn send(
+    &self,
+    method: http::Method,
+    path: String,
+    query: Vec<(&str, String)>,
+    body: Option<(&str, Vec<u8>)>,
+  ) -> Result<http::Response<Bytes>, Error> {
+    let mut uri = format!("{}{path}", self.base_url);
+    for (i, (key, value)) in query.iter().enumerate() {
+      uri.push(if i == 0 { '?' } else { '&' });
+      uri.push_str(&encode(key, false));
+      uri.push('=');
+      uri.push_str(&encode(value, false));
+    }
+    let mut req = http::Request::builder().method(method).uri(uri);
+    let body = match body {
+      Some((content_type, bytes)) => {
+        req = req.header(http::header::CONTENT_TYPE, content_type);
+        bytes
+      }
+      None => Vec::new(),
+    };
+    let res = self.http.send(req.body(Full::new(Bytes::from(body)))?).await?;
+    let (parts, body) = res.into_parts();
+    let bytes = body.collect().await?.to_bytes();
+    if !parts.status.is_success() {
+      return Err(format!("HTTP {}: {}", parts.status, String::from_utf8_lossy(&bytes)).into());
+    }
+    Ok(http::Response::from_parts(parts, bytes))
+  }
+"#;
+
+const HELPERS: &str = r#"
+/// Percent-encodes everything but unreserved characters (and `/` when
+/// `keep_slash` is set).
+fn encode(value: &str, keep_slash: bool) -> String {
+  let mut out = String::with_capacity(value.len());
+  for byte in value.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(byte as char),
+      b'/' if keep_slash => out.push('/'),
+      _ => out.push_str(&format!("%{byte:02X}")),
+    }
+  }
+  out
+}
+
+/// Encodes a serializable struct as `application/x-www-form-urlencoded`.
+fn form<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+  let serde_json::Value::Object(map) = serde_json::to_value(value)? else {
+    return Err("form body must serialize to an object".into());
+  };
+  let mut pairs = Vec::new();
+  for (key, value) in map {
+    let value = match value {
+      serde_json::Value::Null => continue,
+      serde_json::Value::String(s) => s,
+      other => other.to_string(),
+    };
+    pairs.push(format!("{}={}", encode(&key, false), encode(&value, false)));
+  }
+  Ok(pairs.join("&").into_bytes())
+}
+"#;
+
+pub(super) fn render(operations: &[Operation<'_>]) -> String {
+  let mut out = String::from(PRELUDE);
+  for op in operations {
+    let name = pascal(&op.words);
+    if !op.route.query.is_empty() {
+      query_struct(&mut out, &format!("{name}Query"), &op.route.query);
+    }
+    if let Some(body) = &op.route.body
+      && has_fields(&body.content_type, &body.fields)
+    {
+      body_struct(&mut out, &format!("{name}Body"), &body.fields);
+    }
+  }
+  out.push_str(CLIENT);
+  for op in operations {
+    method(&mut out, op);
+  }
+  out.push_str("}\n");
+  out.push_str(HELPERS);
+  out
+}
+
+fn has_fields(content_type: &str, fields: &[ApiField]) -> bool {
+  !fields.is_empty()
+    && (content_type == "application/json" || content_type == "application/x-www-form-urlencoded")
+}
+
+fn doc(out: &mut String, indent: &str, text: Option<&String>) {
+  if let Some(text) = text {
+    for line in text.lines() {
+      let _ = writeln!(out, "{indent}/// {line}");
+    }
+  }
+}
+
+fn rename(out: &mut String, field: &ApiField, ident: &str) {
+  if ident.trim_start_matches("r#") != field.name {
+    let _ = writeln!(out, "  #[serde(rename = {:?})]", field.name);
+  }
+}
+
+fn body_struct(out: &mut String, name: &str, fields: &[ApiField]) {
+  let _ = writeln!(
+    out,
+    "\n#[derive(Debug, Clone, Serialize)]\npub struct {name} {{"
+  );
+  for field in fields {
+    let ident = ident(&field.name);
+    doc(out, "  ", field.description.as_ref());
+    rename(out, field, &ident);
+    let ty = rust_type(&field.kind);
+    if field.required {
+      let _ = writeln!(out, "  pub {ident}: {ty},");
+    } else {
+      out.push_str("  #[serde(skip_serializing_if = \"Option::is_none\")]\n");
+      let _ = writeln!(out, "  pub {ident}: Option<{ty}>,");
+    }
+  }
+  out.push_str("}\n");
+}
+
+fn query_struct(out: &mut String, name: &str, fields: &[ApiField]) {
+  let _ = writeln!(
+    out,
+    "\n#[derive(Debug, Clone, Default)]\npub struct {name} {{"
+  );
+  for field in fields {
+    doc(out, "  ", field.description.as_ref());
+    let ty = match field.kind.as_str() {
+      "array" | "object" => "String",
+      kind => rust_type(kind),
+    };
+    if field.required {
+      let _ = writeln!(out, "  pub {}: {ty},", ident(&field.name));
+    } else {
+      let _ = writeln!(out, "  pub {}: Option<{ty}>,", ident(&field.name));
+    }
+  }
+  out.push_str("}\n");
+}
+
+fn method(out: &mut String, op: &Operation<'_>) {
+  let route = op.route;
+  let type_name = pascal(&op.words);
+
+  let mut params = vec!["&self".to_string()];
+  for (param, _) in op.path_params() {
+    params.push(format!("{}: impl std::fmt::Display", ident(param)));
+  }
+  if !route.query.is_empty() {
+    params.push(format!("query: &{type_name}Query"));
+  }
+  let body = if op.takes_body() {
+    match &route.body {
+      Some(body) if has_fields(&body.content_type, &body.fields) => {
+        params.push(format!("body: &{type_name}Body"));
+        if body.content_type == "application/json" {
+          "Some((\"application/json\", serde_json::to_vec(body)?))".to_string()
+        } else {
+          "Some((\"application/x-www-form-urlencoded\", form(body)?))".to_string()
+        }
+      }
+      Some(body) if body.content_type == "application/json" => {
+        params.push("body: &impl Serialize".to_string());
+        "Some((\"application/json\", serde_json::to_vec(body)?))".to_string()
+      }
+      Some(body) if body.content_type == "application/x-www-form-urlencoded" => {
+        params.push("body: &impl Serialize".to_string());
+        "Some((\"application/x-www-form-urlencoded\", form(body)?))".to_string()
+      }
+      Some(_) => {
+        params.push("content_type: &str".to_string());
+        params.push("body: Vec<u8>".to_string());
+        "Some((content_type, body))".to_string()
+      }
+      None => {
+        params.push("body: Option<&serde_json::Value>".to_string());
+        "body.map(serde_json::to_vec).transpose()?.map(|b| (\"application/json\", b))".to_string()
+      }
+    }
+  } else {
+    "None".to_string()
+  };
+
+  out.push('\n');
+  if let Some(summary) = &route.summary {
+    doc(out, "  ", Some(summary));
+    out.push_str("  ///\n");
+  }
+  let _ = writeln!(out, "  /// `{} {}`", route.method, route.path);
+  if route.deprecated {
+    out.push_str("  #[deprecated]\n");
+  }
+  let _ = writeln!(
+    out,
+    "  pub async fn {}({}) -> Result<http::Response<Bytes>, Error> {{",
+    fn_ident(&op.words),
+    params.join(", ")
+  );
+
+  let query = if route.query.is_empty() {
+    "Vec::new()"
+  } else {
+    out.push_str("    let mut pairs = Vec::new();\n");
+    for field in &route.query {
+      let ident = ident(&field.name);
+      if field.required {
+        let _ = writeln!(
+          out,
+          "    pairs.push(({:?}, query.{ident}.to_string()));",
+          field.name
+        );
+      } else {
+        let _ = writeln!(
+          out,
+          "    if let Some(value) = &query.{ident} {{\n      pairs.push(({:?}, value.to_string()));\n    }}",
+          field.name
+        );
+      }
+    }
+    "pairs"
+  };
+
+  let _ = writeln!(
+    out,
+    "    self\n      .send(http::Method::{}, {}, {query}, {body})\n      .await\n  }}",
+    method_const(&route.method),
+    path_expr(op)
+  );
+}
+
+fn method_const(method: &http::Method) -> String {
+  match method.as_str() {
+    m
+    @ ("GET" | "POST" | "PUT" | "DELETE" | "HEAD" | "OPTIONS" | "CONNECT" | "PATCH" | "TRACE") => {
+      m.to_string()
+    }
+    other => format!("from_bytes(b{other:?}).unwrap()"),
+  }
+}
+
+/// A `format!` expression building the path with encoded parameters.
+fn path_expr(op: &Operation<'_>) -> String {
+  let mut template = String::new();
+  let mut args = Vec::new();
+  for part in &op.path {
+    match part {
+      PathPart::Literal(text) => {
+        template.push_str(&text.replace('{', "{{").replace('}', "}}"));
+      }
+      PathPart::Param { name, catch_all } => {
+        template.push_str("{}");
+        args.push(format!("encode(&{}.to_string(), {catch_all})", ident(name)));
+      }
+    }
+  }
+  if args.is_empty() {
+    format!("{template:?}.to_string()")
+  } else {
+    format!("format!({template:?}, {})", args.join(", "))
+  }
+}
+
+fn rust_type(kind: &str) -> &'static str {
+  match kind {
+    "integer" => "i64",
+    "number" => "f64",
+    "boolean" => "bool",
+    "array" => "Vec<serde_json::Value>",
+    "object" => "serde_json::Value",
+    _ => "String",
+  }
+}
+
+const KEYWORDS: &[&str] = &[
+  "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+  "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+  "ref", "return", "static", "struct", "trait", "true", "try", "type", "unsafe", "use", "where",
+  "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
+  "unsized", "virtual", "yield",
+];
+
+/// A field or parameter identifier in `snake_case`.
+fn ident(name: &str) -> String {
+  let ident = snake(&words(name));
+  match ident.as_str() {
+    "self" | "crate" | "super" | "query" | "body" | "content_type" => format!("{ident}_"),
+    _ if KEYWORDS.contains(&ident.as_str()) => format!("r#{ident}"),
+    _ => ident,
+  }
+}
+
+fn fn_ident(words: &[String]) -> String {
+  let ident = snake(words);
+  match ident.as_str() {
+    "new" | "with_client" | "send" | "self" | "crate" | "super" => format!("{ident}_"),
+    _ if KEYWORDS.contains(&ident.as_str()) => format!("r#{ident}"),
+    _ => ident,
+  }
+}
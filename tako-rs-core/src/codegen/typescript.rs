@@ -0,0 +1,302 @@
+//! TypeScript target: a dependency-free `ApiClient` class over `fetch`.
+
+use std::fmt::Write;
+
+use super::ApiField;
+use super::Operation;
+use super::PathPart;
+use super::camel;
+use super::pascal;
+
+const PRELUDE: &str = r"// Generated by tako from the application's route table. Do not edit.
+
+export interface ClientOptions {
+  /** Origin and path prefix of the API, e.g. `https://api.example.com`. */
+  baseUrl?: string;
+  /** Headers sent with every request. */
+  headers?: Record<string, string>;
+  /** `fetch` implementation; defaults to the global one. */
+  fetch?: typeof fetch;
+}
+
+/** Thrown for non-2xx responses. */
+export class ApiError extends Error {
+  constructor(
+    readonly status: number,
+    readonly body: string,
+  ) {
+    super(`HTTP ${status}`);
+  }
+}
+
+export type QueryValue = string | number | boolean | null | undefined;
+";
+
+const CLIENT: &str = r#"
+export class ApiClient {
+  private readonly baseUrl: string;
+  private readonly headers: Record<string, string>;
+  private readonly fetchImpl: typeof fetch;
+
+  constructor(options: ClientOptions = {}) {
+    this.baseUrl = (options.baseUrl ?? "").replace(/\/+$/, "");
+    this.headers = options.headers ?? {};
+    this.fetchImpl = options.fetch ?? globalThis.fetch.bind(globalThis);
+  }
+
+  private async request<T>(
+    method: string,
+    path: string,
+    query?: object,
+    body?: unknown,
+    contentType = "application/json",
+  ): Promise<T> {
+    let url = this.baseUrl + path;
+    if (query) {
+      const params = new URLSearchParams();
+      for (const [key, value] of Object.entries(query)) {
+        if (value !== undefined && value !== null) params.append(key, String(value));
+      }
+      const qs = params.toString();
+      if (qs) url += `?${qs}`;
+    }
+    const headers: Record<string, string> = { ...this.headers };
+    let payload: BodyInit | undefined;
+    if (body !== undefined) {
+      if (contentType === "application/json") {
+        headers["content-type"] = contentType;
+        payload = JSON.stringify(body);
+      } else if (contentType === "application/x-www-form-urlencoded") {
+        const form = new URLSearchParams();
+        for (const [key, value] of Object.entries(body as object)) {
+          if (value !== undefined && value !== null) form.append(key, String(value));
+        }
+        payload = form;
+      } else {
+        payload = body as BodyInit;
+      }
+    }
+    const res = await this.fetchImpl(url, { method, headers, body: payload });
+    const text = await res.text();
+    if (!res.ok) throw new ApiError(res.status, text);
+    const type = res.headers.get("content-type") ?? "";
+    return (type.includes("json") && text ? JSON.parse(text) : text) as T;
+  }
+"#;
+
+pub(super) fn render(operations: &[Operation<'_>]) -> String {
+  let mut out = String::from(PRELUDE);
+  for op in operations {
+    let name = pascal(&op.words);
+    if !op.route.query.is_empty() {
+      interface(&mut out, &format!("{name}Query"), &op.route.query, true);
+    }
+    if let Some(body) = &op.route.body
+      && has_fields(body.content_type.as_str(), &body.fields)
+    {
+      interface(&mut out, &format!("{name}Body"), &body.fields, false);
+    }
+  }
+  out.push_str(CLIENT);
+  for op in operations {
+    method(&mut out, op);
+  }
+  out.push_str("}\n");
+  out
+}
+
+fn has_fields(content_type: &str, fields: &[ApiField]) -> bool {
+  !fields.is_empty() && !content_type.starts_with("multipart/")
+}
+
+fn interface(out: &mut String, name: &str, fields: &[ApiField], query: bool) {
+  let _ = writeln!(out, "\nexport interface {name} {{");
+  for field in fields {
+    if let Some(doc) = &field.description {
+      let _ = writeln!(out, "  /** {} */", doc.replace("*/", "*\\/"));
+    }
+    let ty = match (query, field.kind.as_str()) {
+      (true, "array" | "object") => "QueryValue",
+      (_, kind) => ts_type(kind),
+    };
+    let optional = if field.required { "" } else { "?" };
+    let _ = writeln!(out, "  {}{optional}: {ty};", property(&field.name));
+  }
+  out.push_str("}\n");
+}
+
+fn method(out: &mut String, op: &Operation<'_>) {
+  let route = op.route;
+  let name = camel(&op.words);
+  let type_name = pascal(&op.words);
+
+  let mut params = Vec::new();
+  for (param, _) in op.path_params() {
+    params.push((ident(param), "string | number".to_string(), false));
+  }
+  let query = (!route.query.is_empty()).then(|| {
+    let optional = !route.query.iter().any(|f| f.required);
+    params.push(("query".to_string(), format!("{type_name}Query"), optional));
+    "query"
+  });
+  let body = op.takes_body().then(|| {
+    let ty = match &route.body {
+      Some(body) if body.content_type.starts_with("multipart/") => "FormData".to_string(),
+      Some(body) if has_fields(&body.content_type, &body.fields) => format!("{type_name}Body"),
+      _ => "unknown".to_string(),
+    };
+    params.push(("body".to_string(), ty, route.body.is_none()));
+  });
+
+  out.push_str("\n  /**\n");
+  if let Some(summary) = &route.summary {
+    let _ = writeln!(out, "   * {}", summary.replace("*/", "*\\/"));
+    out.push_str("   *\n");
+  }
+  let _ = writeln!(out, "   * `{} {}`", route.method, route.path);
+  if route.deprecated {
+    out.push_str("   * @deprecated\n");
+  }
+  out.push_str("   */\n");
+  let _ = writeln!(
+    out,
+    "  {name}<T = unknown>({}): Promise<T> {{",
+    signature(&params)
+  );
+
+  let mut args = vec![format!("\"{}\"", route.method), path_literal(op)];
+  if body.is_some() {
+    args.push(query.unwrap_or("undefined").to_string());
+    args.push("body".to_string());
+    if let Some(body) = &route.body
+      && body.content_type != "application/json"
+    {
+      args.push(format!("\"{}\"", body.content_type));
+    }
+  } else if let Some(query) = query {
+    args.push(query.to_string());
+  }
+  let _ = writeln!(out, "    return this.request<T>({});", args.join(", "));
+  out.push_str("  }\n");
+}
+
+/// Renders `(name, type, optional)` parameters. An optional parameter
+/// followed by a required one takes `undefined` instead of being omitted.
+fn signature(params: &[(String, String, bool)]) -> String {
+  let mut required_after = false;
+  let mut out: Vec<String> = params
+    .iter()
+    .rev()
+    .map(|(name, ty, optional)| {
+      let param = match (*optional, required_after) {
+        (false, _) => format!("{name}: {ty}"),
+        (true, false) => format!("{name}?: {ty}"),
+        (true, true) => format!("{name}: {ty} | undefined"),
+      };
+      required_after |= !optional;
+      param
+    })
+    .collect();
+  out.reverse();
+  out.join(", ")
+}
+
+/// The path as a template literal with encoded parameters.
+fn path_literal(op: &Operation<'_>) -> String {
+  let mut out = String::from("`");
+  for part in &op.path {
+    match part {
+      PathPart::Literal(text) => {
+        out.push_str(
+          &text
+            .replace('\\', "\\\\")
+            .replace('`', "\\`")
+            .replace("${", "\\${"),
+        );
+      }
+      PathPart::Param { name, catch_all } => {
+        let encode = if *catch_all {
+          "encodeURI"
+        } else {
+          "encodeURIComponent"
+        };
+        let _ = write!(out, "${{{encode}(String({}))}}", ident(name));
+      }
+    }
+  }
+  out.push('`');
+  out
+}
+
+fn ts_type(kind: &str) -> &'static str {
+  match kind {
+    "integer" | "number" => "number",
+    "boolean" => "boolean",
+    "array" => "unknown[]",
+    "object" => "Record<string, unknown>",
+    _ => "string",
+  }
+}
+
+/// An interface property name, quoted when it is not an identifier.
+fn property(name: &str) -> String {
+  let plain = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+    && name
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+  if plain {
+    name.to_string()
+  } else {
+    format!("{name:?}")
+  }
+}
+
+/// A parameter identifier; reserved words get a trailing `_`.
+fn ident(name: &str) -> String {
+  const RESERVED: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "query",
+    "body",
+  ];
+  let ident = camel(&super::words(name));
+  if RESERVED.contains(&ident.as_str()) {
+    format!("{ident}_")
+  } else {
+    ident
+  }
+}
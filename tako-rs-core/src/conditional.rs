@@ -0,0 +1,161 @@
+//! Conditional GET for JSON handlers.
+//!
+//! [`CachedJson`] serializes like [`Json`](crate::extractors::json::Json) and
+//! adds a strong `ETag` computed from the serialized bytes. When the request
+//! carried an `If-None-Match` that matches, the handler's response is turned
+//! into a bodiless `304 Not Modified` before it leaves the handler, so the
+//! only change an API handler needs is its return type:
+//!
+//! ```rust
+//! use serde::Serialize;
+//! use tako::conditional::CachedJson;
+//!
+//! #[derive(Serialize)]
+//! struct Catalog {
+//!     items: Vec<String>,
+//! }
+//!
+//! async fn catalog() -> CachedJson<Catalog> {
+//!     CachedJson(Catalog { items: vec!["tea".into(), "coffee".into()] })
+//! }
+//! ```
+//!
+//! Only `GET` and `HEAD` requests are answered with `304`; the `ETag` is sent
+//! on every successful response. `If-None-Match` uses the weak comparison
+//! from RFC 9110 §13.1.2, so `W/"…"` validators from intermediaries still
+//! match. Unlike the `etag` middleware this does not buffer an arbitrary
+//! body — the bytes are already in hand after serialization.
+
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header::CONTENT_LENGTH;
+use http::header::CONTENT_TYPE;
+use http::header::ETAG;
+use http::header::IF_NONE_MATCH;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+
+use crate::body::TakoBody;
+use crate::responder::Responder;
+use crate::types::Request;
+use crate::types::Response;
+
+/// JSON responder with a strong `ETag` and automatic `304 Not Modified`.
+#[doc(alias = "etag")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CachedJson<T>(pub T);
+
+/// Response extension left by [`CachedJson`] so the handler wrapper knows
+/// the `ETag` may be checked against the request.
+#[derive(Clone, Copy)]
+struct Conditional;
+
+impl<T> Responder for CachedJson<T>
+where
+  T: Serialize,
+{
+  fn into_response(self) -> Response {
+    let Ok(buf) = serde_json::to_vec(&self.0) else {
+      // Same failure response as `Json`.
+      return crate::extractors::json::Json(self.0).into_response();
+    };
+    let etag = etag_for(&buf);
+    let mut res = Response::new(TakoBody::from(buf));
+    let headers = res.headers_mut();
+    headers.insert(
+      CONTENT_TYPE,
+      HeaderValue::from_static(mime::APPLICATION_JSON.as_ref()),
+    );
+    headers.insert(ETAG, etag);
+    res.extensions_mut().insert(Conditional);
+    res
+  }
+}
+
+/// Strong validator from the SHA-256 of `bytes`.
+fn etag_for(bytes: &[u8]) -> HeaderValue {
+  let digest = Sha256::digest(bytes);
+  let mut tag = String::with_capacity(2 + digest.len() * 2);
+  tag.push('"');
+  for b in digest {
+    tag.push_str(&format!("{b:02x}"));
+  }
+  tag.push('"');
+  HeaderValue::from_str(&tag).expect("hex is a valid header value")
+}
+
+/// The `If-None-Match` worth remembering while the handler runs: only safe
+/// methods are ever answered with `304`.
+pub(crate) fn if_none_match(req: &Request) -> Option<HeaderValue> {
+  if req.method() == Method::GET || req.method() == Method::HEAD {
+    req.headers().get(IF_NONE_MATCH).cloned()
+  } else {
+    None
+  }
+}
+
+/// Turns a [`CachedJson`] response into `304` when `if_none_match` matches
+/// its `ETag`. Every other response passes through untouched.
+pub(crate) fn finish(if_none_match: Option<HeaderValue>, mut res: Response) -> Response {
+  if res.extensions_mut().remove::<Conditional>().is_none() {
+    return res;
+  }
+  let Some(if_none_match) = if_none_match else {
+    return res;
+  };
+  if !res.status().is_success()
+    || !res
+      .headers()
+      .get(ETAG)
+      .is_some_and(|etag| matches(&if_none_match, etag))
+  {
+    return res;
+  }
+  let (mut parts, _) = res.into_parts();
+  parts.status = StatusCode::NOT_MODIFIED;
+  parts.headers.remove(CONTENT_LENGTH);
+  Response::from_parts(parts, TakoBody::empty())
+}
+
+/// Weak comparison of `etag` against an `If-None-Match` list.
+fn matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+  let Ok(list) = if_none_match.to_str() else {
+    return false;
+  };
+  let Ok(etag) = etag.to_str() else {
+    return false;
+  };
+  let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+  let etag = opaque(etag);
+  list
+    .split(',')
+    .any(|candidate| candidate.trim() == "*" || opaque(candidate) == etag)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn etag_is_strong_and_stable() {
+    let a = etag_for(br#"{"a":1}"#);
+    assert_eq!(a, etag_for(br#"{"a":1}"#));
+    assert_ne!(a, etag_for(br#"{"a":2}"#));
+    let s = a.to_str().unwrap();
+    assert!(s.starts_with('"') && !s.starts_with("W/"));
+    assert_eq!(s.len(), 66);
+  }
+
+  #[test]
+  fn if_none_match_uses_weak_comparison() {
+    let etag = HeaderValue::from_static("\"abc\"");
+    let hv = HeaderValue::from_static;
+    assert!(matches(&hv("\"abc\""), &etag));
+    assert!(matches(&hv("W/\"abc\""), &etag));
+    assert!(matches(&hv("\"x\", W/\"abc\""), &etag));
+    assert!(matches(&hv("*"), &etag));
+    assert!(!matches(&hv("\"abcd\""), &etag));
+  }
+}
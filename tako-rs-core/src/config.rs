@@ -1,4 +1,4 @@
-//! Configuration loading from environment variables.
+//! Configuration loading from environment variables and JSON files.
 //!
 //! Provides a `Config<T>` wrapper that can be loaded from environment variables
 //! and injected as router state for access in handlers.
@@ -19,6 +19,12 @@
 //! // Load from environment variables (DATABASE_URL, PORT, DEBUG)
 //! // let config = Config::<AppConfig>::from_env().expect("missing config");
 //! ```
+//!
+//! With the `watch` feature, `Config::watch_json_file` keeps a JSON-file
+//! config in global state and swaps it whenever the file changes, so
+//! `State<Config<T>>` handlers see the new values on their next request.
+
+use std::path::Path;
 
 use serde::de::DeserializeOwned;
 
@@ -54,6 +60,15 @@ impl<T: DeserializeOwned + Clone> Config<T> {
     Ok(Config(config))
   }
 
+  /// Loads configuration from a JSON file.
+  pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+    let path = path.as_ref();
+    let raw = std::fs::read(path).map_err(|e| ConfigError(format!("{}: {e}", path.display())))?;
+    let config: T =
+      serde_json::from_slice(&raw).map_err(|e| ConfigError(format!("{}: {e}", path.display())))?;
+    Ok(Config(config))
+  }
+
   /// Creates a Config from an existing value.
   pub fn new(config: T) -> Self {
     Config(config)
@@ -70,6 +85,51 @@ impl<T: DeserializeOwned + Clone> Config<T> {
   }
 }
 
+#[cfg(feature = "watch")]
+impl<T: DeserializeOwned + Clone + Send + Sync + 'static> Config<T> {
+  /// Loads `path` into global state (as `Config<T>`) and reloads it whenever
+  /// the file changes.
+  ///
+  /// A reload that cannot read or parse the file keeps the previous value
+  /// and logs a warning, so a half-written file never takes the config away.
+  /// Each successful reload emits [`ids::CONFIG_RELOADED`] on the global
+  /// application arbiter with the file in the `path` metadata. Reloading
+  /// stops when the returned handle is dropped.
+  ///
+  /// [`ids::CONFIG_RELOADED`]: crate::signals::ids::CONFIG_RELOADED
+  pub fn watch_json_file(
+    path: impl Into<std::path::PathBuf>,
+  ) -> Result<crate::watch::WatchHandle, ConfigError> {
+    use crate::signals::Signal;
+    use crate::signals::app_signals;
+    use crate::signals::ids;
+
+    let path = path.into();
+    crate::state::set_state(Self::from_json_file(&path)?);
+    let reload_path = path.clone();
+    crate::watch::FileWatcher::new()
+      .path(&path)
+      .on_change(move |_| {
+        let path = reload_path.clone();
+        async move {
+          match Self::from_json_file(&path) {
+            Ok(config) => {
+              crate::state::set_state(config);
+              app_signals()
+                .emit(Signal::new(ids::CONFIG_RELOADED).meta("path", path.display().to_string()))
+                .await;
+            }
+            Err(e) => {
+              tracing::warn!(error = %e, "config reload failed; keeping the previous value");
+            }
+          }
+        }
+      })
+      .start()
+      .map_err(|e| ConfigError(e.to_string()))
+  }
+}
+
 /// Error type for configuration loading.
 #[derive(Debug, Clone)]
 pub struct ConfigError(pub String);
@@ -0,0 +1,100 @@
+//! Client-disconnect notification for in-flight requests.
+//!
+//! Every `serve_*` transport — tokio and compio, HTTP/3 and the per-thread
+//! servers — inserts a [`DisconnectToken`] into the request's extensions
+//! before dispatch. The token fires when the peer goes away: either the
+//! request future is dropped (HTTP/1 connection closed mid-request, HTTP/2
+//! stream reset), an HTTP/3 stream fails before the response is finished, or
+//! the connection as a whole ends while a streaming response is still being
+//! produced. Long-running handlers can
+//! select on [`DisconnectToken::disconnected`] to abandon work nobody will
+//! receive.
+//!
+//! Requests built by hand (tests, `Router::dispatch` from a custom
+//! transport) carry no token; the `Disconnected` extractor then falls back to
+//! one that never fires.
+
+use tokio_util::sync::CancellationToken;
+use tokio_util::sync::DropGuard;
+
+/// Cancellation handle that fires once the client is gone.
+///
+/// Transports create one token per connection and hold its
+/// [`guard`](Self::guard) until the connection ends, and give every request
+/// a [`child`](Self::child). A request's token therefore fires when the
+/// request itself is abandoned and, at the latest, when its connection
+/// closes, even if a streaming body is still being produced.
+#[derive(Debug, Clone, Default)]
+pub struct DisconnectToken(CancellationToken);
+
+impl DisconnectToken {
+  /// Creates a token that has not fired.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns a token that fires with `self` but can also fire on its own.
+  ///
+  /// Transports hold one token per connection and hand each request a child.
+  #[must_use]
+  pub fn child(&self) -> Self {
+    Self(self.0.child_token())
+  }
+
+  /// Marks the client as disconnected, waking every waiter.
+  pub fn disconnect(&self) {
+    self.0.cancel();
+  }
+
+  /// Whether the client has already disconnected.
+  #[inline]
+  pub fn is_disconnected(&self) -> bool {
+    self.0.is_cancelled()
+  }
+
+  /// Resolves once the client has disconnected.
+  pub async fn disconnected(&self) {
+    self.0.cancelled().await;
+  }
+
+  /// Returns a guard that fires the token when dropped, unless
+  /// [`DisconnectGuard::disarm`] is called first.
+  ///
+  /// Transports hold it across dispatch so that hyper dropping the request
+  /// future signals the handler's spawned work.
+  pub fn guard(&self) -> DisconnectGuard {
+    DisconnectGuard(self.0.clone().drop_guard())
+  }
+}
+
+/// Fires its [`DisconnectToken`] on drop; see [`DisconnectToken::guard`].
+#[derive(Debug)]
+#[must_use = "dropping the guard immediately fires the token"]
+pub struct DisconnectGuard(DropGuard);
+
+impl DisconnectGuard {
+  /// Consumes the guard without firing the token.
+  pub fn disarm(self) {
+    let _ = self.0.disarm();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn child_fires_with_parent_and_guard_fires_on_drop() {
+    let conn = DisconnectToken::new();
+    let req = conn.child();
+    assert!(!req.is_disconnected());
+    conn.disconnect();
+    req.disconnected().await;
+
+    let token = DisconnectToken::new();
+    token.guard().disarm();
+    assert!(!token.is_disconnected());
+    drop(token.guard());
+    assert!(token.is_disconnected());
+  }
+}
@@ -0,0 +1,454 @@
+//! Framework-level error type for handlers and extractor rejections.
+//!
+//! [`Error`] carries an HTTP status, an [`ErrorKind`], a client-facing
+//! message, optional JSON details, and the underlying cause. Handlers return
+//! `Result<T, tako::Error>` and use `?` on any `std::error::Error` or
+//! `anyhow::Error`; unclassified causes become `500 Internal Server Error`
+//! whose message is not leaked to the client.
+//!
+//! By default an error renders as a JSON envelope:
+//!
+//! ```json
+//! {"error": {"kind": "not_found", "status": 404, "message": "no user 7"}}
+//! ```
+//!
+//! Every error response — including extractor rejections and
+//! `anyhow::Error` returns — carries its [`Error`] in the response
+//! extensions, so [`Router::error_renderer`](crate::router::Router::error_renderer)
+//! can map kinds to one consistent format for the whole application.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::Error;
+//! use tako::extractors::params::Params;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct UserPath {
+//!     id: u64,
+//! }
+//!
+//! async fn show(Params(path): Params<UserPath>) -> Result<String, Error> {
+//!     let raw = std::fs::read_to_string(format!("users/{}.json", path.id))
+//!         .map_err(|_| Error::not_found(format!("no user {}", path.id)))?;
+//!     let user: serde_json::Value = serde_json::from_str(&raw)?;
+//!     Ok(user["name"].to_string())
+//! }
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use http_body::Body;
+use http_body_util::BodyExt;
+
+use self::codes::ErrorCode;
+use crate::body::TakoBody;
+use crate::responder::Responder;
+use crate::types::Response;
+
+pub mod codes;
+
+/// Largest rejection body read back into [`Error::message`].
+const REJECTION_BODY_LIMIT: u64 = 4096;
+
+/// Broad category of an [`Error`], derived from its status code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+  /// `400 Bad Request`
+  BadRequest,
+  /// `401 Unauthorized`
+  Unauthorized,
+  /// `403 Forbidden`
+  Forbidden,
+  /// `404 Not Found`
+  NotFound,
+  /// `405 Method Not Allowed`
+  MethodNotAllowed,
+  /// `408 Request Timeout` / `504 Gateway Timeout`
+  Timeout,
+  /// `409 Conflict`
+  Conflict,
+  /// `413 Payload Too Large`
+  PayloadTooLarge,
+  /// `415 Unsupported Media Type`
+  UnsupportedMediaType,
+  /// `422 Unprocessable Entity`
+  Validation,
+  /// `429 Too Many Requests`
+  TooManyRequests,
+  /// `500 Internal Server Error`
+  Internal,
+  /// `503 Service Unavailable`
+  Unavailable,
+  /// Any other status.
+  Other,
+}
+
+impl ErrorKind {
+  /// Classifies a status code.
+  pub fn from_status(status: StatusCode) -> Self {
+    match status {
+      StatusCode::BAD_REQUEST => Self::BadRequest,
+      StatusCode::UNAUTHORIZED => Self::Unauthorized,
+      StatusCode::FORBIDDEN => Self::Forbidden,
+      StatusCode::NOT_FOUND => Self::NotFound,
+      StatusCode::METHOD_NOT_ALLOWED => Self::MethodNotAllowed,
+      StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => Self::Timeout,
+      StatusCode::CONFLICT => Self::Conflict,
+      StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge,
+      StatusCode::UNSUPPORTED_MEDIA_TYPE => Self::UnsupportedMediaType,
+      StatusCode::UNPROCESSABLE_ENTITY => Self::Validation,
+      StatusCode::TOO_MANY_REQUESTS => Self::TooManyRequests,
+      StatusCode::INTERNAL_SERVER_ERROR => Self::Internal,
+      StatusCode::SERVICE_UNAVAILABLE => Self::Unavailable,
+      _ => Self::Other,
+    }
+  }
+
+  /// `snake_case` name used in the JSON envelope, e.g. `not_found`.
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::BadRequest => "bad_request",
+      Self::Unauthorized => "unauthorized",
+      Self::Forbidden => "forbidden",
+      Self::NotFound => "not_found",
+      Self::MethodNotAllowed => "method_not_allowed",
+      Self::Timeout => "timeout",
+      Self::Conflict => "conflict",
+      Self::PayloadTooLarge => "payload_too_large",
+      Self::UnsupportedMediaType => "unsupported_media_type",
+      Self::Validation => "validation",
+      Self::TooManyRequests => "too_many_requests",
+      Self::Internal => "internal",
+      Self::Unavailable => "unavailable",
+      Self::Other => "error",
+    }
+  }
+}
+
+impl fmt::Display for ErrorKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// An error a handler can return, rendered as an HTTP response.
+///
+/// Cheap to clone: the cause is shared. Like `anyhow::Error` it deliberately
+/// does not implement `std::error::Error`, which is what lets every error
+/// type convert into it with `?`.
+#[derive(Clone)]
+pub struct Error {
+  status: StatusCode,
+  kind: ErrorKind,
+  message: Cow<'static, str>,
+  details: Option<serde_json::Value>,
+  source: Option<Arc<anyhow::Error>>,
+  rejection: bool,
+  code: Option<ErrorCode>,
+}
+
+impl Error {
+  /// Creates an error with `status` and a client-facing `message`.
+  pub fn new(status: StatusCode, message: impl Into<Cow<'static, str>>) -> Self {
+    Self {
+      status,
+      kind: ErrorKind::from_status(status),
+      message: message.into(),
+      details: None,
+      source: None,
+      rejection: false,
+      code: None,
+    }
+  }
+
+  /// `400 Bad Request`.
+  pub fn bad_request(message: impl Into<Cow<'static, str>>) -> Self {
+    Self::new(StatusCode::BAD_REQUEST, message)
+  }
+
+  /// `401 Unauthorized`.
+  pub fn unauthorized(message: impl Into<Cow<'static, str>>) -> Self {
+    Self::new(StatusCode::UNAUTHORIZED, message)
+  }
+
+  /// `403 Forbidden`.
+  pub fn forbidden(message: impl Into<Cow<'static, str>>) -> Self {
+    Self::new(StatusCode::FORBIDDEN, message)
+  }
+
+  /// `404 Not Found`.
+  pub fn not_found(message: impl Into<Cow<'static, str>>) -> Self {
+    Self::new(StatusCode::NOT_FOUND, message)
+  }
+
+  /// `409 Conflict`.
+  pub fn conflict(message: impl Into<Cow<'static, str>>) -> Self {
+    Self::new(StatusCode::CONFLICT, message)
+  }
+
+  /// `422 Unprocessable Entity`.
+  pub fn validation(message: impl Into<Cow<'static, str>>) -> Self {
+    Self::new(StatusCode::UNPROCESSABLE_ENTITY, message)
+  }
+
+  /// `500 Internal Server Error` caused by `err`. The client sees only the
+  /// generic reason phrase; `err` is logged when the error is rendered.
+  pub fn internal(err: impl Into<anyhow::Error>) -> Self {
+    Self::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").with_source(err)
+  }
+
+  /// Attaches machine-readable details, rendered under `details`.
+  #[must_use]
+  pub fn with_details(mut self, details: impl serde::Serialize) -> Self {
+    self.details = serde_json::to_value(details).ok();
+    self
+  }
+
+  /// Attaches the underlying cause.
+  #[must_use]
+  pub fn with_source(mut self, source: impl Into<anyhow::Error>) -> Self {
+    self.source = Some(Arc::new(source.into()));
+    self
+  }
+
+  /// The HTTP status.
+  pub fn status(&self) -> StatusCode {
+    self.status
+  }
+
+  /// The category derived from the status.
+  pub fn kind(&self) -> ErrorKind {
+    self.kind
+  }
+
+  /// The client-facing message.
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+
+  /// Details attached with [`Error::with_details`].
+  pub fn details(&self) -> Option<&serde_json::Value> {
+    self.details.as_ref()
+  }
+
+  /// The underlying cause, if any.
+  pub fn source(&self) -> Option<&anyhow::Error> {
+    self.source.as_deref()
+  }
+
+  /// The application error code, for errors built from an
+  /// [`AppError`](codes::AppError).
+  pub fn code(&self) -> Option<ErrorCode> {
+    self.code
+  }
+
+  /// Whether the error stands for an extractor rejection.
+  pub fn is_rejection(&self) -> bool {
+    self.rejection
+  }
+
+  /// The JSON envelope rendered by default.
+  pub fn to_json(&self) -> serde_json::Value {
+    let mut error = serde_json::json!({
+      "kind": self.kind.as_str(),
+      "status": self.status.as_u16(),
+      "message": self.message,
+    });
+    if let Some(code) = &self.code {
+      error["code"] = code.name().into();
+      if let Some(docs) = code.docs_url() {
+        error["docs"] = docs.into();
+      }
+    }
+    if let Some(details) = &self.details {
+      error["details"] = details.clone();
+    }
+    serde_json::json!({ "error": error })
+  }
+
+  /// Rebuilds the error behind an extractor rejection response and attaches
+  /// it, leaving the response otherwise untouched. A short text body becomes
+  /// the message, a JSON body the details.
+  pub(crate) async fn tag_rejection(response: Response) -> Response {
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error())
+      || response.extensions().get::<Error>().is_some()
+    {
+      return response;
+    }
+    let mut error = Error::new(status, status.canonical_reason().unwrap_or("Error"));
+    error.rejection = true;
+
+    let (mut parts, body) = response.into_parts();
+    let body = if body
+      .size_hint()
+      .exact()
+      .is_some_and(|n| n <= REJECTION_BODY_LIMIT)
+    {
+      match body.collect().await {
+        Ok(collected) => {
+          let bytes = collected.to_bytes();
+          let json = parts
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("json"));
+          if json {
+            error.details = serde_json::from_slice(&bytes).ok();
+          } else if let Ok(text) = std::str::from_utf8(&bytes)
+            && !text.trim().is_empty()
+          {
+            error.message = Cow::Owned(text.trim().to_string());
+          }
+          TakoBody::from(bytes)
+        }
+        Err(_) => TakoBody::empty(),
+      }
+    } else {
+      body
+    };
+    parts.extensions.insert(error);
+    Response::from_parts(parts, body)
+  }
+}
+
+impl fmt::Debug for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Error")
+      .field("status", &self.status)
+      .field("kind", &self.kind)
+      .field("message", &self.message)
+      .field("details", &self.details)
+      .field("source", &self.source)
+      .field("rejection", &self.rejection)
+      .field("code", &self.code)
+      .finish()
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} ({})", self.message, self.status)?;
+    if let Some(source) = &self.source {
+      write!(f, ": {source:#}")?;
+    }
+    Ok(())
+  }
+}
+
+/// Any error becomes a `500` via [`Error::internal`].
+impl<E> From<E> for Error
+where
+  E: Into<anyhow::Error>,
+{
+  fn from(err: E) -> Self {
+    Error::internal(err)
+  }
+}
+
+/// Maps a domain error to the HTTP status it should be answered with.
+///
+/// Implementing it makes the type a [`Responder`], so handlers can return
+/// `Result<T, MyError>` and use `?` without funnelling everything through
+/// `anyhow` into opaque `500`s. The error renders through
+/// [`Error::from_status_error`]: client errors show their `Display` message,
+/// server errors show only the reason phrase and log the cause.
+///
+/// # Examples
+///
+/// ```rust
+/// use tako::StatusCode;
+/// use tako::error::IntoStatus;
+///
+/// #[derive(Debug)]
+/// enum AccountError {
+///     NotFound(u64),
+///     Overdrawn,
+///     Ledger(std::io::Error),
+/// }
+///
+/// impl std::fmt::Display for AccountError {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         match self {
+///             Self::NotFound(id) => write!(f, "no account {id}"),
+///             Self::Overdrawn => f.write_str("insufficient funds"),
+///             Self::Ledger(e) => write!(f, "ledger unavailable: {e}"),
+///         }
+///     }
+/// }
+///
+/// impl std::error::Error for AccountError {}
+///
+/// impl From<std::io::Error> for AccountError {
+///     fn from(e: std::io::Error) -> Self {
+///         Self::Ledger(e)
+///     }
+/// }
+///
+/// impl IntoStatus for AccountError {
+///     fn status(&self) -> StatusCode {
+///         match self {
+///             Self::NotFound(_) => StatusCode::NOT_FOUND,
+///             Self::Overdrawn => StatusCode::CONFLICT,
+///             Self::Ledger(_) => StatusCode::SERVICE_UNAVAILABLE,
+///         }
+///     }
+/// }
+///
+/// async fn balance() -> Result<String, AccountError> {
+///     let raw = std::fs::read_to_string("ledger.txt")?;
+///     Ok(raw)
+/// }
+/// ```
+pub trait IntoStatus: std::error::Error + Send + Sync + 'static {
+  /// The response status.
+  fn status(&self) -> StatusCode;
+
+  /// The client-facing message. Defaults to the `Display` output for client
+  /// errors and the reason phrase otherwise.
+  fn message(&self) -> Cow<'static, str> {
+    let status = self.status();
+    if status.is_server_error() {
+      Cow::Borrowed(status.canonical_reason().unwrap_or("Internal Server Error"))
+    } else {
+      Cow::Owned(self.to_string())
+    }
+  }
+}
+
+impl Error {
+  /// Converts a domain error using its [`IntoStatus`] mapping. Server errors
+  /// keep `err` as the source so it is logged.
+  pub fn from_status_error<E: IntoStatus>(err: E) -> Self {
+    let error = Self::new(err.status(), err.message());
+    if error.status.is_server_error() {
+      error.with_source(err)
+    } else {
+      error
+    }
+  }
+}
+
+impl Responder for Error {
+  fn into_response(self) -> Response {
+    if self.status.is_server_error()
+      && let Some(source) = &self.source
+    {
+      tracing::error!(status = %self.status, error = %format!("{source:#}"), "request failed");
+    }
+    let mut res = Response::new(TakoBody::from(self.to_json().to_string()));
+    *res.status_mut() = self.status;
+    res
+      .headers_mut()
+      .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    res.extensions_mut().insert(self);
+    res
+  }
+}
@@ -0,0 +1,333 @@
+//! Application error codes.
+//!
+//! An [`ErrorCode`] names one failure an API can report — a stable
+//! machine-readable name, its HTTP status, a short title, and optionally a
+//! page documenting it. Codes are plain constants; [`register`] them once at
+//! startup so [`registered`] and the `OpenAPI` exports can list them all.
+//!
+//! Return an [`AppError`] built from a code. It renders through [`Error`],
+//! so the body is the usual JSON envelope with `code` (and `docs`) added,
+//! [`Router::error_renderer`](crate::router::Router::error_renderer) sees it
+//! like any other error, and `?` converts it inside handlers returning
+//! `Result<_, tako::Error>`:
+//!
+//! ```json
+//! {"error": {"kind": "not_found", "status": 404, "message": "User not found",
+//!            "code": "user_not_found", "docs": "https://docs.example.com/errors/user_not_found"}}
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::StatusCode;
+//! use tako::error::codes::{self, AppError, ErrorCode};
+//!
+//! const USER_NOT_FOUND: ErrorCode =
+//!     ErrorCode::new("user_not_found", StatusCode::NOT_FOUND, "User not found")
+//!         .docs("https://docs.example.com/errors/user_not_found");
+//!
+//! codes::register([USER_NOT_FOUND]);
+//!
+//! async fn show() -> Result<String, AppError> {
+//!     Err(AppError::new(USER_NOT_FOUND).message("no user 7"))
+//! }
+//! ```
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write as _;
+
+use http::StatusCode;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+use super::Error;
+use crate::responder::Responder;
+use crate::types::Response;
+
+/// A registered kind of application error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorCode {
+  name: &'static str,
+  status: StatusCode,
+  title: &'static str,
+  docs: Option<&'static str>,
+}
+
+impl ErrorCode {
+  /// A code called `name` (e.g. `user_not_found`) answered with `status`.
+  /// `title` is the default client-facing message.
+  pub const fn new(name: &'static str, status: StatusCode, title: &'static str) -> Self {
+    Self {
+      name,
+      status,
+      title,
+      docs: None,
+    }
+  }
+
+  /// Links the page documenting the code.
+  #[must_use]
+  pub const fn docs(mut self, url: &'static str) -> Self {
+    self.docs = Some(url);
+    self
+  }
+
+  /// The machine-readable name.
+  pub fn name(&self) -> &'static str {
+    self.name
+  }
+
+  /// The HTTP status.
+  pub fn status(&self) -> StatusCode {
+    self.status
+  }
+
+  /// The default message.
+  pub fn title(&self) -> &'static str {
+    self.title
+  }
+
+  /// The documentation URL, if any.
+  pub fn docs_url(&self) -> Option<&'static str> {
+    self.docs
+  }
+}
+
+static REGISTRY: Lazy<RwLock<BTreeMap<&'static str, ErrorCode>>> =
+  Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+/// Adds `codes` to the process-wide registry. Registering the same code
+/// twice is harmless.
+///
+/// # Panics
+///
+/// Panics if a name is already registered with a different status, title
+/// or docs URL: two meanings for one code would break clients matching on
+/// it.
+pub fn register(codes: impl IntoIterator<Item = ErrorCode>) {
+  let mut registry = REGISTRY.write();
+  for code in codes {
+    match registry.get(code.name) {
+      Some(existing) => assert!(
+        *existing == code,
+        "error code `{}` registered twice with different definitions",
+        code.name
+      ),
+      None => {
+        registry.insert(code.name, code);
+      }
+    }
+  }
+}
+
+/// Every registered code, sorted by name.
+pub fn registered() -> Vec<ErrorCode> {
+  REGISTRY.read().values().copied().collect()
+}
+
+/// The registered code called `name`.
+pub fn lookup(name: &str) -> Option<ErrorCode> {
+  REGISTRY.read().get(name).copied()
+}
+
+/// JSON Schema of the error envelope, with `code` limited to the registered
+/// names and a table of every code in the description.
+pub fn schema() -> serde_json::Value {
+  let codes = registered();
+  let mut description = String::from(
+    "Error envelope. `code` identifies the error; clients should match on it rather than on `message`.\n\n\
+     | code | status | title |\n|---|---|---|\n",
+  );
+  for code in &codes {
+    let title = match code.docs {
+      Some(url) => format!("[{}]({url})", code.title),
+      None => code.title.to_string(),
+    };
+    let _ = writeln!(
+      description,
+      "| `{}` | {} | {title} |",
+      code.name,
+      code.status.as_u16()
+    );
+  }
+  let names: Vec<&str> = codes.iter().map(|c| c.name).collect();
+  serde_json::json!({
+    "type": "object",
+    "description": description,
+    "required": ["error"],
+    "properties": {
+      "error": {
+        "type": "object",
+        "required": ["kind", "status", "message"],
+        "properties": {
+          "kind": { "type": "string" },
+          "status": { "type": "integer", "format": "int32" },
+          "message": { "type": "string" },
+          "code": { "type": "string", "enum": names },
+          "docs": { "type": "string", "format": "uri" },
+          "details": {}
+        }
+      }
+    }
+  })
+}
+
+/// Adds [`schema`] to a `utoipa` document as the `Error` component.
+///
+/// ```rust,ignore
+/// #[derive(OpenApi)]
+/// #[openapi(modifiers(&ErrorCodes))]
+/// struct ApiDoc;
+/// ```
+#[cfg(feature = "utoipa")]
+#[cfg_attr(docsrs, doc(cfg(feature = "utoipa")))]
+pub struct ErrorCodes;
+
+#[cfg(feature = "utoipa")]
+impl utoipa::Modify for ErrorCodes {
+  fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+    let Ok(schema) = serde_json::from_value::<utoipa::openapi::schema::Schema>(schema()) else {
+      return;
+    };
+    openapi
+      .components
+      .get_or_insert_with(Default::default)
+      .schemas
+      .insert("Error".to_string(), schema.into());
+  }
+}
+
+/// Adds [`schema`] to a `vespera` document as the `Error` component.
+#[cfg(feature = "vespera")]
+#[cfg_attr(docsrs, doc(cfg(feature = "vespera")))]
+pub fn add_to_vespera(spec: &mut vespera_core::OpenApi) {
+  let Ok(schema) = serde_json::from_value::<vespera_core::Schema>(schema()) else {
+    return;
+  };
+  spec
+    .components
+    .get_or_insert(vespera_core::schema::Components {
+      schemas: None,
+      responses: None,
+      parameters: None,
+      examples: None,
+      request_bodies: None,
+      headers: None,
+      security_schemes: None,
+    })
+    .schemas
+    .get_or_insert_with(BTreeMap::new)
+    .insert("Error".to_string(), schema);
+}
+
+/// An error identified by an [`ErrorCode`].
+pub struct AppError {
+  code: ErrorCode,
+  message: Option<Cow<'static, str>>,
+  details: Option<serde_json::Value>,
+  source: Option<anyhow::Error>,
+}
+
+impl AppError {
+  /// An error with `code`'s status and title.
+  pub fn new(code: ErrorCode) -> Self {
+    Self {
+      code,
+      message: None,
+      details: None,
+      source: None,
+    }
+  }
+
+  /// Replaces the code's title as the client-facing message.
+  #[must_use]
+  pub fn message(mut self, message: impl Into<Cow<'static, str>>) -> Self {
+    self.message = Some(message.into());
+    self
+  }
+
+  /// Attaches machine-readable details, rendered under `details`.
+  #[must_use]
+  pub fn details(mut self, details: impl serde::Serialize) -> Self {
+    self.details = serde_json::to_value(details).ok();
+    self
+  }
+
+  /// Attaches the underlying cause; it is logged, never sent.
+  #[must_use]
+  pub fn source(mut self, source: impl Into<anyhow::Error>) -> Self {
+    self.source = Some(source.into());
+    self
+  }
+
+  /// The error's code.
+  pub fn code(&self) -> ErrorCode {
+    self.code
+  }
+}
+
+impl fmt::Debug for AppError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("AppError")
+      .field("code", &self.code.name)
+      .field("message", &self.message)
+      .field("details", &self.details)
+      .field("source", &self.source)
+      .finish()
+  }
+}
+
+impl fmt::Display for AppError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let message = self.message.as_deref().unwrap_or(self.code.title);
+    write!(f, "{}: {message}", self.code.name)
+  }
+}
+
+impl From<AppError> for Error {
+  fn from(err: AppError) -> Self {
+    let code = err.code;
+    let mut error = Error::new(
+      code.status,
+      err.message.unwrap_or(Cow::Borrowed(code.title)),
+    );
+    error.code = Some(code);
+    error.details = err.details;
+    if let Some(source) = err.source {
+      error = error.with_source(source);
+    }
+    error
+  }
+}
+
+impl Responder for AppError {
+  fn into_response(self) -> Response {
+    Error::from(self).into_response()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const OUT_OF_STOCK: ErrorCode =
+    ErrorCode::new("out_of_stock", StatusCode::CONFLICT, "Out of stock");
+
+  #[test]
+  fn register_accepts_repeats_but_not_redefinitions() {
+    register([OUT_OF_STOCK, OUT_OF_STOCK]);
+    assert_eq!(lookup("out_of_stock"), Some(OUT_OF_STOCK));
+    let clash = std::panic::catch_unwind(|| {
+      register([ErrorCode::new("out_of_stock", StatusCode::GONE, "Gone")]);
+    });
+    assert!(clash.is_err());
+    assert!(
+      schema()["properties"]["error"]["properties"]["code"]["enum"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("out_of_stock"))
+    );
+  }
+}
@@ -0,0 +1,252 @@
+//! Static site generation: render `GET` routes to files on disk.
+//!
+//! [`StaticExport`] walks every registered `GET` route, dispatches a request
+//! for it through the full router (middleware included), and writes each
+//! successful response body under an output directory. Parameterised routes
+//! are rendered once per user-supplied parameter set; templates without a
+//! parameter set are reported as skipped. The same router keeps serving
+//! dynamic traffic, so one codebase can back a hybrid SSG/server deployment.
+//!
+//! Paths map to files the way static hosts expect: `/` becomes `index.html`,
+//! `/about` becomes `about/index.html`, and a path whose last segment has an
+//! extension (`/feed.xml`) is written verbatim.
+//!
+//! Parameter values are percent-encoded into the request path, so a value
+//! may contain spaces, `?` or `#`. Files are named after the decoded
+//! segments; a `/` inside a `{name}` value stays encoded as `%2F`.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use tako::export::StaticExport;
+//! use tako::router::Router;
+//!
+//! # async fn run(router: Router) -> std::io::Result<()> {
+//! let report = StaticExport::new("dist")
+//!     .params("/posts/{slug}", [[("slug", "hello-world")], [("slug", "second-post")]])
+//!     .path("/404.html")
+//!     .run(&router)
+//!     .await?;
+//! println!("wrote {} files", report.written.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use http::Method;
+use http::StatusCode;
+use http_body_util::BodyExt;
+
+use crate::body::TakoBody;
+use crate::router::Router;
+
+/// Outcome of a [`StaticExport::run`] call.
+#[derive(Debug, Default)]
+pub struct ExportReport {
+  /// Files written, in render order.
+  pub written: Vec<PathBuf>,
+  /// Route templates that have parameters but no parameter sets.
+  pub skipped: Vec<String>,
+  /// Concrete paths whose response was not a `2xx`.
+  pub failed: Vec<(String, StatusCode)>,
+}
+
+/// Renders a router's `GET` routes into a directory tree.
+pub struct StaticExport {
+  out_dir: PathBuf,
+  params: HashMap<String, Vec<Vec<(String, String)>>>,
+  extra_paths: Vec<String>,
+}
+
+impl StaticExport {
+  /// Creates an exporter writing into `out_dir`.
+  pub fn new(out_dir: impl Into<PathBuf>) -> Self {
+    Self {
+      out_dir: out_dir.into(),
+      params: HashMap::new(),
+      extra_paths: Vec::new(),
+    }
+  }
+
+  /// Supplies parameter sets for a route template such as `/posts/{slug}`.
+  ///
+  /// The template is rendered once per set. Calling this again for the same
+  /// template appends more sets.
+  #[must_use]
+  pub fn params<S, P, K, V>(mut self, template: &str, sets: S) -> Self
+  where
+    S: IntoIterator<Item = P>,
+    P: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<String>,
+  {
+    let entry = self.params.entry(template.to_string()).or_default();
+    for set in sets {
+      entry.push(set.into_iter().map(|(k, v)| (k.into(), v.into())).collect());
+    }
+    self
+  }
+
+  /// Renders an additional concrete path, e.g. a `404.html` served by the
+  /// fallback handler.
+  #[must_use]
+  pub fn path(mut self, path: impl Into<String>) -> Self {
+    self.extra_paths.push(path.into());
+    self
+  }
+
+  /// Dispatches every page through `router` and writes the results.
+  ///
+  /// Fails on the first filesystem error or on a path that would escape the
+  /// output directory; non-`2xx` responses are collected in
+  /// [`ExportReport::failed`] instead.
+  pub async fn run(&self, router: &Router) -> io::Result<ExportReport> {
+    let mut report = ExportReport::default();
+    let mut pages = Vec::new();
+
+    if let Some(routes) = router.routes.get(&Method::GET) {
+      for route in routes.iter().filter_map(std::sync::Weak::upgrade) {
+        let template = route.path.as_str();
+        if !template.contains('{') {
+          pages.push(template.to_string());
+          continue;
+        }
+        match self.params.get(template) {
+          Some(sets) => pages.extend(sets.iter().filter_map(|set| fill(template, set))),
+          None => report.skipped.push(template.to_string()),
+        }
+      }
+    }
+    pages.extend(self.extra_paths.iter().cloned());
+
+    for page in pages {
+      let req = http::Request::builder()
+        .method(Method::GET)
+        .uri(&page)
+        .body(TakoBody::empty())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+      let resp = router.dispatch(req).await;
+      if !resp.status().is_success() {
+        report.failed.push((page, resp.status()));
+        continue;
+      }
+      let body = resp
+        .into_body()
+        .collect()
+        .await
+        .map_err(io::Error::other)?
+        .to_bytes();
+
+      let file = self.out_dir.join(file_for(&page)?);
+      if let Some(parent) = file.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+      }
+      tokio::fs::write(&file, &body).await?;
+      report.written.push(file);
+    }
+
+    Ok(report)
+  }
+}
+
+/// Substitutes `{name}` and `{*name}` segments; `None` if a value is missing.
+///
+/// Values are percent-encoded. A catch-all value keeps its `/` separators.
+fn fill(template: &str, params: &[(String, String)]) -> Option<String> {
+  let mut out = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    out.push_str(&rest[..start]);
+    let end = start + rest[start..].find('}')?;
+    let raw = &rest[start + 1..end];
+    let name = raw.trim_start_matches('*');
+    let (_, value) = params.iter().find(|(k, _)| k == name)?;
+    if raw.starts_with('*') {
+      let segments: Vec<_> = value.split('/').map(urlencoding::encode).collect();
+      out.push_str(&segments.join("/"));
+    } else {
+      out.push_str(&urlencoding::encode(value));
+    }
+    rest = &rest[end + 1..];
+  }
+  out.push_str(rest);
+  Some(out)
+}
+
+/// Maps a URL path to a relative file path inside the output directory,
+/// decoding each segment.
+fn file_for(page: &str) -> io::Result<PathBuf> {
+  let path = page.split(['?', '#']).next().unwrap_or(page);
+  let mut file = PathBuf::new();
+  for segment in path.split('/').filter(|s| !s.is_empty()) {
+    let segment = urlencoding::decode(segment)
+      .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+      .replace('/', "%2F");
+    if segment == "." || segment == ".." || segment.contains(['\\', '\0']) {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("export path escapes the output directory: {page}"),
+      ));
+    }
+    file.push(segment);
+  }
+  let has_extension = !path.ends_with('/') && Path::new(&file).extension().is_some();
+  if !has_extension {
+    file.push("index.html");
+  }
+  Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fills_named_and_catch_all_params() {
+    let params = vec![
+      ("slug".to_string(), "hello".to_string()),
+      ("rest".to_string(), "a/b".to_string()),
+    ];
+    assert_eq!(
+      fill("/posts/{slug}", &params).as_deref(),
+      Some("/posts/hello")
+    );
+    assert_eq!(fill("/docs/{*rest}", &params).as_deref(), Some("/docs/a/b"));
+    assert_eq!(fill("/users/{id}", &params), None);
+  }
+
+  #[test]
+  fn encodes_reserved_characters_in_values() {
+    let params = vec![("slug".to_string(), "a b/c?d#e".to_string())];
+    let page = fill("/posts/{slug}", &params).unwrap();
+    assert_eq!(page, "/posts/a%20b%2Fc%3Fd%23e");
+    assert_eq!(
+      file_for(&page).unwrap(),
+      PathBuf::from("posts/a b%2Fc?d#e/index.html")
+    );
+    assert_eq!(
+      fill(
+        "/docs/{*rest}",
+        &[("rest".to_string(), "a b/c".to_string())]
+      )
+      .as_deref(),
+      Some("/docs/a%20b/c")
+    );
+    assert!(file_for("/%2E%2E/etc").is_err());
+  }
+
+  #[test]
+  fn maps_paths_to_files() {
+    assert_eq!(file_for("/").unwrap(), PathBuf::from("index.html"));
+    assert_eq!(
+      file_for("/about").unwrap(),
+      PathBuf::from("about/index.html")
+    );
+    assert_eq!(file_for("/feed.xml").unwrap(), PathBuf::from("feed.xml"));
+    assert!(file_for("/../etc/passwd").is_err());
+  }
+}
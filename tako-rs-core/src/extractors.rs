@@ -46,6 +46,9 @@ pub fn is_json_content_type(headers: &http::HeaderMap) -> bool {
     })
 }
 
+/// Request body size limits shared by the body-limit middleware and extractors.
+pub mod body_limit;
+
 /// JSON request body parsing and deserialization.
 pub mod json;
 
@@ -99,6 +102,20 @@ pub trait FromRequest<'a>: Sized {
   fn from_request(
     req: &'a mut crate::types::Request,
   ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a;
+
+  /// Records what this extractor contributes to a route's `OpenAPI`
+  /// operation (parameters, request body).
+  ///
+  /// Called once per route at registration time when an `OpenAPI` backend
+  /// feature is enabled, so routes are documented without manual
+  /// annotation. The default contributes nothing; the built-in `Json`,
+  /// `Query`, `Params`/`Path`, `TypedHeader`, `Form` and multipart
+  /// extractors override it.
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "utoipa", feature = "vespera"))))]
+  fn describe_openapi(operation: &mut crate::openapi::RouteOpenApi) {
+    let _ = operation;
+  }
 }
 
 /// Trait for extracting data from HTTP request parts (metadata only).
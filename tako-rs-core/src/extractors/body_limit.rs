@@ -0,0 +1,127 @@
+//! Request body size limits shared by the body-limit middleware and the
+//! body-consuming extractors.
+//!
+//! The effective limit travels in request extensions. The `BodyLimit`
+//! middleware stores a [`RequestBodyLimit`] for every request it admits, and
+//! [`Route::body_limit`](crate::route::Route::body_limit) stores a
+//! [`RouteBodyLimit`] before any middleware runs so a single route can raise
+//! or lower the global cap. `Json`, `Form`, `Cbor`, `Text` and the
+//! multipart extractors read the limit through [`body_limit`] and answer
+//! oversized payloads with the same `413` JSON body as the middleware, built
+//! by [`payload_too_large`].
+
+use bytes::Bytes;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::CONTENT_LENGTH;
+use http::header::CONTENT_TYPE;
+use http_body_util::BodyExt;
+use http_body_util::LengthLimitError;
+use http_body_util::Limited;
+
+use crate::body::TakoBody;
+use crate::types::Request;
+use crate::types::Response;
+
+/// Effective request body limit in bytes, inserted by the `BodyLimit`
+/// middleware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestBodyLimit(pub usize);
+
+/// Per-route body limit override, inserted by the router for routes
+/// configured with [`Route::body_limit`](crate::route::Route::body_limit).
+///
+/// The `BodyLimit` middleware prefers this value over its own configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteBodyLimit(pub usize);
+
+/// Errors produced by [`collect_limited`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollectError {
+  /// The body is larger than the limit (in bytes).
+  TooLarge(usize),
+  /// The body stream failed for another reason.
+  Read(String),
+}
+
+/// Returns the body limit that applies to a request, if any.
+///
+/// The middleware's [`RequestBodyLimit`] wins over a bare
+/// [`RouteBodyLimit`]; the middleware already folds the route override in.
+pub fn body_limit(extensions: &http::Extensions) -> Option<usize> {
+  extensions
+    .get::<RequestBodyLimit>()
+    .map(|l| l.0)
+    .or_else(|| extensions.get::<RouteBodyLimit>().map(|l| l.0))
+}
+
+/// Returns `true` when the request's `Content-Length` exceeds `limit`.
+pub fn exceeds_content_length(req: &Request, limit: usize) -> bool {
+  req
+    .headers()
+    .get(CONTENT_LENGTH)
+    .and_then(|v| v.to_str().ok())
+    .and_then(|s| s.parse::<usize>().ok())
+    .is_some_and(|len| len > limit)
+}
+
+/// Buffers the request body, enforcing the request's configured limit.
+///
+/// Declared lengths over the limit are rejected before reading; chunked
+/// bodies are cut off as soon as the limit is crossed. Without a configured
+/// limit the body is collected as-is.
+pub async fn collect_limited(req: &mut Request) -> Result<Bytes, CollectError> {
+  let Some(limit) = body_limit(req.extensions()) else {
+    return req
+      .body_mut()
+      .collect()
+      .await
+      .map(http_body_util::Collected::to_bytes)
+      .map_err(|e| CollectError::Read(e.to_string()));
+  };
+
+  if exceeds_content_length(req, limit) {
+    return Err(CollectError::TooLarge(limit));
+  }
+
+  let body = std::mem::take(req.body_mut());
+  match Limited::new(body, limit).collect().await {
+    Ok(collected) => Ok(collected.to_bytes()),
+    Err(e) if is_length_limit_error(&*e) => Err(CollectError::TooLarge(limit)),
+    Err(e) => Err(CollectError::Read(e.to_string())),
+  }
+}
+
+/// Returns `true` when a body error (possibly wrapped by an outer `Limited`
+/// layer) was caused by a length limit.
+pub fn is_length_limit_error(err: &(dyn std::error::Error + 'static)) -> bool {
+  let mut current = Some(err);
+  while let Some(e) = current {
+    if e.is::<LengthLimitError>() {
+      return true;
+    }
+    current = e.source();
+  }
+  false
+}
+
+/// Builds the `413 Payload Too Large` response used for oversized bodies.
+///
+/// ```json
+/// {"error":"payload_too_large","message":"request body exceeds 1024 bytes","limit":1024}
+/// ```
+pub fn payload_too_large(limit: usize) -> Response {
+  let body = serde_json::json!({
+    "error": "payload_too_large",
+    "message": format!("request body exceeds {limit} bytes"),
+    "limit": limit,
+  });
+  let mut res = Response::new(TakoBody::from(
+    serde_json::to_vec(&body).unwrap_or_default(),
+  ));
+  *res.status_mut() = StatusCode::PAYLOAD_TOO_LARGE;
+  res
+    .headers_mut()
+    .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+  res
+}
@@ -41,12 +41,14 @@
 
 use http::StatusCode;
 use http::header::HeaderValue;
-use http_body_util::BodyExt;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 
 use crate::body::TakoBody;
 use crate::extractors::FromRequest;
+use crate::extractors::body_limit::CollectError;
+use crate::extractors::body_limit::collect_limited;
+use crate::extractors::body_limit::payload_too_large;
 use crate::responder::Responder;
 use crate::types::Request;
 use crate::types::Response;
@@ -109,6 +111,16 @@ impl Default for SimdJsonMode {
 #[doc(alias = "json")]
 pub struct Json<T>(pub T);
 
+#[cfg(feature = "schemars")]
+#[cfg_attr(docsrs, doc(cfg(feature = "schemars")))]
+impl<T: schemars::JsonSchema> Json<T> {
+  /// JSON Schema of the payload `T`, for request bodies and responses alike.
+  /// See [`crate::schemas`].
+  pub fn json_schema() -> schemars::Schema {
+    crate::schemas::schema_for::<T>()
+  }
+}
+
 /// Error types for JSON extraction and deserialization.
 ///
 /// This error type implements `std::error::Error` for integration with
@@ -124,6 +136,8 @@ pub enum JsonError {
   BodyReadError(String),
   /// JSON deserialization failed (syntax error, type mismatch, etc.).
   DeserializationError(String),
+  /// The request body exceeds the configured body limit (in bytes).
+  PayloadTooLarge(usize),
 }
 
 impl std::fmt::Display for JsonError {
@@ -135,6 +149,7 @@ impl std::fmt::Display for JsonError {
       Self::MissingContentType => write!(f, "missing content type header"),
       Self::BodyReadError(err) => write!(f, "failed to read request body: {err}"),
       Self::DeserializationError(err) => write!(f, "failed to deserialize JSON: {err}"),
+      Self::PayloadTooLarge(limit) => write!(f, "request body exceeds {limit} bytes"),
     }
   }
 }
@@ -163,6 +178,7 @@ impl Responder for JsonError {
         format!("Failed to deserialize JSON: {err}"),
       )
         .into_response(),
+      JsonError::PayloadTooLarge(limit) => payload_too_large(limit),
     }
   }
 }
@@ -182,6 +198,7 @@ where
   /// Returns [`JsonError`] if:
   /// - The Content-Type header is missing or not `application/json`.
   /// - The request body cannot be read.
+  /// - The request body exceeds the configured body limit.
   /// - The JSON cannot be deserialized into the target type.
   fn from_request(
     req: &'a mut Request,
@@ -192,13 +209,11 @@ where
         return Err(JsonError::InvalidContentType);
       }
 
-      // Read the complete request body into memory
-      let body_bytes = req
-        .body_mut()
-        .collect()
-        .await
-        .map_err(|e| JsonError::BodyReadError(e.to_string()))?
-        .to_bytes();
+      // Read the complete request body into memory, within the body limit
+      let body_bytes = collect_limited(req).await.map_err(|e| match e {
+        CollectError::TooLarge(limit) => JsonError::PayloadTooLarge(limit),
+        CollectError::Read(err) => JsonError::BodyReadError(err),
+      })?;
 
       if body_bytes.is_empty() {
         return Err(JsonError::DeserializationError(
@@ -238,6 +253,11 @@ where
       Ok(Json(data))
     }
   }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut crate::openapi::RouteOpenApi) {
+    operation.set_struct_request_body::<T>("application/json");
+  }
 }
 
 impl<T> Responder for Json<T>
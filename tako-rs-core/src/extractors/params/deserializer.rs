@@ -19,14 +19,38 @@ impl<'de> PathParamsDeserializer<'de> {
   ) -> Result<V::Value, PathParamsDeError> {
     if self.0.len() != 1 {
       return Err(de::Error::custom(format!(
-        "expected exactly 1 path parameter, got {}",
-        self.0.len()
+        "expected exactly 1 path parameter, got {} (captured: [{}])",
+        self.0.len(),
+        captured(self.0)
       )));
     }
-    f(ValueDeserializer(&self.0[0].1), visitor)
+    f(ValueDeserializer(&self.0[0].1), visitor).map_err(|e| in_segment(self.0, 0, &e))
   }
 }
 
+/// Comma-separated names of the captured parameters.
+fn captured(params: &[(String, String)]) -> String {
+  params
+    .iter()
+    .map(|(k, _)| k.as_str())
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Prefixes a value error with the parameter it came from, e.g.
+/// ``path parameter `id` (segment 1): invalid digit found in string``.
+fn in_segment(
+  params: &[(String, String)],
+  index: usize,
+  err: &PathParamsDeError,
+) -> PathParamsDeError {
+  de::Error::custom(format!(
+    "path parameter `{}` (segment {}): {err}",
+    params[index].0,
+    index + 1
+  ))
+}
+
 // Trampoline closures preserve the `V` visitor binding through the trait
 // method; replacing them with `Trait::method` function refs introduces UFCS
 // ambiguity around the generic `V`.
@@ -85,12 +109,11 @@ impl<'de> Deserializer<'de> for PathParamsDeserializer<'de> {
       // the user at the actual mismatch between the route pattern (e.g.
       // `/users/{id}/posts/{post_id}` — 2 slots) and the tuple type they
       // tried to extract.
-      let captured: Vec<&str> = self.0.iter().map(|(k, _)| k.as_str()).collect();
       return Err(de::Error::custom(format!(
         "expected tuple of {} path parameters, got {} (captured: [{}])",
         len,
         self.0.len(),
-        captured.join(", ")
+        captured(self.0)
       )));
     }
     self.deserialize_seq(visitor)
@@ -202,9 +225,12 @@ impl<'de> de::SeqAccess<'de> for PathParamsSeqAccess<'de> {
     if self.index >= self.params.len() {
       return Ok(None);
     }
-    let value = &self.params[self.index].1;
+    let index = self.index;
     self.index += 1;
-    seed.deserialize(ValueDeserializer(value)).map(Some)
+    seed
+      .deserialize(ValueDeserializer(&self.params[index].1))
+      .map(Some)
+      .map_err(|e| in_segment(self.params, index, &e))
   }
 
   fn size_hint(&self) -> Option<usize> {
@@ -242,7 +268,10 @@ impl<'de> MapAccess<'de> for PathParamsMapAccess<'de> {
       .value
       .take()
       .expect("next_value_seed called before next_key_seed");
-    seed.deserialize(ValueDeserializer(value))
+    let index = self.index - 1;
+    seed
+      .deserialize(ValueDeserializer(value))
+      .map_err(|e| in_segment(self.params, index, &e))
   }
 }
 
@@ -305,6 +334,25 @@ mod tests {
     assert_eq!(value, (7, "x".to_string()));
   }
 
+  #[test]
+  fn deserialize_errors_name_the_segment() {
+    let err = deserialize::<(u64, u64)>(&[("org", "7"), ("id", "x")]).unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .starts_with("path parameter `id` (segment 2):")
+    );
+
+    let err = deserialize::<(u64, String)>(&[("id", "7")]).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "expected tuple of 2 path parameters, got 1 (captured: [id])"
+    );
+
+    let err = deserialize::<u64>(&[("org", "7"), ("id", "8")]).unwrap_err();
+    assert!(err.to_string().contains("captured: [org, id]"));
+  }
+
   #[test]
   fn deserialize_vec_string() {
     let value: Vec<String> = deserialize(&[("a", "1"), ("b", "2"), ("c", "3")]).unwrap();
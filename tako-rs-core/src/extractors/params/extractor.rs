@@ -29,6 +29,11 @@ where
   ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
     futures_util::future::ready(Self::extract_params(req.extensions()))
   }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut crate::openapi::RouteOpenApi) {
+    operation.add_struct_parameters::<T>(crate::openapi::ParameterLocation::Path);
+  }
 }
 
 impl<'a, T> FromRequestParts<'a> for Params<T>
@@ -3,7 +3,8 @@
 //! - GraphQLRequest / GraphQLBatchRequest extractors
 //! - GraphQLResponse / GraphQLBatchResponse responders
 //! - GraphQLSubscription responder for WebSocket subscriptions
-//! - APQ (Apollo Persisted Queries) and execution-cost limits via submodules
+//! - APQ (Apollo Persisted Queries), execution-cost limits, and Apollo
+//!   Federation subgraph helpers via submodules
 //!
 //! Enable via the `async-graphql` cargo feature.
 //!
@@ -57,6 +58,8 @@
 
 /// Apollo Persisted Queries (APQ) flow.
 pub mod apq;
+/// Apollo Federation v2 subgraph SDL export and router headers.
+pub mod federation;
 /// Execution-cost limits (max depth, max complexity).
 pub mod limits;
 
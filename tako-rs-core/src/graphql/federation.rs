@@ -0,0 +1,236 @@
+//! Apollo Federation v2 subgraph support.
+//!
+//! `async-graphql` already implements the federation resolvers: build the
+//! schema with `enable_federation()` and mark entity lookups with
+//! `#[graphql(entity)]`, and the schema answers the `_service { sdl }` and
+//! `_entities(representations: …)` queries the Apollo Router sends. This
+//! module adds the Tako plumbing around it:
+//!
+//! - [`subgraph_sdl`] / [`SubgraphSdl`] export the federation SDL (with the
+//!   `@link` schema extension) and serve it over plain `GET`, for
+//!   `rover subgraph publish` or a schema registry that polls a URL;
+//! - [`RouterHeaders`] extracts the `apollographql-client-*` and
+//!   `apollo-federation-include-trace` headers the router forwards and
+//!   attaches them to the request as `Data`, so resolvers and extensions
+//!   can read them.
+//!
+//! ```rust,ignore
+//! use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+//! use tako::graphql::GraphQLRequest;
+//! use tako::graphql::GraphQLResponse;
+//! use tako::graphql::federation::{RouterHeaders, SubgraphSdl};
+//!
+//! #[derive(SimpleObject)]
+//! struct User { id: u64, name: String }
+//!
+//! struct Query;
+//!
+//! #[Object]
+//! impl Query {
+//!     #[graphql(entity)]
+//!     async fn find_user_by_id(&self, id: u64) -> User {
+//!         User { id, name: format!("user {id}") }
+//!     }
+//! }
+//!
+//! let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+//!     .enable_federation()
+//!     .finish();
+//!
+//! let sdl = SubgraphSdl::new(&schema);
+//! router.route(Method::GET, "/graphql/sdl", move || {
+//!     let sdl = sdl.clone();
+//!     async move { sdl }
+//! });
+//! router.route(Method::POST, "/graphql", {
+//!     let schema = schema.clone();
+//!     move |headers: RouterHeaders, GraphQLRequest(req): GraphQLRequest| {
+//!         let schema = schema.clone();
+//!         async move { GraphQLResponse(schema.execute(headers.attach(req)).await) }
+//!     }
+//! });
+//! ```
+
+use async_graphql::ObjectType;
+use async_graphql::Schema;
+use async_graphql::SubscriptionType;
+use http::HeaderMap;
+use http::HeaderValue;
+use http::header;
+
+use crate::body::TakoBody;
+use crate::extractors::FromRequest;
+use crate::extractors::FromRequestParts;
+use crate::responder::Responder;
+use crate::types::Request;
+use crate::types::Response;
+
+/// Header carrying the client name the router was called with.
+pub const CLIENT_NAME_HEADER: &str = "apollographql-client-name";
+/// Header carrying the client version the router was called with.
+pub const CLIENT_VERSION_HEADER: &str = "apollographql-client-version";
+/// Header the router sets to `ftv1` when it wants a federated trace.
+pub const INCLUDE_TRACE_HEADER: &str = "apollo-federation-include-trace";
+
+/// Exports the subgraph SDL in the form the Apollo Router composes: with the
+/// federation directives and the `extend schema @link(…)` header.
+pub fn subgraph_sdl<Query, Mutation, Subscription>(
+  schema: &Schema<Query, Mutation, Subscription>,
+) -> String
+where
+  Query: ObjectType + 'static,
+  Mutation: ObjectType + 'static,
+  Subscription: SubscriptionType + 'static,
+{
+  schema.sdl_with_options(
+    async_graphql::SDLExportOptions::new()
+      .federation()
+      .compose_directive(),
+  )
+}
+
+/// Responder serving the subgraph SDL as `text/plain`.
+///
+/// The SDL is exported once at construction; cloning is cheap.
+#[derive(Clone, Debug)]
+pub struct SubgraphSdl(pub std::sync::Arc<str>);
+
+impl SubgraphSdl {
+  /// Exports the SDL of `schema` with [`subgraph_sdl`].
+  pub fn new<Query, Mutation, Subscription>(schema: &Schema<Query, Mutation, Subscription>) -> Self
+  where
+    Query: ObjectType + 'static,
+    Mutation: ObjectType + 'static,
+    Subscription: SubscriptionType + 'static,
+  {
+    Self(subgraph_sdl(schema).into())
+  }
+}
+
+impl Responder for SubgraphSdl {
+  fn into_response(self) -> Response {
+    let mut res = Response::new(TakoBody::from(self.0.to_string()));
+    res.headers_mut().insert(
+      header::CONTENT_TYPE,
+      HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    res
+  }
+}
+
+/// Request metadata forwarded by the Apollo Router.
+///
+/// Every field is optional, so extraction never fails; requests that did
+/// not come through a router simply yield the default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouterHeaders {
+  /// `apollographql-client-name`.
+  pub client_name: Option<String>,
+  /// `apollographql-client-version`.
+  pub client_version: Option<String>,
+  /// The router asked for an `ftv1` federated trace. Tako does not record
+  /// traces itself; a tracing extension can read this flag from `Data`.
+  pub include_trace: bool,
+}
+
+impl RouterHeaders {
+  /// Reads the router headers from a header map.
+  pub fn from_headers(headers: &HeaderMap) -> Self {
+    let get = |name: &str| {
+      headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+    };
+    Self {
+      client_name: get(CLIENT_NAME_HEADER),
+      client_version: get(CLIENT_VERSION_HEADER),
+      include_trace: headers
+        .get(INCLUDE_TRACE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("ftv1")),
+    }
+  }
+
+  /// Attaches these headers to `req` as `Data`, readable from resolvers
+  /// with `ctx.data::<RouterHeaders>()`.
+  pub fn attach(self, req: async_graphql::Request) -> async_graphql::Request {
+    req.data(self)
+  }
+}
+
+impl<'a> FromRequestParts<'a> for RouterHeaders {
+  type Error = core::convert::Infallible;
+
+  fn from_request_parts(
+    parts: &'a mut http::request::Parts,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    futures_util::future::ready(Ok(Self::from_headers(&parts.headers)))
+  }
+}
+
+impl<'a> FromRequest<'a> for RouterHeaders {
+  type Error = core::convert::Infallible;
+
+  fn from_request(
+    req: &'a mut Request,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    futures_util::future::ready(Ok(Self::from_headers(req.headers())))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use async_graphql::Context;
+  use async_graphql::EmptyMutation;
+  use async_graphql::EmptySubscription;
+  use async_graphql::Object;
+  use async_graphql::SimpleObject;
+
+  use super::*;
+
+  #[derive(SimpleObject)]
+  struct User {
+    id: u64,
+  }
+
+  struct Query;
+
+  #[Object]
+  impl Query {
+    #[graphql(entity)]
+    async fn find_user_by_id(&self, id: u64) -> User {
+      User { id }
+    }
+
+    async fn client(&self, ctx: &Context<'_>) -> Option<String> {
+      ctx.data::<RouterHeaders>().ok()?.client_name.clone()
+    }
+  }
+
+  #[tokio::test]
+  async fn serves_federation_sdl_and_entities() {
+    let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+      .enable_federation()
+      .finish();
+
+    let sdl = subgraph_sdl(&schema);
+    assert!(sdl.contains("specs.apollo.dev/federation/v2"));
+    assert!(sdl.contains("@key(fields: \"id\")"));
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CLIENT_NAME_HEADER, HeaderValue::from_static("web"));
+    headers.insert(INCLUDE_TRACE_HEADER, HeaderValue::from_static("ftv1"));
+    let router = RouterHeaders::from_headers(&headers);
+    assert!(router.include_trace);
+
+    let req = async_graphql::Request::new(
+      r#"{ client _entities(representations: [{__typename: "User", id: 7}]) { ... on User { id } } }"#,
+    );
+    let res = schema.execute(router.attach(req)).await;
+    assert!(res.errors.is_empty(), "{:?}", res.errors);
+    let data = res.data.into_json().unwrap();
+    assert_eq!(data["client"], "web");
+    assert_eq!(data["_entities"][0]["id"], 7);
+  }
+}
@@ -32,11 +32,15 @@
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 
 use futures_util::future::BoxFuture;
 
 use crate::extractors::FromRequest;
 use crate::responder::Responder;
+use crate::route::blocking::BLOCKING_COMPLETED;
+use crate::route::blocking::BLOCKING_QUEUED;
+use crate::route::blocking::RunningGuard;
 use crate::types::Request;
 use crate::types::Response;
 
@@ -76,6 +80,14 @@ pub trait Handler<T>: Send + Sync + 'static {
   /// Returns an unboxed future — `BoxHandler` is the single boxing point
   /// for type erasure, eliminating per-handler heap allocation.
   fn call(self, req: Request) -> impl Future<Output = Response> + Send + 'static;
+
+  /// Collects the `OpenAPI` contributions of the handler's extractors; see
+  /// [`FromRequest::describe_openapi`].
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  #[cfg_attr(docsrs, doc(cfg(any(feature = "utoipa", feature = "vespera"))))]
+  fn describe_openapi(operation: &mut crate::openapi::RouteOpenApi) {
+    let _ = operation;
+  }
 }
 
 /// Type-erased handler wrapper for dynamic storage and composition.
@@ -99,7 +111,10 @@ impl BoxHandler {
     H: Handler<T> + Clone,
   {
     let inner = Arc::new(move |req: Request| -> BoxFuture<'static, Response> {
-      Box::pin(h.clone().call(req))
+      // Remembered before extractors consume the request, for `CachedJson`.
+      let if_none_match = crate::conditional::if_none_match(&req);
+      let fut = h.clone().call(req);
+      Box::pin(async move { crate::conditional::finish(if_none_match, fut.await) })
     });
 
     Self { inner }
@@ -109,6 +124,41 @@ impl BoxHandler {
   pub(crate) fn call(&self, req: Request) -> BoxFuture<'_, Response> {
     (self.inner)(req)
   }
+
+  /// Wraps the handler so each call runs on tokio's blocking pool, driven by
+  /// `Handle::block_on`. Without a tokio runtime (e.g. under `compio`) the
+  /// handler runs inline.
+  pub(crate) fn on_blocking_pool(self) -> Self {
+    let inner = Arc::new(move |req: Request| -> BoxFuture<'static, Response> {
+      let handler = self.clone();
+      Box::pin(async move {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+          return handler.call(req).await;
+        };
+        BLOCKING_QUEUED.fetch_add(1, Ordering::Relaxed);
+        let task = tokio::task::spawn_blocking(move || {
+          BLOCKING_QUEUED.fetch_sub(1, Ordering::Relaxed);
+          let running = RunningGuard::enter();
+          let resp = handle.block_on(handler.call(req));
+          drop(running);
+          BLOCKING_COMPLETED.fetch_add(1, Ordering::Relaxed);
+          resp
+        });
+        match task.await {
+          Ok(resp) => resp,
+          // Keep the panic semantics of an inline handler.
+          Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+          Err(_) => {
+            let mut resp = Response::new(crate::body::TakoBody::empty());
+            *resp.status_mut() = http::StatusCode::SERVICE_UNAVAILABLE;
+            resp
+          }
+        }
+      })
+    });
+
+    Self { inner }
+  }
 }
 
 // Zero-argument handlers: `async fn handler() -> impl Responder`
@@ -142,6 +192,9 @@ trait Extract: Sized + Send {
   fn extract<'a>(
     req: &'a mut Request,
   ) -> Pin<Box<dyn Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a>>;
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut crate::openapi::RouteOpenApi);
 }
 
 impl<T, E> Extract for T
@@ -157,6 +210,11 @@ where
   ) -> Pin<Box<dyn Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a>> {
     Box::pin(<T as FromRequest<'a>>::from_request(req))
   }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut crate::openapi::RouteOpenApi) {
+    <T as FromRequest<'static>>::describe_openapi(operation);
+  }
 }
 
 macro_rules! impl_handler {
@@ -171,16 +229,22 @@ macro_rules! impl_handler {
             fn call(self, mut req: Request) -> impl Future<Output = Response> + Send + 'static {
                 async move {
                     $(
-                        let $T = match <$T as Extract>::extract(&mut req).await {
+                        let extracted = <$T as Extract>::extract(&mut req).await;
+                        let $T = match extracted.map_err(Responder::into_response) {
                             Ok(value) => value,
-                            Err(err) => {
-                                return err.into_response();
+                            Err(rejection) => {
+                                return crate::error::Error::tag_rejection(rejection).await;
                             }
                         };
                     )*
                     (self)($($T),*).await.into_response()
                 }
             }
+
+            #[cfg(any(feature = "utoipa", feature = "vespera"))]
+            fn describe_openapi(operation: &mut crate::openapi::RouteOpenApi) {
+                $( <$T as Extract>::describe_openapi(operation); )*
+            }
         }
     };
 }
@@ -26,9 +26,21 @@ pub mod body;
 #[cfg_attr(docsrs, doc(cfg(feature = "client")))]
 pub mod client;
 
-/// Configuration loading from environment variables.
+/// Typed TypeScript and Rust API clients generated from the route table.
+pub mod codegen;
+
+/// Conditional GET: the `CachedJson` responder with automatic `ETag` / `304`.
+pub mod conditional;
+
+/// Configuration loading from environment variables and JSON files.
 pub mod config;
 
+/// Framework-level `Error` type rendered as a JSON error envelope.
+pub mod error;
+
+/// Static site generation: render `GET` routes to files.
+pub mod export;
+
 /// Request data extraction trait + the two extractors (`json`, `params`)
 /// whose internal types are referenced by the router and route.
 pub mod extractors;
@@ -53,6 +65,9 @@ pub mod problem;
 /// Unified per-connection metadata extension shared by every transport.
 pub mod conn_info;
 
+/// Client-disconnect notification inserted by every tokio transport.
+pub mod disconnect;
+
 /// Shared TLS certificate / key PEM loading helpers.
 #[cfg(any(feature = "tls", feature = "http3", feature = "client"))]
 #[cfg_attr(
@@ -64,6 +79,12 @@ pub mod tls;
 /// Redirection utilities for handling HTTP redirects.
 pub mod redirect;
 
+/// `robots.txt` generation.
+pub mod robots;
+
+/// Time source for framework deadlines; follows tokio's clock when paused.
+pub mod clock;
+
 /// Route definition and matching logic.
 pub mod route;
 
@@ -73,6 +94,14 @@ pub mod router;
 /// In-memory background job queue with retry, delayed jobs, and dead letter support.
 pub mod queue;
 
+/// Virtual-clock harness for deterministic tests of time-based behaviour.
+#[cfg(feature = "simulation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "simulation")))]
+pub mod sim;
+
+/// `sitemap.xml` generation from the route table and dynamic URL sources.
+pub mod sitemap;
+
 /// Application state management and dependency injection.
 pub mod state;
 
@@ -83,6 +112,11 @@ pub mod router_state;
 /// In-process signal arbiter for custom events.
 pub mod signals;
 
+/// Filesystem watching that reports changes as signals.
+#[cfg(feature = "watch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "watch")))]
+pub mod watch;
+
 /// Distributed tracing integration for observability.
 #[cfg(feature = "tako-tracing")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tako-tracing")))]
@@ -91,6 +125,9 @@ pub mod tracing;
 /// Core type definitions used throughout the framework.
 pub mod types;
 
+/// `/.well-known/*` documents: `security.txt`, `change-password`, and more.
+pub mod well_known;
+
 /// `GraphQL` support (request extractors, responses, and subscriptions).
 #[cfg(feature = "async-graphql")]
 #[cfg_attr(docsrs, doc(cfg(feature = "async-graphql")))]
@@ -106,12 +143,18 @@ pub mod graphiql;
 #[cfg_attr(docsrs, doc(cfg(any(feature = "utoipa", feature = "vespera"))))]
 pub mod openapi;
 
+/// JSON Schema generation for request and response types.
+#[cfg(feature = "schemars")]
+#[cfg_attr(docsrs, doc(cfg(feature = "schemars")))]
+pub mod schemas;
+
 /// gRPC support for unary RPCs with protobuf serialization.
 #[cfg(feature = "grpc")]
 #[cfg_attr(docsrs, doc(cfg(feature = "grpc")))]
 pub mod grpc;
 
 pub use bytes::Bytes;
+pub use error::Error;
 pub use http::Method;
 pub use http::StatusCode;
 pub use http::header;
@@ -26,6 +26,32 @@ use crate::types::BoxMiddleware;
 use crate::types::Request;
 use crate::types::Response;
 
+/// Well-known priorities for [`Router::middleware_with_priority`] and
+/// [`TakoPlugin::priority`](crate::plugins::TakoPlugin::priority).
+///
+/// A router's global chain is kept sorted by priority: lower values run
+/// earlier on the way in (further out) and later on the way out. Middleware
+/// with equal priority runs in registration order, so middleware registered
+/// without a priority keeps its relative order. The built-in CORS and
+/// compression plugins do set one (`CORS`, `COMPRESSION`) and therefore
+/// run outside every `DEFAULT` middleware, including middleware
+/// registered before them.
+///
+/// [`Router::middleware_with_priority`]: crate::router::Router::middleware_with_priority
+pub mod priority {
+  /// Runs before every other middleware.
+  pub const FIRST: i32 = i32::MIN;
+  /// CORS: answers preflights before authentication can reject them.
+  pub const CORS: i32 = -300;
+  /// Compression: wraps validators such as `ETag` so they hash the
+  /// uncompressed body.
+  pub const COMPRESSION: i32 = -200;
+  /// Middleware registered without an explicit priority.
+  pub const DEFAULT: i32 = 0;
+  /// Runs after every other middleware, right before the route chain.
+  pub const LAST: i32 = i32::MAX;
+}
+
 /// Trait for converting types into middleware functions.
 ///
 /// This trait allows various types to be converted into middleware that can be used
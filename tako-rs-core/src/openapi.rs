@@ -73,6 +73,13 @@
 
 use std::collections::BTreeMap;
 
+use serde::de::DeserializeOwned;
+
+mod fields;
+
+pub use fields::FieldInfo;
+pub use fields::struct_fields;
+
 /// `OpenAPI` metadata that can be attached to a route.
 ///
 /// This struct stores operation-level `OpenAPI` information that can be
@@ -97,6 +104,117 @@ pub struct RouteOpenApi {
   pub request_body: Option<OpenApiRequestBody>,
   /// Security requirements.
   pub security: Vec<String>,
+  /// Example calls; their JSON bodies become request and response examples.
+  pub examples: Vec<crate::route::RouteExample>,
+}
+
+impl RouteOpenApi {
+  /// Adds `param`, replacing an earlier one with the same name and location.
+  pub fn add_parameter(&mut self, param: OpenApiParameter) {
+    match self
+      .parameters
+      .iter_mut()
+      .find(|p| p.name == param.name && p.location == param.location)
+    {
+      Some(existing) => *existing = param,
+      None => self.parameters.push(param),
+    }
+  }
+
+  /// Adds one parameter per field of the struct `T` deserializes from.
+  ///
+  /// Path parameters are always required; elsewhere `Option` fields are
+  /// optional.
+  pub fn add_struct_parameters<T: DeserializeOwned>(&mut self, location: ParameterLocation) {
+    for field in struct_fields::<T>() {
+      let required = field.required || location == ParameterLocation::Path;
+      self.add_parameter(OpenApiParameter {
+        name: field.name.to_string(),
+        location: location.clone(),
+        description: None,
+        required,
+        kind: Some(field.kind.to_string()),
+      });
+    }
+  }
+
+  /// Sets a required request body of `content_type` whose schema lists the
+  /// fields of the struct `T` deserializes from.
+  pub fn set_struct_request_body<T: DeserializeOwned>(&mut self, content_type: &str) {
+    self.request_body = Some(OpenApiRequestBody {
+      description: None,
+      required: true,
+      content_type: content_type.to_string(),
+      schema_properties: struct_fields::<T>()
+        .into_iter()
+        .map(|field| RequestBodyProperty {
+          name: field.name.to_string(),
+          property_type: field.kind.to_string(),
+          description: None,
+          required: field.required,
+        })
+        .collect(),
+    });
+  }
+}
+
+#[cfg(feature = "schemars")]
+#[cfg_attr(docsrs, doc(cfg(feature = "schemars")))]
+impl RouteOpenApi {
+  /// Sets a required request body of `content_type` from the JSON Schema of
+  /// `T`: its top-level properties with their types and doc-comment
+  /// descriptions.
+  pub fn set_schema_request_body<T: schemars::JsonSchema>(&mut self, content_type: &str) {
+    let schema = crate::schemas::schema_for::<T>();
+    let required: Vec<&str> = schema
+      .get("required")
+      .and_then(serde_json::Value::as_array)
+      .map(|names| names.iter().filter_map(serde_json::Value::as_str).collect())
+      .unwrap_or_default();
+    let schema_properties = schema
+      .get("properties")
+      .and_then(serde_json::Value::as_object)
+      .map(|properties| {
+        properties
+          .iter()
+          .map(|(name, property)| RequestBodyProperty {
+            name: name.clone(),
+            property_type: schema_type(property).to_string(),
+            description: property
+              .get("description")
+              .and_then(serde_json::Value::as_str)
+              .map(str::to_string),
+            required: required.contains(&name.as_str()),
+          })
+          .collect()
+      })
+      .unwrap_or_default();
+    self.request_body = Some(OpenApiRequestBody {
+      description: schema
+        .get("description")
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string),
+      required: true,
+      content_type: content_type.to_string(),
+      schema_properties,
+    });
+  }
+}
+
+/// `OpenAPI` type of a JSON Schema property; `Option<T>` fields are typed
+/// `[T, "null"]`, and `$ref`s point at objects or enums.
+#[cfg(feature = "schemars")]
+fn schema_type(property: &serde_json::Value) -> &str {
+  match property.get("type") {
+    Some(serde_json::Value::String(kind)) => kind,
+    Some(serde_json::Value::Array(kinds)) => kinds
+      .iter()
+      .filter_map(serde_json::Value::as_str)
+      .find(|kind| *kind != "null")
+      .unwrap_or("string"),
+    _ if property.get("enum").is_some() => "string",
+    _ => "object",
+  }
 }
 
 /// `OpenAPI` parameter definition.
@@ -110,10 +228,13 @@ pub struct OpenApiParameter {
   pub description: Option<String>,
   /// Whether the parameter is required.
   pub required: bool,
+  /// `OpenAPI` type of the value (e.g. "integer"); `None` means unknown and
+  /// is treated as a string.
+  pub kind: Option<String>,
 }
 
 /// Location of an `OpenAPI` parameter.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub enum ParameterLocation {
   #[default]
   Query,
@@ -144,6 +265,8 @@ pub struct RequestBodyProperty {
   pub property_type: String,
   /// Property description.
   pub description: Option<String>,
+  /// Whether the property must be present.
+  pub required: bool,
 }
 
 #[cfg(feature = "utoipa")]
@@ -0,0 +1,400 @@
+//! Field discovery for `Deserialize` types.
+//!
+//! Extractors such as `Json<T>` or `Query<T>` only know their payload type
+//! through its `Deserialize` impl. [`struct_fields`] drives that impl with a
+//! probing deserializer: serde hands over the (renamed) field list of the
+//! struct, and each field's value reports which primitive it asked for. No
+//! extra derive is needed, so every `#[derive(Deserialize)]` struct can feed
+//! the route's `OpenAPI` metadata.
+//!
+//! The probe is best-effort. Types that deserialize through `deserialize_any`
+//! (untagged enums, `#[serde(flatten)]`) or need non-empty sequences yield
+//! the fields discovered up to that point.
+
+use serde::de;
+use serde::de::DeserializeOwned;
+use serde::de::IntoDeserializer;
+use serde::de::value::Error;
+use serde::forward_to_deserialize_any;
+
+/// A field discovered on a `Deserialize` struct.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldInfo {
+  /// Field name as it appears on the wire (after `rename` / `rename_all`).
+  pub name: &'static str,
+  /// `OpenAPI` type: `string`, `integer`, `number`, `boolean`, `array` or
+  /// `object`.
+  pub kind: &'static str,
+  /// `false` for `Option<_>` fields.
+  pub required: bool,
+}
+
+/// Lists the fields of the struct `T` deserializes from.
+///
+/// Returns an empty list for non-struct types.
+///
+/// # Examples
+///
+/// ```rust
+/// use tako::openapi::struct_fields;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Search {
+///     q: String,
+///     limit: Option<u32>,
+/// }
+///
+/// let fields = struct_fields::<Search>();
+/// assert_eq!(fields[0].name, "q");
+/// assert_eq!(fields[1].kind, "integer");
+/// assert!(!fields[1].required);
+/// ```
+pub fn struct_fields<T: DeserializeOwned>() -> Vec<FieldInfo> {
+  let mut fields = Vec::new();
+  let _ = T::deserialize(StructProbe {
+    out: Some(&mut fields),
+  });
+  fields
+}
+
+/// Top-level probe: only structs are described.
+struct StructProbe<'r> {
+  out: Option<&'r mut Vec<FieldInfo>>,
+}
+
+impl<'de> de::Deserializer<'de> for StructProbe<'_> {
+  type Error = Error;
+
+  fn deserialize_any<V: de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+    Err(de::Error::custom("not a struct"))
+  }
+
+  fn deserialize_struct<V: de::Visitor<'de>>(
+    self,
+    _name: &'static str,
+    fields: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    visitor.visit_map(FieldsAccess {
+      fields,
+      next: 0,
+      out: self.out,
+    })
+  }
+
+  fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+    self,
+    _name: &'static str,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    visitor.visit_newtype_struct(self)
+  }
+
+  forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+    bytes byte_buf option unit unit_struct seq tuple tuple_struct map enum
+    identifier ignored_any
+  }
+}
+
+/// Presents every declared field once, with a probed placeholder value.
+struct FieldsAccess<'r> {
+  fields: &'static [&'static str],
+  next: usize,
+  out: Option<&'r mut Vec<FieldInfo>>,
+}
+
+impl<'de> de::MapAccess<'de> for FieldsAccess<'_> {
+  type Error = Error;
+
+  fn next_key_seed<K: de::DeserializeSeed<'de>>(
+    &mut self,
+    seed: K,
+  ) -> Result<Option<K::Value>, Error> {
+    let Some(name) = self.fields.get(self.next) else {
+      return Ok(None);
+    };
+    seed.deserialize((*name).into_deserializer()).map(Some)
+  }
+
+  fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+    let name = self.fields[self.next];
+    self.next += 1;
+    let mut probe = ValueProbe::default();
+    let value = seed.deserialize(&mut probe);
+    if let Some(out) = self.out.as_mut() {
+      out.push(FieldInfo {
+        name,
+        kind: probe.kind.unwrap_or("string"),
+        required: !probe.optional,
+      });
+    }
+    value
+  }
+}
+
+/// Records the first type hint a field value asks for and answers with a
+/// placeholder of that type.
+#[derive(Default)]
+struct ValueProbe {
+  kind: Option<&'static str>,
+  optional: bool,
+}
+
+impl ValueProbe {
+  fn record(&mut self, kind: &'static str) {
+    self.kind.get_or_insert(kind);
+  }
+}
+
+impl<'de> de::Deserializer<'de> for &mut ValueProbe {
+  type Error = Error;
+
+  fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("object");
+    visitor.visit_unit()
+  }
+
+  fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("boolean");
+    visitor.visit_bool(false)
+  }
+
+  fn deserialize_i8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("integer");
+    visitor.visit_i8(0)
+  }
+
+  fn deserialize_i16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("integer");
+    visitor.visit_i16(0)
+  }
+
+  fn deserialize_i32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("integer");
+    visitor.visit_i32(0)
+  }
+
+  fn deserialize_i64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("integer");
+    visitor.visit_i64(0)
+  }
+
+  fn deserialize_i128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("integer");
+    visitor.visit_i128(0)
+  }
+
+  fn deserialize_u8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("integer");
+    visitor.visit_u8(0)
+  }
+
+  fn deserialize_u16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("integer");
+    visitor.visit_u16(0)
+  }
+
+  fn deserialize_u32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("integer");
+    visitor.visit_u32(0)
+  }
+
+  fn deserialize_u64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("integer");
+    visitor.visit_u64(0)
+  }
+
+  fn deserialize_u128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("integer");
+    visitor.visit_u128(0)
+  }
+
+  fn deserialize_f32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("number");
+    visitor.visit_f32(0.0)
+  }
+
+  fn deserialize_f64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("number");
+    visitor.visit_f64(0.0)
+  }
+
+  fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("string");
+    visitor.visit_char(' ')
+  }
+
+  fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("string");
+    visitor.visit_str("")
+  }
+
+  fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("string");
+    visitor.visit_string(String::new())
+  }
+
+  fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("string");
+    visitor.visit_bytes(&[])
+  }
+
+  fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("string");
+    visitor.visit_byte_buf(Vec::new())
+  }
+
+  fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.optional = true;
+    visitor.visit_some(self)
+  }
+
+  fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("object");
+    visitor.visit_unit()
+  }
+
+  fn deserialize_unit_struct<V: de::Visitor<'de>>(
+    self,
+    _name: &'static str,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    self.deserialize_unit(visitor)
+  }
+
+  fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+    self,
+    _name: &'static str,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    visitor.visit_newtype_struct(self)
+  }
+
+  fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("array");
+    visitor.visit_seq(de::value::SeqDeserializer::<_, Error>::new(
+      std::iter::empty::<()>(),
+    ))
+  }
+
+  fn deserialize_tuple<V: de::Visitor<'de>>(
+    self,
+    _len: usize,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    self.deserialize_seq(visitor)
+  }
+
+  fn deserialize_tuple_struct<V: de::Visitor<'de>>(
+    self,
+    _name: &'static str,
+    _len: usize,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    self.deserialize_seq(visitor)
+  }
+
+  fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.record("object");
+    visitor.visit_map(de::value::MapDeserializer::<_, Error>::new(
+      std::iter::empty::<((), ())>(),
+    ))
+  }
+
+  fn deserialize_struct<V: de::Visitor<'de>>(
+    self,
+    _name: &'static str,
+    fields: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    self.record("object");
+    visitor.visit_map(FieldsAccess {
+      fields,
+      next: 0,
+      out: None,
+    })
+  }
+
+  fn deserialize_enum<V: de::Visitor<'de>>(
+    self,
+    _name: &'static str,
+    variants: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    self.record("string");
+    let first = variants.first().copied().unwrap_or_default();
+    visitor.visit_enum(first.into_deserializer())
+  }
+
+  fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    self.deserialize_str(visitor)
+  }
+
+  fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    visitor.visit_unit()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use serde::Deserialize;
+
+  use super::FieldInfo;
+  use super::struct_fields;
+
+  #[allow(dead_code)]
+  #[derive(Deserialize)]
+  enum Role {
+    Admin,
+    User,
+  }
+
+  #[allow(dead_code)]
+  #[derive(Deserialize)]
+  struct Address {
+    city: String,
+  }
+
+  #[allow(dead_code)]
+  #[derive(Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct CreateUser {
+    user_name: String,
+    age: u8,
+    score: Option<f64>,
+    admin: bool,
+    tags: Vec<String>,
+    role: Role,
+    address: Address,
+    extra: HashMap<String, String>,
+  }
+
+  fn field(name: &'static str, kind: &'static str, required: bool) -> FieldInfo {
+    FieldInfo {
+      name,
+      kind,
+      required,
+    }
+  }
+
+  #[test]
+  fn describes_struct_fields_with_types() {
+    assert_eq!(
+      struct_fields::<CreateUser>(),
+      vec![
+        field("userName", "string", true),
+        field("age", "integer", true),
+        field("score", "number", false),
+        field("admin", "boolean", true),
+        field("tags", "array", true),
+        field("role", "string", true),
+        field("address", "object", true),
+        field("extra", "object", true),
+      ]
+    );
+    assert!(struct_fields::<String>().is_empty());
+  }
+}
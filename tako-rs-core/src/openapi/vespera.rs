@@ -35,6 +35,7 @@ pub use vespera_core::schema;
 
 use crate::body::TakoBody;
 use crate::responder::Responder;
+use crate::route::ExampleBody;
 use crate::types::Response;
 
 /// Serves the `OpenAPI` JSON specification generated by `Vespera`.
@@ -126,7 +127,7 @@ pub fn route_openapi_to_operation(route: &super::RouteOpenApi) -> Operation {
     })
     .collect();
 
-  let responses: BTreeMap<String, VesperaResponse> = route
+  let mut responses: BTreeMap<String, VesperaResponse> = route
     .responses
     .iter()
     .map(|(status, desc)| {
@@ -140,8 +141,30 @@ pub fn route_openapi_to_operation(route: &super::RouteOpenApi) -> Operation {
       )
     })
     .collect();
+  for example in &route.examples {
+    let Some(ExampleBody::Json(value)) = example.response_body() else {
+      continue;
+    };
+    let status = example.status();
+    let response = responses
+      .entry(status.as_u16().to_string())
+      .or_insert_with(|| VesperaResponse {
+        description: status.canonical_reason().unwrap_or_default().to_string(),
+        headers: None,
+        content: None,
+      });
+    add_example(
+      response
+        .content
+        .get_or_insert_with(BTreeMap::new)
+        .entry("application/json".to_string())
+        .or_insert_with(empty_media_type),
+      example.name(),
+      value,
+    );
+  }
 
-  let request_body = route.request_body.as_ref().map(|rb| {
+  let mut request_body = route.request_body.as_ref().map(|rb| {
     let mut content = BTreeMap::new();
     let schema = if rb.schema_properties.is_empty() {
       None
@@ -184,6 +207,30 @@ pub fn route_openapi_to_operation(route: &super::RouteOpenApi) -> Operation {
       content,
     }
   });
+  for example in &route.examples {
+    let Some(ExampleBody::Json(value)) = example.request_body() else {
+      continue;
+    };
+    let body = request_body.get_or_insert_with(|| RequestBody {
+      description: None,
+      required: Some(true),
+      content: BTreeMap::new(),
+    });
+    let content_type = body
+      .content
+      .keys()
+      .next()
+      .cloned()
+      .unwrap_or_else(|| "application/json".to_string());
+    add_example(
+      body
+        .content
+        .entry(content_type)
+        .or_insert_with(empty_media_type),
+      example.name(),
+      value,
+    );
+  }
 
   let security = if route.security.is_empty() {
     None
@@ -221,6 +268,25 @@ pub fn route_openapi_to_operation(route: &super::RouteOpenApi) -> Operation {
   }
 }
 
+fn empty_media_type() -> MediaType {
+  MediaType {
+    schema: None,
+    example: None,
+    examples: None,
+  }
+}
+
+fn add_example(media: &mut MediaType, name: &str, value: &serde_json::Value) {
+  media.examples.get_or_insert_with(Default::default).insert(
+    name.to_string(),
+    Example {
+      summary: None,
+      description: None,
+      value: Some(value.clone()),
+    },
+  );
+}
+
 /// Generates a `Vespera` `OpenAPI` spec from Tako router's collected routes.
 ///
 /// # Examples
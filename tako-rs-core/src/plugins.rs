@@ -46,9 +46,13 @@
 //! route.plugin(LoggingPlugin { level: "debug".to_string() });
 //! ```
 
+use std::sync::Arc;
+
 use anyhow::Result;
 
 use crate::router::Router;
+use crate::types::Request;
+use crate::types::Response;
 
 /// Trait for implementing Tako framework plugins.
 ///
@@ -105,4 +109,121 @@ pub trait TakoPlugin: Send + Sync + 'static {
 
   /// Configures and initializes the plugin with the given router.
   fn setup(&self, router: &Router) -> Result<()>;
+
+  /// Where this plugin's middleware sits in the router's global chain
+  /// (default [`priority::DEFAULT`](crate::middleware::priority::DEFAULT)).
+  ///
+  /// Plugins are set up in ascending priority, ties in registration order,
+  /// and every middleware registered from [`setup`](Self::setup) takes this
+  /// priority. Lower values run earlier on the way in; see
+  /// [`middleware::priority`](crate::middleware::priority) for the values
+  /// used by the built-in plugins.
+  fn priority(&self) -> i32 {
+    crate::middleware::priority::DEFAULT
+  }
+
+  /// Called once a transport starts serving, after [`setup`](Self::setup).
+  ///
+  /// The [`RouterHandle`] outlives the call, so plugins can spawn background
+  /// work (cache warmers, schedulers) that dispatches requests through the
+  /// running router. Only router-level plugins receive it. The default does
+  /// nothing.
+  fn on_start(&self, router: RouterHandle) {
+    let _ = router;
+  }
+}
+
+/// Which registration a [`PluginStatus`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginScope {
+  /// Registered with [`Router::plugin`].
+  Router,
+  /// Registered on a child router mounted with [`Router::nest`].
+  Nested,
+}
+
+/// Outcome of one plugin's [`TakoPlugin::setup`], as reported by
+/// [`Router::plugin_status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginStatus {
+  /// The plugin's [`TakoPlugin::name`].
+  pub name: &'static str,
+  /// Where the plugin was registered.
+  pub scope: PluginScope,
+  /// The setup error, or `None` when the plugin is active.
+  pub error: Option<String>,
+}
+
+impl PluginStatus {
+  /// `true` when setup succeeded.
+  pub fn is_active(&self) -> bool {
+    self.error.is_none()
+  }
+}
+
+/// Returned by transports when a router-level plugin failed to set up and
+/// [`Router::fail_on_plugin_error`] is on.
+#[derive(Debug, Clone)]
+pub struct PluginSetupError {
+  /// Every plugin whose setup failed.
+  pub failed: Vec<PluginStatus>,
+}
+
+impl std::fmt::Display for PluginSetupError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "plugin setup failed:")?;
+    for status in &self.failed {
+      write!(
+        f,
+        " {} ({})",
+        status.name,
+        status.error.as_deref().unwrap_or_default()
+      )?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for PluginSetupError {}
+
+// Dispatch is `!Send` on the compio runtime (its timers are thread-local),
+// matching what `compio::runtime::spawn` accepts there.
+#[cfg(not(feature = "compio"))]
+type DispatchFuture = futures_util::future::BoxFuture<'static, Response>;
+#[cfg(feature = "compio")]
+type DispatchFuture = futures_util::future::LocalBoxFuture<'static, Response>;
+
+type DispatchFn = dyn Fn(Request) -> DispatchFuture + Send + Sync;
+
+/// Cloneable handle that dispatches requests through a router being served.
+///
+/// Transports hold their router either leaked (`&'static`) or in an `Arc`;
+/// the handle hides which, so plugins get the same type from every server.
+#[derive(Clone)]
+pub struct RouterHandle(Arc<DispatchFn>);
+
+impl RouterHandle {
+  /// Wraps a router leaked for the lifetime of the process.
+  pub fn from_static(router: &'static Router) -> Self {
+    Self(Arc::new(move |req| Box::pin(router.dispatch(req))))
+  }
+
+  /// Wraps a shared router.
+  pub fn from_arc(router: Arc<Router>) -> Self {
+    Self(Arc::new(move |req| {
+      let router = router.clone();
+      Box::pin(async move { router.dispatch(req).await })
+    }))
+  }
+
+  /// Dispatches `req` exactly as if it had arrived over the wire.
+  pub async fn dispatch(&self, req: Request) -> Response {
+    (self.0)(req).await
+  }
+}
+
+impl std::fmt::Debug for RouterHandle {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("RouterHandle").finish_non_exhaustive()
+  }
 }
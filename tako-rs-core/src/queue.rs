@@ -11,6 +11,8 @@
 //! - **Delayed jobs** — schedule execution after a duration
 //! - **Dead letter queue** — failed jobs stored for inspection
 //! - **Graceful shutdown** — drain in-flight jobs before exit
+//! - **Accepted jobs** — `202 Accepted` plus a pollable status URL via
+//!   [`AcceptedJobs`]
 //!
 //! # Examples
 //!
@@ -34,6 +36,9 @@
 //! # }
 //! ```
 
+/// `202 Accepted` jobs with a status route and progress events.
+mod accepted;
+
 /// Pluggable queue backend abstraction (v2). The bundled `Queue` keeps its
 /// in-process semantics; opt into a remote broker via [`backend::QueueBackend`].
 pub mod backend;
@@ -65,6 +70,11 @@ mod signals;
 /// Background worker loop draining pending jobs.
 mod worker;
 
+pub use accepted::Accepted;
+pub use accepted::AcceptedJobs;
+pub use accepted::JobContext;
+pub use accepted::JobSnapshot;
+pub use accepted::JobStatus;
 pub use builder::QueueBuilder;
 pub use error::QueueError;
 pub use job::DeadJob;
@@ -0,0 +1,495 @@
+//! `202 Accepted` jobs: long-running work answered with a status URL.
+//!
+//! [`AcceptedJobs`] wraps a [`Queue`]. A handler enqueues its work with
+//! [`accept`](AcceptedJobs::accept) and returns the resulting [`Accepted`],
+//! which answers `202 Accepted` with `Location: /jobs/{id}`. The client then
+//! polls that URL, or follows `/jobs/{id}/events` as server-sent events,
+//! until the job has succeeded or failed. Finished jobs are forgotten once
+//! their TTL has passed.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::hash::Hasher;
+use std::hash::RandomState;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use bytes::Bytes;
+use futures_util::stream;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::sync::watch;
+
+use super::Job;
+use super::Queue;
+use super::QueueError;
+use crate::body::TakoBody;
+use crate::clock;
+use crate::extractors::params::PathParams;
+use crate::responder::Responder;
+use crate::router::Router;
+use crate::types::Request;
+use crate::types::Response;
+
+/// Where an accepted job is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+  /// Waiting for a worker, or for its next retry.
+  Queued,
+  /// A worker is running the handler.
+  Running,
+  /// The handler returned a result.
+  Succeeded,
+  /// The handler failed and has no retries left.
+  Failed,
+}
+
+impl JobStatus {
+  /// Whether the job has finished, successfully or not.
+  pub fn is_terminal(self) -> bool {
+    matches!(self, Self::Succeeded | Self::Failed)
+  }
+}
+
+/// The state of an accepted job, as served by the status route.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobSnapshot {
+  /// The job's public id.
+  pub id: String,
+  /// Lifecycle state.
+  pub status: JobStatus,
+  /// Current attempt number (0-based).
+  pub attempt: u32,
+  /// Last progress reported by the handler, in percent.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub progress: Option<u8>,
+  /// Last status message reported by the handler.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub message: Option<String>,
+  /// The handler's result, once succeeded.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub result: Option<Value>,
+  /// The last error, when an attempt failed.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+impl JobSnapshot {
+  fn queued(id: String) -> Self {
+    Self {
+      id,
+      status: JobStatus::Queued,
+      attempt: 0,
+      progress: None,
+      message: None,
+      result: None,
+      error: None,
+    }
+  }
+}
+
+/// A tracked job: its latest snapshot and, once finished, when to drop it.
+struct Slot {
+  tx: watch::Sender<JobSnapshot>,
+  expires_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct Tracker {
+  jobs: Mutex<HashMap<String, Slot>>,
+}
+
+impl Tracker {
+  /// Drops finished jobs whose TTL has passed.
+  fn purge(jobs: &mut HashMap<String, Slot>) {
+    let now = clock::now();
+    jobs.retain(|_, slot| slot.expires_at.is_none_or(|at| at > now));
+  }
+
+  fn update(&self, id: &str, ttl: Duration, f: impl FnOnce(&mut JobSnapshot)) {
+    let mut jobs = self.jobs.lock();
+    let Some(slot) = jobs.get_mut(id) else {
+      return;
+    };
+    slot.tx.send_modify(f);
+    if slot.tx.borrow().status.is_terminal() {
+      slot.expires_at = Some(clock::now() + ttl);
+    }
+  }
+
+  fn subscribe(&self, id: &str) -> Option<watch::Receiver<JobSnapshot>> {
+    let mut jobs = self.jobs.lock();
+    Self::purge(&mut jobs);
+    jobs.get(id).map(|slot| slot.tx.subscribe())
+  }
+}
+
+/// The payload as pushed to the queue: the caller's payload plus the id the
+/// status route knows the job by.
+#[derive(Serialize, serde::Deserialize)]
+struct Envelope {
+  id: String,
+  payload: Value,
+}
+
+/// What an accepted job's handler receives.
+pub struct JobContext {
+  id: String,
+  attempt: u32,
+  payload: Value,
+  tracker: Arc<Tracker>,
+  ttl: Duration,
+}
+
+impl JobContext {
+  /// The job's public id.
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+
+  /// Current attempt number (0-based).
+  pub fn attempt(&self) -> u32 {
+    self.attempt
+  }
+
+  /// Deserialize the payload passed to [`AcceptedJobs::accept`].
+  pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, QueueError> {
+    T::deserialize(&self.payload).map_err(|e| QueueError::HandlerError(e.to_string()))
+  }
+
+  /// Reports progress in percent, capped at 100.
+  pub fn progress(&self, percent: u8) {
+    self.tracker.update(&self.id, self.ttl, |snapshot| {
+      snapshot.progress = Some(percent.min(100));
+    });
+  }
+
+  /// Reports a human-readable status message, e.g. `"rendering page 3"`.
+  pub fn message(&self, message: impl Into<String>) {
+    let message = message.into();
+    self.tracker.update(&self.id, self.ttl, |snapshot| {
+      snapshot.message = Some(message);
+    });
+  }
+}
+
+/// Long-running handler work as `202 Accepted` jobs with a status URL.
+///
+/// Handlers registered here return a `Serialize` result instead of `()`;
+/// it is served by the status route once the job succeeds. Failed attempts
+/// follow the queue's retry policy, and the job only reports `failed` when
+/// none are left.
+///
+/// Job ids are 128 random bits, so URLs cannot be enumerated, but anyone
+/// holding one can read the job; put the routes behind authentication when
+/// results are private. Jobs live in memory and do not survive a restart.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use tako::queue::{AcceptedJobs, Queue};
+///
+/// let queue = Queue::new();
+/// let jobs = AcceptedJobs::new(queue.clone());
+///
+/// jobs.register("report", |job| async move {
+///     let month: String = job.deserialize()?;
+///     job.progress(50);
+///     Ok(build_report(&month).await)
+/// });
+/// queue.start();
+///
+/// let mut router = Router::new();
+/// jobs.routes(&mut router);
+/// router.post("/reports", {
+///     let jobs = jobs.clone();
+///     move |req: Request| {
+///         let jobs = jobs.clone();
+///         async move { jobs.accept("report", &"2026-09").await }
+///     }
+/// });
+/// ```
+#[derive(Clone)]
+pub struct AcceptedJobs {
+  queue: Queue,
+  tracker: Arc<Tracker>,
+  prefix: String,
+  ttl: Duration,
+  poll_interval: Duration,
+}
+
+impl AcceptedJobs {
+  /// Tracks jobs pushed to `queue`, served under `/jobs`.
+  pub fn new(queue: Queue) -> Self {
+    Self {
+      queue,
+      tracker: Arc::default(),
+      prefix: "/jobs".to_string(),
+      ttl: Duration::from_secs(3600),
+      poll_interval: Duration::from_secs(1),
+    }
+  }
+
+  /// Path under which the status routes are mounted (default `/jobs`).
+  #[must_use]
+  pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+    self.prefix = prefix.into().trim_end_matches('/').to_string();
+    self
+  }
+
+  /// How long a finished job stays readable (default one hour).
+  #[must_use]
+  pub fn ttl(mut self, ttl: Duration) -> Self {
+    self.ttl = ttl;
+    self
+  }
+
+  /// The `Retry-After` hint sent while a job is unfinished (default one
+  /// second).
+  #[must_use]
+  pub fn poll_interval(mut self, interval: Duration) -> Self {
+    self.poll_interval = interval;
+    self
+  }
+
+  /// Registers the handler for jobs accepted under `name`.
+  ///
+  /// Jobs for this name must be enqueued with [`accept`](Self::accept);
+  /// a plain [`Queue::push`] lacks the job id and fails.
+  pub fn register<F, Fut, T>(&self, name: impl Into<String>, handler: F)
+  where
+    F: Fn(JobContext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, QueueError>> + Send + 'static,
+    T: Serialize,
+  {
+    let handler = Arc::new(handler);
+    let tracker = Arc::clone(&self.tracker);
+    let ttl = self.ttl;
+    let max_retries = self.queue.inner.retry_policy.max_retries();
+    self.queue.register(name, move |job: Job| {
+      let handler = Arc::clone(&handler);
+      let tracker = Arc::clone(&tracker);
+      async move {
+        let Envelope { id, payload } = job.deserialize()?;
+        let attempt = job.attempt;
+        tracker.update(&id, ttl, |snapshot| {
+          snapshot.status = JobStatus::Running;
+          snapshot.attempt = attempt;
+        });
+        let ctx = JobContext {
+          id: id.clone(),
+          attempt,
+          payload,
+          tracker: Arc::clone(&tracker),
+          ttl,
+        };
+        let outcome = handler(ctx).await.and_then(|result| {
+          serde_json::to_value(result).map_err(|e| QueueError::SerializeError(e.to_string()))
+        });
+        match &outcome {
+          Ok(result) => tracker.update(&id, ttl, |snapshot| {
+            snapshot.status = JobStatus::Succeeded;
+            snapshot.progress = Some(100);
+            snapshot.result = Some(result.clone());
+            snapshot.error = None;
+          }),
+          Err(err) => tracker.update(&id, ttl, |snapshot| {
+            snapshot.status = if attempt < max_retries {
+              JobStatus::Queued
+            } else {
+              JobStatus::Failed
+            };
+            snapshot.error = Some(err.to_string());
+          }),
+        }
+        outcome.map(drop)
+      }
+    });
+  }
+
+  /// Enqueues `payload` for the handler registered under `name`.
+  ///
+  /// # Errors
+  ///
+  /// Fails as [`Queue::push`] does; the error is itself a responder.
+  pub async fn accept(
+    &self,
+    name: impl Into<String>,
+    payload: &(impl Serialize + ?Sized),
+  ) -> Result<Accepted, QueueError> {
+    let payload =
+      serde_json::to_value(payload).map_err(|e| QueueError::SerializeError(e.to_string()))?;
+    let id = random_id();
+    {
+      let mut jobs = self.tracker.jobs.lock();
+      Tracker::purge(&mut jobs);
+      let (tx, _) = watch::channel(JobSnapshot::queued(id.clone()));
+      jobs.insert(
+        id.clone(),
+        Slot {
+          tx,
+          expires_at: None,
+        },
+      );
+    }
+    let envelope = Envelope {
+      id: id.clone(),
+      payload,
+    };
+    if let Err(err) = self.queue.push(name, &envelope).await {
+      self.tracker.jobs.lock().remove(&id);
+      return Err(err);
+    }
+    Ok(Accepted {
+      location: format!("{}/{id}", self.prefix),
+      id,
+    })
+  }
+
+  /// The current state of job `id`, or `None` when it is unknown or expired.
+  pub fn status(&self, id: &str) -> Option<JobSnapshot> {
+    self.tracker.subscribe(id).map(|rx| rx.borrow().clone())
+  }
+
+  /// Mounts `GET {prefix}/{id}`, answering with the job's [`JobSnapshot`]
+  /// as JSON, and `GET {prefix}/{id}/events`, streaming a `progress` event
+  /// for every change until the job finishes. Unknown ids get `404`.
+  pub fn routes(&self, router: &mut Router) {
+    let jobs = self.clone();
+    router.get(&format!("{}/{{id}}", self.prefix), move |req: Request| {
+      let jobs = jobs.clone();
+      async move {
+        let Some(snapshot) = job_id(&req).and_then(|id| jobs.status(id)) else {
+          return StatusCode::NOT_FOUND.into_response();
+        };
+        let mut resp = json_response(&snapshot);
+        if !snapshot.status.is_terminal() {
+          resp.headers_mut().insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(jobs.poll_interval.as_secs().max(1)),
+          );
+        }
+        resp
+      }
+    });
+
+    let jobs = self.clone();
+    router.get(
+      &format!("{}/{{id}}/events", self.prefix),
+      move |req: Request| {
+        let jobs = jobs.clone();
+        async move {
+          let Some(rx) = job_id(&req).and_then(|id| jobs.tracker.subscribe(id)) else {
+            return StatusCode::NOT_FOUND.into_response();
+          };
+          progress_events(rx)
+        }
+      },
+    );
+  }
+}
+
+/// Answer to a request whose work was enqueued: `202 Accepted` with the
+/// status URL in `Location` and in a JSON body.
+#[derive(Debug, Clone)]
+pub struct Accepted {
+  id: String,
+  location: String,
+}
+
+impl Accepted {
+  /// The job's public id.
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+
+  /// The job's status URL.
+  pub fn location(&self) -> &str {
+    &self.location
+  }
+}
+
+impl Responder for Accepted {
+  fn into_response(self) -> Response {
+    let mut resp = json_response(&serde_json::json!({
+      "id": self.id,
+      "status": JobStatus::Queued,
+      "status_url": self.location,
+    }));
+    *resp.status_mut() = StatusCode::ACCEPTED;
+    if let Ok(location) = HeaderValue::from_str(&self.location) {
+      resp.headers_mut().insert(header::LOCATION, location);
+    }
+    resp
+  }
+}
+
+fn job_id(req: &Request) -> Option<&str> {
+  req
+    .extensions()
+    .get::<PathParams>()?
+    .0
+    .iter()
+    .find(|(name, _)| name == "id")
+    .map(|(_, value)| value.as_str())
+}
+
+fn json_response(value: &impl Serialize) -> Response {
+  match serde_json::to_vec(value) {
+    Ok(body) => {
+      let mut resp = Response::new(TakoBody::from(body));
+      resp.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+      );
+      resp
+    }
+    Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+  }
+}
+
+/// The snapshot now and after every change, as SSE, ending with the
+/// terminal one.
+fn progress_events(rx: watch::Receiver<JobSnapshot>) -> Response {
+  let events = stream::unfold(Some((rx, true)), |state| async move {
+    let (mut rx, first) = state?;
+    if !first && rx.changed().await.is_err() {
+      return None;
+    }
+    let snapshot = rx.borrow_and_update().clone();
+    let data = serde_json::to_string(&snapshot).unwrap_or_default();
+    let frame = Bytes::from(format!("event: progress\ndata: {data}\n\n"));
+    let next = (!snapshot.status.is_terminal()).then_some((rx, false));
+    Some((Ok::<_, std::convert::Infallible>(frame), next))
+  });
+  let mut resp = Response::new(TakoBody::from_stream(events));
+  let headers = resp.headers_mut();
+  headers.insert(
+    header::CONTENT_TYPE,
+    HeaderValue::from_static("text/event-stream"),
+  );
+  headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+  resp
+}
+
+/// 32 hex digits from per-process random keys.
+fn random_id() -> String {
+  static SEQ: AtomicU64 = AtomicU64::new(0);
+  let seq = SEQ.fetch_add(1, Ordering::Relaxed);
+  let word = |salt: u64| {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(seq ^ salt);
+    hasher.finish()
+  };
+  format!("{:016x}{:016x}", word(0), word(u64::MAX))
+}
@@ -11,6 +11,8 @@ use std::time::Instant;
 use async_trait::async_trait;
 use parking_lot::Mutex;
 
+use crate::clock;
+
 /// Job identifier returned by [`QueueBackend::push`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct JobId(pub u64);
@@ -179,7 +181,7 @@ impl QueueBackend for MemoryBackend {
 
   async fn reserve(&self, queue: &str) -> Result<Option<ReservedJob>, BackendError> {
     let mut inner = self.inner.lock();
-    let now = Instant::now();
+    let now = clock::now();
     let pos = inner
       .pending
       .iter()
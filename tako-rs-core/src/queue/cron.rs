@@ -12,11 +12,13 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use chrono::DateTime;
 use chrono::Utc;
 use cron::Schedule;
 
 use super::backend::PushOptions;
 use super::backend::QueueBackend;
+use crate::clock;
 
 /// Periodic-push driver bound to a `QueueBackend`.
 pub struct CronScheduler {
@@ -50,10 +52,10 @@ impl CronScheduler {
   #[cfg(not(feature = "compio"))]
   pub async fn run(self) {
     loop {
-      let Some(next) = self.schedule.upcoming(Utc).next() else {
+      let now = DateTime::<Utc>::from(clock::system_now());
+      let Some(next) = self.schedule.after(&now).next() else {
         return;
       };
-      let now = Utc::now();
       let wait = (next - now).to_std().unwrap_or(Duration::from_secs(0));
       // Pin the wakeup to a concrete monotonic instant. `tokio::sleep(wait)`
       // accumulates micro-overshoot across iterations because each `wait` is
@@ -77,10 +79,10 @@ impl CronScheduler {
   #[cfg(feature = "compio")]
   pub async fn run(self) {
     loop {
-      let Some(next) = self.schedule.upcoming(Utc).next() else {
+      let now = DateTime::<Utc>::from(clock::system_now());
+      let Some(next) = self.schedule.after(&now).next() else {
         return;
       };
-      let now = Utc::now();
       let wait = (next - now).to_std().unwrap_or(Duration::from_secs(0));
       // Same monotonic-anchor rationale as the tokio path.
       let deadline = std::time::Instant::now() + wait;
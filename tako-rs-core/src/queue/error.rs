@@ -25,3 +25,13 @@ impl std::fmt::Display for QueueError {
 }
 
 impl std::error::Error for QueueError {}
+
+impl crate::responder::Responder for QueueError {
+  fn into_response(self) -> crate::types::Response {
+    let status = match self {
+      Self::Shutdown => http::StatusCode::SERVICE_UNAVAILABLE,
+      _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, self.to_string()).into_response()
+  }
+}
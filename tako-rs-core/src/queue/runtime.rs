@@ -22,6 +22,7 @@ use super::RetryPolicy;
 #[cfg(feature = "signals")]
 use super::signal_ids;
 use super::worker::worker_loop;
+use crate::clock;
 #[cfg(feature = "signals")]
 use crate::signals::Signal;
 #[cfg(feature = "signals")]
@@ -138,7 +139,7 @@ impl Queue {
     payload: &(impl serde::Serialize + ?Sized),
     delay: Duration,
   ) -> Result<u64, QueueError> {
-    self.push_inner(name.into(), payload, Some(Instant::now() + delay))
+    self.push_inner(name.into(), payload, Some(clock::now() + delay))
   }
 
   /// Push with a dedup key — the job is queued at most once concurrently.
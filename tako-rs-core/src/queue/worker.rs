@@ -3,7 +3,6 @@
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
-use std::time::Instant;
 
 use super::DeadJob;
 use super::Job;
@@ -13,6 +12,7 @@ use super::runtime::PendingJob;
 use super::runtime::QueueInner;
 #[cfg(feature = "signals")]
 use super::signal_ids;
+use crate::clock;
 
 pub(crate) async fn worker_loop(inner: Arc<QueueInner>) {
   loop {
@@ -53,7 +53,7 @@ pub(crate) async fn worker_loop(inner: Arc<QueueInner>) {
           payload: pj.payload,
           attempts: pj.attempt,
           error: "queue shutdown before job ran".into(),
-          failed_at: Instant::now(),
+          failed_at: clock::now(),
         }));
       }
       break;
@@ -62,7 +62,7 @@ pub(crate) async fn worker_loop(inner: Arc<QueueInner>) {
     // Try to pick up a job
     let job = {
       let mut pending = inner.pending.lock();
-      let now = Instant::now();
+      let now = clock::now();
 
       // Find the first job that's ready to run
       let pos = pending.iter().position(|j| match j.run_after {
@@ -100,7 +100,7 @@ pub(crate) async fn worker_loop(inner: Arc<QueueInner>) {
         payload: pending_job.payload,
         attempts: pending_job.attempt + 1,
         error: "no handler registered".into(),
-        failed_at: Instant::now(),
+        failed_at: clock::now(),
       }));
       continue;
     };
@@ -174,7 +174,7 @@ pub(crate) async fn worker_loop(inner: Arc<QueueInner>) {
           name: pending_job.name,
           payload: pending_job.payload,
           attempt: next_attempt,
-          run_after: Some(Instant::now() + delay),
+          run_after: Some(clock::now() + delay),
           // Preserve the original dedup_key so subsequent `push_dedup`
           // callers continue to see the in-flight retry instead of
           // re-enqueueing a duplicate while the retry sits in `pending`.
@@ -206,7 +206,7 @@ pub(crate) async fn worker_loop(inner: Arc<QueueInner>) {
           payload: pending_job.payload,
           attempts: pending_job.attempt + 1,
           error: e.to_string(),
-          failed_at: Instant::now(),
+          failed_at: clock::now(),
         }));
       }
     }
@@ -34,6 +34,15 @@ use http_body_util::Full;
 use crate::body::TakoBody;
 use crate::types::Response;
 
+mod cache_control;
+mod json_lines;
+mod multipart_mixed;
+
+pub use cache_control::CacheControl;
+pub use json_lines::JsonLines;
+pub use multipart_mixed::MultipartMixed;
+pub use multipart_mixed::Part;
+
 /// A default 404 Not Found response.
 ///
 /// Useful as a simple fallback:
@@ -74,6 +83,77 @@ pub const NOT_FOUND: (StatusCode, &str) = (StatusCode::NOT_FOUND, "Not Found");
 pub trait Responder {
   /// Converts the implementing type into an HTTP response.
   fn into_response(self) -> Response;
+
+  /// Renders the response and replaces its status.
+  ///
+  /// Accepts a [`StatusCode`] or a `u16`; an invalid code is logged and
+  /// leaves the status unchanged.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use tako::responder::Responder;
+  ///
+  /// let res = "created".with_status(201).with_header("x-id", 42);
+  /// assert_eq!(res.status(), 201);
+  /// assert_eq!(res.headers()["x-id"], "42");
+  /// ```
+  fn with_status<S>(self, status: S) -> Response
+  where
+    Self: Sized,
+    S: TryInto<StatusCode>,
+    S::Error: std::fmt::Display,
+  {
+    let mut res = self.into_response();
+    match status.try_into() {
+      Ok(status) => *res.status_mut() = status,
+      Err(e) => tracing::error!("Responder::with_status: invalid status code: {e}"),
+    }
+    res
+  }
+
+  /// Renders the response and sets a header, replacing earlier values of
+  /// the same name.
+  ///
+  /// Names and values convert from strings; values also from integers. An
+  /// invalid name or value is logged and the header is skipped.
+  fn with_header<K, V>(self, name: K, value: V) -> Response
+  where
+    Self: Sized,
+    K: TryInto<HeaderName>,
+    K::Error: std::fmt::Display,
+    V: TryInto<HeaderValue>,
+    V::Error: std::fmt::Display,
+  {
+    let mut res = self.into_response();
+    match (name.try_into(), value.try_into()) {
+      (Ok(name), Ok(value)) => {
+        res.headers_mut().insert(name, value);
+      }
+      (Err(e), _) => tracing::error!("Responder::with_header: invalid header name: {e}"),
+      (_, Err(e)) => tracing::error!("Responder::with_header: invalid header value: {e}"),
+    }
+    res
+  }
+
+  /// Renders the response and transforms its body, keeping status and
+  /// headers.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use tako::body::TakoBody;
+  /// use tako::responder::Responder;
+  ///
+  /// let res = "report".map_body(|_| TakoBody::from("redacted"));
+  /// ```
+  fn map_body<F>(self, f: F) -> Response
+  where
+    Self: Sized,
+    F: FnOnce(TakoBody) -> TakoBody,
+  {
+    self.into_response().map(f)
+  }
 }
 
 /// Alias for [`Responder`] matching the axum-style naming.
@@ -257,25 +337,30 @@ impl Responder for anyhow::Error {
       http::header::CONTENT_TYPE,
       HeaderValue::from_static(mime::TEXT_PLAIN_UTF_8.as_ref()),
     );
+    // Body stays as is; the tag lets `Router::error_renderer` take over.
+    res
+      .extensions_mut()
+      .insert(crate::error::Error::internal(self));
     res
   }
 }
 
-impl ResponderError for anyhow::Error {}
-
 /// Native `Result<R, E>` support for handler returns where both arms implement
 /// [`Responder`]. The `Ok` value renders normally; the `Err` value is rendered
 /// via its own [`Responder`] impl so error types stay typed instead of being
 /// forced through a single panic-or-string path.
 ///
-/// `anyhow::Result<T>` is `Result<T, anyhow::Error>` and falls into this blanket
-/// via the [`Responder`]/[`ResponderError`] impls on [`anyhow::Error`] just above.
+/// Any error type works, so handlers can use `?` with their own domain
+/// errors: implement [`Responder`] directly, or implement
+/// [`IntoStatus`](crate::error::IntoStatus) and let it render as an
+/// [`Error`](crate::error::Error). `anyhow::Result<T>` is
+/// `Result<T, anyhow::Error>` and renders through the impl just above.
 /// There is intentionally only one `Result<_, _>` blanket so future changes cannot
 /// introduce overlap between two specialised impls.
 impl<T, E> Responder for Result<T, E>
 where
   T: Responder,
-  E: ResponderError,
+  E: Responder,
 {
   fn into_response(self) -> Response {
     match self {
@@ -285,10 +370,17 @@ where
   }
 }
 
-/// Marker trait that opts a type into being used as the `Err` arm of a
-/// handler-returned `Result<_, E>`.
+/// Domain errors render through [`Error`](crate::error::Error) with the
+/// status they map to.
+impl<E: crate::error::IntoStatus> Responder for E {
+  fn into_response(self) -> Response {
+    crate::error::Error::from_status_error(self).into_response()
+  }
+}
+
+/// Former marker trait for the `Err` arm of a handler-returned `Result<_, E>`.
 ///
-/// Implement [`Responder`] on your error type, then add `impl ResponderError for MyErr {}`
-/// to make it usable as `Result<_, MyErr>`. `anyhow::Error` already implements both,
-/// so `anyhow::Result<T>` works out of the box.
+/// `Result<T, E>` is now a [`Responder`] whenever `E` is, so the marker is no
+/// longer needed. Existing impls keep compiling.
+#[deprecated(note = "`Result<T, E>` is a `Responder` for any `E: Responder`; remove the impl")]
 pub trait ResponderError: Responder {}
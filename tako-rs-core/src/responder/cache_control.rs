@@ -0,0 +1,187 @@
+//! Typed `Cache-Control` response directives.
+
+use std::fmt;
+use std::time::Duration;
+
+use http::HeaderMap;
+use http::header::CACHE_CONTROL;
+use http::header::HeaderValue;
+
+use super::Responder;
+use crate::types::Response;
+
+/// Builder for a `Cache-Control` response header.
+///
+/// Pair it with any responder as `(CacheControl, R)` to set the header on
+/// that response, or write it into a `HeaderMap` with
+/// [`CacheControl::apply_to`]. Directives render in a fixed order, so equal
+/// builders always produce the same header value.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use tako::responder::CacheControl;
+/// use tako::responder::Responder;
+///
+/// async fn catalog() -> impl Responder {
+///     let cache = CacheControl::new()
+///         .max_age(Duration::from_secs(60))
+///         .s_maxage(Duration::from_secs(600))
+///         .stale_while_revalidate(Duration::from_secs(30));
+///     (cache, "catalog")
+/// }
+///
+/// assert_eq!(
+///     CacheControl::new().private().no_store().to_string(),
+///     "private, no-store"
+/// );
+/// ```
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheControl {
+  public: bool,
+  private: bool,
+  no_cache: bool,
+  no_store: bool,
+  max_age: Option<Duration>,
+  s_maxage: Option<Duration>,
+  must_revalidate: bool,
+  immutable: bool,
+  stale_while_revalidate: Option<Duration>,
+  stale_if_error: Option<Duration>,
+}
+
+impl CacheControl {
+  /// Creates a builder with no directives.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// `max-age`: how long any cache may serve the response as fresh.
+  #[must_use]
+  pub fn max_age(mut self, age: Duration) -> Self {
+    self.max_age = Some(age);
+    self
+  }
+
+  /// `s-maxage`: `max-age` override for shared caches (CDNs, proxies).
+  #[must_use]
+  pub fn s_maxage(mut self, age: Duration) -> Self {
+    self.s_maxage = Some(age);
+    self
+  }
+
+  /// `no-store`: no cache may keep any part of the response.
+  #[must_use]
+  pub fn no_store(mut self) -> Self {
+    self.no_store = true;
+    self
+  }
+
+  /// `no-cache`: caches must revalidate before every reuse.
+  #[must_use]
+  pub fn no_cache(mut self) -> Self {
+    self.no_cache = true;
+    self
+  }
+
+  /// `private`: only the client's own cache may store the response.
+  /// Clears [`CacheControl::public`].
+  #[must_use]
+  pub fn private(mut self) -> Self {
+    self.private = true;
+    self.public = false;
+    self
+  }
+
+  /// `public`: shared caches may store the response even when it would
+  /// otherwise be uncacheable (e.g. authenticated). Clears
+  /// [`CacheControl::private`].
+  #[must_use]
+  pub fn public(mut self) -> Self {
+    self.public = true;
+    self.private = false;
+    self
+  }
+
+  /// `must-revalidate`: a stale response must not be served without
+  /// revalidation.
+  #[must_use]
+  pub fn must_revalidate(mut self) -> Self {
+    self.must_revalidate = true;
+    self
+  }
+
+  /// `immutable`: the response will not change while fresh (fingerprinted
+  /// assets).
+  #[must_use]
+  pub fn immutable(mut self) -> Self {
+    self.immutable = true;
+    self
+  }
+
+  /// `stale-while-revalidate` (RFC 5861): how long a stale response may be
+  /// served while the cache refreshes it in the background.
+  #[must_use]
+  pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+    self.stale_while_revalidate = Some(window);
+    self
+  }
+
+  /// `stale-if-error` (RFC 5861): how long a stale response may be served
+  /// when the origin fails.
+  #[must_use]
+  pub fn stale_if_error(mut self, window: Duration) -> Self {
+    self.stale_if_error = Some(window);
+    self
+  }
+
+  /// The header value, e.g. `public, max-age=60`.
+  pub fn to_header_value(&self) -> HeaderValue {
+    HeaderValue::from_str(&self.to_string()).expect("directives are ASCII")
+  }
+
+  /// Sets `Cache-Control` in `headers`, replacing any earlier value.
+  pub fn apply_to(&self, headers: &mut HeaderMap) {
+    headers.insert(CACHE_CONTROL, self.to_header_value());
+  }
+}
+
+impl fmt::Display for CacheControl {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let secs = |name: &str, d: Option<Duration>| d.map(|d| format!("{name}={}", d.as_secs()));
+    let flag = |name: &str, set: bool| set.then(|| name.to_string());
+    let directives = [
+      flag("public", self.public),
+      flag("private", self.private),
+      flag("no-cache", self.no_cache),
+      flag("no-store", self.no_store),
+      secs("max-age", self.max_age),
+      secs("s-maxage", self.s_maxage),
+      flag("must-revalidate", self.must_revalidate),
+      flag("immutable", self.immutable),
+      secs("stale-while-revalidate", self.stale_while_revalidate),
+      secs("stale-if-error", self.stale_if_error),
+    ];
+    let directives: Vec<String> = directives.into_iter().flatten().collect();
+    f.write_str(&directives.join(", "))
+  }
+}
+
+impl From<CacheControl> for HeaderValue {
+  fn from(cache: CacheControl) -> Self {
+    cache.to_header_value()
+  }
+}
+
+/// Sets `Cache-Control` on whatever `R` renders, replacing a value `R` set
+/// itself.
+impl<R: Responder> Responder for (CacheControl, R) {
+  fn into_response(self) -> Response {
+    let (cache, inner) = self;
+    let mut res = inner.into_response();
+    cache.apply_to(res.headers_mut());
+    res
+  }
+}
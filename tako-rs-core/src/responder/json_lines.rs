@@ -0,0 +1,87 @@
+//! Newline-delimited JSON (`application/x-ndjson`) streaming responses.
+
+use bytes::Bytes;
+use futures_util::Stream;
+use futures_util::StreamExt;
+use futures_util::stream::BoxStream;
+use http::header::CONTENT_TYPE;
+use http::header::HeaderValue;
+use serde::Serialize;
+
+use super::Responder;
+use crate::body::TakoBody;
+use crate::types::BoxError;
+use crate::types::Response;
+
+/// Streams items as JSON Lines (one JSON document per line).
+///
+/// Each item is serialized only when the connection asks for more data and is
+/// sent as its own body frame, so a slow client holds back the source stream
+/// instead of letting serialized output pile up in memory. Responses carry
+/// `Content-Type: application/x-ndjson`.
+///
+/// A serialization error, or an error item from [`JsonLines::try_new`], ends
+/// the body with an error, which aborts the response mid-stream.
+///
+/// # Examples
+///
+/// ```rust
+/// use futures_util::stream;
+/// use tako::responder::JsonLines;
+///
+/// #[derive(serde::Serialize)]
+/// struct Row { id: u32 }
+///
+/// async fn export() -> JsonLines {
+///     JsonLines::new(stream::iter((0..3).map(|id| Row { id })))
+/// }
+/// ```
+#[doc(alias = "ndjson")]
+pub struct JsonLines {
+  lines: BoxStream<'static, Result<Bytes, BoxError>>,
+}
+
+impl JsonLines {
+  /// Streams every item of `items` as one JSON line.
+  pub fn new<S, T>(items: S) -> Self
+  where
+    S: Stream<Item = T> + Send + 'static,
+    T: Serialize,
+  {
+    Self {
+      lines: items.map(|item| encode_line(&item)).boxed(),
+    }
+  }
+
+  /// Streams the `Ok` items of a fallible stream; the first `Err` aborts the
+  /// response.
+  pub fn try_new<S, T, E>(items: S) -> Self
+  where
+    S: Stream<Item = Result<T, E>> + Send + 'static,
+    T: Serialize,
+    E: Into<BoxError>,
+  {
+    Self {
+      lines: items
+        .map(|item| item.map_err(Into::into).and_then(|item| encode_line(&item)))
+        .boxed(),
+    }
+  }
+}
+
+fn encode_line<T: Serialize>(item: &T) -> Result<Bytes, BoxError> {
+  let mut line = serde_json::to_vec(item)?;
+  line.push(b'\n');
+  Ok(Bytes::from(line))
+}
+
+impl Responder for JsonLines {
+  fn into_response(self) -> Response {
+    let mut res = Response::new(TakoBody::from_stream(self.lines));
+    res.headers_mut().insert(
+      CONTENT_TYPE,
+      HeaderValue::from_static("application/x-ndjson"),
+    );
+    res
+  }
+}
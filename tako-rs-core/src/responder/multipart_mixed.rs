@@ -0,0 +1,175 @@
+//! `multipart/mixed` streaming responses.
+
+use std::hash::BuildHasher as _;
+use std::hash::Hasher as _;
+use std::hash::RandomState;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use bytes::Bytes;
+use bytes::BytesMut;
+use futures_util::Stream;
+use futures_util::StreamExt;
+use futures_util::stream;
+use futures_util::stream::BoxStream;
+use http::HeaderMap;
+use http::header::CONTENT_TYPE;
+use http::header::HeaderName;
+use http::header::HeaderValue;
+use http_body_util::BodyExt;
+use serde::Serialize;
+
+use super::Responder;
+use crate::body::TakoBody;
+use crate::types::BoxError;
+use crate::types::Response;
+
+/// One resource of a [`MultipartMixed`] response.
+pub struct Part {
+  headers: HeaderMap,
+  body: TakoBody,
+}
+
+impl Part {
+  /// A part of type `content_type`. The body may be a stream; it is passed
+  /// through frame by frame.
+  pub fn new(content_type: &'static str, body: impl Into<TakoBody>) -> Self {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    Self {
+      headers,
+      body: body.into(),
+    }
+  }
+
+  /// A part holding `value` as `application/json`.
+  pub fn json<T: Serialize>(value: &T) -> serde_json::Result<Self> {
+    Ok(Self::new("application/json", serde_json::to_vec(value)?))
+  }
+
+  /// A part carrying the headers and body of `resp`, e.g. the response of
+  /// an internal dispatch. The status is dropped; add it as a header if
+  /// clients need it.
+  pub fn from_response(resp: Response) -> Self {
+    let (parts, body) = resp.into_parts();
+    Self {
+      headers: parts.headers,
+      body,
+    }
+  }
+
+  /// Adds a header to the part, e.g. `Content-ID` or `Content-Location`.
+  pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+    self.headers.append(name, value);
+    self
+  }
+
+  fn into_stream(self, boundary: &str) -> impl Stream<Item = Result<Bytes, BoxError>> + use<> {
+    let mut head = BytesMut::new();
+    head.extend_from_slice(b"--");
+    head.extend_from_slice(boundary.as_bytes());
+    head.extend_from_slice(b"\r\n");
+    for (name, value) in &self.headers {
+      head.extend_from_slice(name.as_str().as_bytes());
+      head.extend_from_slice(b": ");
+      head.extend_from_slice(value.as_bytes());
+      head.extend_from_slice(b"\r\n");
+    }
+    head.extend_from_slice(b"\r\n");
+    stream::once(async move { Ok(head.freeze()) })
+      .chain(self.body.into_data_stream())
+      .chain(stream::once(async { Ok(Bytes::from_static(b"\r\n")) }))
+  }
+}
+
+/// Streams several resources in one `multipart/mixed` response (RFC 2046).
+///
+/// An HTTP/1.1-friendly way to return a bundle — a page's API calls, a
+/// document and its attachments — in a single round trip. Each [`Part`]
+/// carries its own content type and headers, and parts are written in
+/// order as the connection asks for data, so a part produced by a stream is
+/// never buffered whole.
+///
+/// An error from a part's body aborts the response mid-stream.
+///
+/// # Examples
+///
+/// ```rust
+/// use tako::responder::MultipartMixed;
+/// use tako::responder::Part;
+///
+/// async fn bundle() -> MultipartMixed {
+///     MultipartMixed::new([
+///         Part::json(&serde_json::json!({ "id": 7 })).unwrap(),
+///         Part::new("text/css", "body { margin: 0 }"),
+///     ])
+/// }
+/// ```
+pub struct MultipartMixed {
+  boundary: String,
+  parts: BoxStream<'static, Part>,
+}
+
+impl MultipartMixed {
+  /// Sends every part of `parts`.
+  pub fn new<I>(parts: I) -> Self
+  where
+    I: IntoIterator<Item = Part>,
+    I::IntoIter: Send + 'static,
+  {
+    Self::from_stream(stream::iter(parts))
+  }
+
+  /// Sends parts as `parts` yields them.
+  pub fn from_stream<S>(parts: S) -> Self
+  where
+    S: Stream<Item = Part> + Send + 'static,
+  {
+    Self {
+      boundary: random_boundary(),
+      parts: parts.boxed(),
+    }
+  }
+
+  /// Replaces the random boundary. It must not occur in any part, and RFC
+  /// 2046 limits it to 70 characters.
+  pub fn boundary(mut self, boundary: impl Into<String>) -> Self {
+    self.boundary = boundary.into();
+    self
+  }
+}
+
+/// 32 hex digits; collisions with part content are vanishingly unlikely.
+fn random_boundary() -> String {
+  static SEQ: AtomicU64 = AtomicU64::new(0);
+  let seq = SEQ.fetch_add(1, Ordering::Relaxed);
+  let word = |salt: u64| {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(seq ^ salt);
+    hasher.finish()
+  };
+  format!("{:016x}{:016x}", word(0), word(u64::MAX))
+}
+
+impl Responder for MultipartMixed {
+  fn into_response(self) -> Response {
+    let content_type =
+      HeaderValue::from_str(&format!("multipart/mixed; boundary=\"{}\"", self.boundary));
+    let Ok(content_type) = content_type else {
+      return (
+        http::StatusCode::INTERNAL_SERVER_ERROR,
+        "invalid multipart boundary",
+      )
+        .into_response();
+    };
+    let boundary = self.boundary;
+    let close = Bytes::from(format!("--{boundary}--\r\n"));
+    let body = self
+      .parts
+      .flat_map(move |part| part.into_stream(&boundary))
+      .chain(stream::once(async move { Ok(close) }));
+    let mut res = Response::new(TakoBody::from_stream(body));
+    res.headers_mut().insert(CONTENT_TYPE, content_type);
+    res
+  }
+}
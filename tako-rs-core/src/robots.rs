@@ -0,0 +1,217 @@
+//! `robots.txt` generation.
+//!
+//! [`Robots`] collects crawler rules per user agent and serves them at
+//! `/robots.txt` with a public `Cache-Control`, so crawlers and CDNs do not
+//! refetch it on every visit. With no rules at all it allows everything.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::robots::Robots;
+//! use tako::router::Router;
+//!
+//! let mut router = Router::new();
+//! Robots::new()
+//!   .disallow("/admin/")
+//!   .agent("GPTBot", |g| g.disallow("/"))
+//!   .sitemap("https://example.com/sitemap.xml")
+//!   .mount(&mut router);
+//! ```
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::HeaderValue;
+use http::header::CACHE_CONTROL;
+use http::header::CONTENT_TYPE;
+
+use crate::body::TakoBody;
+use crate::route::Route;
+use crate::router::Router;
+use crate::types::Request;
+
+/// Rules for one set of user agents.
+#[derive(Debug, Clone)]
+pub struct RobotsGroup {
+  agents: Vec<String>,
+  rules: Vec<(&'static str, String)>,
+  crawl_delay: Option<u32>,
+}
+
+impl RobotsGroup {
+  fn new(agent: impl Into<String>) -> Self {
+    Self {
+      agents: vec![agent.into()],
+      rules: Vec::new(),
+      crawl_delay: None,
+    }
+  }
+
+  /// Applies the group to another user agent as well.
+  pub fn also(mut self, agent: impl Into<String>) -> Self {
+    self.agents.push(agent.into());
+    self
+  }
+
+  /// Allows paths starting with `path`.
+  pub fn allow(mut self, path: impl Into<String>) -> Self {
+    self.rules.push(("Allow", path.into()));
+    self
+  }
+
+  /// Disallows paths starting with `path`; `/` blocks the whole site.
+  pub fn disallow(mut self, path: impl Into<String>) -> Self {
+    self.rules.push(("Disallow", path.into()));
+    self
+  }
+
+  /// Asks the crawler to wait `seconds` between requests. Not every crawler
+  /// honours it.
+  pub fn crawl_delay(mut self, seconds: u32) -> Self {
+    self.crawl_delay = Some(seconds);
+    self
+  }
+
+  fn render(&self, out: &mut String) {
+    for agent in &self.agents {
+      let _ = writeln!(out, "User-agent: {agent}");
+    }
+    if self.rules.is_empty() {
+      out.push_str("Disallow:\n");
+    }
+    for (directive, path) in &self.rules {
+      let _ = writeln!(out, "{directive}: {path}");
+    }
+    if let Some(delay) = self.crawl_delay {
+      let _ = writeln!(out, "Crawl-delay: {delay}");
+    }
+  }
+}
+
+/// A `robots.txt` document.
+#[derive(Debug, Clone)]
+pub struct Robots {
+  default: RobotsGroup,
+  groups: Vec<RobotsGroup>,
+  sitemaps: Vec<String>,
+  max_age: Duration,
+}
+
+impl Default for Robots {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Robots {
+  /// An empty document that allows every crawler everywhere, cached for an
+  /// hour.
+  pub fn new() -> Self {
+    Self {
+      default: RobotsGroup::new("*"),
+      groups: Vec::new(),
+      sitemaps: Vec::new(),
+      max_age: Duration::from_secs(3600),
+    }
+  }
+
+  /// Allows `path` for every crawler without a group of its own.
+  pub fn allow(mut self, path: impl Into<String>) -> Self {
+    self.default = self.default.allow(path);
+    self
+  }
+
+  /// Disallows `path` for every crawler without a group of its own.
+  pub fn disallow(mut self, path: impl Into<String>) -> Self {
+    self.default = self.default.disallow(path);
+    self
+  }
+
+  /// Adds rules for crawlers identifying as `agent`. Such crawlers ignore
+  /// the default (`*`) rules, as the robots exclusion protocol specifies.
+  pub fn agent(
+    mut self,
+    agent: impl Into<String>,
+    rules: impl FnOnce(RobotsGroup) -> RobotsGroup,
+  ) -> Self {
+    self.groups.push(rules(RobotsGroup::new(agent)));
+    self
+  }
+
+  /// Advertises a sitemap by absolute URL.
+  pub fn sitemap(mut self, url: impl Into<String>) -> Self {
+    self.sitemaps.push(url.into());
+    self
+  }
+
+  /// `Cache-Control: max-age` of the served document (default 1 hour).
+  pub fn max_age(mut self, max_age: Duration) -> Self {
+    self.max_age = max_age;
+    self
+  }
+
+  /// The document text.
+  pub fn render(&self) -> String {
+    let mut out = String::new();
+    for group in &self.groups {
+      group.render(&mut out);
+      out.push('\n');
+    }
+    self.default.render(&mut out);
+    if !self.sitemaps.is_empty() {
+      out.push('\n');
+    }
+    for url in &self.sitemaps {
+      let _ = writeln!(out, "Sitemap: {url}");
+    }
+    out
+  }
+
+  /// Serves the document at `GET /robots.txt`.
+  pub fn mount(self, router: &mut Router) -> Arc<Route> {
+    let body = Bytes::from(self.render());
+    let cache_control = public_max_age(self.max_age);
+    router.get("/robots.txt", move |_req: Request| {
+      let body = body.clone();
+      let cache_control = cache_control.clone();
+      async move {
+        let mut resp = http::Response::new(TakoBody::from(body));
+        let headers = resp.headers_mut();
+        headers.insert(
+          CONTENT_TYPE,
+          HeaderValue::from_static("text/plain; charset=utf-8"),
+        );
+        headers.insert(CACHE_CONTROL, cache_control);
+        resp
+      }
+    })
+  }
+}
+
+/// `public, max-age=<secs>`.
+pub(crate) fn public_max_age(max_age: Duration) -> HeaderValue {
+  HeaderValue::from_str(&format!("public, max-age={}", max_age.as_secs()))
+    .unwrap_or_else(|_| HeaderValue::from_static("public"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn renders_agent_groups_before_the_default() {
+    let robots = Robots::new()
+      .disallow("/admin/")
+      .agent("GPTBot", |g| g.also("CCBot").disallow("/"))
+      .sitemap("https://example.com/sitemap.xml");
+    assert_eq!(
+      robots.render(),
+      "User-agent: GPTBot\nUser-agent: CCBot\nDisallow: /\n\n\
+       User-agent: *\nDisallow: /admin/\n\n\
+       Sitemap: https://example.com/sitemap.xml\n"
+    );
+    assert_eq!(Robots::new().render(), "User-agent: *\nDisallow:\n");
+  }
+}
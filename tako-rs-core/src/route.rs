@@ -25,9 +25,16 @@
 //!   .timeout(std::time::Duration::from_secs(5));
 //! ```
 
+pub(crate) mod blocking;
 mod builder;
 mod def;
+mod deprecation;
+mod examples;
 #[cfg(any(feature = "utoipa", feature = "vespera"))]
 mod openapi;
 
+pub use blocking::BlockingPoolStats;
+pub use blocking::blocking_pool_stats;
 pub use def::Route;
+pub use examples::ExampleBody;
+pub use examples::RouteExample;
@@ -0,0 +1,70 @@
+//! Queue-depth counters for handlers moved to the blocking pool with
+//! [`Route::blocking`](super::Route::blocking).
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+pub(crate) static BLOCKING_QUEUED: AtomicUsize = AtomicUsize::new(0);
+pub(crate) static BLOCKING_RUNNING: AtomicUsize = AtomicUsize::new(0);
+pub(crate) static BLOCKING_COMPLETED: AtomicU64 = AtomicU64::new(0);
+
+/// Counts one call as running until dropped, so a panicking handler does not
+/// leave `running` inflated.
+pub(crate) struct RunningGuard;
+
+impl RunningGuard {
+  pub(crate) fn enter() -> Self {
+    BLOCKING_RUNNING.fetch_add(1, Ordering::Relaxed);
+    Self
+  }
+}
+
+impl Drop for RunningGuard {
+  fn drop(&mut self) {
+    BLOCKING_RUNNING.fetch_sub(1, Ordering::Relaxed);
+  }
+}
+
+/// Snapshot of handlers running on the blocking pool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockingPoolStats {
+  /// Calls waiting for a free blocking thread.
+  pub queued: usize,
+  /// Calls currently executing.
+  pub running: usize,
+  /// Calls finished since process start.
+  pub completed: u64,
+}
+
+/// Current blocking-pool queue depth and throughput, process-wide.
+pub fn blocking_pool_stats() -> BlockingPoolStats {
+  BlockingPoolStats {
+    queued: BLOCKING_QUEUED.load(Ordering::Relaxed),
+    running: BLOCKING_RUNNING.load(Ordering::Relaxed),
+    completed: BLOCKING_COMPLETED.load(Ordering::Relaxed),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::blocking_pool_stats;
+  use crate::body::TakoBody;
+  use crate::handler::BoxHandler;
+  use crate::types::Request;
+
+  #[tokio::test]
+  async fn panicking_handlers_stop_counting_as_running() {
+    fn boom() -> &'static str {
+      panic!("boom")
+    }
+
+    let handler =
+      BoxHandler::new::<_, (Request,)>(|_req: Request| async { boom() }).on_blocking_pool();
+
+    let call = tokio::spawn(async move { handler.call(Request::new(TakoBody::empty())).await });
+    assert!(call.await.unwrap_err().is_panic());
+    assert_eq!(blocking_pool_stats().running, 0);
+    assert_eq!(blocking_pool_stats().completed, 0);
+  }
+}
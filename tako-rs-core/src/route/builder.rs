@@ -12,6 +12,7 @@ use std::time::Duration;
 
 use super::Route;
 use crate::extractors::json::SimdJsonMode;
+use crate::handler::BoxHandler;
 use crate::middleware::Next;
 #[cfg(feature = "plugins")]
 use crate::plugins::TakoPlugin;
@@ -113,12 +114,12 @@ impl Route {
       let mini_router = crate::router::Router::new();
 
       let plugins = self.plugins.read();
-      for plugin in plugins.iter() {
+      for plugin in crate::router::by_priority(&plugins) {
         // See `Router::setup_plugins_once`: log failures so an erroring
         // route-level plugin (auth, rate-limit, ...) is visible instead
         // of silently dropped — fail-open without diagnostics is
         // exactly what the audit calls out.
-        if let Err(e) = plugin.setup(&mini_router) {
+        if let Err(e) = mini_router.setup_plugin(plugin) {
           tracing::error!(
             plugin = plugin.name(),
             error = %e,
@@ -127,12 +128,18 @@ impl Route {
         }
       }
 
-      // Transfer middleware from mini-router to this route
+      // Transfer middleware from mini-router to this route, after any
+      // middleware inherited from enclosing routers.
       let plugin_middlewares = mini_router.middlewares.load();
       let existing = self.middlewares.load_full();
+      let at = self
+        .inherited_middleware
+        .load(Ordering::Acquire)
+        .min(existing.len());
       let mut merged = Vec::with_capacity(plugin_middlewares.len() + existing.len());
+      merged.extend(existing[..at].iter().cloned());
       merged.extend(plugin_middlewares.iter().cloned());
-      merged.extend(existing.iter().cloned());
+      merged.extend(existing[at..].iter().cloned());
       if !merged.is_empty() {
         self.has_middleware.store(true, Ordering::Release);
       }
@@ -289,4 +296,70 @@ impl Route {
   pub(crate) fn get_simd_json_mode(&self) -> Option<SimdJsonMode> {
     self.simd_json_mode.get().copied()
   }
+
+  /// Overrides the request body limit for this route, in bytes.
+  ///
+  /// The global `BodyLimit` middleware uses this value instead of its own
+  /// limit, so a single route can accept larger uploads (or fewer bytes) than
+  /// the rest of the application. `Json`, `Form` and the multipart extractors
+  /// enforce it even when no `BodyLimit` middleware is installed, answering
+  /// oversized payloads with `413 Payload Too Large`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,ignore
+  /// router.middleware(BodyLimit::new(1024 * 1024).into_middleware());
+  ///
+  /// // Allow 50 MiB on the upload endpoint only
+  /// router.route(Method::POST, "/upload", upload)
+  ///     .body_limit(50 * 1024 * 1024);
+  /// ```
+  pub fn body_limit(&self, bytes: usize) -> &Self {
+    if let Err(_existing) = self.body_limit.set(bytes) {
+      tracing::warn!(
+        path = %self.path,
+        method = ?self.method,
+        existing = ?self.body_limit.get().copied(),
+        requested = bytes,
+        "Route::body_limit called twice; subsequent calls are ignored (OnceLock first-wins)",
+      );
+    }
+    self
+  }
+
+  /// Returns the configured body limit for this route, if any.
+  #[inline]
+  pub(crate) fn get_body_limit(&self) -> Option<usize> {
+    self.body_limit.get().copied()
+  }
+
+  /// Runs this route's handler on tokio's blocking thread pool instead of
+  /// the async workers.
+  ///
+  /// Use it for CPU-heavy or synchronous handlers (image processing, report
+  /// rendering, blocking database drivers) that would otherwise stall every
+  /// other request sharing the worker thread. Middleware still runs on the
+  /// async workers; only the handler moves. Queue depth is exposed through
+  /// [`blocking_pool_stats`](crate::route::blocking_pool_stats), and the
+  /// pool size is the runtime's `max_blocking_threads`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,ignore
+  /// router.route(Method::POST, "/thumbnails", render_thumbnail)
+  ///     .blocking();
+  /// ```
+  pub fn blocking(&self) -> &Self {
+    let _ = self
+      .blocking_handler
+      .get_or_init(|| self.handler.clone().on_blocking_pool());
+    self
+  }
+
+  /// The handler dispatch should call: the blocking-pool wrapper when
+  /// [`blocking`](Self::blocking) was set, otherwise the plain handler.
+  #[inline]
+  pub(crate) fn endpoint(&self) -> &BoxHandler {
+    self.blocking_handler.get().unwrap_or(&self.handler)
+  }
 }
@@ -1,27 +1,31 @@
 //! The [`Route`] struct, its handler/middleware storage, and construction.
 //!
 //! Holds the field layout for a route (path, method, handler, middleware
-//! chain, protocol guard, and feature-gated plugin / signal / `OpenAPI`
-//! state) plus the constructor and the `cloned_with_path` helper used by
-//! the router to re-home routes under a prefix.
+//! chain, protocol guard, feature-gated plugin / signal / `OpenAPI` state,
+//! and a mounted child router's typed state) plus the constructor and the
+//! `cloned_with_path` helper used by the router to re-home routes under a
+//! prefix.
 
 use std::sync::Arc;
 use std::sync::OnceLock;
 use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 use arc_swap::ArcSwap;
 use http::Method;
-#[cfg(any(feature = "plugins", feature = "utoipa", feature = "vespera"))]
 use parking_lot::RwLock;
 
+use super::RouteExample;
 use crate::extractors::json::SimdJsonMode;
 use crate::handler::BoxHandler;
 #[cfg(any(feature = "utoipa", feature = "vespera"))]
 use crate::openapi::RouteOpenApi;
 #[cfg(feature = "plugins")]
 use crate::plugins::TakoPlugin;
+use crate::router::StaticResponse;
+use crate::router_state::RouterState;
 #[cfg(feature = "signals")]
 use crate::signals::SignalArbiter;
 use crate::types::BoxMiddleware;
@@ -49,6 +53,10 @@ pub struct Route {
   pub(crate) middlewares: ArcSwap<Vec<BoxMiddleware>>,
   /// Fast check: true when route middleware is registered (avoids `ArcSwap` load on hot path).
   pub(crate) has_middleware: AtomicBool,
+  /// How many leading entries of `middlewares` were inherited from enclosing
+  /// routers by `nest` / `merge`. Route plugins install their middleware
+  /// after these, so they run where they would on the original router.
+  pub(crate) inherited_middleware: AtomicUsize,
   /// Whether trailing slash redirection is enabled.
   pub tsr: bool,
   /// Route-specific plugins.
@@ -69,6 +77,19 @@ pub struct Route {
   pub(crate) timeout: OnceLock<Duration>,
   /// Route-level SIMD JSON dispatch mode (set once at registration, lock-free reads).
   pub(crate) simd_json_mode: OnceLock<SimdJsonMode>,
+  /// Route-level request body limit override in bytes (set once at registration).
+  pub(crate) body_limit: OnceLock<usize>,
+  /// Blocking-pool wrapper around `handler`, set by [`Route::blocking`].
+  pub(crate) blocking_handler: OnceLock<BoxHandler>,
+  /// Example calls added with [`Route::example`].
+  pub(crate) examples: RwLock<Vec<RouteExample>>,
+  /// Typed state of the child router this route was nested or merged from.
+  /// Dispatch hands it to the request in place of the mounting router's
+  /// state; lookups fall through to that router via the state's parent link.
+  pub(crate) state: OnceLock<Arc<RouterState>>,
+  /// Prebuilt response of a [`Router::static_response`](crate::router::Router::static_response)
+  /// route, copied by dispatch without calling the handler.
+  pub(crate) static_response: OnceLock<Arc<StaticResponse>>,
 }
 
 impl Route {
@@ -80,6 +101,7 @@ impl Route {
       handler,
       middlewares: ArcSwap::new(Arc::default()),
       has_middleware: AtomicBool::new(false),
+      inherited_middleware: AtomicUsize::new(0),
       tsr: tsr.unwrap_or(false),
       #[cfg(feature = "plugins")]
       plugins: RwLock::new(Vec::new()),
@@ -92,6 +114,11 @@ impl Route {
       openapi: RwLock::new(None),
       timeout: OnceLock::new(),
       simd_json_mode: OnceLock::new(),
+      body_limit: OnceLock::new(),
+      blocking_handler: OnceLock::new(),
+      examples: RwLock::new(Vec::new()),
+      state: OnceLock::new(),
+      static_response: OnceLock::new(),
     }
   }
 
@@ -99,10 +126,9 @@ impl Route {
   /// but a different path. Used by [`crate::router::Router::nest`] to register
   /// a child router's routes under a prefix without mutating the originals.
   ///
-  /// Route-level plugins are *not* carried over — `TakoPlugin` is not `Clone`,
-  /// and the cloned route is treated as already-initialized so the empty
-  /// plugin list is never set up. Plugin-bearing routes should be registered
-  /// directly on the parent router after `nest`.
+  /// Route-level plugins are *not* copied — `TakoPlugin` is not `Clone`, and
+  /// the cloned route is treated as already-initialized so the empty plugin
+  /// list is never set up. `nest` moves them over from the consumed child.
   pub(crate) fn cloned_with_path(&self, new_path: String) -> Arc<Route> {
     let cloned = Self {
       path: new_path,
@@ -110,6 +136,7 @@ impl Route {
       handler: self.handler.clone(),
       middlewares: ArcSwap::new(self.middlewares.load_full()),
       has_middleware: AtomicBool::new(self.has_middleware.load(Ordering::Acquire)),
+      inherited_middleware: AtomicUsize::new(self.inherited_middleware.load(Ordering::Acquire)),
       tsr: self.tsr,
       #[cfg(feature = "plugins")]
       plugins: RwLock::new(Vec::new()),
@@ -143,6 +170,35 @@ impl Route {
         }
         lock
       },
+      body_limit: {
+        let lock = OnceLock::new();
+        if let Some(v) = self.body_limit.get() {
+          let _ = lock.set(*v);
+        }
+        lock
+      },
+      blocking_handler: {
+        let lock = OnceLock::new();
+        if let Some(h) = self.blocking_handler.get() {
+          let _ = lock.set(h.clone());
+        }
+        lock
+      },
+      examples: RwLock::new(self.examples.read().clone()),
+      state: {
+        let lock = OnceLock::new();
+        if let Some(v) = self.state.get() {
+          let _ = lock.set(Arc::clone(v));
+        }
+        lock
+      },
+      static_response: {
+        let lock = OnceLock::new();
+        if let Some(v) = self.static_response.get() {
+          let _ = lock.set(Arc::clone(v));
+        }
+        lock
+      },
     };
     Arc::new(cloned)
   }
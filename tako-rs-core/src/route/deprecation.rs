@@ -0,0 +1,101 @@
+//! Deprecation and sunset signalling for a route.
+//!
+//! [`Route::deprecated`] stamps every response of the route with the
+//! `Deprecation` (RFC 9745), `Sunset` (RFC 8594) and `Link` headers, so
+//! clients learn about an endpoint's lifecycle from the responses they
+//! already receive. With an `OpenAPI` backend enabled the operation is also
+//! marked `deprecated` in the generated spec.
+
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use http::HeaderName;
+use http::HeaderValue;
+use http::header::LINK;
+
+use super::Route;
+use crate::middleware::Next;
+use crate::types::Request;
+
+/// `Deprecation` response header (RFC 9745).
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+/// `Sunset` response header (RFC 8594).
+const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+impl Route {
+  /// Marks this route as deprecated since `since`.
+  ///
+  /// Every response gets `Deprecation: @<unix seconds>`; with a
+  /// `sunset_date`, also `Sunset: <HTTP-date>` announcing when the route
+  /// stops responding; with a `link`, also `Link: <link>; rel="deprecation"`
+  /// pointing at migration docs. Headers the handler sets itself are kept —
+  /// the `Link` is appended. Under `utoipa` / `vespera` the operation is
+  /// marked deprecated in `OpenAPI` documentation.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `link` is not a valid header value.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,ignore
+  /// use std::time::{Duration, SystemTime};
+  ///
+  /// let since = SystemTime::UNIX_EPOCH + Duration::from_secs(1_767_225_600);
+  /// router.route(Method::GET, "/v1/users", list_users_v1).deprecated(
+  ///     since,
+  ///     Some(since + Duration::from_secs(180 * 86_400)),
+  ///     Some("https://example.com/docs/migrate-to-v2"),
+  /// );
+  /// ```
+  pub fn deprecated(
+    &self,
+    since: SystemTime,
+    sunset_date: Option<SystemTime>,
+    link: Option<&str>,
+  ) -> &Self {
+    let deprecation =
+      HeaderValue::from_str(&format!("@{}", unix_secs(since))).expect("a number is a valid header");
+    let sunset = sunset_date.map(|at| {
+      HeaderValue::from_str(&httpdate::fmt_http_date(at)).expect("HTTP-date is a valid header")
+    });
+    let link = link.map(|link| {
+      HeaderValue::from_str(&format!("<{link}>; rel=\"deprecation\""))
+        .expect("deprecation link must be a valid header value")
+    });
+
+    #[cfg(any(feature = "utoipa", feature = "vespera"))]
+    {
+      let mut guard = self.openapi.write();
+      guard
+        .get_or_insert_with(crate::openapi::RouteOpenApi::default)
+        .deprecated = true;
+    }
+
+    self.middleware(move |req: Request, next: Next| {
+      let deprecation = deprecation.clone();
+      let sunset = sunset.clone();
+      let link = link.clone();
+      async move {
+        let mut res = next.run(req).await;
+        let headers = res.headers_mut();
+        headers.entry(DEPRECATION).or_insert(deprecation);
+        if let Some(sunset) = sunset {
+          headers.entry(SUNSET).or_insert(sunset);
+        }
+        if let Some(link) = link {
+          headers.append(LINK, link);
+        }
+        res
+      }
+    })
+  }
+}
+
+/// Seconds since the Unix epoch, negative for earlier instants.
+fn unix_secs(at: SystemTime) -> i64 {
+  match at.duration_since(UNIX_EPOCH) {
+    Ok(d) => i64::try_from(d.as_secs()).unwrap_or(i64::MAX),
+    Err(e) => -i64::try_from(e.duration().as_secs()).unwrap_or(i64::MAX),
+  }
+}
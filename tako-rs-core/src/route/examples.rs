@@ -0,0 +1,329 @@
+//! Example requests and expected responses attached to a route.
+//!
+//! A [`RouteExample`] describes one concrete call of a route: the path
+//! parameters, query, headers and body to send, and the status, headers and
+//! body expected back. Examples serve two purposes. They are smoke tests —
+//! [`Router::run_smoke_tests`](crate::router::Router::run_smoke_tests)
+//! dispatches every example in-process and reports mismatches — and, with an
+//! `OpenAPI` backend enabled, their JSON bodies are published as request and
+//! response examples in the generated document.
+
+use std::fmt::Write as _;
+
+use http::StatusCode;
+use serde_json::Value;
+
+use super::Route;
+
+/// Body of an example request or expected response.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExampleBody {
+  /// A JSON document. As an expectation it matches any response whose JSON
+  /// contains at least these object fields (see [`RouteExample::expect_json`]).
+  Json(Value),
+  /// Plain text, compared exactly.
+  Text(String),
+}
+
+/// One example call of a route.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use serde_json::json;
+/// use tako::route::RouteExample;
+///
+/// router
+///   .route(Method::GET, "/users/{id}", get_user)
+///   .example(
+///     RouteExample::new("existing user")
+///       .param("id", "1")
+///       .expect_json(json!({ "id": 1, "name": "alice" })),
+///   )
+///   .example(
+///     RouteExample::new("unknown user")
+///       .param("id", "999")
+///       .expect_status(StatusCode::NOT_FOUND),
+///   );
+/// ```
+#[derive(Clone, Debug)]
+pub struct RouteExample {
+  pub(crate) name: String,
+  pub(crate) params: Vec<(String, String)>,
+  pub(crate) query: Option<String>,
+  pub(crate) headers: Vec<(String, String)>,
+  pub(crate) body: Option<ExampleBody>,
+  pub(crate) status: StatusCode,
+  pub(crate) expect_headers: Vec<(String, String)>,
+  pub(crate) expect_body: Option<ExampleBody>,
+}
+
+impl RouteExample {
+  /// Creates an example named `name` that expects `200 OK`.
+  pub fn new(name: impl Into<String>) -> Self {
+    Self {
+      name: name.into(),
+      params: Vec::new(),
+      query: None,
+      headers: Vec::new(),
+      body: None,
+      status: StatusCode::OK,
+      expect_headers: Vec::new(),
+      expect_body: None,
+    }
+  }
+
+  /// The example's name.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// Fills the `{name}` (or `{*name}`) segment of the route path.
+  pub fn param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+    self.params.push((name.into(), value.into()));
+    self
+  }
+
+  /// Sets the raw query string, without the leading `?`.
+  pub fn query(mut self, query: impl Into<String>) -> Self {
+    self.query = Some(query.into());
+    self
+  }
+
+  /// Adds a request header.
+  pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+    self.headers.push((name.into(), value.into()));
+    self
+  }
+
+  /// Sends `body` as JSON, with `Content-Type: application/json` unless a
+  /// content type header is set explicitly.
+  pub fn json(mut self, body: Value) -> Self {
+    self.body = Some(ExampleBody::Json(body));
+    self
+  }
+
+  /// Sends `body` as plain text, with `Content-Type: text/plain;
+  /// charset=utf-8` unless a content type header is set explicitly.
+  pub fn text(mut self, body: impl Into<String>) -> Self {
+    self.body = Some(ExampleBody::Text(body.into()));
+    self
+  }
+
+  /// Expected response status (default `200 OK`).
+  pub fn expect_status(mut self, status: StatusCode) -> Self {
+    self.status = status;
+    self
+  }
+
+  /// Expects a response header with exactly this value.
+  pub fn expect_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+    self.expect_headers.push((name.into(), value.into()));
+    self
+  }
+
+  /// Expects a JSON response body containing `body`.
+  ///
+  /// Objects match when every expected field matches; extra fields in the
+  /// response are ignored, so generated ids or timestamps can be left out.
+  /// Arrays and scalars must match exactly.
+  pub fn expect_json(mut self, body: Value) -> Self {
+    self.expect_body = Some(ExampleBody::Json(body));
+    self
+  }
+
+  /// Expects exactly this response body.
+  pub fn expect_text(mut self, body: impl Into<String>) -> Self {
+    self.expect_body = Some(ExampleBody::Text(body.into()));
+    self
+  }
+
+  /// The request body, if any.
+  pub fn request_body(&self) -> Option<&ExampleBody> {
+    self.body.as_ref()
+  }
+
+  /// The expected status.
+  pub fn status(&self) -> StatusCode {
+    self.status
+  }
+
+  /// The expected response body, if any.
+  pub fn response_body(&self) -> Option<&ExampleBody> {
+    self.expect_body.as_ref()
+  }
+
+  /// The request URI for a route registered at `pattern`, or the name of the
+  /// first path parameter the example leaves unfilled.
+  pub(crate) fn uri(&self, pattern: &str) -> Result<String, String> {
+    let mut uri = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+      let Some(close) = rest[open..].find('}') else {
+        break;
+      };
+      uri.push_str(&rest[..open]);
+      let name = rest[open + 1..open + close].trim_start_matches('*');
+      let value = self
+        .params
+        .iter()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v)
+        .ok_or_else(|| name.to_string())?;
+      uri.push_str(value);
+      rest = &rest[open + close + 1..];
+    }
+    uri.push_str(rest);
+    if let Some(query) = &self.query {
+      uri.push('?');
+      uri.push_str(query);
+    }
+    Ok(uri)
+  }
+
+  /// Compares a response with the expectations, returning one line per
+  /// mismatch.
+  pub(crate) fn mismatches(&self, parts: &http::response::Parts, body: &[u8]) -> Vec<String> {
+    let mut problems = Vec::new();
+    if parts.status != self.status {
+      problems.push(format!(
+        "status: expected {}, got {}",
+        self.status.as_u16(),
+        parts.status.as_u16()
+      ));
+    }
+    for (name, expected) in &self.expect_headers {
+      match parts.headers.get(name.as_str()).map(|v| v.to_str()) {
+        Some(Ok(actual)) if actual == expected => {}
+        Some(Ok(actual)) => {
+          problems.push(format!(
+            "header {name}: expected `{expected}`, got `{actual}`"
+          ));
+        }
+        Some(Err(_)) => problems.push(format!("header {name}: not valid UTF-8")),
+        None => problems.push(format!("header {name}: missing")),
+      }
+    }
+    match &self.expect_body {
+      None => {}
+      Some(ExampleBody::Text(expected)) => {
+        let actual = String::from_utf8_lossy(body);
+        if actual != expected.as_str() {
+          problems.push(format!(
+            "body: expected `{}`, got `{}`",
+            clip(expected),
+            clip(&actual)
+          ));
+        }
+      }
+      Some(ExampleBody::Json(expected)) => match serde_json::from_slice::<Value>(body) {
+        Ok(actual) => json_mismatches(expected, &actual, "$", &mut problems),
+        Err(e) => problems.push(format!("body: not JSON ({e})")),
+      },
+    }
+    problems
+  }
+}
+
+fn clip(s: &str) -> String {
+  const MAX: usize = 200;
+  match s.char_indices().nth(MAX) {
+    Some((i, _)) => format!("{}…", &s[..i]),
+    None => s.to_string(),
+  }
+}
+
+fn json_mismatches(expected: &Value, actual: &Value, at: &str, out: &mut Vec<String>) {
+  match (expected, actual) {
+    (Value::Object(want), Value::Object(got)) => {
+      for (key, want) in want {
+        let mut path = at.to_string();
+        let _ = write!(path, ".{key}");
+        match got.get(key) {
+          Some(got) => json_mismatches(want, got, &path, out),
+          None => out.push(format!("body {path}: missing")),
+        }
+      }
+    }
+    _ if expected == actual => {}
+    _ => out.push(format!(
+      "body {at}: expected {}, got {}",
+      clip(&expected.to_string()),
+      clip(&actual.to_string())
+    )),
+  }
+}
+
+impl Route {
+  /// Attaches an example call of this route.
+  ///
+  /// Examples are checked by
+  /// [`Router::run_smoke_tests`](crate::router::Router::run_smoke_tests)
+  /// and, with an `OpenAPI` backend enabled, published in the route's
+  /// documentation.
+  pub fn example(&self, example: RouteExample) -> &Self {
+    #[cfg(any(feature = "utoipa", feature = "vespera"))]
+    {
+      let mut guard = self.openapi.write();
+      let openapi = guard.get_or_insert_with(crate::openapi::RouteOpenApi::default);
+      openapi.examples.push(example.clone());
+    }
+    self.examples.write().push(example);
+    self
+  }
+
+  /// The examples attached to this route.
+  pub fn examples(&self) -> Vec<RouteExample> {
+    self.examples.read().clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn fills_path_params_and_query() {
+    let ex = RouteExample::new("x")
+      .param("id", "7")
+      .param("rest", "a/b")
+      .query("full=1");
+    assert_eq!(
+      ex.uri("/users/{id}/files/{*rest}").unwrap(),
+      "/users/7/files/a/b?full=1"
+    );
+    assert_eq!(
+      RouteExample::new("x").uri("/users/{id}"),
+      Err("id".to_string())
+    );
+  }
+
+  #[test]
+  fn json_expectations_ignore_extra_fields() {
+    let mut out = Vec::new();
+    json_mismatches(
+      &json!({ "name": "alice", "tags": ["a"] }),
+      &json!({ "id": 1, "name": "alice", "tags": ["a"] }),
+      "$",
+      &mut out,
+    );
+    assert!(out.is_empty());
+
+    json_mismatches(
+      &json!({ "name": "alice", "tags": ["a"], "role": "admin" }),
+      &json!({ "name": "bob", "tags": ["a", "b"] }),
+      "$",
+      &mut out,
+    );
+    assert_eq!(
+      out,
+      [
+        "body $.name: expected \"alice\", got \"bob\"",
+        "body $.role: missing",
+        "body $.tags: expected [\"a\"], got [\"a\",\"b\"]",
+      ]
+    );
+  }
+}
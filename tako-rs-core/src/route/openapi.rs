@@ -1,10 +1,12 @@
 //! `OpenAPI` metadata attachment for a route.
 //!
 //! The chainable builder methods that record `OpenAPI` documentation
-//! (operation id, summary, description, tags, deprecation, responses,
-//! parameters, request body, security) onto the route's `RouteOpenApi`
-//! store, plus the accessor that reads it back. Compiled only when an
-//! `OpenAPI` backend feature is enabled.
+//! (operation id, summary, description, tags, responses, parameters,
+//! request body, security) onto the route's `RouteOpenApi`
+//! store, plus the accessor that reads it back. Registration seeds the store
+//! with what the handler's extractors describe (see
+//! [`FromRequest::describe_openapi`](crate::extractors::FromRequest::describe_openapi)).
+//! Compiled only when an `OpenAPI` backend feature is enabled.
 
 #![cfg(any(feature = "utoipa", feature = "vespera"))]
 
@@ -81,23 +83,6 @@ impl Route {
     self
   }
 
-  /// Marks this route as deprecated in `OpenAPI` documentation.
-  ///
-  /// # Examples
-  ///
-  /// ```rust,ignore
-  /// router.route(Method::GET, "/v1/users", list_users_v1)
-  ///     .deprecated();
-  /// ```
-  #[cfg(any(feature = "utoipa", feature = "vespera"))]
-  #[cfg_attr(docsrs, doc(cfg(any(feature = "utoipa", feature = "vespera"))))]
-  pub fn deprecated(&self) -> &Self {
-    let mut guard = self.openapi.write();
-    let openapi = guard.get_or_insert_with(RouteOpenApi::default);
-    openapi.deprecated = true;
-    self
-  }
-
   /// Adds a response description for a status code in `OpenAPI` documentation.
   ///
   /// # Examples
@@ -118,6 +103,9 @@ impl Route {
 
   /// Adds a parameter definition for this route in `OpenAPI` documentation.
   ///
+  /// Replaces a parameter with the same name and location, such as one
+  /// described by the handler's extractors.
+  ///
   /// # Examples
   ///
   /// ```rust,ignore
@@ -129,6 +117,7 @@ impl Route {
   ///         location: ParameterLocation::Query,
   ///         description: Some("Maximum number of results".to_string()),
   ///         required: false,
+  ///         kind: Some("integer".to_string()),
   ///     });
   /// ```
   #[cfg(any(feature = "utoipa", feature = "vespera"))]
@@ -136,7 +125,7 @@ impl Route {
   pub fn parameter(&self, param: crate::openapi::OpenApiParameter) -> &Self {
     let mut guard = self.openapi.write();
     let openapi = guard.get_or_insert_with(RouteOpenApi::default);
-    openapi.parameters.push(param);
+    openapi.add_parameter(param);
     self
   }
 
@@ -163,6 +152,27 @@ impl Route {
     self
   }
 
+  /// Describes the request body as JSON with the schema of `T`.
+  ///
+  /// Replaces the body derived from a `Json<T>` extractor with one that also
+  /// carries the doc-comment descriptions from `T`'s
+  /// [`JsonSchema`](schemars::JsonSchema) impl.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,ignore
+  /// router.route(Method::POST, "/users", create_user)
+  ///     .request_schema::<CreateUser>();
+  /// ```
+  #[cfg(feature = "schemars")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "schemars")))]
+  pub fn request_schema<T: schemars::JsonSchema>(&self) -> &Self {
+    let mut guard = self.openapi.write();
+    let openapi = guard.get_or_insert_with(RouteOpenApi::default);
+    openapi.set_schema_request_body::<T>("application/json");
+    self
+  }
+
   /// Adds a security requirement for this route in `OpenAPI` documentation.
   ///
   /// # Examples
@@ -180,6 +190,26 @@ impl Route {
     self
   }
 
+  /// Seeds the metadata with what the handler's extractors describe.
+  pub(crate) fn describe_handler<H, T>(&self)
+  where
+    H: crate::handler::Handler<T>,
+  {
+    let mut described = RouteOpenApi::default();
+    H::describe_openapi(&mut described);
+    if described.parameters.is_empty() && described.request_body.is_none() {
+      return;
+    }
+    let mut guard = self.openapi.write();
+    let openapi = guard.get_or_insert_with(RouteOpenApi::default);
+    for param in described.parameters {
+      openapi.add_parameter(param);
+    }
+    if described.request_body.is_some() {
+      openapi.request_body = described.request_body;
+    }
+  }
+
   /// Returns a clone of the `OpenAPI` metadata for this route, if any.
   #[cfg(any(feature = "utoipa", feature = "vespera"))]
   #[cfg_attr(docsrs, doc(cfg(any(feature = "utoipa", feature = "vespera"))))]
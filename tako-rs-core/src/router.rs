@@ -30,15 +30,31 @@
 //! ```
 
 mod definition;
+mod diff;
 mod dispatch;
 mod layers;
 mod method_map;
 mod mounting;
 mod plugins;
 mod registration;
+mod smoke;
 mod state;
+mod static_response;
 mod timeout;
 
 pub use definition::Router;
+pub use diff::ReloadVetoed;
+pub use diff::RouteChange;
+pub use diff::RouteDiff;
+pub use layers::AllowedMethods;
 pub use layers::ErrorHandler;
+pub use layers::ErrorRenderer;
 pub use mounting::TAKO_ROUTES;
+pub use plugins::PRINT_ROUTES_ENV;
+pub use plugins::ROUTE_LINE_PREFIX;
+#[cfg(feature = "plugins")]
+pub(crate) use plugins::by_priority;
+pub use registration::PathRoutes;
+pub use smoke::SmokeFailure;
+pub use smoke::SmokeReport;
+pub(crate) use static_response::StaticResponse;
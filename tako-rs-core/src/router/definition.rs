@@ -3,14 +3,20 @@
 use std::sync::Arc;
 use std::sync::Weak;
 use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicI32;
 use std::time::Duration;
 
 use arc_swap::ArcSwap;
 
 use super::ErrorHandler;
+use super::ErrorRenderer;
 use super::method_map::MethodMap;
+#[cfg(feature = "plugins")]
+use super::plugins::by_priority;
 use crate::handler::BoxHandler;
 #[cfg(feature = "plugins")]
+use crate::plugins::PluginStatus;
+#[cfg(feature = "plugins")]
 use crate::plugins::TakoPlugin;
 use crate::route::Route;
 use crate::router_state::RouterState;
@@ -18,6 +24,21 @@ use crate::router_state::RouterState;
 use crate::signals::SignalArbiter;
 use crate::types::BoxMiddleware;
 
+/// A nested child router's router-level plugins and the routes it
+/// contributed.
+///
+/// The plugins are set up against a private router when the parent runs
+/// [`Router::setup_plugins_once`]; the middleware they install is spliced
+/// into each route's chain at `offset`, right after the global middleware of
+/// the enclosing routers, which is where the child's own dispatch would have
+/// run it.
+#[cfg(feature = "plugins")]
+pub(crate) struct NestedPlugins {
+  pub(crate) plugins: Vec<Box<dyn TakoPlugin>>,
+  pub(crate) routes: Vec<Weak<Route>>,
+  pub(crate) offset: usize,
+}
+
 /// HTTP router for managing routes, middleware, and request dispatching.
 ///
 /// The `Router` is the central component for routing HTTP requests to appropriate
@@ -61,18 +82,40 @@ pub struct Router {
   /// Used by [`Router::mount_all_into`] and [`Router::scope`] (see v2 roadmap).
   /// Only consulted at registration time — zero cost on the dispatch hot path.
   pub(crate) pending_prefix: Option<String>,
+  /// Middleware registered inside the innermost active [`Router::scope`],
+  /// with its priority. Applied to the scope's routes when the scope closes.
+  pub(crate) scope_middlewares: parking_lot::Mutex<Option<Vec<(i32, BoxMiddleware)>>>,
   /// Global middleware chain applied to all routes.
   pub(crate) middlewares: ArcSwap<Vec<BoxMiddleware>>,
+  /// Priority of each entry in `middlewares`, kept sorted; the lock also
+  /// serializes inserts.
+  pub(crate) middleware_priorities: parking_lot::Mutex<Vec<i32>>,
+  /// Priority given to [`Router::middleware`] registrations; set to a
+  /// plugin's priority while that plugin runs its setup.
+  pub(crate) registration_priority: AtomicI32,
   /// Fast check: true when global middleware is registered (avoids `ArcSwap` load on hot path).
   pub(crate) has_global_middleware: AtomicBool,
   /// Optional fallback handler executed when no route matches.
   pub(crate) fallback: Option<BoxHandler>,
+  /// Optional handler for paths registered under other methods only.
+  pub(crate) method_not_allowed: Option<BoxHandler>,
   /// Registered plugins for extending functionality.
   #[cfg(feature = "plugins")]
   pub(crate) plugins: Vec<Box<dyn TakoPlugin>>,
   /// Flag to ensure plugins are initialized only once.
   #[cfg(feature = "plugins")]
   pub(crate) plugins_initialized: AtomicBool,
+  /// Router-level plugins carried over from child routers by
+  /// [`Router::nest`], innermost child first.
+  #[cfg(feature = "plugins")]
+  pub(crate) nested_plugins: Vec<NestedPlugins>,
+  /// Setup outcome of every router-level and nested plugin, filled by
+  /// [`Router::setup_plugins_once`].
+  #[cfg(feature = "plugins")]
+  pub(crate) plugin_status: parking_lot::Mutex<Vec<PluginStatus>>,
+  /// Whether transports refuse to start when a plugin failed to set up.
+  #[cfg(feature = "plugins")]
+  pub(crate) fail_on_plugin_error: bool,
   /// Signal arbiter for in-process event emission and handling.
   #[cfg(feature = "signals")]
   pub(crate) signals: SignalArbiter,
@@ -84,6 +127,9 @@ pub struct Router {
   pub(crate) error_handler: Option<ErrorHandler>,
   /// Global error handler for 4xx responses (opt-in; runs after dispatch).
   pub(crate) client_error_handler: Option<ErrorHandler>,
+  /// Renders responses carrying a [`crate::error::Error`]; runs before the
+  /// status-based error handlers.
+  pub(crate) error_renderer: Option<ErrorRenderer>,
   /// Per-router typed state populated via [`Router::with_state`].
   /// `Arc` is shared with every dispatched request via the request extension
   /// so the `State<T>` extractor can read instance-local values.
@@ -93,6 +139,50 @@ pub struct Router {
   pub(crate) has_router_state: AtomicBool,
 }
 
+#[cfg(feature = "plugins")]
+impl NestedPlugins {
+  pub(crate) fn setup(&self, status: &mut Vec<PluginStatus>) {
+    use std::sync::atomic::Ordering;
+
+    let private = Router::new();
+    for plugin in by_priority(&self.plugins) {
+      let result = private.setup_plugin(plugin);
+      if let Err(e) = &result {
+        tracing::error!(
+          plugin = plugin.name(),
+          error = %e,
+          "nested router-level TakoPlugin::setup failed; plugin not active"
+        );
+      }
+      status.push(PluginStatus {
+        name: plugin.name(),
+        scope: crate::plugins::PluginScope::Nested,
+        error: result.err().map(|e| e.to_string()),
+      });
+    }
+    let added = private.middlewares.load_full();
+    if added.is_empty() {
+      return;
+    }
+    for weak in &self.routes {
+      let Some(route) = weak.upgrade() else {
+        continue;
+      };
+      let existing = route.middlewares.load_full();
+      let at = self.offset.min(existing.len());
+      let mut merged = Vec::with_capacity(existing.len() + added.len());
+      merged.extend(existing[..at].iter().cloned());
+      merged.extend(added.iter().cloned());
+      merged.extend(existing[at..].iter().cloned());
+      route.has_middleware.store(true, Ordering::Release);
+      route.middlewares.store(Arc::new(merged));
+      route
+        .inherited_middleware
+        .fetch_add(added.len(), Ordering::AcqRel);
+    }
+  }
+}
+
 impl Default for Router {
   #[inline]
   fn default() -> Self {
@@ -108,19 +198,30 @@ impl Router {
       inner: MethodMap::new(),
       routes: MethodMap::new(),
       pending_prefix: None,
+      scope_middlewares: parking_lot::Mutex::new(None),
       middlewares: ArcSwap::new(Arc::default()),
+      middleware_priorities: parking_lot::Mutex::new(Vec::new()),
+      registration_priority: AtomicI32::new(crate::middleware::priority::DEFAULT),
       has_global_middleware: AtomicBool::new(false),
       fallback: None,
+      method_not_allowed: None,
       #[cfg(feature = "plugins")]
       plugins: Vec::new(),
       #[cfg(feature = "plugins")]
       plugins_initialized: AtomicBool::new(false),
+      #[cfg(feature = "plugins")]
+      nested_plugins: Vec::new(),
+      #[cfg(feature = "plugins")]
+      plugin_status: parking_lot::Mutex::new(Vec::new()),
+      #[cfg(feature = "plugins")]
+      fail_on_plugin_error: false,
       #[cfg(feature = "signals")]
       signals: SignalArbiter::new(),
       timeout: None,
       timeout_fallback: None,
       error_handler: None,
       client_error_handler: None,
+      error_renderer: None,
       router_state: Arc::new(RouterState::new()),
       has_router_state: AtomicBool::new(false),
     };
@@ -0,0 +1,259 @@
+//! Route table diffs for validating a hot reload before the swap.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use http::Method;
+
+use super::Router;
+
+/// One route whose configuration differs between two routers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteChange {
+  /// HTTP method.
+  pub method: Method,
+  /// Registered path pattern.
+  pub path: String,
+  /// What changed, e.g. `middleware 1 -> 2` or `timeout none -> 5s`.
+  pub details: Vec<String>,
+}
+
+/// Differences between an old and a new route table.
+///
+/// Handlers and middleware are closures and cannot be compared, so a route
+/// only counts as changed when something observable about it differs: its
+/// number of middleware, timeout, body limit, or whether it serves a static
+/// response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteDiff {
+  /// Routes only the new router has.
+  pub added: Vec<(Method, String)>,
+  /// Routes only the old router has.
+  pub removed: Vec<(Method, String)>,
+  /// Routes both have, configured differently.
+  pub changed: Vec<RouteChange>,
+  /// Router-wide middleware count, old and new, when it changed.
+  pub middleware: Option<(usize, usize)>,
+}
+
+impl RouteDiff {
+  /// Whether the two route tables are equivalent.
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty()
+      && self.removed.is_empty()
+      && self.changed.is_empty()
+      && self.middleware.is_none()
+  }
+}
+
+impl fmt::Display for RouteDiff {
+  /// One line per difference: `+ GET /new`, `- GET /old`,
+  /// `~ GET /users: timeout none -> 5s`.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.is_empty() {
+      return f.write_str("no route changes");
+    }
+    let mut lines = Vec::new();
+    if let Some((old, new)) = self.middleware {
+      lines.push(format!("~ router middleware {old} -> {new}"));
+    }
+    lines.extend(self.added.iter().map(|(m, p)| format!("+ {m} {p}")));
+    lines.extend(self.removed.iter().map(|(m, p)| format!("- {m} {p}")));
+    lines.extend(
+      self
+        .changed
+        .iter()
+        .map(|c| format!("~ {} {}: {}", c.method, c.path, c.details.join(", "))),
+    );
+    f.write_str(&lines.join("\n"))
+  }
+}
+
+/// A reload refused because it would drop routes that were not expected to
+/// go away.
+#[derive(Debug, Clone)]
+pub struct ReloadVetoed {
+  /// The full diff of the refused reload.
+  pub diff: RouteDiff,
+  /// The removed routes that were not allowed.
+  pub unexpected: Vec<(Method, String)>,
+}
+
+impl fmt::Display for ReloadVetoed {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let routes: Vec<String> = self
+      .unexpected
+      .iter()
+      .map(|(m, p)| format!("{m} {p}"))
+      .collect();
+    write!(f, "reload would remove routes: {}", routes.join(", "))
+  }
+}
+
+impl std::error::Error for ReloadVetoed {}
+
+/// The comparable parts of one route.
+#[derive(PartialEq, Eq)]
+struct RouteShape {
+  middleware: usize,
+  timeout: Option<Duration>,
+  body_limit: Option<usize>,
+  is_static: bool,
+}
+
+impl RouteShape {
+  fn changes(&self, next: &Self) -> Vec<String> {
+    let mut details = Vec::new();
+    if self.middleware != next.middleware {
+      details.push(format!(
+        "middleware {} -> {}",
+        self.middleware, next.middleware
+      ));
+    }
+    if self.timeout != next.timeout {
+      details.push(format!(
+        "timeout {} -> {}",
+        show(self.timeout.map(|t| format!("{t:?}"))),
+        show(next.timeout.map(|t| format!("{t:?}")))
+      ));
+    }
+    if self.body_limit != next.body_limit {
+      details.push(format!(
+        "body limit {} -> {}",
+        show(self.body_limit.map(|b| b.to_string())),
+        show(next.body_limit.map(|b| b.to_string()))
+      ));
+    }
+    if self.is_static != next.is_static {
+      details.push(if next.is_static {
+        "now a static response".to_string()
+      } else {
+        "no longer a static response".to_string()
+      });
+    }
+    details
+  }
+}
+
+fn show(value: Option<String>) -> String {
+  value.unwrap_or_else(|| "none".to_string())
+}
+
+impl Router {
+  /// Compares this router's routes with `next`'s.
+  pub fn diff(&self, next: &Router) -> RouteDiff {
+    let old = self.route_shapes();
+    let new = next.route_shapes();
+    let mut diff = RouteDiff::default();
+
+    for (key, (method, shape)) in &old {
+      match new.get(key) {
+        None => diff.removed.push((method.clone(), key.0.clone())),
+        Some((_, next_shape)) if next_shape != shape => diff.changed.push(RouteChange {
+          method: method.clone(),
+          path: key.0.clone(),
+          details: shape.changes(next_shape),
+        }),
+        Some(_) => {}
+      }
+    }
+    diff.added = new
+      .iter()
+      .filter(|(key, _)| !old.contains_key(*key))
+      .map(|(key, (method, _))| (method.clone(), key.0.clone()))
+      .collect();
+
+    let (old_mw, new_mw) = (self.middlewares.load().len(), next.middlewares.load().len());
+    if old_mw != new_mw {
+      diff.middleware = Some((old_mw, new_mw));
+    }
+    diff
+  }
+
+  /// Validates replacing this router with `next` before the swap.
+  ///
+  /// Every removed route must be listed in `expected_removals`; otherwise
+  /// the reload is vetoed. Either way the diff is logged and, with the
+  /// `signals` feature, emitted as
+  /// [`ROUTER_HOT_RELOAD`](crate::signals::ids::ROUTER_HOT_RELOAD) on the
+  /// application arbiter, with `added`, `removed` and `changed` counts, a
+  /// `vetoed` flag and the rendered `diff`.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`ReloadVetoed`] when the reload would drop an unexpected route.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,ignore
+  /// let next = build_router(&new_config);
+  /// match current.check_reload(&next, &[(Method::GET, "/legacy")]).await {
+  ///     Ok(_) => swap.store(Arc::new(next)),
+  ///     Err(vetoed) => tracing::error!("keeping the old routes: {vetoed}"),
+  /// }
+  /// ```
+  pub async fn check_reload(
+    &self,
+    next: &Router,
+    expected_removals: &[(Method, &str)],
+  ) -> Result<RouteDiff, ReloadVetoed> {
+    let diff = self.diff(next);
+    let unexpected: Vec<(Method, String)> = diff
+      .removed
+      .iter()
+      .filter(|(method, path)| {
+        !expected_removals
+          .iter()
+          .any(|(m, p)| m == method && p == path)
+      })
+      .cloned()
+      .collect();
+    let vetoed = !unexpected.is_empty();
+
+    if vetoed {
+      tracing::warn!("route reload vetoed:\n{diff}");
+    } else {
+      tracing::info!("route reload:\n{diff}");
+    }
+
+    #[cfg(feature = "signals")]
+    crate::signals::app_signals()
+      .emit(
+        crate::signals::Signal::new(crate::signals::ids::ROUTER_HOT_RELOAD)
+          .meta("added", diff.added.len().to_string())
+          .meta("removed", diff.removed.len().to_string())
+          .meta("changed", diff.changed.len().to_string())
+          .meta("vetoed", vetoed.to_string())
+          .meta("diff", diff.to_string()),
+      )
+      .await;
+
+    if vetoed {
+      Err(ReloadVetoed { diff, unexpected })
+    } else {
+      Ok(diff)
+    }
+  }
+
+  /// Every live route keyed by `(path, method)`, so iteration follows
+  /// [`Router::route_table`] order.
+  fn route_shapes(&self) -> BTreeMap<(String, String), (Method, RouteShape)> {
+    let mut shapes = BTreeMap::new();
+    for (method, weak_vec) in self.routes.iter() {
+      for route in weak_vec.iter().filter_map(std::sync::Weak::upgrade) {
+        let shape = RouteShape {
+          middleware: route.middlewares.load().len(),
+          timeout: route.timeout.get().copied(),
+          body_limit: route.body_limit.get().copied(),
+          is_static: route.static_response.get().is_some(),
+        };
+        shapes.insert(
+          (route.path.clone(), method.to_string()),
+          (method.clone(), shape),
+        );
+      }
+    }
+    shapes
+  }
+}
@@ -130,10 +130,24 @@ impl Router {
           req.extensions_mut().insert(mode);
         }
 
+        // Inject the route-level body limit so `BodyLimit` and the body
+        // extractors can honor it
+        if let Some(limit) = route.get_body_limit() {
+          req
+            .extensions_mut()
+            .insert(crate::extractors::body_limit::RouteBodyLimit(limit));
+        }
+
         if let Some(params) = params {
           req.extensions_mut().insert(params);
         }
 
+        // Routes mounted from a child router carry its state, which chains
+        // up to this router's.
+        if let Some(state) = route.state.get() {
+          req.extensions_mut().insert(Arc::clone(state));
+        }
+
         // Inject the matched route template (e.g. `/users/{id}`) so handlers
         // and middleware can label metrics/logs by the routing key, not the
         // concrete URI.
@@ -168,13 +182,13 @@ impl Router {
             .await;
 
           let response = if !needs_chain && effective_timeout.is_none() {
-            route.handler.call(req).await
+            Self::call_endpoint(&route, req).await
           } else {
             let next = Next {
               global_middlewares: self.middlewares.load_full(),
               route_middlewares: route.middlewares.load_full(),
               index: 0,
-              endpoint: route.handler.clone(),
+              endpoint: route.endpoint().clone(),
             };
             self.run_with_timeout(req, next, effective_timeout).await
           };
@@ -195,13 +209,13 @@ impl Router {
         #[cfg(not(feature = "signals"))]
         {
           if !needs_chain && effective_timeout.is_none() {
-            route.handler.call(req).await
+            Self::call_endpoint(&route, req).await
           } else {
             let next = Next {
               global_middlewares: self.middlewares.load_full(),
               route_middlewares: route.middlewares.load_full(),
               index: 0,
-              endpoint: route.handler.clone(),
+              endpoint: route.endpoint().clone(),
             };
             self.run_with_timeout(req, next, effective_timeout).await
           }
@@ -251,7 +265,23 @@ impl Router {
         // than 404. This is the cold path; iterating the 9 standard methods
         // is cheap.
         let allowed = self.collect_allowed_methods(req.uri().path());
-        if !allowed.is_empty() {
+        if !allowed.is_empty()
+          && let Some(handler) = &self.method_not_allowed
+        {
+          let allow_value = http::HeaderValue::from_str(&join_methods(&allowed)).ok();
+          req
+            .extensions_mut()
+            .insert(super::AllowedMethods(allowed.to_vec()));
+          let mut resp = self
+            .run_with_global_middlewares_for_endpoint(req, handler.clone())
+            .await;
+          if let Some(v) = allow_value
+            && !resp.headers().contains_key(http::header::ALLOW)
+          {
+            resp.headers_mut().insert(http::header::ALLOW, v);
+          }
+          resp
+        } else if !allowed.is_empty() {
           let allow_value = join_methods(&allowed);
           let handler = move |_req: Request| {
             let allow_value = allow_value.clone();
@@ -307,10 +337,35 @@ impl Router {
     response
   }
 
+  /// Calls the route's endpoint directly, copying a
+  /// [`Router::static_response`] instead when the route has one.
+  #[inline]
+  async fn call_endpoint(route: &Route, req: Request) -> Response {
+    match route.static_response.get() {
+      Some(response) => response.build(),
+      None => route.endpoint().call(req).await,
+    }
+  }
+
   /// Applies the appropriate error handler if one is set:
+  /// - tagged with [`Error`](crate::error::Error) → [`Router::error_renderer`]
   /// - 5xx → [`Router::error_handler`]
   /// - 4xx → [`Router::client_error_handler`]
   fn maybe_apply_error_handler(&self, response: Response) -> Response {
+    if let Some(renderer) = &self.error_renderer
+      && let Some(error) = response.extensions().get::<crate::error::Error>()
+      && let Some(mut rendered) = renderer(error)
+    {
+      if let Some(error) = response
+        .into_parts()
+        .0
+        .extensions
+        .remove::<crate::error::Error>()
+      {
+        rendered.extensions_mut().insert(error);
+      }
+      return rendered;
+    }
     let status = response.status();
     if status.is_server_error() {
       if let Some(handler) = &self.error_handler {
@@ -4,6 +4,8 @@ use std::sync::Arc;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
 
+use futures_util::future::BoxFuture;
+
 use super::Router;
 use crate::handler::BoxHandler;
 use crate::handler::Handler;
@@ -19,12 +21,29 @@ use crate::types::Response;
 /// response and can transform it (e.g., to return JSON errors instead of plain text).
 pub type ErrorHandler = Arc<dyn Fn(Response) -> Response + Send + Sync + 'static>;
 
+/// Type alias for a renderer of [`Error`](crate::error::Error)-tagged responses.
+///
+/// Returning `None` keeps the response as rendered by the error itself.
+pub type ErrorRenderer =
+  Arc<dyn Fn(&crate::error::Error) -> Option<Response> + Send + Sync + 'static>;
+
+/// Methods registered for the requested path, inserted into the request
+/// extensions before a [`Router::method_not_allowed`] handler runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowedMethods(pub Vec<http::Method>);
+
 impl Router {
   /// Adds global middleware to the router.
   ///
   /// Global middleware is executed for all routes in the order it was added,
   /// before any route-specific middleware. Middleware can modify requests,
   /// generate responses, or perform side effects like logging or authentication.
+  /// It gets [`priority::DEFAULT`](crate::middleware::priority::DEFAULT), or
+  /// the plugin's priority when registered from [`TakoPlugin::setup`]; use
+  /// [`Router::middleware_with_priority`] to place it elsewhere in the chain.
+  /// Inside [`Router::scope`] it applies to the scope's routes only.
+  ///
+  /// [`TakoPlugin::setup`]: crate::plugins::TakoPlugin::setup
   ///
   /// # Examples
   ///
@@ -51,6 +70,34 @@ impl Router {
   /// });
   /// ```
   pub fn middleware<F, Fut, R>(&self, f: F) -> &Self
+  where
+    F: Fn(Request, Next) -> Fut + Clone + Send + Sync + 'static,
+    Fut: std::future::Future<Output = R> + Send + 'static,
+    R: Responder + Send + 'static,
+  {
+    self.middleware_with_priority(self.registration_priority.load(Ordering::Relaxed), f)
+  }
+
+  /// Adds global middleware at an explicit position in the chain.
+  ///
+  /// The global chain is ordered by ascending priority: lower values run
+  /// earlier on the way in and later on the way out. Equal priorities keep
+  /// registration order. See [`crate::middleware::priority`] for the values
+  /// the built-in plugins use.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use tako::{router::Router, middleware::priority};
+  ///
+  /// let router = Router::new();
+  /// router.middleware(|req, next| async move { next.run(req).await });
+  /// // Runs before the middleware above despite being added later.
+  /// router.middleware_with_priority(priority::FIRST, |req, next| async move {
+  ///     next.run(req).await
+  /// });
+  /// ```
+  pub fn middleware_with_priority<F, Fut, R>(&self, priority: i32, f: F) -> &Self
   where
     F: Fn(Request, Next) -> Fut + Clone + Send + Sync + 'static,
     Fut: std::future::Future<Output = R> + Send + 'static,
@@ -61,19 +108,74 @@ impl Router {
       Box::pin(async move { fut.await.into_response() })
     });
 
-    // RCU-style append: rebuild the Vec atomically against concurrent pushers.
-    // ArcSwap retries the closure on CAS conflict, so concurrent middleware
-    // registrations cannot lose entries.
+    // Inside `Router::scope` the middleware belongs to the scope's routes.
+    if let Some(scoped) = self.scope_middlewares.lock().as_mut() {
+      let at = scoped.partition_point(|(p, _)| *p <= priority);
+      scoped.insert(at, (priority, mw));
+      return self;
+    }
+
+    // The priority lock serializes registrations, so the index computed here
+    // still matches the chain when the RCU below rebuilds it.
+    let mut priorities = self.middleware_priorities.lock();
+    let at = priorities.partition_point(|p| *p <= priority);
+    priorities.insert(at, priority);
     self.middlewares.rcu(move |current| {
       let mut next = Vec::with_capacity(current.len() + 1);
       next.extend(current.iter().cloned());
-      next.push(mw.clone());
+      next.insert(at, mw.clone());
       Arc::new(next)
     });
+    drop(priorities);
     self.has_global_middleware.store(true, Ordering::Release);
     self
   }
 
+  /// Adds global middleware that only runs for requests matching `predicate`.
+  ///
+  /// Requests for which the predicate returns `false` skip straight to the
+  /// rest of the chain, so one router can, say, exempt `/healthz` from
+  /// authentication or keep compression off a streaming endpoint. The
+  /// predicate sees the request before the middleware does. Placement in the
+  /// chain follows the same rules as [`Router::middleware`].
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use tako::{router::Router, responder::Responder};
+  ///
+  /// let router = Router::new();
+  /// router.middleware_when(
+  ///     |req| req.uri().path() != "/healthz",
+  ///     |req, next| async move {
+  ///         if req.headers().contains_key("authorization") {
+  ///             next.run(req).await
+  ///         } else {
+  ///             (http::StatusCode::UNAUTHORIZED, "Unauthorized").into_response()
+  ///         }
+  ///     },
+  /// );
+  /// ```
+  pub fn middleware_when<P, F, Fut, R>(&self, predicate: P, f: F) -> &Self
+  where
+    P: Fn(&Request) -> bool + Send + Sync + 'static,
+    F: Fn(Request, Next) -> Fut + Clone + Send + Sync + 'static,
+    Fut: std::future::Future<Output = R> + Send + 'static,
+    R: Responder + Send + 'static,
+  {
+    let predicate = Arc::new(predicate);
+    self.middleware(
+      move |req: Request, next: Next| -> BoxFuture<'static, Response> {
+        if predicate(&req) {
+          let fut = f(req, next);
+          Box::pin(async move { fut.await.into_response() })
+        } else {
+          Box::pin(next.run(req))
+        }
+      },
+    )
+  }
+
   /// Sets a fallback handler that will be executed when no route matches.
   ///
   /// The fallback runs after global middlewares and can be used to implement
@@ -101,6 +203,36 @@ impl Router {
     self
   }
 
+  /// Sets the handler for requests whose path is registered under other
+  /// methods only.
+  ///
+  /// Without one the router answers with an empty `405 Method Not Allowed`.
+  /// The handler finds the registered methods in the [`AllowedMethods`]
+  /// request extension, runs after global middlewares, and its response
+  /// gets an `Allow` header unless it sets one itself.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use tako::{router::{AllowedMethods, Router}, Method, StatusCode, types::Request};
+  ///
+  /// let mut router = Router::new();
+  /// router.route(Method::GET, "/", |_req| async { "Hello" });
+  /// router.method_not_allowed(|req: Request| async move {
+  ///     let allowed = req.extensions().get::<AllowedMethods>().map_or(0, |m| m.0.len());
+  ///     (StatusCode::METHOD_NOT_ALLOWED, format!("try one of {allowed} methods"))
+  /// });
+  /// ```
+  pub fn method_not_allowed<F, Fut, R>(&mut self, handler: F) -> &mut Self
+  where
+    F: Fn(Request) -> Fut + Clone + Send + Sync + 'static,
+    Fut: std::future::Future<Output = R> + Send + 'static,
+    R: Responder + Send + 'static,
+  {
+    self.method_not_allowed = Some(BoxHandler::new::<F, (Request,)>(handler));
+    self
+  }
+
   /// Sets a fallback handler that supports extractors (like `Path`, `Query`, etc.).
   ///
   /// Use this when your fallback needs to parse request data via extractors. If you
@@ -222,6 +354,39 @@ impl Router {
     self
   }
 
+  /// Sets a renderer for every response that carries a
+  /// [`tako::Error`](crate::error::Error): handler errors, `anyhow::Error`
+  /// returns, and extractor rejections.
+  ///
+  /// Runs after dispatch and before [`Router::error_handler`] /
+  /// [`Router::client_error_handler`]; returning `None` leaves the response
+  /// to them. Use it to give the whole application one error format.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use tako::Error;
+  /// use tako::error::ErrorKind;
+  /// use tako::responder::Responder;
+  /// use tako::router::Router;
+  ///
+  /// let mut router = Router::new();
+  /// router.error_renderer(|err: &Error| {
+  ///     let body = serde_json::json!({ "code": err.kind().as_str(), "detail": err.message() });
+  ///     match err.kind() {
+  ///         ErrorKind::Internal => None,
+  ///         _ => Some((err.status(), body.to_string()).into_response()),
+  ///     }
+  /// });
+  /// ```
+  pub fn error_renderer(
+    &mut self,
+    renderer: impl Fn(&crate::error::Error) -> Option<Response> + Send + Sync + 'static,
+  ) -> &mut Self {
+    self.error_renderer = Some(Arc::new(renderer));
+    self
+  }
+
   /// Convenience: install [`crate::problem::default_problem_responder`] for
   /// both 4xx and 5xx so unhandled errors always render as
   /// `application/problem+json`.
@@ -1,9 +1,17 @@
 //! Router composition: macro mounting, prefix scoping, nesting, and merging.
 
+#[cfg(feature = "plugins")]
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::sync::Weak;
 use std::sync::atomic::Ordering;
 
 use super::Router;
+#[cfg(feature = "plugins")]
+use super::definition::NestedPlugins;
+use crate::route::Route;
+use crate::types::BoxMiddleware;
 
 impl Router {
   /// Registers every route declared via the `#[tako::route]` / `#[tako::get]`
@@ -95,6 +103,13 @@ impl Router {
   /// prepended. Prefixes nest: a `scope("/v1", |r| r.scope("/users", …))`
   /// produces routes under `/v1/users`. Cold path; no dispatch impact.
   ///
+  /// Middleware added with [`Router::middleware`] (or its `_with_priority` /
+  /// `_when` variants) inside the closure applies to every route the scope
+  /// registers — including routes registered before the call and routes of
+  /// nested scopes — and to nothing else. It runs after the router's global
+  /// middleware and before route-level middleware; an outer scope's
+  /// middleware runs before an inner scope's.
+  ///
   /// # Examples
   ///
   /// ```rust
@@ -109,6 +124,18 @@ impl Router {
   ///     r.get("/users", list_users);
   ///     r.post("/users", create_user);
   /// });
+  ///
+  /// // `/admin/*` requires a token; the rest of the router does not.
+  /// router.scope("/admin", |r| {
+  ///     r.middleware(|req, next| async move {
+  ///         if req.headers().contains_key("authorization") {
+  ///             next.run(req).await
+  ///         } else {
+  ///             (http::StatusCode::UNAUTHORIZED, "Unauthorized").into_response()
+  ///         }
+  ///     });
+  ///     r.get("/stats", list_users);
+  /// });
   /// ```
   pub fn scope<F>(&mut self, prefix: &str, build: F) -> &mut Self
   where
@@ -127,15 +154,58 @@ impl Router {
       None => prefix.to_string(),
     };
     self.pending_prefix = Some(new_prefix);
+    let saved_middlewares = self.scope_middlewares.lock().replace(Vec::new());
+    let existing: HashSet<*const Route> = self
+      .routes
+      .iter()
+      .flat_map(|(_, weak_vec)| weak_vec.iter().map(Weak::as_ptr))
+      .collect();
+    #[cfg(feature = "plugins")]
+    let nested_before = self.nested_plugins.len();
     // Panic-safe restore of `pending_prefix`. A route-conflict panic in the
     // user-supplied `build` closure used to leave the temporary nested
     // prefix in place, permanently poisoning subsequent route registrations
     // on the same builder.
     let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| build(self)));
     self.pending_prefix = saved;
+    let scoped = std::mem::replace(&mut *self.scope_middlewares.lock(), saved_middlewares);
     if let Err(payload) = result {
       std::panic::resume_unwind(payload);
     }
+
+    let scoped: Vec<BoxMiddleware> = scoped
+      .unwrap_or_default()
+      .into_iter()
+      .map(|(_, mw)| mw)
+      .collect();
+    if scoped.is_empty() {
+      return self;
+    }
+    for (_, weak_vec) in self.routes.iter() {
+      for weak in weak_vec {
+        if existing.contains(&weak.as_ptr()) {
+          continue;
+        }
+        let Some(route) = weak.upgrade() else {
+          continue;
+        };
+        let current = route.middlewares.load_full();
+        let mut merged = Vec::with_capacity(scoped.len() + current.len());
+        merged.extend(scoped.iter().cloned());
+        merged.extend(current.iter().cloned());
+        route.has_middleware.store(true, Ordering::Release);
+        route.middlewares.store(Arc::new(merged));
+        route
+          .inherited_middleware
+          .fetch_add(scoped.len(), Ordering::AcqRel);
+      }
+    }
+    // Plugins of routers nested inside the scope go after the scope's
+    // middleware, like the rest of those routers' inherited chain.
+    #[cfg(feature = "plugins")]
+    for group in &mut self.nested_plugins[nested_before..] {
+      group.offset += scoped.len();
+    }
     self
   }
 
@@ -148,9 +218,19 @@ impl Router {
   /// each newly-registered route's middleware chain (so child globals run
   /// before child-route middleware at dispatch time).
   ///
-  /// Caveats:
-  /// - Route-level plugins on the child are **not** carried over.
-  /// - The child's fallback / error handlers are **not** inherited.
+  /// Plugins travel with the child: route-level plugins move onto the
+  /// re-homed routes, and the child's router-level plugins are set up when
+  /// this router's plugins are, with the middleware they install applied to
+  /// the child's routes only (right after the child's global middleware).
+  /// A default timeout set on the child becomes the timeout of each of its
+  /// routes that has none of its own. Nesting composes: a child that itself
+  /// nests grandchildren carries their plugins along.
+  ///
+  /// State the child set with [`Router::with_state`] stays visible to its
+  /// routes; types the child does not hold resolve from this router's state.
+  ///
+  /// Caveat: the child's fallback, 405, and error handlers are **not**
+  /// inherited.
   ///
   /// # Panics
   ///
@@ -176,6 +256,9 @@ impl Router {
   /// ```
   pub fn nest(&mut self, prefix: &str, child: Router) -> &mut Self {
     let upstream_globals = child.middlewares.load_full();
+    // Old route → re-homed route, for carrying the child's plugin groups.
+    #[cfg(feature = "plugins")]
+    let mut rehomed: HashMap<*const Route, Weak<Route>> = HashMap::new();
 
     for (method, weak_vec) in child.routes.iter() {
       for weak in weak_vec {
@@ -188,6 +271,34 @@ impl Router {
 
         let new_route = child_route.cloned_with_path(new_path.clone());
 
+        #[cfg(feature = "plugins")]
+        {
+          // The child is consumed, so its route plugins can move instead of
+          // being dropped. Plugins already set up have installed their
+          // middleware, which `cloned_with_path` copied.
+          let moved = std::mem::take(&mut *child_route.plugins.write());
+          if !moved.is_empty() {
+            *new_route.plugins.write() = moved;
+            new_route.plugins_initialized.store(
+              child_route.plugins_initialized.load(Ordering::Acquire),
+              Ordering::Release,
+            );
+          }
+          rehomed.insert(Arc::as_ptr(&child_route), Arc::downgrade(&new_route));
+        }
+
+        if let Some(timeout) = child.timeout
+          && new_route.timeout.get().is_none()
+        {
+          let _ = new_route.timeout.set(timeout);
+        }
+
+        // A route nested from a grandchild keeps the grandchild's state,
+        // which already chains up through the child's.
+        if child.has_router_state.load(Ordering::Acquire) {
+          let _ = new_route.state.set(Arc::clone(&child.router_state));
+        }
+
         if !upstream_globals.is_empty() {
           let existing = new_route.middlewares.load_full();
           let mut merged = Vec::with_capacity(upstream_globals.len() + existing.len());
@@ -195,6 +306,9 @@ impl Router {
           merged.extend(existing.iter().cloned());
           new_route.has_middleware.store(true, Ordering::Release);
           new_route.middlewares.store(Arc::new(merged));
+          new_route
+            .inherited_middleware
+            .fetch_add(upstream_globals.len(), Ordering::AcqRel);
         }
 
         if let Err(err) = self
@@ -211,9 +325,37 @@ impl Router {
       }
     }
 
+    child
+      .router_state
+      .set_parent(Arc::clone(&self.router_state));
+
     #[cfg(feature = "signals")]
     self.signals.merge_from(&child.signals);
 
+    #[cfg(feature = "plugins")]
+    {
+      let remap = |routes: &[Weak<Route>]| -> Vec<Weak<Route>> {
+        routes
+          .iter()
+          .filter_map(|weak| rehomed.get(&weak.as_ptr()).cloned())
+          .collect()
+      };
+      for group in child.nested_plugins {
+        self.nested_plugins.push(NestedPlugins {
+          routes: remap(&group.routes),
+          plugins: group.plugins,
+          offset: group.offset + upstream_globals.len(),
+        });
+      }
+      if !child.plugins.is_empty() {
+        self.nested_plugins.push(NestedPlugins {
+          plugins: child.plugins,
+          routes: rehomed.into_values().collect(),
+          offset: upstream_globals.len(),
+        });
+      }
+    }
+
     self
   }
 
@@ -221,7 +363,8 @@ impl Router {
   ///
   /// This method combines routes and middleware from another router into the
   /// current one. Routes are copied over, and the other router's global middleware
-  /// is prepended to each merged route's middleware chain.
+  /// is prepended to each merged route's middleware chain. The other router's
+  /// typed state stays scoped to its routes, as with [`Router::nest`].
   ///
   /// # Panics
   ///
@@ -268,6 +411,10 @@ impl Router {
           // unrelated middleware insertions otherwise.
           let new_route = child_route.cloned_with_path(child_route.path.clone());
 
+          if other.has_router_state.load(Ordering::Acquire) {
+            let _ = new_route.state.set(Arc::clone(&other.router_state));
+          }
+
           if !upstream_globals.is_empty() {
             let existing = new_route.middlewares.load_full();
             let mut merged = Vec::with_capacity(upstream_globals.len() + existing.len());
@@ -275,6 +422,9 @@ impl Router {
             merged.extend(existing.iter().cloned());
             new_route.has_middleware.store(true, Ordering::Release);
             new_route.middlewares.store(Arc::new(merged));
+            new_route
+              .inherited_middleware
+              .fetch_add(upstream_globals.len(), Ordering::AcqRel);
           }
 
           // Match `nest` semantics: a path conflict is a builder bug, not a
@@ -299,6 +449,10 @@ impl Router {
       }
     }
 
+    other
+      .router_state
+      .set_parent(Arc::clone(&self.router_state));
+
     #[cfg(feature = "signals")]
     self.signals.merge_from(&other.signals);
   }
@@ -1,12 +1,28 @@
-//! Plugin registration/initialization, `OpenAPI` collection, and route-index GC.
+//! Plugin registration/initialization, `OpenAPI` collection, the route table,
+//! and route-index GC.
 
-#[cfg(any(feature = "utoipa", feature = "vespera"))]
 use http::Method;
 
 use super::Router;
 #[cfg(feature = "plugins")]
+use crate::plugins::PluginScope;
+#[cfg(feature = "plugins")]
+use crate::plugins::PluginSetupError;
+#[cfg(feature = "plugins")]
+use crate::plugins::PluginStatus;
+#[cfg(feature = "plugins")]
+use crate::plugins::RouterHandle;
+#[cfg(feature = "plugins")]
 use crate::plugins::TakoPlugin;
 
+/// Environment variable that makes a debug build's transport print the route
+/// table and exit instead of serving. Release builds ignore it.
+pub const PRINT_ROUTES_ENV: &str = "TAKO_PRINT_ROUTES";
+
+/// Prefix of each route-table line printed under [`PRINT_ROUTES_ENV`], so the
+/// lines can be picked out of whatever else the application writes to stdout.
+pub const ROUTE_LINE_PREFIX: &str = "tako-route";
+
 impl Router {
   /// Registers a plugin with the router.
   ///
@@ -75,18 +91,115 @@ impl Router {
     }
 
     if !self.plugins_initialized.swap(true, Ordering::SeqCst) {
-      for plugin in self.plugins() {
+      let mut status = Vec::with_capacity(self.plugins.len());
+      for plugin in by_priority(&self.plugins) {
         // Surface plugin setup errors loudly — a silently-skipped CORS,
         // auth, rate-limit, or CSRF plugin would leave the server
         // running without the protection the operator expected
         // (security-relevant fail-open). Cold path — first dispatch only.
-        if let Err(e) = plugin.setup(self) {
+        let result = self.setup_plugin(plugin);
+        if let Err(e) = &result {
           tracing::error!(
             plugin = plugin.name(),
             error = %e,
             "router-level TakoPlugin::setup failed; plugin not active"
           );
         }
+        status.push(PluginStatus {
+          name: plugin.name(),
+          scope: PluginScope::Router,
+          error: result.err().map(|e| e.to_string()),
+        });
+      }
+      for group in &self.nested_plugins {
+        group.setup(&mut status);
+      }
+      *self.plugin_status.lock() = status;
+    }
+  }
+
+  /// Sets up plugins for a transport that is about to serve.
+  ///
+  /// Runs [`Router::setup_plugins_once`], emits `plugin.initialized` /
+  /// `plugin.failed` for each plugin when the `signals` feature is enabled,
+  /// and fails when a plugin errored and [`Router::fail_on_plugin_error`] is
+  /// on.
+  #[cfg(feature = "plugins")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+  #[doc(hidden)]
+  pub async fn initialize_plugins(&self) -> Result<(), PluginSetupError> {
+    self.setup_plugins_once();
+    let status = self.plugin_status();
+
+    #[cfg(feature = "signals")]
+    for s in &status {
+      match &s.error {
+        None => crate::signals::transport::emit_plugin_initialized(s.name).await,
+        Some(e) => crate::signals::transport::emit_plugin_failed(s.name, e).await,
+      }
+    }
+
+    let failed: Vec<PluginStatus> = status.into_iter().filter(|s| !s.is_active()).collect();
+    if self.fail_on_plugin_error && !failed.is_empty() {
+      return Err(PluginSetupError { failed });
+    }
+    Ok(())
+  }
+
+  /// Setup outcome of every router-level plugin, including those of routers
+  /// mounted with [`Router::nest`], in setup order (by
+  /// [`TakoPlugin::priority`], then registration order).
+  ///
+  /// Empty until plugins have been set up (by a transport starting, or by
+  /// the first dispatch). Route-level plugins are set up lazily on their
+  /// route's first request and are not listed.
+  #[cfg(feature = "plugins")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+  pub fn plugin_status(&self) -> Vec<PluginStatus> {
+    self.plugin_status.lock().clone()
+  }
+
+  /// Whether transports refuse to start when a router-level plugin fails to
+  /// set up (default `false`). When off, the failure is logged and reported
+  /// by [`Router::plugin_status`] and the server runs without that plugin.
+  #[cfg(feature = "plugins")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+  pub fn fail_on_plugin_error(&mut self, enabled: bool) -> &mut Self {
+    self.fail_on_plugin_error = enabled;
+    self
+  }
+
+  /// Runs `plugin.setup`, giving the middleware it registers the plugin's
+  /// priority.
+  #[cfg(feature = "plugins")]
+  pub(crate) fn setup_plugin(&self, plugin: &dyn TakoPlugin) -> anyhow::Result<()> {
+    use std::sync::atomic::Ordering;
+
+    self
+      .registration_priority
+      .store(plugin.priority(), Ordering::Relaxed);
+    let result = plugin.setup(self);
+    self
+      .registration_priority
+      .store(crate::middleware::priority::DEFAULT, Ordering::Relaxed);
+    result
+  }
+
+  /// Hands every router-level plugin a [`RouterHandle`] once serving starts.
+  ///
+  /// Plugins of routers mounted with [`Router::nest`] receive this router's
+  /// handle too, so any paths they dispatch carry the nesting prefix.
+  /// Transports call this right after [`Router::setup_plugins_once`].
+  #[cfg(feature = "plugins")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+  #[doc(hidden)]
+  pub fn start_plugins(&self, handle: &RouterHandle) {
+    for plugin in self.plugins() {
+      plugin.on_start(handle.clone());
+    }
+    for group in &self.nested_plugins {
+      for plugin in &group.plugins {
+        plugin.on_start(handle.clone());
       }
     }
   }
@@ -128,6 +241,45 @@ impl Router {
     result
   }
 
+  /// Every registered `(method, path)` pair, sorted by path then method.
+  ///
+  /// Paths are the registered patterns (`/users/{id}`), with any
+  /// [`Router::nest`]-style prefix already applied.
+  pub fn route_table(&self) -> Vec<(Method, String)> {
+    let mut table: Vec<(Method, String)> = self
+      .routes
+      .iter()
+      .flat_map(|(method, weak_vec)| {
+        weak_vec
+          .iter()
+          .filter_map(std::sync::Weak::upgrade)
+          .map(move |route| (method.clone(), route.path.clone()))
+      })
+      .collect();
+    table.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.as_str().cmp(b.0.as_str())));
+    table
+  }
+
+  /// Prints [`Router::route_table`] and exits when [`PRINT_ROUTES_ENV`] is
+  /// set, or prints a generated client and exits when
+  /// [`GENERATE_CLIENT_ENV`](crate::codegen::GENERATE_CLIENT_ENV) is set.
+  /// Transports call this right before serving, which is how `tako routes`
+  /// and `tako client` inspect the debug build they run.
+  ///
+  /// Only debug builds honour the variables, so a stray environment variable
+  /// cannot make a release binary exit instead of serving.
+  #[doc(hidden)]
+  pub fn print_routes_and_exit_if_requested(&self) {
+    crate::codegen::print_client_and_exit_if_requested(self);
+    if !cfg!(debug_assertions) || std::env::var_os(PRINT_ROUTES_ENV).is_none() {
+      return;
+    }
+    for (method, path) in self.route_table() {
+      println!("{ROUTE_LINE_PREFIX}\t{method}\t{path}");
+    }
+    std::process::exit(0);
+  }
+
   /// Drops dangling `Weak<Route>` entries from the per-method `routes` index.
   ///
   /// All current routes stay live for the router's lifetime, so this is a
@@ -144,3 +296,12 @@ impl Router {
     }
   }
 }
+
+/// `plugins` in setup order: ascending [`TakoPlugin::priority`], ties in
+/// registration order.
+#[cfg(feature = "plugins")]
+pub(crate) fn by_priority(plugins: &[Box<dyn TakoPlugin>]) -> Vec<&dyn TakoPlugin> {
+  let mut sorted: Vec<&dyn TakoPlugin> = plugins.iter().map(AsRef::as_ref).collect();
+  sorted.sort_by_key(|p| p.priority());
+  sorted
+}
@@ -7,7 +7,10 @@ use http::Method;
 use super::Router;
 use crate::handler::BoxHandler;
 use crate::handler::Handler;
+use crate::middleware::Next;
+use crate::responder::Responder;
 use crate::route::Route;
+use crate::types::Request;
 
 impl Router {
   /// Registers a new route with the router.
@@ -49,6 +52,8 @@ impl Router {
       BoxHandler::new::<H, T>(handler),
       None,
     ));
+    #[cfg(any(feature = "utoipa", feature = "vespera"))]
+    route.describe_handler::<H, T>();
 
     if let Err(err) = self
       .inner
@@ -158,6 +163,42 @@ impl Router {
     self.route(Method::OPTIONS, path, handler)
   }
 
+  /// Starts registering several methods on one path.
+  ///
+  /// Each method call registers a route exactly like [`Router::route`]; a
+  /// request for the path under any other method gets `405` with an `Allow`
+  /// header listing the registered ones.
+  ///
+  /// # Panics
+  ///
+  /// The chained calls panic like [`Router::route`] if a method is already
+  /// registered for the path.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use tako::{router::Router, responder::Responder, types::Request};
+  ///
+  /// async fn show(_req: Request) -> impl Responder { "user" }
+  /// async fn update(_req: Request) -> impl Responder { "updated" }
+  /// async fn remove(_req: Request) -> impl Responder { "removed" }
+  ///
+  /// let mut router = Router::new();
+  /// router
+  ///     .path("/users/{id}")
+  ///     .get(show)
+  ///     .put(update)
+  ///     .delete(remove)
+  ///     .middleware(|req, next| async move { next.run(req).await });
+  /// ```
+  pub fn path(&mut self, path: &str) -> PathRoutes<'_> {
+    PathRoutes {
+      router: self,
+      path: path.to_string(),
+      routes: Vec::new(),
+    }
+  }
+
   /// Registers a route with trailing slash redirection enabled.
   ///
   /// When TSR is enabled, requests to paths with or without trailing slashes
@@ -195,6 +236,8 @@ impl Router {
       BoxHandler::new::<H, T>(handler),
       Some(true),
     ));
+    #[cfg(any(feature = "utoipa", feature = "vespera"))]
+    route.describe_handler::<H, T>();
 
     if let Err(err) = self
       .inner
@@ -212,3 +255,104 @@ impl Router {
     route
   }
 }
+
+/// Fluent registration of several methods on one path, returned by
+/// [`Router::path`].
+pub struct PathRoutes<'a> {
+  router: &'a mut Router,
+  path: String,
+  routes: Vec<Arc<Route>>,
+}
+
+impl PathRoutes<'_> {
+  /// Registers `handler` for `method` on this path.
+  pub fn on<H, T>(mut self, method: Method, handler: H) -> Self
+  where
+    H: Handler<T> + Clone + 'static,
+  {
+    let route = self.router.route(method, &self.path, handler);
+    self.routes.push(route);
+    self
+  }
+
+  /// Registers a `GET` handler on this path.
+  #[inline]
+  pub fn get<H, T>(self, handler: H) -> Self
+  where
+    H: Handler<T> + Clone + 'static,
+  {
+    self.on(Method::GET, handler)
+  }
+
+  /// Registers a `POST` handler on this path.
+  #[inline]
+  pub fn post<H, T>(self, handler: H) -> Self
+  where
+    H: Handler<T> + Clone + 'static,
+  {
+    self.on(Method::POST, handler)
+  }
+
+  /// Registers a `PUT` handler on this path.
+  #[inline]
+  pub fn put<H, T>(self, handler: H) -> Self
+  where
+    H: Handler<T> + Clone + 'static,
+  {
+    self.on(Method::PUT, handler)
+  }
+
+  /// Registers a `DELETE` handler on this path.
+  #[inline]
+  pub fn delete<H, T>(self, handler: H) -> Self
+  where
+    H: Handler<T> + Clone + 'static,
+  {
+    self.on(Method::DELETE, handler)
+  }
+
+  /// Registers a `PATCH` handler on this path.
+  #[inline]
+  pub fn patch<H, T>(self, handler: H) -> Self
+  where
+    H: Handler<T> + Clone + 'static,
+  {
+    self.on(Method::PATCH, handler)
+  }
+
+  /// Registers a `HEAD` handler on this path.
+  #[inline]
+  pub fn head<H, T>(self, handler: H) -> Self
+  where
+    H: Handler<T> + Clone + 'static,
+  {
+    self.on(Method::HEAD, handler)
+  }
+
+  /// Registers an `OPTIONS` handler on this path.
+  #[inline]
+  pub fn options<H, T>(self, handler: H) -> Self
+  where
+    H: Handler<T> + Clone + 'static,
+  {
+    self.on(Method::OPTIONS, handler)
+  }
+
+  /// Adds route middleware to every method registered so far.
+  pub fn middleware<F, Fut, R>(self, f: F) -> Self
+  where
+    F: Fn(Request, Next) -> Fut + Clone + Send + Sync + 'static,
+    Fut: std::future::Future<Output = R> + Send + 'static,
+    R: Responder + Send + 'static,
+  {
+    for route in &self.routes {
+      route.middleware(f.clone());
+    }
+    self
+  }
+
+  /// The routes registered so far, in registration order.
+  pub fn routes(&self) -> &[Arc<Route>] {
+    &self.routes
+  }
+}
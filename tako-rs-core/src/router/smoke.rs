@@ -0,0 +1,168 @@
+//! In-process smoke tests built from route examples.
+//!
+//! [`Router::run_smoke_tests`] sends every [`RouteExample`] through
+//! [`Router::dispatch`] — global and route middleware, plugins and the
+//! handler all run — and collects the mismatches into a [`SmokeReport`].
+
+use std::fmt;
+
+use http::HeaderName;
+use http::HeaderValue;
+use http::header::CONTENT_TYPE;
+use http_body_util::BodyExt;
+
+use super::Router;
+use crate::body::TakoBody;
+use crate::route::ExampleBody;
+use crate::route::RouteExample;
+
+/// A failed example.
+#[derive(Clone, Debug)]
+pub struct SmokeFailure {
+  /// Method of the route.
+  pub method: http::Method,
+  /// Registered path pattern of the route.
+  pub path: String,
+  /// Name of the example.
+  pub example: String,
+  /// One line per mismatch.
+  pub problems: Vec<String>,
+}
+
+/// Outcome of [`Router::run_smoke_tests`].
+#[derive(Clone, Debug, Default)]
+pub struct SmokeReport {
+  /// Number of examples that matched.
+  pub passed: usize,
+  /// Examples that did not.
+  pub failures: Vec<SmokeFailure>,
+}
+
+impl SmokeReport {
+  /// `true` when every example matched.
+  pub fn is_success(&self) -> bool {
+    self.failures.is_empty()
+  }
+}
+
+impl fmt::Display for SmokeReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+      f,
+      "{} example(s) passed, {} failed",
+      self.passed,
+      self.failures.len()
+    )?;
+    for failure in &self.failures {
+      writeln!(
+        f,
+        "{} {} — {}:",
+        failure.method, failure.path, failure.example
+      )?;
+      for problem in &failure.problems {
+        writeln!(f, "  {problem}")?;
+      }
+    }
+    Ok(())
+  }
+}
+
+fn build_request(
+  method: &http::Method,
+  uri: &str,
+  example: &RouteExample,
+) -> Result<crate::types::Request, String> {
+  let mut req = http::Request::new(match &example.body {
+    Some(ExampleBody::Json(value)) => TakoBody::from(value.to_string()),
+    Some(ExampleBody::Text(text)) => TakoBody::from(text.clone()),
+    None => TakoBody::empty(),
+  });
+  *req.method_mut() = method.clone();
+  *req.uri_mut() = uri
+    .parse()
+    .map_err(|e| format!("request uri `{uri}`: {e}"))?;
+  let headers = req.headers_mut();
+  for (name, value) in &example.headers {
+    let name =
+      HeaderName::try_from(name.as_str()).map_err(|e| format!("request header {name}: {e}"))?;
+    let value =
+      HeaderValue::try_from(value.as_str()).map_err(|e| format!("request header {name}: {e}"))?;
+    headers.append(name, value);
+  }
+  if !headers.contains_key(CONTENT_TYPE) {
+    match example.body {
+      Some(ExampleBody::Json(_)) => {
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+      }
+      Some(ExampleBody::Text(_)) => {
+        headers.insert(
+          CONTENT_TYPE,
+          HeaderValue::from_static("text/plain; charset=utf-8"),
+        );
+      }
+      None => {}
+    }
+  }
+  Ok(req)
+}
+
+impl Router {
+  /// Dispatches every route example in-process and reports mismatches.
+  ///
+  /// Examples run one at a time, in path then method order, so examples
+  /// that change state (create, then fetch) can rely on running in
+  /// registration order within a route.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,ignore
+  /// #[tokio::test]
+  /// async fn examples_hold() {
+  ///   let report = app().run_smoke_tests().await;
+  ///   assert!(report.is_success(), "{report}");
+  /// }
+  /// ```
+  pub async fn run_smoke_tests(&self) -> SmokeReport {
+    let mut routes: Vec<_> = self
+      .routes
+      .iter()
+      .flat_map(|(_, weak_vec)| weak_vec.iter().filter_map(std::sync::Weak::upgrade))
+      .collect();
+    routes.sort_by(|a, b| {
+      a.path
+        .cmp(&b.path)
+        .then_with(|| a.method.as_str().cmp(b.method.as_str()))
+    });
+
+    let mut report = SmokeReport::default();
+    for route in routes {
+      for example in route.examples() {
+        let problems = match example
+          .uri(&route.path)
+          .map_err(|param| format!("path parameter `{param}` has no value"))
+          .and_then(|uri| build_request(&route.method, &uri, &example))
+        {
+          Err(problem) => vec![problem],
+          Ok(req) => {
+            let (parts, body) = self.dispatch(req).await.into_parts();
+            match body.collect().await {
+              Ok(body) => example.mismatches(&parts, &body.to_bytes()),
+              Err(e) => vec![format!("body: failed to read ({e})")],
+            }
+          }
+        };
+        if problems.is_empty() {
+          report.passed += 1;
+        } else {
+          report.failures.push(SmokeFailure {
+            method: route.method.clone(),
+            path: route.path.clone(),
+            example: example.name.clone(),
+            problems,
+          });
+        }
+      }
+    }
+    report
+  }
+}
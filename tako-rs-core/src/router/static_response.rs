@@ -0,0 +1,84 @@
+//! Constant routes served from a response built once at registration.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http::HeaderMap;
+use http::Method;
+use http::StatusCode;
+
+use super::Router;
+use crate::body::TakoBody;
+use crate::route::Route;
+use crate::types::Request;
+use crate::types::Response;
+
+/// The parts of a constant response; every request gets a fresh copy.
+pub(crate) struct StaticResponse {
+  status: StatusCode,
+  headers: HeaderMap,
+  body: Bytes,
+}
+
+impl StaticResponse {
+  /// A copy of the response. The body is a reference-counted `Bytes`, so
+  /// only the header map is actually cloned.
+  #[inline]
+  pub(crate) fn build(&self) -> Response {
+    let mut resp = http::Response::new(TakoBody::from(self.body.clone()));
+    *resp.status_mut() = self.status;
+    *resp.headers_mut() = self.headers.clone();
+    resp
+  }
+}
+
+impl Router {
+  /// Registers a route that always answers with the same response.
+  ///
+  /// The response is assembled once here. When the route has no middleware
+  /// and no timeout, dispatch copies it directly instead of calling a
+  /// handler, which suits version strings, banners and health probes on hot
+  /// paths. Route and router middleware, if any, still run around it.
+  ///
+  /// # Panics
+  ///
+  /// Panics if a route with the same method and path pattern is already
+  /// registered.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use tako::{router::Router, Method, StatusCode};
+  /// use http::{HeaderMap, HeaderValue, header};
+  ///
+  /// let mut headers = HeaderMap::new();
+  /// headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+  ///
+  /// let mut router = Router::new();
+  /// router.static_response(Method::GET, "/version", StatusCode::OK, headers, "1.4.2");
+  /// ```
+  pub fn static_response(
+    &mut self,
+    method: Method,
+    path: &str,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: impl Into<Bytes>,
+  ) -> Arc<Route> {
+    let response = Arc::new(StaticResponse {
+      status,
+      headers,
+      body: body.into(),
+    });
+    let handler = {
+      let response = Arc::clone(&response);
+      move |_req: Request| {
+        let response = Arc::clone(&response);
+        async move { response.build() }
+      }
+    };
+    let route = self.route(method, path, handler);
+    let _ = route.static_response.set(response);
+    route
+  }
+}
@@ -10,10 +10,17 @@
 //! `Arc<RouterState>` first (inserted by [`crate::router::Router::dispatch`])
 //! and falls back to [`crate::state::get_state`] if the per-router slot is
 //! empty. Existing code that uses the global store keeps working unchanged.
+//!
+//! When a router is mounted into another with
+//! [`crate::router::Router::nest`] or [`crate::router::Router::merge`], its
+//! state is linked to the mounting router's as a parent: the child's routes
+//! see the child's values first and the parent's for every type the child
+//! does not hold.
 
 use std::any::Any;
 use std::any::TypeId;
 use std::sync::Arc;
+use std::sync::OnceLock;
 
 use scc::HashMap as SccHashMap;
 
@@ -21,6 +28,8 @@ use scc::HashMap as SccHashMap;
 #[derive(Default)]
 pub struct RouterState {
   inner: SccHashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+  /// State of the router this one was mounted into, consulted on a miss.
+  parent: OnceLock<Arc<RouterState>>,
 }
 
 impl std::fmt::Debug for RouterState {
@@ -42,12 +51,21 @@ impl RouterState {
   }
 
   /// Retrieve the value associated with `T`, if any.
+  ///
+  /// Falls back to the parent router's state when this container has no `T`.
   pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
     self
       .inner
       .get_sync(&TypeId::of::<T>())
       .map(|v| v.clone())
       .and_then(|v| v.downcast::<T>().ok())
+      .or_else(|| self.parent.get().and_then(|p| p.get::<T>()))
+  }
+
+  /// Links this container to the state of the router it is mounted into.
+  /// Only the first link sticks.
+  pub(crate) fn set_parent(&self, parent: Arc<RouterState>) {
+    let _ = self.parent.set(parent);
   }
 
   /// `true` when at least one value has been inserted.
@@ -55,7 +73,7 @@ impl RouterState {
     self.inner.is_empty()
   }
 
-  /// Number of distinct types currently stored.
+  /// Number of distinct types currently stored (excluding the parent's).
   pub fn len(&self) -> usize {
     self.inner.len()
   }
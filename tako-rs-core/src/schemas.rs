@@ -0,0 +1,187 @@
+//! JSON Schema generation for request and response types.
+//!
+//! With the `schemars` feature, any `T: JsonSchema` carried by
+//! [`Json<T>`](crate::extractors::json::Json) — as extractor or responder —
+//! can describe itself via [`Json::json_schema`](crate::extractors::json::Json::json_schema).
+//! A [`SchemaRegistry`] collects those schemas by name and serves them from a
+//! standalone endpoint for client code generation:
+//!
+//! - `GET {path}` returns an object mapping every schema name to its schema;
+//! - `GET {path}/{name}` returns one schema, or `404 Not Found`.
+//!
+//! With `utoipa` or `vespera` also enabled, `Route::request_schema` feeds
+//! the same schema into the route's `OpenAPI` request body.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use schemars::JsonSchema;
+//! use tako::extractors::json::Json;
+//! use tako::router::Router;
+//! use tako::schemas::SchemaRegistry;
+//!
+//! /// A new user account.
+//! #[derive(serde::Deserialize, JsonSchema)]
+//! struct CreateUser {
+//!     name: String,
+//! }
+//!
+//! #[derive(serde::Serialize, JsonSchema)]
+//! struct User {
+//!     id: u64,
+//!     name: String,
+//! }
+//!
+//! let mut router = Router::new();
+//! router.post("/users", |Json(body): Json<CreateUser>| async move {
+//!     Json(User { id: 1, name: body.name })
+//! });
+//!
+//! SchemaRegistry::new()
+//!     .register::<CreateUser>()
+//!     .register::<User>()
+//!     .mount(&mut router, "/schemas");
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+pub use schemars::JsonSchema;
+pub use schemars::Schema;
+use serde::Deserialize;
+
+use crate::extractors::params::Params;
+use crate::responder::Responder;
+use crate::router::Router;
+
+/// Generates the JSON Schema of `T`.
+pub fn schema_for<T: JsonSchema>() -> Schema {
+  schemars::schema_for!(T)
+}
+
+/// `{name}` segment of the single-schema endpoint.
+#[derive(Deserialize)]
+struct SchemaName {
+  name: String,
+}
+
+/// Named JSON Schemas served from a standalone endpoint.
+///
+/// Cheap to clone; every clone shares the same schemas, so types registered
+/// after [`SchemaRegistry::mount`] are served too.
+#[derive(Clone, Default)]
+pub struct SchemaRegistry {
+  schemas: Arc<RwLock<BTreeMap<String, Schema>>>,
+}
+
+impl std::fmt::Debug for SchemaRegistry {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("SchemaRegistry")
+      .field("names", &self.names())
+      .finish()
+  }
+}
+
+impl SchemaRegistry {
+  /// Creates an empty registry.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds the schema of `T` under `T::schema_name()`, replacing an earlier
+  /// schema with the same name.
+  pub fn register<T: JsonSchema>(&self) -> &Self {
+    self.insert(T::schema_name(), schema_for::<T>())
+  }
+
+  /// Adds a schema under an explicit name.
+  pub fn insert(&self, name: impl Into<String>, schema: Schema) -> &Self {
+    self.schemas.write().insert(name.into(), schema);
+    self
+  }
+
+  /// The schema registered under `name`.
+  pub fn get(&self, name: &str) -> Option<Schema> {
+    self.schemas.read().get(name).cloned()
+  }
+
+  /// Registered names, sorted.
+  pub fn names(&self) -> Vec<String> {
+    self.schemas.read().keys().cloned().collect()
+  }
+
+  /// Number of registered schemas.
+  pub fn len(&self) -> usize {
+    self.schemas.read().len()
+  }
+
+  /// Returns `true` when nothing is registered.
+  pub fn is_empty(&self) -> bool {
+    self.schemas.read().is_empty()
+  }
+
+  /// All schemas as one JSON object keyed by name.
+  pub fn to_json(&self) -> serde_json::Value {
+    self
+      .schemas
+      .read()
+      .iter()
+      .map(|(name, schema)| (name.clone(), schema.as_value().clone()))
+      .collect::<serde_json::Map<_, _>>()
+      .into()
+  }
+
+  /// Serves the registry at `GET {path}` and `GET {path}/{name}`.
+  pub fn mount(&self, router: &mut Router, path: &str) -> &Self {
+    let path = path.trim_end_matches('/');
+
+    let all = self.clone();
+    router.get(if path.is_empty() { "/" } else { path }, move || {
+      let all = all.clone();
+      async move { all.to_json() }
+    });
+
+    let one = self.clone();
+    router.get(
+      &format!("{path}/{{name}}"),
+      move |Params(SchemaName { name }): Params<SchemaName>| {
+        let one = one.clone();
+        async move {
+          match one.get(&name) {
+            Some(schema) => schema.to_value().into_response(),
+            None => http::StatusCode::NOT_FOUND.into_response(),
+          }
+        }
+      },
+    );
+    self
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use schemars::JsonSchema;
+
+  use super::SchemaRegistry;
+
+  #[allow(dead_code)]
+  #[derive(JsonSchema)]
+  struct Item {
+    id: u64,
+  }
+
+  #[test]
+  fn registers_and_lists_schemas_by_name() {
+    let registry = SchemaRegistry::new();
+    registry.register::<Item>().register::<String>();
+    assert_eq!(registry.names(), ["Item", "string"]);
+
+    let item = registry.get("Item").unwrap();
+    assert_eq!(item.get("type").unwrap(), "object");
+    assert_eq!(
+      registry.to_json()["Item"]["properties"]["id"]["type"],
+      "integer"
+    );
+  }
+}
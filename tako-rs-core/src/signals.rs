@@ -10,6 +10,8 @@ mod rpc;
 mod runtime;
 mod signal;
 
+/// Multi-step workflows with per-step compensation and persisted progress.
+pub mod saga;
 /// Connection-lifecycle signal helpers used by every transport.
 pub mod transport;
 
@@ -19,6 +21,7 @@ pub use arbiter::app_signals;
 pub use rpc::RpcError;
 pub use rpc::RpcResult;
 pub use rpc::RpcTimeoutError;
+pub use runtime::ScheduledSignal;
 pub use signal::FILTERED_SUBSCRIPTION_BUFFER;
 pub use signal::MAX_BROADCAST_CAPACITY;
 pub use signal::RpcHandler;
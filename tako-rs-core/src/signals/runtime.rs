@@ -1,9 +1,14 @@
-//! Runtime-specific dispatch glue: filtered-subscription forwarding and
-//! RPC timeouts, with distinct compio vs tokio spawn / sleep paths.
+//! Runtime-specific dispatch glue: filtered-subscription forwarding, RPC
+//! timeouts, and scheduled emission, with distinct compio vs tokio spawn /
+//! sleep paths.
 
 use std::sync::Arc;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
+use std::time::Instant;
 
+use tokio::sync::Notify;
 use tokio::sync::mpsc;
 #[cfg(not(feature = "compio"))]
 use tokio::time::timeout;
@@ -14,7 +19,120 @@ use super::signal::FILTERED_SUBSCRIPTION_BUFFER;
 use super::signal::Signal;
 use super::signal::SignalStream;
 
+const PENDING: u8 = 0;
+const FIRED: u8 = 1;
+const CANCELLED: u8 = 2;
+
+#[derive(Default)]
+struct Schedule {
+  status: AtomicU8,
+  cancel: Notify,
+}
+
+/// Handle to a signal scheduled with [`SignalArbiter::emit_after`] or
+/// [`SignalArbiter::emit_at`].
+///
+/// Dropping the handle does not cancel the emission; call
+/// [`cancel`](Self::cancel) for that.
+#[derive(Clone)]
+pub struct ScheduledSignal {
+  schedule: Arc<Schedule>,
+}
+
+impl ScheduledSignal {
+  /// Cancels the emission. Returns `true` if the signal had not fired yet
+  /// and now never will, `false` if it already fired or was cancelled.
+  pub fn cancel(&self) -> bool {
+    let cancelled = self
+      .schedule
+      .status
+      .compare_exchange(PENDING, CANCELLED, Ordering::AcqRel, Ordering::Acquire)
+      .is_ok();
+    if cancelled {
+      // `notify_one` stores a permit, so the timer task wakes even if it has
+      // not started waiting yet.
+      self.schedule.cancel.notify_one();
+    }
+    cancelled
+  }
+
+  /// Whether the timer is still running.
+  pub fn is_pending(&self) -> bool {
+    self.schedule.status.load(Ordering::Acquire) == PENDING
+  }
+
+  /// Whether the signal was emitted.
+  pub fn has_fired(&self) -> bool {
+    self.schedule.status.load(Ordering::Acquire) == FIRED
+  }
+
+  /// Whether the emission was cancelled before it fired.
+  pub fn is_cancelled(&self) -> bool {
+    self.schedule.status.load(Ordering::Acquire) == CANCELLED
+  }
+}
+
+impl std::fmt::Debug for ScheduledSignal {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let status = match self.schedule.status.load(Ordering::Acquire) {
+      PENDING => "pending",
+      FIRED => "fired",
+      _ => "cancelled",
+    };
+    f.debug_struct("ScheduledSignal")
+      .field("status", &status)
+      .finish()
+  }
+}
+
 impl SignalArbiter {
+  /// Emits `signal` once `delay` has elapsed, unless the returned handle is
+  /// cancelled first.
+  ///
+  /// Useful for timeout-style workflows: schedule `cart.abandoned` when a
+  /// cart is created and cancel it at checkout. Handlers run on a background
+  /// task exactly as with [`emit`](Self::emit). Schedules live in memory and
+  /// do not survive a restart; use the job queue for durable reminders.
+  pub fn emit_after(&self, delay: Duration, signal: Signal) -> ScheduledSignal {
+    let schedule = Arc::new(Schedule::default());
+    let handle = ScheduledSignal {
+      schedule: schedule.clone(),
+    };
+    let arbiter = self.clone();
+    let task = async move {
+      #[cfg(not(feature = "compio"))]
+      let sleep = std::pin::pin!(tokio::time::sleep(delay));
+      #[cfg(feature = "compio")]
+      let sleep = std::pin::pin!(compio::time::sleep(delay));
+      let cancelled = std::pin::pin!(schedule.cancel.notified());
+      if let futures_util::future::Either::Right(_) =
+        futures_util::future::select(sleep, cancelled).await
+      {
+        return;
+      }
+      if schedule
+        .status
+        .compare_exchange(PENDING, FIRED, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok()
+      {
+        arbiter.emit(signal).await;
+      }
+    };
+
+    #[cfg(not(feature = "compio"))]
+    tokio::spawn(task);
+    #[cfg(feature = "compio")]
+    compio::runtime::spawn(task).detach();
+
+    handle
+  }
+
+  /// Emits `signal` at `at`, or immediately if that moment has passed. See
+  /// [`emit_after`](Self::emit_after).
+  pub fn emit_at(&self, at: Instant, signal: Signal) -> ScheduledSignal {
+    self.emit_after(at.saturating_duration_since(Instant::now()), signal)
+  }
+
   /// Subscribes using a filter function on top of an id-based subscription.
   ///
   /// Spawns a background task that forwards only matching signals into a
@@ -102,3 +220,38 @@ impl SignalArbiter {
     }
   }
 }
+
+#[cfg(all(test, not(feature = "compio")))]
+mod tests {
+  use std::sync::atomic::AtomicUsize;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn scheduled_signals_fire_once_unless_cancelled() {
+    let arbiter = SignalArbiter::new();
+    let fired = Arc::new(AtomicUsize::new(0));
+    let f = fired.clone();
+    arbiter.on("reminder", move |_| {
+      let f = f.clone();
+      async move {
+        f.fetch_add(1, Ordering::SeqCst);
+      }
+    });
+
+    let kept = arbiter.emit_after(Duration::from_millis(20), Signal::new("reminder"));
+    let dropped = arbiter.emit_at(
+      Instant::now() + Duration::from_millis(20),
+      Signal::new("reminder"),
+    );
+    assert!(dropped.cancel());
+    assert!(!dropped.cancel());
+    assert!(kept.is_pending());
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(fired.load(Ordering::SeqCst), 1);
+    assert!(kept.has_fired());
+    assert!(!kept.cancel());
+    assert!(dropped.is_cancelled());
+  }
+}
@@ -0,0 +1,610 @@
+//! Multi-step workflows with compensation, built on the signal arbiter.
+//!
+//! A [`Saga`] is an ordered list of [`Step`]s. Each step is an RPC call
+//! ([`Step::rpc`], [`Step::try_rpc`]), a wait for a signal
+//! ([`Step::wait_signal`]), or arbitrary async code ([`Step::new`]). A step
+//! succeeds with a JSON output that later steps can read from the
+//! [`SagaContext`]. When a step fails or exceeds its timeout, the
+//! compensation handlers of the steps that already succeeded run in reverse
+//! order, and the saga ends as [`SagaStatus::Failed`].
+//!
+//! Progress is written to a [`SagaStore`] after every step, so a process
+//! that restarts mid-workflow can pick up where it left off with
+//! [`Saga::resume`]. [`MemorySagaStore`] keeps records in memory; back the
+//! trait with a database to survive restarts.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//!
+//! use tako::signals::SignalArbiter;
+//! use tako::signals::saga::Saga;
+//! use tako::signals::saga::Step;
+//!
+//! let arbiter = SignalArbiter::new();
+//! let checkout = Saga::new("checkout", arbiter.clone())
+//!     .step(
+//!         Step::rpc::<String, u64, _>("reserve", "inventory.reserve", |ctx| {
+//!             ctx.input()["sku"].as_str().unwrap_or_default().to_string()
+//!         })
+//!         .compensate(|ctx| async move {
+//!             let id = ctx.output_as::<u64>("reserve").unwrap_or_default();
+//!             ctx.arbiter().call_rpc::<u64, ()>("inventory.release", id).await;
+//!         }),
+//!     )
+//!     .step(
+//!         Step::wait_signal("paid", "payment.confirmed", |ctx, sig| {
+//!             ctx.input()["order"].as_str() == sig.metadata.get("order").map(String::as_str)
+//!         })
+//!         .timeout(Duration::from_secs(900)),
+//!     );
+//!
+//! let result = checkout
+//!     .run("order-42", serde_json::json!({ "order": "42", "sku": "tee-m" }))
+//!     .await;
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use scc::HashMap as SccHashMap;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use super::arbiter::SignalArbiter;
+use super::signal::Signal;
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+type ActionFn = Arc<dyn Fn(SagaContext) -> BoxFuture<Result<Value, String>> + Send + Sync>;
+type CompensateFn = Arc<dyn Fn(SagaContext) -> BoxFuture<()> + Send + Sync>;
+
+/// Where a saga instance stands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SagaStatus {
+  /// Steps are still being executed.
+  Running,
+  /// A step failed; completed steps are being compensated.
+  Compensating {
+    /// Name of the step that failed.
+    step: String,
+    /// Why it failed.
+    error: String,
+  },
+  /// Every step succeeded.
+  Completed,
+  /// A step failed and compensation has finished.
+  Failed {
+    /// Name of the step that failed.
+    step: String,
+    /// Why it failed.
+    error: String,
+  },
+}
+
+/// Persisted progress of one saga instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SagaRecord {
+  /// Instance id chosen by the caller (an order id, for example).
+  pub id: String,
+  /// Name of the [`Saga`] definition.
+  pub saga: String,
+  /// Current status.
+  pub status: SagaStatus,
+  /// Input the instance was started with.
+  pub input: Value,
+  /// Outputs of the steps that succeeded, by step name.
+  pub outputs: HashMap<String, Value>,
+  /// Number of leading steps that succeeded and are not yet compensated.
+  pub completed: usize,
+}
+
+/// Errors returned by [`Saga::run`] and [`Saga::resume`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SagaError {
+  /// A step failed; the completed steps were compensated.
+  StepFailed {
+    /// Name of the step that failed.
+    step: String,
+    /// Why it failed.
+    error: String,
+  },
+  /// An instance with this id already exists.
+  AlreadyExists(String),
+  /// No instance with this id exists.
+  NotFound(String),
+  /// The stored record belongs to another saga or no longer fits this one.
+  Mismatch(String),
+  /// The progress store failed.
+  Store(String),
+}
+
+impl fmt::Display for SagaError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::StepFailed { step, error } => write!(f, "saga step '{step}' failed: {error}"),
+      Self::AlreadyExists(id) => write!(f, "saga instance '{id}' already exists"),
+      Self::NotFound(id) => write!(f, "saga instance '{id}' not found"),
+      Self::Mismatch(e) => write!(f, "saga record mismatch: {e}"),
+      Self::Store(e) => write!(f, "saga store error: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for SagaError {}
+
+/// Persistence for saga progress.
+#[async_trait]
+pub trait SagaStore: Send + Sync + 'static {
+  /// Loads an instance.
+  async fn load(&self, id: &str) -> Result<Option<SagaRecord>, SagaError>;
+  /// Inserts or replaces an instance.
+  async fn save(&self, record: &SagaRecord) -> Result<(), SagaError>;
+  /// Deletes an instance.
+  async fn remove(&self, id: &str) -> Result<(), SagaError>;
+}
+
+/// In-memory [`SagaStore`]. Progress is lost on restart.
+#[derive(Clone, Default)]
+pub struct MemorySagaStore {
+  records: Arc<SccHashMap<String, SagaRecord>>,
+}
+
+impl MemorySagaStore {
+  /// Creates an empty store.
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+#[async_trait]
+impl SagaStore for MemorySagaStore {
+  async fn load(&self, id: &str) -> Result<Option<SagaRecord>, SagaError> {
+    Ok(self.records.read_async(id, |_, r| r.clone()).await)
+  }
+
+  async fn save(&self, record: &SagaRecord) -> Result<(), SagaError> {
+    self
+      .records
+      .upsert_async(record.id.clone(), record.clone())
+      .await;
+    Ok(())
+  }
+
+  async fn remove(&self, id: &str) -> Result<(), SagaError> {
+    self.records.remove_async(id).await;
+    Ok(())
+  }
+}
+
+/// What a step sees: the arbiter, the saga input, and earlier outputs.
+#[derive(Clone)]
+pub struct SagaContext {
+  arbiter: SignalArbiter,
+  id: Arc<str>,
+  input: Arc<Value>,
+  outputs: Arc<HashMap<String, Value>>,
+}
+
+impl SagaContext {
+  /// The arbiter the saga runs on.
+  pub fn arbiter(&self) -> &SignalArbiter {
+    &self.arbiter
+  }
+
+  /// The instance id.
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+
+  /// The input the instance was started with.
+  pub fn input(&self) -> &Value {
+    &self.input
+  }
+
+  /// Output of an earlier step.
+  pub fn output(&self, step: &str) -> Option<&Value> {
+    self.outputs.get(step)
+  }
+
+  /// Output of an earlier step, deserialized.
+  pub fn output_as<T: DeserializeOwned>(&self, step: &str) -> Option<T> {
+    serde_json::from_value(self.outputs.get(step)?.clone()).ok()
+  }
+}
+
+/// One step of a [`Saga`].
+#[derive(Clone)]
+pub struct Step {
+  name: String,
+  action: ActionFn,
+  compensate: Option<CompensateFn>,
+  timeout: Option<Duration>,
+}
+
+impl Step {
+  /// A step running arbitrary async code. `Ok` carries the step output;
+  /// `Err` fails the saga.
+  pub fn new<F, Fut>(name: impl Into<String>, action: F) -> Self
+  where
+    F: Fn(SagaContext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Value, String>> + Send + 'static,
+  {
+    Self {
+      name: name.into(),
+      action: Arc::new(move |ctx| Box::pin(action(ctx))),
+      compensate: None,
+      timeout: None,
+    }
+  }
+
+  /// Calls the typed RPC `rpc_id` with the request built by `request`. The
+  /// response becomes the step output. Fails when no handler is registered.
+  pub fn rpc<Req, Res, F>(name: impl Into<String>, rpc_id: impl Into<String>, request: F) -> Self
+  where
+    Req: Send + Sync + 'static,
+    Res: Serialize + Send + Sync + Clone + 'static,
+    F: Fn(&SagaContext) -> Req + Send + Sync + 'static,
+  {
+    let rpc_id: Arc<str> = rpc_id.into().into();
+    Self::new(name, move |ctx| {
+      let req = request(&ctx);
+      let rpc_id = rpc_id.clone();
+      async move {
+        let res = ctx
+          .arbiter
+          .call_rpc_result::<Req, Res>(&*rpc_id, req)
+          .await
+          .map_err(|e| e.to_string())?;
+        serde_json::to_value(res).map_err(|e| e.to_string())
+      }
+    })
+  }
+
+  /// Like [`rpc`](Self::rpc) for handlers answering `Result<T, E>`: `Ok(T)`
+  /// becomes the output and `Err(E)` fails the step.
+  pub fn try_rpc<Req, T, E, F>(
+    name: impl Into<String>,
+    rpc_id: impl Into<String>,
+    request: F,
+  ) -> Self
+  where
+    Req: Send + Sync + 'static,
+    T: Serialize + Send + Sync + Clone + 'static,
+    E: fmt::Display + Send + Sync + Clone + 'static,
+    F: Fn(&SagaContext) -> Req + Send + Sync + 'static,
+  {
+    let rpc_id: Arc<str> = rpc_id.into().into();
+    Self::new(name, move |ctx| {
+      let req = request(&ctx);
+      let rpc_id = rpc_id.clone();
+      async move {
+        let res = ctx
+          .arbiter
+          .call_rpc_result::<Req, Result<T, E>>(&*rpc_id, req)
+          .await
+          .map_err(|e| e.to_string())?
+          .map_err(|e| e.to_string())?;
+        serde_json::to_value(res).map_err(|e| e.to_string())
+      }
+    })
+  }
+
+  /// Waits for a signal `signal_id` accepted by `filter`. The signal's
+  /// metadata becomes the step output.
+  ///
+  /// The subscription starts when the step starts, so signals emitted
+  /// earlier are not seen. Pair it with [`timeout`](Self::timeout) unless
+  /// waiting forever is acceptable.
+  pub fn wait_signal<F>(name: impl Into<String>, signal_id: impl Into<String>, filter: F) -> Self
+  where
+    F: Fn(&SagaContext, &Signal) -> bool + Send + Sync + 'static,
+  {
+    let signal_id: Arc<str> = signal_id.into().into();
+    let filter = Arc::new(filter);
+    Self::new(name, move |ctx| {
+      let mut rx = ctx.arbiter.subscribe(&*signal_id);
+      let filter = filter.clone();
+      async move {
+        loop {
+          match rx.recv().await {
+            Ok(signal) if filter(&ctx, &signal) => {
+              return serde_json::to_value(&signal.metadata).map_err(|e| e.to_string());
+            }
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => {
+              return Err("signal arbiter closed".to_string());
+            }
+          }
+        }
+      }
+    })
+  }
+
+  /// Undo action run when a later step fails.
+  pub fn compensate<F, Fut>(mut self, f: F) -> Self
+  where
+    F: Fn(SagaContext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.compensate = Some(Arc::new(move |ctx| Box::pin(f(ctx))));
+    self
+  }
+
+  /// Fails the step when it has not finished within `dur`.
+  pub fn timeout(mut self, dur: Duration) -> Self {
+    self.timeout = Some(dur);
+    self
+  }
+
+  /// The step name.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  async fn execute(&self, ctx: SagaContext) -> Result<Value, String> {
+    let work = (self.action)(ctx);
+    let Some(dur) = self.timeout else {
+      return work.await;
+    };
+    #[cfg(not(feature = "compio"))]
+    let sleep = std::pin::pin!(tokio::time::sleep(dur));
+    #[cfg(feature = "compio")]
+    let sleep = std::pin::pin!(compio::time::sleep(dur));
+    match futures_util::future::select(work, sleep).await {
+      futures_util::future::Either::Left((res, _)) => res,
+      futures_util::future::Either::Right(_) => Err(format!("timed out after {dur:?}")),
+    }
+  }
+}
+
+/// A named, ordered workflow.
+#[derive(Clone)]
+pub struct Saga {
+  name: String,
+  arbiter: SignalArbiter,
+  steps: Vec<Step>,
+  store: Arc<dyn SagaStore>,
+}
+
+impl Saga {
+  /// Creates an empty saga on `arbiter`, persisting to a
+  /// [`MemorySagaStore`] until [`store`](Self::store) is called.
+  pub fn new(name: impl Into<String>, arbiter: SignalArbiter) -> Self {
+    Self {
+      name: name.into(),
+      arbiter,
+      steps: Vec::new(),
+      store: Arc::new(MemorySagaStore::new()),
+    }
+  }
+
+  /// Appends a step.
+  pub fn step(mut self, step: Step) -> Self {
+    self.steps.push(step);
+    self
+  }
+
+  /// Sets the progress store.
+  pub fn store(mut self, store: impl SagaStore) -> Self {
+    self.store = Arc::new(store);
+    self
+  }
+
+  /// Starts a new instance and drives it to completion or compensation.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`SagaError::StepFailed`] after compensating a failed step,
+  /// [`SagaError::AlreadyExists`] when `id` was used before, or a store
+  /// error.
+  pub async fn run(&self, id: impl Into<String>, input: Value) -> Result<SagaRecord, SagaError> {
+    let id = id.into();
+    if self.store.load(&id).await?.is_some() {
+      return Err(SagaError::AlreadyExists(id));
+    }
+    let record = SagaRecord {
+      id,
+      saga: self.name.clone(),
+      status: SagaStatus::Running,
+      input,
+      outputs: HashMap::new(),
+      completed: 0,
+    };
+    self.store.save(&record).await?;
+    self.drive(record).await
+  }
+
+  /// Continues an instance from its last saved progress, for example after
+  /// a restart. Finished instances are returned unchanged (a failed one as
+  /// [`SagaError::StepFailed`]).
+  ///
+  /// # Errors
+  ///
+  /// As for [`run`](Self::run), plus [`SagaError::NotFound`] and
+  /// [`SagaError::Mismatch`].
+  pub async fn resume(&self, id: &str) -> Result<SagaRecord, SagaError> {
+    let record = self
+      .store
+      .load(id)
+      .await?
+      .ok_or_else(|| SagaError::NotFound(id.to_string()))?;
+    if record.saga != self.name || record.completed > self.steps.len() {
+      return Err(SagaError::Mismatch(format!(
+        "instance '{id}' was recorded by saga '{}'",
+        record.saga
+      )));
+    }
+    self.drive(record).await
+  }
+
+  /// Loads an instance's progress.
+  ///
+  /// # Errors
+  ///
+  /// Returns a store error.
+  pub async fn status(&self, id: &str) -> Result<Option<SagaRecord>, SagaError> {
+    self.store.load(id).await
+  }
+
+  fn context(&self, record: &SagaRecord) -> SagaContext {
+    SagaContext {
+      arbiter: self.arbiter.clone(),
+      id: record.id.as_str().into(),
+      input: Arc::new(record.input.clone()),
+      outputs: Arc::new(record.outputs.clone()),
+    }
+  }
+
+  async fn drive(&self, mut record: SagaRecord) -> Result<SagaRecord, SagaError> {
+    if record.status == SagaStatus::Running {
+      while record.completed < self.steps.len() {
+        let step = &self.steps[record.completed];
+        match step.execute(self.context(&record)).await {
+          Ok(output) => {
+            record.outputs.insert(step.name.clone(), output);
+            record.completed += 1;
+          }
+          Err(error) => {
+            record.status = SagaStatus::Compensating {
+              step: step.name.clone(),
+              error,
+            };
+            self.store.save(&record).await?;
+            break;
+          }
+        }
+        self.store.save(&record).await?;
+      }
+      if record.status == SagaStatus::Running {
+        record.status = SagaStatus::Completed;
+        self.store.save(&record).await?;
+      }
+    }
+
+    if let SagaStatus::Compensating { step, error } = record.status.clone() {
+      while record.completed > 0 {
+        let done = &self.steps[record.completed - 1];
+        if let Some(compensate) = &done.compensate {
+          compensate(self.context(&record)).await;
+        }
+        record.completed -= 1;
+        self.store.save(&record).await?;
+      }
+      record.status = SagaStatus::Failed { step, error };
+      self.store.save(&record).await?;
+    }
+
+    match &record.status {
+      SagaStatus::Failed { step, error } => Err(SagaError::StepFailed {
+        step: step.clone(),
+        error: error.clone(),
+      }),
+      _ => Ok(record),
+    }
+  }
+}
+
+#[cfg(all(test, not(feature = "compio")))]
+mod tests {
+  use std::sync::Mutex;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn completed_steps_are_compensated_in_reverse_order() {
+    let arbiter = SignalArbiter::new();
+    arbiter.register_rpc(
+      "reserve",
+      |sku: Arc<String>| async move { format!("r-{sku}") },
+    );
+    let undone = Arc::new(Mutex::new(Vec::new()));
+
+    let (u1, u2) = (undone.clone(), undone.clone());
+    let saga = Saga::new("checkout", arbiter.clone())
+      .step(
+        Step::rpc::<String, String, _>("reserve", "reserve", |ctx| {
+          ctx.input()["sku"].as_str().unwrap().to_string()
+        })
+        .compensate(move |ctx| {
+          let u = u1.clone();
+          async move {
+            let id: String = ctx.output_as("reserve").unwrap();
+            u.lock().unwrap().push(id);
+          }
+        }),
+      )
+      .step(
+        Step::new("charge", |_| async { Ok(Value::from(100)) }).compensate(move |_| {
+          let u = u2.clone();
+          async move { u.lock().unwrap().push("refund".to_string()) }
+        }),
+      )
+      .step(
+        Step::wait_signal("paid", "payment.confirmed", |_, _| true)
+          .timeout(Duration::from_millis(20)),
+      );
+
+    let err = saga
+      .run("o1", serde_json::json!({ "sku": "tee" }))
+      .await
+      .unwrap_err();
+    assert!(matches!(err, SagaError::StepFailed { ref step, .. } if step == "paid"));
+    assert_eq!(*undone.lock().unwrap(), ["refund", "r-tee"]);
+
+    let record = saga.status("o1").await.unwrap().unwrap();
+    assert_eq!(record.completed, 0);
+    assert!(matches!(record.status, SagaStatus::Failed { .. }));
+    assert!(matches!(
+      saga.run("o1", Value::Null).await,
+      Err(SagaError::AlreadyExists(_))
+    ));
+  }
+
+  #[tokio::test]
+  async fn resume_continues_from_saved_progress() {
+    let arbiter = SignalArbiter::new();
+    let store = MemorySagaStore::new();
+    store
+      .save(&SagaRecord {
+        id: "o2".to_string(),
+        saga: "flow".to_string(),
+        status: SagaStatus::Running,
+        input: Value::Null,
+        outputs: HashMap::from([("first".to_string(), Value::from(1))]),
+        completed: 1,
+      })
+      .await
+      .unwrap();
+
+    let saga = Saga::new("flow", arbiter.clone())
+      .store(store.clone())
+      .step(Step::new("first", |_| async {
+        Err("must not rerun".to_string())
+      }))
+      .step(Step::wait_signal("approved", "approval", |ctx, sig| {
+        sig.metadata.get("id").map(String::as_str) == Some(ctx.id())
+      }));
+
+    let runner = saga.clone();
+    let task = tokio::spawn(async move { runner.resume("o2").await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    arbiter
+      .emit(Signal::new("approval").meta("id", "other"))
+      .await;
+    arbiter.emit(Signal::new("approval").meta("id", "o2")).await;
+
+    let record = task.await.unwrap().unwrap();
+    assert_eq!(record.status, SagaStatus::Completed);
+    assert_eq!(record.outputs["approved"]["id"], "o2");
+  }
+}
@@ -16,12 +16,16 @@ use crate::types::BuildHasher;
 /// | prefix         | scope                                                                     |
 /// |----------------|---------------------------------------------------------------------------|
 /// | `server.*`     | process-level events (server start / stop)                                |
-/// | `connection.*` | per-connection events (open / close, transport snapshot)                  |
+/// | `connection.*` | per-connection events (open / close / rejected, transport snapshot)       |
 /// | `request.*`    | per-request events on the **global** application arbiter                  |
 /// | `route.*`      | per-route events on the **route-local** arbiter (one arbiter per route)   |
 /// | `queue.*`      | background-job lifecycle (queue.job.queued / started / completed / …)     |
 /// | `rpc.*`        | typed-RPC errors raised through the arbiter                               |
 /// | `router.*`     | router-level events (hot reloads, future config swaps)                    |
+/// | `file.*`       | filesystem changes reported by `watch::FileWatcher`                       |
+/// | `config.*`     | configuration reloaded from disk                                          |
+/// | `tls.*`        | TLS certificates reloaded from disk                                       |
+/// | `ws.*`         | tracked WebSocket connections opened / closed (`ws::WsRegistry`)          |
 ///
 /// `route.request.*` is intentionally a separate id (not an alias of
 /// `request.*`) because the two are emitted on different arbiters: the route
@@ -37,12 +41,20 @@ pub mod ids {
   pub const SERVER_STOPPED: &str = "server.stopped";
   pub const CONNECTION_OPENED: &str = "connection.opened";
   pub const CONNECTION_CLOSED: &str = "connection.closed";
+  pub const CONNECTION_REJECTED: &str = "connection.rejected";
   pub const REQUEST_STARTED: &str = "request.started";
   pub const REQUEST_COMPLETED: &str = "request.completed";
   pub const ROUTER_HOT_RELOAD: &str = "router.hot_reload";
   pub const RPC_ERROR: &str = "rpc.error";
   pub const ROUTE_REQUEST_STARTED: &str = "route.request.started";
   pub const ROUTE_REQUEST_COMPLETED: &str = "route.request.completed";
+  pub const FILE_CHANGED: &str = "file.changed";
+  pub const CONFIG_RELOADED: &str = "config.reloaded";
+  pub const TLS_CERT_RELOADED: &str = "tls.cert_reloaded";
+  pub const PLUGIN_INITIALIZED: &str = "plugin.initialized";
+  pub const PLUGIN_FAILED: &str = "plugin.failed";
+  pub const WS_CONNECTED: &str = "ws.connected";
+  pub const WS_DISCONNECTED: &str = "ws.disconnected";
 }
 
 /// Cluster-scope signal bridge.
@@ -3,8 +3,11 @@
 //! `Router::dispatch` already emits the per-request `REQUEST_STARTED` /
 //! `REQUEST_COMPLETED` signals automatically; these helpers cover the
 //! connection-level events (`SERVER_STARTED`, `CONNECTION_OPENED`,
-//! `CONNECTION_CLOSED`) that have no natural per-request hook. They keep the
-//! emit boilerplate out of every transport file.
+//! `CONNECTION_CLOSED`, `CONNECTION_REJECTED`) and the startup plugin
+//! events (`PLUGIN_INITIALIZED`, `PLUGIN_FAILED`) and tracked WebSocket
+//! connections (`WS_CONNECTED`, `WS_DISCONNECTED`) that have no natural
+//! per-request hook. They keep the emit boilerplate out of every transport
+//! file.
 
 use super::arbiter::SignalArbiter;
 use super::signal::Signal;
@@ -42,3 +45,51 @@ pub async fn emit_connection_closed(remote_addr: &str, tls: bool, protocol: Opti
   }
   SignalArbiter::emit_app(sig).await;
 }
+
+/// Emits the `connection.rejected` signal with `remote_addr` / `reason` meta.
+pub async fn emit_connection_rejected(remote_addr: &str, reason: &str) {
+  SignalArbiter::emit_app(
+    Signal::with_capacity(ids::CONNECTION_REJECTED, 2)
+      .meta("remote_addr", remote_addr)
+      .meta("reason", reason),
+  )
+  .await;
+}
+
+/// Emits the `plugin.initialized` signal with `plugin` meta.
+pub async fn emit_plugin_initialized(plugin: &str) {
+  SignalArbiter::emit_app(Signal::with_capacity(ids::PLUGIN_INITIALIZED, 1).meta("plugin", plugin))
+    .await;
+}
+
+/// Emits the `plugin.failed` signal with `plugin` / `error` meta.
+pub async fn emit_plugin_failed(plugin: &str, error: &str) {
+  SignalArbiter::emit_app(
+    Signal::with_capacity(ids::PLUGIN_FAILED, 2)
+      .meta("plugin", plugin)
+      .meta("error", error),
+  )
+  .await;
+}
+
+/// Emits the `ws.connected` signal with `id` / `path` / optional `subject` meta.
+pub async fn emit_ws_connected(id: &str, path: &str, subject: Option<&str>) {
+  let mut sig = Signal::with_capacity(ids::WS_CONNECTED, 3)
+    .meta("id", id)
+    .meta("path", path);
+  if let Some(s) = subject {
+    sig = sig.meta("subject", s);
+  }
+  SignalArbiter::emit_app(sig).await;
+}
+
+/// Emits the `ws.disconnected` signal with `id` / `path` / optional `subject` meta.
+pub async fn emit_ws_disconnected(id: &str, path: &str, subject: Option<&str>) {
+  let mut sig = Signal::with_capacity(ids::WS_DISCONNECTED, 3)
+    .meta("id", id)
+    .meta("path", path);
+  if let Some(s) = subject {
+    sig = sig.meta("subject", s);
+  }
+  SignalArbiter::emit_app(sig).await;
+}
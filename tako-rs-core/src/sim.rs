@@ -0,0 +1,114 @@
+//! Deterministic simulation of time-based behaviour.
+//!
+//! [`Simulation`] freezes the tokio clock so that everything timed by the
+//! framework — rate-limiter refills, cache TTLs, idempotency expiry, delayed
+//! and retried queue jobs, cron schedules, plugin sweepers — advances only
+//! when the test says so. An hour of TTL takes no wall time to expire, and
+//! the same steps always produce the same outcome.
+//!
+//! The framework reads time through [`clock`](crate::clock), which follows
+//! the paused clock; handler code that wants the same treatment should do
+//! the same.
+//!
+//! A simulation must run on a current-thread runtime (the default for
+//! `#[tokio::test]`). Build routers and plugins after [`Simulation::start`]
+//! so their background tasks pick up the paused clock.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//!
+//! use tako::sim::Simulation;
+//!
+//! #[tokio::test]
+//! async fn limit_resets_after_a_minute() {
+//!   let sim = Simulation::start();
+//!   let router = app(); // with a 10 req/min rate limiter
+//!   for _ in 0..10 {
+//!     assert_eq!(router.dispatch(req()).await.status(), 200);
+//!   }
+//!   assert_eq!(router.dispatch(req()).await.status(), 429);
+//!
+//!   sim.advance(Duration::from_secs(60)).await;
+//!   assert_eq!(router.dispatch(req()).await.status(), 200);
+//! }
+//! ```
+
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use crate::clock;
+
+/// Handle to a paused runtime clock.
+#[derive(Debug)]
+pub struct Simulation {
+  started: Instant,
+}
+
+impl Simulation {
+  /// Pauses the tokio clock of the current runtime.
+  ///
+  /// # Panics
+  ///
+  /// Panics outside a current-thread tokio runtime, or when the clock is
+  /// already paused.
+  pub fn start() -> Self {
+    tokio::time::pause();
+    Self {
+      started: clock::now(),
+    }
+  }
+
+  /// Moves the clock forward by `by`, firing due timers, then lets woken
+  /// tasks run.
+  pub async fn advance(&self, by: Duration) {
+    tokio::time::advance(by).await;
+    self.settle().await;
+  }
+
+  /// Moves the clock forward by `by` in increments of `step`, letting tasks
+  /// run after each one. Use this when periodic work (sweepers, interval
+  /// ticks, queue polling) should fire once per period rather than once for
+  /// the whole jump.
+  pub async fn run_for(&self, by: Duration, step: Duration) {
+    let step = step.max(Duration::from_millis(1));
+    let mut left = by;
+    while !left.is_zero() {
+      let next = step.min(left);
+      self.advance(next).await;
+      left -= next;
+    }
+  }
+
+  /// Lets every runnable task make progress without moving the clock.
+  pub async fn settle(&self) {
+    for _ in 0..16 {
+      tokio::task::yield_now().await;
+    }
+  }
+
+  /// Simulated monotonic time.
+  pub fn now(&self) -> Instant {
+    clock::now()
+  }
+
+  /// Simulated wall-clock time.
+  pub fn system_now(&self) -> SystemTime {
+    clock::system_now()
+  }
+
+  /// Simulated time elapsed since [`start`](Self::start).
+  pub fn elapsed(&self) -> Duration {
+    clock::now().saturating_duration_since(self.started)
+  }
+}
+
+impl Drop for Simulation {
+  fn drop(&mut self) {
+    // Resuming panics when the runtime or the pause is already gone; a
+    // simulation dropped during unwinding must not turn that into an abort.
+    let _ = std::panic::catch_unwind(tokio::time::resume);
+  }
+}
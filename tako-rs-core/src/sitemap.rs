@@ -0,0 +1,392 @@
+//! `sitemap.xml` generation from the route table and dynamic sources.
+//!
+//! [`Sitemap::mount`] lists every static `GET` route registered so far —
+//! templates with parameters, paths whose last segment has a file extension
+//! (`/feed.xml`, `/robots.txt`), and excluded patterns are left out — and
+//! adds URLs produced by [`Sitemap::source`] streams, typically one per
+//! database table of posts or products. Mount it after registering the
+//! pages it should cover.
+//!
+//! The URL list is collected on the first request and reused for
+//! [`Sitemap::max_age`], which is also sent as a public `Cache-Control`.
+//! Past 50 000 URLs (the protocol limit) `/sitemap.xml` becomes a sitemap
+//! index pointing at `/sitemap.xml?page=1`, `?page=2`, and so on.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use futures_util::stream;
+//! use tako::router::Router;
+//! use tako::sitemap::ChangeFreq;
+//! use tako::sitemap::Sitemap;
+//! use tako::sitemap::SitemapUrl;
+//!
+//! let mut router = Router::new();
+//! // ... register pages ...
+//! Sitemap::new("https://example.com")
+//!   .exclude("/admin/*")
+//!   .source(|| {
+//!     stream::iter(["hello-world", "second-post"].map(|slug| {
+//!       SitemapUrl::new(format!("/posts/{slug}"))
+//!         .lastmod("2026-10-01")
+//!         .changefreq(ChangeFreq::Monthly)
+//!     }))
+//!   })
+//!   .mount(&mut router);
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use futures_util::Stream;
+use futures_util::StreamExt;
+use futures_util::stream::BoxStream;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header::CACHE_CONTROL;
+use http::header::CONTENT_TYPE;
+use parking_lot::Mutex;
+
+use crate::body::TakoBody;
+use crate::clock;
+use crate::robots::public_max_age;
+use crate::route::Route;
+use crate::router::Router;
+use crate::types::Request;
+use crate::types::Response;
+
+/// URLs per sitemap file allowed by the sitemaps protocol.
+pub const MAX_URLS: usize = 50_000;
+
+/// How often a page is likely to change; a hint crawlers may ignore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeFreq {
+  Always,
+  Hourly,
+  Daily,
+  Weekly,
+  Monthly,
+  Yearly,
+  Never,
+}
+
+impl fmt::Display for ChangeFreq {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Self::Always => "always",
+      Self::Hourly => "hourly",
+      Self::Daily => "daily",
+      Self::Weekly => "weekly",
+      Self::Monthly => "monthly",
+      Self::Yearly => "yearly",
+      Self::Never => "never",
+    })
+  }
+}
+
+/// One `<url>` entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapUrl {
+  loc: String,
+  lastmod: Option<String>,
+  changefreq: Option<ChangeFreq>,
+  priority: Option<f32>,
+}
+
+impl SitemapUrl {
+  /// A URL given as a path (`/posts/1`, joined to the sitemap's base URL)
+  /// or as an absolute `http(s)://` URL.
+  pub fn new(loc: impl Into<String>) -> Self {
+    Self {
+      loc: loc.into(),
+      lastmod: None,
+      changefreq: None,
+      priority: None,
+    }
+  }
+
+  /// Last modification, as a W3C datetime (`2026-10-01` or
+  /// `2026-10-01T12:00:00+00:00`).
+  pub fn lastmod(mut self, lastmod: impl Into<String>) -> Self {
+    self.lastmod = Some(lastmod.into());
+    self
+  }
+
+  /// Expected change frequency.
+  pub fn changefreq(mut self, changefreq: ChangeFreq) -> Self {
+    self.changefreq = Some(changefreq);
+    self
+  }
+
+  /// Priority relative to the site's other URLs, clamped to `0.0..=1.0`.
+  pub fn priority(mut self, priority: f32) -> Self {
+    self.priority = Some(priority.clamp(0.0, 1.0));
+    self
+  }
+
+  fn absolute(&self, base: &str) -> String {
+    if self.loc.starts_with("http://") || self.loc.starts_with("https://") {
+      self.loc.clone()
+    } else {
+      format!("{base}/{}", self.loc.trim_start_matches('/'))
+    }
+  }
+}
+
+type Source = Arc<dyn Fn() -> BoxStream<'static, SitemapUrl> + Send + Sync>;
+
+/// A sitemap built from the route table and dynamic URL sources.
+#[derive(Clone)]
+pub struct Sitemap {
+  base: String,
+  path: String,
+  include_routes: bool,
+  exclude: Vec<String>,
+  sources: Vec<Source>,
+  max_age: Duration,
+  urls_per_file: usize,
+}
+
+impl Sitemap {
+  /// A sitemap for the site at `base_url` (e.g. `https://example.com`),
+  /// served at `/sitemap.xml` and refreshed hourly.
+  pub fn new(base_url: impl Into<String>) -> Self {
+    Self {
+      base: base_url.into().trim_end_matches('/').to_string(),
+      path: "/sitemap.xml".to_string(),
+      include_routes: true,
+      exclude: Vec::new(),
+      sources: Vec::new(),
+      max_age: Duration::from_secs(3600),
+      urls_per_file: MAX_URLS,
+    }
+  }
+
+  /// Serves the sitemap at `path` instead of `/sitemap.xml`.
+  pub fn path(mut self, path: impl Into<String>) -> Self {
+    self.path = path.into();
+    self
+  }
+
+  /// Whether static `GET` routes are listed (default `true`).
+  pub fn include_routes(mut self, yes: bool) -> Self {
+    self.include_routes = yes;
+    self
+  }
+
+  /// Leaves matching routes out: an exact path, or a prefix ending in `*`
+  /// such as `/admin/*`. Applies to route-derived URLs only.
+  pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+    self.exclude.push(pattern.into());
+    self
+  }
+
+  /// Adds a dynamic URL source, re-run whenever the list is refreshed. An
+  /// entry for a path the route table also yields replaces the route's.
+  pub fn source<F, S>(mut self, source: F) -> Self
+  where
+    F: Fn() -> S + Send + Sync + 'static,
+    S: Stream<Item = SitemapUrl> + Send + 'static,
+  {
+    self.sources.push(Arc::new(move || source().boxed()));
+    self
+  }
+
+  /// How long the URL list and the served documents are cached (default 1
+  /// hour).
+  pub fn max_age(mut self, max_age: Duration) -> Self {
+    self.max_age = max_age;
+    self
+  }
+
+  /// URLs per file before the sitemap is split behind an index (default and
+  /// maximum 50 000).
+  pub fn urls_per_file(mut self, n: usize) -> Self {
+    self.urls_per_file = n.clamp(1, MAX_URLS);
+    self
+  }
+
+  fn excluded(&self, path: &str) -> bool {
+    self
+      .exclude
+      .iter()
+      .any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => path.starts_with(prefix),
+        None => path == pattern,
+      })
+  }
+
+  /// Static `GET` routes worth listing, in path order.
+  fn route_paths(&self, router: &Router) -> Vec<String> {
+    let mut paths: Vec<String> = router
+      .routes
+      .get(&Method::GET)
+      .into_iter()
+      .flatten()
+      .filter_map(std::sync::Weak::upgrade)
+      .map(|route| route.path.clone())
+      .filter(|path| {
+        let last = path.rsplit('/').next().unwrap_or_default();
+        !path.contains('{') && !last.contains('.') && !self.excluded(path)
+      })
+      .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+  }
+
+  /// Serves the sitemap, listing the static `GET` routes registered so far.
+  pub fn mount(self, router: &mut Router) -> Arc<Route> {
+    let routes = if self.include_routes {
+      self.route_paths(router)
+    } else {
+      Vec::new()
+    };
+    let path = self.path.clone();
+    let served = Arc::new(Served {
+      sitemap: self,
+      routes,
+      cached: Mutex::new(None),
+    });
+    router.get(&path, move |req: Request| {
+      let served = served.clone();
+      let page = req.uri().query().and_then(|q| {
+        q.split('&')
+          .find_map(|pair| pair.strip_prefix("page="))
+          .map(|n| n.parse::<usize>().unwrap_or(0))
+      });
+      async move { served.respond(page).await }
+    })
+  }
+}
+
+struct Served {
+  sitemap: Sitemap,
+  routes: Vec<String>,
+  cached: Mutex<Option<(Instant, Arc<Vec<SitemapUrl>>)>>,
+}
+
+impl Served {
+  async fn urls(&self) -> Arc<Vec<SitemapUrl>> {
+    if let Some((at, urls)) = &*self.cached.lock()
+      && clock::now().duration_since(*at) < self.sitemap.max_age
+    {
+      return urls.clone();
+    }
+
+    let mut dynamic = Vec::new();
+    for source in &self.sitemap.sources {
+      dynamic.extend(source().collect::<Vec<_>>().await);
+    }
+    let base = &self.sitemap.base;
+    let overridden: HashSet<String> = dynamic.iter().map(|u| u.absolute(base)).collect();
+    let mut urls: Vec<SitemapUrl> = self
+      .routes
+      .iter()
+      .map(SitemapUrl::new)
+      .filter(|u| !overridden.contains(&u.absolute(base)))
+      .collect();
+    urls.extend(dynamic);
+
+    let urls = Arc::new(urls);
+    *self.cached.lock() = Some((clock::now(), urls.clone()));
+    urls
+  }
+
+  /// The document for `?page=<n>`, or the whole sitemap (an index when it
+  /// spans several files) without one.
+  async fn respond(&self, page: Option<usize>) -> Response {
+    let urls = self.urls().await;
+    let per_file = self.sitemap.urls_per_file;
+    let pages = urls.len().div_ceil(per_file).max(1);
+
+    let body = match page {
+      None if pages > 1 => self.render_index(pages),
+      None => self.render_urls(&urls),
+      Some(n) if (1..=pages).contains(&n) => {
+        let start = (n - 1) * per_file;
+        self.render_urls(&urls[start..urls.len().min(start + per_file)])
+      }
+      Some(_) => {
+        let mut resp = http::Response::new(TakoBody::empty());
+        *resp.status_mut() = StatusCode::NOT_FOUND;
+        return resp;
+      }
+    };
+
+    let mut resp = http::Response::new(TakoBody::from(body));
+    let headers = resp.headers_mut();
+    headers.insert(
+      CONTENT_TYPE,
+      HeaderValue::from_static("application/xml; charset=utf-8"),
+    );
+    headers.insert(CACHE_CONTROL, public_max_age(self.sitemap.max_age));
+    resp
+  }
+
+  fn render_urls(&self, urls: &[SitemapUrl]) -> String {
+    let mut out = String::from(
+      "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+       <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for url in urls {
+      let _ = write!(
+        out,
+        "  <url><loc>{}</loc>",
+        escape(&url.absolute(&self.sitemap.base))
+      );
+      if let Some(lastmod) = &url.lastmod {
+        let _ = write!(out, "<lastmod>{}</lastmod>", escape(lastmod));
+      }
+      if let Some(changefreq) = url.changefreq {
+        let _ = write!(out, "<changefreq>{changefreq}</changefreq>");
+      }
+      if let Some(priority) = url.priority {
+        let _ = write!(out, "<priority>{priority:.1}</priority>");
+      }
+      out.push_str("</url>\n");
+    }
+    out.push_str("</urlset>\n");
+    out
+  }
+
+  fn render_index(&self, pages: usize) -> String {
+    let mut out = String::from(
+      "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+       <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for n in 1..=pages {
+      let _ = writeln!(
+        out,
+        "  <sitemap><loc>{}</loc></sitemap>",
+        escape(&format!(
+          "{}{}?page={n}",
+          self.sitemap.base, self.sitemap.path
+        ))
+      );
+    }
+    out.push_str("</sitemapindex>\n");
+    out
+  }
+}
+
+/// Escapes text for XML element content.
+fn escape(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  for c in s.chars() {
+    match c {
+      '&' => out.push_str("&amp;"),
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '"' => out.push_str("&quot;"),
+      '\'' => out.push_str("&apos;"),
+      c => out.push(c),
+    }
+  }
+  out
+}
@@ -0,0 +1,401 @@
+//! Filesystem watching that reports changes as signals.
+//!
+//! [`FileWatcher`] sits on the platform notification API (inotify, `FSEvents`,
+//! kqueue, `ReadDirectoryChangesW`, through the `notify` crate), coalesces the
+//! burst of events a single save or deploy produces into one batch, runs the
+//! registered change handlers, and then emits one [`FILE_CHANGED`] signal per
+//! changed path. Config reloading ([`Config::watch_json_file`]) and TLS
+//! certificate reloading (`ReloadableResolver::watch_pem` in the server
+//! crate) are built on it, so reload-from-disk features share one mechanism.
+//!
+//! A watched file is observed through its parent directory: editors and
+//! secret mounts replace files by renaming over them, which would silently
+//! end a watch placed on the file itself.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use tako::signals::app_signals;
+//! use tako::signals::ids;
+//! use tako::watch::FileWatcher;
+//!
+//! # async fn example() -> Result<(), tako::watch::WatchError> {
+//! let _handle = FileWatcher::new()
+//!   .path("templates")
+//!   .path("app.json")
+//!   .start()?;
+//!
+//! let mut changes = app_signals().subscribe(ids::FILE_CHANGED);
+//! while let Ok(signal) = changes.recv().await {
+//!   println!("{} {}", signal.metadata["kind"], signal.metadata["path"]);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`FILE_CHANGED`]: crate::signals::ids::FILE_CHANGED
+//! [`Config::watch_json_file`]: crate::config::Config::watch_json_file
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use notify::EventKind;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::signals::Signal;
+use crate::signals::SignalArbiter;
+use crate::signals::app_signals;
+use crate::signals::ids;
+
+/// Metadata key carrying the changed path on emitted signals.
+pub const PATH_KEY: &str = "path";
+/// Metadata key carrying the change kind (`created`, `modified`, `removed`,
+/// or `other`) on emitted signals.
+pub const KIND_KEY: &str = "kind";
+
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(200);
+
+type ChangeHandler = Arc<dyn Fn(Vec<PathBuf>) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Builder for a filesystem watch.
+///
+/// Directories are watched recursively; files are watched individually.
+/// Paths may name files that do not exist yet (their parent directory must),
+/// so a watch can be set up before a certificate or config file is first
+/// written.
+pub struct FileWatcher {
+  paths: Vec<PathBuf>,
+  debounce: Duration,
+  arbiter: SignalArbiter,
+  signal_id: String,
+  handlers: Vec<ChangeHandler>,
+}
+
+impl Default for FileWatcher {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl FileWatcher {
+  /// Creates a watcher with no paths that emits [`ids::FILE_CHANGED`] on the
+  /// global application arbiter.
+  pub fn new() -> Self {
+    Self {
+      paths: Vec::new(),
+      debounce: DEFAULT_DEBOUNCE,
+      arbiter: app_signals().clone(),
+      signal_id: ids::FILE_CHANGED.to_string(),
+      handlers: Vec::new(),
+    }
+  }
+
+  /// Adds a file or directory to watch.
+  pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+    self.paths.push(path.into());
+    self
+  }
+
+  /// How long the tree has to stay quiet before a batch of changes is
+  /// reported. Default: 200 ms.
+  pub fn debounce(mut self, debounce: Duration) -> Self {
+    self.debounce = debounce;
+    self
+  }
+
+  /// Emits on `arbiter` instead of the global application arbiter.
+  pub fn arbiter(mut self, arbiter: SignalArbiter) -> Self {
+    self.arbiter = arbiter;
+    self
+  }
+
+  /// Emits under `id` instead of [`ids::FILE_CHANGED`].
+  pub fn signal_id(mut self, id: impl Into<String>) -> Self {
+    self.signal_id = id.into();
+    self
+  }
+
+  /// Runs `handler` with the changed paths of every batch, before the batch's
+  /// signals are emitted. Handlers run one after another in registration
+  /// order.
+  pub fn on_change<F, Fut>(mut self, handler: F) -> Self
+  where
+    F: Fn(Vec<PathBuf>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self
+      .handlers
+      .push(Arc::new(move |paths| Box::pin(handler(paths))));
+    self
+  }
+
+  /// Starts watching. Must be called inside a Tokio runtime; watching stops
+  /// when the returned handle is dropped.
+  pub fn start(self) -> Result<WatchHandle, WatchError> {
+    if self.paths.is_empty() {
+      return Err(WatchError("no paths to watch".to_string()));
+    }
+    let targets = self
+      .paths
+      .iter()
+      .map(|path| Target::resolve(path))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+      let _ = tx.send(event);
+    })
+    .map_err(|e| WatchError(e.to_string()))?;
+    let mut watched: Vec<(&Path, RecursiveMode)> = Vec::new();
+    // Directories first, so a file inside an already watched tree does not
+    // add a second, non-recursive watch on the same directory.
+    let ordered = targets
+      .iter()
+      .filter(|target| target.dir)
+      .chain(targets.iter().filter(|target| !target.dir));
+    for target in ordered {
+      let (dir, mode) = target.watch_root();
+      let covered = watched.iter().any(|(root, root_mode)| match root_mode {
+        RecursiveMode::Recursive => dir.starts_with(root),
+        RecursiveMode::NonRecursive => *root == dir && mode == RecursiveMode::NonRecursive,
+      });
+      if covered {
+        continue;
+      }
+      watcher
+        .watch(dir, mode)
+        .map_err(|e| WatchError(format!("{}: {e}", dir.display())))?;
+      watched.push((dir, mode));
+    }
+
+    let task = tokio::spawn(run(
+      rx,
+      targets,
+      self.debounce,
+      self.arbiter,
+      self.signal_id,
+      self.handlers,
+    ));
+    Ok(WatchHandle {
+      _watcher: watcher,
+      task,
+    })
+  }
+}
+
+/// A running watch. Dropping it stops watching.
+#[must_use = "watching stops when the handle is dropped"]
+pub struct WatchHandle {
+  _watcher: RecommendedWatcher,
+  task: JoinHandle<()>,
+}
+
+impl std::fmt::Debug for WatchHandle {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("WatchHandle").finish_non_exhaustive()
+  }
+}
+
+impl Drop for WatchHandle {
+  fn drop(&mut self) {
+    self.task.abort();
+  }
+}
+
+/// Error starting a watch: a path that cannot be resolved or a platform
+/// watcher that refuses it.
+#[derive(Debug, Clone)]
+pub struct WatchError(pub String);
+
+impl std::fmt::Display for WatchError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "file watch error: {}", self.0)
+  }
+}
+
+impl std::error::Error for WatchError {}
+
+/// One watched path, made absolute so it compares equal to the paths the
+/// platform reports.
+struct Target {
+  path: PathBuf,
+  dir: bool,
+}
+
+impl Target {
+  fn resolve(path: &Path) -> Result<Self, WatchError> {
+    let err = |e: std::io::Error| WatchError(format!("{}: {e}", path.display()));
+    if fs::metadata(path).is_ok_and(|meta| meta.is_dir()) {
+      return Ok(Self {
+        path: fs::canonicalize(path).map_err(err)?,
+        dir: true,
+      });
+    }
+    let Some(name) = path.file_name() else {
+      return Err(WatchError(format!("{}: not a file name", path.display())));
+    };
+    let parent = path
+      .parent()
+      .filter(|parent| !parent.as_os_str().is_empty())
+      .unwrap_or(Path::new("."));
+    Ok(Self {
+      path: fs::canonicalize(parent).map_err(err)?.join(name),
+      dir: false,
+    })
+  }
+
+  fn watch_root(&self) -> (&Path, RecursiveMode) {
+    if self.dir {
+      (&self.path, RecursiveMode::Recursive)
+    } else {
+      let parent = self
+        .path
+        .parent()
+        .expect("resolved file paths have a parent");
+      (parent, RecursiveMode::NonRecursive)
+    }
+  }
+
+  fn matches(&self, path: &Path) -> bool {
+    if self.dir {
+      path.starts_with(&self.path)
+    } else {
+      path == self.path
+    }
+  }
+}
+
+fn kind_label(kind: EventKind) -> Option<&'static str> {
+  match kind {
+    // Reads, including our own reloads, are not changes.
+    EventKind::Access(_) => None,
+    EventKind::Create(_) => Some("created"),
+    EventKind::Modify(_) => Some("modified"),
+    EventKind::Remove(_) => Some("removed"),
+    EventKind::Any | EventKind::Other => Some("other"),
+  }
+}
+
+async fn run(
+  mut rx: mpsc::UnboundedReceiver<notify::Result<notify::Event>>,
+  targets: Vec<Target>,
+  debounce: Duration,
+  arbiter: SignalArbiter,
+  signal_id: String,
+  handlers: Vec<ChangeHandler>,
+) {
+  let collect = |batch: &mut BTreeMap<PathBuf, &'static str>,
+                 event: notify::Result<notify::Event>| {
+    let event = match event {
+      Ok(event) => event,
+      Err(e) => {
+        tracing::warn!(error = %e, "file watch error");
+        return;
+      }
+    };
+    let Some(kind) = kind_label(event.kind) else {
+      return;
+    };
+    for path in event.paths {
+      if targets.iter().any(|target| target.matches(&path)) {
+        batch.insert(path, kind);
+      }
+    }
+  };
+
+  while let Some(event) = rx.recv().await {
+    let mut batch = BTreeMap::new();
+    collect(&mut batch, event);
+    let mut closed = false;
+    loop {
+      match tokio::time::timeout(debounce, rx.recv()).await {
+        Ok(Some(event)) => collect(&mut batch, event),
+        Ok(None) => {
+          closed = true;
+          break;
+        }
+        Err(_) => break,
+      }
+    }
+
+    if !batch.is_empty() {
+      let paths: Vec<PathBuf> = batch.keys().cloned().collect();
+      for handler in &handlers {
+        handler(paths.clone()).await;
+      }
+      for (path, kind) in batch {
+        arbiter
+          .emit(
+            Signal::new(signal_id.clone())
+              .meta(PATH_KEY, path.display().to_string())
+              .meta(KIND_KEY, kind),
+          )
+          .await;
+      }
+    }
+    if closed {
+      return;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn reports_debounced_changes_to_watched_files() {
+    let root = std::env::temp_dir().join(format!("tako-watch-{}", std::process::id()));
+    fs::create_dir_all(root.join("nested")).unwrap();
+    let file = root.join("app.json");
+    let arbiter = SignalArbiter::new();
+    let mut rx = arbiter.subscribe(ids::FILE_CHANGED);
+    let (seen_tx, mut seen_rx) = mpsc::unbounded_channel();
+
+    let _handle = FileWatcher::new()
+      .path(&file)
+      .path(root.join("nested"))
+      .debounce(Duration::from_millis(50))
+      .arbiter(arbiter)
+      .on_change(move |paths| {
+        let seen_tx = seen_tx.clone();
+        async move {
+          let _ = seen_tx.send(paths);
+        }
+      })
+      .start()
+      .unwrap();
+
+    fs::write(root.join("ignored.txt"), "x").unwrap();
+    fs::write(&file, "{}").unwrap();
+    fs::write(&file, "{\"a\":1}").unwrap();
+
+    let paths = tokio::time::timeout(Duration::from_secs(5), seen_rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+    let file = fs::canonicalize(&file).unwrap();
+    assert_eq!(paths, std::slice::from_ref(&file));
+    let signal = rx.recv().await.unwrap();
+    assert_eq!(signal.metadata[PATH_KEY], file.display().to_string());
+
+    fs::write(root.join("nested/page.html"), "<p>").unwrap();
+    let paths = tokio::time::timeout(Duration::from_secs(5), seen_rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+    assert!(paths[0].ends_with("nested/page.html"));
+
+    fs::remove_dir_all(&root).unwrap();
+  }
+}
@@ -0,0 +1,378 @@
+//! `/.well-known/*` documents.
+//!
+//! [`WellKnown`] gathers the small documents clients look up under
+//! `/.well-known/` and serves each with the content type its specification
+//! requires and a public `Cache-Control`:
+//!
+//! - `security.txt` (RFC 9116), built with [`SecurityTxt`];
+//! - `change-password`, a redirect to the password-change page;
+//! - `openid-configuration`, fetched from the identity provider and passed
+//!   through (`client` feature);
+//! - `assetlinks.json` for Android app links, and any other JSON or raw
+//!   document through [`WellKnown::json`] and [`WellKnown::document`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use serde_json::json;
+//! use tako::router::Router;
+//! use tako::well_known::SecurityTxt;
+//! use tako::well_known::WellKnown;
+//!
+//! let mut router = Router::new();
+//! WellKnown::new()
+//!   .security_txt(
+//!     SecurityTxt::new("mailto:security@example.com", "2027-10-01T00:00:00Z")
+//!       .policy("https://example.com/security-policy"),
+//!   )
+//!   .change_password("/account/password")
+//!   .assetlinks(json!([{
+//!     "relation": ["delegate_permission/common.handle_all_urls"],
+//!     "target": {
+//!       "namespace": "android_app",
+//!       "package_name": "com.example.app",
+//!       "sha256_cert_fingerprints": ["14:6D:E9:83:C5:73:06:50"]
+//!     }
+//!   }]))
+//!   .mount(&mut router);
+//! ```
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::CACHE_CONTROL;
+use http::header::CONTENT_TYPE;
+use http::header::LOCATION;
+use serde_json::Value;
+
+use crate::body::TakoBody;
+#[cfg(all(feature = "client", not(feature = "compio")))]
+use crate::client::Client;
+use crate::robots::public_max_age;
+use crate::route::Route;
+use crate::router::Router;
+use crate::types::Request;
+use crate::types::Response;
+
+/// A `security.txt` document (RFC 9116).
+#[derive(Debug, Clone)]
+pub struct SecurityTxt {
+  contacts: Vec<String>,
+  expires: String,
+  fields: Vec<(&'static str, String)>,
+}
+
+impl SecurityTxt {
+  /// A document with one contact (`mailto:`, `tel:` or `https:` URI) that
+  /// expires at `expires`, an RFC 3339 timestamp such as
+  /// `2027-10-01T00:00:00Z`. The RFC recommends expiring within a year.
+  pub fn new(contact: impl Into<String>, expires: impl Into<String>) -> Self {
+    Self {
+      contacts: vec![contact.into()],
+      expires: expires.into(),
+      fields: Vec::new(),
+    }
+  }
+
+  /// Adds another contact, listed in order of preference.
+  pub fn contact(mut self, uri: impl Into<String>) -> Self {
+    self.contacts.push(uri.into());
+    self
+  }
+
+  /// URL of the key to encrypt reports with.
+  pub fn encryption(mut self, url: impl Into<String>) -> Self {
+    self.fields.push(("Encryption", url.into()));
+    self
+  }
+
+  /// URL of the page thanking reporters.
+  pub fn acknowledgments(mut self, url: impl Into<String>) -> Self {
+    self.fields.push(("Acknowledgments", url.into()));
+    self
+  }
+
+  /// Languages reports may be written in, e.g. `en, de`.
+  pub fn preferred_languages(mut self, languages: impl Into<String>) -> Self {
+    self.fields.push(("Preferred-Languages", languages.into()));
+    self
+  }
+
+  /// Canonical URL of the document, so copies can be told apart.
+  pub fn canonical(mut self, url: impl Into<String>) -> Self {
+    self.fields.push(("Canonical", url.into()));
+    self
+  }
+
+  /// URL of the vulnerability disclosure policy.
+  pub fn policy(mut self, url: impl Into<String>) -> Self {
+    self.fields.push(("Policy", url.into()));
+    self
+  }
+
+  /// URL of security-related job openings.
+  pub fn hiring(mut self, url: impl Into<String>) -> Self {
+    self.fields.push(("Hiring", url.into()));
+    self
+  }
+
+  /// The document text.
+  pub fn render(&self) -> String {
+    let mut out = String::new();
+    for contact in &self.contacts {
+      let _ = writeln!(out, "Contact: {contact}");
+    }
+    let _ = writeln!(out, "Expires: {}", self.expires);
+    for (field, value) in &self.fields {
+      let _ = writeln!(out, "{field}: {value}");
+    }
+    out
+  }
+}
+
+enum Document {
+  Static {
+    content_type: HeaderValue,
+    body: Bytes,
+  },
+  Redirect(HeaderValue),
+  #[cfg(all(feature = "client", not(feature = "compio")))]
+  Upstream(Arc<Upstream>),
+}
+
+/// A document fetched from elsewhere and cached for `max_age`.
+#[cfg(all(feature = "client", not(feature = "compio")))]
+struct Upstream {
+  client: Client,
+  url: String,
+  cached: parking_lot::Mutex<Option<(std::time::Instant, HeaderValue, Bytes)>>,
+}
+
+#[cfg(all(feature = "client", not(feature = "compio")))]
+impl Upstream {
+  /// The cached copy while fresh, otherwise a new fetch. When the fetch
+  /// fails a stale copy is better than none; without one the answer is
+  /// `502 Bad Gateway`.
+  async fn fetch(&self, max_age: Duration) -> Result<(HeaderValue, Bytes), StatusCode> {
+    let stale = match &*self.cached.lock() {
+      Some((at, content_type, body)) if crate::clock::now().duration_since(*at) < max_age => {
+        return Ok((content_type.clone(), body.clone()));
+      }
+      cached => cached
+        .as_ref()
+        .map(|(_, ct, body)| (ct.clone(), body.clone())),
+    };
+    let fetched = async {
+      let resp = self
+        .client
+        .get(&self.url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+      let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .cloned()
+        .unwrap_or_else(|| HeaderValue::from_static("application/json"));
+      Some((content_type, resp.bytes().await.ok()?))
+    };
+    match fetched.await {
+      Some((content_type, body)) => {
+        *self.cached.lock() = Some((crate::clock::now(), content_type.clone(), body.clone()));
+        Ok((content_type, body))
+      }
+      None => stale.ok_or(StatusCode::BAD_GATEWAY),
+    }
+  }
+}
+
+/// A set of `/.well-known/*` documents.
+pub struct WellKnown {
+  documents: Vec<(String, Document)>,
+  max_age: Duration,
+}
+
+impl Default for WellKnown {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl WellKnown {
+  /// No documents yet; each is cached for a day once added.
+  pub fn new() -> Self {
+    Self {
+      documents: Vec::new(),
+      max_age: Duration::from_secs(24 * 3600),
+    }
+  }
+
+  /// Serves `security.txt` as `text/plain`.
+  pub fn security_txt(self, doc: SecurityTxt) -> Self {
+    self.document("security.txt", "text/plain; charset=utf-8", doc.render())
+  }
+
+  /// Redirects `change-password` to `url`, the page where signed-in users
+  /// change their password, so password managers can link to it.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `url` is not a valid header value.
+  pub fn change_password(mut self, url: impl AsRef<str>) -> Self {
+    let location = HeaderValue::from_str(url.as_ref())
+      .expect("WellKnown::change_password: url is not a valid header value");
+    self
+      .documents
+      .push(("change-password".to_string(), Document::Redirect(location)));
+    self
+  }
+
+  /// Passes through the `OpenID` Connect discovery document at `url`,
+  /// usually `https://<issuer>/.well-known/openid-configuration`, so
+  /// clients can discover an external identity provider through this
+  /// origin. The document is fetched on first use and refreshed after
+  /// [`WellKnown::max_age`]; if the provider is unreachable the last copy
+  /// is served.
+  #[cfg(all(feature = "client", not(feature = "compio")))]
+  #[cfg_attr(docsrs, doc(cfg(feature = "client")))]
+  pub fn openid_configuration(self, url: impl Into<String>) -> Self {
+    self.openid_configuration_with(Client::new(), url)
+  }
+
+  /// [`WellKnown::openid_configuration`] through `client`.
+  #[cfg(all(feature = "client", not(feature = "compio")))]
+  #[cfg_attr(docsrs, doc(cfg(feature = "client")))]
+  pub fn openid_configuration_with(mut self, client: Client, url: impl Into<String>) -> Self {
+    let upstream = Upstream {
+      client,
+      url: url.into(),
+      cached: parking_lot::Mutex::new(None),
+    };
+    self.documents.push((
+      "openid-configuration".to_string(),
+      Document::Upstream(Arc::new(upstream)),
+    ));
+    self
+  }
+
+  /// Serves `assetlinks.json`, the Digital Asset Links statement list that
+  /// verifies Android app links.
+  pub fn assetlinks(self, statements: Value) -> Self {
+    self.json("assetlinks.json", statements)
+  }
+
+  /// Serves `value` as `application/json` under `name`.
+  pub fn json(self, name: impl Into<String>, value: Value) -> Self {
+    self.document(name, "application/json", value.to_string())
+  }
+
+  /// Serves `body` with `content_type` under `name`, e.g.
+  /// `apple-app-site-association`.
+  pub fn document(
+    mut self,
+    name: impl Into<String>,
+    content_type: &'static str,
+    body: impl Into<Bytes>,
+  ) -> Self {
+    let name = name.into();
+    let name = name
+      .trim_start_matches('/')
+      .trim_start_matches(".well-known/")
+      .to_string();
+    self.documents.push((
+      name,
+      Document::Static {
+        content_type: HeaderValue::from_static(content_type),
+        body: body.into(),
+      },
+    ));
+    self
+  }
+
+  /// `Cache-Control: max-age` of every document (default 1 day). Upstream
+  /// documents are refetched after the same period.
+  pub fn max_age(mut self, max_age: Duration) -> Self {
+    self.max_age = max_age;
+    self
+  }
+
+  /// Serves every document at `GET /.well-known/<name>`.
+  pub fn mount(self, router: &mut Router) -> Vec<Arc<Route>> {
+    let cache_control = public_max_age(self.max_age);
+    let max_age = self.max_age;
+    self
+      .documents
+      .into_iter()
+      .map(|(name, document)| {
+        let document = Arc::new(document);
+        let cache_control = cache_control.clone();
+        router.get(&format!("/.well-known/{name}"), move |_req: Request| {
+          let document = document.clone();
+          let cache_control = cache_control.clone();
+          async move { respond(&document, cache_control, max_age).await }
+        })
+      })
+      .collect()
+  }
+}
+
+#[cfg_attr(
+  not(all(feature = "client", not(feature = "compio"))),
+  allow(unused_variables)
+)]
+async fn respond(document: &Document, cache_control: HeaderValue, max_age: Duration) -> Response {
+  let mut resp = http::Response::new(TakoBody::empty());
+  match document {
+    Document::Static { content_type, body } => {
+      *resp.body_mut() = TakoBody::from(body.clone());
+      resp
+        .headers_mut()
+        .insert(CONTENT_TYPE, content_type.clone());
+    }
+    Document::Redirect(location) => {
+      *resp.status_mut() = StatusCode::FOUND;
+      resp.headers_mut().insert(LOCATION, location.clone());
+    }
+    #[cfg(all(feature = "client", not(feature = "compio")))]
+    Document::Upstream(upstream) => match upstream.fetch(max_age).await {
+      Ok((content_type, body)) => {
+        *resp.body_mut() = TakoBody::from(body);
+        resp.headers_mut().insert(CONTENT_TYPE, content_type);
+      }
+      Err(status) => {
+        *resp.status_mut() = status;
+        return resp;
+      }
+    },
+  }
+  resp.headers_mut().insert(CACHE_CONTROL, cache_control);
+  resp
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn security_txt_lists_contacts_first() {
+    let doc = SecurityTxt::new("mailto:security@example.com", "2027-10-01T00:00:00Z")
+      .contact("https://example.com/report")
+      .preferred_languages("en, de")
+      .policy("https://example.com/policy");
+    assert_eq!(
+      doc.render(),
+      "Contact: mailto:security@example.com\n\
+       Contact: https://example.com/report\n\
+       Expires: 2027-10-01T00:00:00Z\n\
+       Preferred-Languages: en, de\n\
+       Policy: https://example.com/policy\n"
+    );
+  }
+}
@@ -0,0 +1,148 @@
+//! CBOR request body extraction and responses.
+//!
+//! [`Cbor<T>`](crate::cbor::Cbor) decodes an `application/cbor` body (RFC
+//! 8949) into any `DeserializeOwned` type and, as a responder, encodes a
+//! `Serialize` value the same way. It reads the body like
+//! [`Json<T>`](tako_rs_core::extractors::json::Json), so the route's body
+//! limit applies and oversized payloads are answered with `413`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::extractors::cbor::Cbor;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize)]
+//! struct Reading {
+//!     sensor: String,
+//!     celsius: f32,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Ack {
+//!     stored: bool,
+//! }
+//!
+//! async fn ingest(Cbor(reading): Cbor<Reading>) -> Cbor<Ack> {
+//!     println!("{}: {}", reading.sensor, reading.celsius);
+//!     Cbor(Ack { stored: true })
+//! }
+//! ```
+
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use http::header::HeaderValue;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::extractors::FromRequest;
+use tako_rs_core::extractors::body_limit::CollectError;
+use tako_rs_core::extractors::body_limit::collect_limited;
+use tako_rs_core::extractors::body_limit::payload_too_large;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// The CBOR media type.
+pub const APPLICATION_CBOR: &str = "application/cbor";
+
+/// CBOR request body extractor and responder.
+#[doc(alias = "cbor")]
+pub struct Cbor<T>(pub T);
+
+/// Error types for CBOR extraction and deserialization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CborError {
+  /// Content-Type header is not `application/cbor` or a `+cbor` type.
+  InvalidContentType,
+  /// Failed to read the request body.
+  BodyReadError(String),
+  /// CBOR decoding failed (malformed data, type mismatch, etc.).
+  DeserializationError(String),
+  /// The request body exceeds the configured body limit (in bytes).
+  PayloadTooLarge(usize),
+}
+
+impl std::fmt::Display for CborError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::InvalidContentType => write!(f, "invalid content type; expected {APPLICATION_CBOR}"),
+      Self::BodyReadError(err) => write!(f, "failed to read request body: {err}"),
+      Self::DeserializationError(err) => write!(f, "failed to deserialize CBOR: {err}"),
+      Self::PayloadTooLarge(limit) => write!(f, "request body exceeds {limit} bytes"),
+    }
+  }
+}
+
+impl std::error::Error for CborError {}
+
+impl Responder for CborError {
+  fn into_response(self) -> Response {
+    match self {
+      Self::PayloadTooLarge(limit) => payload_too_large(limit),
+      other => (StatusCode::BAD_REQUEST, other.to_string()).into_response(),
+    }
+  }
+}
+
+/// Checks if the Content-Type header indicates CBOR content.
+fn is_cbor_content_type(headers: &http::HeaderMap) -> bool {
+  headers
+    .get(CONTENT_TYPE)
+    .and_then(|v| v.to_str().ok())
+    .and_then(|ct| ct.parse::<mime::Mime>().ok())
+    .is_some_and(|mime| {
+      mime.type_() == "application"
+        && (mime.subtype() == "cbor" || mime.suffix().is_some_and(|s| s == "cbor"))
+    })
+}
+
+impl<'a, T> FromRequest<'a> for Cbor<T>
+where
+  T: DeserializeOwned + Send + 'static,
+{
+  type Error = CborError;
+
+  fn from_request(
+    req: &'a mut Request,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    async move {
+      if !is_cbor_content_type(req.headers()) {
+        return Err(CborError::InvalidContentType);
+      }
+
+      let body_bytes = collect_limited(req).await.map_err(|e| match e {
+        CollectError::TooLarge(limit) => CborError::PayloadTooLarge(limit),
+        CollectError::Read(err) => CborError::BodyReadError(err),
+      })?;
+
+      ciborium::from_reader(&body_bytes[..])
+        .map(Cbor)
+        .map_err(|e| CborError::DeserializationError(e.to_string()))
+    }
+  }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut tako_rs_core::openapi::RouteOpenApi) {
+    operation.set_struct_request_body::<T>(APPLICATION_CBOR);
+  }
+}
+
+impl<T> Responder for Cbor<T>
+where
+  T: Serialize,
+{
+  fn into_response(self) -> Response {
+    let mut buf = Vec::new();
+    match ciborium::into_writer(&self.0, &mut buf) {
+      Ok(()) => {
+        let mut res = Response::new(TakoBody::from(buf));
+        res
+          .headers_mut()
+          .insert(CONTENT_TYPE, HeaderValue::from_static(APPLICATION_CBOR));
+        res
+      }
+      Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+  }
+}
@@ -4,6 +4,12 @@
 //! `CookieJar` and integrates with the application's request lifecycle. It allows
 //! extracting, adding, removing, and retrieving cookies from HTTP requests.
 //!
+//! Changes made to a jar are written back as `Set-Cookie` headers by returning
+//! the jar from a handler, or by wrapping another responder with
+//! [`CookieJar::respond`](crate::cookie_jar::CookieJar::respond). Only cookies
+//! added or removed during the request are emitted; cookies that arrived in the
+//! `Cookie` header are not echoed back.
+//!
 //! # Examples
 //!
 //! ```rust
@@ -23,16 +29,39 @@
 //!     }
 //! }
 //! ```
+//!
+//! Setting a cookie with attributes:
+//!
+//! ```rust
+//! use tako::extractors::cookie_jar::{Cookie, CookieJar, SameSite};
+//! use tako::responder::Responder;
+//!
+//! async fn login(mut jar: CookieJar) -> impl Responder {
+//!     jar.add(
+//!         Cookie::build(("session_id", "abc123"))
+//!             .path("/")
+//!             .secure(true)
+//!             .http_only(true)
+//!             .same_site(SameSite::Lax),
+//!     );
+//!     jar.respond("logged in")
+//! }
+//! ```
 use std::convert::Infallible;
 
-use cookie::Cookie;
+pub use cookie::Cookie;
 use cookie::CookieJar as RawJar;
+pub use cookie::SameSite;
 use http::HeaderMap;
+use http::HeaderValue;
 use http::header::COOKIE;
+use http::header::SET_COOKIE;
 use http::request::Parts;
 use tako_rs_core::extractors::FromRequest;
 use tako_rs_core::extractors::FromRequestParts;
+use tako_rs_core::responder::Responder;
 use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
 
 /// Shared `Cookie:` header parser: pulls every well-formed cookie out of the
 /// `Cookie:` header on `headers` and registers it as an *original* entry on
@@ -48,6 +77,17 @@ pub(crate) fn fill_jar_from_header(jar: &mut RawJar, headers: &HeaderMap) {
   }
 }
 
+/// Appends one `Set-Cookie` header per cookie added to or removed from `jar`
+/// since it was filled. Shared by the plain, signed, and private jars, whose
+/// signed / encrypted values all live in the same underlying `RawJar`.
+pub(crate) fn write_jar_delta(jar: &RawJar, headers: &mut HeaderMap) {
+  for cookie in jar.delta() {
+    if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+      headers.append(SET_COOKIE, value);
+    }
+  }
+}
+
 /// A wrapper around the `cookie::CookieJar` that provides methods for managing cookies
 /// in HTTP requests and responses.
 ///
@@ -89,8 +129,9 @@ impl CookieJar {
     Self(jar)
   }
 
-  /// Inserts a cookie into the `CookieJar`.
-  pub fn add(&mut self, cookie: Cookie<'static>) {
+  /// Inserts a cookie into the `CookieJar`. Accepts a `Cookie` or a
+  /// `Cookie::build(..)` builder.
+  pub fn add(&mut self, cookie: impl Into<Cookie<'static>>) {
     self.0.add(cookie);
   }
 
@@ -124,6 +165,27 @@ impl CookieJar {
   pub fn iter(&self) -> impl Iterator<Item = &Cookie<'static>> {
     self.0.iter()
   }
+
+  /// Appends a `Set-Cookie` header for every cookie added or removed through
+  /// this jar.
+  pub fn write_to(&self, headers: &mut HeaderMap) {
+    write_jar_delta(&self.0, headers);
+  }
+
+  /// Converts `inner` into a response and attaches this jar's `Set-Cookie`
+  /// headers to it.
+  pub fn respond<R: Responder>(&self, inner: R) -> Response {
+    let mut resp = inner.into_response();
+    self.write_to(resp.headers_mut());
+    resp
+  }
+}
+
+/// Responds with an empty `200 OK` carrying the jar's `Set-Cookie` headers.
+impl Responder for CookieJar {
+  fn into_response(self) -> Response {
+    self.respond(())
+  }
 }
 
 impl<'a> FromRequest<'a> for CookieJar {
@@ -37,6 +37,7 @@ use tako_rs_core::extractors::FromRequest;
 use tako_rs_core::extractors::FromRequestParts;
 use tako_rs_core::responder::Responder;
 use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
 
 /// A wrapper that provides methods for managing encrypted cookies in HTTP requests and responses.
 ///
@@ -149,8 +150,9 @@ impl CookiePrivate {
     p
   }
 
-  /// Adds a private cookie to the jar.
-  pub fn add(&mut self, cookie: Cookie<'static>) {
+  /// Adds a private cookie to the jar. Accepts a `Cookie` or a
+  /// `Cookie::build(..)` builder.
+  pub fn add(&mut self, cookie: impl Into<Cookie<'static>>) {
     self.jar.private_mut(&self.key).add(cookie);
   }
 
@@ -224,6 +226,20 @@ impl CookiePrivate {
     &self.key
   }
 
+  /// Appends a `Set-Cookie` header for every cookie added or removed through
+  /// this jar.
+  pub fn write_to(&self, headers: &mut HeaderMap) {
+    crate::cookie_jar::write_jar_delta(&self.jar, headers);
+  }
+
+  /// Converts `inner` into a response and attaches this jar's `Set-Cookie`
+  /// headers to it.
+  pub fn respond<R: Responder>(&self, inner: R) -> Response {
+    let mut resp = inner.into_response();
+    self.write_to(resp.headers_mut());
+    resp
+  }
+
   /// Extracts private cookies from a request, preferring a `KeyRing` over a
   /// single `Key` when both are present in extensions.
   fn extract_from_request(req: &Request) -> Result<Self, CookiePrivateError> {
@@ -262,6 +278,13 @@ impl CookiePrivate {
   }
 }
 
+/// Responds with an empty `200 OK` carrying the jar's `Set-Cookie` headers.
+impl Responder for CookiePrivate {
+  fn into_response(self) -> Response {
+    self.respond(())
+  }
+}
+
 impl<'a> FromRequest<'a> for CookiePrivate {
   type Error = CookiePrivateError;
 
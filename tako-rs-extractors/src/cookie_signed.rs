@@ -32,6 +32,7 @@ mod extract;
 mod jar;
 mod key;
 
+pub use cookie::Key;
 pub use error::CookieSignedError;
 pub use jar::CookieSigned;
 pub use key::KeyRing;
@@ -2,6 +2,8 @@ use cookie::Cookie;
 use cookie::CookieJar;
 use cookie::Key;
 use http::HeaderMap;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::types::Response;
 
 use crate::cookie_signed::KeyRing;
 
@@ -100,8 +102,9 @@ impl CookieSigned {
     None
   }
 
-  /// Adds a signed cookie to the jar.
-  pub fn add(&mut self, cookie: Cookie<'static>) {
+  /// Adds a signed cookie to the jar. Accepts a `Cookie` or a
+  /// `Cookie::build(..)` builder.
+  pub fn add(&mut self, cookie: impl Into<Cookie<'static>>) {
     self.jar.signed_mut(&self.key).add(cookie);
   }
 
@@ -160,6 +163,27 @@ impl CookieSigned {
   pub fn key(&self) -> &Key {
     &self.key
   }
+
+  /// Appends a `Set-Cookie` header for every cookie added or removed through
+  /// this jar.
+  pub fn write_to(&self, headers: &mut HeaderMap) {
+    crate::cookie_jar::write_jar_delta(&self.jar, headers);
+  }
+
+  /// Converts `inner` into a response and attaches this jar's `Set-Cookie`
+  /// headers to it.
+  pub fn respond<R: Responder>(&self, inner: R) -> Response {
+    let mut resp = inner.into_response();
+    self.write_to(resp.headers_mut());
+    resp
+  }
+}
+
+/// Responds with an empty `200 OK` carrying the jar's `Set-Cookie` headers.
+impl Responder for CookieSigned {
+  fn into_response(self) -> Response {
+    self.respond(())
+  }
 }
 
 #[cfg(test)]
@@ -0,0 +1,88 @@
+//! `Disconnected` extractor — notice when the client drops the connection.
+//!
+//! Handlers doing expensive work (report generation, LLM token streaming)
+//! can race that work against the client hanging up and stop early instead
+//! of computing a response nobody will read. The token stays armed after the
+//! handler returns, so a streaming body producer can keep watching it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::extractors::disconnected::Disconnected;
+//! use tako::responder::Responder;
+//!
+//! async fn render(disconnected: Disconnected) -> impl Responder {
+//!     tokio::select! {
+//!         report = build_report() => report,
+//!         () = disconnected.wait() => String::new(),
+//!     }
+//! }
+//! # async fn build_report() -> String { String::new() }
+//! ```
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::future::IntoFuture;
+use std::pin::Pin;
+
+use http::request::Parts;
+use tako_rs_core::disconnect::DisconnectToken;
+use tako_rs_core::extractors::FromRequest;
+use tako_rs_core::extractors::FromRequestParts;
+use tako_rs_core::types::Request;
+
+/// Resolves once the client that sent the request has gone away.
+///
+/// Extraction never fails. Requests that did not come through a tokio
+/// transport (hand-built test requests, custom dispatchers) get a token that
+/// never fires. Awaiting the extractor directly is the same as [`wait`].
+///
+/// [`wait`]: Disconnected::wait
+#[derive(Debug, Clone)]
+pub struct Disconnected(pub DisconnectToken);
+
+impl Disconnected {
+  /// Whether the client has already disconnected.
+  #[inline]
+  pub fn is_disconnected(&self) -> bool {
+    self.0.is_disconnected()
+  }
+
+  /// Resolves once the client has disconnected.
+  pub async fn wait(&self) {
+    self.0.disconnected().await;
+  }
+
+  fn from_extensions(ext: &http::Extensions) -> Self {
+    Self(ext.get::<DisconnectToken>().cloned().unwrap_or_default())
+  }
+}
+
+impl IntoFuture for Disconnected {
+  type Output = ();
+  type IntoFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+  fn into_future(self) -> Self::IntoFuture {
+    Box::pin(async move { self.0.disconnected().await })
+  }
+}
+
+impl<'a> FromRequest<'a> for Disconnected {
+  type Error = Infallible;
+
+  fn from_request(
+    req: &'a mut Request,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    futures_util::future::ready(Ok(Self::from_extensions(req.extensions())))
+  }
+}
+
+impl<'a> FromRequestParts<'a> for Disconnected {
+  type Error = Infallible;
+
+  fn from_request_parts(
+    parts: &'a mut Parts,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    futures_util::future::ready(Ok(Self::from_extensions(&parts.extensions)))
+  }
+}
@@ -23,9 +23,11 @@
 //! ```
 
 use http::StatusCode;
-use http_body_util::BodyExt;
 use serde::de::DeserializeOwned;
 use tako_rs_core::extractors::FromRequest;
+use tako_rs_core::extractors::body_limit::CollectError;
+use tako_rs_core::extractors::body_limit::collect_limited;
+use tako_rs_core::extractors::body_limit::payload_too_large;
 use tako_rs_core::responder::Responder;
 use tako_rs_core::types::Request;
 
@@ -73,6 +75,8 @@ pub enum FormError {
   ParseError(String),
   /// Failed to deserialize form data into the target type.
   DeserializationError(String),
+  /// The request body exceeds the configured body limit (in bytes).
+  PayloadTooLarge(usize),
 }
 
 impl std::fmt::Display for FormError {
@@ -88,6 +92,7 @@ impl std::fmt::Display for FormError {
       Self::InvalidUtf8 => write!(f, "request body contains invalid UTF-8"),
       Self::ParseError(err) => write!(f, "failed to parse form data: {err}"),
       Self::DeserializationError(err) => write!(f, "failed to deserialize form data: {err}"),
+      Self::PayloadTooLarge(limit) => write!(f, "request body exceeds {limit} bytes"),
     }
   }
 }
@@ -98,8 +103,9 @@ impl Responder for FormError {
   /// Converts the error into an HTTP response.
   ///
   /// Maps form extraction errors to appropriate HTTP status codes with descriptive
-  /// error messages. All errors result in `400 Bad Request` as they indicate
-  /// client-side issues with the request format or content.
+  /// error messages. Oversized bodies result in `413 Payload Too Large` with a
+  /// JSON error body; every other error results in `400 Bad Request` as they
+  /// indicate client-side issues with the request format or content.
   ///
   /// # Examples
   ///
@@ -143,6 +149,7 @@ impl Responder for FormError {
         format!("Failed to deserialize form data: {err}"),
       )
         .into_response(),
+      FormError::PayloadTooLarge(limit) => payload_too_large(limit),
     }
   }
 }
@@ -172,13 +179,11 @@ where
         return Err(FormError::InvalidContentType);
       }
 
-      // Read the request body
-      let body_bytes = req
-        .body_mut()
-        .collect()
-        .await
-        .map_err(|e| FormError::BodyReadError(e.to_string()))?
-        .to_bytes();
+      // Read the request body, within the body limit
+      let body_bytes = collect_limited(req).await.map_err(|e| match e {
+        CollectError::TooLarge(limit) => FormError::PayloadTooLarge(limit),
+        CollectError::Read(err) => FormError::BodyReadError(err),
+      })?;
 
       // Convert to string
       let body_str = std::str::from_utf8(&body_bytes).map_err(|_| FormError::InvalidUtf8)?;
@@ -190,4 +195,9 @@ where
       Ok(Form(form_data))
     }
   }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut tako_rs_core::openapi::RouteOpenApi) {
+    operation.set_struct_request_body::<T>("application/x-www-form-urlencoded");
+  }
 }
@@ -0,0 +1,372 @@
+//! JWE-encrypted JSON payloads (RFC 7516 compact serialization).
+//!
+//! For partners that require application-layer encryption on top of TLS,
+//! [`Jwe<T>`](crate::jwe::Jwe) decrypts an `application/jose` request body and
+//! deserializes the plaintext JSON into `T`, and
+//! [`JweResponse<T>`](crate::jwe::JweResponse) serializes a value to JSON and
+//! encrypts it the same way on the way out.
+//!
+//! Keys are shared symmetric content-encryption keys (`alg: "dir"`) used with
+//! `A128GCM` or `A256GCM`, identified by `kid`. A [`JweKeys`](crate::jwe::JweKeys)
+//! ring encrypts with its active key and decrypts with whichever key the
+//! token's `kid` names, so keys can be rotated without breaking in-flight
+//! clients. The extractor reads the ring from request extensions, the same way
+//! the cookie extractors read their `Key`. Key-wrapping and asymmetric
+//! algorithms are not supported.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::extractors::jwe::{Jwe, JweKeys, JweResponse};
+//! use tako::extractors::extension::Extension;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize)]
+//! struct Transfer { amount: u64 }
+//!
+//! #[derive(Serialize)]
+//! struct Receipt { accepted: bool }
+//!
+//! async fn transfer(
+//!     Extension(keys): Extension<JweKeys>,
+//!     Jwe(t): Jwe<Transfer>,
+//! ) -> JweResponse<Receipt> {
+//!     JweResponse::new(keys, Receipt { accepted: t.amount > 0 })
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use http_body_util::BodyExt;
+use ring::aead;
+use ring::rand::SecureRandom;
+use ring::rand::SystemRandom;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::extractors::FromRequest;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// Media type of a compact-serialized JWE.
+pub const APPLICATION_JOSE: &str = "application/jose";
+
+/// Content-encryption algorithm of a [`JweKey`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JweEncryption {
+  /// AES-128 in Galois/Counter Mode.
+  A128Gcm,
+  /// AES-256 in Galois/Counter Mode.
+  A256Gcm,
+}
+
+impl JweEncryption {
+  /// The `enc` header value.
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::A128Gcm => "A128GCM",
+      Self::A256Gcm => "A256GCM",
+    }
+  }
+
+  fn algorithm(self) -> &'static aead::Algorithm {
+    match self {
+      Self::A128Gcm => &aead::AES_128_GCM,
+      Self::A256Gcm => &aead::AES_256_GCM,
+    }
+  }
+}
+
+/// A shared content-encryption key identified by `kid`.
+#[derive(Clone)]
+pub struct JweKey {
+  kid: String,
+  enc: JweEncryption,
+  key: Arc<aead::LessSafeKey>,
+}
+
+impl std::fmt::Debug for JweKey {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("JweKey")
+      .field("kid", &self.kid)
+      .field("enc", &self.enc)
+      .finish_non_exhaustive()
+  }
+}
+
+impl JweKey {
+  /// Creates a key; 16-byte keys use `A128GCM`, 32-byte keys `A256GCM`.
+  pub fn new(kid: impl Into<String>, key: &[u8]) -> Result<Self, JweError> {
+    let enc = match key.len() {
+      16 => JweEncryption::A128Gcm,
+      32 => JweEncryption::A256Gcm,
+      _ => return Err(JweError::InvalidKey),
+    };
+    let unbound = aead::UnboundKey::new(enc.algorithm(), key).map_err(|_| JweError::InvalidKey)?;
+    Ok(Self {
+      kid: kid.into(),
+      enc,
+      key: Arc::new(aead::LessSafeKey::new(unbound)),
+    })
+  }
+
+  /// The key identifier placed in the `kid` header.
+  pub fn kid(&self) -> &str {
+    &self.kid
+  }
+
+  /// The content-encryption algorithm used with this key.
+  pub fn encryption(&self) -> JweEncryption {
+    self.enc
+  }
+}
+
+/// Key ring: encrypts with the active key, decrypts with any known key.
+#[derive(Clone, Debug)]
+pub struct JweKeys {
+  active: JweKey,
+  by_kid: Arc<HashMap<String, JweKey>>,
+}
+
+impl JweKeys {
+  /// Creates a ring whose active key is `active`.
+  pub fn new(active: JweKey) -> Self {
+    let mut by_kid = HashMap::new();
+    by_kid.insert(active.kid.clone(), active.clone());
+    Self {
+      active,
+      by_kid: Arc::new(by_kid),
+    }
+  }
+
+  /// Keeps accepting tokens encrypted under a retired key.
+  pub fn with_previous(mut self, key: JweKey) -> Self {
+    Arc::make_mut(&mut self.by_kid)
+      .entry(key.kid.clone())
+      .or_insert(key);
+    self
+  }
+
+  /// The key new tokens are encrypted with.
+  pub fn active(&self) -> &JweKey {
+    &self.active
+  }
+
+  /// Encrypts `plaintext` into a compact JWE with the active key.
+  pub fn encrypt(&self, plaintext: &[u8]) -> Result<String, JweError> {
+    let key = &self.active;
+    let header = ProtectedHeader {
+      alg: "dir".into(),
+      enc: key.enc.as_str().into(),
+      kid: Some(key.kid.clone()),
+    };
+    let header =
+      URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header).map_err(|_| JweError::EncryptionFailed)?);
+
+    let mut iv = [0u8; aead::NONCE_LEN];
+    SystemRandom::new()
+      .fill(&mut iv)
+      .map_err(|_| JweError::EncryptionFailed)?;
+    let mut buf = plaintext.to_vec();
+    let tag = key
+      .key
+      .seal_in_place_separate_tag(
+        aead::Nonce::assume_unique_for_key(iv),
+        aead::Aad::from(header.as_bytes()),
+        &mut buf,
+      )
+      .map_err(|_| JweError::EncryptionFailed)?;
+
+    Ok(format!(
+      "{header}..{}.{}.{}",
+      URL_SAFE_NO_PAD.encode(iv),
+      URL_SAFE_NO_PAD.encode(&buf),
+      URL_SAFE_NO_PAD.encode(tag.as_ref()),
+    ))
+  }
+
+  /// Decrypts a compact JWE produced with any key in the ring.
+  pub fn decrypt(&self, token: &str) -> Result<Vec<u8>, JweError> {
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    let [header_b64, encrypted_key, iv, ciphertext, tag] = parts[..] else {
+      return Err(JweError::Malformed);
+    };
+    let decode = |s: &str| URL_SAFE_NO_PAD.decode(s).map_err(|_| JweError::Malformed);
+
+    let header: ProtectedHeader =
+      serde_json::from_slice(&decode(header_b64)?).map_err(|_| JweError::Malformed)?;
+    if header.alg != "dir" || !encrypted_key.is_empty() {
+      return Err(JweError::UnsupportedAlgorithm(header.alg));
+    }
+    let key = match &header.kid {
+      Some(kid) => self.by_kid.get(kid).ok_or(JweError::UnknownKey)?,
+      None => &self.active,
+    };
+    if header.enc != key.enc.as_str() {
+      return Err(JweError::UnsupportedAlgorithm(header.enc));
+    }
+
+    let iv: [u8; aead::NONCE_LEN] = decode(iv)?.try_into().map_err(|_| JweError::Malformed)?;
+    let mut buf = decode(ciphertext)?;
+    buf.extend_from_slice(&decode(tag)?);
+    let plaintext = key
+      .key
+      .open_in_place(
+        aead::Nonce::assume_unique_for_key(iv),
+        aead::Aad::from(header_b64.as_bytes()),
+        &mut buf,
+      )
+      .map_err(|_| JweError::DecryptionFailed)?;
+    Ok(plaintext.to_vec())
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProtectedHeader {
+  alg: String,
+  enc: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  kid: Option<String>,
+}
+
+/// Error type for JWE extraction, decryption, and encryption.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JweError {
+  /// No `JweKeys` in request extensions.
+  MissingKeys,
+  /// Key material has an unsupported length.
+  InvalidKey,
+  /// Request content type is not `application/jose`.
+  InvalidContentType,
+  /// Failed to read the request body.
+  BodyReadError(String),
+  /// The token is not a well-formed compact JWE.
+  Malformed,
+  /// The token uses an `alg` / `enc` this ring cannot handle.
+  UnsupportedAlgorithm(String),
+  /// The token's `kid` is not in the ring.
+  UnknownKey,
+  /// Authentication tag mismatch: wrong key or tampered token.
+  DecryptionFailed,
+  /// The plaintext is not valid JSON for the target type.
+  DeserializationError(String),
+  /// The payload could not be serialized or encrypted.
+  EncryptionFailed,
+}
+
+impl std::fmt::Display for JweError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::MissingKeys => write!(f, "JWE keys not configured"),
+      Self::InvalidKey => write!(f, "JWE key must be 16 or 32 bytes"),
+      Self::InvalidContentType => write!(f, "invalid content type; expected {APPLICATION_JOSE}"),
+      Self::BodyReadError(err) => write!(f, "failed to read request body: {err}"),
+      Self::Malformed => write!(f, "malformed JWE"),
+      Self::UnsupportedAlgorithm(alg) => write!(f, "unsupported JWE algorithm `{alg}`"),
+      Self::UnknownKey => write!(f, "unknown JWE key id"),
+      Self::DecryptionFailed => write!(f, "JWE decryption failed"),
+      Self::DeserializationError(err) => write!(f, "failed to deserialize JWE payload: {err}"),
+      Self::EncryptionFailed => write!(f, "JWE encryption failed"),
+    }
+  }
+}
+
+impl std::error::Error for JweError {}
+
+impl Responder for JweError {
+  fn into_response(self) -> Response {
+    let status = match self {
+      Self::MissingKeys | Self::InvalidKey | Self::EncryptionFailed => {
+        StatusCode::INTERNAL_SERVER_ERROR
+      }
+      Self::InvalidContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+      _ => StatusCode::BAD_REQUEST,
+    };
+    (status, self.to_string()).into_response()
+  }
+}
+
+/// Extractor that decrypts an `application/jose` body and deserializes the
+/// JSON plaintext into `T`.
+#[doc(alias = "jwe")]
+pub struct Jwe<T>(pub T);
+
+impl<'a, T> FromRequest<'a> for Jwe<T>
+where
+  T: DeserializeOwned + Send + 'static,
+{
+  type Error = JweError;
+
+  fn from_request(
+    req: &'a mut Request,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    async move {
+      let keys = req
+        .extensions()
+        .get::<JweKeys>()
+        .cloned()
+        .ok_or(JweError::MissingKeys)?;
+      let is_jose = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case(APPLICATION_JOSE));
+      if !is_jose {
+        return Err(JweError::InvalidContentType);
+      }
+
+      let body = req
+        .body_mut()
+        .collect()
+        .await
+        .map_err(|e| JweError::BodyReadError(e.to_string()))?
+        .to_bytes();
+      let token = std::str::from_utf8(&body).map_err(|_| JweError::Malformed)?;
+      let plaintext = keys.decrypt(token)?;
+      serde_json::from_slice(&plaintext)
+        .map(Jwe)
+        .map_err(|e| JweError::DeserializationError(e.to_string()))
+    }
+  }
+}
+
+/// Responder that serializes `T` to JSON and encrypts it with the ring's
+/// active key.
+pub struct JweResponse<T> {
+  keys: JweKeys,
+  value: T,
+}
+
+impl<T> JweResponse<T> {
+  /// Encrypts `value` with `keys` when converted into a response.
+  pub fn new(keys: JweKeys, value: T) -> Self {
+    Self { keys, value }
+  }
+}
+
+impl<T: Serialize> Responder for JweResponse<T> {
+  fn into_response(self) -> Response {
+    let token = serde_json::to_vec(&self.value)
+      .map_err(|_| JweError::EncryptionFailed)
+      .and_then(|json| self.keys.encrypt(&json));
+    match token {
+      Ok(token) => {
+        let mut resp = Response::new(TakoBody::from(token));
+        resp
+          .headers_mut()
+          .insert(CONTENT_TYPE, HeaderValue::from_static(APPLICATION_JOSE));
+        resp
+      }
+      Err(e) => e.into_response(),
+    }
+  }
+}
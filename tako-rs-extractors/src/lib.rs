@@ -25,6 +25,11 @@ pub mod basic;
 /// Bearer token authentication extraction from Authorization header.
 pub mod bearer;
 
+/// CBOR request body parsing and responses (requires `cbor` feature).
+#[cfg(feature = "cbor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+pub mod cbor;
+
 /// Raw byte data extraction from request bodies.
 pub mod bytes;
 
@@ -34,6 +39,9 @@ pub mod connect_info;
 /// `ContentLengthLimit<T, N>` body-bound extractor wrapper.
 pub mod content_length_limit;
 
+/// `Disconnected` extractor that resolves once the client goes away.
+pub mod disconnected;
+
 /// `Extension<T>` typed extractor for request-scoped values.
 pub mod extension;
 
@@ -69,6 +77,11 @@ pub mod header_map;
 /// IP address extraction from request headers and connection info.
 pub mod ipaddr;
 
+/// JWE-encrypted JSON request/response payloads.
+#[cfg(feature = "jwe")]
+#[cfg_attr(docsrs, doc(cfg(feature = "jwe")))]
+pub mod jwe;
+
 /// JSON Web Token (JWT) handling with HMAC verification.
 pub mod jwt;
 
@@ -78,9 +91,20 @@ pub mod path;
 /// Query parameter parsing from URL query strings.
 pub mod query;
 
+/// Charset-aware text bodies (requires `charset` feature).
+#[cfg(feature = "charset")]
+#[cfg_attr(docsrs, doc(cfg(feature = "charset")))]
+pub mod text;
+
 /// Multi-value query parser preserving repeated keys and CSV expansions.
 pub mod query_multi;
 
+/// Bracketed arrays and nested maps in query strings (requires
+/// `query-nested` feature).
+#[cfg(feature = "query-nested")]
+#[cfg_attr(docsrs, doc(cfg(feature = "query-nested")))]
+pub mod query_nested;
+
 /// `Validated<T>` wrapper that runs `validator` / `garde` rules after extraction.
 #[cfg(any(feature = "validator", feature = "garde"))]
 #[cfg_attr(docsrs, doc(cfg(any(feature = "validator", feature = "garde"))))]
@@ -33,6 +33,7 @@ mod error;
 mod extractor;
 mod field;
 mod limits;
+pub mod scan;
 
 pub use error::MultipartError;
 pub use error::TypedMultipartError;
@@ -44,3 +45,9 @@ pub use field::InMemoryFile;
 pub use field::TempFileCleanup;
 pub use field::UploadedFile;
 pub use limits::MultipartConfig;
+pub use scan::ClamdScanner;
+pub use scan::ScanContent;
+pub use scan::ScanError;
+pub use scan::ScanTarget;
+pub use scan::ScanVerdict;
+pub use scan::UploadScanner;
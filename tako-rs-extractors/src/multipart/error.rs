@@ -1,4 +1,5 @@
 use http::StatusCode;
+use tako_rs_core::extractors::body_limit::payload_too_large;
 use tako_rs_core::responder::Responder;
 
 /// Error type for multipart extraction.
@@ -16,6 +17,8 @@ pub enum MultipartError {
   DisallowedContentType(String),
   /// The configured `max_parts` count was exceeded.
   TooManyParts,
+  /// The request body exceeds the configured body limit (in bytes).
+  PayloadTooLarge(usize),
 }
 
 impl Responder for MultipartError {
@@ -48,6 +51,7 @@ impl Responder for MultipartError {
         "too many multipart parts in request",
       )
         .into_response(),
+      MultipartError::PayloadTooLarge(limit) => payload_too_large(limit),
     }
   }
 }
@@ -73,6 +77,12 @@ pub enum TypedMultipartError {
   DisallowedContentType(String),
   /// The configured `max_parts` count was exceeded.
   TooManyParts,
+  /// The request body exceeds the configured body limit (in bytes).
+  PayloadTooLarge(usize),
+  /// The upload scanner found malicious content; carries the signature.
+  Infected(String),
+  /// The upload scanner failed, so the upload was rejected.
+  ScanFailed(String),
 }
 
 impl Responder for TypedMultipartError {
@@ -120,6 +130,17 @@ impl Responder for TypedMultipartError {
         "too many multipart parts in request",
       )
         .into_response(),
+      TypedMultipartError::PayloadTooLarge(limit) => payload_too_large(limit),
+      TypedMultipartError::Infected(_) => (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "upload rejected by content scan",
+      )
+        .into_response(),
+      TypedMultipartError::ScanFailed(_) => (
+        StatusCode::SERVICE_UNAVAILABLE,
+        "upload scanner unavailable",
+      )
+        .into_response(),
     }
   }
 }
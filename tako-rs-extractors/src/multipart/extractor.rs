@@ -5,11 +5,15 @@ use serde::de::DeserializeOwned;
 use serde_json::Map;
 use serde_json::Value;
 use tako_rs_core::extractors::FromRequest;
+use tako_rs_core::extractors::body_limit::body_limit;
+use tako_rs_core::extractors::body_limit::exceeds_content_length;
+use tako_rs_core::extractors::body_limit::is_length_limit_error;
 use tako_rs_core::types::Request;
 
 use crate::multipart::FromMultipartField;
 use crate::multipart::MultipartConfig;
 use crate::multipart::MultipartError;
+use crate::multipart::ScanError;
 use crate::multipart::TypedMultipartError;
 
 /// Wrapper around `multer::Multipart` to provide additional functionality.
@@ -60,6 +64,15 @@ impl<'a> FromRequest<'a> for TakoMultipart<'a> {
   ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
     futures_util::future::ready(Self::extract_multipart(req))
   }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut tako_rs_core::openapi::RouteOpenApi) {
+    operation.request_body = Some(tako_rs_core::openapi::OpenApiRequestBody {
+      required: true,
+      content_type: "multipart/form-data".to_string(),
+      ..Default::default()
+    });
+  }
 }
 
 impl<'a> TakoMultipart<'a> {
@@ -76,7 +89,14 @@ impl<'a> TakoMultipart<'a> {
     let boundary = multer::parse_boundary(content_type_str)
       .map_err(|e| MultipartError::BoundaryParseError(e.to_string()))?;
 
-    let cfg = MultipartConfig::lookup(req.extensions());
+    let limit = body_limit(req.extensions());
+    if let Some(limit) = limit
+      && exceeds_content_length(req, limit)
+    {
+      return Err(MultipartError::PayloadTooLarge(limit));
+    }
+
+    let cfg = MultipartConfig::lookup(req.extensions()).with_body_limit(limit);
     let constraints = cfg.to_constraints();
     let body_stream = req.body_mut().into_data_stream();
     Ok(TakoMultipart(Multipart::with_constraints(
@@ -128,7 +148,14 @@ where
       let boundary = multer::parse_boundary(content_type_str)
         .map_err(|e| TypedMultipartError::BoundaryParseError(e.to_string()))?;
 
-      let cfg = MultipartConfig::lookup(req.extensions());
+      let limit = body_limit(req.extensions());
+      if let Some(limit) = limit
+        && exceeds_content_length(req, limit)
+      {
+        return Err(TypedMultipartError::PayloadTooLarge(limit));
+      }
+
+      let cfg = MultipartConfig::lookup(req.extensions()).with_body_limit(limit);
       let constraints = cfg.to_constraints();
       let mut multipart =
         Multipart::with_constraints(req.body_mut().into_data_stream(), boundary, constraints);
@@ -141,16 +168,14 @@ where
         let field = match field_timeout {
           Some(d) => match tokio::time::timeout(d, next_field_fut).await {
             Ok(Ok(field)) => field,
-            Ok(Err(e)) => return Err(TypedMultipartError::FieldError(e.to_string())),
+            Ok(Err(e)) => return Err(field_error(e, limit)),
             Err(_) => {
               return Err(TypedMultipartError::FieldError(
                 "multipart slow-read timeout".to_string(),
               ));
             }
           },
-          None => next_field_fut
-            .await
-            .map_err(|e| TypedMultipartError::FieldError(e.to_string()))?,
+          None => next_field_fut.await.map_err(|e| field_error(e, limit))?,
         };
         let Some(field) = field else {
           break;
@@ -177,7 +202,7 @@ where
           let file_value: F = match field_timeout {
             Some(d) => match tokio::time::timeout(d, F::from_field(field)).await {
               Ok(Ok(v)) => v,
-              Ok(Err(e)) => return Err(TypedMultipartError::FieldError(e.to_string())),
+              Ok(Err(e)) => return Err(upload_error(e, limit)),
               Err(_) => {
                 return Err(TypedMultipartError::FieldError(
                   "multipart slow-read timeout".to_string(),
@@ -186,7 +211,7 @@ where
             },
             None => F::from_field(field)
               .await
-              .map_err(|e| TypedMultipartError::FieldError(e.to_string()))?,
+              .map_err(|e| upload_error(e, limit))?,
           };
 
           let json_value = serde_json::to_value(file_value)
@@ -197,17 +222,14 @@ where
           let field_bytes = match field_timeout {
             Some(d) => match tokio::time::timeout(d, field.bytes()).await {
               Ok(Ok(b)) => b,
-              Ok(Err(e)) => return Err(TypedMultipartError::FieldError(e.to_string())),
+              Ok(Err(e)) => return Err(field_error(e, limit)),
               Err(_) => {
                 return Err(TypedMultipartError::FieldError(
                   "multipart slow-read timeout".to_string(),
                 ));
               }
             },
-            None => field
-              .bytes()
-              .await
-              .map_err(|e| TypedMultipartError::FieldError(e.to_string()))?,
+            None => field.bytes().await.map_err(|e| field_error(e, limit))?,
           };
 
           let text = String::from_utf8(field_bytes.to_vec())
@@ -226,4 +248,35 @@ where
       })
     }
   }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut tako_rs_core::openapi::RouteOpenApi) {
+    operation.set_struct_request_body::<T>("multipart/form-data");
+  }
+}
+
+/// Maps a `multer` error, reporting an exceeded body limit as `413`.
+fn field_error(e: multer::Error, limit: Option<usize>) -> TypedMultipartError {
+  match e {
+    multer::Error::StreamSizeExceeded { limit } => {
+      TypedMultipartError::PayloadTooLarge(usize::try_from(limit).unwrap_or(usize::MAX))
+    }
+    multer::Error::StreamReadFailed(ref err) if is_length_limit_error(err.as_ref()) => {
+      TypedMultipartError::PayloadTooLarge(limit.unwrap_or_default())
+    }
+    e => TypedMultipartError::FieldError(e.to_string()),
+  }
+}
+
+/// Like [`field_error`], for errors surfaced through `FromMultipartField`.
+fn upload_error(e: anyhow::Error, limit: Option<usize>) -> TypedMultipartError {
+  let e = match e.downcast::<ScanError>() {
+    Ok(ScanError::Infected { signature }) => return TypedMultipartError::Infected(signature),
+    Ok(ScanError::Failed(err)) => return TypedMultipartError::ScanFailed(err),
+    Err(e) => e,
+  };
+  match e.downcast::<multer::Error>() {
+    Ok(e) => field_error(e, limit),
+    Err(e) => TypedMultipartError::FieldError(e.to_string()),
+  }
 }
@@ -1,12 +1,19 @@
+use std::path::Path;
 use std::path::PathBuf;
 
+use bytes::Bytes;
 use serde::Deserialize;
 use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use uuid::Uuid;
 
 use crate::multipart::MultipartConfig;
+use crate::multipart::ScanContent;
+use crate::multipart::ScanTarget;
+use crate::multipart::scan::scan_upload;
 
 /// Trait for types that can be constructed from a multipart field.
 ///
@@ -90,11 +97,116 @@ fn fresh_upload_temp_path() -> PathBuf {
   std::env::temp_dir().join(format!("upload-{}", Uuid::new_v4()))
 }
 
+/// Streams a file part to disk, hashing it on the way when a
+/// content-addressed store is configured.
+struct DiskWriter {
+  path: PathBuf,
+  file: File,
+  cleanup: TempFileCleanup,
+  store: Option<(PathBuf, Sha256)>,
+  size: u64,
+}
+
+impl DiskWriter {
+  async fn create(store: Option<PathBuf>) -> std::io::Result<Self> {
+    // Inside the store the temp file lives next to its final location, so
+    // storing it is a same-filesystem rename.
+    let path = match &store {
+      Some(dir) => {
+        tokio::fs::create_dir_all(dir).await?;
+        dir.join(format!(".upload-{}", Uuid::new_v4()))
+      }
+      None => fresh_upload_temp_path(),
+    };
+    // Register cleanup BEFORE opening the file so an error mid-write still
+    // removes the partial file on early return.
+    let cleanup = TempFileCleanup::for_path(path.clone());
+    let file = File::create(&path).await?;
+    Ok(Self {
+      path,
+      file,
+      cleanup,
+      store: store.map(|dir| (dir, Sha256::new())),
+      size: 0,
+    })
+  }
+
+  async fn write(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+    self.file.write_all(chunk).await?;
+    if let Some((_, hasher)) = &mut self.store {
+      hasher.update(chunk);
+    }
+    self.size += chunk.len() as u64;
+    Ok(())
+  }
+
+  async fn finish(
+    mut self,
+    file_name: Option<String>,
+    content_type: Option<String>,
+  ) -> anyhow::Result<UploadedFile> {
+    self.file.flush().await?;
+    // Scan before anything is stored; on rejection the armed guard removes
+    // the file unless it was quarantined.
+    scan_upload(ScanTarget {
+      file_name: file_name.clone(),
+      content_type: content_type.clone(),
+      size: self.size,
+      content: ScanContent::File(self.path.clone()),
+    })
+    .await?;
+    let mut upload = UploadedFile {
+      file_name,
+      content_type,
+      path: self.path,
+      size: self.size,
+      sha256: None,
+      deduplicated: false,
+      cleanup: self.cleanup,
+    };
+    let Some((dir, hasher)) = self.store else {
+      return Ok(upload);
+    };
+    drop(self.file);
+
+    let digest = format!("{:x}", hasher.finalize());
+    let dest = content_path(&dir, &digest);
+    if tokio::fs::try_exists(&dest).await? {
+      // Dropping the armed guard removes the duplicate temp file.
+      upload.deduplicated = true;
+    } else {
+      if let Some(parent) = dest.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+      }
+      tokio::fs::rename(&upload.path, &dest).await?;
+      upload.cleanup.disarm();
+    }
+    // Stored objects are shared between uploads and never auto-removed.
+    upload.cleanup = TempFileCleanup::default();
+    upload.path = dest;
+    upload.sha256 = Some(digest);
+    Ok(upload)
+  }
+}
+
+/// `<dir>/<first two hex digits>/<hex digest>`.
+fn content_path(dir: &Path, digest: &str) -> PathBuf {
+  dir.join(&digest[..2]).join(digest)
+}
+
+fn content_store() -> Option<PathBuf> {
+  tako_rs_core::state::get_state::<MultipartConfig>().and_then(|c| c.content_store.clone())
+}
+
 /// Represents a file uploaded to the server and saved to disk.
 ///
 /// The on-disk temp file is auto-removed when the `UploadedFile` is dropped
 /// (RAII). Call [`UploadedFile::persist`] or [`UploadedFile::disarm_cleanup`]
 /// before drop if you want to keep the file.
+///
+/// With a [`MultipartConfig::content_store`] configured the upload is
+/// instead deduplicated into the store: `path` points at the stored object,
+/// which may be shared with earlier uploads and is never removed on drop.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UploadedFile {
   /// Original file name provided by the client, if any.
@@ -105,6 +217,12 @@ pub struct UploadedFile {
   pub path: PathBuf,
   /// Size of the uploaded file in bytes.
   pub size: u64,
+  /// Hex SHA-256 of the content, for uploads in a content-addressed store.
+  #[serde(default)]
+  pub sha256: Option<String>,
+  /// Whether identical content was already stored and `path` refers to it.
+  #[serde(default)]
+  pub deduplicated: bool,
   #[serde(skip, default)]
   cleanup: TempFileCleanup,
 }
@@ -118,8 +236,15 @@ impl UploadedFile {
 
   /// Persist the temp file by renaming it to `dest`. On success the cleanup
   /// is disarmed and `self.path` is updated to `dest`.
+  ///
+  /// A content-addressed upload is copied instead, leaving the shared
+  /// stored object in place.
   pub async fn persist(&mut self, dest: PathBuf) -> std::io::Result<()> {
-    tokio::fs::rename(&self.path, &dest).await?;
+    if self.sha256.is_some() {
+      tokio::fs::copy(&self.path, &dest).await?;
+    } else {
+      tokio::fs::rename(&self.path, &dest).await?;
+    }
     self.path = dest;
     self.cleanup.disarm();
     Ok(())
@@ -131,24 +256,11 @@ impl FromMultipartField for UploadedFile {
   async fn from_field(mut field: multer::Field<'_>) -> anyhow::Result<Self> {
     let original = field.file_name().map(std::borrow::ToOwned::to_owned);
     let content_type = field.content_type().map(std::string::ToString::to_string);
-    let tmp_path = fresh_upload_temp_path();
-    // Register cleanup BEFORE opening the file so an error mid-write still
-    // removes the partial file on early return.
-    let cleanup = TempFileCleanup::for_path(tmp_path.clone());
-    let mut outfile = File::create(&tmp_path).await?;
-    let mut bytes_written: u64 = 0;
+    let mut writer = DiskWriter::create(content_store()).await?;
     while let Some(chunk) = field.chunk().await? {
-      outfile.write_all(&chunk).await?;
-      bytes_written += chunk.len() as u64;
+      writer.write(&chunk).await?;
     }
-    outfile.flush().await?;
-    Ok(UploadedFile {
-      file_name: original,
-      content_type,
-      path: tmp_path,
-      size: bytes_written,
-      cleanup,
-    })
+    writer.finish(original, content_type).await
   }
 }
 
@@ -169,7 +281,15 @@ impl FromMultipartField for InMemoryFile {
   async fn from_field(field: multer::Field<'_>) -> anyhow::Result<Self> {
     let file_name = field.file_name().map(std::borrow::ToOwned::to_owned);
     let content_type = field.content_type().map(std::string::ToString::to_string);
-    let data = field.bytes().await?.to_vec();
+    let data = field.bytes().await?;
+    scan_upload(ScanTarget {
+      file_name: file_name.clone(),
+      content_type: content_type.clone(),
+      size: data.len() as u64,
+      content: ScanContent::Memory(data.clone()),
+    })
+    .await?;
+    let data = data.to_vec();
 
     Ok(InMemoryFile {
       file_name,
@@ -200,12 +320,11 @@ impl FromMultipartField for BufferedUploadedFile {
     let content_type = field.content_type().map(std::string::ToString::to_string);
 
     let mut buffer: Vec<u8> = Vec::new();
-    let mut spilled: Option<(PathBuf, File, TempFileCleanup)> = None;
-    let mut bytes_written: u64 = 0;
+    let mut spilled: Option<DiskWriter> = None;
 
     while let Some(chunk) = field.chunk().await? {
-      if let Some((_, ref mut f, _)) = spilled {
-        f.write_all(&chunk).await?;
+      if let Some(writer) = &mut spilled {
+        writer.write(&chunk).await?;
       } else {
         // Try-reserve: a hostile client could send a series of small chunks
         // whose cumulative size eventually exceeds available memory.
@@ -219,31 +338,32 @@ impl FromMultipartField for BufferedUploadedFile {
         if let Some(t) = threshold
           && (buffer.len() as u64) > t
         {
-          let path = fresh_upload_temp_path();
-          let cleanup = TempFileCleanup::for_path(path.clone());
-          let mut f = File::create(&path).await?;
-          f.write_all(&buffer).await?;
-          spilled = Some((path, f, cleanup));
+          let store = cfg.as_ref().and_then(|c| c.content_store.clone());
+          let mut writer = DiskWriter::create(store).await?;
+          writer.write(&buffer).await?;
+          spilled = Some(writer);
           buffer.clear();
         }
       }
-      bytes_written += chunk.len() as u64;
     }
 
-    if let Some((path, mut f, cleanup)) = spilled {
-      f.flush().await?;
-      Ok(BufferedUploadedFile::Disk(UploadedFile {
-        file_name,
-        content_type,
-        path,
-        size: bytes_written,
-        cleanup,
-      }))
+    if let Some(writer) = spilled {
+      Ok(BufferedUploadedFile::Disk(
+        writer.finish(file_name, content_type).await?,
+      ))
     } else {
+      let data = Bytes::from(buffer);
+      scan_upload(ScanTarget {
+        file_name: file_name.clone(),
+        content_type: content_type.clone(),
+        size: data.len() as u64,
+        content: ScanContent::Memory(data.clone()),
+      })
+      .await?;
       Ok(BufferedUploadedFile::Memory(InMemoryFile {
         file_name,
         content_type,
-        data: buffer,
+        data: data.into(),
       }))
     }
   }
@@ -1,14 +1,17 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use multer::Constraints;
 use multer::SizeLimit;
 
+use crate::multipart::UploadScanner;
+
 /// Per-route or global configuration for multipart extraction.
 ///
 /// Insert into request extensions (or set as global state) to constrain how
 /// `TakoMultipart` / `TakoTypedMultipart` consume request bodies. Defaults
 /// are permissive — opt in to limits explicitly.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct MultipartConfig {
   /// Total request body cap, in bytes. `None` = no whole-payload limit.
   pub total_size_limit: Option<u64>,
@@ -30,6 +33,19 @@ pub struct MultipartConfig {
   /// When uploading via `UploadedFile`, switch from in-memory buffering to a
   /// temp file once the part exceeds this many bytes. `None` = always disk.
   pub disk_spill_threshold: Option<u64>,
+  /// Root of a content-addressed upload store. When set, file parts written
+  /// to disk are hashed (SHA-256) while they stream in and kept as
+  /// `<dir>/<first two hex digits>/<hex digest>`; a part whose content is
+  /// already stored is discarded and resolves to the existing object.
+  /// Like the spill threshold, the file types read it from global state.
+  /// `None` = plain temp files.
+  pub content_store: Option<PathBuf>,
+  /// Inspects every received file before the handler sees it; see the
+  /// [`scan`](crate::multipart::scan) module. Read from global state, like
+  /// the content store. `None` = no scanning.
+  pub scanner: Option<Arc<dyn UploadScanner>>,
+  /// Directory infected uploads are moved to instead of being deleted.
+  pub quarantine_dir: Option<PathBuf>,
   /// Maximum time to read a whole multipart field before aborting the
   /// request. Despite the historical "chunk" naming, the timeout currently
   /// wraps the *whole-field* read future ([`TakoTypedMultipart`](crate::multipart::TakoTypedMultipart)'s
@@ -53,11 +69,30 @@ impl Default for MultipartConfig {
       max_parts: None,
       allowed_content_types: None,
       disk_spill_threshold: None,
+      content_store: None,
+      scanner: None,
+      quarantine_dir: None,
       field_chunk_timeout: None,
     }
   }
 }
 
+impl std::fmt::Debug for MultipartConfig {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("MultipartConfig")
+      .field("total_size_limit", &self.total_size_limit)
+      .field("per_part_size_limit", &self.per_part_size_limit)
+      .field("max_parts", &self.max_parts)
+      .field("allowed_content_types", &self.allowed_content_types)
+      .field("disk_spill_threshold", &self.disk_spill_threshold)
+      .field("content_store", &self.content_store)
+      .field("scanner", &self.scanner.is_some())
+      .field("quarantine_dir", &self.quarantine_dir)
+      .field("field_chunk_timeout", &self.field_chunk_timeout)
+      .finish()
+  }
+}
+
 impl MultipartConfig {
   /// Build a permissive config (no limits). Configure via the builder methods.
   pub fn new() -> Self {
@@ -105,6 +140,35 @@ impl MultipartConfig {
     self
   }
 
+  /// Deduplicate disk uploads into a content-addressed store under `dir`.
+  /// See [`Self::content_store`].
+  pub fn content_store(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.content_store = Some(dir.into());
+    self
+  }
+
+  /// Scan uploaded files with `scanner` before the handler runs. See
+  /// [`Self::scanner`].
+  pub fn scanner(mut self, scanner: impl UploadScanner) -> Self {
+    self.scanner = Some(Arc::new(scanner));
+    self
+  }
+
+  /// Move infected uploads to `dir` instead of deleting them.
+  pub fn quarantine_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.quarantine_dir = Some(dir.into());
+    self
+  }
+
+  /// Caps the whole-request limit at the route's body limit, if any.
+  pub(crate) fn with_body_limit(mut self, limit: Option<usize>) -> Self {
+    if let Some(limit) = limit {
+      let limit = u64::try_from(limit).unwrap_or(u64::MAX);
+      self.total_size_limit = Some(self.total_size_limit.map_or(limit, |b| b.min(limit)));
+    }
+    self
+  }
+
   pub(crate) fn to_constraints(&self) -> Constraints {
     let mut limit = SizeLimit::new();
     if let Some(b) = self.total_size_limit {
@@ -0,0 +1,361 @@
+//! Content inspection for uploaded files (virus scanning).
+//!
+//! With a [`MultipartConfig::scanner`](crate::multipart::MultipartConfig::scanner)
+//! installed as global state, every [`UploadedFile`](crate::multipart::UploadedFile),
+//! [`InMemoryFile`](crate::multipart::InMemoryFile), and
+//! [`BufferedUploadedFile`](crate::multipart::BufferedUploadedFile) is handed
+//! to the scanner once fully received and before it reaches the handler or
+//! the content-addressed store. An infected upload fails extraction with
+//! `422 Unprocessable Entity`; a scanner error fails closed with
+//! `503 Service Unavailable`.
+//!
+//! Infected files are deleted, or moved to
+//! [`MultipartConfig::quarantine_dir`](crate::multipart::MultipartConfig::quarantine_dir)
+//! when one is set. With the `signals` feature every detection also emits
+//! [`ids::QUARANTINED`].
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use uuid::Uuid;
+
+use crate::multipart::MultipartConfig;
+
+/// Signal ids emitted by the upload scanner (`signals` feature).
+pub mod ids {
+  /// An upload was found infected and rejected. Carries `signature`,
+  /// `file_name`, `content_type`, `size`, and — when the file was moved to
+  /// the quarantine directory — `path` as metadata.
+  pub const QUARANTINED: &str = "upload.quarantined";
+}
+
+/// Where the content of a [`ScanTarget`] lives.
+#[derive(Debug, Clone)]
+pub enum ScanContent {
+  /// Streamed to a file on disk.
+  File(PathBuf),
+  /// Held in memory.
+  Memory(Bytes),
+}
+
+/// An upload awaiting inspection.
+#[derive(Debug, Clone)]
+pub struct ScanTarget {
+  /// Original file name provided by the client, if any.
+  pub file_name: Option<String>,
+  /// MIME type provided by the client, if any.
+  pub content_type: Option<String>,
+  /// Size of the content in bytes.
+  pub size: u64,
+  /// The content itself.
+  pub content: ScanContent,
+}
+
+impl ScanTarget {
+  /// Reads the whole content, from disk if necessary.
+  pub async fn bytes(&self) -> std::io::Result<Bytes> {
+    match &self.content {
+      ScanContent::File(path) => tokio::fs::read(path).await.map(Bytes::from),
+      ScanContent::Memory(bytes) => Ok(bytes.clone()),
+    }
+  }
+}
+
+/// Outcome of a scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+  /// Nothing found.
+  Clean,
+  /// Malicious content, named by the scanner's signature.
+  Infected(String),
+}
+
+/// Inspects uploads before they are handed to the handler.
+///
+/// Implemented for [`ClamdScanner`] and for async closures taking an owned
+/// [`ScanTarget`]:
+///
+/// ```rust
+/// use tako::extractors::multipart::{MultipartConfig, ScanTarget, ScanVerdict};
+///
+/// let cfg = MultipartConfig::new().scanner(|target: ScanTarget| async move {
+///     let bytes = target.bytes().await?;
+///     Ok(if bytes.starts_with(b"MZ") {
+///         ScanVerdict::Infected("executable".to_string())
+///     } else {
+///         ScanVerdict::Clean
+///     })
+/// });
+/// ```
+#[async_trait]
+pub trait UploadScanner: Send + Sync + 'static {
+  /// Scans `target`. An error fails the upload closed.
+  async fn scan(&self, target: &ScanTarget) -> anyhow::Result<ScanVerdict>;
+}
+
+#[async_trait]
+impl<F, Fut> UploadScanner for F
+where
+  F: Fn(ScanTarget) -> Fut + Send + Sync + 'static,
+  Fut: Future<Output = anyhow::Result<ScanVerdict>> + Send,
+{
+  async fn scan(&self, target: &ScanTarget) -> anyhow::Result<ScanVerdict> {
+    self(target.clone()).await
+  }
+}
+
+/// Why an upload was rejected by its scan.
+#[derive(Debug)]
+pub enum ScanError {
+  /// The scanner found malicious content.
+  Infected {
+    /// Signature reported by the scanner.
+    signature: String,
+  },
+  /// The scanner could not be reached or returned an error.
+  Failed(String),
+}
+
+impl std::fmt::Display for ScanError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ScanError::Infected { signature } => write!(f, "upload infected: {signature}"),
+      ScanError::Failed(err) => write!(f, "upload scan failed: {err}"),
+    }
+  }
+}
+
+impl std::error::Error for ScanError {}
+
+/// Scanner speaking clamd's `INSTREAM` protocol over TCP.
+#[derive(Debug, Clone)]
+pub struct ClamdScanner {
+  addr: String,
+  timeout: Duration,
+  chunk_size: usize,
+}
+
+impl ClamdScanner {
+  /// Scanner for the clamd daemon at `addr`, e.g. `127.0.0.1:3310`.
+  pub fn new(addr: impl Into<String>) -> Self {
+    Self {
+      addr: addr.into(),
+      timeout: Duration::from_secs(30),
+      chunk_size: 64 * 1024,
+    }
+  }
+
+  /// Time allowed for a whole scan, connection included. Defaults to 30 s.
+  #[must_use]
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Size of the chunks streamed to clamd. Defaults to 64 KiB; must stay
+  /// below clamd's `StreamMaxLength`.
+  #[must_use]
+  pub fn chunk_size(mut self, bytes: usize) -> Self {
+    self.chunk_size = bytes.max(1);
+    self
+  }
+
+  async fn instream(&self, target: &ScanTarget) -> anyhow::Result<ScanVerdict> {
+    let mut stream = TcpStream::connect(&self.addr).await?;
+    stream.write_all(b"zINSTREAM\0").await?;
+    match &target.content {
+      ScanContent::Memory(bytes) => {
+        for chunk in bytes.chunks(self.chunk_size) {
+          send_chunk(&mut stream, chunk).await?;
+        }
+      }
+      ScanContent::File(path) => {
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut buf = vec![0; self.chunk_size];
+        loop {
+          let n = file.read(&mut buf).await?;
+          if n == 0 {
+            break;
+          }
+          send_chunk(&mut stream, &buf[..n]).await?;
+        }
+      }
+    }
+    stream.write_all(&0u32.to_be_bytes()).await?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await?;
+    parse_reply(&reply)
+  }
+}
+
+async fn send_chunk(stream: &mut TcpStream, chunk: &[u8]) -> std::io::Result<()> {
+  let len = u32::try_from(chunk.len()).map_err(std::io::Error::other)?;
+  stream.write_all(&len.to_be_bytes()).await?;
+  stream.write_all(chunk).await
+}
+
+/// Parses `stream: OK`, `stream: <signature> FOUND`, or `<message> ERROR`.
+fn parse_reply(reply: &[u8]) -> anyhow::Result<ScanVerdict> {
+  let reply = String::from_utf8_lossy(reply);
+  let reply = reply.trim_end_matches(['\0', '\n']);
+  let body = reply.strip_prefix("stream: ").unwrap_or(reply);
+  if body == "OK" {
+    Ok(ScanVerdict::Clean)
+  } else if let Some(signature) = body.strip_suffix(" FOUND") {
+    Ok(ScanVerdict::Infected(signature.to_string()))
+  } else {
+    Err(anyhow::anyhow!("clamd: {reply}"))
+  }
+}
+
+#[async_trait]
+impl UploadScanner for ClamdScanner {
+  async fn scan(&self, target: &ScanTarget) -> anyhow::Result<ScanVerdict> {
+    tokio::time::timeout(self.timeout, self.instream(target))
+      .await
+      .map_err(|_| anyhow::anyhow!("clamd: scan timed out"))?
+  }
+}
+
+/// Runs the globally configured scanner, if any, over `target`. Infected
+/// content on disk is moved to the quarantine directory when one is set;
+/// otherwise the caller's cleanup removes it.
+pub(crate) async fn scan_upload(target: ScanTarget) -> Result<(), ScanError> {
+  let Some(cfg) = tako_rs_core::state::get_state::<MultipartConfig>() else {
+    return Ok(());
+  };
+  let Some(scanner) = cfg.scanner.as_ref().map(Arc::clone) else {
+    return Ok(());
+  };
+  let signature = match scanner.scan(&target).await {
+    Ok(ScanVerdict::Clean) => return Ok(()),
+    Ok(ScanVerdict::Infected(signature)) => signature,
+    Err(e) => {
+      tracing::warn!(error = %e, "upload scan failed");
+      return Err(ScanError::Failed(e.to_string()));
+    }
+  };
+
+  let quarantined = match &cfg.quarantine_dir {
+    Some(dir) => match quarantine(dir, &target.content).await {
+      Ok(path) => Some(path),
+      Err(e) => {
+        tracing::error!(error = %e, "failed to quarantine infected upload");
+        None
+      }
+    },
+    None => None,
+  };
+  tracing::warn!(
+    signature = %signature,
+    file_name = target.file_name.as_deref().unwrap_or(""),
+    "infected upload rejected"
+  );
+  #[cfg(feature = "signals")]
+  emit(&target, &signature, quarantined.as_deref()).await;
+  #[cfg(not(feature = "signals"))]
+  let _ = quarantined;
+  Err(ScanError::Infected { signature })
+}
+
+async fn quarantine(dir: &Path, content: &ScanContent) -> std::io::Result<PathBuf> {
+  tokio::fs::create_dir_all(dir).await?;
+  let dest = dir.join(Uuid::new_v4().to_string());
+  match content {
+    ScanContent::File(path) => {
+      if tokio::fs::rename(path, &dest).await.is_err() {
+        // Across filesystems: copy, and leave the original to the cleanup.
+        tokio::fs::copy(path, &dest).await?;
+      }
+    }
+    ScanContent::Memory(bytes) => tokio::fs::write(&dest, bytes).await?,
+  }
+  Ok(dest)
+}
+
+#[cfg(feature = "signals")]
+async fn emit(target: &ScanTarget, signature: &str, quarantined: Option<&Path>) {
+  use tako_rs_core::signals::Signal;
+  use tako_rs_core::signals::app_events;
+
+  let mut signal = Signal::new(ids::QUARANTINED)
+    .meta("signature", signature)
+    .meta("file_name", target.file_name.as_deref().unwrap_or(""))
+    .meta("content_type", target.content_type.as_deref().unwrap_or(""))
+    .meta("size", target.size.to_string());
+  if let Some(path) = quarantined {
+    signal = signal.meta("path", path.display().to_string());
+  }
+  app_events().emit(signal).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_clamd_replies() {
+    assert_eq!(parse_reply(b"stream: OK\0").unwrap(), ScanVerdict::Clean);
+    assert_eq!(
+      parse_reply(b"stream: Eicar-Test-Signature FOUND\0").unwrap(),
+      ScanVerdict::Infected("Eicar-Test-Signature".to_string())
+    );
+    assert!(parse_reply(b"INSTREAM size limit exceeded. ERROR\0").is_err());
+  }
+
+  #[tokio::test]
+  async fn clamd_scanner_streams_chunks() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move {
+      let (mut sock, _) = listener.accept().await.unwrap();
+      let mut command = [0u8; 10];
+      sock.read_exact(&mut command).await.unwrap();
+      assert_eq!(&command, b"zINSTREAM\0");
+      let mut received = Vec::new();
+      loop {
+        let mut len = [0u8; 4];
+        sock.read_exact(&mut len).await.unwrap();
+        let len = u32::from_be_bytes(len) as usize;
+        if len == 0 {
+          break;
+        }
+        assert!(len <= 4);
+        let mut chunk = vec![0; len];
+        sock.read_exact(&mut chunk).await.unwrap();
+        received.extend_from_slice(&chunk);
+      }
+      let reply: &[u8] = if received == b"X5O!P%@AP" {
+        b"stream: Eicar-Test-Signature FOUND\0"
+      } else {
+        b"stream: OK\0"
+      };
+      sock.write_all(reply).await.unwrap();
+    });
+
+    let target = ScanTarget {
+      file_name: Some("eicar.com".to_string()),
+      content_type: None,
+      size: 9,
+      content: ScanContent::Memory(Bytes::from_static(b"X5O!P%@AP")),
+    };
+    let verdict = ClamdScanner::new(addr)
+      .chunk_size(4)
+      .scan(&target)
+      .await
+      .unwrap();
+    assert_eq!(
+      verdict,
+      ScanVerdict::Infected("Eicar-Test-Signature".to_string())
+    );
+    server.await.unwrap();
+  }
+}
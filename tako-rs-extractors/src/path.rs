@@ -83,6 +83,12 @@ impl<'a> FromRequestParts<'a> for RawPath {
 /// a `Vec<T>` for repeated captures, an `Option<T>` (`None` when no captures
 /// matched), or a struct deriving `serde::Deserialize`.
 ///
+/// Tuples bind positionally, in the order the captures appear in the route:
+/// `/orgs/{org}/users/{id}` fills `Path<(String, u64)>` as `(org, id)`. A
+/// tuple whose length differs from the number of captures is rejected with
+/// `400` naming the captured parameters, and a value that fails to parse
+/// names its parameter and segment.
+///
 /// Internally delegates to the path-params deserializer in `tako-core`, which
 /// has been extended in v2 to support tuples, sequences, and primitive
 /// destructuring on top of the original struct/map mode.
@@ -119,6 +125,11 @@ where
   ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
     async move { Params::<T>::from_request(req).await.map(|p| Path(p.0)) }
   }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut tako_rs_core::openapi::RouteOpenApi) {
+    operation.add_struct_parameters::<T>(tako_rs_core::openapi::ParameterLocation::Path);
+  }
 }
 
 impl<'a, T> FromRequestParts<'a> for Path<T>
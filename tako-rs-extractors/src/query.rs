@@ -142,6 +142,11 @@ where
   ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
     futures_util::future::ready(Self::extract_from_query_string(req.uri().query()))
   }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut tako_rs_core::openapi::RouteOpenApi) {
+    operation.add_struct_parameters::<T>(tako_rs_core::openapi::ParameterLocation::Query);
+  }
 }
 
 impl<'a, T> FromRequestParts<'a> for Query<T>
@@ -0,0 +1,108 @@
+//! Query strings with bracketed arrays and nested maps.
+//!
+//! [`Query`](crate::query::Query) reads flat `key=value` pairs, so filtering
+//! APIs written in the PHP / Rails / `qs` convention do not parse.
+//! [`QueryNested<T>`](crate::query_nested::QueryNested) is backed by
+//! `serde_qs` and understands that syntax:
+//!
+//! - `?tags[]=a&tags[]=b` and `?tags[0]=a&tags[1]=b` fill a `Vec`
+//! - `?filter[age][gte]=10` fills nested structs or maps
+//!
+//! Brackets may be sent percent-encoded (`tags%5B%5D=a`), as most HTTP
+//! clients do. Keys are split into at most
+//! [`MAX_DEPTH`](crate::query_nested::MAX_DEPTH) levels of nesting.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use serde::Deserialize;
+//! use tako::extractors::query_nested::QueryNested;
+//!
+//! #[derive(Deserialize)]
+//! struct Range {
+//!     gte: Option<u32>,
+//!     lte: Option<u32>,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Filter {
+//!     age: Option<Range>,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Search {
+//!     #[serde(default)]
+//!     tags: Vec<String>,
+//!     filter: Option<Filter>,
+//! }
+//!
+//! // /users?tags[]=admin&tags[]=ops&filter[age][gte]=18
+//! async fn users(QueryNested(search): QueryNested<Search>) -> String {
+//!     format!("{:?}", search.tags)
+//! }
+//! ```
+
+use http::request::Parts;
+use serde::de::DeserializeOwned;
+use tako_rs_core::extractors::FromRequest;
+use tako_rs_core::extractors::FromRequestParts;
+use tako_rs_core::types::Request;
+
+use crate::query::QueryError;
+
+/// Deepest bracket nesting parsed. Bounds the work a hostile query string
+/// can cause.
+pub const MAX_DEPTH: usize = 5;
+
+/// Query extractor for bracketed arrays and nested maps.
+#[doc(alias = "qs")]
+#[doc(alias = "query")]
+pub struct QueryNested<T>(pub T);
+
+impl<T> QueryNested<T>
+where
+  T: DeserializeOwned,
+{
+  /// Non-strict mode accepts percent-encoded brackets.
+  fn extract_from_query_string(query_string: Option<&str>) -> Result<Self, QueryError> {
+    let Some(query) = query_string else {
+      return Err(QueryError::MissingQueryString);
+    };
+
+    serde_qs::Config::new(MAX_DEPTH, false)
+      .deserialize_str::<T>(query)
+      .map(QueryNested)
+      .map_err(|e| QueryError::DeserializationError(e.to_string()))
+  }
+}
+
+impl<'a, T> FromRequest<'a> for QueryNested<T>
+where
+  T: DeserializeOwned + Send + 'a,
+{
+  type Error = QueryError;
+
+  fn from_request(
+    req: &'a mut Request,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    futures_util::future::ready(Self::extract_from_query_string(req.uri().query()))
+  }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut tako_rs_core::openapi::RouteOpenApi) {
+    operation.add_struct_parameters::<T>(tako_rs_core::openapi::ParameterLocation::Query);
+  }
+}
+
+impl<'a, T> FromRequestParts<'a> for QueryNested<T>
+where
+  T: DeserializeOwned + Send + 'a,
+{
+  type Error = QueryError;
+
+  fn from_request_parts(
+    parts: &'a mut Parts,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    futures_util::future::ready(Self::extract_from_query_string(parts.uri.query()))
+  }
+}
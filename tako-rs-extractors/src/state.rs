@@ -1,7 +1,10 @@
-//! Global state extraction for retrieving shared application state.
+//! State extraction for retrieving shared application state.
 //!
-//! This module exposes `State<T>` to access Tako's global state store from handlers.
-//! It retrieves a value by its concrete type (stored via `set_state`).
+//! This module exposes `State<T>` to access application state from handlers.
+//! It retrieves a value by its concrete type, preferring the state of the
+//! router that dispatched the request (stored via `Router::with_state`, and
+//! scoped to a child router's routes after `nest` / `merge`) over the
+//! process-global store (`set_state`).
 //!
 //! # Examples
 //!
@@ -28,7 +31,7 @@ use tako_rs_core::router_state::RouterState;
 use tako_rs_core::state::get_state;
 use tako_rs_core::types::Request;
 
-/// Extractor for accessing a value stored in Tako's global state by type.
+/// Extractor for accessing a value stored in router-local or global state by type.
 pub struct State<T>(pub Arc<T>);
 
 impl<T> Clone for State<T> {
@@ -0,0 +1,206 @@
+//! Text bodies in the client's charset.
+//!
+//! [`Text`](crate::text::Text) reads the request body as a `String`,
+//! decoding it from the charset the client declared — `Content-Type:
+//! text/plain; charset=ISO-8859-1`, `UTF-16`, `Shift_JIS`, and every other
+//! WHATWG encoding label — instead of failing or mangling anything that is
+//! not UTF-8. A byte-order mark takes precedence over the declared charset,
+//! and a body without either is read as UTF-8. Bytes that are not valid in
+//! the charset are rejected rather than replaced.
+//!
+//! [`TextResponse`](crate::text::TextResponse) is the other direction: it
+//! encodes a string in a chosen charset and declares it in `Content-Type`,
+//! for clients that cannot read UTF-8.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::extractors::text::{Text, TextResponse};
+//!
+//! async fn echo(Text(body): Text) -> TextResponse {
+//!     TextResponse::new(body.to_uppercase()).charset("iso-8859-1")
+//! }
+//! ```
+
+use std::borrow::Cow;
+
+use encoding_rs::Encoding;
+use encoding_rs::UTF_8;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::extractors::FromRequest;
+use tako_rs_core::extractors::body_limit::CollectError;
+use tako_rs_core::extractors::body_limit::collect_limited;
+use tako_rs_core::extractors::body_limit::payload_too_large;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// Error type for [`Text`] extraction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextError {
+  /// Failed to read the request body.
+  BodyReadError(String),
+  /// The request body exceeds the configured body limit (in bytes).
+  PayloadTooLarge(usize),
+  /// The declared charset is not a known encoding label.
+  UnsupportedCharset(String),
+  /// The body is not valid in its charset, named here.
+  InvalidEncoding(&'static str),
+}
+
+impl std::fmt::Display for TextError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::BodyReadError(err) => write!(f, "failed to read request body: {err}"),
+      Self::PayloadTooLarge(limit) => write!(f, "request body exceeds {limit} bytes"),
+      Self::UnsupportedCharset(charset) => write!(f, "unsupported charset `{charset}`"),
+      Self::InvalidEncoding(charset) => write!(f, "request body is not valid {charset}"),
+    }
+  }
+}
+
+impl std::error::Error for TextError {}
+
+impl Responder for TextError {
+  fn into_response(self) -> Response {
+    let status = match self {
+      Self::PayloadTooLarge(limit) => return payload_too_large(limit),
+      Self::UnsupportedCharset(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+      Self::BodyReadError(_) | Self::InvalidEncoding(_) => StatusCode::BAD_REQUEST,
+    };
+    (status, self.to_string()).into_response()
+  }
+}
+
+/// Extractor reading the request body as text, decoded from its charset.
+///
+/// Holds the body as UTF-8 whatever it was sent in.
+#[doc(alias = "text")]
+#[doc(alias = "charset")]
+pub struct Text(pub String);
+
+impl Text {
+  /// Decodes `body` sent with the `Content-Type` value `content_type`.
+  ///
+  /// # Errors
+  ///
+  /// Fails when the charset is unknown or the body is not valid in it.
+  pub fn decode(content_type: Option<&str>, body: &[u8]) -> Result<String, TextError> {
+    let declared = match content_type.and_then(charset_param) {
+      Some(label) => Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| TextError::UnsupportedCharset(label.to_string()))?,
+      None => UTF_8,
+    };
+    let (encoding, body) = match Encoding::for_bom(body) {
+      Some((encoding, bom_len)) => (encoding, &body[bom_len..]),
+      None => (declared, body),
+    };
+    encoding
+      .decode_without_bom_handling_and_without_replacement(body)
+      .map(Cow::into_owned)
+      .ok_or(TextError::InvalidEncoding(encoding.name()))
+  }
+}
+
+impl<'a> FromRequest<'a> for Text {
+  type Error = TextError;
+
+  fn from_request(
+    req: &'a mut Request,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    async move {
+      let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+      let body = collect_limited(req).await.map_err(|e| match e {
+        CollectError::TooLarge(limit) => TextError::PayloadTooLarge(limit),
+        CollectError::Read(err) => TextError::BodyReadError(err),
+      })?;
+      Self::decode(content_type.as_deref(), &body).map(Text)
+    }
+  }
+}
+
+/// The `charset` parameter of a `Content-Type` value, unquoted.
+fn charset_param(content_type: &str) -> Option<&str> {
+  content_type.split(';').skip(1).find_map(|param| {
+    let (name, value) = param.split_once('=')?;
+    name
+      .trim()
+      .eq_ignore_ascii_case("charset")
+      .then(|| value.trim().trim_matches('"'))
+  })
+}
+
+/// Responder encoding a string in a declared charset.
+///
+/// Defaults to `text/plain; charset=utf-8`. With another charset, characters
+/// it cannot represent are written as HTML numeric character references
+/// (`&#8364;`), which suits HTML and is the best any encoder can do for
+/// plain text. The UTF-16 labels produce UTF-8, as the Encoding Standard
+/// requires of encoders, and the header says so.
+pub struct TextResponse {
+  text: String,
+  mime: &'static str,
+  encoding: &'static Encoding,
+  status: StatusCode,
+}
+
+impl TextResponse {
+  /// A `text/plain` UTF-8 response.
+  pub fn new(text: impl Into<String>) -> Self {
+    Self {
+      text: text.into(),
+      mime: "text/plain",
+      encoding: UTF_8,
+      status: StatusCode::OK,
+    }
+  }
+
+  /// The media type, e.g. `text/html` (default `text/plain`).
+  #[must_use]
+  pub fn mime(mut self, mime: &'static str) -> Self {
+    self.mime = mime;
+    self
+  }
+
+  /// Encodes the body in the charset named by `label`, e.g. `iso-8859-1`
+  /// or `shift_jis`. Unknown labels keep UTF-8. Labels resolve as browsers
+  /// resolve them, so `iso-8859-1` is sent as its superset `windows-1252`.
+  #[must_use]
+  pub fn charset(mut self, label: &str) -> Self {
+    if let Some(encoding) = Encoding::for_label(label.as_bytes()) {
+      self.encoding = encoding.output_encoding();
+    }
+    self
+  }
+
+  /// The response status (default `200 OK`).
+  #[must_use]
+  pub fn status(mut self, status: StatusCode) -> Self {
+    self.status = status;
+    self
+  }
+}
+
+impl Responder for TextResponse {
+  fn into_response(self) -> Response {
+    let (body, encoding, _) = self.encoding.encode(&self.text);
+    let content_type = format!(
+      "{}; charset={}",
+      self.mime,
+      encoding.name().to_ascii_lowercase()
+    );
+    let mut resp = Response::new(TakoBody::from(body.into_owned()));
+    *resp.status_mut() = self.status;
+    if let Ok(value) = HeaderValue::from_str(&content_type) {
+      resp.headers_mut().insert(CONTENT_TYPE, value);
+    }
+    resp
+  }
+}
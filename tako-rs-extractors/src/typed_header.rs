@@ -100,6 +100,17 @@ where
   ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
     futures_util::future::ready(decode::<H>(req.headers()).map(TypedHeader))
   }
+
+  #[cfg(any(feature = "utoipa", feature = "vespera"))]
+  fn describe_openapi(operation: &mut tako_rs_core::openapi::RouteOpenApi) {
+    operation.add_parameter(tako_rs_core::openapi::OpenApiParameter {
+      name: H::name().as_str().to_string(),
+      location: tako_rs_core::openapi::ParameterLocation::Header,
+      description: None,
+      required: true,
+      kind: None,
+    });
+  }
 }
 
 impl<'a, H> FromRequestParts<'a> for TypedHeader<H>
@@ -0,0 +1,32 @@
+//! Authentication building blocks.
+//!
+//! - [`password`] — Argon2id hashing and verification with sound defaults,
+//!   plus detection of stored hashes that should be upgraded (`password`
+//!   feature).
+//! - [`form_login`] — a login / logout handler pair that checks an HTML
+//!   form against stored hashes and records the user in the
+//!   [session](crate::middleware::session) (`password` feature).
+//! - [`magic_link`] — signed, expiring, single-use tokens for passwordless
+//!   login and email verification (`magic-link` feature).
+//! - [`totp`] — RFC 6238 one-time codes, recovery codes, and a route guard
+//!   for sessions that passed the second factor (`totp` feature).
+//!
+//! Put [`LoginThrottle`](crate::middleware::login_throttle::LoginThrottle)
+//! in front of the login and code-entry routes to slow down guessing.
+
+#[cfg(feature = "password")]
+#[cfg_attr(docsrs, doc(cfg(feature = "password")))]
+pub mod form_login;
+#[cfg(feature = "magic-link")]
+#[cfg_attr(docsrs, doc(cfg(feature = "magic-link")))]
+pub mod magic_link;
+#[cfg(feature = "password")]
+#[cfg_attr(docsrs, doc(cfg(feature = "password")))]
+pub mod password;
+#[cfg(feature = "totp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "totp")))]
+pub mod totp;
+
+/// Session flag set once the second factor is passed; every login clears
+/// it.
+pub(crate) const TWO_FACTOR_SESSION_KEY: &str = "tako.2fa";
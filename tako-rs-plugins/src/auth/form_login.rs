@@ -0,0 +1,283 @@
+//! Session-backed username / password login.
+//!
+//! [`FormLogin`] provides the two handlers a classic login page needs.
+//! [`FormLogin::login`] reads an `application/x-www-form-urlencoded` body,
+//! looks up the stored hash for the username, and verifies the password. On
+//! success it rotates the session id (to prevent fixation), stores the
+//! username in the [`Session`], clears any earlier two-factor state, and
+//! redirects. [`FormLogin::logout`] destroys the session.
+//!
+//! The lookup callback is the only application code required: it maps a
+//! username to its stored PHC hash, usually with a database query. Unknown
+//! usernames still cost one hash verification, so timing does not reveal
+//! which accounts exist. When a stored hash was made under weaker
+//! parameters than the current [`PasswordPolicy`], the optional `on_rehash`
+//! callback receives a fresh hash to store.
+//!
+//! Failed logins answer `401 Unauthorized`, which is also what
+//! [`LoginThrottle`](crate::middleware::login_throttle::LoginThrottle)
+//! counts by default. The throttle cannot see the username inside a form
+//! body, so it throttles form logins by IP unless given an `account_fn`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http::Method;
+//! use tako::auth::form_login::FormLogin;
+//! use tako::middleware::IntoMiddleware;
+//! use tako::middleware::session::SessionMiddleware;
+//! use tako::router::Router;
+//! use tako::types::Request;
+//!
+//! let login = FormLogin::new(|username: String| async move {
+//!     // Look the user up in the database.
+//!     (username == "alice").then(|| "$argon2id$v=19$...".to_string())
+//! })
+//! .success_redirect("/dashboard");
+//!
+//! let mut router = Router::new();
+//! router.middleware(SessionMiddleware::new().into_middleware());
+//! let l = login.clone();
+//! router.route(Method::POST, "/login", move |req: Request| {
+//!     let l = l.clone();
+//!     async move { l.login(req).await }
+//! });
+//! router.route(Method::POST, "/logout", move |req: Request| {
+//!     let l = login.clone();
+//!     async move { l.logout(req).await }
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use http::header::LOCATION;
+use http_body_util::BodyExt;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::password::PasswordPolicy;
+use crate::middleware::session::Session;
+
+/// Maps a username to its stored password hash.
+pub type CredentialLookupFn =
+  Arc<dyn Fn(String) -> Pin<Box<dyn Future<Output = Option<String>> + Send>> + Send + Sync>;
+
+/// Receives `(username, new_hash)` when a stored hash should be replaced.
+pub type RehashFn =
+  Arc<dyn Fn(String, String) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Form login / logout handlers.
+#[derive(Clone)]
+pub struct FormLogin {
+  lookup: CredentialLookupFn,
+  on_rehash: Option<RehashFn>,
+  policy: PasswordPolicy,
+  username_field: String,
+  password_field: String,
+  session_key: String,
+  success_redirect: String,
+  failure_redirect: Option<String>,
+  logout_redirect: String,
+  max_body_bytes: usize,
+}
+
+impl FormLogin {
+  /// Creates the handlers around a credential lookup. Defaults: form fields
+  /// `username` and `password`, session key `"user"`, redirect to `/` after
+  /// login and logout, `401` on failure, 16 KiB body limit.
+  pub fn new<F, Fut>(lookup: F) -> Self
+  where
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Option<String>> + Send + 'static,
+  {
+    Self {
+      lookup: Arc::new(move |username| Box::pin(lookup(username))),
+      on_rehash: None,
+      policy: PasswordPolicy::default(),
+      username_field: "username".to_string(),
+      password_field: "password".to_string(),
+      session_key: "user".to_string(),
+      success_redirect: "/".to_string(),
+      failure_redirect: None,
+      logout_redirect: "/".to_string(),
+      max_body_bytes: 16 * 1024,
+    }
+  }
+
+  /// Hashing policy used for dummy verification and rehash detection.
+  pub fn policy(mut self, policy: PasswordPolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// Called with a fresh hash when a user logs in with a hash that
+  /// [needs rehashing](PasswordPolicy::needs_rehash).
+  pub fn on_rehash<F, Fut>(mut self, f: F) -> Self
+  where
+    F: Fn(String, String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    self.on_rehash = Some(Arc::new(move |user, hash| Box::pin(f(user, hash))));
+    self
+  }
+
+  /// Names of the form fields carrying the credentials.
+  pub fn fields(mut self, username: &str, password: &str) -> Self {
+    self.username_field = username.to_string();
+    self.password_field = password.to_string();
+    self
+  }
+
+  /// Session key the username is stored under.
+  pub fn session_key(mut self, key: &str) -> Self {
+    self.session_key = key.to_string();
+    self
+  }
+
+  /// Where to send the browser after a successful login.
+  pub fn success_redirect(mut self, location: &str) -> Self {
+    self.success_redirect = location.to_string();
+    self
+  }
+
+  /// Redirect failed logins here (`303`) instead of answering `401`.
+  pub fn failure_redirect(mut self, location: &str) -> Self {
+    self.failure_redirect = Some(location.to_string());
+    self
+  }
+
+  /// Where to send the browser after logout.
+  pub fn logout_redirect(mut self, location: &str) -> Self {
+    self.logout_redirect = location.to_string();
+    self
+  }
+
+  /// Largest accepted form body.
+  pub fn max_body_bytes(mut self, n: usize) -> Self {
+    self.max_body_bytes = n;
+    self
+  }
+
+  /// The logged-in username, if any.
+  pub fn current_user(&self, req: &Request) -> Option<String> {
+    req.extensions().get::<Session>()?.get(&self.session_key)
+  }
+
+  /// Login handler.
+  pub async fn login(&self, req: Request) -> Response {
+    let Some(session) = req.extensions().get::<Session>().cloned() else {
+      return text(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "FormLogin requires SessionMiddleware",
+      );
+    };
+    let is_form = req
+      .headers()
+      .get(CONTENT_TYPE)
+      .and_then(|v| v.to_str().ok())
+      .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"));
+    if !is_form {
+      return text(
+        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        "expected application/x-www-form-urlencoded",
+      );
+    }
+    let Ok(body) = http_body_util::Limited::new(req.into_body(), self.max_body_bytes)
+      .collect()
+      .await
+    else {
+      return text(StatusCode::PAYLOAD_TOO_LARGE, "login form is too large");
+    };
+    let Ok(mut form) = serde_urlencoded::from_bytes::<HashMap<String, String>>(&body.to_bytes())
+    else {
+      return text(StatusCode::BAD_REQUEST, "malformed login form");
+    };
+    let (Some(username), Some(password)) = (
+      form.remove(&self.username_field),
+      form.remove(&self.password_field),
+    ) else {
+      return text(StatusCode::BAD_REQUEST, "missing username or password");
+    };
+
+    let stored = (self.lookup)(username.clone()).await;
+    let policy = self.policy.clone();
+    let (valid, rehash) = blocking(move || match stored {
+      Some(stored) => {
+        let valid = policy.verify(&password, &stored);
+        let rehash = (valid && policy.needs_rehash(&stored))
+          .then(|| policy.hash(&password).ok())
+          .flatten();
+        (valid, rehash)
+      }
+      None => (policy.verify_dummy(&password), None),
+    })
+    .await;
+
+    if !valid {
+      return match &self.failure_redirect {
+        Some(location) => redirect(location),
+        None => text(StatusCode::UNAUTHORIZED, "invalid username or password"),
+      };
+    }
+    if let (Some(new_hash), Some(on_rehash)) = (rehash, &self.on_rehash) {
+      on_rehash(username.clone(), new_hash).await;
+    }
+    session.rotate();
+    // A new login has not passed any second factor yet.
+    session.remove(super::TWO_FACTOR_SESSION_KEY);
+    session.set(&self.session_key, username);
+    redirect(&self.success_redirect)
+  }
+
+  /// Logout handler: destroys the session and redirects.
+  pub async fn logout(&self, req: Request) -> Response {
+    if let Some(session) = req.extensions().get::<Session>() {
+      session.destroy();
+    }
+    redirect(&self.logout_redirect)
+  }
+}
+
+/// Runs the hash work off the async workers where the runtime allows it.
+async fn blocking<T, F>(f: F) -> T
+where
+  F: FnOnce() -> T + Send + 'static,
+  T: Send + 'static,
+{
+  #[cfg(not(feature = "compio"))]
+  {
+    tokio::task::spawn_blocking(f)
+      .await
+      .expect("password hashing panicked")
+  }
+  #[cfg(feature = "compio")]
+  {
+    f()
+  }
+}
+
+fn redirect(location: &str) -> Response {
+  let mut resp = http::Response::new(TakoBody::empty());
+  *resp.status_mut() = StatusCode::SEE_OTHER;
+  if let Ok(v) = HeaderValue::from_str(location) {
+    resp.headers_mut().insert(LOCATION, v);
+  }
+  resp
+}
+
+fn text(status: StatusCode, message: &'static str) -> Response {
+  let mut resp = http::Response::new(TakoBody::from(message));
+  *resp.status_mut() = status;
+  resp.headers_mut().insert(
+    CONTENT_TYPE,
+    HeaderValue::from_static("text/plain; charset=utf-8"),
+  );
+  resp
+}
@@ -0,0 +1,376 @@
+//! Signed, expiring, single-use tokens for passwordless login and email
+//! verification.
+//!
+//! [`MagicLinks`] issues tokens of the form `payload.signature`: the payload
+//! is base64url JSON carrying a random id, a purpose, the subject (usually
+//! a user id or email address), and an expiry; the signature is
+//! HMAC-SHA256 over it. Verification needs no lookup, except that the id is
+//! recorded in a [`OneTimeTokenStore`] so each token works once.
+//!
+//! The purpose keeps flows apart: a token issued for [`PURPOSE_VERIFY_EMAIL`]
+//! is refused by [`MagicLinks::login`], which only accepts
+//! [`PURPOSE_LOGIN`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use http::Method;
+//! use tako::auth::magic_link::MagicLinks;
+//! use tako::auth::magic_link::PURPOSE_LOGIN;
+//! use tako::middleware::IntoMiddleware;
+//! use tako::middleware::session::SessionMiddleware;
+//! use tako::router::Router;
+//! use tako::types::Request;
+//!
+//! let links = MagicLinks::new(b"a long random server secret".to_vec())
+//!     .success_redirect("/dashboard");
+//!
+//! // Mail this to the user.
+//! let url = links.link("https://example.com/auth/magic", PURPOSE_LOGIN, "alice");
+//! assert!(url.starts_with("https://example.com/auth/magic?token="));
+//!
+//! let mut router = Router::new();
+//! router.middleware(SessionMiddleware::new().into_middleware());
+//! router.route(Method::GET, "/auth/magic", move |req: Request| {
+//!     let links = links.clone();
+//!     async move { links.login(req).await }
+//! });
+//! ```
+
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::Hmac;
+use hmac::Mac;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use http::header::LOCATION;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Sha256;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::TWO_FACTOR_SESSION_KEY;
+use crate::middleware::session::Session;
+use crate::stores::OneTimeTokenStore;
+use crate::stores::memory::MemoryOneTimeTokenStore;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Purpose accepted by [`MagicLinks::login`].
+pub const PURPOSE_LOGIN: &str = "login";
+/// Conventional purpose for email-address verification links.
+pub const PURPOSE_VERIFY_EMAIL: &str = "verify-email";
+
+/// How often (in consumed tokens) the store is swept.
+const SWEEP_EVERY: u64 = 256;
+
+/// Why a token was refused.
+#[derive(Debug)]
+pub enum MagicLinkError {
+  /// Not a token this module produced.
+  Malformed,
+  /// The signature does not match; the token was forged or altered.
+  BadSignature,
+  /// Issued for another flow.
+  WrongPurpose,
+  Expired,
+  /// Already consumed once.
+  AlreadyUsed,
+  /// The spent-token store failed.
+  Store(io::Error),
+}
+
+impl fmt::Display for MagicLinkError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Malformed => f.write_str("malformed token"),
+      Self::BadSignature => f.write_str("invalid token signature"),
+      Self::WrongPurpose => f.write_str("token was issued for another purpose"),
+      Self::Expired => f.write_str("token has expired"),
+      Self::AlreadyUsed => f.write_str("token was already used"),
+      Self::Store(e) => write!(f, "token store failed: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for MagicLinkError {}
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+  jti: String,
+  pur: String,
+  sub: String,
+  exp: u64,
+}
+
+/// Magic-link issuer and verifier.
+#[derive(Clone)]
+pub struct MagicLinks {
+  key: Arc<[u8]>,
+  ttl: Duration,
+  store: Arc<dyn OneTimeTokenStore>,
+  session_key: String,
+  success_redirect: String,
+  failure_redirect: Option<String>,
+  consumed: Arc<AtomicU64>,
+}
+
+impl MagicLinks {
+  /// Creates an issuer signing with `secret`. Defaults: tokens live 15
+  /// minutes, spent ids are kept in a [`MemoryOneTimeTokenStore`], and
+  /// [`login`](Self::login) stores the subject under the session key
+  /// `"user"` and redirects to `/`.
+  pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+    Self {
+      key: secret.into().into(),
+      ttl: Duration::from_secs(15 * 60),
+      store: Arc::new(MemoryOneTimeTokenStore::new()),
+      session_key: "user".to_string(),
+      success_redirect: "/".to_string(),
+      failure_redirect: None,
+      consumed: Arc::new(AtomicU64::new(0)),
+    }
+  }
+
+  /// Token lifetime.
+  pub fn ttl(mut self, ttl: Duration) -> Self {
+    self.ttl = ttl;
+    self
+  }
+
+  /// Spent-token storage. Use a shared backend with several replicas.
+  pub fn store(mut self, store: impl OneTimeTokenStore) -> Self {
+    self.store = Arc::new(store);
+    self
+  }
+
+  /// Session key [`login`](Self::login) stores the subject under.
+  pub fn session_key(mut self, key: &str) -> Self {
+    self.session_key = key.to_string();
+    self
+  }
+
+  /// Where [`login`](Self::login) sends the browser on success.
+  pub fn success_redirect(mut self, location: &str) -> Self {
+    self.success_redirect = location.to_string();
+    self
+  }
+
+  /// Redirect refused links here (`303`) instead of answering `401`.
+  pub fn failure_redirect(mut self, location: &str) -> Self {
+    self.failure_redirect = Some(location.to_string());
+    self
+  }
+
+  /// Issues a token for `subject`, valid for `purpose` only.
+  pub fn issue(&self, purpose: &str, subject: &str) -> String {
+    let exp = unix(SystemTime::now() + self.ttl);
+    let claims = Claims {
+      jti: uuid::Uuid::new_v4().simple().to_string(),
+      pur: purpose.to_string(),
+      sub: subject.to_string(),
+      exp,
+    };
+    let payload =
+      URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).expect("claims are serializable"));
+    let signature = URL_SAFE_NO_PAD.encode(self.sign(payload.as_bytes()));
+    format!("{payload}.{signature}")
+  }
+
+  /// A ready-to-mail URL: `base_url` with the token in its `token` query
+  /// parameter.
+  pub fn link(&self, base_url: &str, purpose: &str, subject: &str) -> String {
+    let sep = if base_url.contains('?') { '&' } else { '?' };
+    format!("{base_url}{sep}token={}", self.issue(purpose, subject))
+  }
+
+  fn sign(&self, payload: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    mac.finalize().into_bytes().to_vec()
+  }
+
+  /// Checks `token` without consuming it, returning its subject.
+  ///
+  /// # Errors
+  ///
+  /// Returns why the token is not acceptable for `purpose`.
+  pub fn peek(&self, token: &str, purpose: &str) -> Result<String, MagicLinkError> {
+    self.check(token, purpose).map(|c| c.sub)
+  }
+
+  fn check(&self, token: &str, purpose: &str) -> Result<Claims, MagicLinkError> {
+    let (payload, signature) = token
+      .trim()
+      .split_once('.')
+      .ok_or(MagicLinkError::Malformed)?;
+    let signature = URL_SAFE_NO_PAD
+      .decode(signature)
+      .map_err(|_| MagicLinkError::Malformed)?;
+    let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+      .verify_slice(&signature)
+      .map_err(|_| MagicLinkError::BadSignature)?;
+    let claims: Claims = URL_SAFE_NO_PAD
+      .decode(payload)
+      .ok()
+      .and_then(|json| serde_json::from_slice(&json).ok())
+      .ok_or(MagicLinkError::Malformed)?;
+    if claims.pur != purpose {
+      return Err(MagicLinkError::WrongPurpose);
+    }
+    if claims.exp <= unix(SystemTime::now()) {
+      return Err(MagicLinkError::Expired);
+    }
+    Ok(claims)
+  }
+
+  /// Verifies `token` for `purpose`, spends it, and returns its subject.
+  ///
+  /// # Errors
+  ///
+  /// Returns why the token was refused; a refused token is not spent.
+  pub async fn consume(&self, token: &str, purpose: &str) -> Result<String, MagicLinkError> {
+    let claims = self.check(token, purpose)?;
+    if self.consumed.fetch_add(1, Ordering::Relaxed) % SWEEP_EVERY == SWEEP_EVERY - 1 {
+      self.store.sweep_expired(SystemTime::now()).await;
+    }
+    let expires_at = UNIX_EPOCH + Duration::from_secs(claims.exp);
+    match self.store.mark_used(&claims.jti, expires_at).await {
+      Ok(true) => Ok(claims.sub),
+      Ok(false) => Err(MagicLinkError::AlreadyUsed),
+      Err(e) => Err(MagicLinkError::Store(e)),
+    }
+  }
+
+  /// The token in the request's `token` query parameter. Extract it before
+  /// awaiting [`consume`](Self::consume) so the handler future does not
+  /// borrow the request.
+  pub fn token_from_query(req: &Request) -> Option<String> {
+    url::form_urlencoded::parse(req.uri().query()?.as_bytes())
+      .find(|(k, _)| k == "token")
+      .map(|(_, v)| v.into_owned())
+  }
+
+  /// Login handler for links issued with [`PURPOSE_LOGIN`]: spends the
+  /// token, rotates the session id, stores the subject in the session, and
+  /// redirects. Any earlier two-factor state is cleared.
+  pub async fn login(&self, req: Request) -> Response {
+    let Some(session) = req.extensions().get::<Session>().cloned() else {
+      return text(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "MagicLinks::login requires SessionMiddleware",
+      );
+    };
+    let token = Self::token_from_query(&req).unwrap_or_default();
+    match self.consume(&token, PURPOSE_LOGIN).await {
+      Ok(subject) => {
+        session.rotate();
+        session.remove(TWO_FACTOR_SESSION_KEY);
+        session.set(&self.session_key, subject);
+        redirect(&self.success_redirect)
+      }
+      Err(MagicLinkError::Store(e)) => {
+        tracing::warn!(error = %e, "one-time token store failed");
+        text(
+          StatusCode::SERVICE_UNAVAILABLE,
+          "login is temporarily unavailable",
+        )
+      }
+      Err(_) => match &self.failure_redirect {
+        Some(location) => redirect(location),
+        None => text(StatusCode::UNAUTHORIZED, "this link is invalid or expired"),
+      },
+    }
+  }
+}
+
+fn unix(t: SystemTime) -> u64 {
+  t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+fn redirect(location: &str) -> Response {
+  let mut resp = http::Response::new(TakoBody::empty());
+  *resp.status_mut() = StatusCode::SEE_OTHER;
+  if let Ok(v) = HeaderValue::from_str(location) {
+    resp.headers_mut().insert(LOCATION, v);
+  }
+  resp
+}
+
+fn text(status: StatusCode, message: &'static str) -> Response {
+  let mut resp = http::Response::new(TakoBody::from(message));
+  *resp.status_mut() = status;
+  resp.headers_mut().insert(
+    CONTENT_TYPE,
+    HeaderValue::from_static("text/plain; charset=utf-8"),
+  );
+  resp
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn tokens_are_single_use() {
+    let links = MagicLinks::new(b"secret".to_vec());
+    let token = links.issue(PURPOSE_LOGIN, "alice");
+    assert_eq!(links.peek(&token, PURPOSE_LOGIN).unwrap(), "alice");
+    assert_eq!(links.consume(&token, PURPOSE_LOGIN).await.unwrap(), "alice");
+    assert!(matches!(
+      links.consume(&token, PURPOSE_LOGIN).await,
+      Err(MagicLinkError::AlreadyUsed)
+    ));
+  }
+
+  #[tokio::test]
+  async fn tokens_are_bound_to_key_purpose_and_lifetime() {
+    let links = MagicLinks::new(b"secret".to_vec());
+    let token = links.issue(PURPOSE_VERIFY_EMAIL, "alice@example.com");
+    assert!(matches!(
+      links.consume(&token, PURPOSE_LOGIN).await,
+      Err(MagicLinkError::WrongPurpose)
+    ));
+    // Refused tokens are not spent.
+    assert!(links.consume(&token, PURPOSE_VERIFY_EMAIL).await.is_ok());
+
+    let other = MagicLinks::new(b"other secret".to_vec());
+    assert!(matches!(
+      other.peek(&links.issue(PURPOSE_LOGIN, "a"), PURPOSE_LOGIN),
+      Err(MagicLinkError::BadSignature)
+    ));
+
+    let (payload, signature) = token.split_once('.').unwrap();
+    let mut tampered = URL_SAFE_NO_PAD.decode(payload).unwrap();
+    tampered[10] ^= 1;
+    let tampered = format!("{}.{signature}", URL_SAFE_NO_PAD.encode(tampered));
+    assert!(matches!(
+      links.peek(&tampered, PURPOSE_VERIFY_EMAIL),
+      Err(MagicLinkError::BadSignature)
+    ));
+    assert!(matches!(
+      links.peek("garbage", PURPOSE_LOGIN),
+      Err(MagicLinkError::Malformed)
+    ));
+
+    let expired = MagicLinks::new(b"secret".to_vec()).ttl(Duration::ZERO);
+    assert!(matches!(
+      expired.peek(&expired.issue(PURPOSE_LOGIN, "a"), PURPOSE_LOGIN),
+      Err(MagicLinkError::Expired)
+    ));
+  }
+}
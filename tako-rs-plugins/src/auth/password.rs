@@ -0,0 +1,250 @@
+//! Argon2id password hashing.
+//!
+//! Hashes are stored as PHC strings (`$argon2id$v=19$m=19456,t=2,p=1$…`), so
+//! the salt and cost parameters travel with the hash and verification needs
+//! nothing else. The defaults follow the OWASP recommendation for Argon2id:
+//! 19 MiB of memory, two passes, one lane.
+//!
+//! When the policy is strengthened later, [`PasswordPolicy::needs_rehash`]
+//! reports which stored hashes were made with other parameters so they can
+//! be replaced the next time their owner logs in — the only moment the
+//! plaintext is available.
+//!
+//! Hashing is deliberately slow (tens of milliseconds). Call these functions
+//! from a blocking thread when serving requests;
+//! [`FormLogin`](super::form_login::FormLogin) does this for you.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::auth::password;
+//!
+//! let stored = password::hash("correct horse").unwrap();
+//! assert!(password::verify("correct horse", &stored));
+//! assert!(!password::verify("battery staple", &stored));
+//! assert!(!password::needs_rehash(&stored));
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+use argon2::Algorithm;
+use argon2::Argon2;
+use argon2::Params;
+use argon2::PasswordHash;
+use argon2::PasswordHasher;
+use argon2::PasswordVerifier;
+use argon2::Version;
+use argon2::password_hash::SaltString;
+use argon2::password_hash::rand_core::OsRng;
+
+/// Errors raised while hashing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordError {
+  /// The policy's cost parameters are outside what Argon2 accepts.
+  InvalidParams(String),
+  /// Hashing itself failed.
+  Hash(String),
+}
+
+impl fmt::Display for PasswordError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::InvalidParams(e) => write!(f, "invalid argon2 parameters: {e}"),
+      Self::Hash(e) => write!(f, "password hashing failed: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for PasswordError {}
+
+/// Argon2id cost parameters.
+#[derive(Clone)]
+pub struct PasswordPolicy {
+  memory_kib: u32,
+  iterations: u32,
+  parallelism: u32,
+  /// Hash of a fixed password, verified against when the account does not
+  /// exist so that case takes as long as a wrong password.
+  dummy: Arc<OnceLock<String>>,
+}
+
+impl fmt::Debug for PasswordPolicy {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("PasswordPolicy")
+      .field("memory_kib", &self.memory_kib)
+      .field("iterations", &self.iterations)
+      .field("parallelism", &self.parallelism)
+      .finish_non_exhaustive()
+  }
+}
+
+impl Default for PasswordPolicy {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl PasswordPolicy {
+  /// OWASP defaults: 19 MiB, two passes, one lane.
+  pub fn new() -> Self {
+    Self {
+      memory_kib: 19 * 1024,
+      iterations: 2,
+      parallelism: 1,
+      dummy: Arc::new(OnceLock::new()),
+    }
+  }
+
+  /// Memory cost in KiB.
+  pub fn memory_kib(mut self, kib: u32) -> Self {
+    self.memory_kib = kib;
+    self.dummy = Arc::new(OnceLock::new());
+    self
+  }
+
+  /// Number of passes over memory.
+  pub fn iterations(mut self, n: u32) -> Self {
+    self.iterations = n;
+    self.dummy = Arc::new(OnceLock::new());
+    self
+  }
+
+  /// Degree of parallelism (lanes).
+  pub fn parallelism(mut self, n: u32) -> Self {
+    self.parallelism = n;
+    self.dummy = Arc::new(OnceLock::new());
+    self
+  }
+
+  fn argon2(&self) -> Result<Argon2<'static>, PasswordError> {
+    let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+      .map_err(|e| PasswordError::InvalidParams(e.to_string()))?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+  }
+
+  /// Hashes `password` with a fresh random salt, returning a PHC string.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the policy's parameters are invalid.
+  pub fn hash(&self, password: &str) -> Result<String, PasswordError> {
+    let salt = SaltString::generate(&mut OsRng);
+    self
+      .argon2()?
+      .hash_password(password.as_bytes(), &salt)
+      .map(|h| h.to_string())
+      .map_err(|e| PasswordError::Hash(e.to_string()))
+  }
+
+  /// Checks `password` against a stored PHC string. Malformed hashes never
+  /// verify. The hash's own parameters are used, so hashes made under an
+  /// older policy keep working.
+  pub fn verify(&self, password: &str, hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+      return false;
+    };
+    Argon2::default()
+      .verify_password(password.as_bytes(), &parsed)
+      .is_ok()
+  }
+
+  /// True when `hash` was not made by this policy — another algorithm or
+  /// version, different cost parameters, or not a PHC string at all.
+  pub fn needs_rehash(&self, hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+      return true;
+    };
+    if parsed.algorithm != Algorithm::Argon2id.ident() || parsed.version != Some(0x13) {
+      return true;
+    }
+    let Ok(params) = Params::try_from(&parsed) else {
+      return true;
+    };
+    params.m_cost() != self.memory_kib
+      || params.t_cost() != self.iterations
+      || params.p_cost() != self.parallelism
+  }
+
+  /// Spends the time of one verification and returns `false`. Call this
+  /// when the account does not exist so response timing does not reveal
+  /// which usernames are registered.
+  pub fn verify_dummy(&self, password: &str) -> bool {
+    let dummy = self
+      .dummy
+      .get_or_init(|| self.hash("tako-dummy-password").unwrap_or_default());
+    let _ = self.verify(password, dummy);
+    false
+  }
+}
+
+fn default_policy() -> &'static PasswordPolicy {
+  static POLICY: OnceLock<PasswordPolicy> = OnceLock::new();
+  POLICY.get_or_init(PasswordPolicy::new)
+}
+
+/// Hashes `password` with the default [`PasswordPolicy`].
+///
+/// # Errors
+///
+/// Returns an error if hashing fails.
+pub fn hash(password: &str) -> Result<String, PasswordError> {
+  default_policy().hash(password)
+}
+
+/// Checks `password` against a stored hash. See [`PasswordPolicy::verify`].
+pub fn verify(password: &str, hash: &str) -> bool {
+  default_policy().verify(password, hash)
+}
+
+/// Whether `hash` differs from the default policy. See
+/// [`PasswordPolicy::needs_rehash`].
+pub fn needs_rehash(hash: &str) -> bool {
+  default_policy().needs_rehash(hash)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cheap() -> PasswordPolicy {
+    PasswordPolicy::new().memory_kib(64).iterations(1)
+  }
+
+  #[test]
+  fn hashes_are_salted_argon2id() {
+    let policy = cheap();
+    let a = policy.hash("pw").unwrap();
+    let b = policy.hash("pw").unwrap();
+    assert!(a.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+    assert_ne!(a, b);
+    assert!(policy.verify("pw", &a) && policy.verify("pw", &b));
+    assert!(!policy.verify("pW", &a));
+    assert!(!policy.verify("pw", "not a hash"));
+  }
+
+  #[test]
+  fn rehash_is_needed_when_the_policy_changes() {
+    let old = cheap();
+    let stored = old.hash("pw").unwrap();
+    assert!(!old.needs_rehash(&stored));
+
+    let stronger = old.clone().iterations(2);
+    assert!(stronger.needs_rehash(&stored));
+    // Old hashes still verify under the new policy.
+    assert!(stronger.verify("pw", &stored));
+
+    let argon2i =
+      "$argon2i$v=19$m=64,t=1,p=1$c29tZXNhbHQ$iWh06vD8Fy27wf9npn6FXWiCX4K6pW6Ue1Bnzz07Z8A";
+    assert!(old.needs_rehash(argon2i));
+    assert!(old.needs_rehash("$2b$12$bcrypt"));
+  }
+
+  #[test]
+  fn invalid_params_are_reported() {
+    let err = PasswordPolicy::new().iterations(0).hash("pw").unwrap_err();
+    assert!(matches!(err, PasswordError::InvalidParams(_)));
+    assert!(!cheap().verify_dummy("pw"));
+  }
+}
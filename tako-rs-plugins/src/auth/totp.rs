@@ -0,0 +1,470 @@
+//! Time-based one-time passwords (RFC 6238) and recovery codes.
+//!
+//! [`Totp`] holds a shared secret and produces the six-digit codes shown by
+//! authenticator apps. Enrolment is two steps: generate a secret with
+//! [`Totp::generate`], store [`Totp::secret_base32`] with the account, and
+//! show [`Totp::provisioning_uri`] as a QR code. At login, check the code
+//! with [`Totp::verify_step`], which accepts a small clock drift and returns
+//! the matched time step so the caller can refuse to accept it twice.
+//!
+//! Recovery codes are one-use fallbacks for a lost device.
+//! [`generate_recovery_codes`] returns codes to show the user once;
+//! persist only their [`hash_recovery_code`] digests and spend them with
+//! [`redeem_recovery_code`].
+//!
+//! To protect routes, call [`mark_verified`] after a successful second
+//! factor and install [`RequireTwoFactor`] in front of them. The flag lives
+//! in the [session](crate::middleware::session); the form and magic-link
+//! logins clear it.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::auth::totp::Totp;
+//!
+//! let totp = Totp::generate();
+//! let uri = totp.provisioning_uri("Example", "alice@example.com");
+//! assert!(uri.starts_with("otpauth://totp/Example:alice%40example.com?secret="));
+//!
+//! let now = 1_700_000_000;
+//! let code = totp.code_at(now);
+//! assert!(totp.verify_step(&code, now + 20).is_some());
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use hmac::Hmac;
+use hmac::Mac;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::LOCATION;
+use sha1::Sha1;
+use sha2::Digest;
+use sha2::Sha256;
+use sha2::Sha512;
+use subtle::ConstantTimeEq;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::TWO_FACTOR_SESSION_KEY;
+use crate::middleware::session::Session;
+
+/// HMAC digest used to derive codes. Authenticator apps overwhelmingly
+/// support only [`TotpAlgorithm::Sha1`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TotpAlgorithm {
+  Sha1,
+  Sha256,
+  Sha512,
+}
+
+impl TotpAlgorithm {
+  fn as_str(self) -> &'static str {
+    match self {
+      Self::Sha1 => "SHA1",
+      Self::Sha256 => "SHA256",
+      Self::Sha512 => "SHA512",
+    }
+  }
+}
+
+/// A TOTP generator / verifier for one shared secret.
+#[derive(Clone)]
+pub struct Totp {
+  secret: Vec<u8>,
+  digits: u32,
+  step: u64,
+  skew: u64,
+  algorithm: TotpAlgorithm,
+}
+
+impl std::fmt::Debug for Totp {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Totp")
+      .field("digits", &self.digits)
+      .field("step", &self.step)
+      .field("skew", &self.skew)
+      .field("algorithm", &self.algorithm)
+      .finish_non_exhaustive()
+  }
+}
+
+impl Totp {
+  /// Wraps an existing secret. Defaults: six digits, 30-second steps, one
+  /// step of drift either way, SHA-1.
+  pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+    Self {
+      secret: secret.into(),
+      digits: 6,
+      step: 30,
+      skew: 1,
+      algorithm: TotpAlgorithm::Sha1,
+    }
+  }
+
+  /// Creates a fresh 160-bit secret.
+  pub fn generate() -> Self {
+    // Two v4 UUIDs give 244 random bits; hashing them folds away the fixed
+    // version / variant bits.
+    let mut hasher = Sha256::new();
+    hasher.update(uuid::Uuid::new_v4().as_bytes());
+    hasher.update(uuid::Uuid::new_v4().as_bytes());
+    Self::new(&hasher.finalize()[..20])
+  }
+
+  /// Parses a base32 secret as stored or typed by the user. Spaces, dashes,
+  /// padding, and lowercase letters are accepted.
+  pub fn from_base32(secret: &str) -> Option<Self> {
+    base32_decode(secret).map(Self::new)
+  }
+
+  /// The secret as unpadded base32, the form authenticator apps expect.
+  pub fn secret_base32(&self) -> String {
+    base32_encode(&self.secret)
+  }
+
+  /// Code length, 6 to 8 digits.
+  pub fn digits(mut self, digits: u32) -> Self {
+    self.digits = digits.clamp(6, 8);
+    self
+  }
+
+  /// Time step in seconds.
+  pub fn step(mut self, secs: u64) -> Self {
+    self.step = secs.max(1);
+    self
+  }
+
+  /// Number of steps before and after the current one that are also
+  /// accepted, to absorb clock drift and typing time.
+  pub fn skew(mut self, steps: u64) -> Self {
+    self.skew = steps;
+    self
+  }
+
+  pub fn algorithm(mut self, algorithm: TotpAlgorithm) -> Self {
+    self.algorithm = algorithm;
+    self
+  }
+
+  /// `otpauth://` URI for enrolment, usually rendered as a QR code.
+  pub fn provisioning_uri(&self, issuer: &str, account: &str) -> String {
+    let issuer = urlencoding::encode(issuer);
+    format!(
+      "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm={algorithm}&digits={digits}&period={period}",
+      account = urlencoding::encode(account),
+      secret = self.secret_base32(),
+      algorithm = self.algorithm.as_str(),
+      digits = self.digits,
+      period = self.step,
+    )
+  }
+
+  /// The code for time step `counter`.
+  fn code_for_step(&self, counter: u64) -> u32 {
+    let msg = counter.to_be_bytes();
+    let digest = match self.algorithm {
+      TotpAlgorithm::Sha1 => mac::<Hmac<Sha1>>(&self.secret, &msg),
+      TotpAlgorithm::Sha256 => mac::<Hmac<Sha256>>(&self.secret, &msg),
+      TotpAlgorithm::Sha512 => mac::<Hmac<Sha512>>(&self.secret, &msg),
+    };
+    // Dynamic truncation, RFC 4226 §5.3.
+    let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+    let bin = u32::from_be_bytes([
+      digest[offset] & 0x7f,
+      digest[offset + 1],
+      digest[offset + 2],
+      digest[offset + 3],
+    ]);
+    bin % 10u32.pow(self.digits)
+  }
+
+  /// The code valid at `unix_secs`.
+  pub fn code_at(&self, unix_secs: u64) -> String {
+    let code = self.code_for_step(unix_secs / self.step);
+    format!("{code:0width$}", width = self.digits as usize)
+  }
+
+  /// The code valid now.
+  pub fn current_code(&self) -> String {
+    self.code_at(unix_now())
+  }
+
+  /// Checks `code` at `unix_secs` and returns the time step it matched.
+  ///
+  /// A code stays valid for the whole drift window, so persist the returned
+  /// step and reject codes whose step is not greater than the last accepted
+  /// one; otherwise an observed code can be replayed.
+  pub fn verify_step(&self, code: &str, unix_secs: u64) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != self.digits as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+      return None;
+    }
+    let current = unix_secs / self.step;
+    let mut matched = None;
+    // Every candidate is checked so timing does not reveal which one hit.
+    for counter in current.saturating_sub(self.skew)..=current.saturating_add(self.skew) {
+      let candidate = format!(
+        "{:0width$}",
+        self.code_for_step(counter),
+        width = self.digits as usize
+      );
+      if bool::from(candidate.as_bytes().ct_eq(code.as_bytes())) {
+        matched = Some(counter);
+      }
+    }
+    matched
+  }
+
+  /// Checks `code` against the current time. Prefer
+  /// [`Totp::verify_step`] when the last accepted step is stored.
+  pub fn verify(&self, code: &str) -> bool {
+    self.verify_step(code, unix_now()).is_some()
+  }
+}
+
+fn mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], msg: &[u8]) -> Vec<u8> {
+  let mut m = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+  m.update(msg);
+  m.finalize().into_bytes().to_vec()
+}
+
+fn unix_now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs())
+}
+
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+fn base32_encode(bytes: &[u8]) -> String {
+  let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+  let (mut buffer, mut bits) = (0u32, 0u32);
+  for &b in bytes {
+    buffer = (buffer << 8) | u32::from(b);
+    bits += 8;
+    while bits >= 5 {
+      bits -= 5;
+      out.push(char::from(BASE32[((buffer >> bits) & 0x1f) as usize]));
+    }
+  }
+  if bits > 0 {
+    out.push(char::from(BASE32[((buffer << (5 - bits)) & 0x1f) as usize]));
+  }
+  out
+}
+
+fn base32_decode(s: &str) -> Option<Vec<u8>> {
+  let mut out = Vec::with_capacity(s.len() * 5 / 8);
+  let (mut buffer, mut bits) = (0u32, 0u32);
+  for c in s.bytes() {
+    if matches!(c, b' ' | b'-' | b'=') {
+      continue;
+    }
+    let value = BASE32.iter().position(|&a| a == c.to_ascii_uppercase())?;
+    buffer = (buffer << 5) | u32::try_from(value).ok()?;
+    bits += 5;
+    if bits >= 8 {
+      bits -= 8;
+      out.push((buffer >> bits) as u8);
+    }
+  }
+  (!out.is_empty()).then_some(out)
+}
+
+/// Fresh recovery codes of the form `xxxx-xxxx-xxxx-xxxx` (64 random bits
+/// each). Show them to the user once and store only their hashes.
+pub fn generate_recovery_codes(n: usize) -> Vec<String> {
+  (0..n)
+    .map(|_| {
+      let hex = uuid::Uuid::new_v4().simple().to_string();
+      // Skip the version nibble at index 12 and the variant nibble at 16.
+      let hex = format!("{}{}{}", &hex[..12], &hex[13..16], &hex[17..18]);
+      format!(
+        "{}-{}-{}-{}",
+        &hex[..4],
+        &hex[4..8],
+        &hex[8..12],
+        &hex[12..]
+      )
+    })
+    .collect()
+}
+
+/// Digest to persist for a recovery code. Input is normalised, so users may
+/// type codes without dashes or in uppercase.
+pub fn hash_recovery_code(code: &str) -> String {
+  let normalized: String = code
+    .chars()
+    .filter(char::is_ascii_alphanumeric)
+    .map(|c| c.to_ascii_lowercase())
+    .collect();
+  let digest = Sha256::digest(normalized.as_bytes());
+  let mut hex = String::with_capacity(digest.len() * 2);
+  for b in digest {
+    hex.push_str(&format!("{b:02x}"));
+  }
+  hex
+}
+
+/// Spends `code`: if its hash is in `hashes`, it is removed and `true` is
+/// returned. Persist `hashes` afterwards.
+pub fn redeem_recovery_code(code: &str, hashes: &mut Vec<String>) -> bool {
+  let hash = hash_recovery_code(code);
+  let mut found = None;
+  for (i, stored) in hashes.iter().enumerate() {
+    if bool::from(stored.as_bytes().ct_eq(hash.as_bytes())) {
+      found = Some(i);
+    }
+  }
+  found.map(|i| hashes.swap_remove(i)).is_some()
+}
+
+/// Records in the session that the second factor was passed, and rotates
+/// the session id for the privilege change.
+pub fn mark_verified(session: &Session) {
+  session.rotate();
+  session.set(TWO_FACTOR_SESSION_KEY, true);
+}
+
+/// Whether [`mark_verified`] was called for this session.
+pub fn is_verified(session: &Session) -> bool {
+  session.get::<bool>(TWO_FACTOR_SESSION_KEY) == Some(true)
+}
+
+/// Middleware that admits only sessions that passed the second factor.
+/// Install it after the session middleware.
+pub struct RequireTwoFactor {
+  redirect: Option<String>,
+}
+
+impl Default for RequireTwoFactor {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl RequireTwoFactor {
+  /// Answers unverified requests with `401`.
+  pub fn new() -> Self {
+    Self { redirect: None }
+  }
+
+  /// Sends unverified requests to the code-entry page (`303`) instead.
+  pub fn redirect(mut self, location: &str) -> Self {
+    self.redirect = Some(location.to_string());
+    self
+  }
+}
+
+impl IntoMiddleware for RequireTwoFactor {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    let redirect = self
+      .redirect
+      .and_then(|location| HeaderValue::from_str(&location).ok());
+
+    move |req: Request, next: Next| {
+      let redirect = redirect.clone();
+      Box::pin(async move {
+        if req.extensions().get::<Session>().is_some_and(is_verified) {
+          return next.run(req).await;
+        }
+        let mut resp = http::Response::new(TakoBody::empty());
+        match redirect {
+          Some(location) => {
+            *resp.status_mut() = StatusCode::SEE_OTHER;
+            resp.headers_mut().insert(LOCATION, location);
+          }
+          None => *resp.status_mut() = StatusCode::UNAUTHORIZED,
+        }
+        resp
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rfc6238_test_vectors() {
+    let sha1 = Totp::new(b"12345678901234567890".to_vec()).digits(8);
+    assert_eq!(sha1.code_at(59), "94287082");
+    assert_eq!(sha1.code_at(1_111_111_109), "07081804");
+    assert_eq!(sha1.code_at(20_000_000_000), "65353130");
+
+    let sha256 = Totp::new(b"12345678901234567890123456789012".to_vec())
+      .digits(8)
+      .algorithm(TotpAlgorithm::Sha256);
+    assert_eq!(sha256.code_at(59), "46119246");
+
+    let sha512 =
+      Totp::new(b"1234567890123456789012345678901234567890123456789012345678901234".to_vec())
+        .digits(8)
+        .algorithm(TotpAlgorithm::Sha512);
+    assert_eq!(sha512.code_at(59), "90693936");
+  }
+
+  #[test]
+  fn verification_accepts_drift_and_reports_the_step() {
+    let totp = Totp::generate();
+    let code = totp.code_at(1_000_000_000);
+    let step = 1_000_000_000 / 30;
+    assert_eq!(totp.verify_step(&code, 1_000_000_000), Some(step));
+    assert_eq!(totp.verify_step(&code, 1_000_000_030), Some(step));
+    assert_eq!(totp.verify_step(&code, 1_000_000_090), None);
+    assert_eq!(totp.verify_step("12345", 1_000_000_000), None);
+    assert_eq!(totp.verify_step("abcdef", 1_000_000_000), None);
+  }
+
+  #[test]
+  fn base32_round_trips() {
+    let totp = Totp::generate();
+    let encoded = totp.secret_base32();
+    assert_eq!(encoded.len(), 32);
+    assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+    assert_eq!(base32_decode("mzxw 6ytb-oi==").unwrap(), b"foobar");
+    assert_eq!(
+      Totp::from_base32(&encoded).unwrap().secret,
+      totp.secret.clone()
+    );
+    assert!(base32_decode("not base32!").is_none());
+  }
+
+  #[test]
+  fn recovery_codes_are_spent_once() {
+    let codes = generate_recovery_codes(3);
+    assert_eq!(codes.len(), 3);
+    assert_eq!(codes[0].len(), 19);
+    let mut hashes: Vec<String> = codes.iter().map(|c| hash_recovery_code(c)).collect();
+
+    let typed = codes[1].replace('-', "").to_uppercase();
+    assert!(redeem_recovery_code(&typed, &mut hashes));
+    assert!(!redeem_recovery_code(&codes[1], &mut hashes));
+    assert_eq!(hashes.len(), 2);
+  }
+
+  #[test]
+  fn recovery_codes_leave_out_fixed_uuid_nibbles() {
+    // The UUID variant nibble is always 8..=b; a random digit is not.
+    let codes = generate_recovery_codes(64);
+    assert!(
+      codes
+        .iter()
+        .any(|c| !matches!(c.as_bytes()[18], b'8' | b'9' | b'a' | b'b'))
+    );
+  }
+}
@@ -0,0 +1,138 @@
+//! Transport-neutral email messages and templates.
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// An outgoing email.
+///
+/// Addresses are plain strings (`alice@example.com` or
+/// `Alice <alice@example.com>`) and are parsed by the transport, so a
+/// message can be serialized onto the job queue as-is. When `from` is unset
+/// the [`Mailer`](super::Mailer)'s default sender is used.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Message {
+  /// Sender; falls back to the mailer default.
+  pub from: Option<String>,
+  /// Primary recipients.
+  pub to: Vec<String>,
+  /// Carbon-copy recipients.
+  pub cc: Vec<String>,
+  /// Blind carbon-copy recipients.
+  pub bcc: Vec<String>,
+  /// `Reply-To` address.
+  pub reply_to: Option<String>,
+  /// Subject line.
+  pub subject: String,
+  /// Plain-text body.
+  pub text: Option<String>,
+  /// HTML body. Sent as `multipart/alternative` when `text` is also set.
+  pub html: Option<String>,
+}
+
+impl Message {
+  /// Creates an empty message.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Fills subject and bodies from a template.
+  pub fn from_template(template: &impl EmailTemplate) -> Self {
+    Self {
+      subject: template.subject(),
+      text: template.text(),
+      html: template.html(),
+      ..Self::default()
+    }
+  }
+
+  /// Adds a primary recipient.
+  pub fn to(mut self, address: impl Into<String>) -> Self {
+    self.to.push(address.into());
+    self
+  }
+
+  /// Adds a carbon-copy recipient.
+  pub fn cc(mut self, address: impl Into<String>) -> Self {
+    self.cc.push(address.into());
+    self
+  }
+
+  /// Adds a blind carbon-copy recipient.
+  pub fn bcc(mut self, address: impl Into<String>) -> Self {
+    self.bcc.push(address.into());
+    self
+  }
+
+  /// Overrides the mailer's default sender.
+  pub fn from(mut self, address: impl Into<String>) -> Self {
+    self.from = Some(address.into());
+    self
+  }
+
+  /// Sets the `Reply-To` address.
+  pub fn reply_to(mut self, address: impl Into<String>) -> Self {
+    self.reply_to = Some(address.into());
+    self
+  }
+
+  /// Sets the subject line.
+  pub fn subject(mut self, subject: impl Into<String>) -> Self {
+    self.subject = subject.into();
+    self
+  }
+
+  /// Sets the plain-text body.
+  pub fn text(mut self, body: impl Into<String>) -> Self {
+    self.text = Some(body.into());
+    self
+  }
+
+  /// Sets the HTML body.
+  pub fn html(mut self, body: impl Into<String>) -> Self {
+    self.html = Some(body.into());
+    self
+  }
+}
+
+/// Renders the subject and bodies of a message.
+///
+/// Implement this on a context struct with whatever template engine the
+/// application already uses (askama, minijinja, tera, or `format!`), and
+/// build messages with [`Message::from_template`]. At least one of
+/// [`text`](Self::text) and [`html`](Self::html) must return a body.
+///
+/// ```rust
+/// use tako::email::EmailTemplate;
+/// use tako::email::Message;
+///
+/// struct Welcome<'a> {
+///     name: &'a str,
+/// }
+///
+/// impl EmailTemplate for Welcome<'_> {
+///     fn subject(&self) -> String {
+///         format!("Welcome, {}", self.name)
+///     }
+///
+///     fn text(&self) -> Option<String> {
+///         Some(format!("Hi {}, thanks for signing up.", self.name))
+///     }
+/// }
+///
+/// let msg = Message::from_template(&Welcome { name: "Alice" }).to("alice@example.com");
+/// assert_eq!(msg.subject, "Welcome, Alice");
+/// ```
+pub trait EmailTemplate {
+  /// Subject line.
+  fn subject(&self) -> String;
+
+  /// Plain-text body.
+  fn text(&self) -> Option<String> {
+    None
+  }
+
+  /// HTML body.
+  fn html(&self) -> Option<String> {
+    None
+  }
+}
@@ -0,0 +1,115 @@
+#![cfg_attr(docsrs, doc(cfg(feature = "gateway")))]
+//! Declarative gateway configuration loaded from TOML or YAML.
+//!
+//! A [`GatewayConfig`] describes routes to static responses or upstream proxy
+//! targets, plus router-wide and per-route CORS, compression, and rate
+//! limiting. [`GatewayConfig::apply`] registers all of it on a [`Router`], so
+//! a gateway-style deployment needs little more than a config file and a
+//! `serve` call.
+//!
+//! The whole file is validated before anything is registered: an unknown
+//! method, a malformed upstream, or a route with neither `response` nor
+//! `proxy` returns a [`GatewayError`] and leaves the router untouched.
+//!
+//! ```toml
+//! [cors]
+//! origins = ["https://app.example.com"]
+//!
+//! [compression]
+//! brotli = true
+//! min_size = 512
+//!
+//! [rate_limit]
+//! requests_per_second = 100
+//!
+//! [[routes]]
+//! path = "/health"
+//! response = { body = "ok", content_type = "text/plain" }
+//!
+//! [[routes]]
+//! path = "/api/{*rest}"
+//! methods = ["GET", "POST"]
+//! proxy = { upstream = "http://127.0.0.1:9000", strip_prefix = "/api" }
+//! rate_limit = { requests_per_minute = 600, burst = 50 }
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use tako::gateway::GatewayConfig;
+//! use tako::router::Router;
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut router = Router::new();
+//! GatewayConfig::from_path("gateway.toml")?.apply(&mut router)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Router`]: tako_rs_core::router::Router
+
+mod apply;
+mod config;
+mod format;
+mod proxy;
+
+use std::fmt;
+
+pub use config::CompressionSection;
+pub use config::CorsSection;
+pub use config::GatewayConfig;
+pub use config::ProxySection;
+pub use config::RateLimitSection;
+pub use config::ResponseSection;
+pub use config::RouteSection;
+pub use format::Format;
+
+/// Errors produced while loading or applying a [`GatewayConfig`].
+#[derive(Debug)]
+pub enum GatewayError {
+  /// The config file could not be read.
+  Io(std::io::Error),
+  /// The file extension does not name a supported format.
+  UnknownFormat(String),
+  /// The document is not valid TOML / YAML or does not match the schema.
+  Parse(String),
+  /// The document parsed but describes something that cannot be registered.
+  Invalid {
+    /// Where in the document the problem is, e.g. `routes[2].proxy`.
+    at: String,
+    /// What is wrong with it.
+    reason: String,
+  },
+}
+
+impl GatewayError {
+  pub(crate) fn invalid(at: impl Into<String>, reason: impl Into<String>) -> Self {
+    Self::Invalid {
+      at: at.into(),
+      reason: reason.into(),
+    }
+  }
+}
+
+impl fmt::Display for GatewayError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Io(e) => write!(f, "gateway config: {e}"),
+      Self::UnknownFormat(ext) => write!(
+        f,
+        "gateway config: unsupported file extension `{ext}` (expected .toml, .yaml, or .yml)"
+      ),
+      Self::Parse(msg) => write!(f, "gateway config: {msg}"),
+      Self::Invalid { at, reason } => write!(f, "gateway config: {at}: {reason}"),
+    }
+  }
+}
+
+impl std::error::Error for GatewayError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Io(e) => Some(e),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,372 @@
+//! Validates a [`GatewayConfig`] and registers it on a [`Router`].
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::HeaderMap;
+use http::HeaderName;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::route::Route;
+use tako_rs_core::router::Router;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::GatewayError;
+use super::config::CompressionSection;
+use super::config::CorsSection;
+use super::config::GatewayConfig;
+use super::config::RateLimitSection;
+use super::config::ResponseSection;
+use super::config::RouteSection;
+use super::proxy::Upstream;
+use super::proxy::status;
+use crate::plugins::compression::CompressionBuilder;
+use crate::plugins::compression::CompressionPlugin;
+use crate::plugins::cors::CorsBuilder;
+use crate::plugins::cors::CorsPlugin;
+use crate::plugins::rate_limiter::RateLimiterBuilder;
+use crate::plugins::rate_limiter::RateLimiterPlugin;
+
+impl GatewayConfig {
+  /// Registers every route and plugin on `router`.
+  ///
+  /// Top-level sections become router-level plugins. A route's
+  /// `rate_limit` applies in addition to the top-level one and its
+  /// `compression` replaces the top-level one for that route (the inner
+  /// plugin encodes first, the outer one sees `Content-Encoding` and
+  /// steps aside). A route `cors` section is only accepted when there is no
+  /// top-level `cors`, since two policies would both write the headers; such
+  /// routes also get an `OPTIONS` registration so preflights reach them.
+  ///
+  /// Nothing is registered unless the whole config is valid.
+  pub fn apply(&self, router: &mut Router) -> Result<(), GatewayError> {
+    let global = Layers::new(
+      self.cors.as_ref(),
+      self.compression.as_ref(),
+      self.rate_limit.as_ref(),
+      "",
+    )?;
+    let mut seen = HashSet::new();
+    let mut routes = Vec::with_capacity(self.routes.len());
+    for (i, section) in self.routes.iter().enumerate() {
+      let at = format!("routes[{i}]");
+      if section.cors.is_some() && self.cors.is_some() {
+        return Err(GatewayError::invalid(
+          format!("{at}.cors"),
+          "route-level cors cannot be combined with a top-level cors section",
+        ));
+      }
+      let route = PreparedRoute::new(section, &at)?;
+      for method in &route.methods {
+        if !seen.insert((method.clone(), section.path.clone())) {
+          return Err(GatewayError::invalid(
+            at,
+            format!("{method} {} is defined more than once", section.path),
+          ));
+        }
+      }
+      routes.push(route);
+    }
+
+    if let Some(p) = global.cors {
+      router.plugin(p);
+    }
+    if let Some(p) = global.compression {
+      router.plugin(p);
+    }
+    if let Some(p) = global.rate_limit {
+      router.plugin(p);
+    }
+    for route in routes {
+      route.register(router, &mut seen);
+    }
+    Ok(())
+  }
+}
+
+/// The optional plugins one section set turns into.
+struct Layers {
+  cors: Option<CorsPlugin>,
+  compression: Option<CompressionPlugin>,
+  rate_limit: Option<RateLimiterPlugin>,
+}
+
+impl Layers {
+  fn new(
+    cors: Option<&CorsSection>,
+    compression: Option<&CompressionSection>,
+    rate_limit: Option<&RateLimitSection>,
+    at: &str,
+  ) -> Result<Self, GatewayError> {
+    Ok(Self {
+      cors: cors
+        .map(|s| cors_plugin(s, &join(at, "cors")))
+        .transpose()?,
+      compression: compression
+        .map(|s| compression_plugin(s, &join(at, "compression")))
+        .transpose()?,
+      rate_limit: rate_limit
+        .map(|s| rate_limit_plugin(s, &join(at, "rate_limit")))
+        .transpose()?,
+    })
+  }
+
+  fn attach(&self, route: &Route) {
+    if let Some(p) = &self.cors {
+      route.plugin(p.clone());
+    }
+    if let Some(p) = &self.compression {
+      route.plugin(p.clone());
+    }
+    if let Some(p) = &self.rate_limit {
+      route.plugin(p.clone());
+    }
+  }
+}
+
+enum Target {
+  Static(Arc<StaticResponse>),
+  Proxy(Arc<Upstream>),
+}
+
+struct PreparedRoute {
+  path: String,
+  methods: Vec<Method>,
+  timeout: Option<Duration>,
+  target: Target,
+  layers: Layers,
+}
+
+impl PreparedRoute {
+  fn new(section: &RouteSection, at: &str) -> Result<Self, GatewayError> {
+    if !section.path.starts_with('/') {
+      return Err(GatewayError::invalid(
+        join(at, "path"),
+        format!("`{}` must start with `/`", section.path),
+      ));
+    }
+    let mut methods = Vec::new();
+    for name in section.method.iter().chain(&section.methods) {
+      let method = Method::from_bytes(name.to_ascii_uppercase().as_bytes()).map_err(|_| {
+        GatewayError::invalid(join(at, "methods"), format!("invalid method `{name}`"))
+      })?;
+      if !methods.contains(&method) {
+        methods.push(method);
+      }
+    }
+    if methods.is_empty() {
+      methods.push(Method::GET);
+    }
+    let target = match (&section.response, &section.proxy) {
+      (Some(resp), None) => {
+        Target::Static(Arc::new(StaticResponse::new(resp, &join(at, "response"))?))
+      }
+      (None, Some(proxy)) => Target::Proxy(Arc::new(Upstream::new(proxy, &join(at, "proxy"))?)),
+      _ => {
+        return Err(GatewayError::invalid(
+          at,
+          "exactly one of `response` or `proxy` must be set",
+        ));
+      }
+    };
+    Ok(Self {
+      path: section.path.clone(),
+      methods,
+      timeout: section.timeout_ms.map(Duration::from_millis),
+      target,
+      layers: Layers::new(
+        section.cors.as_ref(),
+        section.compression.as_ref(),
+        section.rate_limit.as_ref(),
+        at,
+      )?,
+    })
+  }
+
+  fn register(self, router: &mut Router, seen: &mut HashSet<(Method, String)>) {
+    for method in &self.methods {
+      let route = match &self.target {
+        Target::Static(resp) => {
+          let resp = resp.clone();
+          router.route(method.clone(), &self.path, move |_req: Request| {
+            let resp = resp.clone();
+            async move { resp.to_response() }
+          })
+        }
+        Target::Proxy(upstream) => {
+          let upstream = upstream.clone();
+          router.route(method.clone(), &self.path, move |req: Request| {
+            let upstream = upstream.clone();
+            async move { upstream.forward(req).await }
+          })
+        }
+      };
+      if let Some(d) = self.timeout {
+        route.timeout(d);
+      }
+      self.layers.attach(&route);
+    }
+
+    // Route-level CORS only runs on matched routes, so give preflights one.
+    if self.layers.cors.is_some() && seen.insert((Method::OPTIONS, self.path.clone())) {
+      let route = router.route(Method::OPTIONS, &self.path, |_req: Request| async {
+        status(StatusCode::NO_CONTENT)
+      });
+      if let Some(p) = &self.layers.cors {
+        route.plugin(p.clone());
+      }
+    }
+  }
+}
+
+struct StaticResponse {
+  status: StatusCode,
+  headers: HeaderMap,
+  body: Bytes,
+}
+
+impl StaticResponse {
+  fn new(section: &ResponseSection, at: &str) -> Result<Self, GatewayError> {
+    let status = StatusCode::from_u16(section.status).map_err(|_| {
+      GatewayError::invalid(
+        join(at, "status"),
+        format!("invalid status {}", section.status),
+      )
+    })?;
+    let mut headers = HeaderMap::new();
+    for (name, value) in &section.headers {
+      let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+        GatewayError::invalid(join(at, "headers"), format!("invalid header name `{name}`"))
+      })?;
+      let value = HeaderValue::from_str(value).map_err(|_| {
+        GatewayError::invalid(join(at, "headers"), format!("invalid value for `{name}`"))
+      })?;
+      headers.append(name, value);
+    }
+    if let Some(ct) = &section.content_type {
+      let value = HeaderValue::from_str(ct).map_err(|_| {
+        GatewayError::invalid(join(at, "content_type"), format!("invalid value `{ct}`"))
+      })?;
+      headers.insert(CONTENT_TYPE, value);
+    }
+    Ok(Self {
+      status,
+      headers,
+      body: Bytes::from(section.body.clone()),
+    })
+  }
+
+  fn to_response(&self) -> Response {
+    let mut resp = http::Response::new(TakoBody::from(self.body.clone()));
+    *resp.status_mut() = self.status;
+    *resp.headers_mut() = self.headers.clone();
+    resp
+  }
+}
+
+fn cors_plugin(s: &CorsSection, at: &str) -> Result<CorsPlugin, GatewayError> {
+  let mut builder = CorsBuilder::new();
+  for origin in &s.origins {
+    builder = builder.allow_origin(origin.clone());
+  }
+  if !s.methods.is_empty() {
+    let mut methods = Vec::with_capacity(s.methods.len());
+    for name in &s.methods {
+      methods.push(
+        Method::from_bytes(name.to_ascii_uppercase().as_bytes())
+          .map_err(|_| GatewayError::invalid(at, format!("invalid method `{name}`")))?,
+      );
+    }
+    builder = builder.allow_methods(&methods);
+  }
+  if !s.headers.is_empty() {
+    let mut headers = Vec::with_capacity(s.headers.len());
+    for name in &s.headers {
+      headers.push(
+        HeaderName::from_bytes(name.as_bytes())
+          .map_err(|_| GatewayError::invalid(at, format!("invalid header name `{name}`")))?,
+      );
+    }
+    builder = builder.allow_headers(&headers);
+  }
+  if let Some(secs) = s.max_age_secs {
+    builder = builder.max_age_secs(secs);
+  }
+  builder
+    .allow_credentials(s.credentials)
+    .allow_private_network(s.private_network)
+    .try_build()
+    .map_err(|e| GatewayError::invalid(at, e.to_string()))
+}
+
+// Only fallible (and only uses `at`) when zstd is requested without the feature.
+#[cfg_attr(feature = "zstd", allow(unused_variables, clippy::unnecessary_wraps))]
+fn compression_plugin(s: &CompressionSection, at: &str) -> Result<CompressionPlugin, GatewayError> {
+  let mut builder = CompressionBuilder::new();
+  if let Some(yes) = s.gzip {
+    builder = builder.enable_gzip(yes);
+  }
+  if let Some(yes) = s.brotli {
+    builder = builder.enable_brotli(yes);
+  }
+  if let Some(yes) = s.deflate {
+    builder = builder.enable_deflate(yes);
+  }
+  #[cfg(feature = "zstd")]
+  if let Some(yes) = s.zstd {
+    builder = builder.enable_zstd(yes);
+  }
+  #[cfg(not(feature = "zstd"))]
+  if s.zstd == Some(true) {
+    return Err(GatewayError::invalid(
+      at,
+      "zstd requires the `zstd` feature",
+    ));
+  }
+  if let Some(n) = s.min_size {
+    builder = builder.min_size(n);
+  }
+  if let Some(yes) = s.stream {
+    builder = builder.enable_stream(yes);
+  }
+  Ok(builder.build())
+}
+
+fn rate_limit_plugin(s: &RateLimitSection, at: &str) -> Result<RateLimiterPlugin, GatewayError> {
+  let mut builder = match (s.requests_per_second, s.requests_per_minute) {
+    (Some(n), None) if n > 0 => RateLimiterBuilder::new().requests_per_second(n),
+    (None, Some(n)) if n > 0 => RateLimiterBuilder::new().requests_per_minute(n),
+    _ => {
+      return Err(GatewayError::invalid(
+        at,
+        "set exactly one non-zero `requests_per_second` or `requests_per_minute`",
+      ));
+    }
+  };
+  if let Some(burst) = s.burst {
+    if burst == 0 {
+      return Err(GatewayError::invalid(at, "`burst` must be non-zero"));
+    }
+    builder = builder.max_requests(burst);
+  }
+  if let Some(code) = s.status {
+    let status = StatusCode::from_u16(code)
+      .map_err(|_| GatewayError::invalid(at, format!("invalid status {code}")))?;
+    builder = builder.status(status);
+  }
+  Ok(builder.build())
+}
+
+fn join(at: &str, field: &str) -> String {
+  if at.is_empty() {
+    field.to_string()
+  } else {
+    format!("{at}.{field}")
+  }
+}
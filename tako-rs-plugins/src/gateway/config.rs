@@ -0,0 +1,147 @@
+//! The config file schema.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+/// A whole gateway config file.
+///
+/// The top-level `cors`, `compression`, and `rate_limit` sections apply to
+/// every route; a route's own sections are layered on top of them.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GatewayConfig {
+  #[serde(default)]
+  pub cors: Option<CorsSection>,
+  #[serde(default)]
+  pub compression: Option<CompressionSection>,
+  #[serde(default)]
+  pub rate_limit: Option<RateLimitSection>,
+  #[serde(default)]
+  pub routes: Vec<RouteSection>,
+}
+
+/// One route. Exactly one of `response` or `proxy` must be set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteSection {
+  /// Route pattern in router syntax, e.g. `/users/{id}` or `/api/{*rest}`.
+  pub path: String,
+  /// Single method shorthand; merged with `methods`.
+  #[serde(default)]
+  pub method: Option<String>,
+  /// Methods to register. Default: `["GET"]`.
+  #[serde(default)]
+  pub methods: Vec<String>,
+  /// Per-route handler timeout in milliseconds.
+  #[serde(default)]
+  pub timeout_ms: Option<u64>,
+  #[serde(default)]
+  pub response: Option<ResponseSection>,
+  #[serde(default)]
+  pub proxy: Option<ProxySection>,
+  #[serde(default)]
+  pub cors: Option<CorsSection>,
+  #[serde(default)]
+  pub compression: Option<CompressionSection>,
+  #[serde(default)]
+  pub rate_limit: Option<RateLimitSection>,
+}
+
+/// A fixed response served for every request to the route.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResponseSection {
+  /// Default: 200.
+  #[serde(default = "default_status")]
+  pub status: u16,
+  #[serde(default)]
+  pub body: String,
+  #[serde(default)]
+  pub content_type: Option<String>,
+  #[serde(default)]
+  pub headers: BTreeMap<String, String>,
+}
+
+/// An upstream the route forwards to.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProxySection {
+  /// Base URL (`http://` or `https://`); the request path and query are
+  /// appended to its path.
+  pub upstream: String,
+  /// Prefix removed from the request path before it is appended.
+  #[serde(default)]
+  pub strip_prefix: Option<String>,
+  /// Forward the client's `Host` header instead of the upstream's.
+  #[serde(default)]
+  pub preserve_host: bool,
+  /// Upstream request timeout in milliseconds. Default: 30 000.
+  #[serde(default)]
+  pub timeout_ms: Option<u64>,
+  /// Largest request body forwarded, in bytes; larger ones are answered
+  /// with `413`. Default: 8 MiB.
+  #[serde(default)]
+  pub max_body_bytes: Option<usize>,
+}
+
+/// Maps onto [`CorsBuilder`](crate::plugins::cors::CorsBuilder).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CorsSection {
+  /// Allowed origins; empty reflects `*`.
+  #[serde(default)]
+  pub origins: Vec<String>,
+  /// Allowed methods; empty keeps the plugin default.
+  #[serde(default)]
+  pub methods: Vec<String>,
+  #[serde(default)]
+  pub headers: Vec<String>,
+  #[serde(default)]
+  pub credentials: bool,
+  #[serde(default)]
+  pub max_age_secs: Option<u32>,
+  #[serde(default)]
+  pub private_network: bool,
+}
+
+/// Maps onto [`CompressionBuilder`](crate::plugins::compression::CompressionBuilder).
+/// Unset fields keep the plugin defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompressionSection {
+  #[serde(default)]
+  pub gzip: Option<bool>,
+  #[serde(default)]
+  pub brotli: Option<bool>,
+  #[serde(default)]
+  pub deflate: Option<bool>,
+  /// Only honoured with the `zstd` feature.
+  #[serde(default)]
+  pub zstd: Option<bool>,
+  #[serde(default)]
+  pub min_size: Option<usize>,
+  #[serde(default)]
+  pub stream: Option<bool>,
+}
+
+/// Maps onto [`RateLimiterBuilder`](crate::plugins::rate_limiter::RateLimiterBuilder).
+/// Set exactly one of `requests_per_second` / `requests_per_minute`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitSection {
+  #[serde(default)]
+  pub requests_per_second: Option<u32>,
+  #[serde(default)]
+  pub requests_per_minute: Option<u32>,
+  /// Bucket capacity; defaults to the per-interval rate.
+  #[serde(default)]
+  pub burst: Option<u32>,
+  /// Status returned when limited. Default: 429.
+  #[serde(default)]
+  pub status: Option<u16>,
+}
+
+fn default_status() -> u16 {
+  200
+}
@@ -0,0 +1,163 @@
+//! TOML / YAML parsing into [`GatewayConfig`].
+
+use std::path::Path;
+
+use serde_json::Map;
+use serde_json::Value as Json;
+use toml_edit::Item;
+use toml_edit::Table;
+use toml_edit::Value;
+
+use super::GatewayError;
+use super::config::GatewayConfig;
+
+/// Config file syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+  Toml,
+  Yaml,
+}
+
+impl Format {
+  /// Picks the format from a `.toml`, `.yaml`, or `.yml` extension.
+  pub fn from_path(path: &Path) -> Result<Self, GatewayError> {
+    let ext = path
+      .extension()
+      .and_then(|e| e.to_str())
+      .unwrap_or_default();
+    match ext.to_ascii_lowercase().as_str() {
+      "toml" => Ok(Self::Toml),
+      "yaml" | "yml" => Ok(Self::Yaml),
+      _ => Err(GatewayError::UnknownFormat(ext.to_string())),
+    }
+  }
+}
+
+impl GatewayConfig {
+  /// Reads and parses `path`, choosing the syntax from its extension.
+  pub fn from_path(path: impl AsRef<Path>) -> Result<Self, GatewayError> {
+    let path = path.as_ref();
+    let format = Format::from_path(path)?;
+    let text = std::fs::read_to_string(path).map_err(GatewayError::Io)?;
+    Self::parse(&text, format)
+  }
+
+  /// Parses `text` as `format`.
+  pub fn parse(text: &str, format: Format) -> Result<Self, GatewayError> {
+    match format {
+      Format::Toml => Self::from_toml_str(text),
+      Format::Yaml => Self::from_yaml_str(text),
+    }
+  }
+
+  /// Parses a TOML document.
+  pub fn from_toml_str(text: &str) -> Result<Self, GatewayError> {
+    let doc = toml_edit::Document::parse(text).map_err(|e| GatewayError::Parse(e.to_string()))?;
+    serde_json::from_value(table_to_json(doc.as_table()))
+      .map_err(|e| GatewayError::Parse(e.to_string()))
+  }
+
+  /// Parses a YAML document.
+  pub fn from_yaml_str(text: &str) -> Result<Self, GatewayError> {
+    serde_norway::from_str(text).map_err(|e| GatewayError::Parse(e.to_string()))
+  }
+}
+
+// `toml_edit` is only built with its parser here, so the document is
+// lowered to JSON and deserialized from that.
+fn table_to_json(table: &Table) -> Json {
+  let mut out = Map::new();
+  for (key, item) in table {
+    if let Some(value) = item_to_json(item) {
+      out.insert(key.to_string(), value);
+    }
+  }
+  Json::Object(out)
+}
+
+fn item_to_json(item: &Item) -> Option<Json> {
+  match item {
+    Item::None => None,
+    Item::Value(v) => Some(value_to_json(v)),
+    Item::Table(t) => Some(table_to_json(t)),
+    Item::ArrayOfTables(a) => Some(Json::Array(a.iter().map(table_to_json).collect())),
+  }
+}
+
+fn value_to_json(value: &Value) -> Json {
+  match value {
+    Value::String(s) => Json::String(s.value().clone()),
+    Value::Integer(i) => Json::from(*i.value()),
+    Value::Float(f) => Json::from(*f.value()),
+    Value::Boolean(b) => Json::Bool(*b.value()),
+    Value::Datetime(d) => Json::String(d.value().to_string()),
+    Value::Array(a) => Json::Array(a.iter().map(value_to_json).collect()),
+    Value::InlineTable(t) => {
+      let mut out = Map::new();
+      for (key, v) in t {
+        out.insert(key.to_string(), value_to_json(v));
+      }
+      Json::Object(out)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TOML: &str = r#"
+[compression]
+brotli = true
+
+[[routes]]
+path = "/health"
+response = { body = "ok", headers = { x-served-by = "gateway" } }
+
+[[routes]]
+path = "/api/{*rest}"
+methods = ["GET", "POST"]
+proxy = { upstream = "http://127.0.0.1:9000", strip_prefix = "/api" }
+"#;
+
+  const YAML: &str = r#"
+compression:
+  brotli: true
+routes:
+  - path: /health
+    response:
+      body: ok
+      headers:
+        x-served-by: gateway
+  - path: "/api/{*rest}"
+    methods: [GET, POST]
+    proxy:
+      upstream: http://127.0.0.1:9000
+      strip_prefix: /api
+"#;
+
+  fn check(cfg: &GatewayConfig) {
+    assert_eq!(cfg.compression.as_ref().unwrap().brotli, Some(true));
+    assert_eq!(cfg.routes.len(), 2);
+    let health = cfg.routes[0].response.as_ref().unwrap();
+    assert_eq!(health.status, 200);
+    assert_eq!(health.headers["x-served-by"], "gateway");
+    let proxy = cfg.routes[1].proxy.as_ref().unwrap();
+    assert_eq!(proxy.strip_prefix.as_deref(), Some("/api"));
+    assert_eq!(cfg.routes[1].methods, ["GET", "POST"]);
+  }
+
+  #[test]
+  fn toml_and_yaml_describe_the_same_config() {
+    check(&GatewayConfig::from_toml_str(TOML).unwrap());
+    check(&GatewayConfig::from_yaml_str(YAML).unwrap());
+  }
+
+  #[test]
+  fn unknown_keys_are_rejected() {
+    let err =
+      GatewayConfig::from_toml_str("[[routes]]\npath = \"/\"\nhandler = \"x\"\n").unwrap_err();
+    assert!(matches!(err, GatewayError::Parse(_)));
+    assert!(Format::from_path(Path::new("gateway.json")).is_err());
+  }
+}
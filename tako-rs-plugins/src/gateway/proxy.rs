@@ -0,0 +1,198 @@
+//! Forwarding handler for `proxy` routes.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use http::HeaderMap;
+use http::HeaderValue;
+use http::StatusCode;
+use http::Uri;
+use http::header;
+use http_body_util::BodyExt;
+use http_body_util::Full;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::client::V2Client;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::GatewayError;
+use super::config::ProxySection;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_BODY: usize = 8 * 1024 * 1024;
+
+/// Hop-by-hop headers (RFC 9110 §7.6.1) that must not be forwarded.
+const HOP_BY_HOP: [&str; 8] = [
+  "connection",
+  "keep-alive",
+  "proxy-connection",
+  "proxy-authenticate",
+  "te",
+  "trailer",
+  "transfer-encoding",
+  "upgrade",
+];
+
+/// A parsed `proxy` section plus the pooled client that serves it.
+pub(crate) struct Upstream {
+  client: V2Client,
+  scheme: http::uri::Scheme,
+  authority: http::uri::Authority,
+  base_path: String,
+  strip_prefix: Option<String>,
+  preserve_host: bool,
+  max_body: usize,
+}
+
+impl Upstream {
+  pub(crate) fn new(section: &ProxySection, at: &str) -> Result<Self, GatewayError> {
+    let uri: Uri = section
+      .upstream
+      .parse()
+      .map_err(|e| GatewayError::invalid(at, format!("upstream `{}`: {e}", section.upstream)))?;
+    let (Some(scheme), Some(authority)) = (uri.scheme().cloned(), uri.authority().cloned()) else {
+      return Err(GatewayError::invalid(
+        at,
+        format!(
+          "upstream `{}` must be an absolute http(s) URL",
+          section.upstream
+        ),
+      ));
+    };
+    if scheme != http::uri::Scheme::HTTP && scheme != http::uri::Scheme::HTTPS {
+      return Err(GatewayError::invalid(
+        at,
+        format!("upstream scheme `{scheme}` is not http or https"),
+      ));
+    }
+    let timeout = section
+      .timeout_ms
+      .map_or(DEFAULT_TIMEOUT, Duration::from_millis);
+    Ok(Self {
+      client: V2Client::builder().timeout(timeout).build(),
+      scheme,
+      authority,
+      base_path: uri.path().trim_end_matches('/').to_string(),
+      strip_prefix: section
+        .strip_prefix
+        .as_deref()
+        .map(|p| p.trim_end_matches('/').to_string())
+        .filter(|p| !p.is_empty()),
+      preserve_host: section.preserve_host,
+      max_body: section.max_body_bytes.unwrap_or(DEFAULT_MAX_BODY),
+    })
+  }
+
+  /// The upstream URI for a request to `uri`.
+  fn target(&self, uri: &Uri) -> Result<Uri, http::Error> {
+    let mut path = uri.path();
+    if let Some(prefix) = &self.strip_prefix
+      && let Some(rest) = path.strip_prefix(prefix.as_str())
+      && (rest.is_empty() || rest.starts_with('/'))
+    {
+      path = rest;
+    }
+    let mut target = format!("{}{}", self.base_path, path);
+    if !target.starts_with('/') {
+      target.insert(0, '/');
+    }
+    if let Some(query) = uri.query() {
+      target.push('?');
+      target.push_str(query);
+    }
+    Uri::builder()
+      .scheme(self.scheme.clone())
+      .authority(self.authority.clone())
+      .path_and_query(target)
+      .build()
+  }
+
+  pub(crate) async fn forward(&self, req: Request) -> Response {
+    let (parts, body) = req.into_parts();
+    let Ok(target) = self.target(&parts.uri) else {
+      return status(StatusCode::BAD_REQUEST);
+    };
+    // The body is buffered so the client can retry it; refuse anything
+    // advertised or streamed past the cap instead of growing without bound.
+    if parts
+      .headers
+      .get(header::CONTENT_LENGTH)
+      .and_then(|v| v.to_str().ok()?.parse::<usize>().ok())
+      .is_some_and(|n| n > self.max_body)
+    {
+      return status(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    let body = match http_body_util::Limited::new(body, self.max_body)
+      .collect()
+      .await
+    {
+      Ok(body) => body,
+      Err(e) if e.is::<http_body_util::LengthLimitError>() => {
+        return status(StatusCode::PAYLOAD_TOO_LARGE);
+      }
+      Err(_) => return status(StatusCode::BAD_REQUEST),
+    };
+
+    let mut headers = parts.headers;
+    strip_hop_by_hop(&mut headers);
+    let original_host = headers.remove(header::HOST);
+    if let Some(host) = &original_host {
+      if self.preserve_host {
+        headers.insert(header::HOST, host.clone());
+      } else {
+        headers.insert("x-forwarded-host", host.clone());
+      }
+    }
+    if let Some(peer) = parts.extensions.get::<SocketAddr>() {
+      let forwarded = match headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) {
+        Some(prior) => format!("{prior}, {}", peer.ip()),
+        None => peer.ip().to_string(),
+      };
+      if let Ok(v) = HeaderValue::from_str(&forwarded) {
+        headers.insert("x-forwarded-for", v);
+      }
+    }
+
+    let mut upstream_req = http::Request::new(Full::new(body.to_bytes()));
+    *upstream_req.method_mut() = parts.method;
+    *upstream_req.uri_mut() = target;
+    *upstream_req.headers_mut() = headers;
+
+    match self.client.send(upstream_req).await {
+      Ok(resp) => {
+        let (mut parts, body) = resp.into_parts();
+        strip_hop_by_hop(&mut parts.headers);
+        http::Response::from_parts(parts, TakoBody::incoming(body))
+      }
+      Err(e) => {
+        tracing::warn!(upstream = %self.authority, error = %e, "gateway: upstream request failed");
+        status(StatusCode::BAD_GATEWAY)
+      }
+    }
+  }
+}
+
+/// Removes the fixed hop-by-hop set plus anything listed in `Connection`.
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+  let listed: Vec<String> = headers
+    .get_all(header::CONNECTION)
+    .iter()
+    .filter_map(|v| v.to_str().ok())
+    .flat_map(|v| v.split(','))
+    .map(|name| name.trim().to_ascii_lowercase())
+    .filter(|name| !name.is_empty())
+    .collect();
+  for name in HOP_BY_HOP
+    .iter()
+    .copied()
+    .chain(listed.iter().map(String::as_str))
+  {
+    headers.remove(name);
+  }
+}
+
+pub(super) fn status(code: StatusCode) -> Response {
+  let mut resp = http::Response::new(TakoBody::empty());
+  *resp.status_mut() = code;
+  resp
+}
@@ -0,0 +1,333 @@
+//! Image uploads: validation, metadata stripping, and resized variants.
+//!
+//! - [`ImagePolicy`] checks an upload before anything decodes it: the format
+//!   is sniffed from magic bytes (PNG, JPEG, GIF, WebP) rather than trusted
+//!   from the client's `Content-Type`, and the dimensions are read from the
+//!   header so decompression bombs are rejected up front.
+//! - [`strip_metadata`] removes EXIF (camera, GPS, timestamps) without
+//!   re-encoding.
+//! - [`Transform`] resizes to a box with a [`Fit`] mode and re-encodes in the
+//!   source format, honouring the EXIF orientation.
+//! - [`ImagePipeline`] runs all three on an upload and produces named
+//!   variants (thumbnails, previews) in one call.
+//! - [`ImagesPlugin`] serves variants on demand: `GET /media/cat.jpg?w=200&h=200&fit=cover`
+//!   runs the route as usual and transforms the image it returns, caching
+//!   the result in memory.
+//!
+//! Decoding and resizing are CPU-bound; the async entry points run them on
+//! the blocking pool.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use tako::extractors::multipart::InMemoryFile;
+//! use tako::images::{Fit, ImagePipeline, ImagePolicy, ImagesBuilder, Transform};
+//!
+//! fn avatars() -> ImagePipeline {
+//!     ImagePipeline::new()
+//!         .policy(ImagePolicy::new().max_dimensions(8000, 8000))
+//!         .variant("thumb", Transform::new(200, 200).fit(Fit::Cover))
+//! }
+//!
+//! async fn upload(file: InMemoryFile) -> anyhow::Result<()> {
+//!     let image = avatars().process_async(file.data.into()).await?;
+//!     store("original", image.original).await?;
+//!     for (name, bytes) in image.variants {
+//!         store(&name, bytes).await?;
+//!     }
+//!     Ok(())
+//! }
+//!
+//! router.plugin(ImagesBuilder::new().path_prefix("/media").build());
+//! ```
+
+use std::fmt;
+
+use http::StatusCode;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::types::Response;
+
+mod config;
+mod format;
+mod metadata;
+mod pipeline;
+mod plugin;
+mod transform;
+
+pub use config::Config;
+pub use config::ImagesBuilder;
+pub use format::ImageFormat;
+pub use format::ImageInfo;
+pub use format::ImagePolicy;
+pub use metadata::strip_metadata;
+pub use pipeline::ImagePipeline;
+pub use pipeline::ProcessedImage;
+pub use plugin::ImagesPlugin;
+pub use transform::Fit;
+pub use transform::Transform;
+
+/// Why an image was rejected or could not be processed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+  /// The bytes are not a PNG, JPEG, GIF, or WebP image.
+  UnsupportedFormat,
+  /// The format is recognised but not allowed by the policy.
+  DisallowedFormat(ImageFormat),
+  /// The encoded image is larger than the policy allows.
+  TooLarge {
+    /// Encoded size in bytes.
+    bytes: usize,
+    /// Configured maximum.
+    limit: usize,
+  },
+  /// Width or height (or their product) exceeds the policy.
+  DimensionsTooLarge {
+    /// Image width in pixels.
+    width: u32,
+    /// Image height in pixels.
+    height: u32,
+  },
+  /// The requested transform is invalid (zero or oversized target).
+  InvalidTransform(String),
+  /// The image is truncated or corrupt, or could not be re-encoded.
+  Malformed(String),
+}
+
+impl fmt::Display for ImageError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::UnsupportedFormat => f.write_str("unsupported image format"),
+      Self::DisallowedFormat(format) => write!(f, "{} images are not allowed", format.name()),
+      Self::TooLarge { bytes, limit } => {
+        write!(f, "image is {bytes} bytes, the limit is {limit}")
+      }
+      Self::DimensionsTooLarge { width, height } => {
+        write!(f, "image dimensions {width}x{height} exceed the limit")
+      }
+      Self::InvalidTransform(msg) => write!(f, "invalid image transform: {msg}"),
+      Self::Malformed(msg) => write!(f, "malformed image: {msg}"),
+    }
+  }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<image::ImageError> for ImageError {
+  fn from(err: image::ImageError) -> Self {
+    Self::Malformed(err.to_string())
+  }
+}
+
+impl ImageError {
+  /// Status code a handler should answer with.
+  pub fn status(&self) -> StatusCode {
+    match self {
+      Self::UnsupportedFormat | Self::DisallowedFormat(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+      Self::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+      Self::InvalidTransform(_) => StatusCode::BAD_REQUEST,
+      Self::DimensionsTooLarge { .. } | Self::Malformed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+    }
+  }
+}
+
+impl Responder for ImageError {
+  fn into_response(self) -> Response {
+    (self.status(), self.to_string()).into_response()
+  }
+}
+
+/// Runs CPU-bound image work off the async workers where the runtime allows
+/// it. A panic in `f` (a decoder choking on hostile input) becomes
+/// [`ImageError::Malformed`].
+async fn blocking<T, F>(f: F) -> Result<T, ImageError>
+where
+  F: FnOnce() -> Result<T, ImageError> + Send + 'static,
+  T: Send + 'static,
+{
+  #[cfg(not(feature = "compio"))]
+  {
+    tokio::task::spawn_blocking(f)
+      .await
+      .map_err(|e| ImageError::Malformed(format!("image processing failed: {e}")))?
+  }
+  #[cfg(feature = "compio")]
+  {
+    f()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+
+  use std::sync::Arc;
+
+  use bytes::Bytes;
+  use http::HeaderValue;
+  use http::Method;
+  use http::header;
+  use http_body_util::BodyExt;
+  use image::DynamicImage;
+  use image::GenericImageView;
+  use tako_rs_core::body::TakoBody;
+  use tako_rs_core::router::Router;
+  use tako_rs_core::types::Request;
+
+  use super::*;
+
+  fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    DynamicImage::new_rgb8(width, height)
+      .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+      .unwrap();
+    out
+  }
+
+  fn jpeg_with_exif(width: u32, height: u32) -> Vec<u8> {
+    let mut plain = Vec::new();
+    DynamicImage::new_rgb8(width, height)
+      .write_to(&mut Cursor::new(&mut plain), image::ImageFormat::Jpeg)
+      .unwrap();
+    // SOI, then an APP1 Exif segment carrying a fake GPS marker.
+    let payload = b"Exif\0\0GPS-SECRET";
+    let len = u16::try_from(payload.len() + 2).unwrap().to_be_bytes();
+    let mut out = plain[..2].to_vec();
+    out.extend_from_slice(&[0xFF, 0xE1, len[0], len[1]]);
+    out.extend_from_slice(payload);
+    out.extend_from_slice(&plain[2..]);
+    out
+  }
+
+  fn dimensions(bytes: &[u8]) -> (u32, u32) {
+    image::load_from_memory(bytes).unwrap().dimensions()
+  }
+
+  #[test]
+  fn policy_sniffs_format_and_rejects_oversized_images() {
+    let info = ImagePolicy::new().validate(&png(40, 30)).unwrap();
+    assert_eq!(info.format, ImageFormat::Png);
+    assert_eq!((info.width, info.height), (40, 30));
+
+    assert_eq!(
+      ImagePolicy::new().validate(b"<svg></svg>"),
+      Err(ImageError::UnsupportedFormat)
+    );
+    assert_eq!(
+      ImagePolicy::new()
+        .formats([ImageFormat::Jpeg])
+        .validate(&png(4, 4)),
+      Err(ImageError::DisallowedFormat(ImageFormat::Png))
+    );
+    assert_eq!(
+      ImagePolicy::new()
+        .max_dimensions(32, 32)
+        .validate(&png(40, 30)),
+      Err(ImageError::DimensionsTooLarge {
+        width: 40,
+        height: 30
+      })
+    );
+  }
+
+  #[test]
+  fn strips_exif_and_renders_variants() {
+    let upload = jpeg_with_exif(64, 32);
+    let stripped = strip_metadata(&upload).unwrap();
+    assert!(!stripped.windows(10).any(|w| w == b"GPS-SECRET"));
+    assert_eq!(dimensions(&stripped), (64, 32));
+
+    let processed = ImagePipeline::new()
+      .variant("thumb", Transform::new(16, 16).fit(Fit::Cover))
+      .variant("wide", Transform::width(32))
+      .process(&upload)
+      .unwrap();
+    assert_eq!(processed.info.format, ImageFormat::Jpeg);
+    assert_eq!(processed.original, stripped);
+    assert_eq!(processed.variants[0].0, "thumb");
+    assert_eq!(dimensions(&processed.variants[0].1), (16, 16));
+    assert_eq!(dimensions(&processed.variants[1].1), (32, 16));
+  }
+
+  #[test]
+  fn strip_rejects_short_and_truncated_jpeg_segments() {
+    let malformed = |bytes: &[u8]| matches!(strip_metadata(bytes), Err(ImageError::Malformed(_)));
+    // Zero and one-byte lengths cannot even cover the length field.
+    assert!(malformed(&[0xFF, 0xD8, 0xFF, 0xE1, 0, 0]));
+    assert!(malformed(&[0xFF, 0xD8, 0xFF, 0xE1, 0, 1, 0xAA]));
+    // Length runs past the end of the data.
+    assert!(malformed(&[0xFF, 0xD8, 0xFF, 0xE1, 0, 16, b'E', b'x']));
+    assert!(malformed(&[0xFF, 0xD8, 0xFF, 0xE1, 0]));
+    assert!(malformed(&[0xFF, 0xD8, 0xFF]));
+  }
+
+  #[cfg(not(feature = "compio"))]
+  #[tokio::test]
+  async fn blocking_maps_panics_to_errors() {
+    let result: Result<(), ImageError> = blocking(|| panic!("decoder bug")).await;
+    assert!(matches!(result, Err(ImageError::Malformed(_))));
+  }
+
+  #[test]
+  fn fit_modes_size_the_output() {
+    let source = png(100, 50);
+    let size = |t: Transform| dimensions(&t.apply(&source).unwrap());
+    assert_eq!(size(Transform::new(40, 40)), (40, 20));
+    assert_eq!(size(Transform::new(40, 40).fit(Fit::Cover)), (40, 40));
+    assert_eq!(size(Transform::new(40, 10).fit(Fit::Fill)), (40, 10));
+    assert_eq!(size(Transform::new(400, 400).fit(Fit::Inside)), (100, 50));
+    assert_eq!(size(Transform::height(10)), (20, 10));
+
+    assert_eq!(Transform::from_query("page=2", 100), Ok(None));
+    assert!(Transform::from_query("w=101", 100).is_err());
+    assert!(Transform::from_query("w=10&fit=tile", 100).is_err());
+  }
+
+  #[tokio::test]
+  async fn plugin_resizes_on_demand_and_caches_variants() {
+    let source = Bytes::from(png(80, 40));
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut router = Router::new();
+    {
+      let calls = Arc::clone(&calls);
+      router.route(Method::GET, "/media/{name}", move |_req: Request| {
+        let source = source.clone();
+        let calls = Arc::clone(&calls);
+        async move {
+          calls.fetch_add(1, Ordering::SeqCst);
+          let mut resp = http::Response::new(TakoBody::from(source));
+          resp
+            .headers_mut()
+            .insert(header::ETAG, HeaderValue::from_static("\"src\""));
+          resp
+        }
+      });
+    }
+    router.plugin(ImagesBuilder::new().path_prefix("/media").build());
+    router.setup_plugins_once();
+
+    let get = |uri: &str| {
+      http::Request::builder()
+        .uri(uri)
+        .body(TakoBody::empty())
+        .unwrap()
+    };
+    for _ in 0..2 {
+      let resp = router
+        .dispatch(get("/media/a.png?w=20&h=20&fit=cover"))
+        .await;
+      assert_eq!(resp.status(), StatusCode::OK);
+      assert_eq!(resp.headers()[header::CONTENT_TYPE], "image/png");
+      assert!(resp.headers().get(header::ETAG).is_none());
+      let body = resp.into_body().collect().await.unwrap().to_bytes();
+      assert_eq!(dimensions(&body), (20, 20));
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let resp = router.dispatch(get("/media/a.png")).await;
+    assert_eq!(resp.headers()[header::ETAG], "\"src\"");
+    let resp = router.dispatch(get("/media/a.png?w=0")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  }
+}
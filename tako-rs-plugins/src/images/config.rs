@@ -0,0 +1,91 @@
+//! On-demand transform plugin configuration and its builder.
+
+use std::time::Duration;
+
+use super::ImagePolicy;
+use super::plugin::ImagesPlugin;
+
+/// Runtime settings for [`ImagesPlugin`].
+#[derive(Debug, Clone)]
+pub struct Config {
+  /// Only responses under this path are transformed. Default: `/`.
+  pub path_prefix: String,
+  /// Policy the source image must satisfy.
+  pub policy: ImagePolicy,
+  /// Largest `w` / `h` a query may ask for. Default: 4096.
+  pub max_side: u32,
+  /// Memory budget for cached variants, in bytes; `0` disables caching.
+  /// Default: 64 MiB.
+  pub cache_capacity: usize,
+  /// How long a cached variant is served before the route runs again.
+  /// Default: 10 minutes.
+  pub cache_ttl: Duration,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      path_prefix: "/".to_string(),
+      policy: ImagePolicy::default(),
+      max_side: 4096,
+      cache_capacity: 64 * 1024 * 1024,
+      cache_ttl: Duration::from_secs(600),
+    }
+  }
+}
+
+/// Builder for [`ImagesPlugin`].
+pub struct ImagesBuilder(Config);
+
+impl Default for ImagesBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ImagesBuilder {
+  /// Starts from the defaults.
+  pub fn new() -> Self {
+    Self(Config::default())
+  }
+
+  /// Restricts transforms to paths under `prefix`, e.g. `/media`.
+  pub fn path_prefix(mut self, prefix: impl Into<String>) -> Self {
+    self.0.path_prefix = prefix.into();
+    self
+  }
+
+  /// Replaces the policy applied to source images.
+  pub fn policy(mut self, policy: ImagePolicy) -> Self {
+    self.0.policy = policy;
+    self
+  }
+
+  /// Rejects `w` / `h` above `pixels` with `400 Bad Request`.
+  pub fn max_side(mut self, pixels: u32) -> Self {
+    self.0.max_side = pixels;
+    self
+  }
+
+  /// Sets the cache's memory budget in bytes.
+  pub fn cache_capacity(mut self, bytes: usize) -> Self {
+    self.0.cache_capacity = bytes;
+    self
+  }
+
+  /// Sets how long cached variants are reused.
+  pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+    self.0.cache_ttl = ttl;
+    self
+  }
+
+  /// Transforms every request, caching nothing.
+  pub fn no_cache(mut self) -> Self {
+    self.0.cache_capacity = 0;
+    self
+  }
+
+  pub fn build(self) -> ImagesPlugin {
+    ImagesPlugin::new(self.0)
+  }
+}
@@ -0,0 +1,178 @@
+//! Format sniffing and upload validation.
+
+use std::io::Cursor;
+
+use super::ImageError;
+
+/// Image formats the pipeline understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+  /// `image/png`
+  Png,
+  /// `image/jpeg`
+  Jpeg,
+  /// `image/gif`
+  Gif,
+  /// `image/webp`
+  Webp,
+}
+
+impl ImageFormat {
+  /// Every supported format.
+  pub const ALL: [ImageFormat; 4] = [Self::Png, Self::Jpeg, Self::Gif, Self::Webp];
+
+  /// Detects the format from the leading magic bytes.
+  pub fn sniff(bytes: &[u8]) -> Option<Self> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+      Some(Self::Png)
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+      Some(Self::Jpeg)
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+      Some(Self::Gif)
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+      Some(Self::Webp)
+    } else {
+      None
+    }
+  }
+
+  /// The MIME type, e.g. `image/png`.
+  pub fn mime(self) -> &'static str {
+    match self {
+      Self::Png => "image/png",
+      Self::Jpeg => "image/jpeg",
+      Self::Gif => "image/gif",
+      Self::Webp => "image/webp",
+    }
+  }
+
+  /// Short display name, e.g. `PNG`.
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Png => "PNG",
+      Self::Jpeg => "JPEG",
+      Self::Gif => "GIF",
+      Self::Webp => "WebP",
+    }
+  }
+
+  pub(super) fn codec(self) -> image::ImageFormat {
+    match self {
+      Self::Png => image::ImageFormat::Png,
+      Self::Jpeg => image::ImageFormat::Jpeg,
+      Self::Gif => image::ImageFormat::Gif,
+      Self::Webp => image::ImageFormat::WebP,
+    }
+  }
+}
+
+/// What [`ImagePolicy::validate`] learned about an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageInfo {
+  /// Format sniffed from the magic bytes.
+  pub format: ImageFormat,
+  /// Width in pixels.
+  pub width: u32,
+  /// Height in pixels.
+  pub height: u32,
+}
+
+/// Limits an image has to satisfy before it is accepted or decoded.
+///
+/// Defaults: every supported format, at most 16 MiB encoded, 10 000 pixels
+/// per side and 40 megapixels in total.
+#[derive(Debug, Clone)]
+pub struct ImagePolicy {
+  pub(super) formats: Vec<ImageFormat>,
+  pub(super) max_bytes: usize,
+  pub(super) max_width: u32,
+  pub(super) max_height: u32,
+  pub(super) max_pixels: u64,
+}
+
+impl Default for ImagePolicy {
+  fn default() -> Self {
+    Self {
+      formats: ImageFormat::ALL.to_vec(),
+      max_bytes: 16 * 1024 * 1024,
+      max_width: 10_000,
+      max_height: 10_000,
+      max_pixels: 40_000_000,
+    }
+  }
+}
+
+impl ImagePolicy {
+  /// Starts from the defaults.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Accepts only the listed formats.
+  #[must_use]
+  pub fn formats(mut self, formats: impl IntoIterator<Item = ImageFormat>) -> Self {
+    self.formats = formats.into_iter().collect();
+    self
+  }
+
+  /// Largest encoded size accepted, in bytes.
+  #[must_use]
+  pub fn max_bytes(mut self, bytes: usize) -> Self {
+    self.max_bytes = bytes;
+    self
+  }
+
+  /// Largest width and height accepted, in pixels.
+  #[must_use]
+  pub fn max_dimensions(mut self, width: u32, height: u32) -> Self {
+    self.max_width = width;
+    self.max_height = height;
+    self
+  }
+
+  /// Largest `width * height` accepted.
+  #[must_use]
+  pub fn max_pixels(mut self, pixels: u64) -> Self {
+    self.max_pixels = pixels;
+    self
+  }
+
+  /// Checks format, size, and dimensions without decoding pixel data.
+  pub fn validate(&self, bytes: &[u8]) -> Result<ImageInfo, ImageError> {
+    if bytes.len() > self.max_bytes {
+      return Err(ImageError::TooLarge {
+        bytes: bytes.len(),
+        limit: self.max_bytes,
+      });
+    }
+    let format = ImageFormat::sniff(bytes).ok_or(ImageError::UnsupportedFormat)?;
+    if !self.formats.contains(&format) {
+      return Err(ImageError::DisallowedFormat(format));
+    }
+    let (width, height) =
+      image::ImageReader::with_format(Cursor::new(bytes), format.codec()).into_dimensions()?;
+    if width == 0 || height == 0 {
+      return Err(ImageError::Malformed("image has no pixels".to_string()));
+    }
+    if width > self.max_width
+      || height > self.max_height
+      || u64::from(width) * u64::from(height) > self.max_pixels
+    {
+      return Err(ImageError::DimensionsTooLarge { width, height });
+    }
+    Ok(ImageInfo {
+      format,
+      width,
+      height,
+    })
+  }
+
+  /// Decoder limits matching the policy, guarding decodes of images that
+  /// were not validated first.
+  pub(super) fn limits(&self) -> image::Limits {
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(self.max_width);
+    limits.max_image_height = Some(self.max_height);
+    limits
+  }
+}
@@ -0,0 +1,134 @@
+//! Lossless removal of EXIF / XMP metadata.
+
+use bytes::Bytes;
+
+use super::ImageError;
+use super::ImageFormat;
+
+const JPEG_EXIF: &[u8] = b"Exif\0\0";
+const JPEG_XMP: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// Removes EXIF and XMP metadata (camera model, GPS position, timestamps)
+/// without touching pixel data.
+///
+/// JPEG `APP1` segments, PNG `eXIf` and text chunks, and WebP `EXIF` / `XMP `
+/// chunks are dropped; GIF carries no EXIF and is returned as is. The EXIF
+/// orientation goes with the rest, so run a [`Transform`](super::Transform)
+/// instead when a rotated photo must keep displaying upright.
+pub fn strip_metadata(bytes: &[u8]) -> Result<Bytes, ImageError> {
+  match ImageFormat::sniff(bytes).ok_or(ImageError::UnsupportedFormat)? {
+    ImageFormat::Jpeg => strip_jpeg(bytes),
+    ImageFormat::Png => strip_png(bytes),
+    ImageFormat::Webp => strip_webp(bytes),
+    ImageFormat::Gif => Ok(Bytes::copy_from_slice(bytes)),
+  }
+}
+
+fn truncated() -> ImageError {
+  ImageError::Malformed("truncated metadata segment".to_string())
+}
+
+fn strip_jpeg(bytes: &[u8]) -> Result<Bytes, ImageError> {
+  let mut out = Vec::with_capacity(bytes.len());
+  out.extend_from_slice(&bytes[..2]);
+  let mut pos = 2;
+  loop {
+    if bytes.get(pos) != Some(&0xFF) {
+      return Err(ImageError::Malformed("expected a JPEG marker".to_string()));
+    }
+    // Markers may be preceded by any number of 0xFF fill bytes.
+    let mut marker_pos = pos + 1;
+    while bytes.get(marker_pos) == Some(&0xFF) {
+      marker_pos += 1;
+    }
+    let marker = *bytes.get(marker_pos).ok_or_else(truncated)?;
+    if marker == 0xD9 || (0xD0..=0xD7).contains(&marker) || marker == 0x01 {
+      // Markers without a length field.
+      out.extend_from_slice(&[0xFF, marker]);
+      pos = marker_pos + 1;
+      if marker == 0xD9 {
+        break;
+      }
+      continue;
+    }
+    let len_bytes = bytes
+      .get(marker_pos + 1..marker_pos + 3)
+      .ok_or_else(truncated)?;
+    let len = usize::from(u16::from_be_bytes([len_bytes[0], len_bytes[1]]));
+    if len < 2 {
+      // The length counts its own two bytes.
+      return Err(ImageError::Malformed(format!(
+        "JPEG segment length {len} is below 2"
+      )));
+    }
+    let end = marker_pos + 1 + len;
+    let segment = bytes.get(marker_pos + 1..end).ok_or_else(truncated)?;
+    if marker == 0xDA {
+      // Start of scan: entropy-coded data up to the end is copied verbatim.
+      out.extend_from_slice(&[0xFF, marker]);
+      out.extend_from_slice(&bytes[marker_pos + 1..]);
+      break;
+    }
+    let payload = &segment[2..];
+    let metadata =
+      marker == 0xE1 && (payload.starts_with(JPEG_EXIF) || payload.starts_with(JPEG_XMP));
+    if !metadata {
+      out.extend_from_slice(&[0xFF, marker]);
+      out.extend_from_slice(segment);
+    }
+    pos = end;
+  }
+  Ok(Bytes::from(out))
+}
+
+fn strip_png(bytes: &[u8]) -> Result<Bytes, ImageError> {
+  let mut out = Vec::with_capacity(bytes.len());
+  out.extend_from_slice(&bytes[..8]);
+  let mut pos = 8;
+  while pos < bytes.len() {
+    let header = bytes.get(pos..pos + 8).ok_or_else(truncated)?;
+    let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let kind = &header[4..8];
+    // Length, type, data, CRC.
+    let end = pos + 12 + len;
+    let chunk = bytes.get(pos..end).ok_or_else(truncated)?;
+    if !matches!(kind, b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt") {
+      out.extend_from_slice(chunk);
+    }
+    pos = end;
+    if kind == b"IEND" {
+      break;
+    }
+  }
+  Ok(Bytes::from(out))
+}
+
+/// `VP8X` feature flags announcing EXIF and XMP chunks.
+const VP8X_EXIF: u8 = 0x08;
+const VP8X_XMP: u8 = 0x04;
+
+fn strip_webp(bytes: &[u8]) -> Result<Bytes, ImageError> {
+  let mut out = Vec::with_capacity(bytes.len());
+  out.extend_from_slice(&bytes[..12]);
+  let mut pos = 12;
+  while pos < bytes.len() {
+    let header = bytes.get(pos..pos + 8).ok_or_else(truncated)?;
+    let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    // Chunk payloads are padded to an even length.
+    let end = (pos + 8 + len + (len & 1)).min(bytes.len());
+    let chunk = bytes.get(pos..end).ok_or_else(truncated)?;
+    match &header[..4] {
+      b"EXIF" | b"XMP " => {}
+      b"VP8X" if chunk.len() > 8 => {
+        let start = out.len();
+        out.extend_from_slice(chunk);
+        out[start + 8] &= !(VP8X_EXIF | VP8X_XMP);
+      }
+      _ => out.extend_from_slice(chunk),
+    }
+    pos = end;
+  }
+  let riff_len = u32::try_from(out.len() - 8).map_err(|_| truncated())?;
+  out[4..8].copy_from_slice(&riff_len.to_le_bytes());
+  Ok(Bytes::from(out))
+}
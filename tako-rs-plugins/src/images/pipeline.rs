@@ -0,0 +1,106 @@
+//! Upload processing: validate, strip, and render variants in one pass.
+
+use bytes::Bytes;
+
+use super::ImageError;
+use super::ImageInfo;
+use super::ImagePolicy;
+use super::Transform;
+use super::blocking;
+use super::metadata::strip_metadata;
+use super::transform::decode;
+use super::transform::encode;
+
+/// Validates an uploaded image, strips its metadata, and renders the
+/// configured variants.
+///
+/// The image is decoded at most once, and only when variants are
+/// configured. Variants are re-encoded in the upload's format and never carry
+/// metadata.
+#[derive(Debug, Clone)]
+pub struct ImagePipeline {
+  policy: ImagePolicy,
+  strip: bool,
+  variants: Vec<(String, Transform)>,
+}
+
+impl Default for ImagePipeline {
+  fn default() -> Self {
+    Self {
+      policy: ImagePolicy::default(),
+      strip: true,
+      variants: Vec::new(),
+    }
+  }
+}
+
+/// Output of [`ImagePipeline::process`].
+#[derive(Debug, Clone)]
+pub struct ProcessedImage {
+  /// Format and dimensions of the upload.
+  pub info: ImageInfo,
+  /// The upload, with metadata removed unless the pipeline keeps it.
+  pub original: Bytes,
+  /// Rendered variants, in registration order.
+  pub variants: Vec<(String, Bytes)>,
+}
+
+impl ImagePipeline {
+  /// Default policy, metadata stripping on, no variants.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Replaces the validation policy.
+  #[must_use]
+  pub fn policy(mut self, policy: ImagePolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// Keeps EXIF / XMP metadata on the original.
+  #[must_use]
+  pub fn keep_metadata(mut self) -> Self {
+    self.strip = false;
+    self
+  }
+
+  /// Adds a named variant, e.g. `("thumb", Transform::new(200, 200))`.
+  #[must_use]
+  pub fn variant(mut self, name: impl Into<String>, transform: Transform) -> Self {
+    self.variants.push((name.into(), transform));
+    self
+  }
+
+  /// Processes `bytes` on the current thread.
+  pub fn process(&self, bytes: &[u8]) -> Result<ProcessedImage, ImageError> {
+    let info = self.policy.validate(bytes)?;
+    let original = if self.strip {
+      strip_metadata(bytes)?
+    } else {
+      Bytes::copy_from_slice(bytes)
+    };
+    let mut variants = Vec::with_capacity(self.variants.len());
+    if !self.variants.is_empty() {
+      // Decode the unstripped bytes so the EXIF orientation still applies.
+      let (image, _) = decode(bytes, &self.policy)?;
+      for (name, transform) in &self.variants {
+        variants.push((
+          name.clone(),
+          encode(&transform.render(&image)?, info.format)?,
+        ));
+      }
+    }
+    Ok(ProcessedImage {
+      info,
+      original,
+      variants,
+    })
+  }
+
+  /// Processes `bytes` on the blocking pool.
+  pub async fn process_async(&self, bytes: Bytes) -> Result<ProcessedImage, ImageError> {
+    let pipeline = self.clone();
+    blocking(move || pipeline.process(&bytes)).await
+  }
+}
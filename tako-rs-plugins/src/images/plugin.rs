@@ -0,0 +1,231 @@
+//! The on-demand transform plugin and its variant cache.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::Result;
+use bytes::Bytes;
+use http::HeaderMap;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header;
+use http_body_util::BodyExt;
+use http_body_util::Limited;
+use parking_lot::Mutex;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::plugins::TakoPlugin;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::router::Router;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::ImageError;
+use super::ImageFormat;
+use super::Transform;
+use super::blocking;
+use super::config::Config;
+use super::config::ImagesBuilder;
+
+/// Resizes images on the way out when the request asks for it with
+/// `?w=`, `?h=`, and `?fit=`. Attach at router level.
+///
+/// The route (typically a static file handler) runs unchanged; a `200`
+/// response whose body sniffs as a supported image is transformed and
+/// re-encoded in its own format. Conditional and range headers are dropped
+/// from the forwarded request so the full source comes back. Results are
+/// cached in memory by path and transform for [`Config::cache_ttl`], so a
+/// hit skips both the route and the resize. Requests without `w` / `h`, and
+/// non-image responses, pass through untouched.
+#[derive(Clone)]
+pub struct ImagesPlugin {
+  cfg: Config,
+  cache: Arc<VariantCache>,
+}
+
+impl ImagesPlugin {
+  pub fn builder() -> ImagesBuilder {
+    ImagesBuilder::new()
+  }
+
+  pub fn new(cfg: Config) -> Self {
+    let cache = Arc::new(VariantCache::new(cfg.cache_capacity));
+    Self { cfg, cache }
+  }
+}
+
+impl TakoPlugin for ImagesPlugin {
+  fn name(&self) -> &'static str {
+    "ImagesPlugin"
+  }
+
+  fn setup(&self, router: &Router) -> Result<()> {
+    let cfg = Arc::new(self.cfg.clone());
+    let cache = Arc::clone(&self.cache);
+    router.middleware(move |req, next| {
+      let cfg = Arc::clone(&cfg);
+      let cache = Arc::clone(&cache);
+      async move { handle(req, next, &cfg, &cache).await }
+    });
+    Ok(())
+  }
+}
+
+async fn handle(mut req: Request, next: Next, cfg: &Config, cache: &VariantCache) -> Response {
+  let head = req.method() == Method::HEAD;
+  if !(head || req.method() == Method::GET) || !req.uri().path().starts_with(&cfg.path_prefix) {
+    return next.run(req).await;
+  }
+  let transform = match Transform::from_query(req.uri().query().unwrap_or(""), cfg.max_side) {
+    Ok(Some(transform)) => transform,
+    Ok(None) => return next.run(req).await,
+    Err(e) => return e.into_response(),
+  };
+
+  let key = format!("{}?{}", req.uri().path(), transform.key());
+  if let Some((headers, body)) = cache.get(&key, cfg) {
+    return variant_response(headers, body, head);
+  }
+
+  // Fetch the whole, unconditional source even for `HEAD`.
+  *req.method_mut() = Method::GET;
+  let headers = req.headers_mut();
+  for name in [
+    header::RANGE,
+    header::IF_RANGE,
+    header::IF_NONE_MATCH,
+    header::IF_MODIFIED_SINCE,
+  ] {
+    headers.remove(name);
+  }
+  let resp = next.run(req).await;
+  if resp.status() != StatusCode::OK {
+    return resp;
+  }
+
+  let (mut parts, body) = resp.into_parts();
+  let limit = cfg.policy.max_bytes;
+  let Ok(collected) = Limited::new(body, limit).collect().await else {
+    return ImageError::TooLarge {
+      bytes: limit + 1,
+      limit,
+    }
+    .into_response();
+  };
+  let source = collected.to_bytes();
+  let Some(format) = ImageFormat::sniff(&source) else {
+    return http::Response::from_parts(parts, TakoBody::from(source));
+  };
+
+  let policy = cfg.policy.clone();
+  let rendered = blocking(move || transform.apply_with(&source, &policy)).await;
+  let body = match rendered {
+    Ok(body) => body,
+    Err(e) => return e.into_response(),
+  };
+
+  let headers = &mut parts.headers;
+  for name in [
+    header::CONTENT_LENGTH,
+    header::ETAG,
+    header::ACCEPT_RANGES,
+    header::CONTENT_RANGE,
+  ] {
+    headers.remove(name);
+  }
+  headers.insert(
+    header::CONTENT_TYPE,
+    HeaderValue::from_static(format.mime()),
+  );
+  cache.insert(key, parts.headers.clone(), body.clone());
+  variant_response(parts.headers, body, head)
+}
+
+fn variant_response(headers: HeaderMap, body: Bytes, head: bool) -> Response {
+  let len = body.len();
+  let mut resp = http::Response::new(if head {
+    TakoBody::empty()
+  } else {
+    TakoBody::from(body)
+  });
+  *resp.headers_mut() = headers;
+  resp
+    .headers_mut()
+    .insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+  resp
+}
+
+/// Rendered variants kept in insertion order and evicted oldest-first once
+/// their combined size exceeds the capacity.
+struct VariantCache {
+  capacity: usize,
+  inner: Mutex<CacheInner>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+  entries: HashMap<String, CacheEntry>,
+  order: VecDeque<String>,
+  size: usize,
+}
+
+struct CacheEntry {
+  headers: HeaderMap,
+  body: Bytes,
+  stored: Instant,
+}
+
+impl VariantCache {
+  fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      inner: Mutex::new(CacheInner::default()),
+    }
+  }
+
+  fn get(&self, key: &str, cfg: &Config) -> Option<(HeaderMap, Bytes)> {
+    let mut inner = self.inner.lock();
+    let entry = inner.entries.get(key)?;
+    if entry.stored.elapsed() < cfg.cache_ttl {
+      return Some((entry.headers.clone(), entry.body.clone()));
+    }
+    inner.remove(key);
+    None
+  }
+
+  fn insert(&self, key: String, headers: HeaderMap, body: Bytes) {
+    if body.len() > self.capacity {
+      return;
+    }
+    let mut inner = self.inner.lock();
+    inner.remove(&key);
+    inner.size += body.len();
+    inner.order.push_back(key.clone());
+    inner.entries.insert(
+      key,
+      CacheEntry {
+        headers,
+        body,
+        stored: Instant::now(),
+      },
+    );
+    while inner.size > self.capacity {
+      let Some(oldest) = inner.order.front().cloned() else {
+        break;
+      };
+      inner.remove(&oldest);
+    }
+  }
+}
+
+impl CacheInner {
+  fn remove(&mut self, key: &str) {
+    if let Some(entry) = self.entries.remove(key) {
+      self.size -= entry.body.len();
+      self.order.retain(|k| k != key);
+    }
+  }
+}
@@ -0,0 +1,223 @@
+//! Resizing and re-encoding.
+
+use std::io::Cursor;
+
+use bytes::Bytes;
+use image::DynamicImage;
+use image::ImageDecoder;
+use image::codecs::gif::GifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+
+use super::ImageError;
+use super::ImageFormat;
+use super::ImageInfo;
+use super::ImagePolicy;
+
+/// JPEG quality used when re-encoding.
+const JPEG_QUALITY: u8 = 85;
+
+/// How an image is fitted into the target box.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Fit {
+  /// Scale to fit inside the box, keeping the aspect ratio (may upscale).
+  #[default]
+  Contain,
+  /// Scale to cover the box and crop the overflow, centred.
+  Cover,
+  /// Stretch to exactly the box, ignoring the aspect ratio.
+  Fill,
+  /// Like `Contain`, but never upscales.
+  Inside,
+}
+
+impl Fit {
+  /// Parses a `fit` query value: `contain`, `cover`, `fill`, or `inside`.
+  pub fn parse(value: &str) -> Option<Self> {
+    match value {
+      "contain" => Some(Self::Contain),
+      "cover" => Some(Self::Cover),
+      "fill" => Some(Self::Fill),
+      "inside" => Some(Self::Inside),
+      _ => None,
+    }
+  }
+
+  /// The query value for this mode.
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Contain => "contain",
+      Self::Cover => "cover",
+      Self::Fill => "fill",
+      Self::Inside => "inside",
+    }
+  }
+}
+
+/// A resize to a target box, re-encoded in the source format.
+///
+/// With only one side given the other follows the aspect ratio, and
+/// [`Fit::Cover`] / [`Fit::Fill`] behave like [`Fit::Contain`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Transform {
+  width: Option<u32>,
+  height: Option<u32>,
+  fit: Fit,
+}
+
+impl Transform {
+  /// Resizes into a `width` x `height` box.
+  pub fn new(width: u32, height: u32) -> Self {
+    Self {
+      width: Some(width),
+      height: Some(height),
+      fit: Fit::default(),
+    }
+  }
+
+  /// Resizes to `width`, keeping the aspect ratio.
+  pub fn width(width: u32) -> Self {
+    Self {
+      width: Some(width),
+      height: None,
+      fit: Fit::default(),
+    }
+  }
+
+  /// Resizes to `height`, keeping the aspect ratio.
+  pub fn height(height: u32) -> Self {
+    Self {
+      width: None,
+      height: Some(height),
+      fit: Fit::default(),
+    }
+  }
+
+  /// Sets the fit mode. Default: [`Fit::Contain`].
+  #[must_use]
+  pub fn fit(mut self, fit: Fit) -> Self {
+    self.fit = fit;
+    self
+  }
+
+  /// Reads `w`, `h`, and `fit` from a query string. Returns `Ok(None)` when
+  /// neither `w` nor `h` is present; sides must be `1..=max_side`.
+  pub fn from_query(query: &str, max_side: u32) -> Result<Option<Self>, ImageError> {
+    let side = |value: &str| match value.parse::<u32>() {
+      Ok(n) if (1..=max_side).contains(&n) => Ok(n),
+      _ => Err(ImageError::InvalidTransform(format!(
+        "sides must be between 1 and {max_side}"
+      ))),
+    };
+    let mut transform = Self {
+      width: None,
+      height: None,
+      fit: Fit::default(),
+    };
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+      match &*key {
+        "w" => transform.width = Some(side(&value)?),
+        "h" => transform.height = Some(side(&value)?),
+        "fit" => {
+          transform.fit = Fit::parse(&value)
+            .ok_or_else(|| ImageError::InvalidTransform(format!("unknown fit `{value}`")))?;
+        }
+        _ => {}
+      }
+    }
+    Ok((transform.width.is_some() || transform.height.is_some()).then_some(transform))
+  }
+
+  /// Validates `bytes` against the default [`ImagePolicy`] and applies the
+  /// transform.
+  pub fn apply(&self, bytes: &[u8]) -> Result<Bytes, ImageError> {
+    self.apply_with(bytes, &ImagePolicy::default())
+  }
+
+  pub(super) fn apply_with(&self, bytes: &[u8], policy: &ImagePolicy) -> Result<Bytes, ImageError> {
+    let (image, info) = decode(bytes, policy)?;
+    encode(&self.render(&image)?, info.format)
+  }
+
+  /// Canonical form used in cache keys.
+  pub(super) fn key(&self) -> String {
+    let side = |n: Option<u32>| n.map_or_else(String::new, |n| n.to_string());
+    format!(
+      "w={}&h={}&fit={}",
+      side(self.width),
+      side(self.height),
+      self.fit.as_str()
+    )
+  }
+
+  pub(super) fn render(&self, image: &DynamicImage) -> Result<DynamicImage, ImageError> {
+    let (src_w, src_h) = (image.width(), image.height());
+    let scaled = |side: u32, num: u32, den: u32| {
+      let side = u64::from(side) * u64::from(num) / u64::from(den).max(1);
+      u32::try_from(side).unwrap_or(u32::MAX).max(1)
+    };
+    let (width, height, fit) = match (self.width, self.height) {
+      (Some(w), Some(h)) => (w, h, self.fit),
+      (Some(w), None) => (w, scaled(w, src_h, src_w), self.fit.aspect_preserving()),
+      (None, Some(h)) => (scaled(h, src_w, src_h), h, self.fit.aspect_preserving()),
+      (None, None) => return Ok(image.clone()),
+    };
+    if width == 0 || height == 0 {
+      return Err(ImageError::InvalidTransform(
+        "sides must be positive".to_string(),
+      ));
+    }
+    let filter = FilterType::CatmullRom;
+    Ok(match fit {
+      Fit::Contain => image.resize(width, height, filter),
+      Fit::Inside if src_w <= width && src_h <= height => image.clone(),
+      Fit::Inside => image.resize(width, height, filter),
+      Fit::Cover => image.resize_to_fill(width, height, filter),
+      Fit::Fill => image.resize_exact(width, height, filter),
+    })
+  }
+}
+
+impl Fit {
+  fn aspect_preserving(self) -> Self {
+    match self {
+      Self::Cover | Self::Fill => Self::Contain,
+      other => other,
+    }
+  }
+}
+
+/// Validates and decodes `bytes`, applying the EXIF orientation.
+pub(super) fn decode(
+  bytes: &[u8],
+  policy: &ImagePolicy,
+) -> Result<(DynamicImage, ImageInfo), ImageError> {
+  let info = policy.validate(bytes)?;
+  let mut reader = image::ImageReader::with_format(Cursor::new(bytes), info.format.codec());
+  reader.limits(policy.limits());
+  let mut decoder = reader.into_decoder()?;
+  let orientation = decoder.orientation()?;
+  let mut image = DynamicImage::from_decoder(decoder)?;
+  image.apply_orientation(orientation);
+  Ok((image, info))
+}
+
+/// Encodes `image` as `format`. No metadata is written.
+pub(super) fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Bytes, ImageError> {
+  let mut out = Vec::new();
+  match format {
+    ImageFormat::Png => image.write_with_encoder(PngEncoder::new(&mut out))?,
+    ImageFormat::Jpeg => image
+      .to_rgb8()
+      .write_with_encoder(JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY))?,
+    ImageFormat::Gif => image
+      .to_rgba8()
+      .write_with_encoder(GifEncoder::new(&mut out))?,
+    ImageFormat::Webp => image
+      .to_rgba8()
+      .write_with_encoder(WebPEncoder::new_lossless(&mut out))?,
+  }
+  Ok(Bytes::from(out))
+}
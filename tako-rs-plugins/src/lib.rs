@@ -22,3 +22,29 @@ pub mod stores;
 /// Plugin/middleware-coupled extractors (e.g. verified JWT claims that are
 /// produced by `JwtAuth` middleware and surfaced via `JwtClaimsVerified<C>`).
 pub mod extractors;
+
+/// Password hashing, form and magic-link login, and TOTP second factors.
+#[cfg(any(feature = "password", feature = "totp", feature = "magic-link"))]
+#[cfg_attr(
+  docsrs,
+  doc(cfg(any(feature = "password", feature = "totp", feature = "magic-link")))
+)]
+pub mod auth;
+
+/// Outgoing email: SMTP transport, `Mailer` extractor, and queued delivery.
+///
+/// Built on lettre's tokio transport, so this is absent under `compio`.
+#[cfg(all(feature = "email", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "email")))]
+pub mod email;
+
+/// Declarative TOML / YAML gateway configuration applied to a `Router`.
+///
+/// Proxy routes use the tokio-based client, so this is absent under `compio`.
+#[cfg(feature = "images")]
+#[cfg_attr(docsrs, doc(cfg(feature = "images")))]
+pub mod images;
+
+#[cfg(all(feature = "gateway", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "gateway")))]
+pub mod gateway;
@@ -5,11 +5,15 @@
 
 pub mod access_log;
 pub mod api_key_auth;
+pub mod api_version;
 pub mod basic_auth;
 pub mod bearer_auth;
+pub mod body_inspector;
 pub mod body_limit;
+pub mod chaos;
 pub mod circuit_breaker;
 pub mod csrf;
+pub mod degrade;
 pub mod etag;
 pub mod healthcheck;
 #[cfg(feature = "hmac-signature")]
@@ -18,14 +22,24 @@ pub mod hmac_signature;
 #[cfg(feature = "ip-filter")]
 #[cfg_attr(docsrs, doc(cfg(feature = "ip-filter")))]
 pub mod ip_filter;
+pub mod journal;
 #[cfg(feature = "json-schema")]
 #[cfg_attr(docsrs, doc(cfg(feature = "json-schema")))]
 pub mod json_schema;
 pub mod jwt_auth;
+pub mod login_throttle;
 pub mod problem_json;
+pub mod profiling;
 pub mod request_id;
+pub mod request_schema;
+#[cfg(feature = "response-signature")]
+#[cfg_attr(docsrs, doc(cfg(feature = "response-signature")))]
+pub mod response_signature;
 pub mod security_headers;
 pub mod session;
+#[cfg(feature = "signed-url")]
+#[cfg_attr(docsrs, doc(cfg(feature = "signed-url")))]
+pub mod signed_url;
 pub mod tenant;
 pub mod timeout;
 pub mod traceparent;
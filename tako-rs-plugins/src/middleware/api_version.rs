@@ -0,0 +1,293 @@
+//! Date-pinned API versioning with request/response payload migrations.
+//!
+//! Handlers are written against the newest schema only. Each
+//! [`VersionChange`] describes what changed when its version was introduced:
+//! a request transform that upgrades a body from the previous shape, and a
+//! response transform that downgrades a body back to it. A client pins a
+//! version with the `Api-Version` header (Stripe-style); the middleware runs
+//! every newer change's request transform in ascending order before the
+//! handler, and every newer change's response transform in descending order
+//! after it.
+//!
+//! Requests without a header use the configured default (the latest version
+//! unless [`ApiVersioning::default_version`] says otherwise). Unknown
+//! versions are rejected with `400 Bad Request`. The resolved version is
+//! inserted as an [`ApiVersion`] extension and echoed in the response header.
+//!
+//! Only JSON bodies are migrated; they are buffered up to
+//! [`ApiVersioning::max_bytes`]. Requests pinned at the latest version pass
+//! through without buffering.
+//!
+//! ```rust,ignore
+//! let versioning = ApiVersioning::new("2024-01-01")
+//!   .change(
+//!     VersionChange::new("2024-06-01")
+//!       // `name` was split into `first_name` / `last_name`.
+//!       .request(|body| { /* split body["name"] */ })
+//!       .response(|body| { /* join them back into body["name"] */ }),
+//!   );
+//! router.middleware(versioning.into_middleware());
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use http::HeaderName;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::CONTENT_LENGTH;
+use http::header::CONTENT_TYPE;
+use http_body_util::BodyExt;
+use serde_json::Value;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// API version the request was resolved to, inserted into request extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiVersion(pub String);
+
+/// In-place JSON payload migration.
+pub type JsonTransform = Arc<dyn Fn(&mut Value) + Send + Sync + 'static>;
+
+/// The payload changes introduced by one API version.
+#[derive(Clone)]
+pub struct VersionChange {
+  version: String,
+  request: Option<JsonTransform>,
+  response: Option<JsonTransform>,
+}
+
+impl VersionChange {
+  /// Declares a new version with no payload changes yet.
+  pub fn new(version: impl Into<String>) -> Self {
+    Self {
+      version: version.into(),
+      request: None,
+      response: None,
+    }
+  }
+
+  /// Upgrades a request body from the previous version's shape to this one.
+  pub fn request<F>(mut self, f: F) -> Self
+  where
+    F: Fn(&mut Value) + Send + Sync + 'static,
+  {
+    self.request = Some(Arc::new(f));
+    self
+  }
+
+  /// Downgrades a response body from this version's shape to the previous one.
+  pub fn response<F>(mut self, f: F) -> Self
+  where
+    F: Fn(&mut Value) + Send + Sync + 'static,
+  {
+    self.response = Some(Arc::new(f));
+    self
+  }
+}
+
+/// API versioning middleware.
+pub struct ApiVersioning {
+  header: HeaderName,
+  base: String,
+  changes: Vec<VersionChange>,
+  default: Option<String>,
+  max_bytes: usize,
+}
+
+impl ApiVersioning {
+  /// Starts a version history at `base`, the oldest supported version.
+  pub fn new(base: impl Into<String>) -> Self {
+    Self {
+      header: HeaderName::from_static("api-version"),
+      base: base.into(),
+      changes: Vec::new(),
+      default: None,
+      max_bytes: 1024 * 1024,
+    }
+  }
+
+  /// Appends the next version. Changes must be registered oldest first.
+  ///
+  /// # Panics
+  ///
+  /// Panics when the version is already registered.
+  pub fn change(mut self, change: VersionChange) -> Self {
+    assert!(
+      change.version != self.base && self.changes.iter().all(|c| c.version != change.version),
+      "API version `{}` registered twice",
+      change.version
+    );
+    self.changes.push(change);
+    self
+  }
+
+  /// Header carrying the pinned version (default `Api-Version`).
+  pub fn header(mut self, name: HeaderName) -> Self {
+    self.header = name;
+    self
+  }
+
+  /// Version assumed when the header is absent (default: the latest).
+  pub fn default_version(mut self, version: impl Into<String>) -> Self {
+    self.default = Some(version.into());
+    self
+  }
+
+  /// Maximum JSON body size the middleware will buffer for migration.
+  /// Larger request bodies are rejected with `413`; larger response bodies
+  /// fail with `500`.
+  pub fn max_bytes(mut self, n: usize) -> Self {
+    self.max_bytes = n;
+    self
+  }
+}
+
+struct Versions {
+  header: HeaderName,
+  base: String,
+  changes: Vec<VersionChange>,
+  default: String,
+  max_bytes: usize,
+}
+
+impl Versions {
+  /// Index of the first change newer than `version`, or `None` if unknown.
+  fn pending_from(&self, version: &str) -> Option<usize> {
+    if version == self.base {
+      return Some(0);
+    }
+    self
+      .changes
+      .iter()
+      .position(|c| c.version == version)
+      .map(|i| i + 1)
+  }
+}
+
+fn is_json(content_type: Option<&HeaderValue>) -> bool {
+  content_type
+    .and_then(|v| v.to_str().ok())
+    .map(str::to_ascii_lowercase)
+    .is_some_and(|s| s.contains("json"))
+}
+
+fn reject(status: StatusCode, msg: String) -> Response {
+  http::Response::builder()
+    .status(status)
+    .body(TakoBody::from(msg))
+    .expect("valid response")
+}
+
+impl IntoMiddleware for ApiVersioning {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    let default = self.default.unwrap_or_else(|| {
+      self
+        .changes
+        .last()
+        .map_or_else(|| self.base.clone(), |c| c.version.clone())
+    });
+    let versions = Arc::new(Versions {
+      header: self.header,
+      base: self.base,
+      changes: self.changes,
+      default,
+      max_bytes: self.max_bytes,
+    });
+
+    move |mut req: Request, next: Next| {
+      let versions = versions.clone();
+      Box::pin(async move {
+        let version = match req.headers().get(&versions.header) {
+          Some(v) => match v.to_str() {
+            Ok(s) => s.trim().to_string(),
+            Err(_) => {
+              return reject(StatusCode::BAD_REQUEST, "invalid API version".into());
+            }
+          },
+          None => versions.default.clone(),
+        };
+        let Some(from) = versions.pending_from(&version) else {
+          return reject(
+            StatusCode::BAD_REQUEST,
+            format!("unknown API version `{version}`"),
+          );
+        };
+        let pending = &versions.changes[from..];
+        let echo = HeaderValue::from_str(&version).ok();
+        req.extensions_mut().insert(ApiVersion(version));
+
+        if pending.iter().any(|c| c.request.is_some()) && is_json(req.headers().get(CONTENT_TYPE)) {
+          let (mut parts, body) = req.into_parts();
+          let collected = match http_body_util::Limited::new(body, versions.max_bytes)
+            .collect()
+            .await
+          {
+            Ok(c) => c.to_bytes(),
+            Err(_) => return reject(StatusCode::PAYLOAD_TOO_LARGE, String::new()),
+          };
+          let mut value = match serde_json::from_slice::<Value>(&collected) {
+            Ok(v) => v,
+            Err(e) => return reject(StatusCode::BAD_REQUEST, e.to_string()),
+          };
+          for change in pending {
+            if let Some(f) = &change.request {
+              f(&mut value);
+            }
+          }
+          parts.headers.remove(CONTENT_LENGTH);
+          req = http::Request::from_parts(
+            parts,
+            TakoBody::from(serde_json::to_vec(&value).unwrap_or_default()),
+          );
+        }
+
+        let mut resp = next.run(req).await;
+
+        if pending.iter().any(|c| c.response.is_some()) && is_json(resp.headers().get(CONTENT_TYPE))
+        {
+          let (mut parts, body) = resp.into_parts();
+          let collected = match http_body_util::Limited::new(body, versions.max_bytes)
+            .collect()
+            .await
+          {
+            Ok(c) => c.to_bytes(),
+            // The upstream body has been partially consumed; it cannot be
+            // forwarded in the pinned shape.
+            Err(_) => return reject(StatusCode::INTERNAL_SERVER_ERROR, String::new()),
+          };
+          resp = match serde_json::from_slice::<Value>(&collected) {
+            Ok(mut value) => {
+              for change in pending.iter().rev() {
+                if let Some(f) = &change.response {
+                  f(&mut value);
+                }
+              }
+              parts.headers.remove(CONTENT_LENGTH);
+              http::Response::from_parts(
+                parts,
+                TakoBody::from(serde_json::to_vec(&value).unwrap_or_default()),
+              )
+            }
+            Err(_) => http::Response::from_parts(parts, TakoBody::from(collected)),
+          };
+        }
+
+        if let Some(echo) = echo {
+          resp.headers_mut().insert(versions.header.clone(), echo);
+        }
+        resp
+      })
+    }
+  }
+}
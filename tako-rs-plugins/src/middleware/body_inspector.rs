@@ -0,0 +1,168 @@
+//! Streaming body inspection middleware for byte budgets and size metrics.
+//!
+//! Wraps both the request and the response body in an
+//! [`InspectedBody`](tako_rs_core::body::InspectedBody) so every data frame is
+//! counted as it flows through. Nothing is buffered, which means budgets also
+//! hold for handlers that stream their input or output. Budgets that can be
+//! decided up front (a `Content-Length` or an exact size hint) are rejected
+//! before any bytes move; streamed bodies fail at the frame that crosses the
+//! limit.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::middleware::body_inspector::BodyInspector;
+//! use tako::middleware::IntoMiddleware;
+//!
+//! let inspector = BodyInspector::new()
+//!     .request_budget(8 * 1024 * 1024)
+//!     .response_budget(64 * 1024 * 1024)
+//!     .on_request_size(|bytes| println!("request body: {bytes} bytes"))
+//!     .on_response_size(|bytes| println!("response body: {bytes} bytes"));
+//! let mw = inspector.into_middleware();
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use http::StatusCode;
+use http::header::CONTENT_LENGTH;
+use http_body::Body;
+use tako_rs_core::body::BodySizeCallback;
+use tako_rs_core::body::InspectedBody;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// Byte budget and size reporting for request and response bodies.
+///
+/// Every setting is optional; without any of them the middleware only adds
+/// the counting wrapper. Handlers that read an over-budget request body see a
+/// [`BodyBudgetExceeded`](tako_rs_core::body::BodyBudgetExceeded) error from
+/// the body stream.
+///
+/// # Examples
+///
+/// ```rust
+/// use tako::middleware::body_inspector::BodyInspector;
+///
+/// // Enforce a 1 MiB request budget only.
+/// let inspector = BodyInspector::new().request_budget(1024 * 1024);
+/// ```
+#[derive(Default)]
+pub struct BodyInspector {
+  request_budget: Option<u64>,
+  response_budget: Option<u64>,
+  on_request: Option<BodySizeCallback>,
+  on_response: Option<BodySizeCallback>,
+}
+
+impl BodyInspector {
+  /// Creates an inspector with no budgets and no callbacks.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Maximum number of request body bytes handlers may read.
+  pub fn request_budget(mut self, bytes: u64) -> Self {
+    self.request_budget = Some(bytes);
+    self
+  }
+
+  /// Maximum number of response body bytes sent to the client.
+  pub fn response_budget(mut self, bytes: u64) -> Self {
+    self.response_budget = Some(bytes);
+    self
+  }
+
+  /// Called once with the number of request bytes the handler consumed.
+  ///
+  /// Does not fire when the handler never reads the body to the end.
+  pub fn on_request_size<F>(mut self, f: F) -> Self
+  where
+    F: Fn(u64) + Send + Sync + 'static,
+  {
+    self.on_request = Some(Arc::new(f));
+    self
+  }
+
+  /// Called once with the number of response bytes written to the client.
+  pub fn on_response_size<F>(mut self, f: F) -> Self
+  where
+    F: Fn(u64) + Send + Sync + 'static,
+  {
+    self.on_response = Some(Arc::new(f));
+    self
+  }
+}
+
+/// Applies the optional budget and callback to `body`.
+fn inspect(body: TakoBody, budget: Option<u64>, cb: Option<&BodySizeCallback>) -> TakoBody {
+  let mut inspected = InspectedBody::new(body);
+  if let Some(limit) = budget {
+    inspected = inspected.budget(limit);
+  }
+  if let Some(cb) = cb {
+    inspected = inspected.on_complete(Arc::clone(cb));
+  }
+  TakoBody::new(inspected)
+}
+
+impl IntoMiddleware for BodyInspector {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    let request_budget = self.request_budget;
+    let response_budget = self.response_budget;
+    let on_request = self.on_request;
+    let on_response = self.on_response;
+
+    move |req: Request, next: Next| {
+      let on_request = on_request.clone();
+      let on_response = on_response.clone();
+
+      Box::pin(async move {
+        // Fast-path rejection when the client already announced an
+        // over-budget body.
+        if let Some(limit) = request_budget
+          && let Some(len) = req
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse::<u64>().ok())
+          && len > limit
+        {
+          return (StatusCode::PAYLOAD_TOO_LARGE, "Body exceeds allowed size").into_response();
+        }
+
+        let (parts, body) = req.into_parts();
+        let body = inspect(body, request_budget, on_request.as_ref());
+        let resp = next.run(http::Request::from_parts(parts, body)).await;
+
+        // A response whose exact size is already known to be over budget is
+        // replaced outright rather than cut off part-way through.
+        if let Some(limit) = response_budget
+          && resp.body().size_hint().exact().is_some_and(|n| n > limit)
+        {
+          tracing::warn!(
+            limit,
+            "body inspector: response exceeds byte budget; returning 500"
+          );
+          return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+
+        let (parts, body) = resp.into_parts();
+        let body = inspect(body, response_budget, on_response.as_ref());
+        http::Response::from_parts(parts, body)
+      })
+    }
+  }
+}
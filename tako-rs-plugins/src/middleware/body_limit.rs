@@ -6,6 +6,13 @@
 //! using the Content-Length header when available, avoiding unnecessary body processing
 //! for oversized requests.
 //!
+//! Oversized requests are answered with `413 Payload Too Large` and a JSON
+//! body (`{"error":"payload_too_large",...}`). The effective limit is stored in
+//! request extensions, so the `Json`, `Form` and multipart extractors reject
+//! bodies that cross it mid-stream with the same response. A route registered
+//! with [`Route::body_limit`](tako_rs_core::route::Route::body_limit) overrides
+//! the middleware's limit for that route.
+//!
 //! # Examples
 //!
 //! ```rust
@@ -40,10 +47,12 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
-use http::StatusCode;
-use http::header::CONTENT_LENGTH;
 use http_body_util::Limited;
 use tako_rs_core::body::TakoBody;
+use tako_rs_core::extractors::body_limit::RequestBodyLimit;
+use tako_rs_core::extractors::body_limit::RouteBodyLimit;
+use tako_rs_core::extractors::body_limit::exceeds_content_length;
+use tako_rs_core::extractors::body_limit::payload_too_large;
 use tako_rs_core::middleware::IntoMiddleware;
 use tako_rs_core::middleware::Next;
 use tako_rs_core::responder::Responder;
@@ -90,10 +99,7 @@ where
   dynamic_limit: Option<F>,
 }
 
-impl<F> BodyLimit<F>
-where
-  F: Fn(&Request) -> usize + Send + Sync + 'static,
-{
+impl BodyLimit<fn(&Request) -> usize> {
   /// Creates a body limit middleware with a fixed size limit.
   pub fn new(limit: usize) -> Self {
     Self {
@@ -101,7 +107,12 @@ where
       dynamic_limit: None,
     }
   }
+}
 
+impl<F> BodyLimit<F>
+where
+  F: Fn(&Request) -> usize + Send + Sync + 'static,
+{
   /// Creates a body limit middleware with a dynamic limit function.
   pub fn with_dynamic_limit(f: F) -> Self {
     Self {
@@ -134,28 +145,29 @@ where
     let static_limit = self.limit;
     let dynamic_limit = self.dynamic_limit.map(Arc::new);
 
-    move |req: Request, next: Next| {
+    move |mut req: Request, next: Next| {
       let dynamic_limit = dynamic_limit.clone();
 
       Box::pin(async move {
-        // Determine effective limit: dynamic → static → default 10 MiB
-        let limit = dynamic_limit
-          .as_ref()
-          .map(|f| f(&req))
+        // Determine effective limit: route override → dynamic → static →
+        // default 10 MiB
+        let limit = req
+          .extensions()
+          .get::<RouteBodyLimit>()
+          .map(|l| l.0)
+          .or_else(|| dynamic_limit.as_ref().map(|f| f(&req)))
           .or(static_limit)
           .unwrap_or(10 * 1024 * 1024);
 
         // Fast-path rejection via Content-Length header
-        if let Some(len) = req
-          .headers()
-          .get(CONTENT_LENGTH)
-          .and_then(|v| v.to_str().ok())
-          .and_then(|s| s.parse::<usize>().ok())
-          && len > limit
-        {
-          return (StatusCode::PAYLOAD_TOO_LARGE, "Body exceeds allowed size").into_response();
+        if exceeds_content_length(&req, limit) {
+          return payload_too_large(limit);
         }
 
+        // Publish the limit so `Json`, `Form` and multipart can answer
+        // with the same 413 once the stream crosses it.
+        req.extensions_mut().insert(RequestBodyLimit(limit));
+
         // Stream-aware enforcement: wrap the body in `Limited` so reads past `limit`
         // produce a `LengthLimitError` instead of buffering the entire body up-front.
         // Handlers that consume the body see a normal body error and can return 413
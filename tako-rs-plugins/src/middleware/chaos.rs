@@ -0,0 +1,254 @@
+//! Fault injection for resilience testing.
+//!
+//! [`Chaos`] makes a fraction of requests slow, fail, or lose their
+//! connection, so client timeouts, retries and circuit breakers can be
+//! exercised against a real deployment. Each fault has its own probability
+//! and is rolled independently per request:
+//!
+//! - **latency** — the request waits a random delay before reaching the
+//!   handler.
+//! - **error** — the handler is skipped and a configured status is returned.
+//! - **drop** — the handler is skipped and the response body fails before its
+//!   first byte, which aborts the connection (HTTP/1) or resets the stream
+//!   (HTTP/2).
+//!
+//! Faults only fire on requests carrying the trigger header (`X-Chaos` by
+//! default), so a staging environment keeps serving normal traffic while a
+//! test suite opts in. The header value picks the faults: `all` (or `1`,
+//! `on`, `true`) enables every configured one, otherwise a comma-separated
+//! list such as `latency,error` limits it. [`Chaos::always`] drops the
+//! header requirement for environment-wide chaos. Injected latency and
+//! errors are reported in the `X-Chaos-Fault` response header.
+//!
+//! Do not mount this in production: anyone who can send the header can
+//! degrade the service.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use http::StatusCode;
+//! use tako::middleware::IntoMiddleware;
+//! use tako::middleware::chaos::Chaos;
+//!
+//! // Requests sent with `X-Chaos: all` are delayed 100-500ms a third of the
+//! // time, fail with 503 one time in ten, and lose their connection one time
+//! // in twenty.
+//! let mw = Chaos::new()
+//!   .latency(0.3, Duration::from_millis(100), Duration::from_millis(500))
+//!   .error(0.1, StatusCode::SERVICE_UNAVAILABLE)
+//!   .drop_connection(0.05)
+//!   .into_middleware();
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::HeaderName;
+use http::HeaderValue;
+use http::StatusCode;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// Default trigger header.
+pub const CHAOS_HEADER: &str = "x-chaos";
+/// Response header naming the fault that was injected.
+pub const FAULT_HEADER: &str = "x-chaos-fault";
+
+/// A kind of injected fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+  /// Delayed request.
+  Latency,
+  /// Synthetic error response.
+  Error,
+  /// Aborted connection.
+  Drop,
+}
+
+impl Fault {
+  /// Name used in the trigger and `X-Chaos-Fault` headers.
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Latency => "latency",
+      Self::Error => "error",
+      Self::Drop => "drop",
+    }
+  }
+}
+
+impl fmt::Display for Fault {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// Body error used to abort the connection.
+#[derive(Debug)]
+struct DroppedByChaos;
+
+impl fmt::Display for DroppedByChaos {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("connection dropped by chaos middleware")
+  }
+}
+
+impl std::error::Error for DroppedByChaos {}
+
+/// Fault-injection middleware configuration.
+#[derive(Debug, Clone)]
+pub struct Chaos {
+  header: HeaderName,
+  always: bool,
+  latency: Option<(f64, Duration, Duration)>,
+  error: Option<(f64, StatusCode)>,
+  drop: Option<f64>,
+}
+
+impl Default for Chaos {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Chaos {
+  /// Creates a middleware with no faults configured, triggered by `X-Chaos`.
+  pub fn new() -> Self {
+    Self {
+      header: HeaderName::from_static(CHAOS_HEADER),
+      always: false,
+      latency: None,
+      error: None,
+      drop: None,
+    }
+  }
+
+  /// Sets the trigger header name.
+  pub fn header_name(mut self, name: &'static str) -> Self {
+    self.header = HeaderName::from_static(name);
+    self
+  }
+
+  /// Injects faults into every request, with or without the trigger header.
+  pub fn always(mut self) -> Self {
+    self.always = true;
+    self
+  }
+
+  /// With `probability`, delays the request by a uniformly random time
+  /// between `min` and `max`.
+  pub fn latency(mut self, probability: f64, min: Duration, max: Duration) -> Self {
+    self.latency = Some((probability.clamp(0.0, 1.0), min, max.max(min)));
+    self
+  }
+
+  /// With `probability`, answers `status` instead of running the handler.
+  pub fn error(mut self, probability: f64, status: StatusCode) -> Self {
+    self.error = Some((probability.clamp(0.0, 1.0), status));
+    self
+  }
+
+  /// With `probability`, aborts the connection instead of running the
+  /// handler.
+  pub fn drop_connection(mut self, probability: f64) -> Self {
+    self.drop = Some(probability.clamp(0.0, 1.0));
+    self
+  }
+
+  /// Faults enabled for `req`, or `None` when chaos does not apply.
+  fn enabled(&self, req: &Request) -> Option<[bool; 3]> {
+    let Some(value) = req.headers().get(&self.header) else {
+      return self.always.then_some([true; 3]);
+    };
+    let value = value.to_str().ok()?.trim();
+    if ["", "1", "on", "true", "all"]
+      .iter()
+      .any(|v| value.eq_ignore_ascii_case(v))
+    {
+      return Some([true; 3]);
+    }
+    let mut on = [false; 3];
+    for name in value.split(',').map(str::trim) {
+      for (i, fault) in [Fault::Latency, Fault::Error, Fault::Drop]
+        .iter()
+        .enumerate()
+      {
+        if name.eq_ignore_ascii_case(fault.as_str()) {
+          on[i] = true;
+        }
+      }
+    }
+    Some(on)
+  }
+}
+
+/// Uniform sample in `[0, 1)` from the OS-backed RNG behind uuid v4.
+fn roll() -> f64 {
+  let bits = uuid::Uuid::new_v4().as_u128() >> 75;
+  bits as f64 / (1u64 << 53) as f64
+}
+
+fn hit(probability: f64) -> bool {
+  probability > 0.0 && roll() < probability
+}
+
+impl IntoMiddleware for Chaos {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    move |req: Request, next: Next| {
+      let chaos = self.clone();
+      Box::pin(async move {
+        let Some([latency_on, error_on, drop_on]) = chaos.enabled(&req) else {
+          return next.run(req).await;
+        };
+
+        let mut injected = None;
+        if latency_on
+          && let Some((p, min, max)) = chaos.latency
+          && hit(p)
+        {
+          let delay = min + max.saturating_sub(min).mul_f64(roll());
+          tracing::debug!(path = %req.uri().path(), ?delay, "chaos: injecting latency");
+          tokio::time::sleep(delay).await;
+          injected = Some(Fault::Latency);
+        }
+
+        if drop_on && chaos.drop.is_some_and(hit) {
+          tracing::debug!(path = %req.uri().path(), "chaos: dropping connection");
+          let body = futures_util::stream::once(async { Err::<Bytes, _>(DroppedByChaos) });
+          return http::Response::new(TakoBody::from_stream(body));
+        }
+
+        let mut resp = match chaos.error {
+          Some((p, status)) if error_on && hit(p) => {
+            tracing::debug!(path = %req.uri().path(), %status, "chaos: injecting error");
+            injected = Some(Fault::Error);
+            http::Response::builder()
+              .status(status)
+              .body(TakoBody::from("injected by chaos middleware"))
+              .expect("valid chaos response")
+          }
+          _ => next.run(req).await,
+        };
+        if let Some(fault) = injected {
+          resp
+            .headers_mut()
+            .insert(FAULT_HEADER, HeaderValue::from_static(fault.as_str()));
+        }
+        resp
+      })
+    }
+  }
+}
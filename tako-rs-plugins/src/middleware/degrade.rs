@@ -0,0 +1,395 @@
+//! Graceful degradation for failing routes.
+//!
+//! [`Degrade`] watches a route's responses. When the handler fails — a 5xx
+//! by default, or no answer within [`Degrade::timeout`] — the client gets a
+//! degraded response instead of the error:
+//!
+//! - the last good copy of the same URL ([`Degrade::last_good`]), kept for
+//!   small `GET` responses and served with an `Age` header;
+//! - otherwise a fixed fallback: static HTML ([`Degrade::html`]), minimal
+//!   JSON ([`Degrade::json`]), or a handler of your own
+//!   ([`Degrade::responder`]).
+//!
+//! If neither applies the original failure goes through unchanged.
+//! Degraded responses carry `x-degraded: error` or `x-degraded: timeout`
+//! and `Cache-Control: no-store`, so caches downstream do not keep them.
+//!
+//! With the `signals` feature the middleware emits [`ids::ACTIVATED`] when a
+//! route starts serving degraded responses and [`ids::RECOVERED`] on its
+//! next success. The timeout needs the tokio timer and is ignored on the
+//! `compio` runtime.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use http::Method;
+//! use tako::middleware::IntoMiddleware;
+//! use tako::middleware::degrade::Degrade;
+//! use tako::router::Router;
+//! use tako::types::Request;
+//!
+//! let mut router = Router::new();
+//! router
+//!   .route(Method::GET, "/recommendations", |_req: Request| async { "[]" })
+//!   .middleware(
+//!     Degrade::json(serde_json::json!({ "items": [] }))
+//!       .last_good(true)
+//!       .timeout(Duration::from_millis(500))
+//!       .into_middleware(),
+//!   );
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use bytes::Bytes;
+use http::HeaderMap;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header::AGE;
+use http::header::CACHE_CONTROL;
+use http::header::CONTENT_TYPE;
+use http_body::Body as _;
+use http_body_util::BodyExt;
+use scc::HashMap as SccHashMap;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::clock;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::router_state::MatchedPath;
+#[cfg(feature = "signals")]
+use tako_rs_core::signals::Signal;
+#[cfg(feature = "signals")]
+use tako_rs_core::signals::app_events;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// Signal ids emitted by [`Degrade`] (`signals` feature).
+///
+/// Both carry `route` (the matched route template) as metadata;
+/// `ACTIVATED` also carries `reason` (`error` or `timeout`).
+pub mod ids {
+  /// A route served its first degraded response since it last succeeded.
+  pub const ACTIVATED: &str = "degrade.activated";
+  /// A degraded route succeeded again.
+  pub const RECOVERED: &str = "degrade.recovered";
+}
+
+/// Decides whether a response counts as a failure.
+pub type FailureFn = Arc<dyn Fn(&Response) -> bool + Send + Sync + 'static>;
+type ResponderFn =
+  Arc<dyn Fn(Request) -> Pin<Box<dyn Future<Output = Response> + Send>> + Send + Sync>;
+
+#[derive(Clone)]
+enum Fallback {
+  None,
+  Static {
+    content_type: HeaderValue,
+    body: Bytes,
+  },
+  Responder(ResponderFn),
+}
+
+#[derive(Clone)]
+struct SavedCopy {
+  status: StatusCode,
+  headers: HeaderMap,
+  body: Bytes,
+  stored_at: Instant,
+}
+
+/// Graceful-degradation middleware.
+pub struct Degrade {
+  fallback: Fallback,
+  status: StatusCode,
+  last_good: bool,
+  max_copy_bytes: usize,
+  max_copies: usize,
+  timeout: Option<Duration>,
+  is_failure: FailureFn,
+}
+
+impl Degrade {
+  fn with(fallback: Fallback) -> Self {
+    Self {
+      fallback,
+      status: StatusCode::OK,
+      last_good: false,
+      max_copy_bytes: 256 * 1024,
+      max_copies: 1024,
+      timeout: None,
+      is_failure: Arc::new(|resp: &Response| resp.status().is_server_error()),
+    }
+  }
+
+  /// Serves only last good copies; failures without one pass through.
+  pub fn last_good_only() -> Self {
+    Self::with(Fallback::None).last_good(true)
+  }
+
+  /// Falls back to a static HTML page.
+  pub fn html(body: impl Into<Bytes>) -> Self {
+    Self::with(Fallback::Static {
+      content_type: HeaderValue::from_static("text/html; charset=utf-8"),
+      body: body.into(),
+    })
+  }
+
+  /// Falls back to a fixed JSON document, e.g. an empty list.
+  pub fn json(value: serde_json::Value) -> Self {
+    Self::with(Fallback::Static {
+      content_type: HeaderValue::from_static("application/json"),
+      body: Bytes::from(value.to_string()),
+    })
+  }
+
+  /// Falls back to `handler`, called with the method, URI, and headers of
+  /// the failed request (the body has already been consumed).
+  pub fn responder<F, Fut, R>(handler: F) -> Self
+  where
+    F: Fn(Request) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = R> + Send + 'static,
+    R: Responder + Send + 'static,
+  {
+    let handler = Arc::new(handler);
+    Self::with(Fallback::Responder(Arc::new(move |req| {
+      let handler = handler.clone();
+      Box::pin(async move { handler(req).await.into_response() })
+    })))
+  }
+
+  /// Status of the static fallbacks (default `200 OK`).
+  pub fn status(mut self, status: StatusCode) -> Self {
+    self.status = status;
+    self
+  }
+
+  /// Prefer the last good copy of the URL over the fallback (default off).
+  /// Only `GET` responses with a `2xx` status and a known length up to
+  /// [`Degrade::max_copy_bytes`] are kept.
+  pub fn last_good(mut self, yes: bool) -> Self {
+    self.last_good = yes;
+    self
+  }
+
+  /// Largest response body kept as a last good copy (default 256 KiB).
+  pub fn max_copy_bytes(mut self, bytes: usize) -> Self {
+    self.max_copy_bytes = bytes;
+    self
+  }
+
+  /// Most URLs with a last good copy (default 1024). URLs beyond the cap
+  /// are not copied until an existing one is replaced.
+  pub fn max_copies(mut self, n: usize) -> Self {
+    self.max_copies = n;
+    self
+  }
+
+  /// Degrades requests the handler has not answered within `d`.
+  pub fn timeout(mut self, d: Duration) -> Self {
+    self.timeout = Some(d);
+    self
+  }
+
+  /// Decides which responses are failures (default: any 5xx).
+  pub fn failure_when<F>(mut self, f: F) -> Self
+  where
+    F: Fn(&Response) -> bool + Send + Sync + 'static,
+  {
+    self.is_failure = Arc::new(f);
+    self
+  }
+}
+
+#[derive(Clone, Copy)]
+enum Reason {
+  Error,
+  Timeout,
+}
+
+impl Reason {
+  fn as_str(self) -> &'static str {
+    match self {
+      Self::Error => "error",
+      Self::Timeout => "timeout",
+    }
+  }
+}
+
+struct Inner {
+  fallback: Fallback,
+  status: StatusCode,
+  last_good: bool,
+  max_copy_bytes: usize,
+  max_copies: usize,
+  #[cfg_attr(feature = "compio", allow(dead_code))]
+  timeout: Option<Duration>,
+  is_failure: FailureFn,
+  copies: SccHashMap<String, SavedCopy>,
+  /// Route templates currently serving degraded responses.
+  degraded: SccHashMap<String, ()>,
+}
+
+impl Inner {
+  async fn handle(&self, req: Request, next: Next) -> Response {
+    let route = req
+      .extensions()
+      .get::<MatchedPath>()
+      .map_or_else(|| "<unmatched>".to_string(), |mp| mp.as_str().to_string());
+    let copy_key = (self.last_good && req.method() == Method::GET).then(|| req.uri().to_string());
+    let replay = matches!(self.fallback, Fallback::Responder(_)).then(|| {
+      let mut replay = http::Request::new(TakoBody::empty());
+      *replay.method_mut() = req.method().clone();
+      *replay.uri_mut() = req.uri().clone();
+      *replay.version_mut() = req.version();
+      *replay.headers_mut() = req.headers().clone();
+      replay
+    });
+
+    let outcome = match self.timeout {
+      #[cfg(not(feature = "compio"))]
+      Some(d) => tokio::time::timeout(d, next.run(req)).await.map_err(|_| ()),
+      _ => Ok(next.run(req).await),
+    };
+    let (resp, reason) = match outcome {
+      Ok(resp) if !(self.is_failure)(&resp) => {
+        if self.degraded.remove_async(&route).await.is_some() {
+          tracing::info!(route = %route, "route recovered from degraded mode");
+          #[cfg(feature = "signals")]
+          app_events()
+            .emit(Signal::new(ids::RECOVERED).meta("route", route.clone()))
+            .await;
+        }
+        return match copy_key {
+          Some(key) => self.keep(key, resp).await,
+          None => resp,
+        };
+      }
+      Ok(resp) => (Some(resp), Reason::Error),
+      Err(()) => (None, Reason::Timeout),
+    };
+
+    let degraded = match copy_key.as_ref() {
+      Some(key) => self.copies.read_async(key, |_, c| c.clone()).await,
+      None => None,
+    };
+    let degraded = match (degraded, &self.fallback) {
+      (Some(copy), _) => Some(copy.into_response()),
+      (None, Fallback::None) => None,
+      (None, Fallback::Static { content_type, body }) => {
+        let mut resp = http::Response::new(TakoBody::from(body.clone()));
+        *resp.status_mut() = self.status;
+        resp
+          .headers_mut()
+          .insert(CONTENT_TYPE, content_type.clone());
+        Some(resp)
+      }
+      (None, Fallback::Responder(f)) => match replay {
+        Some(replay) => Some(f(replay).await),
+        None => None,
+      },
+    };
+    let Some(mut degraded) = degraded else {
+      return resp.unwrap_or_else(|| {
+        http::Response::builder()
+          .status(StatusCode::SERVICE_UNAVAILABLE)
+          .body(TakoBody::empty())
+          .expect("valid degraded response")
+      });
+    };
+
+    let headers = degraded.headers_mut();
+    headers.insert("x-degraded", HeaderValue::from_static(reason.as_str()));
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    if self.degraded.insert_async(route.clone(), ()).await.is_ok() {
+      tracing::warn!(route = %route, reason = reason.as_str(), "route serving degraded responses");
+      #[cfg(feature = "signals")]
+      app_events()
+        .emit(
+          Signal::new(ids::ACTIVATED)
+            .meta("route", route)
+            .meta("reason", reason.as_str()),
+        )
+        .await;
+    }
+    degraded
+  }
+
+  /// Stores a copy of a small successful response and returns it intact.
+  async fn keep(&self, key: String, resp: Response) -> Response {
+    let fits = resp
+      .body()
+      .size_hint()
+      .exact()
+      .is_some_and(|n| n as usize <= self.max_copy_bytes);
+    if !resp.status().is_success() || !fits {
+      return resp;
+    }
+    let (parts, body) = resp.into_parts();
+    let Ok(body) = body
+      .collect()
+      .await
+      .map(http_body_util::Collected::to_bytes)
+    else {
+      return http::Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .body(TakoBody::empty())
+        .expect("valid 500 response");
+    };
+    let copy = SavedCopy {
+      status: parts.status,
+      headers: parts.headers.clone(),
+      body: body.clone(),
+      stored_at: clock::now(),
+    };
+    if self.copies.len() < self.max_copies {
+      self.copies.upsert_async(key, copy).await;
+    } else {
+      self.copies.update_async(&key, |_, c| *c = copy).await;
+    }
+    http::Response::from_parts(parts, TakoBody::from(body))
+  }
+}
+
+impl SavedCopy {
+  fn into_response(self) -> Response {
+    let age = clock::now().duration_since(self.stored_at).as_secs();
+    let mut resp = http::Response::new(TakoBody::from(self.body));
+    *resp.status_mut() = self.status;
+    *resp.headers_mut() = self.headers;
+    resp.headers_mut().insert(AGE, HeaderValue::from(age));
+    resp
+  }
+}
+
+impl IntoMiddleware for Degrade {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    let inner = Arc::new(Inner {
+      fallback: self.fallback,
+      status: self.status,
+      last_good: self.last_good,
+      max_copy_bytes: self.max_copy_bytes,
+      max_copies: self.max_copies,
+      timeout: self.timeout,
+      is_failure: self.is_failure,
+      copies: SccHashMap::new(),
+      degraded: SccHashMap::new(),
+    });
+    move |req: Request, next: Next| {
+      let inner = inner.clone();
+      Box::pin(async move { inner.handle(req, next).await })
+    }
+  }
+}
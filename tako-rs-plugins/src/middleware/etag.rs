@@ -67,7 +67,7 @@ impl ETag {
   }
 }
 
-fn weak_match(if_none_match: &str, etag: &str) -> bool {
+pub(crate) fn weak_match(if_none_match: &str, etag: &str) -> bool {
   // Both `*` (wildcard) and a comma-separated list are valid.
   if if_none_match.trim() == "*" {
     return true;
@@ -80,7 +80,7 @@ fn weak_match(if_none_match: &str, etag: &str) -> bool {
   })
 }
 
-fn build_304(
+pub(crate) fn build_304(
   status_headers: http::HeaderMap,
   request_id_header_keep: Option<HeaderValue>,
 ) -> Response {
@@ -109,7 +109,7 @@ fn build_304(
 /// the validator should also cover (e.g. content-type negotiation on the same
 /// path) — clients may not assume byte-for-byte equality, only semantic
 /// equivalence.
-fn make_etag(bytes: &[u8]) -> String {
+pub(crate) fn make_etag(bytes: &[u8]) -> String {
   let mut hasher = Sha256::new();
   hasher.update(bytes);
   let digest = hasher.finalize();
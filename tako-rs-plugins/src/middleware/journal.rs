@@ -0,0 +1,396 @@
+//! Write-ahead journaling of accepted write requests.
+//!
+//! [`Journal`] appends every write request that reaches it — method, URI,
+//! headers and the full body — to an append-only file before the handler
+//! runs, and only lets the request through once the record is written with
+//! the configured [`FsyncPolicy`]. After a crash, [`Journal::read`] returns
+//! the records and [`JournalEntry::to_request`] rebuilds each one for
+//! `Router::dispatch`, so ingestion endpoints can replay what they had
+//! accepted but not yet applied.
+//!
+//! Place it after authentication and validation middleware: whatever reaches
+//! the journal counts as accepted. Handlers see a [`Journaled`] extension
+//! with the record's sequence number, which replays carry too, so they can
+//! skip work they already finished. Replays pass the journal without being
+//! recorded again.
+//!
+//! The file holds one JSON object per line; the body is base64-encoded.
+//! `Authorization`, `Cookie` and `Proxy-Authorization` are left out unless
+//! [`Journal::redact`] is overridden, and header values that are not valid
+//! UTF-8 are dropped. A record torn by a crash mid-write is ignored on read.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use tako::Method;
+//! use tako::middleware::IntoMiddleware;
+//! use tako::middleware::journal::{FsyncPolicy, Journal};
+//! use tako::router::Router;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let mut router = Router::new();
+//! router
+//!     .post("/readings", |_req| async { "stored" })
+//!     .middleware(
+//!         Journal::open("/var/lib/ingest/readings.journal")?
+//!             .fsync(FsyncPolicy::Interval(Duration::from_millis(50)))
+//!             .into_middleware(),
+//!     );
+//!
+//! // On startup, before serving:
+//! for entry in Journal::read("/var/lib/ingest/readings.journal")? {
+//!     router.dispatch(entry.to_request()).await;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io;
+use std::io::BufRead as _;
+use std::io::BufReader;
+use std::io::Write as _;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::UNIX_EPOCH;
+
+use bytes::Bytes;
+use http::HeaderName;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header::AUTHORIZATION;
+use http::header::COOKIE;
+use http::header::PROXY_AUTHORIZATION;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde::Serialize;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::clock;
+use tako_rs_core::extractors::body_limit::CollectError;
+use tako_rs_core::extractors::body_limit::collect_limited;
+use tako_rs_core::extractors::body_limit::payload_too_large;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// When journal writes are flushed to stable storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+  /// `fsync` every record before its request proceeds. Nothing accepted is
+  /// lost, at the cost of one disk flush per request.
+  Always,
+  /// `fsync` with the first record written after `interval` has passed since
+  /// the last one; a crash can lose up to that much of accepted requests.
+  Interval(Duration),
+  /// Leave flushing to the operating system. Survives a process crash but
+  /// not a power loss.
+  Never,
+}
+
+/// One journaled request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+  /// Position in the journal, starting at 1.
+  pub seq: u64,
+  /// When the request was accepted, in milliseconds since the Unix epoch.
+  pub timestamp_ms: u64,
+  /// Request method.
+  pub method: String,
+  /// Request URI, path and query.
+  pub uri: String,
+  /// Request headers, minus the redacted ones.
+  pub headers: Vec<(String, String)>,
+  /// Request body.
+  #[serde(with = "base64_body")]
+  pub body: Bytes,
+}
+
+impl JournalEntry {
+  /// Rebuilds the request for `Router::dispatch`, tagged with a
+  /// [`Journaled`] extension marked as a replay.
+  pub fn to_request(&self) -> Request {
+    let mut req = http::Request::new(TakoBody::from(self.body.clone()));
+    *req.method_mut() = Method::from_bytes(self.method.as_bytes()).unwrap_or(Method::POST);
+    if let Ok(uri) = self.uri.parse() {
+      *req.uri_mut() = uri;
+    }
+    for (name, value) in &self.headers {
+      if let (Ok(name), Ok(value)) = (
+        HeaderName::from_bytes(name.as_bytes()),
+        HeaderValue::from_str(value),
+      ) {
+        req.headers_mut().append(name, value);
+      }
+    }
+    req.extensions_mut().insert(Journaled {
+      seq: self.seq,
+      replayed: true,
+    });
+    req
+  }
+}
+
+mod base64_body {
+  use base64::Engine as _;
+  use base64::engine::general_purpose::STANDARD;
+  use bytes::Bytes;
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serializer;
+
+  pub(super) fn serialize<S: Serializer>(body: &Bytes, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&STANDARD.encode(body))
+  }
+
+  pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Bytes, D::Error> {
+    let encoded = String::deserialize(d)?;
+    STANDARD
+      .decode(encoded)
+      .map(Bytes::from)
+      .map_err(serde::de::Error::custom)
+  }
+}
+
+/// Request extension identifying the journal record of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Journaled {
+  /// The record's sequence number.
+  pub seq: u64,
+  /// `true` when the request comes from [`JournalEntry::to_request`].
+  pub replayed: bool,
+}
+
+struct Log {
+  file: File,
+  next_seq: u64,
+  last_sync: Instant,
+}
+
+impl Log {
+  fn append(&mut self, entry: &mut JournalEntry, policy: FsyncPolicy) -> io::Result<()> {
+    entry.seq = self.next_seq;
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    self.file.write_all(&line)?;
+    self.next_seq += 1;
+    let due = match policy {
+      FsyncPolicy::Always => true,
+      FsyncPolicy::Interval(interval) => clock::now().duration_since(self.last_sync) >= interval,
+      FsyncPolicy::Never => false,
+    };
+    if due {
+      self.file.sync_data()?;
+      self.last_sync = clock::now();
+    }
+    Ok(())
+  }
+}
+
+/// Journals write requests to an append-only file before they reach the
+/// handler.
+pub struct Journal {
+  log: Arc<Mutex<Log>>,
+  policy: FsyncPolicy,
+  methods: Vec<Method>,
+  redact: Vec<HeaderName>,
+  max_body: usize,
+}
+
+impl Journal {
+  /// Opens or creates the journal at `path`, continuing its numbering. A
+  /// torn final record is cut off first, so new records start on a clean
+  /// line; its request was never let through.
+  ///
+  /// Defaults: [`FsyncPolicy::Always`]; `POST`, `PUT`, `PATCH` and `DELETE`
+  /// journaled; bodies up to 16 MiB.
+  ///
+  /// # Errors
+  ///
+  /// Fails when the file cannot be opened or read.
+  pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+    let path = path.as_ref();
+    let (entries, complete_len) = read_entries(path)?;
+    let next_seq = entries.last().map_or(1, |e| e.seq + 1);
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() > complete_len {
+      file.set_len(complete_len)?;
+    }
+    Ok(Self {
+      log: Arc::new(Mutex::new(Log {
+        file,
+        next_seq,
+        last_sync: clock::now(),
+      })),
+      policy: FsyncPolicy::Always,
+      methods: vec![Method::POST, Method::PUT, Method::PATCH, Method::DELETE],
+      redact: vec![AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION],
+      max_body: 16 * 1024 * 1024,
+    })
+  }
+
+  /// Every complete record in the journal at `path`, oldest first. A
+  /// missing file has none.
+  ///
+  /// # Errors
+  ///
+  /// Fails when the file cannot be read or a record other than the last is
+  /// corrupt.
+  pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<JournalEntry>> {
+    read_entries(path.as_ref()).map(|(entries, _)| entries)
+  }
+
+  /// When records are flushed to disk (default [`FsyncPolicy::Always`]).
+  pub fn fsync(mut self, policy: FsyncPolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// The methods to journal; others pass straight through.
+  pub fn methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+    self.methods = methods.into_iter().collect();
+    self
+  }
+
+  /// Headers kept out of the journal (default `Authorization`, `Cookie`
+  /// and `Proxy-Authorization`).
+  pub fn redact(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+    self.redact = headers.into_iter().collect();
+    self
+  }
+
+  /// Largest body journaled, in bytes (default 16 MiB); larger requests are
+  /// rejected with `413`. A smaller route or `BodyLimit` limit wins.
+  pub fn max_body(mut self, bytes: usize) -> Self {
+    self.max_body = bytes;
+    self
+  }
+}
+
+/// The records in `path` and the length of the file they occupy, which
+/// excludes a torn final record.
+fn read_entries(path: &Path) -> io::Result<(Vec<JournalEntry>, u64)> {
+  let file = match File::open(path) {
+    Ok(file) => file,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+    Err(e) => return Err(e),
+  };
+  let mut reader = BufReader::new(file);
+  let mut entries = Vec::new();
+  let mut complete_len = 0u64;
+  let mut line = Vec::new();
+  loop {
+    line.clear();
+    let n = reader.read_until(b'\n', &mut line)?;
+    if n == 0 {
+      break;
+    }
+    let parsed = line
+      .strip_suffix(b"\n")
+      .and_then(|record| serde_json::from_slice(record).ok());
+    match parsed {
+      Some(entry) => {
+        entries.push(entry);
+        complete_len += n as u64;
+      }
+      // Only the final record may be incomplete.
+      None if reader.fill_buf()?.is_empty() => break,
+      None => {
+        return Err(io::Error::new(
+          io::ErrorKind::InvalidData,
+          "corrupt journal record",
+        ));
+      }
+    }
+  }
+  Ok((entries, complete_len))
+}
+
+impl IntoMiddleware for Journal {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    let log = self.log;
+    let policy = self.policy;
+    let methods = Arc::new(self.methods);
+    let redact = Arc::new(self.redact);
+    let max_body = self.max_body;
+
+    move |mut req: Request, next: Next| {
+      let log = Arc::clone(&log);
+      let methods = Arc::clone(&methods);
+      let redact = Arc::clone(&redact);
+
+      Box::pin(async move {
+        // Replayed requests are already in the journal.
+        if !methods.contains(req.method()) || req.extensions().get::<Journaled>().is_some() {
+          return next.run(req).await;
+        }
+
+        let body = collect_limited(&mut req).await;
+        let body = match body {
+          Ok(body) if body.len() <= max_body => body,
+          Ok(_) => return payload_too_large(max_body),
+          Err(CollectError::TooLarge(limit)) => return payload_too_large(limit),
+          Err(CollectError::Read(err)) => {
+            return (
+              StatusCode::BAD_REQUEST,
+              format!("failed to read request body: {err}"),
+            )
+              .into_response();
+          }
+        };
+
+        let mut entry = JournalEntry {
+          seq: 0,
+          timestamp_ms: clock::system_now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+          method: req.method().to_string(),
+          uri: req.uri().to_string(),
+          headers: req
+            .headers()
+            .iter()
+            .filter(|(name, _)| !redact.contains(name))
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+          body: body.clone(),
+        };
+        let written = tokio::task::spawn_blocking(move || {
+          log.lock().append(&mut entry, policy).map(|()| entry.seq)
+        })
+        .await;
+        let seq = match written {
+          Ok(Ok(seq)) => seq,
+          Ok(Err(err)) => {
+            tracing::error!(error = %err, "request journal write failed");
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+          }
+          Err(err) => {
+            tracing::error!(error = %err, "request journal task failed");
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+          }
+        };
+
+        *req.body_mut() = TakoBody::from(body);
+        req.extensions_mut().insert(Journaled {
+          seq,
+          replayed: false,
+        });
+        next.run(req).await
+      })
+    }
+  }
+}
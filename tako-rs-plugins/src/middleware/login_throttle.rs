@@ -0,0 +1,506 @@
+//! Brute-force protection for credential endpoints.
+//!
+//! [`LoginThrottle`] sits in front of whatever checks the credentials — the
+//! [`BasicAuth`](super::basic_auth::BasicAuth),
+//! [`BearerAuth`](super::bearer_auth::BearerAuth), or
+//! [`JwtAuth`](super::jwt_auth) middleware, or a login handler — and watches
+//! the responses. A `401 Unauthorized` (or whatever the classifier flags)
+//! counts as a failed attempt against two keys: the account being tried and
+//! the peer IP. Once either key reaches its threshold it is locked out, and
+//! every further failure doubles the lockout, up to a cap. Locked requests
+//! are answered with `429 Too Many Requests` and `Retry-After` without the
+//! credentials ever being checked.
+//!
+//! A successful login clears the account's counter but not the IP's, so one
+//! valid account cannot be used to launder a credential-stuffing run. Quiet
+//! keys are forgotten after `forget_after`.
+//!
+//! Every response — success, failure, or lockout — is held back until
+//! `min_response_time` has passed since the request arrived, so response
+//! timing does not reveal whether a user exists, whether the password was
+//! close, or whether the key is locked. The padding needs the tokio timer and
+//! is skipped on the `compio` runtime.
+//!
+//! With the `signals` feature the middleware emits [`ids::LOCKED`] when a key
+//! is locked out and [`ids::BLOCKED`] for each request turned away.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use tako::middleware::IntoMiddleware;
+//! use tako::middleware::basic_auth::BasicAuth;
+//! use tako::middleware::login_throttle::LoginThrottle;
+//! use tako::router::Router;
+//!
+//! let mut router = Router::new();
+//! // The throttle must run outside the middleware that checks credentials.
+//! router.middleware(
+//!     LoginThrottle::new()
+//!         .account_threshold(5)
+//!         .max_lockout(Duration::from_secs(600))
+//!         .into_middleware(),
+//! );
+//! router.middleware(BasicAuth::single("admin", "secret").into_middleware());
+//! ```
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use base64::Engine;
+use http::HeaderName;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::AUTHORIZATION;
+use http::header::RETRY_AFTER;
+use scc::HashMap as SccHashMap;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::conn_info::ConnInfo;
+use tako_rs_core::conn_info::PeerAddr;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+#[cfg(feature = "signals")]
+use tako_rs_core::signals::Signal;
+#[cfg(feature = "signals")]
+use tako_rs_core::signals::app_events;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// Signal ids emitted by the login throttle (`signals` feature).
+///
+/// Both carry `scope` (`account` or `ip`), `key`, `failures`, and
+/// `retry_after` (seconds) as metadata.
+pub mod ids {
+  /// A key reached its failure threshold and was locked out.
+  pub const LOCKED: &str = "login_throttle.locked";
+  /// A request was rejected because its account or IP is locked out.
+  pub const BLOCKED: &str = "login_throttle.blocked";
+}
+
+/// Extracts the account an attempt is for.
+pub type AccountFn = Arc<dyn Fn(&Request) -> Option<String> + Send + Sync + 'static>;
+/// Decides whether a response is a failed attempt.
+pub type FailureFn = Arc<dyn Fn(&Response) -> bool + Send + Sync + 'static>;
+
+/// How often (in requests) quiet keys are swept from the tables.
+const SWEEP_EVERY: u64 = 1024;
+
+#[derive(Clone, Copy)]
+struct Entry {
+  failures: u32,
+  last_failure: Instant,
+  locked_until: Option<Instant>,
+}
+
+/// Failure counters for one scope (accounts or IPs).
+struct Table {
+  scope: &'static str,
+  threshold: u32,
+  entries: SccHashMap<String, Entry>,
+}
+
+impl Table {
+  fn new(scope: &'static str, threshold: u32) -> Self {
+    Self {
+      scope,
+      threshold,
+      entries: SccHashMap::new(),
+    }
+  }
+
+  /// Time left on `key`'s lockout, if any.
+  async fn locked(&self, key: &str, now: Instant) -> Option<Duration> {
+    let until = self
+      .entries
+      .read_async(key, |_, e| e.locked_until)
+      .await??;
+    (until > now).then(|| until - now)
+  }
+
+  /// Counts a failure; returns the failure count and the new lockout when
+  /// the key crossed its threshold.
+  async fn fail(&self, key: &str, now: Instant, policy: &Lockout) -> (u32, Option<Duration>) {
+    let mut entry = self
+      .entries
+      .entry_async(key.to_string())
+      .await
+      .or_insert(Entry {
+        failures: 0,
+        last_failure: now,
+        locked_until: None,
+      });
+    let e = entry.get_mut();
+    if policy.is_stale(e, now) {
+      e.failures = 0;
+      e.locked_until = None;
+    }
+    e.failures = e.failures.saturating_add(1);
+    e.last_failure = now;
+    if e.failures < self.threshold {
+      return (e.failures, None);
+    }
+    let lockout = policy.duration(e.failures - self.threshold);
+    e.locked_until = Some(now + lockout);
+    (e.failures, Some(lockout))
+  }
+
+  async fn clear(&self, key: &str) {
+    self.entries.remove_async(key).await;
+  }
+
+  async fn sweep(&self, now: Instant, policy: &Lockout) {
+    self
+      .entries
+      .retain_async(|_, e| !policy.is_stale(e, now))
+      .await;
+  }
+}
+
+#[derive(Clone, Copy)]
+struct Lockout {
+  base: Duration,
+  max: Duration,
+  forget_after: Duration,
+}
+
+impl Lockout {
+  /// `base × 2^excess`, capped at `max`.
+  fn duration(&self, excess: u32) -> Duration {
+    let factor = 1u32.checked_shl(excess).unwrap_or(u32::MAX);
+    self.base.saturating_mul(factor).min(self.max)
+  }
+
+  /// A key is forgotten once it has been quiet for `forget_after` beyond any
+  /// lockout.
+  fn is_stale(&self, e: &Entry, now: Instant) -> bool {
+    let quiet_since = e.locked_until.unwrap_or(e.last_failure).max(e.last_failure);
+    now.saturating_duration_since(quiet_since) >= self.forget_after
+  }
+}
+
+/// Login throttling middleware configuration.
+pub struct LoginThrottle {
+  account_threshold: u32,
+  ip_threshold: u32,
+  base_lockout: Duration,
+  max_lockout: Duration,
+  forget_after: Duration,
+  min_response_time: Duration,
+  locked_status: StatusCode,
+  account_fn: AccountFn,
+  is_failure: FailureFn,
+}
+
+impl Default for LoginThrottle {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl LoginThrottle {
+  /// Creates a throttle with conservative defaults: lock an account after 5
+  /// failures and an IP after 20, starting at one second and doubling up to
+  /// 15 minutes; forget keys after 15 quiet minutes; pad responses to 250ms.
+  /// The account is the Basic-auth username.
+  pub fn new() -> Self {
+    Self {
+      account_threshold: 5,
+      ip_threshold: 20,
+      base_lockout: Duration::from_secs(1),
+      max_lockout: Duration::from_secs(900),
+      forget_after: Duration::from_secs(900),
+      min_response_time: Duration::from_millis(250),
+      locked_status: StatusCode::TOO_MANY_REQUESTS,
+      account_fn: Arc::new(basic_auth_user),
+      is_failure: Arc::new(|resp: &Response| resp.status() == StatusCode::UNAUTHORIZED),
+    }
+  }
+
+  /// Failures before an account is locked out.
+  pub fn account_threshold(mut self, n: u32) -> Self {
+    self.account_threshold = n.max(1);
+    self
+  }
+
+  /// Failures before an IP is locked out. Keep this well above the account
+  /// threshold: many users can share one NAT address.
+  pub fn ip_threshold(mut self, n: u32) -> Self {
+    self.ip_threshold = n.max(1);
+    self
+  }
+
+  /// Lockout applied when a key first reaches its threshold; it doubles with
+  /// every further failure.
+  pub fn base_lockout(mut self, d: Duration) -> Self {
+    self.base_lockout = d;
+    self
+  }
+
+  /// Upper bound on a single lockout.
+  pub fn max_lockout(mut self, d: Duration) -> Self {
+    self.max_lockout = d;
+    self
+  }
+
+  /// How long a key must stay quiet (after any lockout) before its counter
+  /// is dropped.
+  pub fn forget_after(mut self, d: Duration) -> Self {
+    self.forget_after = d;
+    self
+  }
+
+  /// Minimum time every response takes. Set it above the slowest credential
+  /// check; `Duration::ZERO` disables padding.
+  pub fn min_response_time(mut self, d: Duration) -> Self {
+    self.min_response_time = d;
+    self
+  }
+
+  /// Status returned while locked out.
+  pub fn locked_status(mut self, status: StatusCode) -> Self {
+    self.locked_status = status;
+    self
+  }
+
+  /// Extracts the account from the request, e.g. a JWT `sub` or a field
+  /// copied into a header by the login form. Requests without one are
+  /// throttled by IP only.
+  pub fn account_fn<F>(mut self, f: F) -> Self
+  where
+    F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+  {
+    self.account_fn = Arc::new(f);
+    self
+  }
+
+  /// Takes the account from a request header.
+  pub fn account_header(self, name: impl Into<HeaderName>) -> Self {
+    let name = name.into();
+    self.account_fn(move |req| {
+      let value = req.headers().get(&name)?.to_str().ok()?;
+      Some(value.to_string())
+    })
+  }
+
+  /// Plug a custom failure classifier (default: status `401`).
+  pub fn failure_classifier<F>(mut self, f: F) -> Self
+  where
+    F: Fn(&Response) -> bool + Send + Sync + 'static,
+  {
+    self.is_failure = Arc::new(f);
+    self
+  }
+}
+
+/// The username from `Authorization: Basic …`, lowercased so case variants
+/// share a counter.
+fn basic_auth_user(req: &Request) -> Option<String> {
+  let value = req.headers().get(AUTHORIZATION)?.to_str().ok()?;
+  let (scheme, rest) = value.split_once(' ')?;
+  if !scheme.eq_ignore_ascii_case("Basic") {
+    return None;
+  }
+  let decoded = base64::engine::general_purpose::STANDARD
+    .decode(rest.trim())
+    .ok()?;
+  let decoded = String::from_utf8(decoded).ok()?;
+  let (user, _) = decoded.split_once(':')?;
+  Some(user.to_lowercase())
+}
+
+fn peer_ip(req: &Request) -> Option<String> {
+  if let Some(info) = req.extensions().get::<ConnInfo>()
+    && let PeerAddr::Ip(sa) = &info.peer
+  {
+    return Some(sa.ip().to_string());
+  }
+  req
+    .extensions()
+    .get::<SocketAddr>()
+    .map(|sa| sa.ip().to_string())
+}
+
+fn locked_response(status: StatusCode, retry_after: Duration) -> Response {
+  let mut resp = http::Response::builder()
+    .status(status)
+    .body(TakoBody::from("too many failed login attempts"))
+    .expect("valid lockout response");
+  // Round up so clients never retry into a lockout that is still running.
+  let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+  resp
+    .headers_mut()
+    .insert(RETRY_AFTER, HeaderValue::from(secs));
+  resp
+}
+
+#[cfg(feature = "signals")]
+async fn emit(id: &str, scope: &str, key: &str, failures: Option<u32>, retry_after: Duration) {
+  let mut signal = Signal::new(id)
+    .meta("scope", scope)
+    .meta("key", key)
+    .meta("retry_after", retry_after.as_secs().to_string());
+  if let Some(failures) = failures {
+    signal = signal.meta("failures", failures.to_string());
+  }
+  app_events().emit(signal).await;
+}
+
+struct Inner {
+  accounts: Table,
+  ips: Table,
+  policy: Lockout,
+  #[cfg_attr(feature = "compio", allow(dead_code))]
+  min_response_time: Duration,
+  locked_status: StatusCode,
+  account_fn: AccountFn,
+  is_failure: FailureFn,
+  requests: AtomicU64,
+}
+
+impl Inner {
+  async fn handle(&self, req: Request, next: Next) -> Response {
+    let now = Instant::now();
+    if self.requests.fetch_add(1, Ordering::Relaxed) % SWEEP_EVERY == SWEEP_EVERY - 1 {
+      self.accounts.sweep(now, &self.policy).await;
+      self.ips.sweep(now, &self.policy).await;
+    }
+
+    let keys = [
+      ((self.account_fn)(&req), &self.accounts),
+      (peer_ip(&req), &self.ips),
+    ];
+    for (key, table) in &keys {
+      let Some(key) = key else { continue };
+      if let Some(left) = table.locked(key, now).await {
+        #[cfg(feature = "signals")]
+        emit(ids::BLOCKED, table.scope, key, None, left).await;
+        return locked_response(self.locked_status, left);
+      }
+    }
+
+    let resp = next.run(req).await;
+    if (self.is_failure)(&resp) {
+      let now = Instant::now();
+      for (key, table) in &keys {
+        let Some(key) = key else { continue };
+        let (failures, lockout) = table.fail(key, now, &self.policy).await;
+        if let Some(lockout) = lockout {
+          tracing::warn!(scope = table.scope, key = %key, failures, "login lockout");
+          #[cfg(feature = "signals")]
+          emit(ids::LOCKED, table.scope, key, Some(failures), lockout).await;
+          #[cfg(not(feature = "signals"))]
+          let _ = lockout;
+        }
+      }
+    } else if resp.status().is_success()
+      && let Some(account) = &keys[0].0
+    {
+      self.accounts.clear(account).await;
+    }
+    resp
+  }
+}
+
+impl IntoMiddleware for LoginThrottle {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    let inner = Arc::new(Inner {
+      accounts: Table::new("account", self.account_threshold),
+      ips: Table::new("ip", self.ip_threshold),
+      policy: Lockout {
+        base: self.base_lockout,
+        max: self.max_lockout.max(self.base_lockout),
+        forget_after: self.forget_after,
+      },
+      min_response_time: self.min_response_time,
+      locked_status: self.locked_status,
+      account_fn: self.account_fn,
+      is_failure: self.is_failure,
+      requests: AtomicU64::new(0),
+    });
+
+    move |req: Request, next: Next| {
+      let inner = inner.clone();
+      Box::pin(async move {
+        #[cfg(not(feature = "compio"))]
+        let deadline = tokio::time::Instant::now() + inner.min_response_time;
+        let resp = inner.handle(req, next).await;
+        #[cfg(not(feature = "compio"))]
+        tokio::time::sleep_until(deadline).await;
+        resp
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn policy() -> Lockout {
+    Lockout {
+      base: Duration::from_secs(2),
+      max: Duration::from_secs(60),
+      forget_after: Duration::from_secs(300),
+    }
+  }
+
+  #[test]
+  fn lockout_doubles_up_to_the_cap() {
+    let p = policy();
+    assert_eq!(p.duration(0), Duration::from_secs(2));
+    assert_eq!(p.duration(3), Duration::from_secs(16));
+    assert_eq!(p.duration(5), Duration::from_secs(60));
+    assert_eq!(p.duration(200), Duration::from_secs(60));
+  }
+
+  #[tokio::test]
+  async fn table_locks_at_threshold_and_forgets_quiet_keys() {
+    let (p, table, t0) = (policy(), Table::new("account", 3), Instant::now());
+    assert_eq!(table.fail("a", t0, &p).await, (1, None));
+    assert_eq!(table.fail("a", t0, &p).await, (2, None));
+    assert_eq!(
+      table.fail("a", t0, &p).await,
+      (3, Some(Duration::from_secs(2)))
+    );
+    assert!(table.locked("a", t0).await.is_some());
+    assert!(
+      table
+        .locked("a", t0 + Duration::from_secs(2))
+        .await
+        .is_none()
+    );
+    assert_eq!(
+      table.fail("a", t0, &p).await,
+      (4, Some(Duration::from_secs(4)))
+    );
+    assert!(table.locked("b", t0).await.is_none());
+
+    let later = t0 + Duration::from_secs(400);
+    assert_eq!(table.fail("a", later, &p).await, (1, None));
+    table.sweep(later + Duration::from_secs(300), &p).await;
+    assert!(table.entries.is_empty());
+  }
+
+  #[test]
+  fn account_defaults_to_the_basic_auth_user() {
+    let mut req = http::Request::new(TakoBody::empty());
+    let creds = base64::engine::general_purpose::STANDARD.encode("Alice:pw");
+    req.headers_mut().insert(
+      AUTHORIZATION,
+      HeaderValue::from_str(&format!("basic {creds}")).unwrap(),
+    );
+    assert_eq!(basic_auth_user(&req).as_deref(), Some("alice"));
+  }
+}
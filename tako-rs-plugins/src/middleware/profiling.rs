@@ -0,0 +1,324 @@
+//! Protected on-demand CPU and heap profiling endpoints.
+//!
+//! Reserves two paths under a prefix (default `/debug/pprof`) that
+//! short-circuit the router:
+//!
+//! - `GET {prefix}/profile?seconds=N` — samples the process for `N` seconds
+//!   (default 30, capped by [`Profiling::max_seconds`]) and returns the CPU
+//!   profile.
+//! - `GET {prefix}/heap` — returns a snapshot of live allocations.
+//!
+//! Both accept `format=pprof` (default, gzipped protobuf for
+//! `go tool pprof`) or `format=flamegraph` (SVG). Requests must carry
+//! `Authorization: Bearer <token>`; anything else gets `401`. Only one
+//! profile runs at a time; a second request gets `409`.
+//!
+//! The sampling itself is delegated to a [`Profiler`], so the endpoint works
+//! with whichever profiler fits the deployment. Kinds the profiler does not
+//! implement answer `501`. With the `pprof` feature (Unix), [`PprofProfiler`]
+//! samples the CPU through `pprof-rs`; adding the `jemalloc` feature (Linux)
+//! lets it dump jemalloc heap profiles too, provided the process starts with
+//! `_RJEM_MALLOC_CONF=prof:true,prof_active:true`.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use tako::middleware::IntoMiddleware;
+//! use tako::middleware::profiling::{PprofProfiler, Profiling};
+//!
+//! router.middleware(
+//!   Profiling::new(PprofProfiler::new(), std::env::var("PROFILING_TOKEN")?).into_middleware(),
+//! );
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header::AUTHORIZATION;
+use http::header::CONTENT_DISPOSITION;
+use http::header::CONTENT_TYPE;
+use subtle::ConstantTimeEq;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+#[cfg(all(feature = "pprof", unix))]
+mod pprof_rs;
+
+#[cfg(all(feature = "pprof", unix))]
+#[cfg_attr(docsrs, doc(cfg(feature = "pprof")))]
+pub use pprof_rs::PprofProfiler;
+
+/// Output format requested with `?format=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProfileFormat {
+  /// pprof protobuf, usually gzipped.
+  #[default]
+  Pprof,
+  /// Flamegraph SVG.
+  Flamegraph,
+}
+
+/// A finished profile, ready to send.
+#[derive(Debug, Clone)]
+pub struct Profile {
+  /// `Content-Type` of `body`.
+  pub content_type: &'static str,
+  /// File name suggested in `Content-Disposition`.
+  pub file_name: &'static str,
+  /// Encoded profile.
+  pub body: Bytes,
+}
+
+impl Profile {
+  /// A pprof protobuf profile.
+  pub fn pprof(body: impl Into<Bytes>) -> Self {
+    Self {
+      content_type: "application/octet-stream",
+      file_name: "profile.pb.gz",
+      body: body.into(),
+    }
+  }
+
+  /// A flamegraph SVG.
+  pub fn flamegraph(svg: impl Into<Bytes>) -> Self {
+    Self {
+      content_type: "image/svg+xml",
+      file_name: "flamegraph.svg",
+      body: svg.into(),
+    }
+  }
+}
+
+/// Why a profile could not be produced.
+#[derive(Debug)]
+pub enum ProfileError {
+  /// The profiler does not support this kind or format (`501`).
+  Unsupported,
+  /// Sampling or encoding failed (`500`).
+  Failed(String),
+}
+
+impl ProfileError {
+  /// Wraps any displayable error as [`ProfileError::Failed`].
+  pub fn failed(e: impl fmt::Display) -> Self {
+    Self::Failed(e.to_string())
+  }
+}
+
+impl fmt::Display for ProfileError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Unsupported => f.write_str("profile kind not supported"),
+      Self::Failed(e) => write!(f, "profiling failed: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for ProfileError {}
+
+/// Produces profiles for [`Profiling`].
+#[async_trait]
+pub trait Profiler: Send + Sync + 'static {
+  /// Samples the CPU for `duration`.
+  async fn cpu(&self, duration: Duration, format: ProfileFormat) -> Result<Profile, ProfileError>;
+
+  /// Snapshots the heap. Unsupported by default.
+  async fn heap(&self, format: ProfileFormat) -> Result<Profile, ProfileError> {
+    let _ = format;
+    Err(ProfileError::Unsupported)
+  }
+}
+
+/// Async closures taking `(duration, format)` profile the CPU; the heap
+/// endpoint then answers `501`.
+#[async_trait]
+impl<F, Fut> Profiler for F
+where
+  F: Fn(Duration, ProfileFormat) -> Fut + Send + Sync + 'static,
+  Fut: Future<Output = Result<Profile, ProfileError>> + Send,
+{
+  async fn cpu(&self, duration: Duration, format: ProfileFormat) -> Result<Profile, ProfileError> {
+    (self)(duration, format).await
+  }
+}
+
+/// Profiling endpoint middleware configuration.
+pub struct Profiling {
+  profiler: Arc<dyn Profiler>,
+  token: String,
+  prefix: String,
+  default_seconds: u64,
+  max_seconds: u64,
+}
+
+impl Profiling {
+  /// Serves profiles from `profiler` to requests bearing `token`.
+  pub fn new(profiler: impl Profiler, token: impl Into<String>) -> Self {
+    Self {
+      profiler: Arc::new(profiler),
+      token: token.into(),
+      prefix: "/debug/pprof".to_string(),
+      default_seconds: 30,
+      max_seconds: 60,
+    }
+  }
+
+  /// Path prefix of the endpoints (default `/debug/pprof`).
+  pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+    self.prefix = prefix.into().trim_end_matches('/').to_string();
+    self
+  }
+
+  /// CPU sampling time when `seconds` is omitted (default 30).
+  pub fn default_seconds(mut self, secs: u64) -> Self {
+    self.default_seconds = secs;
+    self
+  }
+
+  /// Upper bound for `seconds` (default 60); longer requests are clamped.
+  pub fn max_seconds(mut self, secs: u64) -> Self {
+    self.max_seconds = secs;
+    self
+  }
+}
+
+fn text_response(status: StatusCode, body: &'static str) -> Response {
+  http::Response::builder()
+    .status(status)
+    .body(TakoBody::from(body))
+    .expect("valid profiling response")
+}
+
+fn query_param<'a>(req: &'a Request, name: &str) -> Option<&'a str> {
+  req
+    .uri()
+    .query()?
+    .split('&')
+    .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+}
+
+fn authorized(req: &Request, token: &str) -> bool {
+  req
+    .headers()
+    .get(AUTHORIZATION)
+    .and_then(|v| v.to_str().ok())
+    .and_then(|v| v.strip_prefix("Bearer "))
+    .is_some_and(|given| {
+      !token.is_empty() && bool::from(given.trim().as_bytes().ct_eq(token.as_bytes()))
+    })
+}
+
+/// Clears the busy flag when the profile finishes or the request is dropped.
+struct BusyGuard(Arc<AtomicBool>);
+
+impl Drop for BusyGuard {
+  fn drop(&mut self) {
+    self.0.store(false, Ordering::Release);
+  }
+}
+
+impl IntoMiddleware for Profiling {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    let profiler = self.profiler;
+    let token = Arc::new(self.token);
+    let cpu_path = Arc::new(format!("{}/profile", self.prefix));
+    let heap_path = Arc::new(format!("{}/heap", self.prefix));
+    let default_seconds = self.default_seconds;
+    let max_seconds = self.max_seconds;
+    let busy = Arc::new(AtomicBool::new(false));
+
+    move |req: Request, next: Next| {
+      let profiler = profiler.clone();
+      let token = token.clone();
+      let cpu_path = cpu_path.clone();
+      let heap_path = heap_path.clone();
+      let busy = busy.clone();
+
+      Box::pin(async move {
+        let path = req.uri().path();
+        let is_cpu = path == cpu_path.as_str();
+        if !is_cpu && path != heap_path.as_str() {
+          return next.run(req).await;
+        }
+        if req.method() != Method::GET {
+          return text_response(StatusCode::METHOD_NOT_ALLOWED, "use GET");
+        }
+        if !authorized(&req, &token) {
+          return text_response(StatusCode::UNAUTHORIZED, "profiling token required");
+        }
+
+        let format = match query_param(&req, "format") {
+          None | Some("pprof") => ProfileFormat::Pprof,
+          Some("flamegraph") => ProfileFormat::Flamegraph,
+          Some(_) => {
+            return text_response(
+              StatusCode::BAD_REQUEST,
+              "format must be pprof or flamegraph",
+            );
+          }
+        };
+        let seconds = match query_param(&req, "seconds").map(str::parse::<u64>) {
+          None => default_seconds,
+          Some(Ok(n)) if n > 0 => n,
+          Some(_) => {
+            return text_response(
+              StatusCode::BAD_REQUEST,
+              "seconds must be a positive integer",
+            );
+          }
+        };
+
+        if busy.swap(true, Ordering::AcqRel) {
+          return text_response(StatusCode::CONFLICT, "a profile is already running");
+        }
+        let _guard = BusyGuard(busy);
+
+        let result = if is_cpu {
+          let duration = Duration::from_secs(seconds.min(max_seconds));
+          profiler.cpu(duration, format).await
+        } else {
+          profiler.heap(format).await
+        };
+        match result {
+          Ok(profile) => {
+            let mut resp = http::Response::new(TakoBody::from(profile.body));
+            let headers = resp.headers_mut();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(profile.content_type));
+            if let Ok(v) =
+              HeaderValue::from_str(&format!("attachment; filename=\"{}\"", profile.file_name))
+            {
+              headers.insert(CONTENT_DISPOSITION, v);
+            }
+            resp
+          }
+          Err(ProfileError::Unsupported) => {
+            text_response(StatusCode::NOT_IMPLEMENTED, "profile kind not supported")
+          }
+          Err(e) => {
+            tracing::error!(error = %e, "profiling request failed");
+            text_response(StatusCode::INTERNAL_SERVER_ERROR, "profiling failed")
+          }
+        }
+      })
+    }
+  }
+}
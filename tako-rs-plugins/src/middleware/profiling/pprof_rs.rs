@@ -0,0 +1,236 @@
+//! [`Profiler`] backed by `pprof-rs`, with jemalloc heap dumps.
+
+use std::io::Write;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use pprof::protos::Message;
+
+use super::Profile;
+use super::ProfileError;
+use super::ProfileFormat;
+use super::Profiler;
+
+/// Libraries skipped while unwinding; sampling inside them can deadlock.
+const BLOCKLIST: &[&str] = &["libc", "libgcc", "pthread", "vdso"];
+
+/// Samples the whole process with `pprof-rs` (`SIGPROF`).
+///
+/// With the `jemalloc` feature on Linux, the heap endpoint dumps jemalloc's
+/// sampled allocations. jemalloc only records them when started with
+/// profiling on, e.g. `_RJEM_MALLOC_CONF=prof:true,prof_active:true` in the
+/// environment; otherwise the heap endpoint fails with a hint in the log.
+/// Without the feature it answers `501`.
+///
+/// Sampling, symbolization, and encoding run on a dedicated thread, so the
+/// profiler works under any async runtime and never blocks a worker.
+#[derive(Debug, Clone)]
+pub struct PprofProfiler {
+  frequency: i32,
+}
+
+impl PprofProfiler {
+  /// A profiler sampling at 99 Hz.
+  pub fn new() -> Self {
+    Self { frequency: 99 }
+  }
+
+  /// Samples per second (default 99).
+  pub fn frequency(mut self, hz: i32) -> Self {
+    self.frequency = hz;
+    self
+  }
+}
+
+impl Default for PprofProfiler {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[async_trait]
+impl Profiler for PprofProfiler {
+  async fn cpu(&self, duration: Duration, format: ProfileFormat) -> Result<Profile, ProfileError> {
+    let frequency = self.frequency;
+    off_thread("tako-cpu-profile", move || {
+      let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(frequency)
+        .blocklist(BLOCKLIST)
+        .build()
+        .map_err(ProfileError::failed)?;
+      std::thread::sleep(duration);
+      let report = guard.report().build().map_err(ProfileError::failed)?;
+      drop(guard);
+
+      match format {
+        ProfileFormat::Flamegraph => {
+          let mut svg = Vec::new();
+          report.flamegraph(&mut svg).map_err(ProfileError::failed)?;
+          Ok(Profile::flamegraph(svg))
+        }
+        ProfileFormat::Pprof => {
+          let proto = report.pprof().map_err(ProfileError::failed)?;
+          let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+          gz.write_all(&proto.encode_to_vec())
+            .map_err(ProfileError::failed)?;
+          Ok(Profile::pprof(gz.finish().map_err(ProfileError::failed)?))
+        }
+      }
+    })
+    .await
+  }
+
+  #[cfg(all(feature = "jemalloc", target_os = "linux"))]
+  async fn heap(&self, format: ProfileFormat) -> Result<Profile, ProfileError> {
+    off_thread("tako-heap-profile", move || {
+      let Some(ctl) = jemalloc_pprof::PROF_CTL.as_ref() else {
+        return Err(ProfileError::Failed(
+          "jemalloc was started without `prof:true` (set `_RJEM_MALLOC_CONF`)".to_string(),
+        ));
+      };
+      let mut ctl = ctl.blocking_lock();
+      if !ctl.activated() {
+        return Err(ProfileError::Failed(
+          "jemalloc heap profiling is inactive (`prof_active:false`)".to_string(),
+        ));
+      }
+      match format {
+        // Already gzipped.
+        ProfileFormat::Pprof => ctl
+          .dump_pprof()
+          .map(Profile::pprof)
+          .map_err(ProfileError::failed),
+        ProfileFormat::Flamegraph => ctl
+          .dump_flamegraph()
+          .map(Profile::flamegraph)
+          .map_err(ProfileError::failed),
+      }
+    })
+    .await
+  }
+}
+
+/// Runs `f` on its own thread and awaits the result without touching the
+/// runtime.
+async fn off_thread<F>(name: &str, f: F) -> Result<Profile, ProfileError>
+where
+  F: FnOnce() -> Result<Profile, ProfileError> + Send + 'static,
+{
+  let (tx, rx) = tokio::sync::oneshot::channel();
+  std::thread::Builder::new()
+    .name(name.to_string())
+    .spawn(move || {
+      let _ = tx.send(f());
+    })
+    .map_err(ProfileError::failed)?;
+  rx.await
+    .map_err(|_| ProfileError::Failed("profiler thread panicked".to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+  use std::hint::black_box;
+  use std::io::Read;
+  use std::sync::Arc;
+  use std::sync::atomic::AtomicBool;
+  use std::sync::atomic::Ordering;
+
+  use flate2::read::GzDecoder;
+
+  use super::*;
+
+  #[cfg(all(feature = "jemalloc", target_os = "linux"))]
+  #[global_allocator]
+  static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+  /// Turns jemalloc's sampling profiler on for this test binary.
+  #[cfg(all(feature = "jemalloc", target_os = "linux"))]
+  #[allow(non_upper_case_globals)]
+  #[unsafe(export_name = "_rjem_malloc_conf")]
+  static malloc_conf: &[u8] = b"prof:true,prof_active:true,lg_prof_sample:10\0";
+
+  fn decode(gzipped: &[u8]) -> pprof::protos::Profile {
+    let mut raw = Vec::new();
+    GzDecoder::new(gzipped).read_to_end(&mut raw).unwrap();
+    pprof::protos::Profile::decode(raw.as_slice()).unwrap()
+  }
+
+  #[inline(never)]
+  fn burn_cpu(stop: &AtomicBool) -> u64 {
+    let mut x = 0u64;
+    while !stop.load(Ordering::Relaxed) {
+      for i in 0..10_000u64 {
+        x = black_box(x.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(i));
+      }
+    }
+    x
+  }
+
+  // One test for both formats: `pprof-rs` allows a single profiler per process.
+  #[tokio::test]
+  async fn cpu_profiles_sample_a_busy_thread() {
+    let stop = Arc::new(AtomicBool::new(false));
+    let burner = {
+      let stop = stop.clone();
+      std::thread::spawn(move || burn_cpu(&stop))
+    };
+    let profiler = PprofProfiler::new().frequency(199);
+
+    let profile = profiler
+      .cpu(Duration::from_millis(700), ProfileFormat::Pprof)
+      .await
+      .unwrap();
+    assert_eq!(profile.file_name, "profile.pb.gz");
+    let proto = decode(&profile.body);
+    assert!(proto.sample.iter().map(|s| s.value[0]).sum::<i64>() > 10);
+    assert!(
+      proto.string_table.iter().any(|s| s.contains("burn_cpu")),
+      "busy function missing from the profile"
+    );
+
+    let profile = profiler
+      .cpu(Duration::from_millis(700), ProfileFormat::Flamegraph)
+      .await
+      .unwrap();
+    assert_eq!(profile.content_type, "image/svg+xml");
+    let svg = std::str::from_utf8(&profile.body).unwrap();
+    assert!(svg.contains("<svg"));
+    assert!(svg.contains("burn_cpu"));
+
+    stop.store(true, Ordering::Relaxed);
+    burner.join().unwrap();
+  }
+
+  #[cfg(all(feature = "jemalloc", target_os = "linux"))]
+  #[tokio::test]
+  async fn heap_profiles_dump_live_allocations() {
+    let live: Vec<Vec<u8>> = (0..64).map(|_| vec![7u8; 64 * 1024]).collect();
+
+    let profile = PprofProfiler::new()
+      .heap(ProfileFormat::Pprof)
+      .await
+      .unwrap();
+    let proto = decode(&profile.body);
+    let in_use: i64 = proto.sample.iter().map(|s| s.value[0]).sum();
+    assert!(in_use >= 3 * 1024 * 1024, "only {in_use} bytes sampled");
+
+    let profile = PprofProfiler::new()
+      .heap(ProfileFormat::Flamegraph)
+      .await
+      .unwrap();
+    assert!(std::str::from_utf8(&profile.body).unwrap().contains("<svg"));
+    drop(black_box(live));
+  }
+
+  #[cfg(not(all(feature = "jemalloc", target_os = "linux")))]
+  #[tokio::test]
+  async fn heap_is_unsupported_without_jemalloc() {
+    let err = PprofProfiler::new()
+      .heap(ProfileFormat::Pprof)
+      .await
+      .unwrap_err();
+    assert!(matches!(err, ProfileError::Unsupported));
+  }
+}
@@ -0,0 +1,346 @@
+//! Declarative header / query / content-type validation middleware.
+//!
+//! For routes that read the raw [`Request`] instead of typed extractors,
+//! [`RequestSchema`] declares what the request must carry and rejects
+//! anything else before the handler runs. Every violation is collected, so a
+//! client fixing its request sees all problems at once:
+//!
+//! ```rust,ignore
+//! let schema = RequestSchema::new()
+//!   .header(Field::required("x-tenant-id"))
+//!   .query(Field::required("page").integer())
+//!   .query(Field::optional("order").one_of(["asc", "desc"]))
+//!   .content_type("application/json");
+//! router.post("/reports", handler).middleware(schema.into_middleware());
+//! ```
+//!
+//! Failures are answered with `400 Bad Request` and an
+//! `application/problem+json` body whose `errors` array lists each violation.
+//! Body-less requests (no `Content-Type` and no declared length) skip the
+//! content-type check unless [`RequestSchema::require_body`] is set.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use http::HeaderName;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::CONTENT_LENGTH;
+use http::header::CONTENT_TYPE;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// Predicate used by [`Field::matches`].
+pub type FieldPredicate = Arc<dyn Fn(&str) -> bool + Send + Sync + 'static>;
+
+/// Accepted shape of a header or query value.
+#[derive(Clone)]
+pub enum FieldKind {
+  /// Any value.
+  Any,
+  /// A base-10 integer (`i64`).
+  Integer,
+  /// A floating-point number.
+  Number,
+  /// `true` or `false`.
+  Boolean,
+  /// One of a fixed set of values (case-sensitive).
+  OneOf(Vec<String>),
+  /// Caller-defined check.
+  Custom(FieldPredicate),
+}
+
+impl FieldKind {
+  fn accepts(&self, value: &str) -> bool {
+    match self {
+      Self::Any => true,
+      Self::Integer => value.parse::<i64>().is_ok(),
+      Self::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+      Self::Boolean => matches!(value, "true" | "false"),
+      Self::OneOf(allowed) => allowed.iter().any(|a| a == value),
+      Self::Custom(f) => f(value),
+    }
+  }
+
+  fn describe(&self) -> String {
+    match self {
+      Self::Any => "any value".into(),
+      Self::Integer => "an integer".into(),
+      Self::Number => "a number".into(),
+      Self::Boolean => "`true` or `false`".into(),
+      Self::OneOf(allowed) => format!("one of {}", allowed.join(", ")),
+      Self::Custom(_) => "a valid value".into(),
+    }
+  }
+}
+
+/// A single header or query parameter declaration.
+#[derive(Clone)]
+pub struct Field {
+  name: String,
+  required: bool,
+  kind: FieldKind,
+  max_len: Option<usize>,
+}
+
+impl Field {
+  /// A field that must be present.
+  pub fn required(name: impl Into<String>) -> Self {
+    Self {
+      name: name.into(),
+      required: true,
+      kind: FieldKind::Any,
+      max_len: None,
+    }
+  }
+
+  /// A field that is checked only when present.
+  pub fn optional(name: impl Into<String>) -> Self {
+    Self {
+      required: false,
+      ..Self::required(name)
+    }
+  }
+
+  /// Value must parse as an integer.
+  pub fn integer(mut self) -> Self {
+    self.kind = FieldKind::Integer;
+    self
+  }
+
+  /// Value must parse as a number.
+  pub fn number(mut self) -> Self {
+    self.kind = FieldKind::Number;
+    self
+  }
+
+  /// Value must be `true` or `false`.
+  pub fn boolean(mut self) -> Self {
+    self.kind = FieldKind::Boolean;
+    self
+  }
+
+  /// Value must be one of `values`.
+  pub fn one_of<I, S>(mut self, values: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    self.kind = FieldKind::OneOf(values.into_iter().map(Into::into).collect());
+    self
+  }
+
+  /// Value must satisfy `f`.
+  pub fn matches<F>(mut self, f: F) -> Self
+  where
+    F: Fn(&str) -> bool + Send + Sync + 'static,
+  {
+    self.kind = FieldKind::Custom(Arc::new(f));
+    self
+  }
+
+  /// Value must be at most `n` bytes long.
+  pub fn max_len(mut self, n: usize) -> Self {
+    self.max_len = Some(n);
+    self
+  }
+
+  fn check(&self, what: &str, value: Option<&str>, errors: &mut Vec<String>) {
+    let Some(value) = value else {
+      if self.required {
+        errors.push(format!("missing required {what} `{}`", self.name));
+      }
+      return;
+    };
+    if let Some(max) = self.max_len
+      && value.len() > max
+    {
+      errors.push(format!("{what} `{}` exceeds {max} bytes", self.name));
+      return;
+    }
+    if !self.kind.accepts(value) {
+      errors.push(format!(
+        "{what} `{}` must be {}",
+        self.name,
+        self.kind.describe()
+      ));
+    }
+  }
+}
+
+/// Declarative request validation middleware.
+#[derive(Clone, Default)]
+pub struct RequestSchema {
+  headers: Vec<(HeaderName, Field)>,
+  query: Vec<Field>,
+  content_types: Vec<String>,
+  require_body: bool,
+}
+
+impl RequestSchema {
+  /// An empty schema that accepts every request.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Declares a header.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the field name is not a valid header name.
+  pub fn header(mut self, field: Field) -> Self {
+    let name = HeaderName::try_from(field.name.as_str()).expect("valid header name");
+    self.headers.push((name, field));
+    self
+  }
+
+  /// Declares a query parameter. Repeated parameters are checked one by one.
+  pub fn query(mut self, field: Field) -> Self {
+    self.query.push(field);
+    self
+  }
+
+  /// Adds an accepted media type (`application/json`, `text/*`). Parameters
+  /// such as `charset` are ignored when matching.
+  pub fn content_type(mut self, media_type: impl Into<String>) -> Self {
+    self
+      .content_types
+      .push(media_type.into().to_ascii_lowercase());
+    self
+  }
+
+  /// Rejects requests without a body-describing `Content-Type`.
+  pub fn require_body(mut self, required: bool) -> Self {
+    self.require_body = required;
+    self
+  }
+
+  fn validate(&self, req: &Request) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for (name, field) in &self.headers {
+      let values = req.headers().get_all(name);
+      let mut any = false;
+      for value in values {
+        any = true;
+        match value.to_str() {
+          Ok(v) => field.check("header", Some(v.trim()), &mut errors),
+          Err(_) => errors.push(format!("header `{name}` is not valid text")),
+        }
+      }
+      if !any {
+        field.check("header", None, &mut errors);
+      }
+    }
+
+    if !self.query.is_empty() {
+      let pairs: Vec<(String, String)> = req
+        .uri()
+        .query()
+        .map(|q| {
+          url::form_urlencoded::parse(q.as_bytes())
+            .into_owned()
+            .collect()
+        })
+        .unwrap_or_default();
+      for field in &self.query {
+        let mut any = false;
+        for (_, v) in pairs.iter().filter(|(k, _)| *k == field.name) {
+          any = true;
+          field.check("query parameter", Some(v), &mut errors);
+        }
+        if !any {
+          field.check("query parameter", None, &mut errors);
+        }
+      }
+    }
+
+    if !self.content_types.is_empty() {
+      let has_body = req.headers().contains_key(CONTENT_TYPE)
+        || req
+          .headers()
+          .get(CONTENT_LENGTH)
+          .and_then(|v| v.to_str().ok())
+          .is_some_and(|v| v.trim() != "0");
+      if has_body || self.require_body {
+        let actual = req
+          .headers()
+          .get(CONTENT_TYPE)
+          .and_then(|v| v.to_str().ok())
+          .map(|v| {
+            v.split(';')
+              .next()
+              .unwrap_or("")
+              .trim()
+              .to_ascii_lowercase()
+          });
+        let ok = actual
+          .as_deref()
+          .is_some_and(|a| self.content_types.iter().any(|e| media_type_matches(e, a)));
+        if !ok {
+          errors.push(format!(
+            "content type must be one of {}",
+            self.content_types.join(", ")
+          ));
+        }
+      }
+    }
+
+    errors
+  }
+}
+
+fn media_type_matches(expected: &str, actual: &str) -> bool {
+  match expected.strip_suffix("/*") {
+    Some(top) => actual.split('/').next() == Some(top),
+    None => expected == actual,
+  }
+}
+
+fn problem(errors: &[String]) -> Response {
+  let body = serde_json::json!({
+    "type": "about:blank",
+    "title": "Bad Request",
+    "status": 400,
+    "errors": errors,
+  });
+  let mut resp = http::Response::builder()
+    .status(StatusCode::BAD_REQUEST)
+    .body(TakoBody::from(
+      serde_json::to_vec(&body).unwrap_or_default(),
+    ))
+    .expect("valid problem response");
+  resp.headers_mut().insert(
+    CONTENT_TYPE,
+    HeaderValue::from_static("application/problem+json"),
+  );
+  resp
+}
+
+impl IntoMiddleware for RequestSchema {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    let schema = Arc::new(self);
+
+    move |req: Request, next: Next| {
+      let schema = schema.clone();
+      Box::pin(async move {
+        let errors = schema.validate(&req);
+        if errors.is_empty() {
+          next.run(req).await
+        } else {
+          problem(&errors)
+        }
+      })
+    }
+  }
+}
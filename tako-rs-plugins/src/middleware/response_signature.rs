@@ -0,0 +1,339 @@
+//! Response signing with HTTP Message Signatures (RFC 9421).
+//!
+//! Buffers each response body, adds a `Content-Digest` header (RFC 9530,
+//! `sha-256`), and signs the status, the digest, and a configurable set of
+//! headers with the configured key. The result is emitted as the
+//! `Signature-Input` / `Signature` header pair so clients holding the shared
+//! secret or the public key can verify that the response came from this
+//! service and was not altered in transit, independently of TLS.
+//!
+//! Supported algorithms are `hmac-sha256` and `ed25519`. Covered headers that
+//! are absent from a given response are left out of that response's
+//! signature rather than signed as empty values, as RFC 9421 requires.
+//! Bodies larger than [`ResponseSignature::max_body_bytes`] cannot be signed
+//! and are replaced with `500 Internal Server Error` — an unsigned response
+//! would be indistinguishable from a forged one.
+//!
+//! ```rust,ignore
+//! let signer = ResponseSignature::new(SigningKey::ed25519_from_pkcs8("api-2026", &der)?)
+//!   .cover_header(http::header::CACHE_CONTROL);
+//! router.middleware(signer.into_middleware());
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use http::HeaderName;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use http_body_util::BodyExt;
+use ring::signature::Ed25519KeyPair;
+use ring::signature::KeyPair;
+use sha2::Digest;
+use sha2::Sha256;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+const CONTENT_DIGEST: HeaderName = HeaderName::from_static("content-digest");
+const SIGNATURE: HeaderName = HeaderName::from_static("signature");
+const SIGNATURE_INPUT: HeaderName = HeaderName::from_static("signature-input");
+
+enum KeyMaterial {
+  Hmac(ring::hmac::Key),
+  Ed25519(Ed25519KeyPair),
+}
+
+/// Key used to sign responses, identified to clients by its `keyid`.
+#[derive(Clone)]
+pub struct SigningKey {
+  key_id: String,
+  material: Arc<KeyMaterial>,
+}
+
+impl SigningKey {
+  /// Shared-secret key for the `hmac-sha256` algorithm.
+  pub fn hmac_sha256(key_id: impl Into<String>, secret: &[u8]) -> Self {
+    Self {
+      key_id: key_id.into(),
+      material: Arc::new(KeyMaterial::Hmac(ring::hmac::Key::new(
+        ring::hmac::HMAC_SHA256,
+        secret,
+      ))),
+    }
+  }
+
+  /// Ed25519 key from a PKCS#8 v1 or v2 DER document.
+  pub fn ed25519_from_pkcs8(key_id: impl Into<String>, der: &[u8]) -> anyhow::Result<Self> {
+    let pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(der)
+      .map_err(|e| anyhow::anyhow!("invalid Ed25519 PKCS#8 key: {e}"))?;
+    Ok(Self::ed25519(key_id, pair))
+  }
+
+  /// Ed25519 key from its 32-byte seed.
+  pub fn ed25519_from_seed(key_id: impl Into<String>, seed: &[u8]) -> anyhow::Result<Self> {
+    let pair = Ed25519KeyPair::from_seed_unchecked(seed)
+      .map_err(|e| anyhow::anyhow!("invalid Ed25519 seed: {e}"))?;
+    Ok(Self::ed25519(key_id, pair))
+  }
+
+  fn ed25519(key_id: impl Into<String>, pair: Ed25519KeyPair) -> Self {
+    Self {
+      key_id: key_id.into(),
+      material: Arc::new(KeyMaterial::Ed25519(pair)),
+    }
+  }
+
+  /// The `keyid` parameter advertised in `Signature-Input`.
+  pub fn key_id(&self) -> &str {
+    &self.key_id
+  }
+
+  /// RFC 9421 algorithm name.
+  pub fn algorithm(&self) -> &'static str {
+    match *self.material {
+      KeyMaterial::Hmac(_) => "hmac-sha256",
+      KeyMaterial::Ed25519(_) => "ed25519",
+    }
+  }
+
+  /// Raw Ed25519 public key to hand to clients; `None` for HMAC keys.
+  pub fn public_key(&self) -> Option<&[u8]> {
+    match &*self.material {
+      KeyMaterial::Hmac(_) => None,
+      KeyMaterial::Ed25519(pair) => Some(pair.public_key().as_ref()),
+    }
+  }
+
+  fn sign(&self, base: &[u8]) -> Vec<u8> {
+    match &*self.material {
+      KeyMaterial::Hmac(key) => ring::hmac::sign(key, base).as_ref().to_vec(),
+      KeyMaterial::Ed25519(pair) => pair.sign(base).as_ref().to_vec(),
+    }
+  }
+
+  fn verify(&self, base: &[u8], signature: &[u8]) -> bool {
+    match &*self.material {
+      KeyMaterial::Hmac(key) => ring::hmac::verify(key, base, signature).is_ok(),
+      KeyMaterial::Ed25519(pair) => ring::signature::UnparsedPublicKey::new(
+        &ring::signature::ED25519,
+        pair.public_key().as_ref(),
+      )
+      .verify(base, signature)
+      .is_ok(),
+    }
+  }
+}
+
+/// Response signing middleware.
+pub struct ResponseSignature {
+  key: SigningKey,
+  label: String,
+  headers: Vec<HeaderName>,
+  max_body_bytes: usize,
+}
+
+impl ResponseSignature {
+  /// Signs `@status`, `content-type`, and `content-digest` with `key`.
+  pub fn new(key: SigningKey) -> Self {
+    Self {
+      key,
+      label: "sig1".into(),
+      headers: vec![CONTENT_TYPE],
+      max_body_bytes: 1024 * 1024,
+    }
+  }
+
+  /// Adds a response header to the covered components.
+  pub fn cover_header(mut self, name: HeaderName) -> Self {
+    if !self.headers.contains(&name) {
+      self.headers.push(name);
+    }
+    self
+  }
+
+  /// Signature label used in `Signature-Input` / `Signature` (default `sig1`).
+  pub fn label(mut self, label: impl Into<String>) -> Self {
+    self.label = label.into();
+    self
+  }
+
+  /// Largest response body that can be signed (default 1 MiB).
+  pub fn max_body_bytes(mut self, n: usize) -> Self {
+    self.max_body_bytes = n;
+    self
+  }
+
+  /// Checks a signed response against this middleware's key.
+  ///
+  /// Recomputes the body digest and the signature base from the components
+  /// listed in `Signature-Input`. Intended for tests and for in-house clients
+  /// sharing the configuration.
+  pub fn verify(&self, parts: &http::response::Parts, body: &[u8]) -> bool {
+    let Some(digest) = parts.headers.get(CONTENT_DIGEST) else {
+      return false;
+    };
+    if digest.as_bytes() != content_digest(body).as_bytes() {
+      return false;
+    }
+    let prefix = format!("{}=", self.label);
+    let Some(params) =
+      header_str(&parts.headers, &SIGNATURE_INPUT).and_then(|v| v.strip_prefix(prefix.as_str()))
+    else {
+      return false;
+    };
+    let Some(signature) = header_str(&parts.headers, &SIGNATURE)
+      .and_then(|v| v.strip_prefix(prefix.as_str()))
+      .and_then(|v| v.strip_prefix(':'))
+      .and_then(|v| v.strip_suffix(':'))
+      .and_then(|v| STANDARD.decode(v).ok())
+    else {
+      return false;
+    };
+    let Some(list) = params
+      .strip_prefix('(')
+      .and_then(|p| p.split_once(')'))
+      .map(|(list, _)| list)
+    else {
+      return false;
+    };
+    let mut components = Vec::new();
+    for id in list.split_whitespace() {
+      let name = id.trim_matches('"');
+      let value = if name == "@status" {
+        parts.status.as_u16().to_string()
+      } else {
+        let value = HeaderName::from_bytes(name.as_bytes())
+          .ok()
+          .and_then(|n| header_str(&parts.headers, &n));
+        match value {
+          Some(v) => v.to_string(),
+          None => return false,
+        }
+      };
+      components.push((name.to_string(), value));
+    }
+    let base = signature_base(&components, params);
+    self.key.verify(base.as_bytes(), &signature)
+  }
+}
+
+fn header_str<'a>(headers: &'a http::HeaderMap, name: &HeaderName) -> Option<&'a str> {
+  headers
+    .get(name)
+    .and_then(|v| v.to_str().ok())
+    .map(str::trim)
+}
+
+fn content_digest(body: &[u8]) -> String {
+  format!("sha-256=:{}:", STANDARD.encode(Sha256::digest(body)))
+}
+
+/// RFC 9421 §2.5 signature base: one `"<id>": <value>` line per component,
+/// then the `@signature-params` line, joined by `\n` with no trailing newline.
+fn signature_base(components: &[(String, String)], params: &str) -> String {
+  let mut base = String::new();
+  for (name, value) in components {
+    base.push_str(&format!("\"{name}\": {value}\n"));
+  }
+  base.push_str(&format!("\"@signature-params\": {params}"));
+  base
+}
+
+struct Signer {
+  key: SigningKey,
+  label: String,
+  headers: Vec<HeaderName>,
+  max_body_bytes: usize,
+}
+
+impl Signer {
+  fn sign(&self, parts: &mut http::response::Parts, body: &[u8]) {
+    let digest = content_digest(body);
+    parts.headers.insert(
+      CONTENT_DIGEST,
+      HeaderValue::from_str(&digest).expect("base64 digest is a valid header value"),
+    );
+
+    let mut components = vec![("@status".to_string(), parts.status.as_u16().to_string())];
+    for name in &self.headers {
+      if let Some(value) = parts.headers.get(name).and_then(|v| v.to_str().ok()) {
+        components.push((name.as_str().to_string(), value.trim().to_string()));
+      }
+    }
+    components.push((CONTENT_DIGEST.as_str().to_string(), digest));
+
+    let created = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |d| d.as_secs());
+    let list = components
+      .iter()
+      .map(|(name, _)| format!("\"{name}\""))
+      .collect::<Vec<_>>()
+      .join(" ");
+    let params = format!(
+      "({list});created={created};keyid=\"{}\";alg=\"{}\"",
+      self.key.key_id,
+      self.key.algorithm()
+    );
+    let signature = self
+      .key
+      .sign(signature_base(&components, &params).as_bytes());
+
+    if let Ok(v) = HeaderValue::from_str(&format!("{}={params}", self.label)) {
+      parts.headers.insert(SIGNATURE_INPUT, v);
+    }
+    if let Ok(v) =
+      HeaderValue::from_str(&format!("{}=:{}:", self.label, STANDARD.encode(signature)))
+    {
+      parts.headers.insert(SIGNATURE, v);
+    }
+  }
+}
+
+impl IntoMiddleware for ResponseSignature {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    let signer = Arc::new(Signer {
+      key: self.key,
+      label: self.label,
+      headers: self.headers,
+      max_body_bytes: self.max_body_bytes,
+    });
+
+    move |req: Request, next: Next| {
+      let signer = signer.clone();
+      Box::pin(async move {
+        let resp = next.run(req).await;
+        let (mut parts, body) = resp.into_parts();
+        let collected = match http_body_util::Limited::new(body, signer.max_body_bytes)
+          .collect()
+          .await
+        {
+          Ok(c) => c.to_bytes(),
+          Err(_) => {
+            return http::Response::builder()
+              .status(StatusCode::INTERNAL_SERVER_ERROR)
+              .body(TakoBody::empty())
+              .expect("valid 500");
+          }
+        };
+        signer.sign(&mut parts, &collected);
+        http::Response::from_parts(parts, TakoBody::from(collected))
+      })
+    }
+  }
+}
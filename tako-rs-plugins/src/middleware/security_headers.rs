@@ -19,51 +19,130 @@
 //! ignore the header and OWASP recommends removing it. CSP is the
 //! authoritative replacement.
 //!
-//! Per-request CSP nonces are exposed as a [`CspNonce`] extension so handlers
-//! can interpolate them into inline `<script>` / `<style>` blocks. The header
-//! emitted to the client substitutes the nonce into a template string.
+//! Per-request CSP nonces are exposed as a [`CspNonce`] extension (and
+//! extractor) so handlers and the templates they render can interpolate them
+//! into inline `<script>` / `<style>` blocks. The header emitted to the client
+//! substitutes the nonce into a template string. A nonce already present in
+//! the request extensions (inserted by an outer layer) is reused rather than
+//! replaced, so every layer in the chain agrees on one value.
 
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
 use http::HeaderValue;
+use http::request::Parts;
+use tako_rs_core::extractors::FromRequest;
+use tako_rs_core::extractors::FromRequestParts;
 use tako_rs_core::middleware::IntoMiddleware;
 use tako_rs_core::middleware::Next;
+use tako_rs_core::responder::Responder;
 use tako_rs_core::types::Request;
 use tako_rs_core::types::Response;
 
 /// Per-request CSP nonce inserted into request extensions when
 /// [`SecurityHeaders::csp_with_nonce`] is configured.
 ///
-/// Handlers can substitute the value into inline scripts:
+/// Handlers can extract it directly and hand it to their templates:
 ///
 /// ```rust,ignore
-/// let nonce = req.extensions().get::<CspNonce>().expect("CSP middleware mounted").0.clone();
-/// let html = format!(r#"<script nonce="{nonce}">…</script>"#);
+/// async fn page(nonce: CspNonce) -> impl Responder {
+///   Html(format!("<script {}>…</script>", nonce.attr()))
+/// }
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CspNonce(pub String);
 
+impl CspNonce {
+  /// Generates a fresh nonce: 18 random bytes, base64-encoded.
+  pub fn generate() -> Self {
+    Self(rand_nonce())
+  }
+
+  /// The nonce value, as it appears in the CSP header.
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+
+  /// The HTML attribute form, `nonce="…"`, ready to splice into a tag.
+  pub fn attr(&self) -> String {
+    format!("nonce=\"{}\"", self.0)
+  }
+}
+
+impl std::fmt::Display for CspNonce {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+/// Rejection returned by the [`CspNonce`] extractor when no nonce-based CSP is
+/// mounted in front of the handler.
+#[derive(Debug)]
+pub struct MissingCspNonce;
+
+impl Responder for MissingCspNonce {
+  fn into_response(self) -> tako_rs_core::types::Response {
+    (
+      http::StatusCode::INTERNAL_SERVER_ERROR,
+      "no CSP nonce: mount SecurityHeaders::csp_with_nonce",
+    )
+      .into_response()
+  }
+}
+
+impl<'a> FromRequest<'a> for CspNonce {
+  type Error = MissingCspNonce;
+
+  fn from_request(
+    req: &'a mut Request,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    futures_util::future::ready(
+      req
+        .extensions()
+        .get::<CspNonce>()
+        .cloned()
+        .ok_or(MissingCspNonce),
+    )
+  }
+}
+
+impl<'a> FromRequestParts<'a> for CspNonce {
+  type Error = MissingCspNonce;
+
+  fn from_request_parts(
+    parts: &'a mut Parts,
+  ) -> impl core::future::Future<Output = core::result::Result<Self, Self::Error>> + Send + 'a {
+    futures_util::future::ready(
+      parts
+        .extensions
+        .get::<CspNonce>()
+        .cloned()
+        .ok_or(MissingCspNonce),
+    )
+  }
+}
+
 #[derive(Clone)]
-enum CspMode {
+pub(crate) enum CspMode {
   Static(HeaderValue),
   WithNonce { template: String, header: bool },
 }
 
 /// Security headers middleware configuration.
+#[derive(Clone)]
 pub struct SecurityHeaders {
-  frame_options: HeaderValue,
-  hsts: bool,
-  hsts_max_age: u64,
-  hsts_include_subdomains: bool,
-  hsts_preload: bool,
-  referrer_policy: HeaderValue,
-  csp: Option<CspMode>,
-  coop: Option<HeaderValue>,
-  coep: Option<HeaderValue>,
-  corp: Option<HeaderValue>,
-  permissions_policy: Option<HeaderValue>,
+  pub(crate) frame_options: HeaderValue,
+  pub(crate) hsts: bool,
+  pub(crate) hsts_max_age: u64,
+  pub(crate) hsts_include_subdomains: bool,
+  pub(crate) hsts_preload: bool,
+  pub(crate) referrer_policy: HeaderValue,
+  pub(crate) csp: Option<CspMode>,
+  pub(crate) coop: Option<HeaderValue>,
+  pub(crate) coep: Option<HeaderValue>,
+  pub(crate) corp: Option<HeaderValue>,
+  pub(crate) permissions_policy: Option<HeaderValue>,
 }
 
 impl Default for SecurityHeaders {
@@ -237,9 +316,13 @@ impl IntoMiddleware for SecurityHeaders {
           None => None,
           Some(CspMode::Static(v)) => Some((v.clone(), false)),
           Some(CspMode::WithNonce { template, header }) => {
-            let nonce = rand_nonce();
-            let value = template.replace("{nonce}", &nonce);
-            req.extensions_mut().insert(CspNonce(nonce));
+            let nonce = req
+              .extensions()
+              .get::<CspNonce>()
+              .cloned()
+              .unwrap_or_else(CspNonce::generate);
+            let value = template.replace("{nonce}", nonce.as_str());
+            req.extensions_mut().insert(nonce);
             HeaderValue::from_str(&value).ok().map(|hv| (hv, *header))
           }
         };
@@ -0,0 +1,325 @@
+//! Expiring signed URLs for protected downloads.
+//!
+//! [`SignedUrls`] mints links such as
+//! `/files/report.pdf?user=alice&expires=1767225600&sig=…` and, used as a
+//! middleware, refuses requests whose link was altered or has run out. The
+//! signature is HMAC-SHA256 over the path and the whole query string up to
+//! `sig`, so extra claims (a user id, a download name) travel with the link
+//! and cannot be changed or added to. No session or lookup is needed, which
+//! makes these links suitable for emails, `<img>` tags, and handing to a
+//! CDN.
+//!
+//! Rejected links answer `403 Forbidden`, expired ones `410 Gone`. On
+//! success the claims are inserted into the request extensions as
+//! [`SignedClaims`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use http::Method;
+//! use tako::middleware::IntoMiddleware;
+//! use tako::middleware::signed_url::SignedClaims;
+//! use tako::middleware::signed_url::SignedUrls;
+//! use tako::router::Router;
+//! use tako::types::Request;
+//!
+//! let urls = SignedUrls::new(b"a long random server secret".to_vec());
+//!
+//! // Hand this out; it stops working after an hour.
+//! let link = urls.sign_with("/files/report.pdf", &[("user", "alice")], Duration::from_secs(3600));
+//! assert!(link.starts_with("/files/report.pdf?user=alice&expires="));
+//!
+//! let mut router = Router::new();
+//! router
+//!   .route(Method::GET, "/files/{name}", |req: Request| async move {
+//!     let user = req.extensions().get::<SignedClaims>().and_then(|c| c.get("user"));
+//!     format!("for {}", user.unwrap_or("anyone"))
+//!   })
+//!   .middleware(urls.into_middleware());
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::Hmac;
+use hmac::Mac;
+use http::StatusCode;
+use sha2::Sha256;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Query parameter holding the expiry (Unix seconds).
+pub const EXPIRES_PARAM: &str = "expires";
+/// Query parameter holding the signature; always the last one.
+pub const SIGNATURE_PARAM: &str = "sig";
+
+/// Why a link was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignedUrlError {
+  /// No `sig` or `expires` parameter.
+  Missing,
+  /// The parameters are present but unreadable.
+  Malformed,
+  /// The signature does not match; the link was forged or altered.
+  BadSignature,
+  Expired,
+}
+
+impl fmt::Display for SignedUrlError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Missing => f.write_str("link is not signed"),
+      Self::Malformed => f.write_str("malformed signed link"),
+      Self::BadSignature => f.write_str("invalid link signature"),
+      Self::Expired => f.write_str("link has expired"),
+    }
+  }
+}
+
+impl std::error::Error for SignedUrlError {}
+
+impl SignedUrlError {
+  /// Status the middleware answers with: `410` for expired links, `403`
+  /// otherwise.
+  pub fn status(self) -> StatusCode {
+    match self {
+      Self::Expired => StatusCode::GONE,
+      _ => StatusCode::FORBIDDEN,
+    }
+  }
+}
+
+/// What a verified link carried.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignedClaims {
+  /// When the link stops working.
+  pub expires: u64,
+  /// The other signed query parameters, decoded and in link order.
+  pub claims: Vec<(String, String)>,
+}
+
+impl SignedClaims {
+  /// First value of the claim `name`.
+  pub fn get(&self, name: &str) -> Option<&str> {
+    self
+      .claims
+      .iter()
+      .find(|(k, _)| k == name)
+      .map(|(_, v)| v.as_str())
+  }
+}
+
+/// Signed-link issuer and verifying middleware.
+#[derive(Clone)]
+pub struct SignedUrls {
+  key: Arc<[u8]>,
+  ttl: Duration,
+}
+
+impl SignedUrls {
+  /// Creates an issuer signing with `secret`. Links live one hour unless
+  /// [`ttl`](Self::ttl) says otherwise.
+  pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+    Self {
+      key: secret.into().into(),
+      ttl: Duration::from_secs(60 * 60),
+    }
+  }
+
+  /// Default lifetime used by [`sign`](Self::sign).
+  pub fn ttl(mut self, ttl: Duration) -> Self {
+    self.ttl = ttl;
+    self
+  }
+
+  /// Signs `path` with the default lifetime.
+  ///
+  /// `path` is the request path as it appears on the wire (already
+  /// percent-encoded). It may carry a query string, which is then covered by
+  /// the signature too.
+  pub fn sign(&self, path: &str) -> String {
+    self.sign_with(path, &[], self.ttl)
+  }
+
+  /// Signs `path` with extra `claims`, valid for `ttl`. Claims named
+  /// `expires` or `sig` are skipped.
+  pub fn sign_with(&self, path: &str, claims: &[(&str, &str)], ttl: Duration) -> String {
+    self.sign_until(path, claims, SystemTime::now() + ttl)
+  }
+
+  /// Signs `path` with extra `claims`, valid until `expires`.
+  pub fn sign_until(&self, path: &str, claims: &[(&str, &str)], expires: SystemTime) -> String {
+    let mut unsigned = path.to_string();
+    let mut sep = if path.contains('?') { '&' } else { '?' };
+    for (k, v) in claims
+      .iter()
+      .filter(|(k, _)| *k != EXPIRES_PARAM && *k != SIGNATURE_PARAM)
+    {
+      unsigned.push(sep);
+      unsigned.push_str(&urlencoding::encode(k));
+      unsigned.push('=');
+      unsigned.push_str(&urlencoding::encode(v));
+      sep = '&';
+    }
+    unsigned.push(sep);
+    unsigned.push_str(EXPIRES_PARAM);
+    unsigned.push('=');
+    unsigned.push_str(&unix(expires).to_string());
+    let signature = URL_SAFE_NO_PAD.encode(self.mac(&unsigned).finalize().into_bytes());
+    format!("{unsigned}&{SIGNATURE_PARAM}={signature}")
+  }
+
+  fn mac(&self, unsigned: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+    mac.update(unsigned.as_bytes());
+    mac
+  }
+
+  /// Verifies a link given its `path` and raw `query`.
+  ///
+  /// # Errors
+  ///
+  /// Returns why the link is not acceptable.
+  pub fn verify(&self, path: &str, query: Option<&str>) -> Result<SignedClaims, SignedUrlError> {
+    let query = query.ok_or(SignedUrlError::Missing)?;
+    let prefix = format!("&{SIGNATURE_PARAM}=");
+    let (signed, signature) = query
+      .rsplit_once(prefix.as_str())
+      .ok_or(SignedUrlError::Missing)?;
+    if signature.contains('&') {
+      return Err(SignedUrlError::Malformed);
+    }
+    let signature = URL_SAFE_NO_PAD
+      .decode(signature)
+      .map_err(|_| SignedUrlError::Malformed)?;
+    self
+      .mac(&format!("{path}?{signed}"))
+      .verify_slice(&signature)
+      .map_err(|_| SignedUrlError::BadSignature)?;
+
+    let mut expires = None;
+    let mut claims = Vec::new();
+    for pair in signed.split('&') {
+      let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+      let k = urlencoding::decode(k).map_err(|_| SignedUrlError::Malformed)?;
+      let v = urlencoding::decode(v).map_err(|_| SignedUrlError::Malformed)?;
+      if k == EXPIRES_PARAM {
+        expires = Some(v.parse::<u64>().map_err(|_| SignedUrlError::Malformed)?);
+      } else {
+        claims.push((k.into_owned(), v.into_owned()));
+      }
+    }
+    let expires = expires.ok_or(SignedUrlError::Missing)?;
+    if expires <= unix(SystemTime::now()) {
+      return Err(SignedUrlError::Expired);
+    }
+    Ok(SignedClaims { expires, claims })
+  }
+}
+
+fn unix(t: SystemTime) -> u64 {
+  t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+impl IntoMiddleware for SignedUrls {
+  fn into_middleware(
+    self,
+  ) -> impl Fn(Request, Next) -> Pin<Box<dyn Future<Output = Response> + Send + 'static>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    move |mut req: Request, next: Next| {
+      let urls = self.clone();
+      Box::pin(async move {
+        match urls.verify(req.uri().path(), req.uri().query()) {
+          Ok(claims) => {
+            req.extensions_mut().insert(claims);
+            next.run(req).await
+          }
+          Err(e) => http::Response::builder()
+            .status(e.status())
+            .body(TakoBody::from(e.to_string()))
+            .expect("valid response"),
+        }
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn split(link: &str) -> (&str, Option<&str>) {
+    match link.split_once('?') {
+      Some((p, q)) => (p, Some(q)),
+      None => (link, None),
+    }
+  }
+
+  #[test]
+  fn round_trips_claims() {
+    let urls = SignedUrls::new(b"secret".to_vec());
+    let link = urls.sign_with(
+      "/files/a.pdf?v=2",
+      &[("user", "alice smith"), ("sig", "x")],
+      Duration::from_secs(60),
+    );
+    let (path, query) = split(&link);
+    let claims = urls.verify(path, query).unwrap();
+    assert_eq!(claims.get("v"), Some("2"));
+    assert_eq!(claims.get("user"), Some("alice smith"));
+    assert_eq!(claims.get("sig"), None);
+  }
+
+  #[test]
+  fn refuses_altered_and_expired_links() {
+    let urls = SignedUrls::new(b"secret".to_vec());
+    let link = urls.sign_with(
+      "/files/a.pdf",
+      &[("user", "alice")],
+      Duration::from_secs(60),
+    );
+    let (_, query) = split(&link);
+    assert_eq!(
+      urls.verify("/files/b.pdf", query),
+      Err(SignedUrlError::BadSignature)
+    );
+    let forged = query.unwrap().replace("alice", "mallory");
+    assert_eq!(
+      urls.verify("/files/a.pdf", Some(&forged)),
+      Err(SignedUrlError::BadSignature)
+    );
+    assert_eq!(
+      urls.verify("/files/a.pdf", Some(&format!("{}&extra=1", query.unwrap()))),
+      Err(SignedUrlError::Malformed)
+    );
+    assert_eq!(
+      urls.verify("/files/a.pdf", None),
+      Err(SignedUrlError::Missing)
+    );
+    assert_eq!(
+      SignedUrls::new(b"other".to_vec()).verify("/files/a.pdf", query),
+      Err(SignedUrlError::BadSignature)
+    );
+
+    let stale = urls.sign_until("/files/a.pdf", &[], UNIX_EPOCH + Duration::from_secs(10));
+    let (path, query) = split(&stale);
+    assert_eq!(urls.verify(path, query), Err(SignedUrlError::Expired));
+  }
+}
@@ -20,10 +20,31 @@
 //!
 //! Handlers and downstream middleware can read [`TraceContext`] from request
 //! extensions to forward the trace identifiers into outbound calls.
+//!
+//! For mixed environments, [`Traceparent::propagators`] also accepts Zipkin
+//! B3 (multi-header and single `b3` header) and Jaeger `uber-trace-id`
+//! contexts; the first configured format present on the request wins. W3C
+//! `baggage` (and Jaeger `uberctx-*` headers when Jaeger is enabled) is
+//! decoded into a [`Baggage`] extension. [`inject`] writes both back onto an
+//! outbound request in whichever formats the next hop expects:
+//!
+//! ```rust
+//! use tako::middleware::traceparent::Propagator;
+//! use tako::middleware::traceparent::inject;
+//! use tako::types::Request;
+//!
+//! async fn handler(req: Request) -> &'static str {
+//!   let mut outbound = http::Request::get("http://inventory/items").body(()).unwrap();
+//!   inject(&req, outbound.headers_mut(), &[Propagator::W3c, Propagator::B3]);
+//!   // ... send `outbound` with any client
+//!   "ok"
+//! }
+//! ```
 
 use std::future::Future;
 use std::pin::Pin;
 
+use http::HeaderMap;
 use http::HeaderName;
 use http::HeaderValue;
 use tako_rs_core::middleware::IntoMiddleware;
@@ -35,6 +56,44 @@ use tako_rs_core::types::Response;
 pub const TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
 /// Header carrying vendor-specific trace state.
 pub const TRACESTATE: HeaderName = HeaderName::from_static("tracestate");
+/// Header carrying W3C baggage.
+pub const BAGGAGE: HeaderName = HeaderName::from_static("baggage");
+/// Zipkin B3 single-header context.
+pub const B3: HeaderName = HeaderName::from_static("b3");
+/// Zipkin B3 multi-header trace id.
+pub const X_B3_TRACE_ID: HeaderName = HeaderName::from_static("x-b3-traceid");
+/// Zipkin B3 multi-header span id.
+pub const X_B3_SPAN_ID: HeaderName = HeaderName::from_static("x-b3-spanid");
+/// Zipkin B3 multi-header parent span id.
+pub const X_B3_PARENT_SPAN_ID: HeaderName = HeaderName::from_static("x-b3-parentspanid");
+/// Zipkin B3 multi-header sampling decision.
+pub const X_B3_SAMPLED: HeaderName = HeaderName::from_static("x-b3-sampled");
+/// Zipkin B3 multi-header debug flag.
+pub const X_B3_FLAGS: HeaderName = HeaderName::from_static("x-b3-flags");
+/// Jaeger trace context.
+pub const UBER_TRACE_ID: HeaderName = HeaderName::from_static("uber-trace-id");
+/// Prefix of Jaeger baggage headers (`uberctx-<key>`).
+pub const UBERCTX_PREFIX: &str = "uberctx-";
+
+/// Maximum number of baggage members kept, per the W3C Baggage spec.
+const MAX_BAGGAGE_MEMBERS: usize = 64;
+/// Maximum serialized `baggage` header length, per the W3C Baggage spec.
+const MAX_BAGGAGE_BYTES: usize = 8192;
+
+/// A trace-context wire format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Propagator {
+  /// W3C `traceparent` / `tracestate`.
+  W3c,
+  /// Zipkin B3 multi-header (`X-B3-TraceId`, `X-B3-SpanId`, ...).
+  B3,
+  /// Zipkin B3 single header (`b3: {trace}-{span}-{sampled}-{parent}`).
+  B3Single,
+  /// Jaeger `uber-trace-id: {trace}:{span}:{parent}:{flags}`, with
+  /// `uberctx-*` baggage.
+  Jaeger,
+}
 
 /// Decoded W3C trace context for the current request.
 ///
@@ -60,12 +119,211 @@ impl TraceContext {
   pub fn to_header(&self) -> String {
     format!("00-{}-{}-{:02x}", self.trace_id, self.span_id, self.flags)
   }
+
+  /// Whether the upstream asked for this trace to be recorded.
+  pub fn sampled(&self) -> bool {
+    self.flags & 0x01 != 0
+  }
+
+  /// Writes this context into `headers` in each of the given formats, with
+  /// this hop's span as the parent of the outbound call.
+  pub fn inject(&self, headers: &mut HeaderMap, propagators: &[Propagator]) {
+    let sampled = if self.sampled() { "1" } else { "0" };
+    for propagator in propagators {
+      match propagator {
+        Propagator::W3c => {
+          insert(headers, TRACEPARENT, &self.to_header());
+          if let Some(state) = &self.tracestate {
+            insert(headers, TRACESTATE, state);
+          }
+        }
+        Propagator::B3 => {
+          insert(headers, X_B3_TRACE_ID, &self.trace_id);
+          insert(headers, X_B3_SPAN_ID, &self.span_id);
+          insert(headers, X_B3_SAMPLED, sampled);
+        }
+        Propagator::B3Single => {
+          let value = format!("{}-{}-{sampled}", self.trace_id, self.span_id);
+          insert(headers, B3, &value);
+        }
+        Propagator::Jaeger => {
+          let value = format!("{}:{}:0:{:x}", self.trace_id, self.span_id, self.flags);
+          insert(headers, UBER_TRACE_ID, &value);
+        }
+      }
+    }
+  }
+}
+
+/// W3C baggage: application key/value pairs that travel with a trace.
+///
+/// Member properties (`key=value;prop`) are kept and re-emitted unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Baggage {
+  members: Vec<BaggageMember>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BaggageMember {
+  key: String,
+  value: String,
+  properties: Option<String>,
+}
+
+impl Baggage {
+  /// Creates empty baggage.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Parses a `baggage` header value. Malformed members are skipped.
+  pub fn parse(value: &str) -> Self {
+    let mut baggage = Self::new();
+    baggage.extend_from_header(value);
+    baggage
+  }
+
+  fn extend_from_header(&mut self, value: &str) {
+    for member in value.split(',') {
+      let (pair, properties) = match member.split_once(';') {
+        Some((pair, props)) => (pair, Some(props.trim().to_string())),
+        None => (member, None),
+      };
+      let Some((key, value)) = pair.split_once('=') else {
+        continue;
+      };
+      let key = key.trim();
+      if key.is_empty() || !key.bytes().all(is_token_byte) {
+        continue;
+      }
+      let Ok(value) = urlencoding::decode(value.trim()) else {
+        continue;
+      };
+      self.push(key, &value, properties);
+    }
+  }
+
+  fn push(&mut self, key: &str, value: &str, properties: Option<String>) {
+    if let Some(existing) = self.members.iter_mut().find(|m| m.key == key) {
+      existing.value = value.to_string();
+      existing.properties = properties;
+    } else if self.members.len() < MAX_BAGGAGE_MEMBERS {
+      self.members.push(BaggageMember {
+        key: key.to_string(),
+        value: value.to_string(),
+        properties,
+      });
+    }
+  }
+
+  /// Value of `key`, if present.
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self
+      .members
+      .iter()
+      .find(|m| m.key == key)
+      .map(|m| m.value.as_str())
+  }
+
+  /// Sets `key` to `value`, replacing any previous value. Keys that are not
+  /// valid HTTP tokens are ignored.
+  pub fn insert(&mut self, key: &str, value: &str) {
+    if !key.is_empty() && key.bytes().all(is_token_byte) {
+      self.push(key, value, None);
+    }
+  }
+
+  /// Removes `key`.
+  pub fn remove(&mut self, key: &str) {
+    self.members.retain(|m| m.key != key);
+  }
+
+  /// Iterates over `(key, value)` pairs in header order.
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+    self
+      .members
+      .iter()
+      .map(|m| (m.key.as_str(), m.value.as_str()))
+  }
+
+  /// Whether there are no members.
+  pub fn is_empty(&self) -> bool {
+    self.members.is_empty()
+  }
+
+  /// Renders the `baggage` header value, dropping trailing members that
+  /// would push it past 8192 bytes.
+  pub fn to_header(&self) -> String {
+    let mut out = String::new();
+    for m in &self.members {
+      let mut member = format!("{}={}", m.key, urlencoding::encode(&m.value));
+      if let Some(props) = &m.properties {
+        member.push(';');
+        member.push_str(props);
+      }
+      let sep = usize::from(!out.is_empty());
+      if out.len() + sep + member.len() > MAX_BAGGAGE_BYTES {
+        break;
+      }
+      if sep == 1 {
+        out.push(',');
+      }
+      out.push_str(&member);
+    }
+    out
+  }
+
+  /// Writes the baggage into `headers`: as `baggage` for
+  /// [`Propagator::W3c`] and as `uberctx-<key>` headers for
+  /// [`Propagator::Jaeger`]. B3 has no baggage format.
+  pub fn inject(&self, headers: &mut HeaderMap, propagators: &[Propagator]) {
+    if self.is_empty() {
+      return;
+    }
+    for propagator in propagators {
+      match propagator {
+        Propagator::W3c => insert(headers, BAGGAGE, &self.to_header()),
+        Propagator::Jaeger => {
+          for (key, value) in self.iter() {
+            let name = format!("{UBERCTX_PREFIX}{}", key.to_ascii_lowercase());
+            if let Ok(name) = HeaderName::from_bytes(name.as_bytes()) {
+              insert(headers, name, &urlencoding::encode(value));
+            }
+          }
+        }
+        Propagator::B3 | Propagator::B3Single => {}
+      }
+    }
+  }
+}
+
+/// Copies the [`TraceContext`] and [`Baggage`] the middleware attached to
+/// `req` into `headers` of an outbound request, in each of `propagators`.
+pub fn inject(req: &Request, headers: &mut HeaderMap, propagators: &[Propagator]) {
+  if let Some(ctx) = req.extensions().get::<TraceContext>() {
+    ctx.inject(headers, propagators);
+  }
+  if let Some(baggage) = req.extensions().get::<Baggage>() {
+    baggage.inject(headers, propagators);
+  }
+}
+
+fn insert(headers: &mut HeaderMap, name: HeaderName, value: &str) {
+  if let Ok(v) = HeaderValue::from_str(value) {
+    headers.insert(name, v);
+  }
+}
+
+fn is_token_byte(b: u8) -> bool {
+  b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
 }
 
 /// Builder for the [`TraceContext`] middleware.
 pub struct Traceparent {
   /// When true, emit `tracestate` in the response unchanged (when present).
   emit_tracestate: bool,
+  /// Inbound formats, tried in order.
+  propagators: Vec<Propagator>,
 }
 
 impl Default for Traceparent {
@@ -79,9 +337,17 @@ impl Traceparent {
   pub fn new() -> Self {
     Self {
       emit_tracestate: true,
+      propagators: vec![Propagator::W3c],
     }
   }
 
+  /// Inbound context formats to accept, tried in order; the first one
+  /// present and well-formed wins. Defaults to W3C only.
+  pub fn propagators(mut self, propagators: impl IntoIterator<Item = Propagator>) -> Self {
+    self.propagators = propagators.into_iter().collect();
+    self
+  }
+
   /// Disables echoing `tracestate` in responses (it is still readable from
   /// [`TraceContext::tracestate`] in handlers).
   pub fn skip_tracestate(mut self) -> Self {
@@ -143,6 +409,91 @@ fn parse_traceparent(value: &str) -> Option<(String, String, u8)> {
   ))
 }
 
+/// Left-pads a hex id to `len` chars (B3 and Jaeger allow 64-bit trace ids
+/// and drop leading zeros), rejecting non-hex and all-zero ids.
+fn normalize_id(id: &str, len: usize) -> Option<String> {
+  if id.is_empty()
+    || id.len() > len
+    || !id.bytes().all(|b| b.is_ascii_hexdigit())
+    || id.bytes().all(|b| b == b'0')
+  {
+    return None;
+  }
+  Some(format!("{:0>len$}", id.to_ascii_lowercase()))
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+  headers
+    .get(name)
+    .and_then(|v| v.to_str().ok())
+    .map(str::trim)
+}
+
+fn b3_flags(sampled: Option<&str>, debug: bool) -> u8 {
+  u8::from(debug || matches!(sampled, Some("1" | "true" | "d")))
+}
+
+/// `(trace_id, parent_id, flags)` from the B3 multi-header format.
+fn parse_b3(headers: &HeaderMap) -> Option<(String, String, u8)> {
+  let trace_id = normalize_id(header_str(headers, &X_B3_TRACE_ID)?, 32)?;
+  let span_id = normalize_id(header_str(headers, &X_B3_SPAN_ID)?, 16)?;
+  let debug = header_str(headers, &X_B3_FLAGS) == Some("1");
+  let flags = b3_flags(header_str(headers, &X_B3_SAMPLED), debug);
+  Some((trace_id, span_id, flags))
+}
+
+/// `(trace_id, parent_id, flags)` from `b3: {trace}-{span}[-{sampled}[-{parent}]]`.
+fn parse_b3_single(value: &str) -> Option<(String, String, u8)> {
+  let mut parts = value.split('-');
+  let trace_id = normalize_id(parts.next()?, 32)?;
+  let span_id = normalize_id(parts.next()?, 16)?;
+  let flags = b3_flags(parts.next(), false);
+  Some((trace_id, span_id, flags))
+}
+
+/// `(trace_id, parent_id, flags)` from `uber-trace-id: {trace}:{span}:{parent}:{flags}`.
+fn parse_jaeger(value: &str) -> Option<(String, String, u8)> {
+  let value = value.replace("%3A", ":").replace("%3a", ":");
+  let mut parts = value.split(':');
+  let trace_id = normalize_id(parts.next()?, 32)?;
+  let span_id = normalize_id(parts.next()?, 16)?;
+  let _parent = parts.next()?;
+  let flags = u8::from_str_radix(parts.next()?, 16).ok()?;
+  if parts.next().is_some() {
+    return None;
+  }
+  Some((trace_id, span_id, flags & 0x01))
+}
+
+fn extract(headers: &HeaderMap, propagators: &[Propagator]) -> Option<(String, String, u8)> {
+  propagators.iter().find_map(|p| match p {
+    Propagator::W3c => header_str(headers, &TRACEPARENT).and_then(parse_traceparent),
+    Propagator::B3 => parse_b3(headers),
+    Propagator::B3Single => header_str(headers, &B3).and_then(parse_b3_single),
+    Propagator::Jaeger => header_str(headers, &UBER_TRACE_ID).and_then(parse_jaeger),
+  })
+}
+
+fn extract_baggage(headers: &HeaderMap, jaeger: bool) -> Baggage {
+  let mut baggage = Baggage::new();
+  for value in headers.get_all(BAGGAGE) {
+    if let Ok(value) = value.to_str() {
+      baggage.extend_from_header(value);
+    }
+  }
+  if jaeger {
+    for (name, value) in headers {
+      if let Some(key) = name.as_str().strip_prefix(UBERCTX_PREFIX)
+        && let Ok(value) = value.to_str()
+        && let Ok(value) = urlencoding::decode(value.trim())
+      {
+        baggage.insert(key, &value);
+      }
+    }
+  }
+  baggage
+}
+
 impl IntoMiddleware for Traceparent {
   fn into_middleware(
     self,
@@ -152,21 +503,19 @@ impl IntoMiddleware for Traceparent {
   + Sync
   + 'static {
     let emit_tracestate = self.emit_tracestate;
+    let propagators: std::sync::Arc<[Propagator]> = self.propagators.into();
 
     move |mut req: Request, next: Next| {
+      let propagators = propagators.clone();
       Box::pin(async move {
-        let inbound = req
-          .headers()
-          .get(TRACEPARENT)
-          .and_then(|v| v.to_str().ok())
-          .map(str::to_string);
         let inbound_state = req
           .headers()
           .get(TRACESTATE)
           .and_then(|v| v.to_str().ok())
           .map(str::to_string);
 
-        let parsed = inbound.as_ref().and_then(|h| parse_traceparent(h));
+        let parsed = extract(req.headers(), &propagators);
+        let baggage = extract_baggage(req.headers(), propagators.contains(&Propagator::Jaeger));
         let (trace_id, parent_id, flags) = match parsed {
           Some((tid, pid, fl)) => (tid, Some(pid), fl),
           None => (rand_hex(16), None, 0u8),
@@ -182,6 +531,7 @@ impl IntoMiddleware for Traceparent {
         };
         let header_value = ctx.to_header();
         req.extensions_mut().insert(ctx);
+        req.extensions_mut().insert(baggage);
 
         let mut resp = next.run(req).await;
         if let Ok(v) = HeaderValue::from_str(&header_value) {
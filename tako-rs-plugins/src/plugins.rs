@@ -1,7 +1,7 @@
 //! Built-in plugin implementations.
 //!
 //! Each submodule provides one ready-to-use plugin (CORS, compression, rate
-//! limiting, idempotency, metrics) gated behind the appropriate feature flag.
+//! limiting, quotas, idempotency, response caching, metrics, tus uploads, batching) gated behind the appropriate feature flag.
 
 /// Compression plugin for automatic response compression.
 #[cfg(feature = "plugins")]
@@ -18,15 +18,46 @@ pub mod cors;
 #[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
 pub mod rate_limiter;
 
-/// Metrics/tracing plugin for integrating with systems like Prometheus or OpenTelemetry.
-#[cfg(any(feature = "metrics-prometheus", feature = "metrics-opentelemetry"))]
-#[cfg_attr(
-  docsrs,
-  doc(cfg(any(feature = "metrics-prometheus", feature = "metrics-opentelemetry")))
-)]
+/// Fixed- and sliding-window request quotas with pluggable counters.
+#[cfg(feature = "plugins")]
+#[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+pub mod quota;
+
+/// Request metrics with a Prometheus endpoint, plus signal-driven backends
+/// for Prometheus, OpenTelemetry, and StatsD.
+#[cfg(feature = "plugins")]
+#[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
 pub mod metrics;
 
+/// Response cache with stale-while-revalidate and stale-if-error.
+#[cfg(feature = "plugins")]
+#[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+pub mod cache;
+
+/// Batch endpoint that multiplexes several API calls into one request.
+///
+/// Dispatch through the router handle is `!Send` on compio, which
+/// middleware cannot hold, so the plugin is tokio-only.
+#[cfg(all(feature = "plugins", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+pub mod batch;
+
 /// Idempotency-Key based request de-duplication plugin.
 #[cfg(feature = "plugins")]
 #[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
 pub mod idempotency;
+
+/// Security headers applied router-wide.
+#[cfg(feature = "plugins")]
+#[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+pub mod security_headers;
+
+/// Validation errors of the serde-loadable plugin `Settings`.
+#[cfg(feature = "plugins")]
+#[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+pub mod settings;
+
+/// tus resumable upload protocol plugin.
+#[cfg(feature = "plugins")]
+#[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+pub mod tus;
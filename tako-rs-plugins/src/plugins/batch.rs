@@ -0,0 +1,63 @@
+#![cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+//! Batch endpoint: several API calls in one round trip.
+//!
+//! The plugin reserves a path (default `POST /batch`) that accepts a JSON
+//! list of sub-requests, dispatches each one through the running router, and
+//! answers with every sub-response in the same order:
+//!
+//! ```json
+//! { "requests": [
+//!     { "id": "me",     "method": "GET",  "path": "/users/me" },
+//!     { "id": "follow", "method": "POST", "path": "/follows",
+//!       "headers": { "x-trace": "1" }, "body": { "user": 42 } }
+//! ] }
+//! ```
+//!
+//! ```json
+//! { "responses": [
+//!     { "id": "me",     "status": 200, "headers": { "content-type": "application/json" },
+//!       "body": { "name": "Ada" } },
+//!     { "id": "follow", "status": 201, "headers": {}, "body": "created" }
+//! ] }
+//! ```
+//!
+//! Sub-requests go through [`Router::dispatch`](tako_rs_core::router::Router::dispatch),
+//! so router-level middleware, route middleware, and plugins (auth, rate
+//! limits, caching) apply to each one exactly as if it had arrived on its own.
+//! They inherit the outer request's headers (`Authorization`, `Cookie`, …)
+//! and extensions (peer address, TLS info); an item's own `headers` win.
+//!
+//! - A JSON `body` object or array is sent serialized, with
+//!   `content-type: application/json` unless the item sets one; a string is
+//!   sent as-is.
+//! - JSON sub-response bodies are embedded as JSON, other UTF-8 bodies as a
+//!   string, and binary bodies as base64 with `"encoding": "base64"`.
+//! - A malformed item gets a `400` entry of its own instead of failing the
+//!   batch; a batch cannot contain a request to the batch path itself.
+//!
+//! The router handle arrives when the server starts serving, so the endpoint
+//! answers `503` before then (for example when dispatching directly in
+//! tests without calling [`TakoPlugin::on_start`](tako_rs_core::plugins::TakoPlugin::on_start)).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::plugins::batch::BatchBuilder;
+//! use tako::router::Router;
+//!
+//! let mut router = Router::new();
+//! router.plugin(
+//!     BatchBuilder::new()
+//!         .path("/api/batch")
+//!         .max_items(25)
+//!         .max_concurrency(4)
+//!         .build(),
+//! );
+//! ```
+
+mod config;
+mod plugin;
+
+pub use config::BatchBuilder;
+pub use config::Config;
+pub use plugin::BatchPlugin;
@@ -0,0 +1,73 @@
+//! Batch endpoint limits and the builder.
+
+use super::plugin::BatchPlugin;
+
+/// Batch endpoint configuration.
+#[derive(Clone)]
+pub struct Config {
+  /// Path that accepts `POST` batches. Default: `/batch`.
+  pub path: String,
+  /// Most sub-requests accepted in one batch; larger batches get `413`.
+  /// Default: 20.
+  pub max_items: usize,
+  /// Largest accepted batch body, in bytes; larger bodies get `413`.
+  /// Default: 1 MiB.
+  pub max_body_bytes: usize,
+  /// How many sub-requests run at once. `1` runs them one after another, in
+  /// order. Default: 8.
+  pub max_concurrency: usize,
+  /// Copy the outer request's headers onto every sub-request. Default: true.
+  pub inherit_headers: bool,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      path: "/batch".to_string(),
+      max_items: 20,
+      max_body_bytes: 1024 * 1024,
+      max_concurrency: 8,
+      inherit_headers: true,
+    }
+  }
+}
+
+/// Builder for the batch plugin.
+pub struct BatchBuilder(Config);
+
+impl Default for BatchBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl BatchBuilder {
+  /// Start with sensible defaults.
+  pub fn new() -> Self {
+    Self(Config::default())
+  }
+  pub fn path(mut self, p: impl Into<String>) -> Self {
+    self.0.path = p.into();
+    self
+  }
+  pub fn max_items(mut self, n: usize) -> Self {
+    self.0.max_items = n;
+    self
+  }
+  pub fn max_body_bytes(mut self, n: usize) -> Self {
+    self.0.max_body_bytes = n;
+    self
+  }
+  /// Clamped to at least 1.
+  pub fn max_concurrency(mut self, n: usize) -> Self {
+    self.0.max_concurrency = n.max(1);
+    self
+  }
+  pub fn inherit_headers(mut self, yes: bool) -> Self {
+    self.0.inherit_headers = yes;
+    self
+  }
+  pub fn build(self) -> BatchPlugin {
+    BatchPlugin::new(self.0)
+  }
+}
@@ -0,0 +1,340 @@
+//! The batch plugin: router-handle wiring, wire format, and sub-request
+//! dispatch.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use base64::Engine;
+use futures_util::StreamExt;
+use futures_util::stream;
+use http::Extensions;
+use http::HeaderMap;
+use http::HeaderName;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::Uri;
+use http::header::ACCEPT_ENCODING;
+use http::header::ALLOW;
+use http::header::CONTENT_ENCODING;
+use http::header::CONTENT_LENGTH;
+use http::header::CONTENT_TYPE;
+use http::header::EXPECT;
+use http::header::TRANSFER_ENCODING;
+use http_body_util::BodyExt;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::IgnoredAny;
+use serde_json::Value;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::plugins::RouterHandle;
+use tako_rs_core::plugins::TakoPlugin;
+use tako_rs_core::router::Router;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::config::BatchBuilder;
+use super::config::Config;
+
+/// Outer headers that describe the batch body itself or would change how
+/// each sub-response is encoded, so they are never inherited. The
+/// idempotency key would collapse every item onto one stored response.
+const NOT_INHERITED: [HeaderName; 6] = [
+  CONTENT_LENGTH,
+  CONTENT_TYPE,
+  CONTENT_ENCODING,
+  TRANSFER_ENCODING,
+  ACCEPT_ENCODING,
+  EXPECT,
+];
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Batch endpoint plugin. Attach at router level.
+#[derive(Clone)]
+#[doc(alias = "batch")]
+#[doc(alias = "multiplex")]
+pub struct BatchPlugin {
+  cfg: Arc<Config>,
+  router: Arc<OnceLock<RouterHandle>>,
+}
+
+impl BatchPlugin {
+  pub fn builder() -> BatchBuilder {
+    BatchBuilder::new()
+  }
+
+  pub fn new(cfg: Config) -> Self {
+    Self {
+      cfg: Arc::new(cfg),
+      router: Arc::new(OnceLock::new()),
+    }
+  }
+}
+
+impl TakoPlugin for BatchPlugin {
+  fn name(&self) -> &'static str {
+    "BatchPlugin"
+  }
+
+  fn setup(&self, router: &Router) -> Result<()> {
+    let cfg = self.cfg.clone();
+    let handle = self.router.clone();
+    router.middleware(move |req, next| {
+      let cfg = cfg.clone();
+      let handle = handle.clone();
+      async move { handle_batch(req, next, cfg, handle).await }
+    });
+    Ok(())
+  }
+
+  fn on_start(&self, router: RouterHandle) {
+    let _ = self.router.set(router);
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchRequest {
+  requests: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Item {
+  /// Echoed from the raw item, so a malformed item still reports its id.
+  #[serde(default, rename = "id")]
+  _id: Option<IgnoredAny>,
+  #[serde(default)]
+  method: Option<String>,
+  path: String,
+  #[serde(default)]
+  headers: BTreeMap<String, String>,
+  #[serde(default)]
+  body: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct ItemResponse {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  id: Option<Value>,
+  status: u16,
+  headers: BTreeMap<String, String>,
+  body: Value,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  encoding: Option<&'static str>,
+}
+
+impl ItemResponse {
+  fn error(id: Option<Value>, status: StatusCode, message: impl Into<String>) -> Self {
+    Self {
+      id,
+      status: status.as_u16(),
+      headers: BTreeMap::new(),
+      body: Value::String(message.into()),
+      encoding: None,
+    }
+  }
+}
+
+#[derive(Serialize)]
+struct BatchResponse {
+  responses: Vec<ItemResponse>,
+}
+
+async fn handle_batch(
+  req: Request,
+  next: Next,
+  cfg: Arc<Config>,
+  handle: Arc<OnceLock<RouterHandle>>,
+) -> Response {
+  if req.uri().path() != cfg.path {
+    return next.run(req).await;
+  }
+  if req.method() != Method::POST {
+    let mut resp = text(StatusCode::METHOD_NOT_ALLOWED, "batches must be POSTed");
+    resp
+      .headers_mut()
+      .insert(ALLOW, HeaderValue::from_static("POST"));
+    return resp;
+  }
+  let Some(router) = handle.get().cloned() else {
+    return text(
+      StatusCode::SERVICE_UNAVAILABLE,
+      "batch endpoint is not started",
+    );
+  };
+
+  let (parts, body) = req.into_parts();
+  let Ok(collected) = http_body_util::Limited::new(body, cfg.max_body_bytes)
+    .collect()
+    .await
+  else {
+    return text(StatusCode::PAYLOAD_TOO_LARGE, "batch body is too large");
+  };
+  let batch: BatchRequest = match serde_json::from_slice(&collected.to_bytes()) {
+    Ok(batch) => batch,
+    Err(e) => return text(StatusCode::BAD_REQUEST, &format!("invalid batch: {e}")),
+  };
+  if batch.requests.len() > cfg.max_items {
+    return text(
+      StatusCode::PAYLOAD_TOO_LARGE,
+      &format!("a batch holds at most {} requests", cfg.max_items),
+    );
+  }
+
+  let mut inherited = HeaderMap::new();
+  if cfg.inherit_headers {
+    inherited = parts.headers;
+    for name in &NOT_INHERITED {
+      inherited.remove(name);
+    }
+    inherited.remove(IDEMPOTENCY_KEY);
+  }
+  let (cfg, router, inherited, extensions) = (&*cfg, &router, &inherited, &parts.extensions);
+  let responses = stream::iter(batch.requests)
+    .map(|item| run_item(item, cfg, router, inherited, extensions))
+    .buffered(cfg.max_concurrency)
+    .collect()
+    .await;
+
+  let body = serde_json::to_vec(&BatchResponse { responses }).expect("serializable");
+  let mut resp = http::Response::new(TakoBody::from(body));
+  resp
+    .headers_mut()
+    .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+  resp
+}
+
+async fn run_item(
+  raw: Value,
+  cfg: &Config,
+  router: &RouterHandle,
+  inherited: &HeaderMap,
+  extensions: &Extensions,
+) -> ItemResponse {
+  let id = raw.get("id").cloned();
+  let req = match build_request(raw, cfg, inherited, extensions) {
+    Ok(req) => req,
+    Err(message) => return ItemResponse::error(id, StatusCode::BAD_REQUEST, message),
+  };
+
+  let (parts, body) = router.dispatch(req).await.into_parts();
+  let bytes = match body.collect().await {
+    Ok(c) => c.to_bytes(),
+    Err(e) => {
+      return ItemResponse::error(
+        id,
+        StatusCode::BAD_GATEWAY,
+        format!("reading the response failed: {e}"),
+      );
+    }
+  };
+
+  let mut headers = BTreeMap::new();
+  for (name, value) in &parts.headers {
+    let value = String::from_utf8_lossy(value.as_bytes());
+    headers
+      .entry(name.as_str().to_string())
+      .and_modify(|v: &mut String| {
+        v.push_str(", ");
+        v.push_str(&value);
+      })
+      .or_insert_with(|| value.into_owned());
+  }
+
+  let (body, encoding) = encode_body(parts.headers.get(CONTENT_TYPE), &bytes);
+  ItemResponse {
+    id,
+    status: parts.status.as_u16(),
+    headers,
+    body,
+    encoding,
+  }
+}
+
+fn build_request(
+  raw: Value,
+  cfg: &Config,
+  inherited: &HeaderMap,
+  extensions: &Extensions,
+) -> Result<Request, String> {
+  let item: Item = serde_json::from_value(raw).map_err(|e| format!("invalid request: {e}"))?;
+
+  let method = match item.method {
+    Some(m) => Method::from_bytes(m.to_ascii_uppercase().as_bytes())
+      .map_err(|_| format!("invalid method `{m}`"))?,
+    None => Method::GET,
+  };
+  if !item.path.starts_with('/') || item.path.starts_with("//") {
+    return Err("`path` must be an absolute path such as `/users/1`".to_string());
+  }
+  let uri: Uri = item
+    .path
+    .parse()
+    .map_err(|e| format!("invalid path: {e}"))?;
+  if uri.path() == cfg.path {
+    return Err("a batch cannot contain another batch".to_string());
+  }
+
+  let mut headers = inherited.clone();
+  for (name, value) in &item.headers {
+    let name = HeaderName::from_bytes(name.as_bytes())
+      .map_err(|_| format!("invalid header name `{name}`"))?;
+    let value =
+      HeaderValue::from_str(value).map_err(|_| format!("invalid value for header `{name}`"))?;
+    headers.insert(name, value);
+  }
+
+  let body = match item.body {
+    None | Some(Value::Null) => TakoBody::empty(),
+    Some(Value::String(s)) => TakoBody::from(s),
+    Some(json) => {
+      if !headers.contains_key(CONTENT_TYPE) {
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+      }
+      TakoBody::from(serde_json::to_vec(&json).expect("serializable"))
+    }
+  };
+
+  let mut req = http::Request::new(body);
+  *req.method_mut() = method;
+  *req.uri_mut() = uri;
+  *req.headers_mut() = headers;
+  *req.extensions_mut() = extensions.clone();
+  Ok(req)
+}
+
+/// JSON bodies are embedded as JSON, other UTF-8 as a string, anything else
+/// as base64.
+fn encode_body(content_type: Option<&HeaderValue>, bytes: &[u8]) -> (Value, Option<&'static str>) {
+  if bytes.is_empty() {
+    return (Value::Null, None);
+  }
+  let is_json = content_type
+    .and_then(|v| v.to_str().ok())
+    .and_then(|v| v.parse::<mime::Mime>().ok())
+    .is_some_and(|m| m.subtype() == mime::JSON || m.suffix() == Some(mime::JSON));
+  if is_json && let Ok(value) = serde_json::from_slice(bytes) {
+    return (value, None);
+  }
+  match std::str::from_utf8(bytes) {
+    Ok(s) => (Value::String(s.to_string()), None),
+    Err(_) => (
+      Value::String(base64::engine::general_purpose::STANDARD.encode(bytes)),
+      Some("base64"),
+    ),
+  }
+}
+
+fn text(status: StatusCode, message: &str) -> Response {
+  let mut resp = http::Response::new(TakoBody::from(message.to_string()));
+  *resp.status_mut() = status;
+  resp.headers_mut().insert(
+    CONTENT_TYPE,
+    HeaderValue::from_static("text/plain; charset=utf-8"),
+  );
+  resp
+}
@@ -0,0 +1,119 @@
+#![cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+//! Response cache plugin with `stale-while-revalidate` / `stale-if-error`.
+//!
+//! Successful responses to safe methods are buffered and kept in memory. An
+//! entry moves through three windows after it is stored:
+//!
+//! - **fresh** (`ttl` / `max-age`): served straight from memory.
+//! - **stale-while-revalidate**: served immediately while a single background
+//!   refresh re-runs the handler and replaces the entry.
+//! - **stale-if-error**: the handler runs in the foreground, but a `5xx`
+//!   response is swapped for the stale entry.
+//!
+//! When the response carries a `Cache-Control` header its `max-age` /
+//! `s-maxage`, `stale-while-revalidate`, and `stale-if-error` directives
+//! (RFC 5861) override the configured defaults, and `no-store` / `private`
+//! keep it out of the cache. Requests with `Cache-Control: no-store` bypass
+//! the cache; `no-cache` skips the lookup but still stores the new response.
+//! Responses with `Vary` are only replayed to requests whose varying headers
+//! match.
+//!
+//! Requests carrying `Authorization` bypass the cache unless
+//! [`CacheBuilder::cache_authorized`] is on. Pair that with
+//! [`CacheBuilder::partition_by`], which gives every authenticated
+//! principal entries of its own (and lets it keep `private` responses), so
+//! one user's response is never replayed to another.
+//!
+//! Stored responses without an `ETag` get a weak one hashed from the body,
+//! and `GET` / `HEAD` requests whose `If-None-Match` matches are answered
+//! with `304 Not Modified`, whether the entry was replayed or just stored.
+//!
+//! Every response that passes through gets an `x-cache` header (`HIT`,
+//! `STALE`, or `MISS`) and replays carry `Age`. With the `signals` feature the
+//! plugin emits [`ids::REFRESHED`], [`ids::REFRESH_FAILED`], and
+//! [`ids::STALE_IF_ERROR`] on the global arbiter.
+//!
+//! [`CacheWarmer`]s dispatch synthetic requests through the running router on
+//! a schedule, starting as soon as the server is up, so the first real
+//! clients after a deploy hit a warm cache. [`CachePlugin::warm`] runs them
+//! once on demand.
+//!
+//! [`CachePlugin::invalidate`] and [`CachePlugin::invalidate_all`] drop
+//! entries when content changes, and [`CacheBuilder::invalidate_on_write`]
+//! does so after every successful write to a path. Code without a handle on
+//! the plugin can emit [`ids::INVALIDATE`] instead. With an [`EdgePurger`]
+//! configured, each invalidation also purges the CDN — Cloudflare, Fastly,
+//! or a webhook with the `edge-purge` feature — so origin and edge stay
+//! consistent.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use tako::plugins::cache::CacheBuilder;
+//! use tako::plugins::cache::CacheWarmer;
+//! use tako::router::Router;
+//!
+//! let mut router = Router::new();
+//! router.plugin(
+//!     CacheBuilder::new()
+//!         .ttl(Duration::from_secs(30))
+//!         .stale_while_revalidate(Duration::from_secs(300))
+//!         .stale_if_error(Duration::from_secs(3600))
+//!         .warmer(CacheWarmer::paths(Duration::from_secs(25), ["/", "/pricing"]))
+//!         .build(),
+//! );
+//! ```
+
+mod config;
+mod edge;
+mod plugin;
+mod policy;
+mod settings;
+mod store;
+mod warmer;
+
+pub use config::CacheBuilder;
+pub use config::Config;
+#[cfg(feature = "edge-purge")]
+pub use edge::Cloudflare;
+pub use edge::EdgePurger;
+#[cfg(feature = "edge-purge")]
+pub use edge::Fastly;
+pub use edge::Purge;
+pub use edge::PurgeError;
+#[cfg(feature = "edge-purge")]
+pub use edge::PurgeWebhook;
+pub use plugin::CachePlugin;
+pub use settings::Settings;
+pub use warmer::CacheWarmer;
+pub use warmer::WarmRequests;
+
+/// Signal ids emitted by the cache plugin (`signals` feature).
+///
+/// Entry signals carry `key` (`METHOD path?query`, followed by the
+/// partition when there is one) and the handler's `status` as metadata.
+pub mod ids {
+  /// Emit to invalidate entries. Set `paths` metadata to a comma-separated
+  /// list of paths, or leave it out (or use `*`) to drop everything. Every
+  /// cache plugin in the process acts on it.
+  pub const INVALIDATE: &str = "cache.invalidate";
+  /// A background revalidation stored a new entry.
+  pub const REFRESHED: &str = "cache.refreshed";
+  /// A background revalidation returned a non-cacheable response; the stale
+  /// entry was kept.
+  pub const REFRESH_FAILED: &str = "cache.refresh_failed";
+  /// A `5xx` response was replaced by a stale entry.
+  pub const STALE_IF_ERROR: &str = "cache.stale_if_error";
+  /// A warmer run finished; carries `requests` and `ok` counts instead of
+  /// `key` / `status`.
+  pub const WARMED: &str = "cache.warmed";
+  /// Entries were invalidated; carries `paths` (`*` for everything) and the
+  /// number of local entries `removed`.
+  pub const INVALIDATED: &str = "cache.invalidated";
+  /// The edge purger accepted a purge; carries `provider` and `paths`.
+  pub const EDGE_PURGED: &str = "cache.edge_purged";
+  /// The edge purger failed; carries `provider`, `paths`, and `error`.
+  pub const EDGE_PURGE_FAILED: &str = "cache.edge_purge_failed";
+}
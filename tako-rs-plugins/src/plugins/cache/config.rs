@@ -0,0 +1,172 @@
+//! Cache windows, cacheability rules, and the builder.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::HeaderName;
+use http::Method;
+use http::StatusCode;
+
+use super::edge::EdgePurger;
+use super::plugin::CachePlugin;
+use super::warmer::CacheWarmer;
+use crate::plugins::rate_limiter::KeyExtractor;
+
+/// Cache policy and matching configuration.
+#[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Config {
+  /// Methods whose responses are cached. Default: `[GET, HEAD]`.
+  pub methods: Vec<Method>,
+  /// Statuses that are cacheable. Default: `[200]`.
+  pub statuses: Vec<StatusCode>,
+  /// Freshness lifetime when the response has no `max-age`. Default: 60s.
+  pub ttl: Duration,
+  /// How long past freshness a stale entry is served while a background
+  /// refresh runs. Default: zero (disabled).
+  pub stale_while_revalidate: Duration,
+  /// How long past freshness a stale entry may replace a `5xx` response.
+  /// Default: zero (disabled).
+  pub stale_if_error: Duration,
+  /// Honour `Cache-Control` on responses and requests. Default: true.
+  pub respect_cache_control: bool,
+  /// Responses whose body is larger than this (or of unknown length) are not
+  /// cached. Default: 1 MiB.
+  pub max_body_bytes: u64,
+  /// Upper bound on stored entries; new keys are not cached once it is
+  /// reached. Default: 10 000.
+  pub max_entries: usize,
+  /// Header reporting `HIT` / `STALE` / `MISS`. Default: `x-cache`.
+  pub status_header: Option<HeaderName>,
+  /// Warmers started when the server starts serving. Default: none.
+  pub warmers: Vec<CacheWarmer>,
+  /// CDN purged alongside every invalidation. Default: none.
+  pub edge_purger: Option<Arc<dyn EdgePurger>>,
+  /// Invalidate a path when a request with an unsafe method (`POST`, `PUT`,
+  /// `PATCH`, `DELETE`, ...) to it succeeds. Default: false.
+  pub invalidate_on_write: bool,
+  /// Give stored responses without an `ETag` a weak one derived from the
+  /// body, and answer matching `If-None-Match` requests with `304 Not
+  /// Modified`. Default: true.
+  pub etag: bool,
+  /// Splits the cache by principal: requests the extractor keys get
+  /// entries of their own, and may store `Cache-Control: private`
+  /// responses. Unkeyed requests share the common entries. Default: none.
+  pub partition: Option<Arc<dyn KeyExtractor>>,
+  /// Cache requests that carry `Authorization`. Off by default, since a
+  /// shared entry would hand one user's response to another; turn it on
+  /// together with [`Config::partition`]. Default: false.
+  pub cache_authorized: bool,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      methods: vec![Method::GET, Method::HEAD],
+      statuses: vec![StatusCode::OK],
+      ttl: Duration::from_secs(60),
+      stale_while_revalidate: Duration::ZERO,
+      stale_if_error: Duration::ZERO,
+      respect_cache_control: true,
+      max_body_bytes: 1024 * 1024,
+      max_entries: 10_000,
+      status_header: Some(HeaderName::from_static("x-cache")),
+      warmers: Vec::new(),
+      edge_purger: None,
+      invalidate_on_write: false,
+      etag: true,
+      partition: None,
+      cache_authorized: false,
+    }
+  }
+}
+
+/// Builder for the cache plugin.
+pub struct CacheBuilder(Config);
+
+impl Default for CacheBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl CacheBuilder {
+  /// Start with sensible defaults.
+  pub fn new() -> Self {
+    Self(Config::default())
+  }
+  pub fn methods(mut self, m: &[Method]) -> Self {
+    self.0.methods = m.to_vec();
+    self
+  }
+  pub fn statuses(mut self, s: &[StatusCode]) -> Self {
+    self.0.statuses = s.to_vec();
+    self
+  }
+  pub fn ttl(mut self, d: Duration) -> Self {
+    self.0.ttl = d;
+    self
+  }
+  pub fn stale_while_revalidate(mut self, d: Duration) -> Self {
+    self.0.stale_while_revalidate = d;
+    self
+  }
+  pub fn stale_if_error(mut self, d: Duration) -> Self {
+    self.0.stale_if_error = d;
+    self
+  }
+  pub fn respect_cache_control(mut self, yes: bool) -> Self {
+    self.0.respect_cache_control = yes;
+    self
+  }
+  pub fn max_body_bytes(mut self, n: u64) -> Self {
+    self.0.max_body_bytes = n;
+    self
+  }
+  pub fn max_entries(mut self, n: usize) -> Self {
+    self.0.max_entries = n;
+    self
+  }
+  pub fn status_header(mut self, h: Option<HeaderName>) -> Self {
+    self.0.status_header = h;
+    self
+  }
+  /// Adds a warmer that pre-populates the cache on a schedule.
+  pub fn warmer(mut self, w: CacheWarmer) -> Self {
+    self.0.warmers.push(w);
+    self
+  }
+  /// Purges invalidated paths from a CDN as well.
+  pub fn edge_purger(mut self, purger: impl EdgePurger) -> Self {
+    self.0.edge_purger = Some(Arc::new(purger));
+    self
+  }
+  /// Invalidates a path (locally and at the edge) after a successful write
+  /// to it.
+  pub fn invalidate_on_write(mut self, yes: bool) -> Self {
+    self.0.invalidate_on_write = yes;
+    self
+  }
+  /// Adds `ETag`s to stored responses and answers conditional requests
+  /// with `304`.
+  pub fn etag(mut self, yes: bool) -> Self {
+    self.0.etag = yes;
+    self
+  }
+  /// Keeps a separate cache per principal, e.g. per
+  /// [`JwtSubject`](crate::plugins::rate_limiter::JwtSubject) or
+  /// [`SessionValue`](crate::plugins::rate_limiter::SessionValue). The
+  /// middleware that authenticates the request has to run first.
+  pub fn partition_by(mut self, key: impl KeyExtractor) -> Self {
+    self.0.partition = Some(Arc::new(key));
+    self
+  }
+  /// Caches requests that carry `Authorization`.
+  pub fn cache_authorized(mut self, yes: bool) -> Self {
+    self.0.cache_authorized = yes;
+    self
+  }
+  pub fn build(self) -> CachePlugin {
+    CachePlugin::new(self.0)
+  }
+}
@@ -0,0 +1,385 @@
+//! CDN purging for cache invalidations.
+//!
+//! An [`EdgePurger`] set with [`CacheBuilder::edge_purger`](super::CacheBuilder::edge_purger)
+//! receives every invalidation the cache plugin makes, so a CDN in front of
+//! the origin drops the same paths. With the `edge-purge` feature the module
+//! ships purgers for Cloudflare, Fastly, and a generic JSON webhook.
+
+use std::fmt;
+#[cfg(feature = "edge-purge")]
+use std::time::Duration;
+
+use async_trait::async_trait;
+use http::StatusCode;
+#[cfg(feature = "edge-purge")]
+use http::header::HeaderName;
+#[cfg(feature = "edge-purge")]
+use http::header::HeaderValue;
+#[cfg(feature = "edge-purge")]
+use serde_json::Value;
+#[cfg(feature = "edge-purge")]
+use serde_json::json;
+#[cfg(feature = "edge-purge")]
+use tako_rs_core::client::Client;
+#[cfg(feature = "edge-purge")]
+use tako_rs_core::client::ClientError;
+#[cfg(feature = "edge-purge")]
+use tako_rs_core::client::RequestBuilder;
+
+/// What to purge at the edge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Purge {
+  /// Origin-relative paths, e.g. `/posts/1`. A path with a query string
+  /// names that exact URL.
+  Paths(Vec<String>),
+  /// Everything cached for the site.
+  All,
+}
+
+impl Purge {
+  /// `*` for [`Purge::All`], otherwise the comma-separated paths.
+  pub fn describe(&self) -> String {
+    match self {
+      Self::Paths(paths) => paths.join(","),
+      Self::All => "*".to_string(),
+    }
+  }
+}
+
+/// A failed purge.
+#[derive(Debug, Clone)]
+pub enum PurgeError {
+  /// The provider answered with a non-success status; carries the body.
+  Status(StatusCode, String),
+  /// The request could not be sent or timed out.
+  Transport(String),
+}
+
+impl fmt::Display for PurgeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Status(status, body) => write!(f, "purge rejected with {status}: {body}"),
+      Self::Transport(e) => write!(f, "purge request failed: {e}"),
+    }
+  }
+}
+
+impl std::error::Error for PurgeError {}
+
+/// Purges cached content from a CDN.
+#[async_trait]
+pub trait EdgePurger: Send + Sync + 'static {
+  /// Provider name, reported in logs and signals.
+  fn name(&self) -> &'static str;
+
+  /// Purges `purge`, returning once the provider has accepted it.
+  async fn purge(&self, purge: &Purge) -> Result<(), PurgeError>;
+}
+
+#[cfg(feature = "edge-purge")]
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends `req` and maps anything but a `2xx` to a [`PurgeError`].
+#[cfg(feature = "edge-purge")]
+async fn send(req: RequestBuilder, timeout: Duration) -> Result<(), PurgeError> {
+  let resp = req
+    .timeout(timeout)
+    .send()
+    .await
+    .map_err(|e: ClientError| PurgeError::Transport(e.to_string()))?;
+  let status = resp.status();
+  if status.is_success() {
+    return Ok(());
+  }
+  let body = resp.text().await.unwrap_or_default();
+  Err(PurgeError::Status(status, body))
+}
+
+/// Joins an origin (`https://example.com`) and a path.
+#[cfg(feature = "edge-purge")]
+fn absolute(origin: &str, path: &str) -> String {
+  format!(
+    "{}/{}",
+    origin.trim_end_matches('/'),
+    path.trim_start_matches('/')
+  )
+}
+
+/// Cloudflare's purge-cache API.
+///
+/// Paths are purged by URL (`files`), thirty per request as the API
+/// allows; [`Purge::All`] uses `purge_everything`. The API token needs the
+/// `Zone.Cache Purge` permission.
+#[cfg(feature = "edge-purge")]
+#[derive(Clone)]
+pub struct Cloudflare {
+  client: Client,
+  api_base: String,
+  zone_id: String,
+  api_token: String,
+  origin: String,
+  timeout: Duration,
+}
+
+#[cfg(feature = "edge-purge")]
+impl Cloudflare {
+  /// URLs per purge request accepted by the API.
+  pub const MAX_FILES: usize = 30;
+
+  /// Purges URLs under `origin` (e.g. `https://example.com`) in `zone_id`.
+  pub fn new(
+    zone_id: impl Into<String>,
+    api_token: impl Into<String>,
+    origin: impl Into<String>,
+  ) -> Self {
+    Self {
+      client: Client::new(),
+      api_base: "https://api.cloudflare.com/client/v4".to_string(),
+      zone_id: zone_id.into(),
+      api_token: api_token.into(),
+      origin: origin.into(),
+      timeout: DEFAULT_TIMEOUT,
+    }
+  }
+
+  /// Overrides the API base URL.
+  pub fn api_base(mut self, url: impl Into<String>) -> Self {
+    self.api_base = url.into().trim_end_matches('/').to_string();
+    self
+  }
+
+  /// Sends requests through `client` instead of a private one.
+  pub fn client(mut self, client: Client) -> Self {
+    self.client = client;
+    self
+  }
+
+  /// Per-request timeout (default 10s).
+  pub fn timeout(mut self, d: Duration) -> Self {
+    self.timeout = d;
+    self
+  }
+
+  /// Request bodies for `purge`.
+  fn bodies(origin: &str, purge: &Purge) -> Vec<Value> {
+    match purge {
+      Purge::All => vec![json!({ "purge_everything": true })],
+      Purge::Paths(paths) => paths
+        .chunks(Self::MAX_FILES)
+        .map(|chunk| {
+          let files: Vec<String> = chunk.iter().map(|p| absolute(origin, p)).collect();
+          json!({ "files": files })
+        })
+        .collect(),
+    }
+  }
+}
+
+#[cfg(feature = "edge-purge")]
+#[async_trait]
+impl EdgePurger for Cloudflare {
+  fn name(&self) -> &'static str {
+    "cloudflare"
+  }
+
+  async fn purge(&self, purge: &Purge) -> Result<(), PurgeError> {
+    let url = format!("{}/zones/{}/purge_cache", self.api_base, self.zone_id);
+    for body in Self::bodies(&self.origin, purge) {
+      let req = self
+        .client
+        .post(&url)
+        .bearer_auth(&self.api_token)
+        .json(&body);
+      send(req, self.timeout).await?;
+    }
+    Ok(())
+  }
+}
+
+/// Fastly's purge API.
+///
+/// Paths are purged one URL at a time; [`Purge::All`] purges the whole
+/// service. [`Fastly::soft`] marks content stale instead of evicting it.
+#[cfg(feature = "edge-purge")]
+#[derive(Clone)]
+pub struct Fastly {
+  client: Client,
+  api_base: String,
+  service_id: String,
+  api_token: String,
+  host: String,
+  soft: bool,
+  timeout: Duration,
+}
+
+#[cfg(feature = "edge-purge")]
+impl Fastly {
+  /// Purges URLs on `host` (e.g. `www.example.com`) served by `service_id`.
+  pub fn new(
+    service_id: impl Into<String>,
+    api_token: impl Into<String>,
+    host: impl Into<String>,
+  ) -> Self {
+    Self {
+      client: Client::new(),
+      api_base: "https://api.fastly.com".to_string(),
+      service_id: service_id.into(),
+      api_token: api_token.into(),
+      host: host.into(),
+      soft: false,
+      timeout: DEFAULT_TIMEOUT,
+    }
+  }
+
+  /// Overrides the API base URL.
+  pub fn api_base(mut self, url: impl Into<String>) -> Self {
+    self.api_base = url.into().trim_end_matches('/').to_string();
+    self
+  }
+
+  /// Sends requests through `client` instead of a private one.
+  pub fn client(mut self, client: Client) -> Self {
+    self.client = client;
+    self
+  }
+
+  /// Soft purge: mark content stale rather than evicting it.
+  pub fn soft(mut self, yes: bool) -> Self {
+    self.soft = yes;
+    self
+  }
+
+  /// Per-request timeout (default 10s).
+  pub fn timeout(mut self, d: Duration) -> Self {
+    self.timeout = d;
+    self
+  }
+
+  /// API URLs to `POST` for `purge`.
+  fn urls(&self, purge: &Purge) -> Vec<String> {
+    match purge {
+      Purge::All => vec![format!(
+        "{}/service/{}/purge_all",
+        self.api_base, self.service_id
+      )],
+      Purge::Paths(paths) => paths
+        .iter()
+        .map(|p| absolute(&format!("{}/purge/{}", self.api_base, self.host), p))
+        .collect(),
+    }
+  }
+}
+
+#[cfg(feature = "edge-purge")]
+#[async_trait]
+impl EdgePurger for Fastly {
+  fn name(&self) -> &'static str {
+    "fastly"
+  }
+
+  async fn purge(&self, purge: &Purge) -> Result<(), PurgeError> {
+    for url in self.urls(purge) {
+      let mut req = self
+        .client
+        .post(url)
+        .header("fastly-key", self.api_token.as_str());
+      if self.soft {
+        req = req.header("fastly-soft-purge", "1");
+      }
+      send(req, self.timeout).await?;
+    }
+    Ok(())
+  }
+}
+
+/// Posts purges as JSON to an endpoint of your own: `{"paths": [...]}`, or
+/// `{"all": true}` for [`Purge::All`]. Any `2xx` counts as success.
+#[cfg(feature = "edge-purge")]
+#[derive(Clone)]
+pub struct PurgeWebhook {
+  client: Client,
+  url: String,
+  headers: Vec<(HeaderName, HeaderValue)>,
+  timeout: Duration,
+}
+
+#[cfg(feature = "edge-purge")]
+impl PurgeWebhook {
+  /// Posts to `url`.
+  pub fn new(url: impl Into<String>) -> Self {
+    Self {
+      client: Client::new(),
+      url: url.into(),
+      headers: Vec::new(),
+      timeout: DEFAULT_TIMEOUT,
+    }
+  }
+
+  /// Adds a header to every request, e.g. a shared secret.
+  pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+    self.headers.push((name, value));
+    self
+  }
+
+  /// Sends requests through `client` instead of a private one.
+  pub fn client(mut self, client: Client) -> Self {
+    self.client = client;
+    self
+  }
+
+  /// Per-request timeout (default 10s).
+  pub fn timeout(mut self, d: Duration) -> Self {
+    self.timeout = d;
+    self
+  }
+
+  fn body(purge: &Purge) -> Value {
+    match purge {
+      Purge::All => json!({ "all": true }),
+      Purge::Paths(paths) => json!({ "paths": paths }),
+    }
+  }
+}
+
+#[cfg(feature = "edge-purge")]
+#[async_trait]
+impl EdgePurger for PurgeWebhook {
+  fn name(&self) -> &'static str {
+    "webhook"
+  }
+
+  async fn purge(&self, purge: &Purge) -> Result<(), PurgeError> {
+    let mut req = self.client.post(&self.url).json(&Self::body(purge));
+    for (name, value) in &self.headers {
+      req = req.header(name.clone(), value.clone());
+    }
+    send(req, self.timeout).await
+  }
+}
+
+#[cfg(all(test, feature = "edge-purge"))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cloudflare_batches_files_under_the_origin() {
+    let paths: Vec<String> = (0..31).map(|i| format!("/p/{i}")).collect();
+    let bodies = Cloudflare::bodies("https://example.com/", &Purge::Paths(paths));
+    assert_eq!(bodies.len(), 2);
+    assert_eq!(bodies[0]["files"][0], "https://example.com/p/0");
+    assert_eq!(bodies[1]["files"], json!(["https://example.com/p/30"]));
+    assert_eq!(
+      Cloudflare::bodies("https://example.com", &Purge::All),
+      [json!({ "purge_everything": true })]
+    );
+  }
+
+  #[test]
+  fn webhook_body_lists_paths_or_all() {
+    assert_eq!(
+      PurgeWebhook::body(&Purge::Paths(vec!["/a?x=1".into()])),
+      json!({ "paths": ["/a?x=1"] })
+    );
+    assert_eq!(PurgeWebhook::body(&Purge::All), json!({ "all": true }));
+  }
+}
@@ -0,0 +1,587 @@
+//! The cache plugin itself: janitor and warmer wiring, lookup, background
+//! revalidation, and the stale-if-error fallback.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use anyhow::Result;
+use http::HeaderMap;
+use http::HeaderName;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header::AUTHORIZATION;
+use http::header::ETAG;
+use http::header::IF_NONE_MATCH;
+use http::header::SET_COOKIE;
+use http::header::VARY;
+use http_body::Body as _;
+use http_body_util::BodyExt;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::clock;
+use tako_rs_core::disconnect::DisconnectToken;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::plugins::RouterHandle;
+use tako_rs_core::plugins::TakoPlugin;
+use tako_rs_core::router::Router;
+#[cfg(feature = "signals")]
+use tako_rs_core::signals::Signal;
+#[cfg(feature = "signals")]
+use tako_rs_core::signals::app_events;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::config::CacheBuilder;
+use super::config::Config;
+use super::edge::Purge;
+use super::edge::PurgeError;
+use super::policy::Directives;
+use super::policy::Windows;
+use super::store::CachedEntry;
+use super::store::Freshness;
+use super::store::Store;
+use super::store::filter_headers;
+use super::warmer::CacheWarmer;
+use super::warmer::Warming;
+use crate::middleware::etag::build_304;
+use crate::middleware::etag::make_etag;
+use crate::middleware::etag::weak_match;
+
+/// Response cache plugin. Attach at router or route level.
+#[derive(Clone)]
+#[doc(alias = "cache")]
+#[doc(alias = "swr")]
+pub struct CachePlugin {
+  cfg: Arc<Config>,
+  store: Store,
+  janitor_started: Arc<AtomicBool>,
+  warmers_started: Arc<AtomicBool>,
+  #[cfg(feature = "signals")]
+  listener_started: Arc<AtomicBool>,
+}
+
+impl CachePlugin {
+  pub fn builder() -> CacheBuilder {
+    CacheBuilder::new()
+  }
+  pub fn new(cfg: Config) -> Self {
+    Self {
+      cfg: Arc::new(cfg),
+      store: Store::new(),
+      janitor_started: Arc::new(AtomicBool::new(false)),
+      warmers_started: Arc::new(AtomicBool::new(false)),
+      #[cfg(feature = "signals")]
+      listener_started: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  /// Runs every configured warmer once through `router`, replacing fresh
+  /// entries too. Returns how many warm requests succeeded.
+  ///
+  /// Scheduled warming starts on its own when a server starts; call this to
+  /// warm before serving or from a deploy hook.
+  pub async fn warm(&self, router: &Router) -> usize {
+    let mut ok = 0;
+    for warmer in &self.cfg.warmers {
+      ok += warmer.run(|req| router.dispatch(req)).await.1;
+    }
+    ok
+  }
+
+  /// Drops the cached responses for `paths` and purges them from the edge
+  /// when an [`EdgePurger`](super::EdgePurger) is configured. A path without
+  /// a query string covers every query string and method. Returns how many
+  /// local entries were dropped.
+  ///
+  /// # Errors
+  ///
+  /// Returns the purger's error; the local entries are dropped regardless.
+  pub async fn invalidate<I, S>(&self, paths: I) -> Result<usize, PurgeError>
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    let purge = Purge::Paths(paths.into_iter().map(Into::into).collect());
+    let removed = remove_local(&self.store, &purge);
+    purge_edge(&self.cfg, &purge, removed).await?;
+    Ok(removed)
+  }
+
+  /// Drops every cached response and purges the whole site from the edge.
+  ///
+  /// # Errors
+  ///
+  /// Returns the purger's error; the local entries are dropped regardless.
+  pub async fn invalidate_all(&self) -> Result<usize, PurgeError> {
+    let removed = remove_local(&self.store, &Purge::All);
+    purge_edge(&self.cfg, &Purge::All, removed).await?;
+    Ok(removed)
+  }
+}
+
+impl TakoPlugin for CachePlugin {
+  fn name(&self) -> &'static str {
+    "CachePlugin"
+  }
+
+  fn setup(&self, router: &Router) -> Result<()> {
+    let cfg = self.cfg.clone();
+    let store = self.store.clone();
+    router.middleware(move |req, next| {
+      let cfg = cfg.clone();
+      let store = store.clone();
+      async move { handle(req, next, cfg, store).await }
+    });
+
+    // Like the idempotency janitor, this runs for the life of the runtime.
+    if !self.janitor_started.swap(true, Ordering::SeqCst) {
+      let store = self.store.clone();
+      let interval = self
+        .cfg
+        .ttl
+        .clamp(Duration::from_secs(5), Duration::from_secs(300));
+
+      #[cfg(not(feature = "compio"))]
+      tokio::spawn(async move {
+        let mut tick = tokio::time::interval(interval);
+        loop {
+          tick.tick().await;
+          store.retain_live();
+        }
+      });
+
+      #[cfg(feature = "compio")]
+      compio::runtime::spawn(async move {
+        loop {
+          compio::time::sleep(interval).await;
+          store.retain_live();
+        }
+      })
+      .detach();
+    }
+
+    #[cfg(feature = "signals")]
+    if !self.listener_started.swap(true, Ordering::SeqCst) {
+      let cfg = self.cfg.clone();
+      let store = self.store.clone();
+      app_events().on(super::ids::INVALIDATE, move |signal: Signal| {
+        let cfg = cfg.clone();
+        let store = store.clone();
+        async move {
+          let purge = match signal.metadata.get("paths").map(|p| p.trim()) {
+            None | Some("*") => Purge::All,
+            Some(paths) => Purge::Paths(
+              paths
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect(),
+            ),
+          };
+          let removed = remove_local(&store, &purge);
+          let _ = purge_edge(&cfg, &purge, removed).await;
+        }
+      });
+    }
+
+    Ok(())
+  }
+
+  fn on_start(&self, router: RouterHandle) {
+    if self.warmers_started.swap(true, Ordering::SeqCst) {
+      return;
+    }
+    for warmer in self.cfg.warmers.iter().cloned() {
+      spawn_warmer(warmer, router.clone());
+    }
+  }
+}
+
+fn spawn_warmer(warmer: CacheWarmer, router: RouterHandle) {
+  let interval = warmer.interval.max(Duration::from_secs(1));
+  let run = move || {
+    let warmer = warmer.clone();
+    let router = router.clone();
+    async move {
+      let (requests, ok) = warmer.run(|req| router.dispatch(req)).await;
+      #[cfg(feature = "signals")]
+      app_events()
+        .emit(
+          Signal::new(super::ids::WARMED)
+            .meta("requests", requests.to_string())
+            .meta("ok", ok.to_string()),
+        )
+        .await;
+      #[cfg(not(feature = "signals"))]
+      let _ = (requests, ok);
+    }
+  };
+
+  #[cfg(not(feature = "compio"))]
+  tokio::spawn(async move {
+    // The first tick completes immediately: warm as soon as serving starts.
+    let mut tick = tokio::time::interval(interval);
+    loop {
+      tick.tick().await;
+      run().await;
+    }
+  });
+
+  #[cfg(feature = "compio")]
+  compio::runtime::spawn(async move {
+    loop {
+      run().await;
+      compio::time::sleep(interval).await;
+    }
+  })
+  .detach();
+}
+
+async fn handle(req: Request, next: Next, cfg: Arc<Config>, store: Store) -> Response {
+  if !cfg.methods.iter().any(|m| m == req.method()) {
+    if !cfg.invalidate_on_write || req.method().is_safe() {
+      return next.run(req).await;
+    }
+    let path = req.uri().path().to_string();
+    let resp = next.run(req).await;
+    if resp.status().is_success() {
+      // Local entries go before the response does, so the writer's next
+      // read misses; the CDN round trip happens in the background.
+      let purge = Purge::Paths(vec![path]);
+      let removed = remove_local(&store, &purge);
+      let task = async move {
+        let _ = purge_edge(&cfg, &purge, removed).await;
+      };
+
+      #[cfg(not(feature = "compio"))]
+      tokio::spawn(task);
+
+      #[cfg(feature = "compio")]
+      compio::runtime::spawn(task).detach();
+    }
+    return resp;
+  }
+  let directives = if cfg.respect_cache_control {
+    Directives::parse(req.headers())
+  } else {
+    Directives::default()
+  };
+  if directives.no_store || (!cfg.cache_authorized && req.headers().contains_key(AUTHORIZATION)) {
+    return next.run(req).await;
+  }
+
+  let principal = cfg.partition.as_ref().and_then(|p| p.key(&req));
+  let partitioned = principal.is_some();
+  let key = cache_key(&req, principal.as_deref());
+  let if_none_match = (cfg.etag && matches!(*req.method(), Method::GET | Method::HEAD))
+    .then(|| req.headers().get(IF_NONE_MATCH).cloned())
+    .flatten();
+  let now = clock::now();
+  // Warm requests always reach the handler so they replace fresh entries.
+  let warming = req.extensions().get::<Warming>().is_some();
+  let cached = if directives.no_cache || warming {
+    None
+  } else {
+    store.get(&key).filter(|e| e.matches(req.headers()))
+  };
+
+  if let Some(entry) = &cached {
+    match entry.freshness(now) {
+      Freshness::Fresh => {
+        let resp = not_modified(entry.to_response(now), if_none_match.as_ref());
+        return label(resp, &cfg, "HIT");
+      }
+      Freshness::Revalidate => {
+        if !entry.refreshing.swap(true, Ordering::AcqRel) {
+          spawn_refresh(
+            refresh_request(&req),
+            next,
+            cfg.clone(),
+            store,
+            key,
+            partitioned,
+            entry.clone(),
+          );
+        }
+        let resp = not_modified(entry.to_response(now), if_none_match.as_ref());
+        return label(resp, &cfg, "STALE");
+      }
+      Freshness::StaleIfError | Freshness::Expired => {}
+    }
+  }
+
+  let req_headers = req.headers().clone();
+  let resp = next.run(req).await;
+
+  if resp.status().is_server_error()
+    && let Some(entry) = cached
+  {
+    let now = clock::now();
+    if entry.serves_on_error(now) {
+      #[cfg(feature = "signals")]
+      emit(super::ids::STALE_IF_ERROR, &key, resp.status()).await;
+      return label(entry.to_response(now), &cfg, "STALE");
+    }
+  }
+
+  let (resp, _) = store_response(resp, &req_headers, &cfg, &store, key, partitioned).await;
+  label(not_modified(resp, if_none_match.as_ref()), &cfg, "MISS")
+}
+
+/// `304 Not Modified` in place of `resp` when it is a success whose `ETag`
+/// matches `if_none_match`.
+fn not_modified(resp: Response, if_none_match: Option<&HeaderValue>) -> Response {
+  let matched = resp.status().is_success()
+    && if_none_match
+      .and_then(|v| v.to_str().ok())
+      .zip(resp.headers().get(ETAG).and_then(|v| v.to_str().ok()))
+      .is_some_and(|(inm, etag)| weak_match(inm, etag));
+  if matched {
+    build_304(resp.headers().clone(), None)
+  } else {
+    resp
+  }
+}
+
+/// `METHOD path?query`; the host is included for absolute-form URIs and the
+/// principal, after a space, for partitioned requests.
+fn cache_key(req: &Request, principal: Option<&str>) -> String {
+  let uri = req.uri();
+  let target = uri.path_and_query().map_or("/", |pq| pq.as_str());
+  let mut key = match uri.authority() {
+    Some(authority) => format!("{} {authority}{target}", req.method()),
+    None => format!("{} {target}", req.method()),
+  };
+  if let Some(principal) = principal {
+    key.push(' ');
+    key.push_str(principal);
+  }
+  key
+}
+
+fn label(mut resp: Response, cfg: &Config, value: &'static str) -> Response {
+  if let Some(name) = &cfg.status_header {
+    resp
+      .headers_mut()
+      .insert(name.clone(), HeaderValue::from_static(value));
+  }
+  resp
+}
+
+/// Stores `resp` when it is cacheable and returns it (body re-buffered if it
+/// was read), plus whether an entry was written.
+async fn store_response(
+  resp: Response,
+  req_headers: &HeaderMap,
+  cfg: &Config,
+  store: &Store,
+  key: String,
+  partitioned: bool,
+) -> (Response, bool) {
+  if !cfg.statuses.contains(&resp.status()) || resp.headers().contains_key(SET_COOKIE) {
+    return (resp, false);
+  }
+  let Some(windows) = Windows::for_response(cfg, resp.headers(), partitioned) else {
+    return (resp, false);
+  };
+  let Some(vary) = vary_values(resp.headers(), req_headers) else {
+    return (resp, false);
+  };
+  // Streaming bodies (SSE, unbounded proxies) report no upper bound and are
+  // passed through untouched.
+  match resp.body().size_hint().upper() {
+    Some(n) if n <= cfg.max_body_bytes => {}
+    _ => return (resp, false),
+  }
+
+  let (mut parts, body) = resp.into_parts();
+  let Ok(collected) = body.collect().await else {
+    let mut bad = http::Response::new(TakoBody::empty());
+    *bad.status_mut() = StatusCode::BAD_GATEWAY;
+    return (bad, false);
+  };
+  let body = collected.to_bytes();
+  if cfg.etag
+    && !parts.headers.contains_key(ETAG)
+    && let Ok(etag) = HeaderValue::from_str(&make_etag(&body))
+  {
+    parts.headers.insert(ETAG, etag);
+  }
+  let entry = CachedEntry {
+    status: parts.status,
+    headers: filter_headers(&parts.headers),
+    body: body.clone(),
+    vary,
+    stored_at: clock::now(),
+    windows,
+    refreshing: AtomicBool::new(false),
+  };
+  let stored = store.insert(key, entry, cfg.max_entries);
+  (
+    http::Response::from_parts(parts, TakoBody::from(body)),
+    stored,
+  )
+}
+
+/// The request header values named by `Vary`, or `None` for `Vary: *`.
+fn vary_values(
+  resp_headers: &HeaderMap,
+  req_headers: &HeaderMap,
+) -> Option<Vec<(HeaderName, Option<HeaderValue>)>> {
+  let mut out = Vec::new();
+  for value in resp_headers.get_all(VARY) {
+    for name in value.to_str().unwrap_or_default().split(',') {
+      let name = name.trim();
+      if name == "*" {
+        return None;
+      }
+      if let Ok(name) = HeaderName::from_bytes(name.as_bytes()) {
+        let value = req_headers.get(&name).cloned();
+        out.push((name, value));
+      }
+    }
+  }
+  Some(out)
+}
+
+/// A body-less copy of `req` for the background refresh. The client's
+/// disconnect token is dropped: the refresh must not be cancelled just
+/// because the request that triggered it has finished.
+fn refresh_request(req: &Request) -> Request {
+  let mut out = http::Request::new(TakoBody::empty());
+  *out.method_mut() = req.method().clone();
+  *out.uri_mut() = req.uri().clone();
+  *out.version_mut() = req.version();
+  *out.headers_mut() = req.headers().clone();
+  *out.extensions_mut() = req.extensions().clone();
+  out.extensions_mut().remove::<DisconnectToken>();
+  out
+}
+
+fn spawn_refresh(
+  req: Request,
+  next: Next,
+  cfg: Arc<Config>,
+  store: Store,
+  key: String,
+  partitioned: bool,
+  stale: Arc<CachedEntry>,
+) {
+  let task = async move {
+    let req_headers = req.headers().clone();
+    let resp = next.run(req).await;
+    let status = resp.status();
+    let stored = if status.is_server_error() {
+      false
+    } else {
+      store_response(resp, &req_headers, &cfg, &store, key.clone(), partitioned)
+        .await
+        .1
+    };
+    if !stored {
+      tracing::debug!(%key, %status, "cache: background refresh kept the stale entry");
+      stale.refreshing.store(false, Ordering::Release);
+    }
+    #[cfg(feature = "signals")]
+    {
+      let id = if stored {
+        super::ids::REFRESHED
+      } else {
+        super::ids::REFRESH_FAILED
+      };
+      emit(id, &key, status).await;
+    }
+  };
+
+  #[cfg(not(feature = "compio"))]
+  tokio::spawn(task);
+
+  #[cfg(feature = "compio")]
+  compio::runtime::spawn(task).detach();
+}
+
+fn remove_local(store: &Store, purge: &Purge) -> usize {
+  match purge {
+    Purge::Paths(paths) => store.remove_paths(paths),
+    Purge::All => store.clear(),
+  }
+}
+
+/// Reports an invalidation and forwards it to the edge purger, if any.
+async fn purge_edge(cfg: &Config, purge: &Purge, removed: usize) -> Result<(), PurgeError> {
+  let paths = purge.describe();
+  tracing::debug!(%paths, removed, "cache: invalidated");
+  #[cfg(feature = "signals")]
+  app_events()
+    .emit(
+      Signal::new(super::ids::INVALIDATED)
+        .meta("paths", paths.clone())
+        .meta("removed", removed.to_string()),
+    )
+    .await;
+
+  let Some(purger) = &cfg.edge_purger else {
+    return Ok(());
+  };
+  let result = purger.purge(purge).await;
+  let provider = purger.name();
+  match &result {
+    Ok(()) => tracing::debug!(provider, %paths, "cache: edge purged"),
+    Err(e) => tracing::warn!(provider, %paths, error = %e, "cache: edge purge failed"),
+  }
+  #[cfg(feature = "signals")]
+  {
+    let signal = match &result {
+      Ok(()) => Signal::new(super::ids::EDGE_PURGED),
+      Err(e) => Signal::new(super::ids::EDGE_PURGE_FAILED).meta("error", e.to_string()),
+    };
+    app_events()
+      .emit(signal.meta("provider", provider).meta("paths", paths))
+      .await;
+  }
+  result
+}
+
+#[cfg(feature = "signals")]
+async fn emit(id: &str, key: &str, status: StatusCode) {
+  app_events()
+    .emit(
+      Signal::new(id)
+        .meta("key", key)
+        .meta("status", status.as_u16().to_string()),
+    )
+    .await;
+}
+
+#[cfg(all(test, feature = "signals"))]
+mod tests {
+  use http::Method;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn invalidate_signal_drops_entries() {
+    let mut router = Router::new();
+    router.route(Method::GET, "/news", |_req: Request| async { "news" });
+    router.route(Method::GET, "/about", |_req: Request| async { "about" });
+    CacheBuilder::new().build().setup(&router).unwrap();
+    let x_cache = |uri: &'static str| {
+      let router = &router;
+      async move {
+        let req = http::Request::get(uri).body(TakoBody::empty()).unwrap();
+        router.dispatch(req).await.headers()["x-cache"].clone()
+      }
+    };
+
+    x_cache("/news").await;
+    x_cache("/about").await;
+    app_events()
+      .emit(Signal::new(super::super::ids::INVALIDATE).meta("paths", "/news"))
+      .await;
+    assert_eq!(x_cache("/news").await, "MISS");
+    assert_eq!(x_cache("/about").await, "HIT");
+  }
+}
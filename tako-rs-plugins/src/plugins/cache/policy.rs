@@ -0,0 +1,143 @@
+//! `Cache-Control` parsing and the per-entry lifetime windows derived from it.
+
+use std::time::Duration;
+
+use http::HeaderMap;
+use http::header::CACHE_CONTROL;
+
+use super::config::Config;
+
+/// The `Cache-Control` directives the cache acts on.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Directives {
+  pub(crate) no_store: bool,
+  pub(crate) no_cache: bool,
+  pub(crate) private: bool,
+  /// `s-maxage` when present, otherwise `max-age`.
+  pub(crate) max_age: Option<Duration>,
+  pub(crate) stale_while_revalidate: Option<Duration>,
+  pub(crate) stale_if_error: Option<Duration>,
+}
+
+impl Directives {
+  pub(crate) fn parse(headers: &HeaderMap) -> Self {
+    let mut out = Self::default();
+    let mut s_maxage = None;
+    for value in headers.get_all(CACHE_CONTROL) {
+      let Ok(value) = value.to_str() else {
+        continue;
+      };
+      for directive in value.split(',') {
+        let (name, arg) = match directive.split_once('=') {
+          Some((n, a)) => (n.trim(), Some(a.trim().trim_matches('"'))),
+          None => (directive.trim(), None),
+        };
+        let secs = || {
+          arg
+            .and_then(|a| a.parse::<u64>().ok())
+            .map(Duration::from_secs)
+        };
+        match name.to_ascii_lowercase().as_str() {
+          "no-store" => out.no_store = true,
+          "no-cache" => out.no_cache = true,
+          "private" => out.private = true,
+          "max-age" => out.max_age = secs(),
+          "s-maxage" => s_maxage = secs(),
+          "stale-while-revalidate" => out.stale_while_revalidate = secs(),
+          "stale-if-error" => out.stale_if_error = secs(),
+          _ => {}
+        }
+      }
+    }
+    if s_maxage.is_some() {
+      out.max_age = s_maxage;
+    }
+    out
+  }
+}
+
+/// How long an entry stays fresh, then servable while revalidating, then
+/// servable in place of an error — all measured from the moment it is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Windows {
+  pub(crate) fresh: Duration,
+  pub(crate) stale_while_revalidate: Duration,
+  pub(crate) stale_if_error: Duration,
+}
+
+impl Windows {
+  /// Lifetimes for a response with `headers`, or `None` if it must not be
+  /// stored. `private` responses are only stored in a per-principal
+  /// partition.
+  pub(crate) fn for_response(cfg: &Config, headers: &HeaderMap, partitioned: bool) -> Option<Self> {
+    if !cfg.respect_cache_control {
+      return Some(Self::from_config(cfg));
+    }
+    let d = Directives::parse(headers);
+    if d.no_store || (d.private && !partitioned) || d.no_cache {
+      return None;
+    }
+    Some(Self {
+      fresh: d.max_age.unwrap_or(cfg.ttl),
+      stale_while_revalidate: d
+        .stale_while_revalidate
+        .unwrap_or(cfg.stale_while_revalidate),
+      stale_if_error: d.stale_if_error.unwrap_or(cfg.stale_if_error),
+    })
+  }
+
+  fn from_config(cfg: &Config) -> Self {
+    Self {
+      fresh: cfg.ttl,
+      stale_while_revalidate: cfg.stale_while_revalidate,
+      stale_if_error: cfg.stale_if_error,
+    }
+  }
+
+  /// Age after which the entry is of no further use.
+  pub(crate) fn lifetime(&self) -> Duration {
+    self.fresh + self.stale_while_revalidate.max(self.stale_if_error)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use http::HeaderValue;
+
+  use super::*;
+
+  fn headers(cc: &str) -> HeaderMap {
+    let mut h = HeaderMap::new();
+    h.insert(CACHE_CONTROL, HeaderValue::from_str(cc).unwrap());
+    h
+  }
+
+  #[test]
+  fn response_directives_override_config() {
+    let cfg = Config::default();
+    let w = Windows::for_response(
+      &cfg,
+      &headers("public, max-age=10, s-maxage=20, stale-while-revalidate=30, stale-if-error=\"40\""),
+      false,
+    )
+    .unwrap();
+    assert_eq!(w.fresh, Duration::from_secs(20));
+    assert_eq!(w.stale_while_revalidate, Duration::from_secs(30));
+    assert_eq!(w.stale_if_error, Duration::from_secs(40));
+    assert_eq!(w.lifetime(), Duration::from_secs(60));
+  }
+
+  #[test]
+  fn private_and_no_store_are_not_cached() {
+    let cfg = Config::default();
+    assert!(Windows::for_response(&cfg, &headers("private, max-age=60"), false).is_none());
+    assert!(Windows::for_response(&cfg, &headers("private, max-age=60"), true).is_some());
+    assert!(Windows::for_response(&cfg, &headers("No-Store"), true).is_none());
+    assert_eq!(
+      Windows::for_response(&cfg, &HeaderMap::new(), false)
+        .unwrap()
+        .fresh,
+      cfg.ttl
+    );
+  }
+}
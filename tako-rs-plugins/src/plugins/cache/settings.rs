@@ -0,0 +1,124 @@
+//! Serde-loadable response cache settings.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::config::CacheBuilder;
+use super::config::Config;
+use super::plugin::CachePlugin;
+use crate::plugins::settings;
+use crate::plugins::settings::SettingsError;
+
+const PLUGIN: &str = "cache";
+
+/// Response cache settings as read from a configuration file.
+///
+/// Warmers, edge purgers and partition keys are code-only: add them to the
+/// builder returned by [`Settings::builder`].
+///
+/// ```toml
+/// ttl_secs = 300
+/// stale_while_revalidate_secs = 30
+/// statuses = [200, 404]
+/// status_header = ""   # no X-Cache header
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Settings {
+  /// Methods whose responses are cached.
+  pub methods: Vec<String>,
+  /// Cacheable statuses.
+  pub statuses: Vec<u16>,
+  /// Freshness lifetime in seconds when the response sets none.
+  pub ttl_secs: u64,
+  /// Seconds a stale entry is served while it is refreshed.
+  pub stale_while_revalidate_secs: u64,
+  /// Seconds a stale entry may stand in for a `5xx`.
+  pub stale_if_error_secs: u64,
+  /// Honour `Cache-Control`.
+  pub respect_cache_control: bool,
+  /// Largest body cached, in bytes.
+  pub max_body_bytes: u64,
+  /// Most entries stored.
+  pub max_entries: usize,
+  /// Header reporting `HIT` / `STALE` / `MISS`; empty disables it.
+  pub status_header: String,
+  /// Invalidate a path after a successful write to it.
+  pub invalidate_on_write: bool,
+  /// Add weak `ETag`s and answer `If-None-Match`.
+  pub etag: bool,
+  /// Cache requests that carry `Authorization`.
+  pub cache_authorized: bool,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    let cfg = Config::default();
+    Self {
+      methods: cfg.methods.iter().map(ToString::to_string).collect(),
+      statuses: cfg.statuses.iter().map(http::StatusCode::as_u16).collect(),
+      ttl_secs: cfg.ttl.as_secs(),
+      stale_while_revalidate_secs: cfg.stale_while_revalidate.as_secs(),
+      stale_if_error_secs: cfg.stale_if_error.as_secs(),
+      respect_cache_control: cfg.respect_cache_control,
+      max_body_bytes: cfg.max_body_bytes,
+      max_entries: cfg.max_entries,
+      status_header: cfg.status_header.map(|h| h.to_string()).unwrap_or_default(),
+      invalidate_on_write: cfg.invalidate_on_write,
+      etag: cfg.etag,
+      cache_authorized: cfg.cache_authorized,
+    }
+  }
+}
+
+impl Settings {
+  /// Validates the settings into a builder.
+  pub fn builder(&self) -> Result<CacheBuilder, SettingsError> {
+    if self.ttl_secs == 0 {
+      return Err(SettingsError::new(
+        PLUGIN,
+        "ttl_secs",
+        "must be greater than 0",
+      ));
+    }
+    if self.max_entries == 0 {
+      return Err(SettingsError::new(
+        PLUGIN,
+        "max_entries",
+        "must be greater than 0",
+      ));
+    }
+    let methods = settings::methods(PLUGIN, "methods", &self.methods)?;
+    let statuses = self
+      .statuses
+      .iter()
+      .map(|code| settings::status(PLUGIN, "statuses", *code))
+      .collect::<Result<Vec<_>, _>>()?;
+    let status_header = match self.status_header.as_str() {
+      "" => None,
+      name => Some(settings::header_name(PLUGIN, "status_header", name)?),
+    };
+    Ok(
+      CacheBuilder::new()
+        .methods(&methods)
+        .statuses(&statuses)
+        .ttl(Duration::from_secs(self.ttl_secs))
+        .stale_while_revalidate(Duration::from_secs(self.stale_while_revalidate_secs))
+        .stale_if_error(Duration::from_secs(self.stale_if_error_secs))
+        .respect_cache_control(self.respect_cache_control)
+        .max_body_bytes(self.max_body_bytes)
+        .max_entries(self.max_entries)
+        .status_header(status_header)
+        .invalidate_on_write(self.invalidate_on_write)
+        .etag(self.etag)
+        .cache_authorized(self.cache_authorized),
+    )
+  }
+
+  /// Validates the settings into the plugin.
+  pub fn build(&self) -> Result<CachePlugin, SettingsError> {
+    self.builder().map(CacheBuilder::build)
+  }
+}
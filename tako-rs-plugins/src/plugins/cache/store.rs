@@ -0,0 +1,178 @@
+//! In-memory response store and the replay of a stored entry.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+
+use bytes::Bytes;
+use http::HeaderMap;
+use http::HeaderName;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::AGE;
+use http::header::CONTENT_LENGTH;
+use scc::HashMap as SccHashMap;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::clock;
+use tako_rs_core::types::Response;
+
+use super::policy::Windows;
+
+/// Where an entry sits on its lifetime at a given instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Freshness {
+  Fresh,
+  /// Past freshness but inside `stale-while-revalidate`.
+  Revalidate,
+  /// Only usable in place of a `5xx`.
+  StaleIfError,
+  Expired,
+}
+
+pub(crate) struct CachedEntry {
+  pub(crate) status: StatusCode,
+  pub(crate) headers: Vec<(HeaderName, HeaderValue)>,
+  pub(crate) body: Bytes,
+  /// Request header values named by the response's `Vary`, as seen on the
+  /// request that produced the entry.
+  pub(crate) vary: Vec<(HeaderName, Option<HeaderValue>)>,
+  pub(crate) stored_at: Instant,
+  pub(crate) windows: Windows,
+  /// Claimed by the one request that starts a background refresh, so a burst
+  /// of stale hits triggers a single handler run.
+  pub(crate) refreshing: AtomicBool,
+}
+
+impl CachedEntry {
+  pub(crate) fn freshness(&self, now: Instant) -> Freshness {
+    let age = now.saturating_duration_since(self.stored_at);
+    let w = &self.windows;
+    if age < w.fresh {
+      Freshness::Fresh
+    } else if age < w.fresh + w.stale_while_revalidate {
+      Freshness::Revalidate
+    } else if age < w.fresh + w.stale_if_error {
+      Freshness::StaleIfError
+    } else {
+      Freshness::Expired
+    }
+  }
+
+  /// Whether the entry may replace a `5xx` response at `now`.
+  pub(crate) fn serves_on_error(&self, now: Instant) -> bool {
+    now.saturating_duration_since(self.stored_at) < self.windows.fresh + self.windows.stale_if_error
+  }
+
+  /// Whether `headers` agree with the request that produced the entry on
+  /// every header the response varies on.
+  pub(crate) fn matches(&self, headers: &HeaderMap) -> bool {
+    self
+      .vary
+      .iter()
+      .all(|(name, value)| headers.get(name) == value.as_ref())
+  }
+
+  pub(crate) fn to_response(&self, now: Instant) -> Response {
+    let mut resp = http::Response::new(TakoBody::from(self.body.clone()));
+    *resp.status_mut() = self.status;
+    let headers = resp.headers_mut();
+    for (k, v) in &self.headers {
+      headers.append(k, v.clone());
+    }
+    let age = now.saturating_duration_since(self.stored_at).as_secs();
+    headers.insert(AGE, HeaderValue::from(age));
+    resp
+  }
+}
+
+/// Drops headers that must not be replayed from a stored response.
+pub(crate) fn filter_headers(src: &HeaderMap) -> Vec<(HeaderName, HeaderValue)> {
+  // Hop-by-hop headers (RFC 9110 §7.6.1), plus `Content-Length` (the body
+  // layer recomputes it) and `Age` (recomputed on every replay).
+  const DENY: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "age",
+  ];
+  src
+    .iter()
+    .filter(|(name, _)| *name != CONTENT_LENGTH && !DENY.contains(&name.as_str()))
+    .map(|(name, v)| (name.clone(), v.clone()))
+    .collect()
+}
+
+#[derive(Clone)]
+pub(crate) struct Store(Arc<SccHashMap<String, Arc<CachedEntry>>>);
+
+impl Store {
+  pub(crate) fn new() -> Self {
+    Self(Arc::new(SccHashMap::new()))
+  }
+
+  pub(crate) fn get(&self, k: &str) -> Option<Arc<CachedEntry>> {
+    self.0.get_sync(k).map(|e| e.clone())
+  }
+
+  /// Stores `entry`, replacing any previous one for `k`. New keys are refused
+  /// once `max_entries` is reached.
+  pub(crate) fn insert(&self, k: String, entry: CachedEntry, max_entries: usize) -> bool {
+    if self.0.len() >= max_entries && !self.0.contains_sync(&k) {
+      return false;
+    }
+    self.0.upsert_sync(k, Arc::new(entry));
+    true
+  }
+
+  /// Drops entries for any of `paths`, returning how many were removed. A
+  /// path without a query matches every query string and method.
+  pub(crate) fn remove_paths(&self, paths: &[String]) -> usize {
+    let mut removed = 0;
+    self.0.retain_sync(|k, _| {
+      let target = key_target(k);
+      let hit = paths.iter().any(|p| {
+        if p.contains('?') {
+          target == p
+        } else {
+          target.split('?').next() == Some(p.as_str())
+        }
+      });
+      removed += usize::from(hit);
+      !hit
+    });
+    removed
+  }
+
+  /// Drops every entry, returning how many there were.
+  pub(crate) fn clear(&self) -> usize {
+    let mut removed = 0;
+    self.0.retain_sync(|_, _| {
+      removed += 1;
+      false
+    });
+    removed
+  }
+
+  pub(crate) fn retain_live(&self) {
+    let now = clock::now();
+    self
+      .0
+      .retain_sync(|_, e| now.saturating_duration_since(e.stored_at) < e.windows.lifetime());
+  }
+}
+
+/// The `path?query` part of a cache key, without the method, authority, or
+/// partition.
+fn key_target(key: &str) -> &str {
+  let target = key.split_once(' ').map_or(key, |(_, t)| t);
+  let target = target.split_once(' ').map_or(target, |(t, _)| t);
+  match target.find('/') {
+    Some(i) => &target[i..],
+    None => target,
+  }
+}
@@ -0,0 +1,80 @@
+//! Scheduled cache warmers: synthetic requests that pre-populate the cache.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+/// Produces the requests one warming run dispatches.
+pub type WarmRequests = Arc<dyn Fn() -> Vec<Request> + Send + Sync>;
+
+/// Marks a request as coming from a warmer. The cache middleware skips the
+/// lookup for it, so a still-fresh entry is replaced rather than replayed.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Warming;
+
+/// A set of synthetic requests dispatched through the router on a fixed
+/// interval so their responses land in the cache before clients ask.
+///
+/// The first run happens as soon as the server starts; later runs follow
+/// every `interval`. Requests go through the full router, so middleware
+/// registered before the cache (auth, tenant resolution) sees them too.
+#[derive(Clone)]
+pub struct CacheWarmer {
+  pub(crate) interval: Duration,
+  pub(crate) requests: WarmRequests,
+}
+
+impl CacheWarmer {
+  /// Runs `requests` every `interval`.
+  pub fn every<F>(interval: Duration, requests: F) -> Self
+  where
+    F: Fn() -> Vec<Request> + Send + Sync + 'static,
+  {
+    Self {
+      interval,
+      requests: Arc::new(requests),
+    }
+  }
+
+  /// Warms plain `GET` requests for `paths` every `interval`.
+  pub fn paths<I, S>(interval: Duration, paths: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    let paths: Vec<String> = paths.into_iter().map(Into::into).collect();
+    Self::every(interval, move || {
+      paths
+        .iter()
+        .filter_map(|p| http::Request::get(p.as_str()).body(TakoBody::empty()).ok())
+        .collect()
+    })
+  }
+
+  /// Dispatches one run through `dispatch`; returns how many requests were
+  /// sent and how many of their responses were successful.
+  pub(crate) async fn run<F, Fut>(&self, dispatch: F) -> (usize, usize)
+  where
+    F: Fn(Request) -> Fut,
+    Fut: Future<Output = Response>,
+  {
+    let requests = (self.requests)();
+    let total = requests.len();
+    let mut ok = 0;
+    for mut req in requests {
+      req.extensions_mut().insert(Warming);
+      let uri = req.uri().clone();
+      let resp = dispatch(req).await;
+      if resp.status().is_success() {
+        ok += 1;
+      } else {
+        tracing::debug!(%uri, status = %resp.status(), "cache: warm request failed");
+      }
+    }
+    (total, ok)
+  }
+}
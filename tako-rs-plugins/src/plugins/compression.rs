@@ -58,11 +58,15 @@ mod encoding;
 pub mod gzip_stream;
 mod negotiate;
 mod plugin;
+mod settings;
 pub mod zstd_stream;
 
 pub use builder::CompressionBuilder;
+pub use config::BodilessMode;
 pub use config::Config;
 pub use config::ContentTypePolicy;
+pub use config::SkipPredicate;
 pub use encoding::Encoding;
 pub use plugin::CompressionPlugin;
 pub use plugin::CompressionResponse;
+pub use settings::Settings;
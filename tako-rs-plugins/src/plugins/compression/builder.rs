@@ -1,5 +1,11 @@
 //! Fluent builder for assembling a [`CompressionPlugin`](super::plugin::CompressionPlugin).
 
+use std::sync::Arc;
+
+use http::HeaderMap;
+use http::StatusCode;
+
+use super::config::BodilessMode;
 use super::config::Config;
 use super::config::ContentTypePolicy;
 use super::encoding::Encoding;
@@ -145,6 +151,36 @@ impl CompressionBuilder {
     self
   }
 
+  /// Adds a predicate that opts matching responses out of compression.
+  ///
+  /// Runs after the built-in skip rules (non-2xx, already encoded, partial
+  /// content). Calling this more than once combines the predicates: a
+  /// response is skipped if any of them returns `true`.
+  ///
+  /// ```rust
+  /// use tako::plugins::compression::CompressionBuilder;
+  ///
+  /// let plugin = CompressionBuilder::new()
+  ///     .skip_when(|_, headers| headers.contains_key("x-signature"))
+  ///     .build();
+  /// ```
+  pub fn skip_when<F>(mut self, f: F) -> Self
+  where
+    F: Fn(StatusCode, &HeaderMap) -> bool + Send + Sync + 'static,
+  {
+    self.0.skip = Some(match self.0.skip.take() {
+      Some(prev) => Arc::new(move |status, headers| prev(status, headers) || f(status, headers)),
+      None => Arc::new(f),
+    });
+    self
+  }
+
+  /// Chooses how `HEAD` and `304 Not Modified` responses are handled.
+  pub fn bodiless(mut self, mode: BodilessMode) -> Self {
+    self.0.bodiless = mode;
+    self
+  }
+
   /// Builds the compression plugin with the configured settings.
   pub fn build(self) -> CompressionPlugin {
     CompressionPlugin { cfg: self.0 }
@@ -1,7 +1,36 @@
 //! Compression configuration: content-type policy and runtime settings.
 
+use std::sync::Arc;
+
+use http::HeaderMap;
+use http::StatusCode;
+
 use super::encoding::Encoding;
 
+/// Caller-supplied rule that vetoes compression for a response.
+///
+/// Receives the response status and headers after the handler ran; returning
+/// `true` sends the response uncompressed.
+pub type SkipPredicate = Arc<dyn Fn(StatusCode, &HeaderMap) -> bool + Send + Sync + 'static>;
+
+/// How the plugin treats responses that carry no body on the wire: `HEAD`
+/// responses and `304 Not Modified`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BodilessMode {
+  /// Send the headers the matching `GET` / `200` would carry (default).
+  ///
+  /// Both get `Vary: Accept-Encoding`. A `HEAD` response also gets the
+  /// negotiated `Content-Encoding`; its `Content-Length` is the encoded
+  /// length when the handler produced the full body, and is dropped when
+  /// only the identity length is known. A `304` never gets a
+  /// `Content-Encoding`, and no body is compressed for either.
+  #[default]
+  Mirror,
+  /// Leave `HEAD` and `304` responses exactly as the handler produced them.
+  Skip,
+}
+
 /// Content-type matching policy.
 #[derive(Clone, Default)]
 pub enum ContentTypePolicy {
@@ -63,6 +92,32 @@ pub struct Config {
   /// [`CompressionBuilder::protect_sensitive`](super::builder::CompressionBuilder::protect_sensitive) when you have other
   /// mitigations (e.g. per-response random padding or rotated CSRF tokens).
   pub protect_sensitive: bool,
+  /// Extra skip rule evaluated after the built-in ones (non-2xx, already
+  /// encoded, `206 Partial Content` / `Content-Range`). Use it to exempt
+  /// responses such as signed downloads whose bytes must reach the client
+  /// verbatim.
+  pub skip: Option<SkipPredicate>,
+  /// Treatment of `HEAD` and `304 Not Modified` responses.
+  pub bodiless: BodilessMode,
+}
+
+impl Config {
+  /// Returns true when the response must be sent as-is.
+  ///
+  /// Range responses are always skipped: `Content-Range` offsets refer to the
+  /// identity representation, so compressing the slice would corrupt them.
+  pub(crate) fn should_skip(&self, status: StatusCode, headers: &HeaderMap) -> bool {
+    if !(status.is_success() || status == StatusCode::NOT_MODIFIED) {
+      return true;
+    }
+    if status == StatusCode::PARTIAL_CONTENT
+      || headers.contains_key(http::header::CONTENT_RANGE)
+      || headers.contains_key(http::header::CONTENT_ENCODING)
+    {
+      return true;
+    }
+    self.skip.as_ref().is_some_and(|f| f(status, headers))
+  }
 }
 
 impl Default for Config {
@@ -79,6 +134,8 @@ impl Default for Config {
       stream: false,
       content_types: ContentTypePolicy::default(),
       protect_sensitive: true,
+      skip: None,
+      bodiless: BodilessMode::default(),
     }
   }
 }
@@ -1,11 +1,16 @@
 //! Supported HTTP compression encodings and their header identities.
 
 /// Supported HTTP compression encoding algorithms.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///
+/// Deserializes from its `Content-Encoding` token (`gzip`, `br`, `deflate`,
+/// `zstd`); `brotli` is accepted as well.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Encoding {
   /// Gzip compression (RFC 1952) - widely supported, good compression ratio.
   Gzip,
   /// Brotli compression (RFC 7932) - excellent compression ratio, modern browsers.
+  #[serde(rename = "br", alias = "brotli")]
   Brotli,
   /// DEFLATE compression (RFC 1951) - fast compression, good compatibility.
   Deflate,
@@ -4,12 +4,14 @@ use anyhow::Result;
 use async_trait::async_trait;
 use bytes::Bytes;
 use http::HeaderValue;
+use http::Method;
 use http::StatusCode;
 use http::header::ACCEPT_ENCODING;
 use http::header::CONTENT_ENCODING;
 use http::header::CONTENT_LENGTH;
 use http::header::CONTENT_TYPE;
 use http::header::VARY;
+use http_body::Body as _;
 use http_body_util::BodyExt;
 use tako_rs_core::body::TakoBody;
 use tako_rs_core::middleware::Next;
@@ -20,6 +22,7 @@ use tako_rs_core::types::Request;
 use tako_rs_core::types::Response;
 
 use super::brotli_stream::stream_brotli;
+use super::config::BodilessMode;
 use super::config::Config;
 use super::deflate_stream::stream_deflate;
 use super::encoder::compress_brotli;
@@ -107,6 +110,11 @@ impl TakoPlugin for CompressionPlugin {
     "CompressionPlugin"
   }
 
+  /// Runs outside `ETag` and other validators so they see the uncompressed body.
+  fn priority(&self) -> i32 {
+    tako_rs_core::middleware::priority::COMPRESSION
+  }
+
   /// Sets up the compression plugin by registering middleware with the router.
   fn setup(&self, router: &Router) -> Result<()> {
     let cfg = self.cfg.clone();
@@ -142,18 +150,15 @@ async fn compress_middleware(req: Request, next: Next, cfg: Config) -> impl Resp
     .unwrap_or("")
     .to_ascii_lowercase();
   let request_is_authenticated = cfg.protect_sensitive && request_carries_credentials(&req);
+  let is_head = req.method() == Method::HEAD;
 
   // Process the request and get the response.
   let mut resp = next.run(req).await;
   let chosen = choose_encoding(&accepted, &cfg.enabled);
 
-  // Skip compression for non-successful responses or if already encoded.
-  let status = resp.status();
-  if !(status.is_success() || status == StatusCode::NOT_MODIFIED) {
-    return resp.into_response();
-  }
-
-  if resp.headers().contains_key(CONTENT_ENCODING) {
+  // Skip compression for non-successful, already encoded, or partial
+  // responses, plus anything the caller's predicate rejects.
+  if cfg.should_skip(resp.status(), resp.headers()) {
     return resp.into_response();
   }
 
@@ -175,6 +180,10 @@ async fn compress_middleware(req: Request, next: Next, cfg: Config) -> impl Resp
     }
   }
 
+  if is_head || resp.status() == StatusCode::NOT_MODIFIED {
+    return bodiless_response(resp, is_head, chosen, &cfg).await;
+  }
+
   // The response is now compression-eligible. Always advertise that the
   // representation depends on `Accept-Encoding` so caches don't serve a
   // wrongly-encoded variant to a peer with different `Accept-Encoding`.
@@ -217,14 +226,7 @@ async fn compress_middleware(req: Request, next: Next, cfg: Config) -> impl Resp
   // fail. Track success explicitly and only advertise the encoding when
   // the compressed buffer was produced.
   if let Some(enc) = chosen {
-    let compressed = match enc {
-      Encoding::Gzip => compress_gzip(&body_bytes, cfg.gzip_level).ok(),
-      Encoding::Brotli => compress_brotli(&body_bytes, cfg.brotli_level).ok(),
-      Encoding::Deflate => compress_deflate(&body_bytes, cfg.deflate_level).ok(),
-      #[cfg(feature = "zstd")]
-      Encoding::Zstd => compress_zstd(&body_bytes, cfg.zstd_level).ok(),
-    };
-    if let Some(buf) = compressed {
+    if let Some(buf) = encode(enc, &body_bytes, &cfg) {
       *resp.body_mut() = TakoBody::from(Bytes::from(buf));
       resp
         .headers_mut()
@@ -270,18 +272,15 @@ pub(crate) async fn compress_stream_middleware(
     .unwrap_or("")
     .to_ascii_lowercase();
   let request_is_authenticated = cfg.protect_sensitive && request_carries_credentials(&req);
+  let is_head = req.method() == Method::HEAD;
 
   // Process the request and get the response.
   let mut resp = next.run(req).await;
   let chosen = choose_encoding(&accepted, &cfg.enabled);
 
-  // Skip compression for non-successful responses or if already encoded.
-  let status = resp.status();
-  if !(status.is_success() || status == StatusCode::NOT_MODIFIED) {
-    return resp.into_response();
-  }
-
-  if resp.headers().contains_key(CONTENT_ENCODING) {
+  // Skip compression for non-successful, already encoded, or partial
+  // responses, plus anything the caller's predicate rejects.
+  if cfg.should_skip(resp.status(), resp.headers()) {
     return resp.into_response();
   }
 
@@ -300,6 +299,10 @@ pub(crate) async fn compress_stream_middleware(
     }
   }
 
+  if is_head || resp.status() == StatusCode::NOT_MODIFIED {
+    return bodiless_response(resp, is_head, chosen, &cfg).await;
+  }
+
   // The response is compression-eligible: advertise Vary regardless of whether we
   // actually apply an encoding, so caches key on `Accept-Encoding`.
   ensure_vary_accept_encoding(resp.headers_mut());
@@ -334,6 +337,91 @@ pub(crate) async fn compress_stream_middleware(
   resp.into_response()
 }
 
+/// Compresses `bytes` in one shot; `None` if the encoder failed.
+fn encode(enc: Encoding, bytes: &[u8], cfg: &Config) -> Option<Vec<u8>> {
+  match enc {
+    Encoding::Gzip => compress_gzip(bytes, cfg.gzip_level).ok(),
+    Encoding::Brotli => compress_brotli(bytes, cfg.brotli_level).ok(),
+    Encoding::Deflate => compress_deflate(bytes, cfg.deflate_level).ok(),
+    #[cfg(feature = "zstd")]
+    Encoding::Zstd => compress_zstd(bytes, cfg.zstd_level).ok(),
+  }
+}
+
+/// Finishes a compression-eligible `HEAD` or `304` response according to
+/// [`BodilessMode`]. Neither sends a body, so nothing is streamed; for `HEAD`
+/// the body a handler may have built is only used to report the encoded
+/// `Content-Length` a `GET` would have had.
+async fn bodiless_response(
+  mut resp: Response,
+  is_head: bool,
+  chosen: Option<Encoding>,
+  cfg: &Config,
+) -> Response {
+  if cfg.bodiless == BodilessMode::Skip {
+    return resp;
+  }
+  ensure_vary_accept_encoding(resp.headers_mut());
+  if !is_head {
+    // A 304 describes the cached representation; it must not claim an
+    // encoding of its own.
+    return resp;
+  }
+  let Some(enc) = chosen else {
+    return resp;
+  };
+
+  let body = std::mem::replace(resp.body_mut(), TakoBody::empty());
+  let bytes = if body.size_hint().upper().is_some() {
+    body
+      .collect()
+      .await
+      .map(http_body_util::Collected::to_bytes)
+      .unwrap_or_default()
+  } else {
+    Bytes::new()
+  };
+
+  let identity_len = if bytes.is_empty() {
+    resp
+      .headers()
+      .get(CONTENT_LENGTH)
+      .and_then(|v| v.to_str().ok())
+      .and_then(|v| v.parse::<usize>().ok())
+  } else {
+    Some(bytes.len())
+  };
+  // Mirror the GET decision: below `min_size` the identity representation
+  // (and its length) stands.
+  if identity_len.is_some_and(|n| n < cfg.min_size) {
+    return resp;
+  }
+
+  let encoded_len = if bytes.is_empty() {
+    None
+  } else {
+    match encode(enc, &bytes, cfg) {
+      Some(buf) => Some(buf.len()),
+      // The GET path serves identity when the encoder fails; so does HEAD.
+      None => return resp,
+    }
+  };
+  resp
+    .headers_mut()
+    .insert(CONTENT_ENCODING, HeaderValue::from_static(enc.as_str()));
+  match encoded_len {
+    Some(n) => {
+      resp
+        .headers_mut()
+        .insert(CONTENT_LENGTH, HeaderValue::from(n));
+    }
+    None => {
+      resp.headers_mut().remove(CONTENT_LENGTH);
+    }
+  }
+  resp
+}
+
 /// Returns true if the request carries credentials that would make its
 /// response a CRIME/BREACH target. The check is intentionally broad: any
 /// auth header or cookie is treated as authenticated.
@@ -0,0 +1,132 @@
+//! Serde-loadable compression settings.
+
+use serde::Deserialize;
+
+use super::builder::CompressionBuilder;
+use super::config::BodilessMode;
+use super::config::Config;
+use super::config::ContentTypePolicy;
+use super::encoding::Encoding;
+use super::plugin::CompressionPlugin;
+use crate::plugins::settings::SettingsError;
+use crate::plugins::settings::in_range;
+
+const PLUGIN: &str = "compression";
+
+/// Compression settings as read from a configuration file.
+///
+/// ```toml
+/// encodings = ["br", "gzip"]
+/// min_size = 512
+/// gzip_level = 6
+/// content_type_prefixes = ["text/", "application/json"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+  /// Enabled encodings in preference order.
+  pub encodings: Vec<Encoding>,
+  /// Minimum response size in bytes.
+  pub min_size: usize,
+  /// Gzip level, 1-9.
+  pub gzip_level: u32,
+  /// Brotli level, 1-11.
+  pub brotli_level: u32,
+  /// DEFLATE level, 1-9.
+  pub deflate_level: u32,
+  /// Zstandard level, 1-22.
+  #[cfg(feature = "zstd")]
+  pub zstd_level: i32,
+  /// Compress streaming instead of buffering.
+  pub stream: bool,
+  /// The CRIME / BREACH mitigation.
+  pub protect_sensitive: bool,
+  /// Exact content types to compress. Exclusive with
+  /// `content_type_prefixes`; neither means the default heuristic.
+  pub content_types: Option<Vec<String>>,
+  /// Content-type prefixes to compress.
+  pub content_type_prefixes: Option<Vec<String>>,
+  /// Treatment of `HEAD` and `304` responses: `mirror` or `skip`.
+  pub bodiless: BodilessMode,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    let cfg = Config::default();
+    Self {
+      encodings: cfg.enabled,
+      min_size: cfg.min_size,
+      gzip_level: cfg.gzip_level,
+      brotli_level: cfg.brotli_level,
+      deflate_level: cfg.deflate_level,
+      #[cfg(feature = "zstd")]
+      zstd_level: cfg.zstd_level,
+      stream: cfg.stream,
+      protect_sensitive: cfg.protect_sensitive,
+      content_types: None,
+      content_type_prefixes: None,
+      bodiless: cfg.bodiless,
+    }
+  }
+}
+
+impl Settings {
+  /// Validates the settings into a builder.
+  pub fn builder(&self) -> Result<CompressionBuilder, SettingsError> {
+    let policy = match (&self.content_types, &self.content_type_prefixes) {
+      (Some(_), Some(_)) => {
+        return Err(SettingsError::new(
+          PLUGIN,
+          "content_types",
+          "set either content_types or content_type_prefixes, not both",
+        ));
+      }
+      (Some(exact), None) => ContentTypePolicy::Exact(exact.clone()),
+      (None, Some(prefixes)) => ContentTypePolicy::Prefix(prefixes.clone()),
+      (None, None) => ContentTypePolicy::Default,
+    };
+
+    let mut builder = CompressionBuilder::new()
+      .enable_gzip(false)
+      .enable_brotli(false)
+      .enable_deflate(false);
+    #[cfg(feature = "zstd")]
+    {
+      builder = builder.enable_zstd(false);
+    }
+    for encoding in &self.encodings {
+      builder = match encoding {
+        Encoding::Gzip => builder.enable_gzip(true),
+        Encoding::Brotli => builder.enable_brotli(true),
+        Encoding::Deflate => builder.enable_deflate(true),
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd => builder.enable_zstd(true),
+      };
+    }
+
+    builder = builder
+      .min_size(self.min_size)
+      .gzip_level(in_range(PLUGIN, "gzip_level", self.gzip_level, 1..=9)?)
+      .brotli_level(in_range(PLUGIN, "brotli_level", self.brotli_level, 1..=11)?)
+      .deflate_level(in_range(
+        PLUGIN,
+        "deflate_level",
+        self.deflate_level,
+        1..=9,
+      )?)
+      .enable_stream(self.stream)
+      .protect_sensitive(self.protect_sensitive)
+      .content_types(policy)
+      .bodiless(self.bodiless);
+    #[cfg(feature = "zstd")]
+    {
+      builder = builder.zstd_level(in_range(PLUGIN, "zstd_level", self.zstd_level, 1..=22)?);
+    }
+    Ok(builder)
+  }
+
+  /// Validates the settings into the plugin.
+  pub fn build(&self) -> Result<CompressionPlugin, SettingsError> {
+    self.builder().map(CompressionBuilder::build)
+  }
+}
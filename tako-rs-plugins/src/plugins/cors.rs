@@ -52,9 +52,11 @@ mod config;
 mod middleware;
 mod origin;
 mod plugin;
+mod settings;
 
 pub use builder::CorsBuilder;
 pub use config::Config;
 pub use config::CorsConfigError;
 pub use origin::OriginMatcher;
 pub use plugin::CorsPlugin;
+pub use settings::Settings;
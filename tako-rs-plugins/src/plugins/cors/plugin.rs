@@ -54,6 +54,11 @@ impl TakoPlugin for CorsPlugin {
     "CorsPlugin"
   }
 
+  /// Runs outside authentication so preflights are answered first.
+  fn priority(&self) -> i32 {
+    tako_rs_core::middleware::priority::CORS
+  }
+
   /// Sets up the CORS plugin by registering middleware with the router.
   fn setup(&self, router: &Router) -> Result<()> {
     let cfg = self.cfg.clone();
@@ -0,0 +1,96 @@
+//! Serde-loadable CORS settings.
+
+use serde::Deserialize;
+
+use super::builder::CorsBuilder;
+use super::config::Config;
+use super::config::CorsConfigError;
+use super::plugin::CorsPlugin;
+use crate::plugins::settings;
+use crate::plugins::settings::SettingsError;
+
+const PLUGIN: &str = "cors";
+
+/// CORS settings as read from a configuration file.
+///
+/// ```toml
+/// origins = ["https://app.example.com"]
+/// origin_suffixes = [".example.com"]
+/// methods = ["GET", "POST"]
+/// headers = ["x-api-key"]
+/// allow_credentials = true
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+  /// Exact allowed origins; empty with no suffixes allows any origin.
+  pub origins: Vec<String>,
+  /// Allowed origin suffixes, e.g. `.example.com`.
+  pub origin_suffixes: Vec<String>,
+  /// Allowed methods.
+  pub methods: Vec<String>,
+  /// Allowed request headers.
+  pub headers: Vec<String>,
+  /// Allow credentials; requires an explicit origin or suffix.
+  pub allow_credentials: bool,
+  /// Preflight cache lifetime in seconds.
+  pub max_age_secs: u32,
+  /// Private Network Access preflight handling.
+  pub allow_private_network: bool,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    let cfg = Config::default();
+    Self {
+      origins: cfg.origins,
+      origin_suffixes: Vec::new(),
+      methods: cfg.methods.iter().map(ToString::to_string).collect(),
+      headers: Vec::new(),
+      allow_credentials: cfg.allow_credentials,
+      max_age_secs: cfg.max_age_secs.unwrap_or(3600),
+      allow_private_network: cfg.allow_private_network,
+    }
+  }
+}
+
+impl Settings {
+  /// Validates the settings into a builder.
+  pub fn builder(&self) -> Result<CorsBuilder, SettingsError> {
+    if self.allow_credentials && self.origins.is_empty() && self.origin_suffixes.is_empty() {
+      return Err(SettingsError::new(
+        PLUGIN,
+        "allow_credentials",
+        CorsConfigError::CredentialsWithWildcardOrigin.to_string(),
+      ));
+    }
+    let methods = settings::methods(PLUGIN, "methods", &self.methods)?;
+    let headers = self
+      .headers
+      .iter()
+      .map(|h| settings::header_name(PLUGIN, "headers", h))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut builder = CorsBuilder::new()
+      .allow_methods(&methods)
+      .allow_headers(&headers)
+      .allow_credentials(self.allow_credentials)
+      .max_age_secs(self.max_age_secs)
+      .allow_private_network(self.allow_private_network);
+    for origin in &self.origins {
+      builder = builder.allow_origin(origin.clone());
+    }
+    for suffix in &self.origin_suffixes {
+      builder = builder.allow_origin_suffix(suffix.clone());
+    }
+    Ok(builder)
+  }
+
+  /// Validates the settings into the plugin.
+  pub fn build(&self) -> Result<CorsPlugin, SettingsError> {
+    self
+      .builder()?
+      .try_build()
+      .map_err(|e| SettingsError::new(PLUGIN, "allow_credentials", e.to_string()))
+  }
+}
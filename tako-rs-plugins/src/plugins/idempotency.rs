@@ -20,9 +20,11 @@
 mod config;
 mod plugin;
 mod response;
+mod settings;
 mod store;
 
 pub use config::Config;
 pub use config::IdempotencyBuilder;
 pub use config::Scope;
 pub use plugin::IdempotencyPlugin;
+pub use settings::Settings;
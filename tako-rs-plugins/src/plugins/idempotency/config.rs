@@ -6,7 +6,8 @@ use http::Method;
 use super::plugin::IdempotencyPlugin;
 
 /// Which request attributes are included in the idempotency key scope.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Scope {
   /// Only the header value identifies the operation.
   KeyOnly,
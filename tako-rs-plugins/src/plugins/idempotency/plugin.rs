@@ -5,7 +5,6 @@ use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
-use std::time::Instant;
 
 use anyhow::Result;
 use bytes::Bytes;
@@ -15,6 +14,7 @@ use http_body_util::BodyExt;
 use sha1::Digest;
 use sha1::Sha1;
 use tako_rs_core::body::TakoBody;
+use tako_rs_core::clock;
 use tako_rs_core::middleware::Next;
 use tako_rs_core::plugins::TakoPlugin;
 use tako_rs_core::responder::Responder;
@@ -308,7 +308,7 @@ async fn handle(req: Request, next: Next, cfg: Config, store: Store) -> impl Res
   let completed = Completed {
     payload_sig: sig,
     cached: cached.clone(),
-    expires_at: Instant::now() + ttl,
+    expires_at: clock::now() + ttl,
   };
   store.complete(cache_key.clone(), completed);
   notify.notify_waiters();
@@ -0,0 +1,100 @@
+//! Serde-loadable idempotency settings.
+
+use serde::Deserialize;
+
+use super::config::Config;
+use super::config::IdempotencyBuilder;
+use super::config::Scope;
+use super::plugin::IdempotencyPlugin;
+use crate::plugins::settings;
+use crate::plugins::settings::SettingsError;
+
+const PLUGIN: &str = "idempotency";
+
+/// Idempotency settings as read from a configuration file.
+///
+/// ```toml
+/// methods = ["POST", "PATCH"]
+/// ttl_secs = 3600
+/// scope = "key_only"
+/// inflight_wait_timeout_ms = 5000
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+  /// Header carrying the key.
+  pub header: String,
+  /// Methods to protect.
+  pub methods: Vec<String>,
+  /// Lifetime of cached results in seconds.
+  pub ttl_secs: u64,
+  /// `key_only` or `method_and_path`.
+  pub scope: Scope,
+  /// Make concurrent calls with the same key wait for the first.
+  pub coalesce_inflight: bool,
+  /// How long to wait for an in-flight call, in milliseconds; unset waits
+  /// indefinitely.
+  pub inflight_wait_timeout_ms: Option<u64>,
+  /// Largest response body cached, in bytes.
+  pub max_cached_body_bytes: usize,
+  /// Largest request body hashed, in bytes.
+  pub max_request_body_bytes: usize,
+  /// Require the same payload for a reused key.
+  pub verify_payload: bool,
+  /// Cache non-success responses too.
+  pub cache_error_statuses: bool,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    let cfg = Config::default();
+    Self {
+      header: cfg.header.to_string(),
+      methods: cfg.methods.iter().map(ToString::to_string).collect(),
+      ttl_secs: cfg.ttl_secs,
+      scope: cfg.scope,
+      coalesce_inflight: cfg.coalesce_inflight,
+      inflight_wait_timeout_ms: cfg.inflight_wait_timeout_ms,
+      max_cached_body_bytes: cfg.max_cached_body_bytes,
+      max_request_body_bytes: cfg.max_request_body_bytes,
+      verify_payload: cfg.verify_payload,
+      cache_error_statuses: cfg.cache_error_statuses,
+    }
+  }
+}
+
+impl Settings {
+  /// Validates the settings into a builder.
+  pub fn builder(&self) -> Result<IdempotencyBuilder, SettingsError> {
+    if self.ttl_secs == 0 {
+      return Err(SettingsError::new(
+        PLUGIN,
+        "ttl_secs",
+        "must be greater than 0",
+      ));
+    }
+    if self.methods.is_empty() {
+      return Err(SettingsError::new(PLUGIN, "methods", "must not be empty"));
+    }
+    let header = settings::header_name(PLUGIN, "header", &self.header)?;
+    let methods = settings::methods(PLUGIN, "methods", &self.methods)?;
+    Ok(
+      IdempotencyBuilder::new()
+        .header(header)
+        .methods(&methods)
+        .ttl_secs(self.ttl_secs)
+        .scope(self.scope)
+        .coalesce_inflight(self.coalesce_inflight)
+        .inflight_wait_timeout_ms(self.inflight_wait_timeout_ms)
+        .max_cached_body_bytes(self.max_cached_body_bytes)
+        .max_request_body_bytes(self.max_request_body_bytes)
+        .verify_payload(self.verify_payload)
+        .cache_error_statuses(self.cache_error_statuses),
+    )
+  }
+
+  /// Validates the settings into the plugin.
+  pub fn build(&self) -> Result<IdempotencyPlugin, SettingsError> {
+    self.builder().map(IdempotencyBuilder::build)
+  }
+}
@@ -10,6 +10,7 @@ use http::HeaderName;
 use http::HeaderValue;
 use http::StatusCode;
 use scc::HashMap as SccHashMap;
+use tako_rs_core::clock;
 use tokio::sync::Notify;
 
 #[derive(Clone)]
@@ -118,7 +119,7 @@ impl Store {
         v.insert_entry(Entry::InFlight {
           payload_sig,
           notify: notify.clone(),
-          started: Instant::now(),
+          started: clock::now(),
         });
         Ok(notify)
       }
@@ -152,7 +153,7 @@ impl Store {
   }
 
   pub(crate) fn retain_expired(&self) {
-    let now = Instant::now();
+    let now = clock::now();
     self.0.retain_sync(|_, v| match v {
       Entry::Completed(c) => c.expires_at > now,
       Entry::InFlight { .. } => true,
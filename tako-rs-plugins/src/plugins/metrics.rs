@@ -1,15 +1,22 @@
 #![cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
 //! Metrics and tracing plugin for integrating Tako's signal system with
-//! backends like Prometheus or OpenTelemetry.
+//! backends like Prometheus, OpenTelemetry, or StatsD.
 //!
 //! This plugin listens to application-level and route-level signals and
 //! updates metrics using an injected backend implementation. When the
 //! `metrics-prometheus` or `metrics-opentelemetry` features are enabled,
 //! a concrete backend is provided based on the selected feature, while
 //! the core plugin logic remains backend-agnostic.
+//!
+//! [`HttpMetrics`] needs neither: it measures requests in a middleware and
+//! serves them in the Prometheus text format from `/metrics`.
 
+mod http;
 mod recorder;
 
+pub use http::DEFAULT_SIZE_BUCKETS_BYTES;
+pub use http::HttpMetrics;
+pub use http::HttpMetricsBuilder;
 pub use recorder::DEFAULT_LATENCY_BUCKETS_SEC;
 #[cfg(feature = "signals")]
 pub use recorder::MetricsBackend;
@@ -31,3 +38,7 @@ mod opentelemetry;
 pub use opentelemetry::OtelMetricsConfig;
 #[cfg(feature = "metrics-opentelemetry")]
 pub use opentelemetry::opentelemetry_backend;
+
+#[cfg(feature = "metrics-statsd")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics-statsd")))]
+pub mod statsd;
@@ -0,0 +1,408 @@
+//! Dependency-free HTTP metrics with a Prometheus scrape endpoint.
+//!
+//! [`HttpMetrics`] measures requests in a middleware rather than through the
+//! signal bus, so it works without the `signals` feature or an external
+//! metrics crate. It records, per method, matched route template, and status
+//! class (`2xx`, `4xx`, ...):
+//!
+//! - `<ns>_http_requests_total` — completed requests (counter),
+//! - `<ns>_http_request_duration_seconds` — time until the handler produced
+//!   a response (histogram),
+//! - `<ns>_http_response_size_bytes` — response body bytes (histogram),
+//!
+//! plus `<ns>_http_requests_in_flight` (gauge). Requests that match no route
+//! are labelled `route="unmatched"`, so scanners cannot inflate the series
+//! count. The endpoint (`/metrics` by default) answers `GET` in the
+//! Prometheus text format and is not itself measured. For OTLP export, use
+//! the signal-driven `metrics-opentelemetry` backend instead.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::plugins::metrics::HttpMetricsBuilder;
+//! use tako::router::Router;
+//!
+//! let mut router = Router::new();
+//! let metrics = HttpMetricsBuilder::new().namespace("shop").build();
+//! router.plugin(metrics.clone());
+//!
+//! // The same text the endpoint serves, e.g. for a push gateway.
+//! let text = metrics.render();
+//! ```
+
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use anyhow::Result;
+use http::HeaderValue;
+use http::Method;
+use http::header::CONTENT_TYPE;
+use http_body::Body as _;
+use scc::HashMap as SccHashMap;
+use tako_rs_core::body::InspectedBody;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::clock;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::plugins::TakoPlugin;
+use tako_rs_core::router::Router;
+use tako_rs_core::router_state::MatchedPath;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::DEFAULT_LATENCY_BUCKETS_SEC;
+
+/// Default response-size buckets (bytes), from 100 B to 10 MB.
+pub const DEFAULT_SIZE_BUCKETS_BYTES: &[f64] = &[
+  100.0,
+  1_000.0,
+  10_000.0,
+  100_000.0,
+  1_000_000.0,
+  10_000_000.0,
+];
+
+/// Content type of the Prometheus text exposition format.
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Builder for [`HttpMetrics`].
+pub struct HttpMetricsBuilder {
+  namespace: String,
+  endpoint: Option<String>,
+  duration_buckets: Vec<f64>,
+  size_buckets: Vec<f64>,
+}
+
+impl Default for HttpMetricsBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl HttpMetricsBuilder {
+  /// `tako_*` metrics served at `/metrics`.
+  pub fn new() -> Self {
+    Self {
+      namespace: "tako".to_string(),
+      endpoint: Some("/metrics".to_string()),
+      duration_buckets: DEFAULT_LATENCY_BUCKETS_SEC.to_vec(),
+      size_buckets: DEFAULT_SIZE_BUCKETS_BYTES.to_vec(),
+    }
+  }
+
+  /// Metric name prefix (default `tako`).
+  pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+    self.namespace = namespace.into();
+    self
+  }
+
+  /// Path of the scrape endpoint, or `None` to serve nothing and call
+  /// [`HttpMetrics::render`] yourself.
+  pub fn endpoint(mut self, path: Option<&str>) -> Self {
+    self.endpoint = path.map(str::to_string);
+    self
+  }
+
+  /// Request duration bucket bounds, in seconds.
+  pub fn duration_buckets(mut self, buckets: Vec<f64>) -> Self {
+    self.duration_buckets = sorted(buckets);
+    self
+  }
+
+  /// Response size bucket bounds, in bytes.
+  pub fn size_buckets(mut self, buckets: Vec<f64>) -> Self {
+    self.size_buckets = sorted(buckets);
+    self
+  }
+
+  pub fn build(self) -> HttpMetrics {
+    HttpMetrics {
+      inner: Arc::new(Inner {
+        namespace: self.namespace,
+        endpoint: self.endpoint,
+        duration_buckets: self.duration_buckets.into(),
+        size_buckets: self.size_buckets.into(),
+        series: SccHashMap::new(),
+        in_flight: AtomicI64::new(0),
+      }),
+    }
+  }
+}
+
+fn sorted(mut buckets: Vec<f64>) -> Vec<f64> {
+  buckets.retain(|b| b.is_finite());
+  buckets.sort_by(f64::total_cmp);
+  buckets.dedup();
+  buckets
+}
+
+/// HTTP metrics plugin. Cheap to clone; clones share the collected series.
+#[derive(Clone)]
+pub struct HttpMetrics {
+  inner: Arc<Inner>,
+}
+
+struct Inner {
+  namespace: String,
+  endpoint: Option<String>,
+  duration_buckets: Arc<[f64]>,
+  size_buckets: Arc<[f64]>,
+  series: SccHashMap<SeriesKey, Arc<Series>>,
+  in_flight: AtomicI64,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct SeriesKey {
+  route: String,
+  method: String,
+  class: u16,
+}
+
+struct Series {
+  requests: AtomicU64,
+  duration: Histogram,
+  size: Histogram,
+}
+
+struct Histogram {
+  bounds: Arc<[f64]>,
+  /// Per-bucket (non-cumulative) counts; the last slot is `+Inf`.
+  counts: Box<[AtomicU64]>,
+  count: AtomicU64,
+  sum: AtomicU64,
+}
+
+impl Histogram {
+  fn new(bounds: Arc<[f64]>) -> Self {
+    Self {
+      counts: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+      bounds,
+      count: AtomicU64::new(0),
+      sum: AtomicU64::new(0f64.to_bits()),
+    }
+  }
+
+  fn observe(&self, value: f64) {
+    let slot = self.bounds.partition_point(|b| *b < value);
+    self.counts[slot].fetch_add(1, Ordering::Relaxed);
+    self.count.fetch_add(1, Ordering::Relaxed);
+    let _ = self
+      .sum
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+        Some((f64::from_bits(bits) + value).to_bits())
+      });
+  }
+
+  fn render(&self, out: &mut String, name: &str, labels: &str) {
+    let mut cumulative = 0;
+    for (i, count) in self.counts.iter().enumerate() {
+      cumulative += count.load(Ordering::Relaxed);
+      let le = self
+        .bounds
+        .get(i)
+        .map_or_else(|| "+Inf".to_string(), f64::to_string);
+      let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{le}\"}} {cumulative}");
+    }
+    let sum = f64::from_bits(self.sum.load(Ordering::Relaxed));
+    let _ = writeln!(out, "{name}_sum{{{labels}}} {sum}");
+    let _ = writeln!(
+      out,
+      "{name}_count{{{labels}}} {}",
+      self.count.load(Ordering::Relaxed)
+    );
+  }
+}
+
+/// Holds one in-flight slot; released on drop so cancelled requests do not
+/// leak it.
+struct InFlight<'a>(&'a AtomicI64);
+
+impl<'a> InFlight<'a> {
+  fn enter(gauge: &'a AtomicI64) -> Self {
+    gauge.fetch_add(1, Ordering::Relaxed);
+    Self(gauge)
+  }
+}
+
+impl Drop for InFlight<'_> {
+  fn drop(&mut self) {
+    self.0.fetch_sub(1, Ordering::Relaxed);
+  }
+}
+
+/// Escapes a label value for the text format.
+fn escape(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n")
+}
+
+impl HttpMetrics {
+  pub fn builder() -> HttpMetricsBuilder {
+    HttpMetricsBuilder::new()
+  }
+
+  fn series(&self, key: SeriesKey) -> Arc<Series> {
+    if let Some(series) = self.inner.series.get_sync(&key) {
+      return series.clone();
+    }
+    self
+      .inner
+      .series
+      .entry_sync(key)
+      .or_insert_with(|| {
+        Arc::new(Series {
+          requests: AtomicU64::new(0),
+          duration: Histogram::new(self.inner.duration_buckets.clone()),
+          size: Histogram::new(self.inner.size_buckets.clone()),
+        })
+      })
+      .clone()
+  }
+
+  /// Renders every series in the Prometheus text exposition format.
+  pub fn render(&self) -> String {
+    let mut series = Vec::new();
+    self.inner.series.iter_sync(|key, s| {
+      series.push((key.clone(), s.clone()));
+      true
+    });
+    series.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let ns = &self.inner.namespace;
+    let labels: Vec<String> = series
+      .iter()
+      .map(|(key, _)| {
+        format!(
+          "method=\"{}\",route=\"{}\",status=\"{}xx\"",
+          escape(&key.method),
+          escape(&key.route),
+          key.class
+        )
+      })
+      .collect();
+
+    let mut out = String::new();
+    let name = format!("{ns}_http_requests_total");
+    let _ = writeln!(out, "# HELP {name} Total HTTP requests completed.");
+    let _ = writeln!(out, "# TYPE {name} counter");
+    for ((_, s), labels) in series.iter().zip(&labels) {
+      let _ = writeln!(
+        out,
+        "{name}{{{labels}}} {}",
+        s.requests.load(Ordering::Relaxed)
+      );
+    }
+
+    let name = format!("{ns}_http_request_duration_seconds");
+    let _ = writeln!(
+      out,
+      "# HELP {name} Time until the handler produced a response."
+    );
+    let _ = writeln!(out, "# TYPE {name} histogram");
+    for ((_, s), labels) in series.iter().zip(&labels) {
+      s.duration.render(&mut out, &name, labels);
+    }
+
+    let name = format!("{ns}_http_response_size_bytes");
+    let _ = writeln!(out, "# HELP {name} Response body size.");
+    let _ = writeln!(out, "# TYPE {name} histogram");
+    for ((_, s), labels) in series.iter().zip(&labels) {
+      s.size.render(&mut out, &name, labels);
+    }
+
+    let name = format!("{ns}_http_requests_in_flight");
+    let _ = writeln!(out, "# HELP {name} Requests currently being handled.");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    let _ = writeln!(
+      out,
+      "{name} {}",
+      self.inner.in_flight.load(Ordering::Relaxed)
+    );
+    out
+  }
+
+  async fn handle(&self, req: Request, next: Next) -> Response {
+    if req.method() == Method::GET && self.inner.endpoint.as_deref() == Some(req.uri().path()) {
+      let mut resp = http::Response::new(TakoBody::from(self.render()));
+      resp
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(TEXT_FORMAT));
+      return resp;
+    }
+
+    let method = req.method().as_str().to_string();
+    let route = req
+      .extensions()
+      .get::<MatchedPath>()
+      .map_or_else(|| "unmatched".to_string(), |m| m.0.clone());
+
+    let started = clock::now();
+    let resp = {
+      let _in_flight = InFlight::enter(&self.inner.in_flight);
+      next.run(req).await
+    };
+
+    let series = self.series(SeriesKey {
+      route,
+      method,
+      class: resp.status().as_u16() / 100,
+    });
+    series.requests.fetch_add(1, Ordering::Relaxed);
+    series
+      .duration
+      .observe(clock::now().duration_since(started).as_secs_f64());
+
+    // Buffered bodies are measured up front; streams once they finish.
+    if let Some(len) = resp.body().size_hint().exact() {
+      series.size.observe(len as f64);
+      return resp;
+    }
+    let (parts, body) = resp.into_parts();
+    let body =
+      InspectedBody::new(body).on_complete(Arc::new(move |n| series.size.observe(n as f64)));
+    http::Response::from_parts(parts, TakoBody::new(body))
+  }
+}
+
+impl TakoPlugin for HttpMetrics {
+  fn name(&self) -> &'static str {
+    "HttpMetrics"
+  }
+
+  fn setup(&self, router: &Router) -> Result<()> {
+    let metrics = self.clone();
+    router.middleware(move |req, next| {
+      let metrics = metrics.clone();
+      async move { metrics.handle(req, next).await }
+    });
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn histogram_buckets_are_cumulative() {
+    let h = Histogram::new(vec![0.1, 1.0].into());
+    h.observe(0.0625);
+    h.observe(0.5);
+    h.observe(1.0);
+    h.observe(3.0);
+    let mut out = String::new();
+    h.render(&mut out, "d", "a=\"b\"");
+    assert_eq!(
+      out,
+      "d_bucket{a=\"b\",le=\"0.1\"} 1\n\
+       d_bucket{a=\"b\",le=\"1\"} 3\n\
+       d_bucket{a=\"b\",le=\"+Inf\"} 4\n\
+       d_sum{a=\"b\"} 4.5625\n\
+       d_count{a=\"b\"} 4\n"
+    );
+  }
+}
@@ -23,6 +23,7 @@ pub mod opentelemetry_backend {
     http_route_requests_total: Counter<u64>,
     connections_opened_total: Counter<u64>,
     connections_closed_total: Counter<u64>,
+    connections_rejected_total: Counter<u64>,
   }
 
   impl OtelMetricsBackend {
@@ -31,12 +32,14 @@ pub mod opentelemetry_backend {
       let http_route_requests_total = meter.u64_counter("tako_route_requests_total").build();
       let connections_opened_total = meter.u64_counter("tako_connections_opened_total").build();
       let connections_closed_total = meter.u64_counter("tako_connections_closed_total").build();
+      let connections_rejected_total = meter.u64_counter("tako_connections_rejected_total").build();
 
       Self {
         http_requests_total,
         http_route_requests_total,
         connections_opened_total,
         connections_closed_total,
+        connections_rejected_total,
       }
     }
   }
@@ -105,6 +108,13 @@ pub mod opentelemetry_backend {
         .connections_closed_total
         .add(1, &[KeyValue::new("transport", transport_label(signal))]);
     }
+
+    fn on_connection_rejected(&self, signal: &Signal) {
+      let reason = signal.metadata.get("reason").cloned().unwrap_or_default();
+      self
+        .connections_rejected_total
+        .add(1, &[KeyValue::new("reason", reason)]);
+    }
   }
 }
 
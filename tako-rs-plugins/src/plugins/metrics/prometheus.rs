@@ -86,6 +86,7 @@ pub mod prometheus_backend {
     http_request_duration: HistogramVec,
     connections_opened_total: IntCounterVec,
     connections_closed_total: IntCounterVec,
+    connections_rejected_total: IntCounterVec,
   }
 
   impl PrometheusMetricsBackend {
@@ -147,6 +148,16 @@ pub mod prometheus_backend {
       )
       .expect("failed to create connections_closed_total metric");
 
+      // `reason` is a fixed set of server-side literals.
+      let connections_rejected_total = IntCounterVec::new(
+        Opts::new(
+          "tako_connections_rejected_total",
+          "Total connections refused by the server",
+        ),
+        &["reason"],
+      )
+      .expect("failed to create connections_rejected_total metric");
+
       // PPL-12: `Registry::register` returns `Err(AlreadyReg)` if the same
       // metric name is already registered. The original code `.unwrap()`d
       // these, so any user who installed PrometheusMetricsPlugin twice on
@@ -174,6 +185,11 @@ pub mod prometheus_backend {
         &connections_closed_total,
         "connections_closed_total",
       );
+      register_metric(
+        &registry,
+        &connections_rejected_total,
+        "connections_rejected_total",
+      );
 
       Self {
         registry,
@@ -182,6 +198,7 @@ pub mod prometheus_backend {
         http_request_duration,
         connections_opened_total,
         connections_closed_total,
+        connections_rejected_total,
       }
     }
 
@@ -239,6 +256,14 @@ pub mod prometheus_backend {
         .with_label_values(&[transport])
         .inc();
     }
+
+    fn on_connection_rejected(&self, signal: &Signal) {
+      let reason = signal.metadata.get("reason").map_or("", String::as_str);
+      self
+        .connections_rejected_total
+        .with_label_values(&[reason])
+        .inc();
+    }
   }
 }
 
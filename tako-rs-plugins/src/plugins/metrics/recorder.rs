@@ -35,6 +35,10 @@ pub trait MetricsBackend: Send + Sync + 'static {
 
   /// Called when a connection is closed.
   fn on_connection_closed(&self, signal: &Signal);
+
+  /// Called when the server refuses a connection (for example, a per-IP
+  /// connection limit). No-op by default.
+  fn on_connection_rejected(&self, _signal: &Signal) {}
 }
 
 /// Default Prometheus / `OTel` histogram bucket schedule (seconds), tuned for
@@ -105,6 +109,14 @@ impl<B: MetricsBackend> TakoPlugin for MetricsPlugin<B> {
       }
     });
 
+    let backend_rejected = self.backend.clone();
+    app_arbiter.on(ids::CONNECTION_REJECTED, move |signal: Signal| {
+      let backend = backend_rejected.clone();
+      async move {
+        backend.on_connection_rejected(&signal);
+      }
+    });
+
     // Route-level request.completed metrics via prefix subscription
     let backend_route = self.backend.clone();
     let mut rx = app_arbiter.subscribe_prefix("route.request.");
@@ -0,0 +1,396 @@
+//! `StatsD` / `DogStatsD` exporter fed by the signal bus.
+//!
+//! Each configured [`StatsdMetric`] maps a signal topic to a counter or a
+//! timer. Matching signals are formatted into `StatsD` lines and sent over UDP
+//! from a non-blocking socket, so a slow or absent collector never stalls the
+//! emitting request. All lines produced by one signal share a datagram.
+//!
+//! With [`StatsdFlavor::DogStatsd`] the configured metadata keys are attached
+//! as `|#key:value` tags; plain `StatsD` has no tag syntax, so tags are dropped.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::plugins::metrics::statsd::{StatsdBuilder, StatsdFlavor, StatsdMetric};
+//! use tako::router::Router;
+//!
+//! let mut router = Router::new();
+//! router.plugin(
+//!     StatsdBuilder::new("127.0.0.1:8125")
+//!         .prefix("shop")
+//!         .flavor(StatsdFlavor::DogStatsd)
+//!         .metric(StatsdMetric::counter("order.placed", "orders").tags(["region"]))
+//!         .default_http_metrics()
+//!         .build(),
+//! );
+//! ```
+
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::net::UdpSocket;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+use anyhow::Context as _;
+use anyhow::Result;
+use tako_rs_core::plugins::TakoPlugin;
+use tako_rs_core::router::Router;
+use tako_rs_core::signals::Signal;
+use tako_rs_core::signals::app_events;
+use tako_rs_core::signals::ids;
+
+/// Wire dialect spoken by the collector.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatsdFlavor {
+  /// Etsy `StatsD`: `name:value|type`. Tags are not representable.
+  #[default]
+  Statsd,
+  /// Datadog's extension: `name:value|type|#tag:value,...`.
+  DogStatsd,
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+  Counter,
+  /// Reads the named metadata field in microseconds; emitted in milliseconds.
+  Timer {
+    field: String,
+  },
+}
+
+/// Mapping from one signal topic to one `StatsD` metric.
+#[derive(Debug, Clone)]
+pub struct StatsdMetric {
+  topic: String,
+  name: String,
+  kind: Kind,
+  tags: Vec<String>,
+}
+
+impl StatsdMetric {
+  /// Increments `name` by one for every `topic` signal.
+  pub fn counter(topic: impl Into<String>, name: impl Into<String>) -> Self {
+    Self {
+      topic: topic.into(),
+      name: name.into(),
+      kind: Kind::Counter,
+      tags: Vec::new(),
+    }
+  }
+
+  /// Records `field` (microseconds, the convention used by `duration_us`) as
+  /// a millisecond timer. Signals without a numeric `field` are ignored.
+  pub fn timer(
+    topic: impl Into<String>,
+    name: impl Into<String>,
+    field: impl Into<String>,
+  ) -> Self {
+    Self {
+      topic: topic.into(),
+      name: name.into(),
+      kind: Kind::Timer {
+        field: field.into(),
+      },
+      tags: Vec::new(),
+    }
+  }
+
+  /// Metadata keys copied into `DogStatsD` tags. Missing keys are skipped.
+  #[must_use]
+  pub fn tags<I, S>(mut self, keys: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    self.tags = keys.into_iter().map(Into::into).collect();
+    self
+  }
+}
+
+/// Builder for [`StatsdExporter`].
+pub struct StatsdBuilder {
+  addr: String,
+  prefix: Option<String>,
+  flavor: StatsdFlavor,
+  metrics: Vec<StatsdMetric>,
+}
+
+impl StatsdBuilder {
+  /// Targets the collector at `addr` (e.g. `127.0.0.1:8125` or
+  /// `statsd:8125`).
+  ///
+  /// IP addresses are connected during plugin setup. Host names are resolved
+  /// in the background, and signals emitted before that finishes are not
+  /// sent.
+  pub fn new(addr: impl Into<String>) -> Self {
+    Self {
+      addr: addr.into(),
+      prefix: None,
+      flavor: StatsdFlavor::default(),
+      metrics: Vec::new(),
+    }
+  }
+
+  /// Prepends `prefix.` to every metric name.
+  pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+    self.prefix = Some(prefix.into().trim_end_matches('.').to_string());
+    self
+  }
+
+  /// Selects plain `StatsD` or `DogStatsD` output.
+  pub fn flavor(mut self, flavor: StatsdFlavor) -> Self {
+    self.flavor = flavor;
+    self
+  }
+
+  /// Adds a topic-to-metric mapping.
+  pub fn metric(mut self, metric: StatsdMetric) -> Self {
+    self.metrics.push(metric);
+    self
+  }
+
+  /// Adds the request metrics the Prometheus backend exposes:
+  /// `http.requests` (counter) and `http.request.duration` (timer), both
+  /// tagged by method, route, and status.
+  pub fn default_http_metrics(self) -> Self {
+    let tags = ["method", "route", "status"];
+    self
+      .metric(StatsdMetric::counter(ids::REQUEST_COMPLETED, "http.requests").tags(tags))
+      .metric(
+        StatsdMetric::timer(
+          ids::REQUEST_COMPLETED,
+          "http.request.duration",
+          "duration_us",
+        )
+        .tags(tags),
+      )
+  }
+
+  /// Finishes the configuration; register the result with
+  /// [`Router::plugin`].
+  pub fn build(self) -> StatsdExporter {
+    StatsdExporter {
+      inner: Arc::new(self),
+    }
+  }
+}
+
+/// Plugin that forwards signals to a `StatsD` collector over UDP.
+#[derive(Clone)]
+#[doc(alias = "statsd")]
+#[doc(alias = "dogstatsd")]
+pub struct StatsdExporter {
+  inner: Arc<StatsdBuilder>,
+}
+
+impl StatsdExporter {
+  /// Shorthand for [`StatsdBuilder::new`].
+  pub fn builder(addr: impl Into<String>) -> StatsdBuilder {
+    StatsdBuilder::new(addr)
+  }
+
+  /// Formats every line `signal` produces, newline separated.
+  fn render(&self, signal: &Signal) -> String {
+    let cfg = &self.inner;
+    let mut out = String::new();
+    for metric in cfg.metrics.iter().filter(|m| m.topic == signal.id) {
+      let value = match &metric.kind {
+        Kind::Counter => "1|c".to_string(),
+        Kind::Timer { field } => {
+          let Some(us) = signal
+            .metadata
+            .get(field)
+            .and_then(|v| v.parse::<f64>().ok())
+          else {
+            continue;
+          };
+          format!("{}|ms", us / 1000.0)
+        }
+      };
+      if !out.is_empty() {
+        out.push('\n');
+      }
+      if let Some(prefix) = &cfg.prefix {
+        out.push_str(&sanitize(prefix));
+        out.push('.');
+      }
+      out.push_str(&sanitize(&metric.name));
+      out.push(':');
+      out.push_str(&value);
+
+      if cfg.flavor == StatsdFlavor::DogStatsd {
+        let mut first = true;
+        for key in &metric.tags {
+          let Some(v) = signal.metadata.get(key) else {
+            continue;
+          };
+          out.push_str(if first { "|#" } else { "," });
+          first = false;
+          let _ = write!(out, "{}:{}", sanitize(key), sanitize_tag(v));
+        }
+      }
+    }
+    out
+  }
+}
+
+/// Metric names may not contain the `StatsD` field separators.
+fn sanitize(name: &str) -> String {
+  name
+    .chars()
+    .map(|c| match c {
+      ':' | '|' | '@' | '#' | ',' | '\n' | ' ' => '_',
+      c => c,
+    })
+    .collect()
+}
+
+/// Tag values may contain `:` but not the line and tag separators.
+fn sanitize_tag(value: &str) -> String {
+  value
+    .chars()
+    .map(|c| match c {
+      '|' | ',' | '#' | '\n' => '_',
+      c => c,
+    })
+    .collect()
+}
+
+impl TakoPlugin for StatsdExporter {
+  fn name(&self) -> &'static str {
+    "StatsdExporter"
+  }
+
+  fn setup(&self, _router: &Router) -> Result<()> {
+    let socket: Arc<OnceLock<UdpSocket>> = Arc::default();
+    if let Ok(target) = self.inner.addr.parse::<SocketAddr>() {
+      let _ = socket.set(connect(target).context("failed to open StatsD socket")?);
+    } else {
+      // A DNS lookup would block the runtime thread running setup.
+      let addr = self.inner.addr.clone();
+      let slot = Arc::clone(&socket);
+      let task = async move {
+        match resolve(&addr).await.map(|mut addrs| addrs.next()) {
+          Ok(Some(target)) => match connect(target) {
+            Ok(s) => {
+              let _ = slot.set(s);
+            }
+            Err(e) => tracing::error!(error = %e, addr, "statsd: failed to open socket"),
+          },
+          Ok(None) => tracing::error!(addr, "statsd: address did not resolve"),
+          Err(e) => tracing::error!(error = %e, addr, "statsd: failed to resolve address"),
+        }
+      };
+
+      #[cfg(not(feature = "compio"))]
+      tokio::spawn(task);
+
+      #[cfg(feature = "compio")]
+      compio::runtime::spawn(task).detach();
+    }
+
+    let exporter = self.clone();
+    app_events().register_exporter(move |signal: &Signal| {
+      let Some(socket) = socket.get() else {
+        return;
+      };
+      let payload = exporter.render(signal);
+      if !payload.is_empty()
+        && let Err(e) = socket.send(payload.as_bytes())
+        && e.kind() != std::io::ErrorKind::WouldBlock
+      {
+        tracing::debug!(error = %e, "statsd: failed to send datagram");
+      }
+    });
+    Ok(())
+  }
+}
+
+#[cfg(not(feature = "compio"))]
+async fn resolve(addr: &str) -> std::io::Result<impl Iterator<Item = SocketAddr>> {
+  tokio::net::lookup_host(addr).await
+}
+
+#[cfg(feature = "compio")]
+async fn resolve(addr: &str) -> std::io::Result<impl Iterator<Item = SocketAddr>> {
+  use compio::net::ToSocketAddrsAsync;
+  addr.to_socket_addrs_async().await
+}
+
+/// Opens a non-blocking UDP socket connected to `target`.
+fn connect(target: SocketAddr) -> std::io::Result<UdpSocket> {
+  let bind = if target.is_ipv6() {
+    "[::]:0"
+  } else {
+    "0.0.0.0:0"
+  };
+  let socket = UdpSocket::bind(bind)?;
+  socket.connect(target)?;
+  // Metrics are best-effort: a full send buffer drops the datagram rather
+  // than blocking the task that emitted the signal.
+  socket.set_nonblocking(true)?;
+  Ok(socket)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn completed() -> Signal {
+    Signal::new(ids::REQUEST_COMPLETED)
+      .meta("method", "GET")
+      .meta("route", "/users/{id}")
+      .meta("status", "200")
+      .meta("duration_us", "1500")
+  }
+
+  #[test]
+  fn renders_dogstatsd_lines_with_tags() {
+    let exporter = StatsdBuilder::new("127.0.0.1:8125")
+      .prefix("app.")
+      .flavor(StatsdFlavor::DogStatsd)
+      .default_http_metrics()
+      .build();
+    assert_eq!(
+      exporter.render(&completed()),
+      "app.http.requests:1|c|#method:GET,route:/users/{id},status:200\n\
+       app.http.request.duration:1.5|ms|#method:GET,route:/users/{id},status:200"
+    );
+  }
+
+  #[test]
+  fn plain_statsd_drops_tags_and_ignores_other_topics() {
+    let exporter = StatsdBuilder::new("127.0.0.1:8125")
+      .metric(StatsdMetric::counter("order.placed", "orders").tags(["region"]))
+      .build();
+    assert_eq!(exporter.render(&completed()), "");
+    let order = Signal::new("order.placed").meta("region", "eu");
+    assert_eq!(exporter.render(&order), "orders:1|c");
+  }
+
+  #[cfg(not(feature = "compio"))]
+  #[tokio::test]
+  async fn host_names_resolve_without_blocking_setup() {
+    // Same lookup order as the exporter, so both land on one address family.
+    let collector = UdpSocket::bind("localhost:0").unwrap();
+    collector.set_nonblocking(true).unwrap();
+    let port = collector.local_addr().unwrap().port();
+
+    StatsdBuilder::new(format!("localhost:{port}"))
+      .metric(StatsdMetric::counter("statsd.test.resolved", "resolved"))
+      .build()
+      .setup(&Router::new())
+      .unwrap();
+
+    let mut buf = [0u8; 64];
+    for _ in 0..100 {
+      app_events().emit(Signal::new("statsd.test.resolved")).await;
+      tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+      if let Ok(n) = collector.recv(&mut buf) {
+        assert_eq!(&buf[..n], b"resolved:1|c");
+        return;
+      }
+    }
+    panic!("no datagram reached the collector");
+  }
+}
@@ -0,0 +1,61 @@
+#![cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+//! Time-window request quotas with persistent counters.
+//!
+//! Where the [rate limiter](crate::plugins::rate_limiter) smooths bursts
+//! over seconds, a quota caps how much a caller may use over a long window —
+//! "10 000 requests per day per API key" — the shape billing plans are sold
+//! in. Counters live in a pluggable [`QuotaStore`](crate::stores::QuotaStore)
+//! so they can survive restarts and be shared by every replica; the default
+//! [`MemoryQuotaStore`](crate::stores::memory::MemoryQuotaStore) is
+//! per-process.
+//!
+//! Two window shapes are supported:
+//!
+//! - [`Window::Fixed`] — windows aligned to the UNIX epoch (a daily quota
+//!   resets at 00:00 UTC). Exact and cheap, but a caller can spend two
+//!   windows' worth around a boundary.
+//! - [`Window::Sliding`] — the previous window's count is weighted by how
+//!   much of it still overlaps the trailing period, which smooths the
+//!   boundary at the cost of one extra counter read per request.
+//!
+//! Every keyed response carries `X-Quota-Limit`, `X-Quota-Remaining`, and
+//! `X-Quota-Reset` (seconds until the current window ends). Requests over
+//! quota get `429` with `Retry-After` and are not counted. With the
+//! `signals` feature the plugin emits [`ids::EXHAUSTED`] when a request
+//! spends the last unit and [`ids::REJECTED`] for each request turned away.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::plugins::quota::QuotaBuilder;
+//! use tako::router::Router;
+//!
+//! let mut router = Router::new();
+//! router.plugin(
+//!     QuotaBuilder::new()
+//!         .per_day(10_000)
+//!         .sliding()
+//!         .key_header("x-api-key")
+//!         .build(),
+//! );
+//! ```
+
+mod config;
+mod plugin;
+
+pub use config::Config;
+pub use config::CostFn;
+pub use config::QuotaBuilder;
+pub use config::Window;
+pub use plugin::QuotaPlugin;
+
+/// Signal ids emitted by the quota plugin (`signals` feature).
+///
+/// Both carry `key`, `limit`, `used`, and `reset` (seconds until the window
+/// ends) as metadata.
+pub mod ids {
+  /// A request spent the last unit of its key's quota.
+  pub const EXHAUSTED: &str = "quota.exhausted";
+  /// A request was rejected because it would exceed the quota.
+  pub const REJECTED: &str = "quota.rejected";
+}
@@ -0,0 +1,174 @@
+//! Quota size and window, keying, storage, and the builder.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::HeaderName;
+use http::StatusCode;
+use tako_rs_core::types::Request;
+
+use super::plugin::QuotaPlugin;
+use crate::plugins::rate_limiter::KeyFn;
+use crate::plugins::rate_limiter::UnkeyedBehavior;
+use crate::stores::QuotaStore;
+use crate::stores::memory::MemoryQuotaStore;
+
+/// How request counts are grouped in time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Window {
+  /// Consecutive windows aligned to the UNIX epoch.
+  Fixed,
+  /// A trailing window approximated from the current and previous fixed
+  /// windows.
+  Sliding,
+}
+
+/// Units a request spends; defaults to one per request.
+pub type CostFn = Arc<dyn Fn(&Request) -> u64 + Send + Sync + 'static>;
+
+/// Quota configuration.
+#[derive(Clone)]
+pub struct Config {
+  /// Units allowed per window. Default: 1000.
+  pub limit: u64,
+  /// Window length, whole seconds. Default: one day.
+  pub period: Duration,
+  /// Default: [`Window::Fixed`].
+  pub window: Window,
+  /// Status for requests over quota. Default: `429`.
+  pub status_on_limit: StatusCode,
+  /// Behavior for requests that cannot be keyed. Default: allow.
+  pub on_unkeyed: UnkeyedBehavior,
+  /// Let requests through (without headers) when the store fails, rather
+  /// than answering `503`. Default: true.
+  pub fail_open: bool,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      limit: 1000,
+      period: Duration::from_secs(86_400),
+      window: Window::Fixed,
+      status_on_limit: StatusCode::TOO_MANY_REQUESTS,
+      on_unkeyed: UnkeyedBehavior::Allow,
+      fail_open: true,
+    }
+  }
+}
+
+/// Builder for the quota plugin.
+pub struct QuotaBuilder {
+  cfg: Config,
+  key_fn: Option<KeyFn>,
+  cost_fn: Option<CostFn>,
+  store: Arc<dyn QuotaStore>,
+}
+
+impl Default for QuotaBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl QuotaBuilder {
+  pub fn new() -> Self {
+    Self {
+      cfg: Config::default(),
+      key_fn: None,
+      cost_fn: None,
+      store: Arc::new(MemoryQuotaStore::new()),
+    }
+  }
+
+  /// `limit` units per `period`.
+  pub fn limit(mut self, limit: u64, period: Duration) -> Self {
+    self.cfg.limit = limit;
+    self.cfg.period = period;
+    self
+  }
+
+  /// Convenience: N units per hour.
+  pub fn per_hour(self, n: u64) -> Self {
+    self.limit(n, Duration::from_secs(3_600))
+  }
+
+  /// Convenience: N units per day.
+  pub fn per_day(self, n: u64) -> Self {
+    self.limit(n, Duration::from_secs(86_400))
+  }
+
+  pub fn window(mut self, w: Window) -> Self {
+    self.cfg.window = w;
+    self
+  }
+
+  /// Shorthand for `window(Window::Sliding)`.
+  pub fn sliding(self) -> Self {
+    self.window(Window::Sliding)
+  }
+
+  pub fn status(mut self, st: StatusCode) -> Self {
+    self.cfg.status_on_limit = st;
+    self
+  }
+
+  pub fn on_unkeyed(mut self, b: UnkeyedBehavior) -> Self {
+    self.cfg.on_unkeyed = b;
+    self
+  }
+
+  pub fn fail_open(mut self, yes: bool) -> Self {
+    self.cfg.fail_open = yes;
+    self
+  }
+
+  /// Override the quota key (default: peer IP).
+  pub fn key_fn<F>(mut self, f: F) -> Self
+  where
+    F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+  {
+    self.key_fn = Some(Arc::new(f));
+    self
+  }
+
+  /// Key the quota by a request header, typically the API key. Requests
+  /// without it are unkeyed.
+  pub fn key_header(self, name: impl Into<HeaderName>) -> Self {
+    let name = name.into();
+    self.key_fn(move |req| {
+      let value = req.headers().get(&name)?.to_str().ok()?;
+      Some(format!("{name}:{value}"))
+    })
+  }
+
+  /// Charge requests unequally, e.g. by endpoint or by declared batch size.
+  pub fn cost_fn<F>(mut self, f: F) -> Self
+  where
+    F: Fn(&Request) -> u64 + Send + Sync + 'static,
+  {
+    self.cost_fn = Some(Arc::new(f));
+    self
+  }
+
+  /// Counter storage. Default: [`MemoryQuotaStore`].
+  pub fn store(mut self, store: impl QuotaStore) -> Self {
+    self.store = Arc::new(store);
+    self
+  }
+
+  /// Build the plugin.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the limit is zero or the period is shorter than one second;
+  /// both would reject every request.
+  pub fn build(self) -> QuotaPlugin {
+    assert!(self.cfg.limit > 0, "Quota limit must be > 0");
+    assert!(
+      self.cfg.period.as_secs() > 0,
+      "Quota period must be at least one second"
+    );
+    QuotaPlugin::new(self.cfg, self.key_fn, self.cost_fn, self.store)
+  }
+}
@@ -0,0 +1,284 @@
+//! The quota plugin: window arithmetic, the per-request middleware, and the
+//! janitor wiring.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Result;
+use http::HeaderMap;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::RETRY_AFTER;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::clock;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::plugins::TakoPlugin;
+use tako_rs_core::router::Router;
+#[cfg(feature = "signals")]
+use tako_rs_core::signals::Signal;
+#[cfg(feature = "signals")]
+use tako_rs_core::signals::app_events;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::config::Config;
+use super::config::CostFn;
+use super::config::QuotaBuilder;
+use super::config::Window;
+use crate::plugins::rate_limiter::KeyFn;
+use crate::plugins::rate_limiter::UnkeyedBehavior;
+use crate::plugins::rate_limiter::default_key;
+use crate::stores::QuotaStore;
+
+/// Window-based quota plugin. Attach at router or route level.
+#[derive(Clone)]
+#[doc(alias = "quota")]
+#[doc(alias = "billing")]
+pub struct QuotaPlugin {
+  inner: Arc<Inner>,
+  janitor_started: Arc<AtomicBool>,
+}
+
+struct Inner {
+  cfg: Config,
+  key_fn: Option<KeyFn>,
+  cost_fn: Option<CostFn>,
+  store: Arc<dyn QuotaStore>,
+}
+
+impl QuotaPlugin {
+  pub fn builder() -> QuotaBuilder {
+    QuotaBuilder::new()
+  }
+
+  pub(super) fn new(
+    cfg: Config,
+    key_fn: Option<KeyFn>,
+    cost_fn: Option<CostFn>,
+    store: Arc<dyn QuotaStore>,
+  ) -> Self {
+    Self {
+      inner: Arc::new(Inner {
+        cfg,
+        key_fn,
+        cost_fn,
+        store,
+      }),
+      janitor_started: Arc::new(AtomicBool::new(false)),
+    }
+  }
+}
+
+impl TakoPlugin for QuotaPlugin {
+  fn name(&self) -> &'static str {
+    "QuotaPlugin"
+  }
+
+  fn setup(&self, router: &Router) -> Result<()> {
+    let inner = self.inner.clone();
+    router.middleware(move |req, next| {
+      let inner = inner.clone();
+      async move { handle(req, next, &inner).await }
+    });
+
+    if !self.janitor_started.swap(true, Ordering::SeqCst) {
+      let store = self.inner.store.clone();
+      let interval = self
+        .inner
+        .cfg
+        .period
+        .clamp(Duration::from_secs(5), Duration::from_secs(3600));
+
+      #[cfg(not(feature = "compio"))]
+      tokio::spawn(async move {
+        let mut tick = tokio::time::interval(interval);
+        loop {
+          tick.tick().await;
+          store.sweep_expired(clock::system_now()).await;
+        }
+      });
+
+      #[cfg(feature = "compio")]
+      compio::runtime::spawn(async move {
+        loop {
+          compio::time::sleep(interval).await;
+          store.sweep_expired(clock::system_now()).await;
+        }
+      })
+      .detach();
+    }
+
+    Ok(())
+  }
+}
+
+/// Usage after one request was counted (or turned away).
+struct Usage {
+  allowed: bool,
+  used: u64,
+  reset_secs: u64,
+}
+
+/// Counts `cost` against `key` at `now` (seconds since the epoch).
+async fn consume(
+  cfg: &Config,
+  store: &dyn QuotaStore,
+  key: &str,
+  cost: u64,
+  now: u64,
+) -> std::io::Result<Usage> {
+  let period = cfg.period.as_secs();
+  let window = now - now % period;
+  let reset_secs = window + period - now;
+  match cfg.window {
+    Window::Fixed => {
+      let count = store
+        .consume(key, window, cost, cfg.limit, cfg.period)
+        .await?;
+      Ok(Usage {
+        allowed: count.consumed,
+        used: count.used,
+        reset_secs,
+      })
+    }
+    Window::Sliding => {
+      // The part of the previous window still inside the trailing period
+      // counts in proportion to the overlap.
+      let previous = store.count(key, window.saturating_sub(period)).await?;
+      let overlap = u128::from(period - (now - window));
+      let carried =
+        u64::try_from(u128::from(previous) * overlap / u128::from(period)).unwrap_or(u64::MAX);
+      // Counters are read back during the following window.
+      let ttl = cfg.period * 2;
+      let count = store
+        .consume(key, window, cost, cfg.limit.saturating_sub(carried), ttl)
+        .await?;
+      Ok(Usage {
+        allowed: count.consumed,
+        used: carried.saturating_add(count.used),
+        reset_secs,
+      })
+    }
+  }
+}
+
+async fn handle(req: Request, next: Next, inner: &Inner) -> Response {
+  let cfg = &inner.cfg;
+  let key = match inner.key_fn.as_ref() {
+    Some(f) => f(&req),
+    None => default_key(&req),
+  };
+  let Some(key) = key else {
+    return match cfg.on_unkeyed {
+      UnkeyedBehavior::Allow => next.run(req).await,
+      UnkeyedBehavior::Reject => status_response(cfg.status_on_limit),
+    };
+  };
+  let cost = inner.cost_fn.as_ref().map_or(1, |f| f(&req));
+  let now = clock::system_now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs());
+
+  let usage = match consume(cfg, &*inner.store, &key, cost, now).await {
+    Ok(usage) => usage,
+    Err(e) => {
+      tracing::warn!(error = %e, key = %key, "quota store failed");
+      return if cfg.fail_open {
+        next.run(req).await
+      } else {
+        status_response(StatusCode::SERVICE_UNAVAILABLE)
+      };
+    }
+  };
+
+  if !usage.allowed {
+    #[cfg(feature = "signals")]
+    emit(super::ids::REJECTED, &key, cfg, &usage).await;
+    let mut resp = status_response(cfg.status_on_limit);
+    write_headers(resp.headers_mut(), cfg, &usage);
+    resp
+      .headers_mut()
+      .insert(RETRY_AFTER, HeaderValue::from(usage.reset_secs));
+    return resp;
+  }
+
+  #[cfg(feature = "signals")]
+  if usage.used >= cfg.limit {
+    emit(super::ids::EXHAUSTED, &key, cfg, &usage).await;
+  }
+
+  let mut resp = next.run(req).await;
+  write_headers(resp.headers_mut(), cfg, &usage);
+  resp
+}
+
+fn write_headers(headers: &mut HeaderMap, cfg: &Config, usage: &Usage) {
+  headers.insert("x-quota-limit", HeaderValue::from(cfg.limit));
+  headers.insert(
+    "x-quota-remaining",
+    HeaderValue::from(cfg.limit.saturating_sub(usage.used)),
+  );
+  headers.insert("x-quota-reset", HeaderValue::from(usage.reset_secs));
+}
+
+fn status_response(status: StatusCode) -> Response {
+  let mut resp = http::Response::new(TakoBody::empty());
+  *resp.status_mut() = status;
+  resp
+}
+
+#[cfg(feature = "signals")]
+async fn emit(id: &str, key: &str, cfg: &Config, usage: &Usage) {
+  app_events()
+    .emit(
+      Signal::new(id)
+        .meta("key", key)
+        .meta("limit", cfg.limit.to_string())
+        .meta("used", usage.used.to_string())
+        .meta("reset", usage.reset_secs.to_string()),
+    )
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::stores::memory::MemoryQuotaStore;
+
+  fn cfg(window: Window) -> Config {
+    Config {
+      limit: 10,
+      period: Duration::from_secs(100),
+      window,
+      ..Config::default()
+    }
+  }
+
+  #[tokio::test]
+  async fn fixed_windows_reset_on_epoch_boundaries() {
+    let (cfg, store) = (cfg(Window::Fixed), MemoryQuotaStore::new());
+    let usage = consume(&cfg, &store, "k", 10, 1_030).await.unwrap();
+    assert!(usage.allowed);
+    assert_eq!((usage.used, usage.reset_secs), (10, 70));
+    assert!(!consume(&cfg, &store, "k", 1, 1_099).await.unwrap().allowed);
+    // A rejected request is not counted.
+    assert_eq!(store.count("k", 1_000).await.unwrap(), 10);
+    assert!(consume(&cfg, &store, "k", 1, 1_100).await.unwrap().allowed);
+  }
+
+  #[tokio::test]
+  async fn sliding_windows_carry_the_overlapping_share() {
+    let (cfg, store) = (cfg(Window::Sliding), MemoryQuotaStore::new());
+    assert!(consume(&cfg, &store, "k", 10, 1_090).await.unwrap().allowed);
+    // 30s into the next window, 70% of the previous 10 still counts.
+    let usage = consume(&cfg, &store, "k", 3, 1_130).await.unwrap();
+    assert!(usage.allowed);
+    assert_eq!(usage.used, 10);
+    assert!(!consume(&cfg, &store, "k", 1, 1_130).await.unwrap().allowed);
+    // Later in the window more of the old count has slid out.
+    assert!(consume(&cfg, &store, "k", 2, 1_150).await.unwrap().allowed);
+  }
+}
@@ -1,27 +1,49 @@
 #![cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
-//! Rate limiting plugin: token-bucket or GCRA, with composite keys and
-//! IETF rate-limit response headers.
+//! Rate limiting plugin: token-bucket, GCRA or sliding-window, with
+//! pluggable keys and IETF rate-limit response headers.
 //!
 //! v2 additions over the original token-bucket-by-IP design:
 //!
 //! - **Composite keys.** Default key is still the peer IP, but
 //!   [`RateLimiterBuilder::key_fn`](crate::plugins::rate_limiter::RateLimiterBuilder::key_fn) lets callers compose per-route /
 //!   per-tenant / per-user buckets without forking the plugin.
+//! - **Key extractors.** [`RateLimiterBuilder::key`](crate::plugins::rate_limiter::RateLimiterBuilder::key) takes any
+//!   [`KeyExtractor`](crate::plugins::rate_limiter::KeyExtractor); [`ApiKeyHeader`](crate::plugins::rate_limiter::ApiKeyHeader),
+//!   [`JwtSubject`](crate::plugins::rate_limiter::JwtSubject), [`SessionValue`](crate::plugins::rate_limiter::SessionValue) and [`RouteAndIp`](crate::plugins::rate_limiter::RouteAndIp) are built in.
 //! - **Strict IP fallback.** Requests without a discoverable peer IP no
 //!   longer all collapse into the `0.0.0.0` bucket — the request is treated
 //!   as unkeyed and skipped (configurable via [`RateLimiterBuilder::on_unkeyed`](crate::plugins::rate_limiter::RateLimiterBuilder::on_unkeyed)).
 //! - **`RateLimit-*` headers.** Emits `RateLimit-Limit`, `RateLimit-Remaining`,
-//!   `RateLimit-Reset`, and `Retry-After` per the IETF httpapi draft.
+//!   `RateLimit-Reset`, `RateLimit-Policy`, and `Retry-After` per the IETF
+//!   httpapi draft.
 //! - **GCRA mode.** Opt in via [`Algorithm::Gcra`](crate::plugins::rate_limiter::Algorithm::Gcra). The per-key state stays
 //!   one f64; no separate refill ticker.
+//! - **Sliding window.** Opt in via [`Algorithm::SlidingWindow`](crate::plugins::rate_limiter::Algorithm::SlidingWindow) for a
+//!   "N per window" limit without the fixed-window boundary burst.
 
 mod algorithm;
 mod config;
+mod key;
 mod plugin;
+mod settings;
 
+pub(crate) use algorithm::default_key;
+#[cfg(feature = "redis")]
+pub(crate) use algorithm::gcra_decision;
+#[cfg(feature = "redis")]
+pub(crate) use algorithm::sliding_window_decision;
+#[cfg(feature = "redis")]
+pub(crate) use algorithm::token_bucket_decision;
 pub use config::Algorithm;
 pub use config::Config;
 pub use config::KeyFn;
 pub use config::UnkeyedBehavior;
+pub use key::ApiKeyHeader;
+pub use key::JwtSubject;
+pub use key::KeyExtractor;
+pub use key::PeerIp;
+pub use key::RouteAndIp;
+pub use key::SessionValue;
 pub use plugin::RateLimiterBuilder;
 pub use plugin::RateLimiterPlugin;
+pub use settings::Settings;
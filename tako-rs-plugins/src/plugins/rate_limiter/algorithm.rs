@@ -1,16 +1,20 @@
-//! Quota state and the rate-limiting algorithm: per-key bucket, token-bucket
-//! and GCRA evaluation, IETF `RateLimit-*` headers, key extraction, and the
-//! per-request middleware handler.
+//! Quota state and the rate-limiting algorithm: per-key bucket, token-bucket,
+//! GCRA and sliding-window evaluation, the in-process store, IETF
+//! `RateLimit-*` headers, key extraction, and the per-request middleware
+//! handler.
 
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use std::time::Instant;
 
+use async_trait::async_trait;
 use http::HeaderValue;
 use http::header::RETRY_AFTER;
 use parking_lot::Mutex;
 use scc::HashMap as SccHashMap;
 use tako_rs_core::body::TakoBody;
+use tako_rs_core::clock;
 use tako_rs_core::conn_info::ConnInfo;
 use tako_rs_core::conn_info::PeerAddr;
 use tako_rs_core::middleware::Next;
@@ -21,14 +25,37 @@ use super::config::Algorithm;
 use super::config::Config;
 use super::config::KeyFn;
 use super::config::UnkeyedBehavior;
+use crate::stores::RateLimitSnapshot;
+use crate::stores::RateLimitStore;
 
 #[derive(Clone)]
 pub(crate) struct Bucket {
   available: f64,
   pub(crate) last_refill: Instant,
+  /// Start of the current fixed window (`SlidingWindow` only).
+  window_start: Instant,
+  /// Requests counted in the previous window (`SlidingWindow` only).
+  previous: f64,
 }
 
-fn default_key(req: &Request) -> Option<String> {
+impl Bucket {
+  fn new(cfg: &Config, now: Instant) -> Self {
+    let available = match cfg.algorithm {
+      Algorithm::TokenBucket => f64::from(cfg.max_requests),
+      // GCRA tracks used credit and the sliding window counts requests;
+      // both start from zero.
+      Algorithm::Gcra | Algorithm::SlidingWindow => 0.0,
+    };
+    Self {
+      available,
+      last_refill: now,
+      window_start: now,
+      previous: 0.0,
+    }
+  }
+}
+
+pub(crate) fn default_key(req: &Request) -> Option<String> {
   if let Some(info) = req.extensions().get::<ConnInfo>()
     && let PeerAddr::Ip(sa) = &info.peer
   {
@@ -40,86 +67,180 @@ fn default_key(req: &Request) -> Option<String> {
   None
 }
 
-struct Outcome {
+type Decision = Result<RateLimitSnapshot, RateLimitSnapshot>;
+
+fn decide(allowed: bool, snapshot: RateLimitSnapshot) -> Decision {
+  if allowed { Ok(snapshot) } else { Err(snapshot) }
+}
+
+/// Token-bucket decision given the tokens left after it.
+pub(crate) fn token_bucket_decision(
+  cfg: &Config,
+  tokens: f64,
+  cost: f64,
   allowed: bool,
-  remaining: u32,
-  reset_secs: u64,
-  retry_after_secs: u64,
+) -> Decision {
+  let rate_per_sec = cfg.rate_per_sec();
+  let needed = (cost - tokens).max(0.0);
+  let reset_secs = if rate_per_sec > 0.0 {
+    (needed / rate_per_sec).ceil() as u64
+  } else {
+    0
+  };
+  decide(
+    allowed,
+    RateLimitSnapshot {
+      limit: cfg.max_requests,
+      remaining: tokens.max(0.0).floor() as u32,
+      reset_secs,
+      retry_after_secs: if allowed { 0 } else { reset_secs.max(1) },
+    },
+  )
 }
 
-fn evaluate(cfg: &Config, bucket: &mut Bucket, now: Instant) -> Outcome {
+/// GCRA decision given the seconds of credit in use after it (the
+/// theoretical arrival time minus now).
+pub(crate) fn gcra_decision(cfg: &Config, credit: f64, cost: f64, allowed: bool) -> Decision {
+  let rate_per_sec = cfg.rate_per_sec();
+  let increment = 1.0 / rate_per_sec;
+  let burst_tolerance = cfg.window_secs();
+  let retry_after_secs = if allowed {
+    0
+  } else {
+    (credit + cost * increment - burst_tolerance)
+      .max(0.0)
+      .ceil() as u64
+  };
+  decide(
+    allowed,
+    RateLimitSnapshot {
+      limit: cfg.max_requests,
+      remaining: ((burst_tolerance - credit).max(0.0) * rate_per_sec).floor() as u32,
+      reset_secs: credit.ceil() as u64,
+      retry_after_secs: retry_after_secs.max(1),
+    },
+  )
+}
+
+/// Sliding-window decision given the counts of the previous and current
+/// fixed windows after it, and the seconds elapsed in the current one.
+pub(crate) fn sliding_window_decision(
+  cfg: &Config,
+  previous: f64,
+  current: f64,
+  into: f64,
+  cost: f64,
+  allowed: bool,
+) -> Decision {
   let cap = f64::from(cfg.max_requests);
+  let window = cfg.window_secs();
+  let used = previous * (1.0 - into / window).max(0.0) + current;
+  // Time until `cost` more fits: either the previous window's weight drains
+  // far enough within this window, or — when this window alone is full —
+  // this window's count drains after the rollover.
+  let room = cap - cost;
+  let wait = if current <= room && previous > 0.0 {
+    window * (1.0 - (room - current) / previous) - into
+  } else {
+    (window - into) + window * (1.0 - room / current.max(1.0))
+  };
+  decide(
+    allowed,
+    RateLimitSnapshot {
+      limit: cfg.max_requests,
+      remaining: (cap - used).max(0.0).floor() as u32,
+      reset_secs: (window - into).max(0.0).ceil() as u64,
+      retry_after_secs: wait.max(0.0).ceil().max(1.0) as u64,
+    },
+  )
+}
+
+fn evaluate(cfg: &Config, bucket: &mut Bucket, now: Instant, cost: f64) -> Decision {
+  let cap = f64::from(cfg.max_requests);
+  let elapsed = now
+    .duration_since(bucket.last_refill)
+    .as_secs_f64()
+    .max(0.0);
+  bucket.last_refill = now;
   match cfg.algorithm {
     Algorithm::TokenBucket => {
       // Lazy refill so each request observes the latest count even between
       // ticker ticks.
-      let dt = now
-        .duration_since(bucket.last_refill)
-        .as_secs_f64()
-        .max(0.0);
-      let rate_per_sec = f64::from(cfg.refill_rate) / (cfg.refill_interval_ms as f64 / 1_000.0);
-      bucket.available = (bucket.available + dt * rate_per_sec).min(cap);
-      bucket.last_refill = now;
-      let allowed = bucket.available >= 1.0;
+      bucket.available = (bucket.available + elapsed * cfg.rate_per_sec()).min(cap);
+      let allowed = bucket.available >= cost;
       if allowed {
-        bucket.available -= 1.0;
-      }
-      let remaining = bucket.available.max(0.0).floor() as u32;
-      let needed = (1.0 - bucket.available).max(0.0);
-      let reset_secs = if rate_per_sec > 0.0 {
-        (needed / rate_per_sec).ceil() as u64
-      } else {
-        0
-      };
-      let retry_after_secs = if allowed { 0 } else { reset_secs.max(1) };
-      Outcome {
-        allowed,
-        remaining,
-        reset_secs,
-        retry_after_secs,
+        bucket.available -= cost;
       }
+      token_bucket_decision(cfg, bucket.available, cost, allowed)
     }
     Algorithm::Gcra => {
-      // GCRA: maintain a virtual "next free time"; if it is in the future
-      // beyond the burst tolerance, reject. We map `available` ↔ remaining
-      // headroom for backwards-compatible book-keeping.
-      let rate_per_sec = f64::from(cfg.refill_rate) / (cfg.refill_interval_ms as f64 / 1_000.0);
-      let increment = if rate_per_sec > 0.0 {
-        1.0 / rate_per_sec
-      } else {
-        f64::INFINITY
-      };
-      let burst_tolerance = cap * increment;
-      // bucket.available represents seconds of "credit" remaining (negative
-      // means the request would have to wait).
-      let elapsed = now
-        .duration_since(bucket.last_refill)
-        .as_secs_f64()
-        .max(0.0);
+      // GCRA: `available` holds the seconds of credit in use — how far the
+      // theoretical arrival time runs ahead of now. A request that would
+      // push it past the burst tolerance is rejected.
+      let increment = 1.0 / cfg.rate_per_sec();
       bucket.available = (bucket.available - elapsed).max(0.0);
-      bucket.last_refill = now;
-      let allowed = bucket.available + increment <= burst_tolerance;
+      let allowed = bucket.available + cost * increment <= cfg.window_secs();
       if allowed {
-        bucket.available += increment;
+        bucket.available += cost * increment;
       }
-      let credit_used = bucket.available;
-      let remaining = ((burst_tolerance - credit_used).max(0.0) * rate_per_sec).floor() as u32;
-      let reset_secs = bucket.available.ceil() as u64;
-      let retry_after_secs = if allowed {
-        0
-      } else {
-        ((bucket.available + increment - burst_tolerance).max(0.0)).ceil() as u64
-      };
-      Outcome {
-        allowed,
-        remaining,
-        reset_secs,
-        retry_after_secs: retry_after_secs.max(1),
+      gcra_decision(cfg, bucket.available, cost, allowed)
+    }
+    Algorithm::SlidingWindow => {
+      // Sliding-window counter: `available` counts requests in the current
+      // fixed window and `previous` those of the one before it, weighted by
+      // the share of it the sliding window still covers.
+      let window = cfg.window_secs();
+      let passed = (now.duration_since(bucket.window_start).as_secs_f64() / window).floor();
+      if passed >= 1.0 {
+        bucket.previous = if passed >= 2.0 { 0.0 } else { bucket.available };
+        bucket.available = 0.0;
+        bucket.window_start += Duration::from_secs_f64(passed * window);
+      }
+      let into = now.duration_since(bucket.window_start).as_secs_f64();
+      let used = bucket.previous * (1.0 - into / window).max(0.0) + bucket.available;
+      let allowed = used + cost <= cap;
+      if allowed {
+        bucket.available += cost;
       }
+      sliding_window_decision(cfg, bucket.previous, bucket.available, into, cost, allowed)
     }
   }
 }
 
+/// The in-process store used unless the builder selects another: one
+/// [`Bucket`] per key, evaluated with the builder's [`Config`].
+pub(crate) struct LocalStore {
+  cfg: Config,
+  pub(crate) buckets: Arc<SccHashMap<String, Mutex<Bucket>>>,
+}
+
+impl LocalStore {
+  pub(crate) fn new(cfg: Config) -> Self {
+    Self {
+      cfg,
+      buckets: Arc::new(SccHashMap::new()),
+    }
+  }
+}
+
+#[async_trait]
+impl RateLimitStore for LocalStore {
+  async fn consume(&self, key: &str, cost: u32) -> Decision {
+    let entry = self
+      .buckets
+      .entry_async(key.to_string())
+      .await
+      .or_insert_with(|| Mutex::new(Bucket::new(&self.cfg, clock::now())));
+    // `parking_lot::Mutex` (sync lock) is deliberate here: we hold it across
+    // a strictly synchronous `evaluate` call and never `.await` under the
+    // guard. A `tokio::sync::Mutex` would force this hot path through a
+    // Notify-backed wait list with no contention benefit, and would prevent
+    // running the limiter outside an async runtime context.
+    let mut bucket = entry.get().lock();
+    evaluate(&self.cfg, &mut bucket, clock::now(), f64::from(cost))
+  }
+}
+
 /// Write the IETF draft-`RateLimit-Headers` set into the response.
 ///
 /// PPL-16: previously this used `headers.insert(...)` which replaces any
@@ -134,23 +255,37 @@ fn evaluate(cfg: &Config, bucket: &mut Bucket, now: Instant) -> Outcome {
 /// header wins. In middleware chains the inner (closest-to-handler) limiter
 /// runs its post-processing FIRST on the response path, so first-wins is
 /// inner-wins — which is the more restrictive observable signal.
-fn write_rate_limit_headers(headers: &mut http::HeaderMap, cfg: &Config, outcome: &Outcome) {
-  if let Ok(v) = HeaderValue::from_str(&cfg.max_requests.to_string()) {
-    headers.entry("ratelimit-limit").or_insert(v);
-  }
-  if let Ok(v) = HeaderValue::from_str(&outcome.remaining.to_string()) {
-    headers.entry("ratelimit-remaining").or_insert(v);
-  }
-  if let Ok(v) = HeaderValue::from_str(&outcome.reset_secs.to_string()) {
-    headers.entry("ratelimit-reset").or_insert(v);
+fn write_rate_limit_headers(
+  headers: &mut http::HeaderMap,
+  snapshot: &RateLimitSnapshot,
+  policy: Option<&HeaderValue>,
+) {
+  headers
+    .entry("ratelimit-limit")
+    .or_insert(HeaderValue::from(snapshot.limit));
+  headers
+    .entry("ratelimit-remaining")
+    .or_insert(HeaderValue::from(snapshot.remaining));
+  headers
+    .entry("ratelimit-reset")
+    .or_insert(HeaderValue::from(snapshot.reset_secs));
+  if let Some(policy) = policy {
+    headers.entry("ratelimit-policy").or_insert(policy.clone());
   }
 }
 
+/// `RateLimit-Policy` for the limits in `cfg`, e.g. `100;w=60`.
+pub(crate) fn policy_header(cfg: &Config) -> Option<HeaderValue> {
+  let policy = format!("{};w={}", cfg.max_requests, cfg.window_secs().ceil() as u64);
+  HeaderValue::from_str(&policy).ok()
+}
+
 pub(crate) async fn handle(
   req: Request,
   next: Next,
   cfg: Config,
-  store: Arc<SccHashMap<String, Mutex<Bucket>>>,
+  store: Arc<dyn RateLimitStore>,
+  policy: Option<HeaderValue>,
   key_fn: Option<KeyFn>,
 ) -> Response {
   let key = match key_fn.as_ref() {
@@ -167,35 +302,22 @@ pub(crate) async fn handle(
     };
   };
 
-  let outcome = {
-    let entry = store.entry_async(key).await.or_insert_with(|| {
-      Mutex::new(Bucket {
-        available: f64::from(cfg.max_requests),
-        last_refill: Instant::now(),
-      })
-    });
-    // `parking_lot::Mutex` (sync lock) is deliberate here: we hold it across
-    // a strictly synchronous `evaluate` call and never `.await` under the
-    // guard. A `tokio::sync::Mutex` would force this hot path through a
-    // Notify-backed wait list with no contention benefit, and would prevent
-    // running the limiter outside an async runtime context.
-    let mut bucket = entry.get().lock();
-    evaluate(&cfg, &mut bucket, Instant::now())
-  };
-
-  if !outcome.allowed {
-    let mut resp = http::Response::builder()
-      .status(cfg.status_on_limit)
-      .body(TakoBody::empty())
-      .expect("valid rate-limit response");
-    write_rate_limit_headers(resp.headers_mut(), &cfg, &outcome);
-    if let Ok(v) = HeaderValue::from_str(&outcome.retry_after_secs.to_string()) {
-      resp.headers_mut().insert(RETRY_AFTER, v);
+  match store.consume(&key, 1).await {
+    Ok(snapshot) => {
+      let mut resp = next.run(req).await;
+      write_rate_limit_headers(resp.headers_mut(), &snapshot, policy.as_ref());
+      resp
+    }
+    Err(snapshot) => {
+      let mut resp = http::Response::builder()
+        .status(cfg.status_on_limit)
+        .body(TakoBody::empty())
+        .expect("valid rate-limit response");
+      write_rate_limit_headers(resp.headers_mut(), &snapshot, policy.as_ref());
+      resp
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(snapshot.retry_after_secs));
+      resp
     }
-    return resp;
   }
-
-  let mut resp = next.run(req).await;
-  write_rate_limit_headers(resp.headers_mut(), &cfg, &outcome);
-  resp
 }
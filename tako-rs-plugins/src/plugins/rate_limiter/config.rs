@@ -7,7 +7,8 @@ use http::StatusCode;
 use tako_rs_core::types::Request;
 
 /// Rate-limiting algorithm.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Algorithm {
   /// Classic token bucket. `refill_rate` tokens added every
   /// `refill_interval_ms`, capped at `max_requests` (burst capacity).
@@ -16,11 +17,18 @@ pub enum Algorithm {
   /// One token every `1 / rate_per_second` second; bursts up to
   /// `max_requests` allowed.
   Gcra,
+  /// Sliding-window counter: at most `max_requests` in any window of
+  /// `max_requests / rate` seconds (one minute for
+  /// `requests_per_minute`). The previous fixed window's count is weighted
+  /// by how much of it still overlaps, which smooths the double burst a
+  /// fixed window allows at its boundary.
+  SlidingWindow,
 }
 
 /// Behavior when a request cannot be keyed (unknown peer, custom key fn
 /// returned `None`).
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum UnkeyedBehavior {
   /// Allow the request through without rate-limit accounting.
   Allow,
@@ -33,9 +41,9 @@ pub enum UnkeyedBehavior {
 pub struct Config {
   /// Maximum burst capacity.
   pub max_requests: u32,
-  /// Tokens added per refill interval (`TokenBucket` only).
+  /// Tokens added per refill interval.
   pub refill_rate: u32,
-  /// Refill interval (`TokenBucket` only).
+  /// Refill interval.
   pub refill_interval_ms: u64,
   /// HTTP status returned on rejection.
   pub status_on_limit: StatusCode,
@@ -58,6 +66,20 @@ impl Default for Config {
   }
 }
 
+impl Config {
+  /// Sustained rate in requests per second.
+  pub(crate) fn rate_per_sec(&self) -> f64 {
+    f64::from(self.refill_rate) / (self.refill_interval_ms as f64 / 1_000.0)
+  }
+
+  /// Seconds to earn back a full burst: the `w` of `RateLimit-Policy` and
+  /// the window length of [`Algorithm::SlidingWindow`].
+  pub(crate) fn window_secs(&self) -> f64 {
+    f64::from(self.max_requests) / self.rate_per_sec()
+  }
+}
+
 /// Custom key function: maps a request to a rate-limit bucket id. Returning
-/// `None` defers to [`Config::on_unkeyed`].
+/// `None` defers to [`Config::on_unkeyed`]. Any [`KeyExtractor`](super::KeyExtractor)
+/// can be used through [`RateLimiterBuilder::key`](super::RateLimiterBuilder::key).
 pub type KeyFn = Arc<dyn Fn(&Request) -> Option<String> + Send + Sync + 'static>;
@@ -0,0 +1,167 @@
+//! Bucket keys: the [`KeyExtractor`] trait and the built-in extractors.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use http::HeaderName;
+use tako_rs_core::router_state::MatchedPath;
+use tako_rs_core::types::Request;
+
+use super::algorithm::default_key;
+use crate::middleware::session::Session;
+
+/// Maps a request to a rate-limit bucket id. Returning `None` defers to
+/// [`Config::on_unkeyed`](super::Config::on_unkeyed).
+///
+/// Implemented for every `Fn(&Request) -> Option<String>`, so closures work
+/// wherever an extractor is expected.
+pub trait KeyExtractor: Send + Sync + 'static {
+  /// The bucket id for `req`.
+  fn key(&self, req: &Request) -> Option<String>;
+}
+
+impl<F> KeyExtractor for F
+where
+  F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+{
+  fn key(&self, req: &Request) -> Option<String> {
+    self(req)
+  }
+}
+
+/// The peer IP address — the default key.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PeerIp;
+
+impl KeyExtractor for PeerIp {
+  fn key(&self, req: &Request) -> Option<String> {
+    default_key(req)
+  }
+}
+
+/// The value of an API key header (`x-api-key` by default). Requests without
+/// the header are unkeyed.
+#[derive(Clone, Debug)]
+pub struct ApiKeyHeader {
+  name: HeaderName,
+}
+
+impl Default for ApiKeyHeader {
+  fn default() -> Self {
+    Self {
+      name: HeaderName::from_static("x-api-key"),
+    }
+  }
+}
+
+impl ApiKeyHeader {
+  /// Reads the key from `name` instead of `x-api-key`.
+  pub fn new(name: HeaderName) -> Self {
+    Self { name }
+  }
+}
+
+impl KeyExtractor for ApiKeyHeader {
+  fn key(&self, req: &Request) -> Option<String> {
+    let value = req.headers().get(&self.name)?.to_str().ok()?;
+    (!value.is_empty()).then(|| format!("key:{value}"))
+  }
+}
+
+type SubjectFn<C> = Arc<dyn Fn(&C) -> Option<String> + Send + Sync>;
+
+/// The subject of a verified JWT.
+///
+/// Reads the claims [`JwtAuth`](crate::middleware::jwt_auth::JwtAuth)
+/// inserted into the request extensions, so the JWT middleware has to run
+/// before the limiter: register it globally, ahead of the plugin. Requests
+/// without claims are unkeyed. The token itself is never decoded here — a
+/// subject from an unverified token would let clients mint fresh buckets at
+/// will.
+pub struct JwtSubject<C> {
+  subject: SubjectFn<C>,
+  _claims: PhantomData<fn() -> C>,
+}
+
+impl<C> Clone for JwtSubject<C> {
+  fn clone(&self) -> Self {
+    Self {
+      subject: self.subject.clone(),
+      _claims: PhantomData,
+    }
+  }
+}
+
+impl<C: Send + Sync + 'static> JwtSubject<C> {
+  /// Keys by `subject(claims)`, typically the `sub` claim.
+  pub fn new<F>(subject: F) -> Self
+  where
+    F: Fn(&C) -> Option<String> + Send + Sync + 'static,
+  {
+    Self {
+      subject: Arc::new(subject),
+      _claims: PhantomData,
+    }
+  }
+}
+
+#[cfg(feature = "jwt-simple")]
+impl<T: Send + Sync + 'static> JwtSubject<jwt_simple::claims::JWTClaims<T>> {
+  /// Keys by the registered `sub` claim of a `jwt-simple` token.
+  pub fn registered() -> Self {
+    Self::new(|claims: &jwt_simple::claims::JWTClaims<T>| claims.subject.clone())
+  }
+}
+
+impl<C: Send + Sync + 'static> KeyExtractor for JwtSubject<C> {
+  fn key(&self, req: &Request) -> Option<String> {
+    let claims = req.extensions().get::<C>()?;
+    (self.subject)(claims).map(|sub| format!("sub:{sub}"))
+  }
+}
+
+/// A value stored in the request's [`Session`], such as the signed-in
+/// user's id. The session middleware has to run first; requests without a
+/// session, or whose session lacks the value, are unkeyed.
+#[derive(Clone, Debug)]
+pub struct SessionValue {
+  name: String,
+}
+
+impl SessionValue {
+  /// Keys by the session value stored under `name`.
+  pub fn new(name: impl Into<String>) -> Self {
+    Self { name: name.into() }
+  }
+}
+
+impl KeyExtractor for SessionValue {
+  fn key(&self, req: &Request) -> Option<String> {
+    let value = req
+      .extensions()
+      .get::<Session>()?
+      .get::<serde_json::Value>(&self.name)?;
+    match value {
+      serde_json::Value::Null => None,
+      serde_json::Value::String(s) => Some(format!("session:{s}")),
+      other => Some(format!("session:{other}")),
+    }
+  }
+}
+
+/// The matched route template combined with the peer IP, so each client
+/// gets a separate bucket per route. Requests that matched no route share
+/// one bucket per IP.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RouteAndIp;
+
+impl KeyExtractor for RouteAndIp {
+  fn key(&self, req: &Request) -> Option<String> {
+    let ip = default_key(req)?;
+    let route = req
+      .extensions()
+      .get::<MatchedPath>()
+      .map_or("*", MatchedPath::as_str);
+    Some(format!("{} {route}|{ip}", req.method()))
+  }
+}
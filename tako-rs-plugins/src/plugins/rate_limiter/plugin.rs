@@ -1,32 +1,48 @@
 //! The rate-limiter plugin: fluent builder, the plugin struct, and the
-//! [`TakoPlugin`] wiring that installs the middleware and the staleness
-//! janitor.
+//! [`TakoPlugin`] wiring that installs the middleware and, for the
+//! in-process store, the staleness janitor.
 
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
-use std::time::Instant;
 
 use anyhow::Result;
+use http::HeaderValue;
 use http::StatusCode;
 use parking_lot::Mutex;
 use scc::HashMap as SccHashMap;
+use tako_rs_core::clock;
 use tako_rs_core::plugins::TakoPlugin;
 use tako_rs_core::router::Router;
 use tako_rs_core::types::Request;
 
 use super::algorithm::Bucket;
+use super::algorithm::LocalStore;
 use super::algorithm::handle;
+use super::algorithm::policy_header;
 use super::config::Algorithm;
 use super::config::Config;
 use super::config::KeyFn;
 use super::config::UnkeyedBehavior;
+use super::key::KeyExtractor;
+use crate::stores::RateLimitStore;
+#[cfg(feature = "redis")]
+use crate::stores::redis::RedisRateLimitStore;
+
+/// Where buckets live.
+enum Backend {
+  Local,
+  Custom(Arc<dyn RateLimitStore>),
+  #[cfg(feature = "redis")]
+  Redis(redis::aio::ConnectionManager, String),
+}
 
 /// Builder.
 pub struct RateLimiterBuilder {
   cfg: Config,
   key_fn: Option<KeyFn>,
+  backend: Backend,
 }
 
 impl Default for RateLimiterBuilder {
@@ -40,6 +56,7 @@ impl RateLimiterBuilder {
     Self {
       cfg: Config::default(),
       key_fn: None,
+      backend: Backend::Local,
     }
   }
 
@@ -85,6 +102,32 @@ impl RateLimiterBuilder {
     self
   }
 
+  /// Key buckets with `extractor`: [`ApiKeyHeader`](super::ApiKeyHeader),
+  /// [`JwtSubject`](super::JwtSubject), [`RouteAndIp`](super::RouteAndIp),
+  /// or your own [`KeyExtractor`].
+  pub fn key<K: KeyExtractor>(self, extractor: K) -> Self {
+    self.key_fn(move |req| extractor.key(req))
+  }
+
+  /// Keep buckets in `store` instead of in process memory. The store
+  /// enforces its own limits: `max_requests`, the refill settings and
+  /// `algorithm` only configure the built-in stores, and no
+  /// `RateLimit-Policy` header is sent.
+  pub fn store(mut self, store: impl RateLimitStore) -> Self {
+    self.backend = Backend::Custom(Arc::new(store));
+    self
+  }
+
+  /// Keep buckets in Redis so every instance behind a load balancer draws
+  /// from the same quota. The limits and algorithm configured on this
+  /// builder apply; keys are prefixed with `prefix` (e.g. `rl:api:`).
+  #[cfg(feature = "redis")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "redis")))]
+  pub fn redis(mut self, conn: redis::aio::ConnectionManager, prefix: impl Into<String>) -> Self {
+    self.backend = Backend::Redis(conn, prefix.into());
+    self
+  }
+
   /// Convenience: N requests / second.
   pub fn requests_per_second(mut self, n: u32) -> Self {
     self.cfg.max_requests = n;
@@ -131,10 +174,27 @@ impl RateLimiterBuilder {
       self.cfg.max_requests > 0,
       "RateLimiter::max_requests must be > 0 (zero cap silently denies every request)"
     );
+    let policy = policy_header(&self.cfg);
+    let (store, policy, local): (Arc<dyn RateLimitStore>, _, _) = match self.backend {
+      Backend::Local => {
+        let local = LocalStore::new(self.cfg.clone());
+        let buckets = local.buckets.clone();
+        (Arc::new(local), policy, Some(buckets))
+      }
+      Backend::Custom(store) => (store, None, None),
+      #[cfg(feature = "redis")]
+      Backend::Redis(conn, prefix) => (
+        Arc::new(RedisRateLimitStore::from_config(conn, &self.cfg).prefix(prefix)),
+        policy,
+        None,
+      ),
+    };
     RateLimiterPlugin {
       cfg: self.cfg,
       key_fn: self.key_fn,
-      store: Arc::new(SccHashMap::new()),
+      store,
+      policy,
+      local,
       task_started: Arc::new(AtomicBool::new(false)),
     }
   }
@@ -146,7 +206,10 @@ impl RateLimiterBuilder {
 pub struct RateLimiterPlugin {
   cfg: Config,
   key_fn: Option<KeyFn>,
-  store: Arc<SccHashMap<String, Mutex<Bucket>>>,
+  store: Arc<dyn RateLimitStore>,
+  policy: Option<HeaderValue>,
+  /// Buckets of the in-process store, swept by the janitor.
+  local: Option<Arc<SccHashMap<String, Mutex<Bucket>>>>,
   task_started: Arc<AtomicBool>,
 }
 
@@ -158,20 +221,22 @@ impl TakoPlugin for RateLimiterPlugin {
   fn setup(&self, router: &Router) -> Result<()> {
     let cfg = self.cfg.clone();
     let store = self.store.clone();
+    let policy = self.policy.clone();
     let key_fn = self.key_fn.clone();
 
     router.middleware(move |req, next| {
       let cfg = cfg.clone();
       let store = store.clone();
+      let policy = policy.clone();
       let key_fn = key_fn.clone();
-      async move { handle(req, next, cfg, store, key_fn).await }
+      async move { handle(req, next, cfg, store, policy, key_fn).await }
     });
 
-    if matches!(self.cfg.algorithm, Algorithm::TokenBucket)
+    if let Some(store) = self.local.clone()
+      && !matches!(self.cfg.algorithm, Algorithm::Gcra)
       && !self.task_started.swap(true, Ordering::SeqCst)
     {
       let cfg = self.cfg.clone();
-      let store = self.store.clone();
 
       // Janitor is **staleness-eviction only**. Refilling here too would
       // double-count: `evaluate()` already does lazy refill per request
@@ -181,7 +246,14 @@ impl TakoPlugin for RateLimiterPlugin {
       // DoS-quota control. We also can't mutate `last_refill` here
       // because doing so before the staleness predicate makes
       // `duration_since` always 0 and turns `purge_after` into dead code.
-      let purge_after = Duration::from_secs(300);
+      // A sliding-window bucket idle for two windows has counted down to
+      // nothing, so dropping it is unobservable.
+      let purge_after = match self.cfg.algorithm {
+        Algorithm::SlidingWindow => {
+          Duration::from_secs_f64(2.0 * self.cfg.window_secs()).max(Duration::from_secs(300))
+        }
+        _ => Duration::from_secs(300),
+      };
       let interval = Duration::from_millis(cfg.refill_interval_ms);
 
       #[cfg(not(feature = "compio"))]
@@ -189,7 +261,7 @@ impl TakoPlugin for RateLimiterPlugin {
         let mut tick = tokio::time::interval(interval);
         loop {
           tick.tick().await;
-          let now = Instant::now();
+          let now = clock::now();
           store
             .retain_async(|_, mutex| {
               let bucket = mutex.lock();
@@ -203,7 +275,7 @@ impl TakoPlugin for RateLimiterPlugin {
       compio::runtime::spawn(async move {
         loop {
           compio::time::sleep(interval).await;
-          let now = Instant::now();
+          let now = clock::now();
           store
             .retain_async(|_, mutex| {
               let bucket = mutex.lock();
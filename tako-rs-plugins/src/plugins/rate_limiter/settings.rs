@@ -0,0 +1,95 @@
+//! Serde-loadable rate limiter settings.
+
+use serde::Deserialize;
+
+use super::config::Algorithm;
+use super::config::Config;
+use super::config::UnkeyedBehavior;
+use super::plugin::RateLimiterBuilder;
+use super::plugin::RateLimiterPlugin;
+use crate::plugins::settings;
+use crate::plugins::settings::SettingsError;
+
+const PLUGIN: &str = "rate_limiter";
+
+/// Rate limiter settings as read from a configuration file.
+///
+/// The key function and a shared store are code-only: set them on the
+/// builder returned by [`Settings::builder`].
+///
+/// ```toml
+/// max_requests = 100
+/// refill_rate = 100
+/// refill_interval_ms = 60000
+/// algorithm = "sliding_window"
+/// on_unkeyed = "reject"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+  /// Burst capacity.
+  pub max_requests: u32,
+  /// Tokens added per refill interval.
+  pub refill_rate: u32,
+  /// Refill interval in milliseconds.
+  pub refill_interval_ms: u64,
+  /// Status answered when limited, 4xx or 5xx.
+  pub status: u16,
+  /// `token_bucket`, `gcra` or `sliding_window`.
+  pub algorithm: Algorithm,
+  /// `allow` or `reject` requests that cannot be keyed.
+  pub on_unkeyed: UnkeyedBehavior,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    let cfg = Config::default();
+    Self {
+      max_requests: cfg.max_requests,
+      refill_rate: cfg.refill_rate,
+      refill_interval_ms: cfg.refill_interval_ms,
+      status: cfg.status_on_limit.as_u16(),
+      algorithm: cfg.algorithm,
+      on_unkeyed: cfg.on_unkeyed,
+    }
+  }
+}
+
+impl Settings {
+  /// Validates the settings into a builder.
+  ///
+  /// Rejects what [`RateLimiterBuilder::build`] would panic on.
+  pub fn builder(&self) -> Result<RateLimiterBuilder, SettingsError> {
+    for (field, value) in [
+      ("max_requests", u64::from(self.max_requests)),
+      ("refill_rate", u64::from(self.refill_rate)),
+      ("refill_interval_ms", self.refill_interval_ms),
+    ] {
+      if value == 0 {
+        return Err(SettingsError::new(PLUGIN, field, "must be greater than 0"));
+      }
+    }
+    let status = settings::status(PLUGIN, "status", self.status)?;
+    if !(status.is_client_error() || status.is_server_error()) {
+      return Err(SettingsError::new(
+        PLUGIN,
+        "status",
+        format!("{} is not an error status", self.status),
+      ));
+    }
+    Ok(
+      RateLimiterBuilder::new()
+        .max_requests(self.max_requests)
+        .refill_rate(self.refill_rate)
+        .refill_interval_ms(self.refill_interval_ms)
+        .status(status)
+        .algorithm(self.algorithm)
+        .on_unkeyed(self.on_unkeyed),
+    )
+  }
+
+  /// Validates the settings into the plugin.
+  pub fn build(&self) -> Result<RateLimiterPlugin, SettingsError> {
+    self.builder().map(RateLimiterBuilder::build)
+  }
+}
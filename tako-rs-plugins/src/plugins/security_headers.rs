@@ -0,0 +1,189 @@
+#![cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+//! Security headers as a router-wide plugin.
+//!
+//! [`SecurityHeadersPlugin`] installs the
+//! [`SecurityHeaders`](crate::middleware::security_headers::SecurityHeaders)
+//! middleware on every route of the router it is registered with, so the
+//! headers — HSTS, CSP with optional per-request nonces, `X-Content-Type-Options`,
+//! `Referrer-Policy`, `Permissions-Policy`, and the rest — cannot be missed
+//! on a newly added route. Configure it with the same builder; handlers read
+//! the nonce through the
+//! [`CspNonce`](crate::middleware::security_headers::CspNonce) extractor.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use tako::middleware::security_headers::SecurityHeaders;
+//! use tako::plugins::security_headers::SecurityHeadersPlugin;
+//! use tako::router::Router;
+//!
+//! let mut router = Router::new();
+//! router.plugin(SecurityHeadersPlugin::new(
+//!     SecurityHeaders::new()
+//!         .hsts(true)
+//!         .csp_with_nonce("default-src 'self'; script-src 'nonce-{nonce}'")
+//!         .permissions_policy("camera=(), microphone=()"),
+//! ));
+//! ```
+//!
+//! [`Settings`] builds the same middleware from a configuration file.
+
+use anyhow::Result;
+use serde::Deserialize;
+use tako_rs_core::middleware::IntoMiddleware;
+use tako_rs_core::plugins::TakoPlugin;
+use tako_rs_core::router::Router;
+
+use crate::middleware::security_headers::CspMode;
+use crate::middleware::security_headers::SecurityHeaders;
+use crate::plugins::settings;
+use crate::plugins::settings::SettingsError;
+
+/// Applies [`SecurityHeaders`] to every response of a router.
+#[derive(Clone, Default)]
+pub struct SecurityHeadersPlugin {
+  headers: SecurityHeaders,
+}
+
+impl SecurityHeadersPlugin {
+  /// Applies `headers` router-wide.
+  pub fn new(headers: SecurityHeaders) -> Self {
+    Self { headers }
+  }
+}
+
+impl From<SecurityHeaders> for SecurityHeadersPlugin {
+  fn from(headers: SecurityHeaders) -> Self {
+    Self::new(headers)
+  }
+}
+
+impl TakoPlugin for SecurityHeadersPlugin {
+  fn name(&self) -> &'static str {
+    "SecurityHeadersPlugin"
+  }
+
+  fn setup(&self, router: &Router) -> Result<()> {
+    router.middleware(self.headers.clone().into_middleware());
+    Ok(())
+  }
+}
+
+const PLUGIN: &str = "security_headers";
+
+/// Security header settings as read from a configuration file.
+///
+/// A `csp` containing `{nonce}` gets a fresh nonce per request, as with
+/// [`SecurityHeaders::csp_with_nonce`]; unset headers are not sent.
+///
+/// ```toml
+/// hsts = true
+/// frame_options = "SAMEORIGIN"
+/// csp = "default-src 'self'; script-src 'nonce-{nonce}'"
+/// permissions_policy = "camera=(), microphone=()"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Settings {
+  /// `X-Frame-Options`: `DENY` or `SAMEORIGIN`.
+  pub frame_options: String,
+  /// Send `Strict-Transport-Security`.
+  pub hsts: bool,
+  /// HSTS `max-age` in seconds.
+  pub hsts_max_age: u64,
+  /// HSTS `includeSubDomains`.
+  pub hsts_include_subdomains: bool,
+  /// HSTS `preload`; needs a one-year `max-age` and `includeSubDomains`.
+  pub hsts_preload: bool,
+  /// `Referrer-Policy`.
+  pub referrer_policy: String,
+  /// `Content-Security-Policy`.
+  pub csp: Option<String>,
+  /// Send the CSP as `Content-Security-Policy-Report-Only`.
+  pub csp_report_only: bool,
+  /// `Cross-Origin-Opener-Policy`.
+  pub coop: Option<String>,
+  /// `Cross-Origin-Embedder-Policy`.
+  pub coep: Option<String>,
+  /// `Cross-Origin-Resource-Policy`.
+  pub corp: Option<String>,
+  /// `Permissions-Policy`.
+  pub permissions_policy: Option<String>,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    let defaults = SecurityHeaders::new();
+    Self {
+      frame_options: "DENY".to_string(),
+      hsts: defaults.hsts,
+      hsts_max_age: defaults.hsts_max_age,
+      hsts_include_subdomains: defaults.hsts_include_subdomains,
+      hsts_preload: defaults.hsts_preload,
+      referrer_policy: "strict-origin-when-cross-origin".to_string(),
+      csp: None,
+      csp_report_only: false,
+      coop: None,
+      coep: None,
+      corp: None,
+      permissions_policy: None,
+    }
+  }
+}
+
+impl Settings {
+  /// Validates the settings into the middleware.
+  pub fn builder(&self) -> Result<SecurityHeaders, SettingsError> {
+    let frame_options = self.frame_options.to_ascii_uppercase();
+    if frame_options != "DENY" && frame_options != "SAMEORIGIN" {
+      return Err(SettingsError::new(
+        PLUGIN,
+        "frame_options",
+        format!("expected DENY or SAMEORIGIN, got `{}`", self.frame_options),
+      ));
+    }
+    if self.hsts_preload && (self.hsts_max_age < 31_536_000 || !self.hsts_include_subdomains) {
+      return Err(SettingsError::new(
+        PLUGIN,
+        "hsts_preload",
+        "preload requires hsts_max_age >= 31536000 and hsts_include_subdomains",
+      ));
+    }
+    let optional = |field, value: &Option<String>| {
+      value
+        .as_deref()
+        .map(|v| settings::header_value(PLUGIN, field, v))
+        .transpose()
+    };
+
+    let mut headers = SecurityHeaders::new()
+      .hsts(self.hsts)
+      .hsts_max_age(self.hsts_max_age)
+      .hsts_include_subdomains(self.hsts_include_subdomains)
+      .hsts_preload(self.hsts_preload);
+    headers.frame_options = settings::header_value(PLUGIN, "frame_options", &frame_options)?;
+    headers.referrer_policy =
+      settings::header_value(PLUGIN, "referrer_policy", &self.referrer_policy)?;
+    headers.coop = optional("coop", &self.coop)?;
+    headers.coep = optional("coep", &self.coep)?;
+    headers.corp = optional("corp", &self.corp)?;
+    headers.permissions_policy = optional("permissions_policy", &self.permissions_policy)?;
+    if let Some(csp) = &self.csp {
+      headers = if self.csp_report_only {
+        headers.csp_report_only(csp.clone())
+      } else if csp.contains("{nonce}") {
+        headers.csp_with_nonce(csp.clone())
+      } else {
+        headers.csp = Some(CspMode::Static(settings::header_value(PLUGIN, "csp", csp)?));
+        headers
+      };
+    }
+    Ok(headers)
+  }
+
+  /// Validates the settings into the plugin.
+  pub fn build(&self) -> Result<SecurityHeadersPlugin, SettingsError> {
+    self.builder().map(SecurityHeadersPlugin::new)
+  }
+}
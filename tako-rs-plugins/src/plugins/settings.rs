@@ -0,0 +1,134 @@
+//! Plugin settings loaded from configuration files.
+//!
+//! Each built-in plugin with a builder also has a `Settings` struct —
+//! [`compression::Settings`](super::compression::Settings),
+//! [`cors::Settings`](super::cors::Settings),
+//! [`rate_limiter::Settings`](super::rate_limiter::Settings),
+//! [`idempotency::Settings`](super::idempotency::Settings),
+//! [`cache::Settings`](super::cache::Settings) and
+//! [`security_headers::Settings`](super::security_headers::Settings). They
+//! deserialize with serde from JSON, TOML, YAML or environment variables
+//! (through `tako::config::Config`), default every field to the builder's
+//! default, and reject unknown fields. `builder()` validates them and
+//! returns the plugin's builder, for code-only options such as key
+//! functions; `build()` goes straight to the plugin.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use serde::Deserialize;
+//! use tako::config::Config;
+//! use tako::plugins::{compression, cors};
+//!
+//! #[derive(Clone, Deserialize)]
+//! struct AppConfig {
+//!     compression: compression::Settings,
+//!     cors: cors::Settings,
+//! }
+//!
+//! // { "compression": { "encodings": ["br", "gzip"], "min_size": 512 },
+//! //   "cors": { "origins": ["https://app.example.com"] } }
+//! let Config(cfg) = Config::<AppConfig>::from_json_file("app.json")?;
+//! router.plugin(cfg.compression.build()?);
+//! router.plugin(cfg.cors.build()?);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use http::HeaderName;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+
+/// A plugin setting that failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsError {
+  /// The plugin, e.g. `"cors"`.
+  pub plugin: &'static str,
+  /// The offending field.
+  pub field: &'static str,
+  /// What is wrong with it.
+  pub message: String,
+}
+
+impl SettingsError {
+  pub(crate) fn new(plugin: &'static str, field: &'static str, message: impl Into<String>) -> Self {
+    Self {
+      plugin,
+      field,
+      message: message.into(),
+    }
+  }
+}
+
+impl fmt::Display for SettingsError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}.{}: {}", self.plugin, self.field, self.message)
+  }
+}
+
+impl std::error::Error for SettingsError {}
+
+/// Parses method names such as `"GET"`.
+pub(crate) fn methods(
+  plugin: &'static str,
+  field: &'static str,
+  names: &[String],
+) -> Result<Vec<Method>, SettingsError> {
+  names
+    .iter()
+    .map(|name| {
+      Method::from_bytes(name.to_ascii_uppercase().as_bytes())
+        .map_err(|_| SettingsError::new(plugin, field, format!("invalid method `{name}`")))
+    })
+    .collect()
+}
+
+/// Parses a header name.
+pub(crate) fn header_name(
+  plugin: &'static str,
+  field: &'static str,
+  name: &str,
+) -> Result<HeaderName, SettingsError> {
+  HeaderName::from_str(name)
+    .map_err(|_| SettingsError::new(plugin, field, format!("invalid header name `{name}`")))
+}
+
+/// Parses a header value.
+pub(crate) fn header_value(
+  plugin: &'static str,
+  field: &'static str,
+  value: &str,
+) -> Result<HeaderValue, SettingsError> {
+  HeaderValue::from_str(value)
+    .map_err(|_| SettingsError::new(plugin, field, format!("invalid header value `{value}`")))
+}
+
+/// Parses a status code.
+pub(crate) fn status(
+  plugin: &'static str,
+  field: &'static str,
+  code: u16,
+) -> Result<StatusCode, SettingsError> {
+  StatusCode::from_u16(code)
+    .map_err(|_| SettingsError::new(plugin, field, format!("invalid status code {code}")))
+}
+
+/// Fails unless `value` lies in `range`.
+pub(crate) fn in_range<T: PartialOrd + fmt::Display>(
+  plugin: &'static str,
+  field: &'static str,
+  value: T,
+  range: std::ops::RangeInclusive<T>,
+) -> Result<T, SettingsError> {
+  if range.contains(&value) {
+    Ok(value)
+  } else {
+    Err(SettingsError::new(
+      plugin,
+      field,
+      format!("{value} is outside {}..={}", range.start(), range.end()),
+    ))
+  }
+}
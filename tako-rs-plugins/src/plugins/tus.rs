@@ -0,0 +1,47 @@
+#![cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
+//! Resumable uploads over the [tus](https://tus.io/protocols/resumable-upload) protocol.
+//!
+//! The plugin reserves a base path (default `/files`) and implements tus
+//! 1.0.0 core plus the `creation`, `expiration`, and `termination`
+//! extensions:
+//!
+//! - `OPTIONS /files` advertises the protocol version, extensions, and the
+//!   optional maximum upload size.
+//! - `POST /files` creates an upload from `Upload-Length` (and optional
+//!   `Upload-Metadata`) and answers `201 Created` with its `Location`.
+//! - `HEAD /files/{id}` reports the current `Upload-Offset` so a client can
+//!   resume after a dropped connection.
+//! - `PATCH /files/{id}` appends an `application/offset+octet-stream` body at
+//!   the given offset. Bytes are streamed into the store frame by frame, so
+//!   whatever arrived before a disconnect is kept.
+//! - `DELETE /files/{id}` terminates an upload.
+//!
+//! Unfinished uploads expire after a configurable lifetime and are swept by
+//! a background janitor. Storage is pluggable through
+//! [`TusStore`](crate::stores::TusStore); the default keeps bytes in memory.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use tako::plugins::tus::TusBuilder;
+//! use tako::router::Router;
+//!
+//! let mut router = Router::new();
+//! router.plugin(
+//!     TusBuilder::new()
+//!         .base_path("/uploads")
+//!         .max_size(4 * 1024 * 1024 * 1024)
+//!         .expiration(Duration::from_secs(6 * 3600))
+//!         .on_complete(|upload| println!("finished {}", upload.id))
+//!         .build(),
+//! );
+//! ```
+
+mod config;
+mod plugin;
+
+pub use config::Config;
+pub use config::TusBuilder;
+pub use plugin::TUS_VERSION;
+pub use plugin::TusPlugin;
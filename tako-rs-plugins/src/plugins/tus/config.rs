@@ -0,0 +1,100 @@
+//! tus plugin configuration and its builder.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::plugin::TusPlugin;
+use crate::stores::TusStore;
+use crate::stores::TusUpload;
+use crate::stores::memory::MemoryTusStore;
+
+/// Callback fired once an upload has received every declared byte.
+pub type CompleteCallback = Arc<dyn Fn(TusUpload) + Send + Sync + 'static>;
+
+/// Runtime settings for the tus plugin.
+#[derive(Clone)]
+pub struct Config {
+  /// Collection URL; uploads live at `{base_path}/{id}`. Default: `/files`.
+  pub base_path: String,
+  /// Largest `Upload-Length` accepted, advertised as `Tus-Max-Size`.
+  pub max_size: Option<u64>,
+  /// Lifetime of an unfinished upload. Default: 24 hours.
+  pub expiration: Option<Duration>,
+  /// Backend holding upload state and bytes.
+  pub store: Arc<dyn TusStore>,
+  /// Invoked when an upload completes.
+  pub on_complete: Option<CompleteCallback>,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      base_path: "/files".to_string(),
+      max_size: None,
+      expiration: Some(Duration::from_secs(24 * 3600)),
+      store: Arc::new(MemoryTusStore::new()),
+      on_complete: None,
+    }
+  }
+}
+
+/// Builder for [`TusPlugin`].
+pub struct TusBuilder(Config);
+
+impl Default for TusBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl TusBuilder {
+  /// Starts from the defaults: `/files`, in-memory store, 24h expiration.
+  pub fn new() -> Self {
+    Self(Config::default())
+  }
+
+  /// Sets the collection URL. A trailing slash is ignored.
+  pub fn base_path(mut self, path: impl Into<String>) -> Self {
+    let path = path.into();
+    self.0.base_path = path.trim_end_matches('/').to_string();
+    self
+  }
+
+  /// Rejects uploads larger than `bytes` with `413 Payload Too Large`.
+  pub fn max_size(mut self, bytes: u64) -> Self {
+    self.0.max_size = Some(bytes);
+    self
+  }
+
+  /// Sets how long an unfinished upload is kept.
+  pub fn expiration(mut self, ttl: Duration) -> Self {
+    self.0.expiration = Some(ttl);
+    self
+  }
+
+  /// Keeps unfinished uploads until they are deleted explicitly.
+  pub fn no_expiration(mut self) -> Self {
+    self.0.expiration = None;
+    self
+  }
+
+  /// Replaces the storage backend.
+  pub fn store<S: TusStore>(mut self, store: S) -> Self {
+    self.0.store = Arc::new(store);
+    self
+  }
+
+  /// Registers a callback fired when an upload has been fully received.
+  pub fn on_complete<F>(mut self, f: F) -> Self
+  where
+    F: Fn(TusUpload) + Send + Sync + 'static,
+  {
+    self.0.on_complete = Some(Arc::new(f));
+    self
+  }
+
+  /// Builds the tus plugin with the configured settings.
+  pub fn build(self) -> TusPlugin {
+    TusPlugin::new(self.0)
+  }
+}
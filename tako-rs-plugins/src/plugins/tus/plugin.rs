@@ -0,0 +1,375 @@
+//! The tus plugin: janitor wiring and the protocol request handlers.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use http::HeaderMap;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header::CACHE_CONTROL;
+use http::header::CONTENT_TYPE;
+use http_body_util::BodyExt;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::middleware::Next;
+use tako_rs_core::plugins::TakoPlugin;
+use tako_rs_core::router::Router;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+
+use super::config::Config;
+use super::config::TusBuilder;
+use crate::stores::TusUpload;
+
+/// Protocol version implemented by the plugin.
+pub const TUS_VERSION: &str = "1.0.0";
+
+const TUS_EXTENSIONS: &str = "creation,expiration,termination";
+const OFFSET_OCTET_STREAM: &str = "application/offset+octet-stream";
+
+/// tus resumable upload plugin. Attach at router level.
+#[derive(Clone)]
+#[doc(alias = "tus")]
+#[doc(alias = "resumable")]
+pub struct TusPlugin {
+  cfg: Config,
+  janitor_started: Arc<AtomicBool>,
+}
+
+impl TusPlugin {
+  /// Creates a builder starting from the default settings.
+  pub fn builder() -> TusBuilder {
+    TusBuilder::new()
+  }
+
+  /// Creates the plugin from a complete configuration. The expiry janitor
+  /// starts when the plugin is set up on a router.
+  pub fn new(cfg: Config) -> Self {
+    Self {
+      cfg,
+      janitor_started: Arc::new(AtomicBool::new(false)),
+    }
+  }
+}
+
+impl TakoPlugin for TusPlugin {
+  fn name(&self) -> &'static str {
+    "TusPlugin"
+  }
+
+  fn setup(&self, router: &Router) -> Result<()> {
+    let cfg = self.cfg.clone();
+    router.middleware(move |req, next| {
+      let cfg = cfg.clone();
+      async move { handle(req, next, cfg).await }
+    });
+
+    // Like the idempotency janitor, the sweep task lives for the runtime and
+    // is started at most once per plugin instance.
+    if let Some(ttl) = self.cfg.expiration
+      && !self.janitor_started.swap(true, Ordering::SeqCst)
+    {
+      let store = Arc::clone(&self.cfg.store);
+      let interval = ttl.clamp(Duration::from_secs(5), Duration::from_secs(3600));
+
+      #[cfg(not(feature = "compio"))]
+      tokio::spawn(async move {
+        let mut tick = tokio::time::interval(interval);
+        loop {
+          tick.tick().await;
+          store.sweep_expired(SystemTime::now()).await;
+        }
+      });
+
+      #[cfg(feature = "compio")]
+      compio::runtime::spawn(async move {
+        loop {
+          compio::time::sleep(interval).await;
+          store.sweep_expired(SystemTime::now()).await;
+        }
+      })
+      .detach();
+    }
+
+    Ok(())
+  }
+}
+
+/// Which tus resource a request path addresses.
+enum Target<'a> {
+  Collection,
+  Upload(&'a str),
+}
+
+fn target<'a>(path: &'a str, base: &str) -> Option<Target<'a>> {
+  let rest = path.strip_prefix(base)?;
+  match rest {
+    "" | "/" => Some(Target::Collection),
+    _ => {
+      let id = rest.strip_prefix('/')?;
+      (!id.is_empty() && !id.contains('/')).then_some(Target::Upload(id))
+    }
+  }
+}
+
+async fn handle(req: Request, next: Next, cfg: Config) -> Response {
+  let path = req.uri().path().to_string();
+  let Some(target) = target(&path, &cfg.base_path) else {
+    return next.run(req).await;
+  };
+
+  if req.method() == Method::OPTIONS {
+    return options(&cfg);
+  }
+
+  // Every non-OPTIONS request must declare the protocol version it speaks.
+  if req
+    .headers()
+    .get("tus-resumable")
+    .map(HeaderValue::as_bytes)
+    != Some(TUS_VERSION.as_bytes())
+  {
+    let mut resp = tus_response(StatusCode::PRECONDITION_FAILED);
+    set(resp.headers_mut(), "tus-version", TUS_VERSION);
+    return resp;
+  }
+
+  match (target, req.method().clone()) {
+    (Target::Collection, Method::POST) => create(req, &cfg).await,
+    (Target::Upload(id), Method::HEAD) => head(id, &cfg).await,
+    (Target::Upload(id), Method::PATCH) => {
+      let id = id.to_string();
+      patch(req, &id, &cfg).await
+    }
+    (Target::Upload(id), Method::DELETE) => terminate(id, &cfg).await,
+    (Target::Collection, _) => method_not_allowed("OPTIONS, POST"),
+    (Target::Upload(_), _) => method_not_allowed("OPTIONS, HEAD, PATCH, DELETE"),
+  }
+}
+
+fn options(cfg: &Config) -> Response {
+  let mut resp = tus_response(StatusCode::NO_CONTENT);
+  let headers = resp.headers_mut();
+  set(headers, "tus-version", TUS_VERSION);
+  set(headers, "tus-extension", TUS_EXTENSIONS);
+  if let Some(max) = cfg.max_size {
+    set(headers, "tus-max-size", &max.to_string());
+  }
+  resp
+}
+
+async fn create(req: Request, cfg: &Config) -> Response {
+  let Some(length) = header_u64(req.headers(), "upload-length") else {
+    return tus_error(StatusCode::BAD_REQUEST, "Upload-Length is required");
+  };
+  if cfg.max_size.is_some_and(|max| length > max) {
+    return tus_error(StatusCode::PAYLOAD_TOO_LARGE, "Upload exceeds Tus-Max-Size");
+  }
+  let metadata = match req
+    .headers()
+    .get("upload-metadata")
+    .map(HeaderValue::to_str)
+  {
+    Some(Ok(v)) => Some(v.to_string()),
+    Some(Err(_)) => return tus_error(StatusCode::BAD_REQUEST, "Upload-Metadata must be ASCII"),
+    None => None,
+  };
+
+  let upload = TusUpload {
+    id: uuid::Uuid::new_v4().simple().to_string(),
+    length,
+    offset: 0,
+    metadata,
+    expires_at: cfg.expiration.map(|ttl| SystemTime::now() + ttl),
+  };
+  if let Err(e) = cfg.store.create(upload.clone()).await {
+    tracing::error!(error = %e, "tus: failed to create upload");
+    return tus_response(StatusCode::INTERNAL_SERVER_ERROR);
+  }
+
+  let mut resp = tus_response(StatusCode::CREATED);
+  let location = format!("{}/{}", cfg.base_path, upload.id);
+  set(resp.headers_mut(), "location", &location);
+  set_expires(resp.headers_mut(), &upload);
+  if length == 0 {
+    complete(cfg, upload);
+  }
+  resp
+}
+
+async fn head(id: &str, cfg: &Config) -> Response {
+  let upload = match lookup(id, cfg).await {
+    Ok(upload) => upload,
+    Err(resp) => return resp,
+  };
+  let mut resp = tus_response(StatusCode::OK);
+  let headers = resp.headers_mut();
+  set(headers, "upload-offset", &upload.offset.to_string());
+  set(headers, "upload-length", &upload.length.to_string());
+  if let Some(meta) = &upload.metadata {
+    set(headers, "upload-metadata", meta);
+  }
+  headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+  set_expires(headers, &upload);
+  resp
+}
+
+async fn patch(req: Request, id: &str, cfg: &Config) -> Response {
+  let content_type = req.headers().get(CONTENT_TYPE).map(HeaderValue::as_bytes);
+  if content_type != Some(OFFSET_OCTET_STREAM.as_bytes()) {
+    return tus_error(
+      StatusCode::UNSUPPORTED_MEDIA_TYPE,
+      "Content-Type must be application/offset+octet-stream",
+    );
+  }
+  let Some(client_offset) = header_u64(req.headers(), "upload-offset") else {
+    return tus_error(StatusCode::BAD_REQUEST, "Upload-Offset is required");
+  };
+  let mut upload = match lookup(id, cfg).await {
+    Ok(upload) => upload,
+    Err(resp) => return resp,
+  };
+  if client_offset != upload.offset {
+    return tus_error(StatusCode::CONFLICT, "Upload-Offset does not match");
+  }
+
+  // Stream frames straight into the store: each chunk is persisted before
+  // the next one is read, so a dropped connection loses nothing that was
+  // already received and the client resumes from the stored offset.
+  let mut body = req.into_body();
+  let mut failure = None;
+  while let Some(frame) = body.frame().await {
+    let Ok(frame) = frame else {
+      failure = Some(tus_error(
+        StatusCode::BAD_REQUEST,
+        "Upload body was interrupted",
+      ));
+      break;
+    };
+    let Ok(chunk) = frame.into_data() else {
+      continue;
+    };
+    if chunk.is_empty() {
+      continue;
+    }
+    if upload.offset + chunk.len() as u64 > upload.length {
+      failure = Some(tus_error(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        "Body exceeds Upload-Length",
+      ));
+      break;
+    }
+    match cfg.store.append(id, upload.offset, chunk).await {
+      Ok(offset) => upload.offset = offset,
+      Err(e) if e.kind() == std::io::ErrorKind::InvalidInput => {
+        failure = Some(tus_error(
+          StatusCode::CONFLICT,
+          "Upload-Offset does not match",
+        ));
+        break;
+      }
+      Err(e) => {
+        tracing::error!(error = %e, upload = id, "tus: failed to append chunk");
+        failure = Some(tus_response(StatusCode::INTERNAL_SERVER_ERROR));
+        break;
+      }
+    }
+  }
+
+  let mut resp = failure.unwrap_or_else(|| tus_response(StatusCode::NO_CONTENT));
+  set(
+    resp.headers_mut(),
+    "upload-offset",
+    &upload.offset.to_string(),
+  );
+  set_expires(resp.headers_mut(), &upload);
+  if upload.is_complete() && resp.status() == StatusCode::NO_CONTENT {
+    complete(cfg, upload);
+  }
+  resp
+}
+
+async fn terminate(id: &str, cfg: &Config) -> Response {
+  match cfg.store.remove(id).await {
+    Ok(true) => tus_response(StatusCode::NO_CONTENT),
+    Ok(false) => tus_response(StatusCode::NOT_FOUND),
+    Err(e) => {
+      tracing::error!(error = %e, upload = id, "tus: failed to remove upload");
+      tus_response(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+  }
+}
+
+/// Loads an upload, mapping missing and expired ones to 404 / 410.
+async fn lookup(id: &str, cfg: &Config) -> std::result::Result<TusUpload, Response> {
+  match cfg.store.info(id).await {
+    Ok(Some(upload)) if upload.is_expired(SystemTime::now()) => {
+      let _ = cfg.store.remove(id).await;
+      Err(tus_response(StatusCode::GONE))
+    }
+    Ok(Some(upload)) => Ok(upload),
+    Ok(None) => Err(tus_response(StatusCode::NOT_FOUND)),
+    Err(e) => {
+      tracing::error!(error = %e, upload = id, "tus: failed to read upload");
+      Err(tus_response(StatusCode::INTERNAL_SERVER_ERROR))
+    }
+  }
+}
+
+fn complete(cfg: &Config, upload: TusUpload) {
+  if let Some(cb) = &cfg.on_complete {
+    cb(upload);
+  }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+  headers
+    .get(name)
+    .and_then(|v| v.to_str().ok())
+    .and_then(|s| s.trim().parse().ok())
+}
+
+fn set(headers: &mut HeaderMap, name: &'static str, value: &str) {
+  if let Ok(v) = HeaderValue::from_str(value) {
+    headers.insert(name, v);
+  }
+}
+
+fn set_expires(headers: &mut HeaderMap, upload: &TusUpload) {
+  if !upload.is_complete()
+    && let Some(at) = upload.expires_at
+  {
+    set(headers, "upload-expires", &httpdate::fmt_http_date(at));
+  }
+}
+
+fn tus_response(status: StatusCode) -> Response {
+  let mut resp = http::Response::new(TakoBody::empty());
+  *resp.status_mut() = status;
+  resp
+    .headers_mut()
+    .insert("tus-resumable", HeaderValue::from_static(TUS_VERSION));
+  resp
+}
+
+fn tus_error(status: StatusCode, msg: &'static str) -> Response {
+  let mut resp = tus_response(status);
+  *resp.body_mut() = TakoBody::from(msg);
+  resp.headers_mut().insert(
+    CONTENT_TYPE,
+    HeaderValue::from_static("text/plain; charset=utf-8"),
+  );
+  resp
+}
+
+fn method_not_allowed(allow: &'static str) -> Response {
+  let mut resp = tus_response(StatusCode::METHOD_NOT_ALLOWED);
+  resp
+    .headers_mut()
+    .insert(http::header::ALLOW, HeaderValue::from_static(allow));
+  resp
+}
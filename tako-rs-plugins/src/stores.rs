@@ -1,6 +1,6 @@
 //! Pluggable backend traits for stateful middleware.
 //!
-//! Built-in middleware (sessions, rate limiting, idempotency, JWKS, CSRF) all
+//! Built-in middleware (sessions, rate limiting, quotas, idempotency, JWKS, CSRF) all
 //! ship with an in-memory `scc::HashMap` store. Production deployments often
 //! want to swap that out for Redis, Postgres, or another shared backend so a
 //! cluster of replicas can share state. The traits here define the minimum
@@ -18,12 +18,22 @@
 //! deployments must implement these traits themselves (or accept the
 //! per-process state silos of the in-memory defaults). See `V2_ROADMAP.md`
 //! § 4.1 for the linked follow-up checklist — do not let this slip.
+//!
+//! The exception is rate limiting: the `redis` feature adds
+//! [`redis::RedisRateLimitStore`], selected with
+//! [`RateLimiterBuilder::redis`](crate::plugins::rate_limiter::RateLimiterBuilder::redis).
 
+use std::io;
 use std::time::Duration;
+use std::time::SystemTime;
 
 use async_trait::async_trait;
+use bytes::Bytes;
 
 pub mod memory;
+#[cfg(feature = "redis")]
+#[cfg_attr(docsrs, doc(cfg(feature = "redis")))]
+pub mod redis;
 
 /// Persistent session storage.
 ///
@@ -75,6 +85,63 @@ pub struct RateLimitSnapshot {
   pub retry_after_secs: u64,
 }
 
+/// Windowed request-quota counters used by the quota plugin.
+///
+/// A counter is identified by the quota key and its window, the window's
+/// start in seconds since the UNIX epoch. Unlike rate-limit buckets these
+/// counters are meant to outlive the process: a Redis backend maps `consume`
+/// onto a small `INCRBY`-if-below script with `EXPIRE`, a SQL backend onto a
+/// conditional upsert, and daily or monthly quotas then survive deploys and
+/// hold across replicas.
+#[async_trait]
+pub trait QuotaStore: Send + Sync + 'static {
+  /// Atomically adds `cost` to the counter for `key` in `window`, but only
+  /// if the result stays at or below `limit`. The counter must be kept for
+  /// at least `ttl`.
+  async fn consume(
+    &self,
+    key: &str,
+    window: u64,
+    cost: u64,
+    limit: u64,
+    ttl: Duration,
+  ) -> io::Result<QuotaCount>;
+
+  /// Current value of the counter for `key` in `window` (zero if absent).
+  async fn count(&self, key: &str, window: u64) -> io::Result<u64>;
+
+  /// Drops counters whose `ttl` has passed at `now`. Called periodically by
+  /// the plugin's janitor; backends with native expiry can ignore it.
+  async fn sweep_expired(&self, _now: SystemTime) {}
+}
+
+/// Result of [`QuotaStore::consume`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaCount {
+  /// Counter value after the call.
+  pub used: u64,
+  /// Whether `cost` was added (`false` when it would have crossed `limit`).
+  pub consumed: bool,
+}
+
+/// Record of spent one-time tokens (magic links, email verification).
+///
+/// Signed tokens carry their own expiry, so the store only needs to remember
+/// which ids were used until they would have expired anyway. A shared
+/// backend is required for single use to hold across replicas: Redis maps
+/// `mark_used` onto `SET NX PXAT`, SQL onto an insert that fails on a
+/// duplicate key.
+#[async_trait]
+pub trait OneTimeTokenStore: Send + Sync + 'static {
+  /// Atomically records `id` as used. Returns `false` if it already was.
+  /// The record must be kept until at least `expires_at`.
+  async fn mark_used(&self, id: &str, expires_at: SystemTime) -> io::Result<bool>;
+
+  /// Drops records whose tokens have expired at `now`. Backends with native
+  /// expiry can ignore it.
+  async fn sweep_expired(&self, _now: SystemTime) {}
+}
+
 /// Idempotency-key cache.
 #[async_trait]
 pub trait IdempotencyStore: Send + Sync + 'static {
@@ -129,3 +196,56 @@ pub trait CsrfTokenStore: Send + Sync + 'static {
   /// success when `single_use` is true.
   async fn validate(&self, session_id: &str, token: &str, single_use: bool) -> bool;
 }
+
+/// Resumable upload storage used by the tus plugin.
+///
+/// Offsets are authoritative in the store: `append` must reject a chunk
+/// whose `offset` does not match the stored one, so two racing `PATCH`
+/// requests cannot interleave bytes. Backends that write to disk or object
+/// storage should persist every appended chunk before returning, because a
+/// client resumes from whatever offset `info` reports after a disconnect.
+#[async_trait]
+pub trait TusStore: Send + Sync + 'static {
+  /// Registers a new, empty upload.
+  async fn create(&self, upload: TusUpload) -> io::Result<()>;
+
+  /// Returns the current state of `id`, or `None` if it does not exist.
+  async fn info(&self, id: &str) -> io::Result<Option<TusUpload>>;
+
+  /// Appends `chunk` at `offset` and returns the new offset.
+  async fn append(&self, id: &str, offset: u64, chunk: Bytes) -> io::Result<u64>;
+
+  /// Deletes the upload and its data, returning whether it existed.
+  async fn remove(&self, id: &str) -> io::Result<bool>;
+
+  /// Removes every unfinished upload whose expiry is at or before `now`.
+  /// Called periodically by the plugin's janitor.
+  async fn sweep_expired(&self, _now: SystemTime) {}
+}
+
+/// Metadata for one resumable upload.
+#[derive(Debug, Clone)]
+pub struct TusUpload {
+  /// Opaque upload id, also the last segment of the upload URL.
+  pub id: String,
+  /// Total size declared by the client via `Upload-Length`.
+  pub length: u64,
+  /// Number of bytes received so far.
+  pub offset: u64,
+  /// Raw `Upload-Metadata` header value, if the client sent one.
+  pub metadata: Option<String>,
+  /// When an unfinished upload becomes eligible for deletion.
+  pub expires_at: Option<SystemTime>,
+}
+
+impl TusUpload {
+  /// Returns true once every declared byte has been received.
+  pub fn is_complete(&self) -> bool {
+    self.offset >= self.length
+  }
+
+  /// Returns true if the upload is unfinished and past its expiry.
+  pub fn is_expired(&self, now: SystemTime) -> bool {
+    !self.is_complete() && self.expires_at.is_some_and(|at| at <= now)
+  }
+}
@@ -5,13 +5,17 @@
 //! swap any of these out for Redis / Postgres / other shared backends without
 //! forking the middleware itself.
 
+use std::io;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU32;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
 use std::time::Instant;
+use std::time::SystemTime;
 
 use async_trait::async_trait;
+use bytes::Bytes;
+use bytes::BytesMut;
 use parking_lot::Mutex;
 use scc::HashMap as SccHashMap;
 
@@ -19,9 +23,15 @@ use super::CsrfTokenStore;
 use super::IdempotencyEntry;
 use super::IdempotencyStore;
 use super::JwksProvider;
+use super::OneTimeTokenStore;
+use super::QuotaCount;
+use super::QuotaStore;
 use super::RateLimitSnapshot;
 use super::RateLimitStore;
 use super::SessionStore;
+use super::TusStore;
+use super::TusUpload;
+use tako_rs_core::clock;
 
 #[derive(Clone)]
 struct SessionEntry {
@@ -45,7 +55,7 @@ impl MemorySessionStore {
 impl SessionStore for MemorySessionStore {
   async fn load(&self, id: &str) -> Option<Vec<u8>> {
     let entry = self.inner.get_async(id).await?;
-    if entry.expires_at <= Instant::now() {
+    if entry.expires_at <= clock::now() {
       return None;
     }
     Some(entry.data.clone())
@@ -54,7 +64,7 @@ impl SessionStore for MemorySessionStore {
   async fn store(&self, id: &str, data: Vec<u8>, ttl: Duration) {
     let entry = SessionEntry {
       data,
-      expires_at: Instant::now() + ttl,
+      expires_at: clock::now() + ttl,
     };
     let _ = self.inner.upsert_async(id.to_string(), entry).await;
   }
@@ -64,7 +74,7 @@ impl SessionStore for MemorySessionStore {
   }
 
   async fn sweep(&self) {
-    let now = Instant::now();
+    let now = clock::now();
     self.inner.retain_async(|_, v| v.expires_at > now).await;
   }
 }
@@ -126,13 +136,13 @@ impl RateLimitStore for MemoryRateLimitStore {
             available: f64::from(capacity),
             capacity,
             refill_rate_per_sec: refill_rate,
-            last_refill: Instant::now(),
+            last_refill: clock::now(),
           }))
         });
       entry.get().clone()
     };
     let mut bucket = mutex.lock();
-    let now = Instant::now();
+    let now = clock::now();
     bucket.refill(now);
     let cost_f = f64::from(cost);
     let allowed = bucket.available >= cost_f;
@@ -201,7 +211,7 @@ impl MemoryIdempotencyStore {
 impl IdempotencyStore for MemoryIdempotencyStore {
   async fn get(&self, key: &str) -> Option<IdempotencyEntry> {
     let stored = self.inner.get_async(key).await?;
-    if stored.expires_at <= Instant::now() {
+    if stored.expires_at <= clock::now() {
       return None;
     }
     Some(stored.entry.clone())
@@ -217,7 +227,7 @@ impl IdempotencyStore for MemoryIdempotencyStore {
     };
     let stored = StoredIdempotency {
       entry: entry.clone(),
-      expires_at: Instant::now() + self.inflight_ttl,
+      expires_at: clock::now() + self.inflight_ttl,
     };
     let _ = self.inner.upsert_async(key.to_string(), stored).await;
     entry
@@ -226,7 +236,7 @@ impl IdempotencyStore for MemoryIdempotencyStore {
   async fn complete(&self, key: &str, entry: IdempotencyEntry, ttl: Duration) {
     let stored = StoredIdempotency {
       entry,
-      expires_at: Instant::now() + ttl,
+      expires_at: clock::now() + ttl,
     };
     let _ = self.inner.upsert_async(key.to_string(), stored).await;
   }
@@ -302,7 +312,7 @@ impl CsrfTokenStore for MemoryCsrfTokenStore {
     let token = uuid::Uuid::new_v4().simple().to_string();
     let record = CsrfRecord {
       token: token.clone(),
-      expires_at: Instant::now() + ttl,
+      expires_at: clock::now() + ttl,
       uses_left: Arc::new(AtomicU32::new(u32::MAX)),
     };
     let _ = self
@@ -317,7 +327,7 @@ impl CsrfTokenStore for MemoryCsrfTokenStore {
     let Some(record) = record else {
       return false;
     };
-    if record.expires_at <= Instant::now() {
+    if record.expires_at <= clock::now() {
       return false;
     }
     if record.token != candidate {
@@ -344,3 +354,166 @@ impl CsrfTokenStore for MemoryCsrfTokenStore {
     true
   }
 }
+
+/// In-memory quota counters.
+///
+/// Counters live only as long as the process, so a restart resets every
+/// quota; implement [`QuotaStore`] against a shared database for billing.
+#[derive(Default, Clone)]
+pub struct MemoryQuotaStore {
+  inner: Arc<SccHashMap<(String, u64), (u64, SystemTime)>>,
+}
+
+impl MemoryQuotaStore {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+#[async_trait]
+impl QuotaStore for MemoryQuotaStore {
+  async fn consume(
+    &self,
+    key: &str,
+    window: u64,
+    cost: u64,
+    limit: u64,
+    ttl: Duration,
+  ) -> io::Result<QuotaCount> {
+    let expires = clock::system_now() + ttl;
+    let mut entry = self
+      .inner
+      .entry_async((key.to_string(), window))
+      .await
+      .or_insert((0, expires));
+    let (used, _) = entry.get_mut();
+    let consumed = used.saturating_add(cost) <= limit;
+    if consumed {
+      *used += cost;
+    }
+    Ok(QuotaCount {
+      used: *used,
+      consumed,
+    })
+  }
+
+  async fn count(&self, key: &str, window: u64) -> io::Result<u64> {
+    Ok(
+      self
+        .inner
+        .get_async(&(key.to_string(), window))
+        .await
+        .map_or(0, |e| e.0),
+    )
+  }
+
+  async fn sweep_expired(&self, now: SystemTime) {
+    self.inner.retain_async(|_, (_, exp)| *exp > now).await;
+  }
+}
+
+/// In-memory spent-token set.
+///
+/// Per-process: with several replicas, a token used on one can be used again
+/// on another. Expired records are dropped on the next sweep.
+#[derive(Default, Clone)]
+pub struct MemoryOneTimeTokenStore {
+  inner: Arc<SccHashMap<String, SystemTime>>,
+}
+
+impl MemoryOneTimeTokenStore {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+#[async_trait]
+impl OneTimeTokenStore for MemoryOneTimeTokenStore {
+  async fn mark_used(&self, id: &str, expires_at: SystemTime) -> io::Result<bool> {
+    Ok(
+      self
+        .inner
+        .insert_async(id.to_string(), expires_at)
+        .await
+        .is_ok(),
+    )
+  }
+
+  async fn sweep_expired(&self, now: SystemTime) {
+    self.inner.retain_async(|_, exp| *exp > now).await;
+  }
+}
+
+struct StoredUpload {
+  upload: TusUpload,
+  data: BytesMut,
+}
+
+/// In-memory resumable upload store.
+///
+/// Keeps every upload's bytes in RAM, so it suits tests and small files.
+/// Use [`data`](Self::data) to read a finished upload back.
+#[derive(Default, Clone)]
+pub struct MemoryTusStore {
+  inner: Arc<SccHashMap<String, StoredUpload>>,
+}
+
+impl MemoryTusStore {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns the bytes received so far for `id`.
+  pub async fn data(&self, id: &str) -> Option<Bytes> {
+    let stored = self.inner.get_async(id).await?;
+    Some(Bytes::copy_from_slice(&stored.data))
+  }
+}
+
+#[async_trait]
+impl TusStore for MemoryTusStore {
+  async fn create(&self, upload: TusUpload) -> io::Result<()> {
+    let id = upload.id.clone();
+    let stored = StoredUpload {
+      upload,
+      data: BytesMut::new(),
+    };
+    self
+      .inner
+      .insert_async(id, stored)
+      .await
+      .map_err(|_| io::Error::new(io::ErrorKind::AlreadyExists, "upload id already exists"))
+  }
+
+  async fn info(&self, id: &str) -> io::Result<Option<TusUpload>> {
+    Ok(self.inner.get_async(id).await.map(|s| s.upload.clone()))
+  }
+
+  async fn append(&self, id: &str, offset: u64, chunk: Bytes) -> io::Result<u64> {
+    let mut stored = self
+      .inner
+      .get_async(id)
+      .await
+      .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unknown upload"))?;
+    if stored.upload.offset != offset {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "offset does not match upload state",
+      ));
+    }
+    stored.data.extend_from_slice(&chunk);
+    stored.upload.offset += chunk.len() as u64;
+    Ok(stored.upload.offset)
+  }
+
+  async fn remove(&self, id: &str) -> io::Result<bool> {
+    Ok(self.inner.remove_async(id).await.is_some())
+  }
+
+  async fn sweep_expired(&self, now: SystemTime) {
+    self
+      .inner
+      .retain_async(|_, v| !v.upload.is_expired(now))
+      .await;
+  }
+}
@@ -0,0 +1,247 @@
+//! Redis-backed [`RateLimitStore`].
+//!
+//! Every decision is one Lua script run, so concurrent requests from any
+//! number of instances see a consistent bucket. Scripts read the clock with
+//! Redis `TIME`, which keeps instances with skewed clocks in agreement;
+//! Redis 5 or newer is required for that. Each key expires once its bucket
+//! would be full again, so idle clients cost nothing.
+//!
+//! Each bucket is a single Redis key, so the store also works against
+//! Redis Cluster.
+
+use async_trait::async_trait;
+use redis::Script;
+use redis::aio::ConnectionManager;
+
+use super::RateLimitSnapshot;
+use super::RateLimitStore;
+use crate::plugins::rate_limiter::Algorithm;
+use crate::plugins::rate_limiter::Config;
+use crate::plugins::rate_limiter::gcra_decision;
+use crate::plugins::rate_limiter::sliding_window_decision;
+use crate::plugins::rate_limiter::token_bucket_decision;
+
+/// ARGV: capacity, rate per second, cost, ttl in ms.
+/// Returns: allowed, tokens left.
+const TOKEN_BUCKET: &str = r"
+local t = redis.call('TIME')
+local now = tonumber(t[1]) + tonumber(t[2]) / 1000000
+local cap = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local cost = tonumber(ARGV[3])
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(state[1]) or cap
+local ts = tonumber(state[2]) or now
+tokens = math.min(cap, tokens + math.max(0, now - ts) * rate)
+local allowed = 0
+if tokens >= cost then
+  tokens = tokens - cost
+  allowed = 1
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', tostring(now))
+redis.call('PEXPIRE', KEYS[1], ARGV[4])
+return { tostring(allowed), tostring(tokens) }
+";
+
+/// ARGV: emission interval in seconds, burst tolerance in seconds, cost.
+/// Returns: allowed, seconds of credit in use.
+const GCRA: &str = r"
+local t = redis.call('TIME')
+local now = tonumber(t[1]) + tonumber(t[2]) / 1000000
+local increment = tonumber(ARGV[1])
+local burst = tonumber(ARGV[2])
+local cost = tonumber(ARGV[3])
+local tat = math.max(tonumber(redis.call('GET', KEYS[1])) or now, now)
+local new_tat = tat + cost * increment
+if new_tat - now > burst then
+  return { '0', tostring(tat - now) }
+end
+redis.call('SET', KEYS[1], tostring(new_tat), 'PX', math.max(1, math.ceil((new_tat - now) * 1000)))
+return { '1', tostring(new_tat - now) }
+";
+
+/// ARGV: window in seconds, capacity, cost.
+/// Returns: allowed, previous window count, current window count, seconds
+/// into the current window.
+const SLIDING_WINDOW: &str = r"
+local t = redis.call('TIME')
+local now = tonumber(t[1]) + tonumber(t[2]) / 1000000
+local window = tonumber(ARGV[1])
+local cap = tonumber(ARGV[2])
+local cost = tonumber(ARGV[3])
+local idx = math.floor(now / window)
+local into = now - idx * window
+local state = redis.call('HMGET', KEYS[1], 'idx', 'cur', 'prev')
+local seen = tonumber(state[1]) or idx
+local cur = tonumber(state[2]) or 0
+local prev = tonumber(state[3]) or 0
+if seen == idx - 1 then
+  prev = cur
+  cur = 0
+elseif seen < idx - 1 then
+  prev = 0
+  cur = 0
+end
+local allowed = 0
+if prev * (1 - into / window) + cur + cost <= cap then
+  cur = cur + cost
+  allowed = 1
+end
+redis.call('HSET', KEYS[1], 'idx', tostring(idx), 'cur', tostring(cur), 'prev', tostring(prev))
+redis.call('PEXPIRE', KEYS[1], math.ceil(window * 2000))
+return { tostring(allowed), tostring(prev), tostring(cur), tostring(into) }
+";
+
+/// Rate-limit buckets in Redis, evaluated atomically by Lua scripts.
+///
+/// Usually selected with
+/// [`RateLimiterBuilder::redis`](crate::plugins::rate_limiter::RateLimiterBuilder::redis),
+/// which applies the builder's limits and algorithm.
+#[derive(Clone)]
+pub struct RedisRateLimitStore {
+  conn: ConnectionManager,
+  cfg: Config,
+  script: Script,
+  prefix: String,
+  fail_open: bool,
+}
+
+impl RedisRateLimitStore {
+  /// Token bucket of `capacity` requests refilled at `refill_per_sec`, the
+  /// Redis counterpart of
+  /// [`MemoryRateLimitStore::new`](super::memory::MemoryRateLimitStore::new).
+  ///
+  /// # Panics
+  ///
+  /// Panics unless both `capacity` and `refill_per_sec` are positive.
+  pub fn new(conn: ConnectionManager, capacity: u32, refill_per_sec: f64) -> Self {
+    assert!(
+      capacity > 0 && refill_per_sec > 0.0,
+      "RedisRateLimitStore: capacity and refill rate must be > 0"
+    );
+    // A full refill of `capacity` tokens takes `capacity / refill_per_sec`
+    // seconds.
+    let cfg = Config {
+      max_requests: capacity,
+      refill_rate: capacity,
+      refill_interval_ms: ((f64::from(capacity) / refill_per_sec) * 1_000.0)
+        .round()
+        .max(1.0) as u64,
+      ..Config::default()
+    };
+    Self::from_config(conn, &cfg)
+  }
+
+  pub(crate) fn from_config(conn: ConnectionManager, cfg: &Config) -> Self {
+    Self {
+      conn,
+      cfg: cfg.clone(),
+      script: script_for(cfg.algorithm),
+      prefix: "tako:ratelimit:".to_string(),
+      fail_open: true,
+    }
+  }
+
+  /// Algorithm to apply (default token bucket).
+  pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+    self.cfg.algorithm = algorithm;
+    self.script = script_for(algorithm);
+    self
+  }
+
+  /// Key prefix (default `tako:ratelimit:`). Give limiters that share a
+  /// Redis database distinct prefixes.
+  pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+    self.prefix = prefix.into();
+    self
+  }
+
+  /// Allow requests when Redis cannot be reached (default `true`). With
+  /// `false` they are rejected, retrying after a second.
+  pub fn fail_open(mut self, yes: bool) -> Self {
+    self.fail_open = yes;
+    self
+  }
+
+  async fn run(&self, key: &str, cost: u32) -> redis::RedisResult<Decision> {
+    let cfg = &self.cfg;
+    let mut invocation = self.script.prepare_invoke();
+    invocation.key(format!("{}{key}", self.prefix));
+    match cfg.algorithm {
+      Algorithm::TokenBucket => {
+        let ttl_ms = (cfg.window_secs() * 1_000.0).ceil().max(1.0) as u64;
+        invocation
+          .arg(cfg.max_requests)
+          .arg(cfg.rate_per_sec())
+          .arg(cost)
+          .arg(ttl_ms);
+      }
+      Algorithm::Gcra => {
+        invocation
+          .arg(1.0 / cfg.rate_per_sec())
+          .arg(cfg.window_secs())
+          .arg(cost);
+      }
+      Algorithm::SlidingWindow => {
+        invocation
+          .arg(cfg.window_secs())
+          .arg(cfg.max_requests)
+          .arg(cost);
+      }
+    }
+    let mut conn = self.conn.clone();
+    let reply: Vec<String> = invocation.invoke_async(&mut conn).await?;
+    let num = |i: usize| {
+      reply
+        .get(i)
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(0.0)
+    };
+    let allowed = reply.first().is_some_and(|v| v == "1");
+    let cost = f64::from(cost);
+    Ok(match cfg.algorithm {
+      Algorithm::TokenBucket => token_bucket_decision(cfg, num(1), cost, allowed),
+      Algorithm::Gcra => gcra_decision(cfg, num(1), cost, allowed),
+      Algorithm::SlidingWindow => {
+        sliding_window_decision(cfg, num(1), num(2), num(3), cost, allowed)
+      }
+    })
+  }
+}
+
+type Decision = Result<RateLimitSnapshot, RateLimitSnapshot>;
+
+fn script_for(algorithm: Algorithm) -> Script {
+  Script::new(match algorithm {
+    Algorithm::TokenBucket => TOKEN_BUCKET,
+    Algorithm::Gcra => GCRA,
+    Algorithm::SlidingWindow => SLIDING_WINDOW,
+  })
+}
+
+#[async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+  async fn consume(&self, key: &str, cost: u32) -> Result<RateLimitSnapshot, RateLimitSnapshot> {
+    match self.run(key, cost).await {
+      Ok(decision) => decision,
+      Err(e) => {
+        tracing::warn!(error = %e, fail_open = self.fail_open, "rate-limit store unavailable");
+        let snapshot = RateLimitSnapshot {
+          limit: self.cfg.max_requests,
+          remaining: if self.fail_open {
+            self.cfg.max_requests
+          } else {
+            0
+          },
+          reset_secs: 0,
+          retry_after_secs: 1,
+        };
+        if self.fail_open {
+          Ok(snapshot)
+        } else {
+          Err(snapshot)
+        }
+      }
+    }
+  }
+}
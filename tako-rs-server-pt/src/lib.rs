@@ -92,9 +92,12 @@ pub fn spawn_per_thread(
   // Leak the router so workers share a `&'static` reference — no Arc clones
   // on the per-connection or per-request hot path.
   let router: &'static Router = Box::leak(Box::new(router));
+  router.print_routes_and_exit_if_requested();
 
   let shutdown = PerThreadShutdown::new();
-  let mut handles = Vec::with_capacity(cfg.workers);
+  let mut handles = Vec::with_capacity(cfg.workers + 1);
+  #[cfg(feature = "plugins")]
+  handles.push(start_plugins(router, &shutdown)?);
   for worker_id in 0..cfg.workers {
     let cfg = cfg.clone();
     let shutdown = shutdown.clone();
@@ -120,10 +123,13 @@ pub fn serve_per_thread_compio(addr: &str, router: Router, cfg: PerThreadConfig)
     SocketAddr::from_str(addr).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
 
   let router: &'static Router = Box::leak(Box::new(router));
+  router.print_routes_and_exit_if_requested();
 
   let workers = cfg.workers;
   let shutdown = PerThreadShutdown::new();
-  let mut handles = Vec::with_capacity(cfg.workers);
+  let mut handles = Vec::with_capacity(cfg.workers + 1);
+  #[cfg(feature = "plugins")]
+  handles.push(start_plugins(router, &shutdown)?);
   for worker_id in 0..cfg.workers {
     let cfg = cfg.clone();
     let shutdown = shutdown.clone();
@@ -151,3 +157,125 @@ pub fn serve_per_thread_compio(addr: &str, router: Router, cfg: PerThreadConfig)
   }
   result
 }
+
+/// Sets up the router's plugins and hands them a [`RouterHandle`] on a
+/// dedicated `tako-pt-plugins` thread.
+///
+/// A failed setup is returned, under [`Router::fail_on_plugin_error`], before
+/// any worker is spawned.
+///
+/// `on_start` hooks spawn tokio tasks (cache warmers, schedulers), and the
+/// workers' runtimes are either busy serving or not tokio at all, so the
+/// hooks get their own `current_thread` runtime. It lives until `shutdown`
+/// is triggered.
+///
+/// [`RouterHandle`]: tako_rs_core::plugins::RouterHandle
+#[cfg(feature = "plugins")]
+fn start_plugins(
+  router: &'static Router,
+  shutdown: &PerThreadShutdown,
+) -> io::Result<std::thread::JoinHandle<()>> {
+  let rt = tokio::runtime::Builder::new_current_thread()
+    .enable_all()
+    .build()
+    .map_err(|e| io::Error::other(format!("plugin runtime: {e}")))?;
+  rt.block_on(router.initialize_plugins())
+    .map_err(io::Error::other)?;
+  let shutdown = shutdown.clone();
+  std::thread::Builder::new()
+    .name("tako-pt-plugins".to_string())
+    .spawn(move || {
+      rt.block_on(async {
+        router.start_plugins(&tako_rs_core::plugins::RouterHandle::from_static(router));
+        shutdown.notified().await;
+      });
+    })
+}
+
+#[cfg(all(test, feature = "plugins"))]
+mod tests {
+  use std::sync::Arc;
+  use std::sync::atomic::AtomicBool;
+  use std::sync::atomic::Ordering;
+  use std::time::Duration;
+  use std::time::Instant;
+
+  use tako_rs_core::plugins::RouterHandle;
+  use tako_rs_core::plugins::TakoPlugin;
+
+  use super::*;
+
+  #[derive(Clone)]
+  struct Started(Arc<AtomicBool>);
+
+  impl TakoPlugin for Started {
+    fn name(&self) -> &'static str {
+      "Started"
+    }
+
+    fn setup(&self, _router: &Router) -> anyhow::Result<()> {
+      Ok(())
+    }
+
+    fn on_start(&self, _router: RouterHandle) {
+      self.0.store(true, Ordering::SeqCst);
+    }
+  }
+
+  #[test]
+  fn spawn_per_thread_starts_plugins() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+      .unwrap()
+      .local_addr()
+      .unwrap()
+      .port();
+    let started = Arc::new(AtomicBool::new(false));
+    let mut router = Router::new();
+    router.plugin(Started(started.clone()));
+    let cfg = PerThreadConfig {
+      workers: 1,
+      pin_to_core: false,
+      ..PerThreadConfig::default()
+    };
+
+    let (handles, shutdown) = spawn_per_thread(&format!("127.0.0.1:{port}"), router, cfg).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !started.load(Ordering::SeqCst) && Instant::now() < deadline {
+      std::thread::sleep(Duration::from_millis(10));
+    }
+    shutdown.trigger();
+    for h in handles {
+      h.join().unwrap();
+    }
+    assert!(started.load(Ordering::SeqCst));
+  }
+
+  #[derive(Clone)]
+  struct Broken;
+
+  impl TakoPlugin for Broken {
+    fn name(&self) -> &'static str {
+      "Broken"
+    }
+
+    fn setup(&self, _router: &Router) -> anyhow::Result<()> {
+      anyhow::bail!("no backend")
+    }
+  }
+
+  #[test]
+  fn spawn_per_thread_fails_on_plugin_setup_error() {
+    let mut router = Router::new();
+    router.plugin(Broken).fail_on_plugin_error(true);
+    let cfg = PerThreadConfig {
+      workers: 1,
+      pin_to_core: false,
+      ..PerThreadConfig::default()
+    };
+
+    let Err(err) = spawn_per_thread("127.0.0.1:0", router, cfg) else {
+      panic!("spawn_per_thread ignored the plugin error");
+    };
+    assert!(err.to_string().contains("Broken"), "{err}");
+  }
+}
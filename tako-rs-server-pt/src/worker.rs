@@ -6,6 +6,7 @@ use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use tako_rs_core::body::TakoBody;
 use tako_rs_core::conn_info::ConnInfo;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 use tokio::runtime::Builder;
 use tokio::task::LocalSet;
@@ -114,11 +115,19 @@ pub(crate) fn worker_main(
           let io = hyper_util::rt::TokioIo::new(stream);
 
           connection_handles.spawn_local(async move {
-            let svc = service_fn(move |mut req| async move {
-              req.extensions_mut().insert(peer);
-              req.extensions_mut().insert(ConnInfo::tcp(peer));
-              let resp = router.dispatch(req.map(TakoBody::incoming)).await;
-              Ok::<_, Infallible>(resp)
+            let conn_disconnect = DisconnectToken::new();
+            let _closed = conn_disconnect.guard();
+            let svc = service_fn(move |mut req| {
+              let disconnect = conn_disconnect.child();
+              async move {
+                req.extensions_mut().insert(peer);
+                req.extensions_mut().insert(ConnInfo::tcp(peer));
+                req.extensions_mut().insert(disconnect.clone());
+                let guard = disconnect.guard();
+                let resp = router.dispatch(req.map(TakoBody::incoming)).await;
+                guard.disarm();
+                Ok::<_, Infallible>(resp)
+              }
             });
 
             let mut http = http1::Builder::new();
@@ -6,6 +6,7 @@ use std::time::Duration;
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use tako_rs_core::conn_info::ConnInfo;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 
 use crate::config::PerThreadConfig;
@@ -166,19 +167,27 @@ pub(crate) fn worker_main_compio(
         // RAII: dropping `_guard` (on normal completion, panic, or task
         // cancellation) decrements `inflight` and wakes drain waiters.
         let _guard = guard;
-        let svc = service_fn(move |mut req| async move {
-          // Match the tokio variant: insert both the raw `SocketAddr`
-          // (legacy lookup key) and the typed `ConnInfo` so extractors that
-          // key off either type observe the same runtime regardless of
-          // whether the build is `compio` or `tokio`. The compio path used
-          // to insert only `peer`, breaking extractors that look up
-          // `ConnInfo` (notably the IP-trust / forwarded-host helpers).
-          req.extensions_mut().insert(peer);
-          req.extensions_mut().insert(ConnInfo::tcp(peer));
-          let resp = router
-            .dispatch(req.map(tako_rs_core::body::TakoBody::new))
-            .await;
-          Ok::<_, Infallible>(resp)
+        let conn_disconnect = DisconnectToken::new();
+        let _closed = conn_disconnect.guard();
+        let svc = service_fn(move |mut req| {
+          let disconnect = conn_disconnect.child();
+          async move {
+            // Match the tokio variant: insert both the raw `SocketAddr`
+            // (legacy lookup key) and the typed `ConnInfo` so extractors that
+            // key off either type observe the same runtime regardless of
+            // whether the build is `compio` or `tokio`. The compio path used
+            // to insert only `peer`, breaking extractors that look up
+            // `ConnInfo` (notably the IP-trust / forwarded-host helpers).
+            req.extensions_mut().insert(peer);
+            req.extensions_mut().insert(ConnInfo::tcp(peer));
+            req.extensions_mut().insert(disconnect.clone());
+            let guard = disconnect.guard();
+            let resp = router
+              .dispatch(req.map(tako_rs_core::body::TakoBody::new))
+              .await;
+            guard.disarm();
+            Ok::<_, Infallible>(resp)
+          }
         });
 
         let mut http = http1::Builder::new();
@@ -10,8 +10,15 @@ use std::str::FromStr;
 /// if it is already in use.
 ///
 /// This helper is primarily intended for local development and example binaries.
+/// With the `socket-activation` feature an inherited `LISTEN_FDS` socket (from
+/// systemd or `tako dev --socket`) is used instead of binding `addr`.
 #[cfg(not(feature = "compio"))]
 pub async fn bind_with_port_fallback(addr: &str) -> io::Result<tokio::net::TcpListener> {
+  #[cfg(feature = "socket-activation")]
+  if let Some(listener) = crate::socket_activation::ListenFds::from_env().tcp_listener(0)? {
+    return Ok(listener);
+  }
+
   let mut socket_addr =
     SocketAddr::from_str(addr).map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
   let start_port = socket_addr.port();
@@ -183,6 +183,8 @@ impl TlsCert {
 /// let server = Server::builder().tls(cert).build();
 /// // Later, after a cert rotation:
 /// resolver.reload_from_pem("cert.pem", "key.pem")?;
+/// // Or, with the `watch` feature, reload whenever the files change:
+/// // let _watch = resolver.watch_pem("cert.pem", "key.pem")?;
 /// # Ok(())
 /// # }
 /// ```
@@ -222,6 +224,54 @@ impl ReloadableResolver {
   pub fn reload(&self, ck: rustls::sign::CertifiedKey) {
     self.current.store(Arc::new(ck));
   }
+
+  /// Reloads from `cert_path` and `key_path` whenever either file changes.
+  ///
+  /// Rotation tools usually write the pair one file at a time; a reload that
+  /// fails (unreadable file, key not yet matching) keeps serving the current
+  /// cert and is retried on the next change. Each successful reload emits
+  /// [`ids::TLS_CERT_RELOADED`] on the global application arbiter. Reloading
+  /// stops when the returned handle is dropped.
+  ///
+  /// [`ids::TLS_CERT_RELOADED`]: tako_rs_core::signals::ids::TLS_CERT_RELOADED
+  #[cfg(feature = "watch")]
+  pub fn watch_pem(
+    self: &Arc<Self>,
+    cert_path: impl Into<String>,
+    key_path: impl Into<String>,
+  ) -> Result<tako_rs_core::watch::WatchHandle, tako_rs_core::watch::WatchError> {
+    use tako_rs_core::signals::Signal;
+    use tako_rs_core::signals::app_signals;
+    use tako_rs_core::signals::ids;
+
+    let cert_path = cert_path.into();
+    let key_path = key_path.into();
+    let resolver = Arc::downgrade(self);
+    tako_rs_core::watch::FileWatcher::new()
+      .path(&cert_path)
+      .path(&key_path)
+      .on_change(move |_| {
+        let resolver = resolver.clone();
+        let cert_path = cert_path.clone();
+        let key_path = key_path.clone();
+        async move {
+          let Some(resolver) = resolver.upgrade() else {
+            return;
+          };
+          match resolver.reload_from_pem(&cert_path, &key_path) {
+            Ok(()) => {
+              app_signals()
+                .emit(Signal::new(ids::TLS_CERT_RELOADED).meta("cert_path", cert_path))
+                .await;
+            }
+            Err(e) => {
+              tracing::warn!(error = %e, "TLS certificate reload failed; keeping the current cert");
+            }
+          }
+        }
+      })
+      .start()
+  }
 }
 
 #[cfg(feature = "tls")]
@@ -32,6 +32,59 @@ impl ServerBuilder {
     self
   }
 
+  /// Cap concurrent connections. At the cap the accept loop stops calling
+  /// `accept()` until a connection closes, so bursts queue in the kernel
+  /// backlog instead of exhausting file descriptors. Shorthand for
+  /// [`ServerConfig::max_connections`].
+  #[must_use]
+  pub fn max_connections(mut self, limit: usize) -> Self {
+    self.config.max_connections = Some(limit);
+    self
+  }
+
+  /// Time a connection gets to send a complete request head; `None`
+  /// disables the timeout. Shorthand for
+  /// [`ServerConfig::header_read_timeout`].
+  #[must_use]
+  pub fn header_read_timeout(mut self, timeout: Option<std::time::Duration>) -> Self {
+    self.config.header_read_timeout = timeout;
+    self
+  }
+
+  /// `TCP_NODELAY` on accepted connections (default `true`). Shorthand for
+  /// [`ServerConfig::tcp_nodelay`].
+  #[must_use]
+  pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+    self.config.tcp_nodelay = enabled;
+    self
+  }
+
+  /// TCP keepalive probes after `idle` of silence on accepted connections.
+  /// Shorthand for [`ServerConfig::tcp_keepalive`].
+  #[must_use]
+  pub fn tcp_keepalive(mut self, idle: std::time::Duration) -> Self {
+    self.config.tcp_keepalive = Some(idle);
+    self
+  }
+
+  /// Accept h2c (HTTP/2 cleartext, prior knowledge) next to HTTP/1.1 on
+  /// [`Server::spawn_http`]. Shorthand for [`ServerConfig::h2c`].
+  #[cfg(feature = "http2")]
+  #[must_use]
+  pub fn h2c(mut self, enabled: bool) -> Self {
+    self.config.h2c = enabled;
+    self
+  }
+
+  /// Advertise an HTTP/3 endpoint on `port` from the TCP listeners via
+  /// `Alt-Svc`. Shorthand for [`ServerConfig::alt_svc`] set to
+  /// [`ServerConfig::h3_alt_svc`].
+  #[must_use]
+  pub fn advertise_h3(mut self, port: u16, max_age: std::time::Duration) -> Self {
+    self.config.alt_svc = Some(ServerConfig::h3_alt_svc(port, max_age));
+    self
+  }
+
   /// Attach TLS material so [`Server::spawn_tls`] / [`Server::spawn_h3`] become usable.
   #[must_use]
   pub fn tls(mut self, cert: TlsCert) -> Self {
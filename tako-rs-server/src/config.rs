@@ -23,6 +23,8 @@ pub enum H3Congestion {
 /// read, 100 H2 streams, …) so existing call sites keep their behavior. Pass
 /// a populated `ServerConfig` to `*_with_config` entry points to override
 /// individual knobs.
+// Independent on/off knobs, not an encoded state machine.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
   /// Maximum time the coordinator waits for in-flight connections to finish
@@ -45,6 +47,12 @@ pub struct ServerConfig {
   pub h2_max_pending_accept_reset_streams: usize,
   /// HTTP/2 keep-alive ping interval. `None` disables.
   pub h2_keep_alive_interval: Option<Duration>,
+  /// Also accept HTTP/2 cleartext (h2c, prior knowledge) on the plain
+  /// `serve*` listener. Connections that open with the HTTP/2 preface are
+  /// served as HTTP/2 with the `h2_*` settings above; everything else stays
+  /// HTTP/1.1. Default `false`.
+  #[cfg(feature = "http2")]
+  pub h2c: bool,
   /// HTTP/3 cap on concurrent client-initiated bidirectional streams. Maps to
   /// `quinn::TransportConfig::max_concurrent_bidi_streams`.
   pub h3_max_concurrent_bidi_streams: u32,
@@ -59,6 +67,11 @@ pub struct ServerConfig {
   /// Enable QUIC datagrams (RFC 9221) on HTTP/3 connections. Required for
   /// downstream WebTransport-style traffic.
   pub h3_enable_datagrams: bool,
+  /// WebTransport session routes (experimental). When set, HTTP/3
+  /// connections advertise WebTransport, extended CONNECT, and HTTP
+  /// datagrams, and matching `CONNECT` requests become sessions.
+  #[cfg(all(feature = "webtransport", not(feature = "compio")))]
+  pub h3_webtransport: Option<crate::server_h3::webtransport::WebTransportRoutes>,
   /// Issue a QUIC Retry packet for each new connection whose source address
   /// has not been validated. Mitigates UDP source-address-spoofing
   /// amplification attacks at the cost of one extra round-trip per new client.
@@ -75,6 +88,9 @@ pub struct ServerConfig {
   /// Optional ceiling on concurrent in-flight connections. Enforced via a
   /// semaphore in the accept loop; `None` disables.
   pub max_connections: Option<usize>,
+  /// Optional cap on simultaneous connections per client IP, checked right
+  /// after `accept()`. See [`PerIpLimit`](crate::PerIpLimit).
+  pub per_ip_limit: Option<crate::PerIpLimit>,
   /// Read deadline applied before the PROXY protocol header is parsed.
   pub proxy_read_timeout: Duration,
   /// Maximum time the TLS acceptor waits for the client to complete its
@@ -83,6 +99,18 @@ pub struct ServerConfig {
   pub tls_handshake_timeout: Duration,
   /// Backoff schedule for `accept()` errors (typically EMFILE/ENFILE).
   pub accept_backoff: AcceptBackoff,
+  /// `TCP_NODELAY` on accepted TCP connections (default `true`): responses
+  /// go out without waiting for Nagle coalescing.
+  pub tcp_nodelay: bool,
+  /// TCP keepalive idle time on accepted TCP connections. The kernel probes
+  /// a connection after this much silence, so dead peers free their
+  /// `max_connections` slot. `None` (default) leaves the OS setting.
+  pub tcp_keepalive: Option<Duration>,
+  /// `Alt-Svc` value added to responses from the TCP listeners (HTTP/1.1,
+  /// h2c, TLS) that do not set one, so clients discover an HTTP/3 endpoint
+  /// on the same host. Build it with [`ServerConfig::h3_alt_svc`]. `None`
+  /// (default) sends nothing.
+  pub alt_svc: Option<http::HeaderValue>,
 }
 
 impl Default for ServerConfig {
@@ -97,17 +125,78 @@ impl Default for ServerConfig {
       h2_max_send_buf_size: 1024 * 1024,
       h2_max_pending_accept_reset_streams: 50,
       h2_keep_alive_interval: None,
+      #[cfg(feature = "http2")]
+      h2c: false,
       h3_max_concurrent_bidi_streams: 100,
       h3_max_concurrent_uni_streams: 8,
       h3_max_idle_timeout: Some(Duration::from_secs(30)),
       h3_congestion: H3Congestion::default(),
       h3_enable_datagrams: false,
+      #[cfg(all(feature = "webtransport", not(feature = "compio")))]
+      h3_webtransport: None,
       h3_use_retry: false,
       h3_goaway_grace: Duration::from_secs(10),
       max_connections: None,
+      per_ip_limit: None,
       proxy_read_timeout: Duration::from_secs(10),
       tls_handshake_timeout: Duration::from_secs(10),
       accept_backoff: AcceptBackoff::new(),
+      tcp_nodelay: true,
+      tcp_keepalive: None,
+      alt_svc: None,
+    }
+  }
+}
+
+impl ServerConfig {
+  /// `Alt-Svc` value advertising HTTP/3 on `port` of the same host for
+  /// `max_age`, e.g. `h3=":443"; ma=86400`.
+  pub fn h3_alt_svc(port: u16, max_age: Duration) -> http::HeaderValue {
+    let value = format!("h3=\":{port}\"; ma={}", max_age.as_secs());
+    http::HeaderValue::try_from(value).expect("Alt-Svc value is ASCII")
+  }
+
+  /// Socket options for accepted TCP connections.
+  #[cfg_attr(feature = "compio", allow(dead_code))]
+  pub(crate) fn tcp_options(&self) -> TcpOptions {
+    TcpOptions {
+      nodelay: self.tcp_nodelay,
+      keepalive: self.tcp_keepalive,
+    }
+  }
+
+  /// Adds the configured `Alt-Svc` header unless the handler set its own.
+  #[cfg_attr(feature = "compio", allow(dead_code))]
+  pub(crate) fn apply_alt_svc<B>(alt_svc: Option<&http::HeaderValue>, res: &mut http::Response<B>) {
+    if let Some(value) = alt_svc {
+      res
+        .headers_mut()
+        .entry(http::header::ALT_SVC)
+        .or_insert_with(|| value.clone());
+    }
+  }
+}
+
+/// [`ServerConfig::tcp_nodelay`] and [`ServerConfig::tcp_keepalive`], copied
+/// out of the config once per listener.
+#[cfg_attr(feature = "compio", allow(dead_code))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TcpOptions {
+  nodelay: bool,
+  keepalive: Option<Duration>,
+}
+
+impl TcpOptions {
+  /// Applies the options to an accepted stream. Failures are logged and
+  /// otherwise ignored; the connection is served either way.
+  #[cfg_attr(feature = "compio", allow(dead_code))]
+  pub(crate) fn apply(self, stream: &tokio::net::TcpStream) {
+    let _ = stream.set_nodelay(self.nodelay);
+    if let Some(idle) = self.keepalive {
+      let keepalive = socket2::TcpKeepalive::new().with_time(idle);
+      if let Err(err) = socket2::SockRef::from(stream).set_tcp_keepalive(&keepalive) {
+        tracing::debug!("failed to enable TCP keepalive: {err}");
+      }
     }
   }
 }
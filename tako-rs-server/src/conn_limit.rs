@@ -0,0 +1,246 @@
+//! Per-client-IP connection limiting for the accept loops.
+//!
+//! [`PerIpLimit`] caps how many connections a single client address may hold
+//! open at once, so one host cannot exhaust `max_connections` (or file
+//! descriptors) on its own. IPv6 peers are grouped by prefix (a `/64` by
+//! default) because a single client usually controls a whole subnet.
+//!
+//! Peers in the allowlist are never limited. Peers in the trusted-proxy list
+//! are not limited either on the plain transports — every client behind a
+//! load balancer shares its address — while the PROXY protocol listener keys
+//! the limit on the client address the proxy advertises instead.
+//!
+//! Rejected connections are closed right after `accept()` and reported as a
+//! `connection.rejected` signal (with `reason = "per_ip_limit"`) when the
+//! `signals` feature is enabled, which the metrics plugin counts.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::net::Ipv6Addr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+use ipnet::IpNet;
+
+/// Per-client-IP connection cap for [`ServerConfig`](crate::ServerConfig).
+///
+/// ```rust,ignore
+/// let config = ServerConfig {
+///   per_ip_limit: Some(
+///     PerIpLimit::new(32)
+///       .allow("10.0.0.0/8".parse()?)
+///       .trusted_proxy("192.0.2.10/32".parse()?),
+///   ),
+///   ..ServerConfig::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct PerIpLimit {
+  max_per_ip: usize,
+  ipv6_prefix: u8,
+  allowlist: Vec<IpNet>,
+  trusted_proxies: Vec<IpNet>,
+}
+
+impl PerIpLimit {
+  /// Allows at most `max_per_ip` simultaneous connections per client.
+  pub fn new(max_per_ip: usize) -> Self {
+    Self {
+      max_per_ip,
+      ipv6_prefix: 64,
+      allowlist: Vec::new(),
+      trusted_proxies: Vec::new(),
+    }
+  }
+
+  /// Prefix length IPv6 clients are grouped by (default `/64`, max `/128`).
+  #[must_use]
+  pub fn ipv6_prefix(mut self, len: u8) -> Self {
+    self.ipv6_prefix = len.min(128);
+    self
+  }
+
+  /// Exempts a network from the limit.
+  #[must_use]
+  pub fn allow(mut self, net: IpNet) -> Self {
+    self.allowlist.push(net);
+    self
+  }
+
+  /// Marks a network as a reverse proxy whose own address is not limited.
+  #[must_use]
+  pub fn trusted_proxy(mut self, net: IpNet) -> Self {
+    self.trusted_proxies.push(net);
+    self
+  }
+
+  /// The configured per-client cap.
+  pub fn max_per_ip(&self) -> usize {
+    self.max_per_ip
+  }
+
+  pub(crate) fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+    self.trusted_proxies.iter().any(|n| n.contains(&ip))
+  }
+
+  fn key(&self, ip: IpAddr) -> IpAddr {
+    match ip {
+      IpAddr::V6(v6) => {
+        let bits = u128::from(v6);
+        let mask = u128::MAX
+          .checked_shl(u32::from(128 - self.ipv6_prefix))
+          .unwrap_or(0);
+        IpAddr::V6(Ipv6Addr::from(bits & mask))
+      }
+      v4 @ IpAddr::V4(_) => v4,
+    }
+  }
+}
+
+/// Shared counter table consulted by an accept loop.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnLimiter {
+  limit: Arc<PerIpLimit>,
+  active: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl ConnLimiter {
+  pub(crate) fn new(limit: PerIpLimit) -> Self {
+    Self {
+      limit: Arc::new(limit),
+      active: Arc::new(Mutex::new(HashMap::new())),
+    }
+  }
+
+  pub(crate) fn from_config(limit: Option<&PerIpLimit>) -> Option<Self> {
+    limit.cloned().map(Self::new)
+  }
+
+  /// Checks a socket peer. Trusted proxies and allowlisted peers pass
+  /// without a slot.
+  pub(crate) fn acquire_peer(&self, peer: IpAddr) -> Result<Option<IpSlot>, ()> {
+    if self.limit.is_trusted_proxy(peer.to_canonical()) {
+      return Ok(None);
+    }
+    self.acquire(peer)
+  }
+
+  /// Checks a client address; `Ok(None)` when the client is allowlisted.
+  pub(crate) fn acquire(&self, client: IpAddr) -> Result<Option<IpSlot>, ()> {
+    let client = client.to_canonical();
+    if self.limit.allowlist.iter().any(|n| n.contains(&client)) {
+      return Ok(None);
+    }
+    let key = self.limit.key(client);
+    let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+    let count = active.entry(key).or_insert(0);
+    if *count >= self.limit.max_per_ip {
+      return Err(());
+    }
+    *count += 1;
+    Ok(Some(IpSlot {
+      key,
+      active: self.active.clone(),
+    }))
+  }
+}
+
+/// One counted connection; releases its slot on drop.
+#[derive(Debug)]
+pub(crate) struct IpSlot {
+  key: IpAddr,
+  active: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl Drop for IpSlot {
+  fn drop(&mut self) {
+    let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(count) = active.get_mut(&self.key) {
+      *count -= 1;
+      if *count == 0 {
+        active.remove(&self.key);
+      }
+    }
+  }
+}
+
+/// Waits for a `max_connections` permit before the next `accept()`, so a
+/// saturated server leaves new connections in the kernel backlog instead of
+/// holding their file descriptors. `Err(())` once `cancel` fires.
+#[cfg_attr(feature = "compio", allow(dead_code))]
+pub(crate) async fn acquire_conn_permit(
+  limit: Option<&Arc<tokio::sync::Semaphore>>,
+  cancel: &tokio_util::sync::CancellationToken,
+) -> Result<Option<tokio::sync::OwnedSemaphorePermit>, ()> {
+  let Some(limit) = limit else {
+    return Ok(None);
+  };
+  tokio::select! {
+    biased;
+    () = cancel.cancelled() => Err(()),
+    // The semaphore is never closed; treat a closed one as "no limit".
+    permit = limit.clone().acquire_owned() => Ok(permit.ok()),
+  }
+}
+
+/// Logs a per-IP rejection and emits `connection.rejected`.
+pub(crate) async fn report_rejected(addr: std::net::SocketAddr) {
+  tracing::debug!("per-IP connection limit reached for {addr}; closing connection");
+  #[cfg(feature = "signals")]
+  tako_rs_core::signals::transport::emit_connection_rejected(&addr.to_string(), "per_ip_limit")
+    .await;
+  #[cfg(not(feature = "signals"))]
+  let _ = addr;
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::IpAddr;
+
+  use super::ConnLimiter;
+  use super::PerIpLimit;
+
+  fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+  }
+
+  #[test]
+  fn caps_each_client_and_releases_on_drop() {
+    let limiter = ConnLimiter::new(PerIpLimit::new(2));
+    let a = limiter.acquire_peer(ip("198.51.100.1")).unwrap();
+    let b = limiter.acquire_peer(ip("198.51.100.1")).unwrap();
+    assert!(limiter.acquire_peer(ip("198.51.100.1")).is_err());
+    assert!(limiter.acquire_peer(ip("198.51.100.2")).is_ok());
+    drop(a);
+    assert!(limiter.acquire_peer(ip("198.51.100.1")).is_ok());
+    drop(b);
+  }
+
+  #[test]
+  fn groups_ipv6_by_prefix_and_maps_v4_in_v6() {
+    let limiter = ConnLimiter::new(PerIpLimit::new(1));
+    let _a = limiter.acquire_peer(ip("2001:db8::1")).unwrap();
+    assert!(limiter.acquire_peer(ip("2001:db8::ffff")).is_err());
+    assert!(limiter.acquire_peer(ip("2001:db8:0:1::1")).is_ok());
+
+    let _v4 = limiter.acquire_peer(ip("203.0.113.9")).unwrap();
+    assert!(limiter.acquire_peer(ip("::ffff:203.0.113.9")).is_err());
+  }
+
+  #[test]
+  fn allowlist_and_trusted_proxies_bypass_the_cap() {
+    let limiter = ConnLimiter::new(
+      PerIpLimit::new(1)
+        .allow("10.0.0.0/8".parse().unwrap())
+        .trusted_proxy("192.0.2.10/32".parse().unwrap()),
+    );
+    for _ in 0..3 {
+      assert!(limiter.acquire_peer(ip("10.1.2.3")).unwrap().is_none());
+      assert!(limiter.acquire_peer(ip("192.0.2.10")).unwrap().is_none());
+    }
+    // A proxy-advertised client is limited like any other.
+    let _c = limiter.acquire(ip("192.0.2.10")).unwrap();
+    assert!(limiter.acquire(ip("192.0.2.10")).is_err());
+  }
+}
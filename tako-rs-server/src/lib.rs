@@ -11,6 +11,9 @@ pub use config::AcceptBackoff;
 pub use config::H3Congestion;
 pub use config::ServerConfig;
 
+mod conn_limit;
+pub use conn_limit::PerIpLimit;
+
 #[cfg(not(feature = "compio"))]
 mod server;
 
@@ -98,6 +101,12 @@ pub use server_h3::serve_h3_with_shutdown;
 #[cfg(all(feature = "http3", not(feature = "compio")))]
 #[cfg_attr(docsrs, doc(cfg(feature = "http3")))]
 pub use server_h3::serve_h3_with_shutdown_and_config;
+#[cfg(all(feature = "http3", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "http3")))]
+pub use server_h3::serve_quic;
+#[cfg(all(feature = "http3", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "http3")))]
+pub use server_h3::serve_quic_with_shutdown_and_config;
 
 /// Raw TCP server for handling arbitrary TCP connections.
 pub mod server_tcp;
@@ -9,12 +9,14 @@ use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use tako_rs_core::body::TakoBody;
 use tako_rs_core::conn_info::ConnInfo;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 use tako_rs_core::types::BoxError;
 use tokio::task::JoinSet;
 
 use super::read_proxy_protocol;
 use crate::ServerConfig;
+use crate::conn_limit;
 
 /// Build an RFC 7239 `Forwarded` header value from the PROXY-protocol-supplied
 /// peer address. IPv6 addresses get bracketed per the RFC's `node` ABNF.
@@ -85,8 +87,15 @@ async fn run_proxy_http(
 ) -> Result<(), BoxError> {
   let router = Arc::new(router);
 
+  router.print_routes_and_exit_if_requested();
+
   #[cfg(feature = "plugins")]
-  router.setup_plugins_once();
+  {
+    router.initialize_plugins().await?;
+    router.start_plugins(&tako_rs_core::plugins::RouterHandle::from_arc(
+      router.clone(),
+    ));
+  }
 
   tracing::debug!(
     "Tako PROXY protocol HTTP listening on {}",
@@ -98,6 +107,8 @@ async fn run_proxy_http(
   let max_conn_semaphore = config
     .max_connections
     .map(|n| Arc::new(tokio::sync::Semaphore::new(n)));
+  let tcp = config.tcp_options();
+  let ip_limiter = conn_limit::ConnLimiter::from_config(config.per_ip_limit.as_ref());
   let drain_timeout = config.drain_timeout;
   let header_read_timeout = config.header_read_timeout;
   let keep_alive = config.keep_alive;
@@ -112,9 +123,16 @@ async fn run_proxy_http(
   }
 
   loop {
+    // Optional connection cap, raced against shutdown so a saturated
+    // `max_connections` cannot deadlock graceful shutdown.
+    let Ok(permit) = conn_limit::acquire_conn_permit(max_conn_semaphore.as_ref(), &cancel).await
+    else {
+      tracing::info!("PROXY protocol HTTP server shutting down...");
+      break;
+    };
     tokio::select! {
       result = listener.accept() => {
-        let (mut stream, _tcp_addr) = match result {
+        let (mut stream, tcp_addr) = match result {
           Ok(v) => { accept_backoff.reset(); v }
           Err(err) => {
             tracing::warn!("PROXY accept failed: {err}; backing off");
@@ -122,20 +140,9 @@ async fn run_proxy_http(
             continue;
           }
         };
-        let permit = if let Some(sem) = &max_conn_semaphore {
-          tokio::select! {
-            biased;
-            () = cancel.cancelled() => break,
-            permit = sem.clone().acquire_owned() => match permit {
-              Ok(p) => Some(p),
-              Err(_) => continue,
-            },
-          }
-        } else {
-          None
-        };
-        let _ = stream.set_nodelay(true);
+        tcp.apply(&stream);
         let router = router.clone();
+        let ip_limiter = ip_limiter.clone();
 
         join_set.spawn(async move {
           // Parse PROXY protocol header under a read deadline so a stalled
@@ -157,9 +164,26 @@ async fn run_proxy_http(
             };
 
           let real_addr = proxy_header.source;
+
+          // Per-client-IP cap keyed on the address the proxy advertises; the
+          // proxy's own socket address is shared by every client behind it.
+          let ip_slot = match ip_limiter.as_ref().map(|l| match real_addr {
+            Some(client) => l.acquire(client.ip()),
+            None => l.acquire_peer(tcp_addr.ip()),
+          }) {
+            Some(Err(())) => {
+              conn_limit::report_rejected(real_addr.unwrap_or(tcp_addr)).await;
+              return;
+            }
+            Some(Ok(slot)) => slot,
+            None => None,
+          };
           let io = hyper_util::rt::TokioIo::new(stream);
 
+          let conn_disconnect = DisconnectToken::new();
+          let _closed = conn_disconnect.guard();
           let svc = service_fn(move |mut req| {
+            let disconnect = conn_disconnect.child();
             let router = router.clone();
             let proxy_header = proxy_header.clone();
             let real_addr = real_addr;
@@ -185,7 +209,10 @@ async fn run_proxy_http(
                 req.extensions_mut().insert(ConnInfo::tcp(addr));
               }
               req.extensions_mut().insert(proxy_header);
+              req.extensions_mut().insert(disconnect.clone());
+              let guard = disconnect.guard();
               let response = router.dispatch(req.map(TakoBody::incoming)).await;
+              guard.disarm();
               Ok::<_, Infallible>(response)
             }
           });
@@ -207,6 +234,7 @@ async fn run_proxy_http(
           }
 
           drop(permit);
+          drop(ip_slot);
         });
       }
       () = cancel.cancelled() => {
@@ -5,6 +5,12 @@
 //! manages the server lifecycle. The main entry point is the `serve` function which
 //! starts an HTTP server with the provided listener and router configuration.
 //!
+//! With the `http2` feature and [`ServerConfig::h2c`] set, the same listener
+//! also serves HTTP/2 cleartext to clients that send the HTTP/2 preface right
+//! away (prior knowledge), which is what gRPC clients and most load balancers
+//! do. HTTP/1.1 requests carrying `Upgrade: h2c` are answered over HTTP/1.1;
+//! RFC 7540 lets a server ignore the upgrade, and RFC 9113 deprecates it.
+//!
 //! # Examples
 //!
 //! ```rust,no_run
@@ -30,8 +36,13 @@ use std::sync::Arc;
 
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
+#[cfg(feature = "http2")]
+use hyper_util::rt::TokioExecutor;
+#[cfg(feature = "http2")]
+use hyper_util::server::conn::auto;
 use tako_rs_core::body::TakoBody;
 use tako_rs_core::conn_info::ConnInfo;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 #[cfg(feature = "signals")]
 use tako_rs_core::signals::transport as signal_tx;
@@ -42,6 +53,7 @@ use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
 
 use crate::ServerConfig;
+use crate::conn_limit;
 
 /// Starts the Tako HTTP server with the given listener and router.
 pub async fn serve(listener: TcpListener, router: Router) {
@@ -106,9 +118,14 @@ async fn run(
   // The allocation is reclaimed when the process exits.
   let router: &'static Router = Box::leak(Box::new(router));
 
+  router.print_routes_and_exit_if_requested();
+
   // Setup plugins
   #[cfg(feature = "plugins")]
-  router.setup_plugins_once();
+  {
+    router.initialize_plugins().await?;
+    router.start_plugins(&tako_rs_core::plugins::RouterHandle::from_static(router));
+  }
 
   let addr_str = listener.local_addr()?.to_string();
 
@@ -120,10 +137,17 @@ async fn run(
   let mut join_set = JoinSet::new();
   let mut accept_backoff = config.accept_backoff;
   let max_conn_semaphore = config.max_connections.map(|n| Arc::new(Semaphore::new(n)));
+  let tcp = config.tcp_options();
+  let ip_limiter = conn_limit::ConnLimiter::from_config(config.per_ip_limit.as_ref());
   let keep_alive = config.keep_alive;
   let header_read_timeout = config.header_read_timeout;
   let keep_alive_timeout = config.keep_alive_timeout;
   let drain_timeout = config.drain_timeout;
+  #[cfg(feature = "http2")]
+  let h2c = config.h2c.then(|| H2cSettings::from_config(&config));
+  #[cfg(not(feature = "http2"))]
+  let h2c: Option<Infallible> = None;
+  let alt_svc = config.alt_svc.clone();
 
   // Emit the upstream-gap warning at startup rather than per-connection.
   // The previous spot inside the accept loop used a `OnceLock` to dedupe,
@@ -151,6 +175,13 @@ async fn run(
   }
 
   loop {
+    // Optional connection cap, raced against shutdown so a saturated
+    // `max_connections` cannot deadlock graceful shutdown.
+    let Ok(permit) = conn_limit::acquire_conn_permit(max_conn_semaphore.as_ref(), &cancel).await
+    else {
+      tracing::info!("Shutdown signal received, draining connections...");
+      break;
+    };
     tokio::select! {
       result = listener.accept() => {
         let (stream, addr) = match result {
@@ -164,25 +195,21 @@ async fn run(
           }
         };
 
-        // Optional connection cap: park here until a permit is available so
-        // we exert backpressure on the kernel listen queue rather than
-        // accepting unbounded work. Race the acquire against shutdown so a
-        // saturated `max_connections` cannot deadlock graceful shutdown.
-        let permit = if let Some(sem) = &max_conn_semaphore {
-          tokio::select! {
-            biased;
-            () = cancel.cancelled() => break,
-            permit = sem.clone().acquire_owned() => match permit {
-              Ok(p) => Some(p),
-              Err(_) => continue,
-            },
+        // Per-client-IP cap: close the socket right away when this peer already
+        // holds its share of connections.
+        let ip_slot = match ip_limiter.as_ref().map(|l| l.acquire_peer(addr.ip())) {
+          Some(Err(())) => {
+            conn_limit::report_rejected(addr).await;
+            continue;
           }
-        } else {
-          None
+          Some(Ok(slot)) => slot,
+          None => None,
         };
 
-        let _ = stream.set_nodelay(true);
+
+        tcp.apply(&stream);
         let io = hyper_util::rt::TokioIo::new(stream);
+        let alt_svc = alt_svc.clone();
 
         join_set.spawn(async move {
           #[cfg(feature = "signals")]
@@ -191,32 +218,53 @@ async fn run(
           // `router` is `&'static Router` — no Arc clone per connection or request.
           // Per-request REQUEST_STARTED / REQUEST_COMPLETED signals fire from
           // inside Router::dispatch, so transports stay free of that boilerplate.
-          let svc = service_fn(move |mut req| async move {
+          let conn_disconnect = DisconnectToken::new();
+          let _closed = conn_disconnect.guard();
+          let svc = service_fn(move |mut req| {
+            let disconnect = conn_disconnect.child();
+            let alt_svc = alt_svc.clone();
+            async move {
               req.extensions_mut().insert(addr);
-              req.extensions_mut().insert(ConnInfo::tcp(addr));
-              let response = router.dispatch(req.map(TakoBody::incoming)).await;
+              let info = conn_info(addr, req.version());
+              req.extensions_mut().insert(info);
+              req.extensions_mut().insert(disconnect.clone());
+              let guard = disconnect.guard();
+              let mut response = router.dispatch(req.map(TakoBody::incoming)).await;
+              guard.disarm();
+              ServerConfig::apply_alt_svc(alt_svc.as_ref(), &mut response);
               Ok::<_, Infallible>(response)
+            }
           });
 
-          let mut http = http1::Builder::new();
-          http.keep_alive(keep_alive);
-          http.pipeline_flush(true);
-          // hyper requires a Timer when header_read_timeout is set; default
-          // installs the tokio timer integration.
-          http.timer(hyper_util::rt::TokioTimer::new());
-          if let Some(t) = header_read_timeout {
-            http.header_read_timeout(t);
-          }
           // `keep_alive_timeout` (currently ignored — upstream gap) is now
           // logged once at startup; nothing to do here per connection.
           let _ = keep_alive_timeout;
-          let conn = http.serve_connection(io, svc).with_upgrades();
+          let result: Result<(), BoxError> = match h2c {
+            #[cfg(feature = "http2")]
+            Some(h2) => {
+              h2.builder(keep_alive, header_read_timeout)
+                .serve_connection_with_upgrades(io, svc)
+                .await
+            }
+            _ => {
+              let mut http = http1::Builder::new();
+              http.keep_alive(keep_alive);
+              http.pipeline_flush(true);
+              // hyper requires a Timer when header_read_timeout is set; default
+              // installs the tokio timer integration.
+              http.timer(hyper_util::rt::TokioTimer::new());
+              if let Some(t) = header_read_timeout {
+                http.header_read_timeout(t);
+              }
+              http.serve_connection(io, svc).with_upgrades().await.map_err(Into::into)
+            }
+          };
 
-          if let Err(err) = conn.await {
+          if let Err(err) = result {
             // Hyper raises `IncompleteMessage` when the peer closes mid-request
             // or mid-response. This is normal traffic (keep-alive races, client
             // cancellation, NAT/proxy timeouts) and shouldn't pollute ERROR logs.
-            if err.is_incomplete_message() {
+            if err.downcast_ref::<hyper::Error>().is_some_and(hyper::Error::is_incomplete_message) {
               tracing::debug!("client disconnected mid-message: {err}");
             } else {
               tracing::error!("Error serving connection: {err}");
@@ -229,6 +277,7 @@ async fn run(
           // Permit lives until here; dropping it returns a slot to the
           // max_connections semaphore so the next accept can proceed.
           drop(permit);
+          drop(ip_slot);
         });
       }
       () = cancel.cancelled() => {
@@ -255,3 +304,61 @@ async fn run(
   tracing::info!("Server shut down gracefully");
   Ok(())
 }
+
+/// Connection info for a request; HTTP/2 requests on the plain listener came
+/// in over h2c.
+fn conn_info(addr: std::net::SocketAddr, version: hyper::Version) -> ConnInfo {
+  if version == hyper::Version::HTTP_2 {
+    ConnInfo::h2c(addr)
+  } else {
+    ConnInfo::tcp(addr)
+  }
+}
+
+/// HTTP/2 settings for listeners that also accept h2c.
+#[cfg(feature = "http2")]
+#[derive(Debug, Clone, Copy)]
+struct H2cSettings {
+  max_concurrent_streams: u32,
+  max_header_list_size: u32,
+  max_send_buf_size: usize,
+  max_pending_accept_reset_streams: usize,
+  keep_alive_interval: Option<std::time::Duration>,
+}
+
+#[cfg(feature = "http2")]
+impl H2cSettings {
+  fn from_config(config: &ServerConfig) -> Self {
+    Self {
+      max_concurrent_streams: config.h2_max_concurrent_streams,
+      max_header_list_size: config.h2_max_header_list_size,
+      max_send_buf_size: config.h2_max_send_buf_size,
+      max_pending_accept_reset_streams: config.h2_max_pending_accept_reset_streams,
+      keep_alive_interval: config.h2_keep_alive_interval,
+    }
+  }
+
+  /// Builds a connection builder that picks HTTP/2 when the client opens with
+  /// the HTTP/2 preface and HTTP/1.1 otherwise.
+  fn builder(
+    self,
+    keep_alive: bool,
+    header_read_timeout: Option<std::time::Duration>,
+  ) -> auto::Builder<TokioExecutor> {
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    builder
+      .http1()
+      .keep_alive(keep_alive)
+      .pipeline_flush(true)
+      .timer(hyper_util::rt::TokioTimer::new())
+      .header_read_timeout(header_read_timeout);
+    builder
+      .http2()
+      .max_concurrent_streams(self.max_concurrent_streams)
+      .max_header_list_size(self.max_header_list_size)
+      .max_send_buf_size(self.max_send_buf_size)
+      .max_pending_accept_reset_streams(self.max_pending_accept_reset_streams)
+      .keep_alive_interval(self.keep_alive_interval);
+    builder
+  }
+}
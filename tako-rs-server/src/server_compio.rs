@@ -11,6 +11,7 @@ use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use tako_rs_core::body::TakoBody;
 use tako_rs_core::conn_info::ConnInfo;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 #[cfg(feature = "signals")]
 use tako_rs_core::signals::transport as signal_tx;
@@ -18,6 +19,7 @@ use tako_rs_core::types::BoxError;
 use tokio::sync::Notify;
 
 use crate::ServerConfig;
+use crate::conn_limit;
 
 /// RAII guard that increments `inflight` on construction and decrements it on
 /// drop, then wakes drain waiters. Captured into the spawned connection task
@@ -100,8 +102,16 @@ async fn run(
   tako_rs_core::tracing::init_tracing();
 
   let router = Arc::new(router);
+
+  router.print_routes_and_exit_if_requested();
+
   #[cfg(feature = "plugins")]
-  router.setup_plugins_once();
+  {
+    router.initialize_plugins().await?;
+    router.start_plugins(&tako_rs_core::plugins::RouterHandle::from_arc(
+      router.clone(),
+    ));
+  }
 
   let addr_str = listener.local_addr()?.to_string();
 
@@ -119,6 +129,7 @@ async fn run(
   let max_conn_semaphore = config
     .max_connections
     .map(|n| Arc::new(tokio::sync::Semaphore::new(n)));
+  let ip_limiter = conn_limit::ConnLimiter::from_config(config.per_ip_limit.as_ref());
   // C15: per-loop accept backoff for transient errors (EMFILE / ConnectionAborted).
   let mut accept_backoff = config.accept_backoff;
 
@@ -156,6 +167,17 @@ async fn run(
           }
         };
 
+        // Per-client-IP cap: close the socket right away when this peer already
+        // holds its share of connections.
+        let ip_slot = match ip_limiter.as_ref().map(|l| l.acquire_peer(addr.ip())) {
+          Some(Err(())) => {
+            conn_limit::report_rejected(addr).await;
+            continue;
+          }
+          Some(Ok(slot)) => slot,
+          None => None,
+        };
+
         // C14: park here until a permit is available, racing the wait
         // against the shutdown signal so a saturated cap can't deadlock
         // graceful shutdown.
@@ -176,18 +198,25 @@ async fn run(
 
         compio::runtime::spawn(async move {
           let _permit = permit;
+          let _ip_slot = ip_slot;
           // RAII: dropping `_guard` (on normal completion, panic, or task
           // cancellation) decrements `inflight` and wakes drain waiters.
           let _guard = guard;
           #[cfg(feature = "signals")]
           signal_tx::emit_connection_opened(&addr.to_string(), false, None).await;
 
+          let conn_disconnect = DisconnectToken::new();
+          let _closed = conn_disconnect.guard();
           let svc = service_fn(move |mut req| {
+            let disconnect = conn_disconnect.child();
             let router = router.clone();
             async move {
               req.extensions_mut().insert(addr);
               req.extensions_mut().insert(ConnInfo::tcp(addr));
+              req.extensions_mut().insert(disconnect.clone());
+              let guard = disconnect.guard();
               let response = router.dispatch(req.map(TakoBody::new)).await;
+              guard.disarm();
               Ok::<_, Infallible>(response)
             }
           });
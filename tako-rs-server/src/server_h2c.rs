@@ -21,6 +21,7 @@ use hyper_util::rt::TokioExecutor;
 use hyper_util::rt::TokioIo;
 use tako_rs_core::body::TakoBody;
 use tako_rs_core::conn_info::ConnInfo;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 use tako_rs_core::types::BoxError;
 use tokio::net::TcpListener;
@@ -28,6 +29,7 @@ use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 
 use crate::ServerConfig;
+use crate::conn_limit;
 
 /// Starts an h2c server with default [`ServerConfig`].
 pub async fn serve_h2c(listener: TcpListener, router: Router) {
@@ -84,8 +86,13 @@ async fn run(
 
   let router: &'static Router = Box::leak(Box::new(router));
 
+  router.print_routes_and_exit_if_requested();
+
   #[cfg(feature = "plugins")]
-  router.setup_plugins_once();
+  {
+    router.initialize_plugins().await?;
+    router.start_plugins(&tako_rs_core::plugins::RouterHandle::from_static(router));
+  }
 
   let addr_str = listener.local_addr()?.to_string();
   tracing::info!("Tako h2c (HTTP/2 cleartext) listening on {addr_str}");
@@ -93,12 +100,15 @@ async fn run(
   let mut join_set = JoinSet::new();
   let mut accept_backoff = config.accept_backoff;
   let max_conn_semaphore = config.max_connections.map(|n| Arc::new(Semaphore::new(n)));
+  let tcp = config.tcp_options();
+  let ip_limiter = conn_limit::ConnLimiter::from_config(config.per_ip_limit.as_ref());
   let drain_timeout = config.drain_timeout;
   let h2_max_concurrent_streams = config.h2_max_concurrent_streams;
   let h2_max_header_list_size = config.h2_max_header_list_size;
   let h2_max_send_buf_size = config.h2_max_send_buf_size;
   let h2_max_pending_accept_reset_streams = config.h2_max_pending_accept_reset_streams;
   let h2_keep_alive_interval = config.h2_keep_alive_interval;
+  let alt_svc = config.alt_svc.clone();
 
   let cancel = tokio_util::sync::CancellationToken::new();
   if let Some(s) = signal {
@@ -110,6 +120,13 @@ async fn run(
   }
 
   loop {
+    // Optional connection cap, raced against shutdown so a saturated
+    // `max_connections` cannot deadlock graceful shutdown.
+    let Ok(permit) = conn_limit::acquire_conn_permit(max_conn_semaphore.as_ref(), &cancel).await
+    else {
+      tracing::info!("Shutdown signal received, draining h2c connections...");
+      break;
+    };
     tokio::select! {
       result = listener.accept() => {
         let (stream, addr) = match result {
@@ -120,27 +137,37 @@ async fn run(
             continue;
           }
         };
-        let permit = if let Some(sem) = &max_conn_semaphore {
-          tokio::select! {
-            biased;
-            () = cancel.cancelled() => break,
-            permit = sem.clone().acquire_owned() => match permit {
-              Ok(p) => Some(p),
-              Err(_) => continue,
-            },
+
+        // Per-client-IP cap: close the socket right away when this peer already
+        // holds its share of connections.
+        let ip_slot = match ip_limiter.as_ref().map(|l| l.acquire_peer(addr.ip())) {
+          Some(Err(())) => {
+            conn_limit::report_rejected(addr).await;
+            continue;
           }
-        } else {
-          None
+          Some(Ok(slot)) => slot,
+          None => None,
         };
-        let _ = stream.set_nodelay(true);
+        tcp.apply(&stream);
         let io = TokioIo::new(stream);
+        let alt_svc = alt_svc.clone();
 
         join_set.spawn(async move {
-          let svc = service_fn(move |mut req| async move {
-            req.extensions_mut().insert(addr);
-            req.extensions_mut().insert(ConnInfo::h2c(addr));
-            let resp = router.dispatch(req.map(TakoBody::incoming)).await;
-            Ok::<_, Infallible>(resp)
+          let conn_disconnect = DisconnectToken::new();
+          let _closed = conn_disconnect.guard();
+          let svc = service_fn(move |mut req| {
+            let disconnect = conn_disconnect.child();
+            let alt_svc = alt_svc.clone();
+            async move {
+              req.extensions_mut().insert(addr);
+              req.extensions_mut().insert(ConnInfo::h2c(addr));
+              req.extensions_mut().insert(disconnect.clone());
+              let guard = disconnect.guard();
+              let mut resp = router.dispatch(req.map(TakoBody::incoming)).await;
+              guard.disarm();
+              ServerConfig::apply_alt_svc(alt_svc.as_ref(), &mut resp);
+              Ok::<_, Infallible>(resp)
+            }
           });
 
           let mut h2 = http2::Builder::new(TokioExecutor::new());
@@ -157,6 +184,7 @@ async fn run(
           }
 
           drop(permit);
+          drop(ip_slot);
         });
       }
       () = cancel.cancelled() => {
@@ -33,6 +33,9 @@ mod connection;
 mod request;
 mod run;
 mod serve;
+#[cfg(feature = "webtransport")]
+#[cfg_attr(docsrs, doc(cfg(feature = "webtransport")))]
+pub mod webtransport;
 
 pub use serve::serve_h3;
 pub use serve::serve_h3_with_config;
@@ -40,6 +43,8 @@ pub use serve::serve_h3_with_rustls_config;
 pub use serve::serve_h3_with_rustls_config_and_shutdown;
 pub use serve::serve_h3_with_shutdown;
 pub use serve::serve_h3_with_shutdown_and_config;
+pub use serve::serve_quic;
+pub use serve::serve_quic_with_shutdown_and_config;
 /// Loads TLS certificates from a PEM-encoded file. Re-export of
 /// [`tako_rs_core::tls::load_certs`].
 pub use tako_rs_core::tls::load_certs;
@@ -20,7 +20,11 @@ pub(crate) fn transport_config_from(config: &ServerConfig) -> quinn::TransportCo
   }
   // QUIC datagrams (RFC 9221). Required for downstream WebTransport-style
   // traffic. Send buffer is left at the quinn default.
-  if config.h3_enable_datagrams {
+  #[cfg(feature = "webtransport")]
+  let datagrams = config.h3_enable_datagrams || config.h3_webtransport.is_some();
+  #[cfg(not(feature = "webtransport"))]
+  let datagrams = config.h3_enable_datagrams;
+  if datagrams {
     tc.datagram_receive_buffer_size(Some(64 * 1024));
   } else {
     tc.datagram_receive_buffer_size(None);
@@ -2,6 +2,7 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 use tako_rs_core::types::BoxError;
 
@@ -23,6 +24,8 @@ pub(crate) async fn handle_connection(
   let mut h3_conn = h3::server::Connection::new(h3_quinn::Connection::new(conn)).await?;
   let mut request_tasks = tokio::task::JoinSet::new();
   let body_tracker = Arc::new(H3BodyTracker::default());
+  let conn_disconnect = DisconnectToken::new();
+  let _closed = conn_disconnect.guard();
 
   loop {
     tokio::select! {
@@ -31,10 +34,11 @@ pub(crate) async fn handle_connection(
           Ok(Some(resolver)) => {
             let router = router.clone();
             let body_tracker = body_tracker.clone();
+            let disconnect = conn_disconnect.child();
             request_tasks.spawn(async move {
               match resolver.resolve_request().await {
                 Ok((req, stream)) => {
-                  if let Err(e) = handle_request(req, stream, router, remote_addr, body_tracker).await {
+                  if let Err(e) = handle_request(req, stream, router, remote_addr, body_tracker, disconnect).await {
                     tracing::error!("HTTP/3 request error: {e}");
                   }
                 }
@@ -14,6 +14,7 @@ use http_body::Frame;
 use tako_rs_core::body::TakoBody;
 use tako_rs_core::conn_info::ConnInfo;
 use tako_rs_core::conn_info::TlsInfo;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 use tako_rs_core::types::BoxError;
 use tokio_stream::wrappers::ReceiverStream;
@@ -117,12 +118,16 @@ where
 }
 
 /// Handles a single HTTP/3 request.
+///
+/// `disconnect` fires if the stream fails before the response is finished,
+/// including when the request task is aborted at the end of the drain.
 pub(crate) async fn handle_request<S>(
   req: Request<()>,
   stream: RequestStream<S, Bytes>,
   router: Arc<Router>,
   remote_addr: SocketAddr,
   body_tracker: Arc<H3BodyTracker>,
+  disconnect: DisconnectToken,
 ) -> Result<(), BoxError>
 where
   S: BidiStream<Bytes> + Send + 'static,
@@ -149,6 +154,9 @@ where
     },
   ));
 
+  tako_req.extensions_mut().insert(disconnect.clone());
+  let guard = disconnect.guard();
+
   // Dispatch through router
   let response = router.dispatch(tako_req).await;
 
@@ -189,5 +197,6 @@ where
     send_stream.finish().await?;
   }
 
+  guard.disarm();
   Ok(())
 }
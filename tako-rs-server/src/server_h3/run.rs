@@ -1,5 +1,6 @@
 use std::future::Future;
 use std::net::SocketAddr;
+use std::net::UdpSocket;
 use std::sync::Arc;
 
 use quinn::crypto::rustls::QuicServerConfig;
@@ -13,6 +14,7 @@ use super::connection::handle_connection;
 use super::load_certs;
 use super::load_key;
 use crate::ServerConfig;
+use crate::conn_limit;
 
 /// Runs the HTTP/3 server loop.
 pub(crate) async fn run(
@@ -58,6 +60,19 @@ pub(crate) async fn run_with_rustls_config(
   tls_config: Arc<rustls::ServerConfig>,
   signal: Option<impl Future<Output = ()> + Send + 'static>,
   config: ServerConfig,
+) -> Result<(), BoxError> {
+  let socket_addr: SocketAddr = addr.parse()?;
+  let socket = UdpSocket::bind(socket_addr)?;
+  run_on_socket(router, socket, tls_config, signal, config).await
+}
+
+/// Variant of [`run_with_rustls_config`] on a caller-bound UDP socket.
+pub(crate) async fn run_on_socket(
+  router: Router,
+  socket: UdpSocket,
+  tls_config: Arc<rustls::ServerConfig>,
+  signal: Option<impl Future<Output = ()> + Send + 'static>,
+  config: ServerConfig,
 ) -> Result<(), BoxError> {
   #[cfg(feature = "tako-tracing")]
   tako_rs_core::tracing::init_tracing();
@@ -88,13 +103,24 @@ pub(crate) async fn run_with_rustls_config(
     quinn::ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(tls_config_inner)?));
   server_config.transport_config(Arc::new(transport_config_from(&config)));
 
-  let socket_addr: SocketAddr = addr.parse()?;
-  let endpoint = quinn::Endpoint::server(server_config, socket_addr)?;
+  let endpoint = quinn::Endpoint::new(
+    quinn::EndpointConfig::default(),
+    Some(server_config),
+    socket,
+    Arc::new(quinn::TokioRuntime),
+  )?;
 
   let router = Arc::new(router);
 
+  router.print_routes_and_exit_if_requested();
+
   #[cfg(feature = "plugins")]
-  router.setup_plugins_once();
+  {
+    router.initialize_plugins().await?;
+    router.start_plugins(&tako_rs_core::plugins::RouterHandle::from_arc(
+      router.clone(),
+    ));
+  }
 
   let addr_str = endpoint.local_addr()?.to_string();
 
@@ -107,9 +133,12 @@ pub(crate) async fn run_with_rustls_config(
   let drain_timeout = config.drain_timeout;
   let goaway_grace = config.h3_goaway_grace.min(drain_timeout);
   let h3_use_retry = config.h3_use_retry;
+  #[cfg(feature = "webtransport")]
+  let webtransport = config.h3_webtransport.clone();
   let max_conn_semaphore = config
     .max_connections
     .map(|n| Arc::new(tokio::sync::Semaphore::new(n)));
+  let ip_limiter = conn_limit::ConnLimiter::from_config(config.per_ip_limit.as_ref());
 
   // Per-connection graceful shutdown signal. `CancellationToken` is sticky:
   // once cancelled, all subsequent and pre-existing `.cancelled()` awaits
@@ -144,6 +173,18 @@ pub(crate) async fn run_with_rustls_config(
           continue;
         }
 
+        // Per-client-IP cap, checked before the QUIC handshake starts.
+        let remote = incoming.remote_address();
+        let ip_slot = match ip_limiter.as_ref().map(|l| l.acquire_peer(remote.ip())) {
+          Some(Err(())) => {
+            incoming.refuse();
+            conn_limit::report_rejected(remote).await;
+            continue;
+          }
+          Some(Ok(slot)) => slot,
+          None => None,
+        };
+
         let permit = if let Some(sem) = &max_conn_semaphore {
           tokio::select! {
             biased;
@@ -158,6 +199,8 @@ pub(crate) async fn run_with_rustls_config(
         };
         let router = router.clone();
         let conn_shutdown = conn_shutdown.clone();
+        #[cfg(feature = "webtransport")]
+        let webtransport = webtransport.clone();
 
         join_set.spawn(async move {
           match incoming.await {
@@ -167,9 +210,25 @@ pub(crate) async fn run_with_rustls_config(
               #[cfg(feature = "signals")]
               signal_tx::emit_connection_opened(&remote_addr.to_string(), true, Some("h3")).await;
 
-              if let Err(e) =
-                handle_connection(conn, router, remote_addr, conn_shutdown, goaway_grace).await
-              {
+              #[cfg(feature = "webtransport")]
+              let result = match webtransport {
+                Some(routes) => {
+                  super::webtransport::handle_connection(
+                    conn,
+                    router,
+                    remote_addr,
+                    conn_shutdown,
+                    goaway_grace,
+                    routes,
+                  )
+                  .await
+                }
+                None => handle_connection(conn, router, remote_addr, conn_shutdown, goaway_grace).await,
+              };
+              #[cfg(not(feature = "webtransport"))]
+              let result =
+                handle_connection(conn, router, remote_addr, conn_shutdown, goaway_grace).await;
+              if let Err(e) = result {
                 tracing::error!("HTTP/3 connection error: {e}");
               }
 
@@ -182,6 +241,7 @@ pub(crate) async fn run_with_rustls_config(
           }
 
           drop(permit);
+          drop(ip_slot);
         });
       }
       () = cancel.cancelled() => {
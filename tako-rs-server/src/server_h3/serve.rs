@@ -1,9 +1,11 @@
 use std::future::Future;
+use std::net::UdpSocket;
 use std::sync::Arc;
 
 use tako_rs_core::router::Router;
 
 use super::run::run;
+use super::run::run_on_socket;
 use super::run::run_with_rustls_config;
 use crate::ServerConfig;
 
@@ -127,3 +129,53 @@ pub async fn serve_h3_with_rustls_config_and_shutdown(
     tracing::error!("HTTP/3 server error: {e}");
   }
 }
+
+/// Serves HTTP/3 on an already-bound UDP socket.
+///
+/// Use this when the socket comes from elsewhere — socket activation, a
+/// port shared with a TCP listener picked at runtime, or `SO_REUSEPORT`
+/// setup done by the caller. `tls_config` must list `h3` in
+/// `alpn_protocols`; 0-RTT is disabled regardless of its settings. Pair it
+/// with [`ServerConfig::alt_svc`] on the TCP listener so browsers discover
+/// the endpoint.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "http3")]
+/// # async fn example(router: tako::router::Router) -> Result<(), Box<dyn std::error::Error>> {
+/// use tako::server_h3::serve_quic;
+///
+/// let tls = tako::build_rustls_server_config(
+///     &tako::TlsCert::pem_paths("cert.pem", "key.pem"),
+///     vec![b"h3".to_vec()],
+/// )?;
+/// let socket = std::net::UdpSocket::bind("[::]:443")?;
+/// serve_quic(socket, router, tls).await;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn serve_quic(socket: UdpSocket, router: Router, tls_config: Arc<rustls::ServerConfig>) {
+  serve_quic_with_shutdown_and_config(
+    socket,
+    router,
+    tls_config,
+    std::future::pending(),
+    ServerConfig::default(),
+  )
+  .await;
+}
+
+/// Like [`serve_quic`] with graceful shutdown and caller-supplied
+/// [`ServerConfig`].
+pub async fn serve_quic_with_shutdown_and_config(
+  socket: UdpSocket,
+  router: Router,
+  tls_config: Arc<rustls::ServerConfig>,
+  signal: impl Future<Output = ()> + Send + 'static,
+  config: ServerConfig,
+) {
+  if let Err(e) = run_on_socket(router, socket, tls_config, Some(signal), config).await {
+    tracing::error!("HTTP/3 server error: {e}");
+  }
+}
@@ -0,0 +1,492 @@
+//! WebTransport over HTTP/3 (experimental).
+//!
+//! Unlike [`tako_rs_streams::webtransport`](https://docs.rs/tako-rs-streams),
+//! which hands out raw QUIC connections, this module performs the W3C
+//! WebTransport handshake: the server advertises
+//! `SETTINGS_ENABLE_WEBTRANSPORT`, extended CONNECT, and HTTP datagrams, and
+//! a browser's `new WebTransport("https://host:4433/chat")` arrives as a
+//! `CONNECT :protocol = webtransport` request. When its path matches a route
+//! in [`WebTransportRoutes`], the session is accepted and passed to the
+//! handler; every other request on the connection is served by the
+//! [`Router`] as usual.
+//!
+//! ```rust,no_run
+//! use tako::router::Router;
+//! use tako::server_h3::webtransport::WebTransportRoutes;
+//! use tako::server_h3::webtransport::WebTransportSession;
+//! use tako::serve_h3_with_config;
+//! use tako::ServerConfig;
+//! use tokio::io::AsyncReadExt;
+//! use tokio::io::AsyncWriteExt;
+//!
+//! # async fn example() {
+//! let routes = WebTransportRoutes::new().route("/echo", |session: WebTransportSession| async move {
+//!     while let Some(mut stream) = session.accept_bi().await {
+//!         tokio::spawn(async move {
+//!             let mut buf = vec![0u8; 4096];
+//!             while let Ok(n) = stream.read(&mut buf).await {
+//!                 if n == 0 || stream.write_all(&buf[..n]).await.is_err() {
+//!                     break;
+//!                 }
+//!             }
+//!         });
+//!     }
+//! });
+//!
+//! let config = ServerConfig {
+//!     h3_webtransport: Some(routes),
+//!     ..ServerConfig::default()
+//! };
+//! serve_h3_with_config(Router::new(), "[::]:4433", Some("cert.pem"), Some("key.pem"), config).await;
+//! # }
+//! ```
+//!
+//! Each connection carries at most one session. The session ends when the
+//! handler returns or the connection closes.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use h3::ext::Protocol;
+use h3_webtransport::server::AcceptedBi;
+use http::HeaderMap;
+use http::Method;
+use http::Uri;
+use tako_rs_core::disconnect::DisconnectToken;
+use tako_rs_core::router::Router;
+use tako_rs_core::types::BoxError;
+
+use super::request::H3BodyTracker;
+use super::request::handle_request;
+
+type Session = h3_webtransport::server::WebTransportSession<h3_quinn::Connection, Bytes>;
+
+/// A bidirectional WebTransport stream; implements tokio's `AsyncRead` and
+/// `AsyncWrite`.
+pub type BidiStream = h3_webtransport::stream::BidiStream<h3_quinn::BidiStream<Bytes>, Bytes>;
+/// The sending half of a unidirectional WebTransport stream.
+pub type SendStream = h3_webtransport::stream::SendStream<h3_quinn::SendStream<Bytes>, Bytes>;
+/// The receiving half of a unidirectional WebTransport stream.
+pub type RecvStream = h3_webtransport::stream::RecvStream<h3_quinn::RecvStream, Bytes>;
+
+/// Handler invoked with each accepted session.
+pub type WebTransportHandler =
+  Arc<dyn Fn(WebTransportSession) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Path → session handler table, set on
+/// [`ServerConfig::h3_webtransport`](crate::ServerConfig::h3_webtransport).
+#[derive(Clone, Default)]
+pub struct WebTransportRoutes {
+  routes: Arc<HashMap<String, WebTransportHandler>>,
+}
+
+impl fmt::Debug for WebTransportRoutes {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_set().entries(self.routes.keys()).finish()
+  }
+}
+
+impl WebTransportRoutes {
+  /// Creates an empty table.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Accepts sessions whose CONNECT path is exactly `path`.
+  pub fn route<F, Fut>(mut self, path: impl Into<String>, handler: F) -> Self
+  where
+    F: Fn(WebTransportSession) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    let handler: WebTransportHandler = Arc::new(move |session| Box::pin(handler(session)));
+    Arc::make_mut(&mut self.routes).insert(path.into(), handler);
+    self
+  }
+
+  fn handler_for(&self, req: &http::Request<()>) -> Option<WebTransportHandler> {
+    if req.method() != Method::CONNECT
+      || req.extensions().get::<Protocol>() != Some(&Protocol::WEB_TRANSPORT)
+    {
+      return None;
+    }
+    self.routes.get(req.uri().path()).cloned()
+  }
+}
+
+/// An accepted WebTransport session.
+pub struct WebTransportSession {
+  inner: Arc<Session>,
+  uri: Uri,
+  headers: HeaderMap,
+  remote_addr: SocketAddr,
+  router: Arc<Router>,
+  body_tracker: Arc<H3BodyTracker>,
+  disconnect: DisconnectToken,
+}
+
+impl WebTransportSession {
+  /// URI of the CONNECT request that opened the session.
+  pub fn uri(&self) -> &Uri {
+    &self.uri
+  }
+
+  /// Headers of the CONNECT request (`origin`, cookies, …).
+  pub fn headers(&self) -> &HeaderMap {
+    &self.headers
+  }
+
+  /// Address of the peer.
+  pub fn remote_addr(&self) -> SocketAddr {
+    self.remote_addr
+  }
+
+  /// Waits for the client to open a bidirectional stream. Returns `None`
+  /// once the session is closed.
+  ///
+  /// Ordinary HTTP/3 requests sent on the same connection arrive through
+  /// this call too; they are served by the router in the background.
+  pub async fn accept_bi(&self) -> Option<BidiStream> {
+    loop {
+      match self.inner.accept_bi().await {
+        Ok(Some(AcceptedBi::BidiStream(_, stream))) => return Some(stream),
+        Ok(Some(AcceptedBi::Request(req, stream))) => {
+          let router = self.router.clone();
+          let body_tracker = self.body_tracker.clone();
+          let remote_addr = self.remote_addr;
+          let disconnect = self.disconnect.child();
+          tokio::spawn(async move {
+            if let Err(e) =
+              handle_request(req, stream, router, remote_addr, body_tracker, disconnect).await
+            {
+              tracing::error!("HTTP/3 request error: {e}");
+            }
+          });
+        }
+        Ok(None) => return None,
+        Err(e) => {
+          tracing::debug!("WebTransport session closed: {e}");
+          return None;
+        }
+      }
+    }
+  }
+
+  /// Waits for the client to open a unidirectional stream. Returns `None`
+  /// once the session is closed.
+  pub async fn accept_uni(&self) -> Option<RecvStream> {
+    match self.inner.accept_uni().await {
+      Ok(Some((_, stream))) => Some(stream),
+      Ok(None) => None,
+      Err(e) => {
+        tracing::debug!("WebTransport session closed: {e}");
+        None
+      }
+    }
+  }
+
+  /// Opens a bidirectional stream to the client.
+  ///
+  /// # Errors
+  ///
+  /// Fails when the session or connection is closed.
+  pub async fn open_bi(&self) -> Result<BidiStream, BoxError> {
+    Ok(self.inner.open_bi(self.inner.session_id()).await?)
+  }
+
+  /// Opens a unidirectional stream to the client.
+  ///
+  /// # Errors
+  ///
+  /// Fails when the session or connection is closed.
+  pub async fn open_uni(&self) -> Result<SendStream, BoxError> {
+    Ok(self.inner.open_uni(self.inner.session_id()).await?)
+  }
+
+  /// Receives the next datagram. Returns `None` once the session is closed.
+  pub async fn recv_datagram(&self) -> Option<Bytes> {
+    let mut reader = self.inner.datagram_reader();
+    match reader.read_datagram().await {
+      Ok(datagram) => Some(datagram.into_payload()),
+      Err(e) => {
+        tracing::debug!("WebTransport datagram reader closed: {e}");
+        None
+      }
+    }
+  }
+
+  /// Sends an unreliable datagram. Datagrams larger than the path MTU are
+  /// rejected rather than fragmented.
+  ///
+  /// # Errors
+  ///
+  /// Fails when the datagram is too large or the connection is closed.
+  pub fn send_datagram(&self, data: Bytes) -> Result<(), BoxError> {
+    Ok(self.inner.datagram_sender().send_datagram(data)?)
+  }
+}
+
+/// Serves one HTTP/3 connection with WebTransport enabled.
+///
+/// Requests are resolved on the accept loop rather than in the spawned
+/// task, because accepting a session takes ownership of the connection.
+pub(crate) async fn handle_connection(
+  conn: quinn::Connection,
+  router: Arc<Router>,
+  remote_addr: SocketAddr,
+  shutdown: tokio_util::sync::CancellationToken,
+  goaway_grace: Duration,
+  routes: WebTransportRoutes,
+) -> Result<(), BoxError> {
+  let mut h3_conn = h3::server::builder()
+    .enable_webtransport(true)
+    .enable_extended_connect(true)
+    .enable_datagram(true)
+    .max_webtransport_sessions(1)
+    .send_grease(true)
+    .build(h3_quinn::Connection::new(conn))
+    .await?;
+  let mut request_tasks = tokio::task::JoinSet::new();
+  let body_tracker = Arc::new(H3BodyTracker::default());
+  let conn_disconnect = DisconnectToken::new();
+  let _closed = conn_disconnect.guard();
+
+  loop {
+    let resolver = tokio::select! {
+      accepted = h3_conn.accept() => match accepted {
+        Ok(Some(resolver)) => resolver,
+        Ok(None) => break,
+        Err(e) => {
+          tracing::error!("HTTP/3 accept error: {e}");
+          break;
+        }
+      },
+      () = shutdown.cancelled() => {
+        if let Err(e) = h3_conn.shutdown(0).await {
+          tracing::debug!("HTTP/3 GOAWAY error: {e}");
+        }
+        break;
+      }
+    };
+    let (req, stream) = match resolver.resolve_request().await {
+      Ok(resolved) => resolved,
+      Err(e) => {
+        tracing::error!("HTTP/3 request resolve error: {e}");
+        continue;
+      }
+    };
+
+    if let Some(handler) = routes.handler_for(&req) {
+      let uri = req.uri().clone();
+      let headers = req.headers().clone();
+      let inner = Session::accept(req, stream, h3_conn).await?;
+      let session = WebTransportSession {
+        inner: Arc::new(inner),
+        uri,
+        headers,
+        remote_addr,
+        router,
+        body_tracker,
+        disconnect: conn_disconnect.clone(),
+      };
+      tokio::select! {
+        () = handler(session) => {}
+        () = shutdown.cancelled() => {}
+      }
+      break;
+    }
+
+    let router = router.clone();
+    let body_tracker = body_tracker.clone();
+    let disconnect = conn_disconnect.child();
+    request_tasks.spawn(async move {
+      if let Err(e) =
+        handle_request(req, stream, router, remote_addr, body_tracker, disconnect).await
+      {
+        tracing::error!("HTTP/3 request error: {e}");
+      }
+    });
+  }
+
+  if tokio::time::timeout(goaway_grace, async {
+    while request_tasks.join_next().await.is_some() {}
+  })
+  .await
+  .is_err()
+  {
+    request_tasks.abort_all();
+  }
+  Ok(())
+}
+
+#[cfg(all(test, not(feature = "compio")))]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use http::StatusCode;
+  use tokio::sync::mpsc;
+
+  use super::*;
+  use crate::ServerConfig;
+  use crate::TlsCert;
+
+  /// Appends a QUIC variable-length integer.
+  fn varint(out: &mut Vec<u8>, v: u64) {
+    match v {
+      0..=0x3f => out.push(v as u8),
+      0x40..=0x3fff => out.extend_from_slice(&(0x4000 | v as u16).to_be_bytes()),
+      0x4000..=0x3fff_ffff => out.extend_from_slice(&(0x8000_0000 | v as u32).to_be_bytes()),
+      _ => out.extend_from_slice(&(0xc000_0000_0000_0000 | v).to_be_bytes()),
+    }
+  }
+
+  fn frame(kind: u64, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    varint(&mut out, kind);
+    varint(&mut out, payload.len() as u64);
+    out.extend_from_slice(payload);
+    out
+  }
+
+  /// Control stream announcing extended CONNECT, datagrams and WebTransport,
+  /// which `h3::client` cannot send.
+  fn control_stream() -> Vec<u8> {
+    let mut settings = Vec::new();
+    for id in [0x08, 0x33, 0x2b60_3742] {
+      varint(&mut settings, id);
+      varint(&mut settings, 1);
+    }
+    let mut out = vec![0x00];
+    out.extend(frame(0x04, &settings));
+    out
+  }
+
+  /// `HEADERS` for `CONNECT :protocol = webtransport`, QPACK-encoded with
+  /// static-table references and plain literals only.
+  fn connect_headers(path: &str) -> Vec<u8> {
+    fn literal(out: &mut Vec<u8>, value: &str) {
+      out.push(value.len() as u8);
+      out.extend_from_slice(value.as_bytes());
+    }
+    // Required insert count and base: no dynamic table.
+    let mut block = vec![0x00, 0x00];
+    // Indexed static fields: `:method CONNECT` (15), `:scheme https` (23).
+    block.extend([0xcf, 0xd7]);
+    // Static name references: `:authority`, `:path`.
+    block.push(0x50);
+    literal(&mut block, "localhost");
+    block.push(0x51);
+    literal(&mut block, path);
+    // Literal name `:protocol` (length 9 = 7 + 2 on a 3-bit prefix).
+    block.extend([0x27, 0x02]);
+    block.extend_from_slice(b":protocol");
+    literal(&mut block, "webtransport");
+    frame(0x01, &block)
+  }
+
+  async fn start(
+    routes: WebTransportRoutes,
+    router: Router,
+  ) -> (SocketAddr, Vec<u8>, tokio::sync::oneshot::Sender<()>) {
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_der = cert.cert.der().to_vec();
+    let tls = crate::build_rustls_server_config(
+      &TlsCert::Der {
+        certs: Arc::new(vec![cert.cert.der().clone()]),
+        key: Arc::new(rustls::pki_types::PrivateKeyDer::Pkcs8(
+          cert.key_pair.serialize_der().into(),
+        )),
+        client_auth: None,
+      },
+      vec![b"h3".to_vec()],
+    )
+    .unwrap();
+    let socket = std::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let addr = socket.local_addr().unwrap();
+    let (stop, stopped) = tokio::sync::oneshot::channel();
+    let config = ServerConfig {
+      h3_webtransport: Some(routes),
+      ..ServerConfig::default()
+    };
+    tokio::spawn(crate::server_h3::serve_quic_with_shutdown_and_config(
+      socket,
+      router,
+      tls,
+      async move {
+        let _ = stopped.await;
+      },
+      config,
+    ));
+    (addr, cert_der, stop)
+  }
+
+  async fn connect(addr: SocketAddr, cert_der: Vec<u8>) -> quinn::Connection {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(cert_der.into()).unwrap();
+    let mut tls = rustls::ClientConfig::builder()
+      .with_root_certificates(roots)
+      .with_no_client_auth();
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+    let quic = quinn::crypto::rustls::QuicClientConfig::try_from(tls).unwrap();
+    let mut endpoint = quinn::Endpoint::client((Ipv4Addr::LOCALHOST, 0).into()).unwrap();
+    endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(quic)));
+    endpoint.connect(addr, "localhost").unwrap().await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn connect_opens_a_session_only_on_a_matching_path() {
+    let (accepted_tx, mut accepted) = mpsc::unbounded_channel();
+    let routes = WebTransportRoutes::new().route("/chat", move |session: WebTransportSession| {
+      let accepted_tx = accepted_tx.clone();
+      async move {
+        let _ = accepted_tx.send(session.uri().path().to_string());
+        while session.accept_bi().await.is_some() {}
+      }
+    });
+    let (routed_tx, mut routed) = mpsc::unbounded_channel();
+    let mut router = Router::new();
+    router.fallback(move |req: tako_rs_core::types::Request| {
+      let routed_tx = routed_tx.clone();
+      async move {
+        let _ = routed_tx.send(req.uri().path().to_string());
+        StatusCode::IM_A_TEAPOT
+      }
+    });
+    let (addr, cert_der, stop) = start(routes, router).await;
+    let conn = connect(addr, cert_der).await;
+
+    let mut control = conn.open_uni().await.unwrap();
+    control.write_all(&control_stream()).await.unwrap();
+
+    // Unknown path: served by the router, no session.
+    let (mut send, _recv) = conn.open_bi().await.unwrap();
+    send.write_all(&connect_headers("/other")).await.unwrap();
+    let routed_path = tokio::time::timeout(Duration::from_secs(5), routed.recv())
+      .await
+      .unwrap();
+    assert_eq!(routed_path.as_deref(), Some("/other"));
+    assert!(accepted.try_recv().is_err());
+
+    // Registered path: the session handler runs and the CONNECT gets `200`.
+    let (mut send, mut recv) = conn.open_bi().await.unwrap();
+    send.write_all(&connect_headers("/chat")).await.unwrap();
+    let accepted_path = tokio::time::timeout(Duration::from_secs(5), accepted.recv())
+      .await
+      .unwrap();
+    assert_eq!(accepted_path.as_deref(), Some("/chat"));
+    let mut head = [0u8; 5];
+    recv.read_exact(&mut head).await.unwrap();
+    // HEADERS frame whose first field is the static `:status 200` (25).
+    assert_eq!(head[0], 0x01);
+    assert_eq!(head[4], 0xd9);
+
+    conn.close(0u32.into(), b"done");
+    let _ = stop.send(());
+  }
+}
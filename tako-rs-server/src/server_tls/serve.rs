@@ -12,6 +12,7 @@ use hyper_util::rt::TokioIo;
 use tako_rs_core::body::TakoBody;
 use tako_rs_core::conn_info::ConnInfo;
 use tako_rs_core::conn_info::TlsInfo;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 #[cfg(feature = "signals")]
 use tako_rs_core::signals::transport as signal_tx;
@@ -22,6 +23,7 @@ use tokio_rustls::TlsAcceptor;
 use tokio_rustls::rustls::ServerConfig as RustlsServerConfig;
 
 use crate::ServerConfig;
+use crate::conn_limit;
 
 /// Variant of [`run`](super::run) that accepts a pre-built `Arc<rustls::ServerConfig>`.
 pub async fn run_with_config(
@@ -37,9 +39,16 @@ pub async fn run_with_config(
   let acceptor = TlsAcceptor::from(tls_config);
   let router = Arc::new(router);
 
+  router.print_routes_and_exit_if_requested();
+
   // Setup plugins
   #[cfg(feature = "plugins")]
-  router.setup_plugins_once();
+  {
+    router.initialize_plugins().await?;
+    router.start_plugins(&tako_rs_core::plugins::RouterHandle::from_arc(
+      router.clone(),
+    ));
+  }
 
   let addr_str = listener.local_addr()?.to_string();
 
@@ -53,10 +62,13 @@ pub async fn run_with_config(
   let max_conn_semaphore = config
     .max_connections
     .map(|n| Arc::new(tokio::sync::Semaphore::new(n)));
+  let tcp = config.tcp_options();
+  let ip_limiter = conn_limit::ConnLimiter::from_config(config.per_ip_limit.as_ref());
   let drain_timeout = config.drain_timeout;
   let header_read_timeout = config.header_read_timeout;
   let tls_handshake_timeout = config.tls_handshake_timeout;
   let keep_alive = config.keep_alive;
+  let alt_svc = config.alt_svc.clone();
   #[cfg(feature = "http2")]
   let h2_max_concurrent_streams = config.h2_max_concurrent_streams;
   #[cfg(feature = "http2")]
@@ -81,6 +93,13 @@ pub async fn run_with_config(
   }
 
   loop {
+    // Optional connection cap, raced against shutdown so a saturated
+    // `max_connections` cannot deadlock graceful shutdown.
+    let Ok(permit) = conn_limit::acquire_conn_permit(max_conn_semaphore.as_ref(), &cancel).await
+    else {
+      tracing::info!("Shutdown signal received, draining TLS connections...");
+      break;
+    };
     tokio::select! {
       result = listener.accept() => {
         let (stream, addr) = match result {
@@ -91,21 +110,21 @@ pub async fn run_with_config(
             continue;
           }
         };
-        let permit = if let Some(sem) = &max_conn_semaphore {
-          tokio::select! {
-            biased;
-            () = cancel.cancelled() => break,
-            permit = sem.clone().acquire_owned() => match permit {
-              Ok(p) => Some(p),
-              Err(_) => continue,
-            },
+
+        // Per-client-IP cap: close the socket right away when this peer already
+        // holds its share of connections.
+        let ip_slot = match ip_limiter.as_ref().map(|l| l.acquire_peer(addr.ip())) {
+          Some(Err(())) => {
+            conn_limit::report_rejected(addr).await;
+            continue;
           }
-        } else {
-          None
+          Some(Ok(slot)) => slot,
+          None => None,
         };
-        let _ = stream.set_nodelay(true);
+        tcp.apply(&stream);
         let acceptor = acceptor.clone();
         let router = router.clone();
+        let alt_svc = alt_svc.clone();
 
         join_set.spawn(async move {
           // Bound the TLS handshake so a slow / stalled client cannot
@@ -171,13 +190,21 @@ pub async fn run_with_config(
 
           let io = TokioIo::new(tls_stream);
           // Per-request signals fire from inside Router::dispatch.
+          let conn_disconnect = DisconnectToken::new();
+          let _closed = conn_disconnect.guard();
           let svc = service_fn(move |mut req| {
+            let disconnect = conn_disconnect.child();
             let r = router.clone();
             let conn_info = conn_info.clone();
+            let alt_svc = alt_svc.clone();
             async move {
               req.extensions_mut().insert(addr);
               req.extensions_mut().insert(conn_info);
-              let response = r.dispatch(req.map(TakoBody::incoming)).await;
+              req.extensions_mut().insert(disconnect.clone());
+              let guard = disconnect.guard();
+              let mut response = r.dispatch(req.map(TakoBody::incoming)).await;
+              guard.disarm();
+              ServerConfig::apply_alt_svc(alt_svc.as_ref(), &mut response);
               Ok::<_, Infallible>(response)
             }
           });
@@ -221,6 +248,7 @@ pub async fn run_with_config(
           signal_tx::emit_connection_closed(&addr.to_string(), true, None).await;
 
           drop(permit);
+          drop(ip_slot);
         });
       }
       () = cancel.cancelled() => {
@@ -19,6 +19,7 @@ use rustls::ServerConfig as RustlsServerConfig;
 use tako_rs_core::body::TakoBody;
 use tako_rs_core::conn_info::ConnInfo;
 use tako_rs_core::conn_info::TlsInfo;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 #[cfg(feature = "signals")]
 use tako_rs_core::signals::transport as signal_tx;
@@ -27,6 +28,7 @@ use tokio::sync::Notify;
 use tokio_util::sync::CancellationToken;
 
 use crate::ServerConfig;
+use crate::conn_limit;
 #[cfg(feature = "http2")]
 use crate::server_tls_compio::executor::CompioH2Executor;
 #[cfg(feature = "http2")]
@@ -51,8 +53,15 @@ pub async fn run_with_config(
   let acceptor = TlsAcceptor::from(tls_config);
   let router = Arc::new(router);
 
+  router.print_routes_and_exit_if_requested();
+
   #[cfg(feature = "plugins")]
-  router.setup_plugins_once();
+  {
+    router.initialize_plugins().await?;
+    router.start_plugins(&tako_rs_core::plugins::RouterHandle::from_arc(
+      router.clone(),
+    ));
+  }
 
   let addr_str = listener.local_addr()?.to_string();
 
@@ -80,6 +89,7 @@ pub async fn run_with_config(
   let max_conn_semaphore = config
     .max_connections
     .map(|n| Arc::new(tokio::sync::Semaphore::new(n)));
+  let ip_limiter = conn_limit::ConnLimiter::from_config(config.per_ip_limit.as_ref());
   // C15 (compio TLS): per-loop accept backoff to survive transient
   // EMFILE / ConnectionAborted errors rather than exiting the server.
   let mut accept_backoff = config.accept_backoff;
@@ -126,6 +136,17 @@ pub async fn run_with_config(
           }
         };
 
+        // Per-client-IP cap: close the socket right away when this peer already
+        // holds its share of connections.
+        let ip_slot = match ip_limiter.as_ref().map(|l| l.acquire_peer(addr.ip())) {
+          Some(Err(())) => {
+            conn_limit::report_rejected(addr).await;
+            continue;
+          }
+          Some(Ok(slot)) => slot,
+          None => None,
+        };
+
         // C14: hold the permit across the TLS handshake + connection lifetime.
         // Race against shutdown so a saturated cap can't deadlock the drain.
         let permit = if let Some(sem) = max_conn_semaphore.as_ref() {
@@ -150,6 +171,7 @@ pub async fn run_with_config(
 
         compio::runtime::spawn(async move {
           let _permit = permit;
+          let _ip_slot = ip_slot;
           // RAII guard. Dropping `_guard` decrements `inflight` and wakes
           // drain waiters on any exit path — error, timeout, panic, or
           // success — so we no longer need manual `fetch_sub` calls.
@@ -229,13 +251,19 @@ pub async fn run_with_config(
 
           let io = HyperStream::new(tls_stream);
           // Per-request signals fire from inside Router::dispatch.
+          let conn_disconnect = DisconnectToken::new();
+          let _closed = conn_disconnect.guard();
           let svc = service_fn(move |mut req| {
+            let disconnect = conn_disconnect.child();
             let r = router.clone();
             let conn_info = conn_info.clone();
             async move {
               req.extensions_mut().insert(addr);
               req.extensions_mut().insert(conn_info);
+              req.extensions_mut().insert(disconnect.clone());
+              let guard = disconnect.guard();
               let response = r.dispatch(req.map(TakoBody::new)).await;
+              guard.disarm();
               Ok::<_, Infallible>(response)
             }
           });
@@ -10,6 +10,7 @@ use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use tako_rs_core::body::TakoBody;
 use tako_rs_core::conn_info::ConnInfo;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 use tako_rs_core::types::BoxError;
 use tokio::task::JoinSet;
@@ -86,8 +87,15 @@ async fn run_http(
   let listener = bind_unix_listener(path).await?;
   let router = Arc::new(router);
 
+  router.print_routes_and_exit_if_requested();
+
   #[cfg(feature = "plugins")]
-  router.setup_plugins_once();
+  {
+    router.initialize_plugins().await?;
+    router.start_plugins(&tako_rs_core::plugins::RouterHandle::from_arc(
+      router.clone(),
+    ));
+  }
 
   tracing::debug!("Tako Unix HTTP listening on {}", path.display());
 
@@ -139,14 +147,20 @@ async fn run_http(
         };
 
         join_set.spawn(async move {
+          let conn_disconnect = DisconnectToken::new();
+          let _closed = conn_disconnect.guard();
           let svc = service_fn(move |mut req| {
+            let disconnect = conn_disconnect.child();
             let router = router.clone();
             let peer_addr = peer_addr.clone();
             async move {
               let conn_info = ConnInfo::unix(peer_addr.path.clone());
               req.extensions_mut().insert(peer_addr);
               req.extensions_mut().insert(conn_info);
+              req.extensions_mut().insert(disconnect.clone());
+              let guard = disconnect.guard();
               let response = router.dispatch(req.map(TakoBody::incoming)).await;
+              guard.disarm();
               Ok::<_, Infallible>(response)
             }
           });
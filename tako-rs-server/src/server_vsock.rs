@@ -39,6 +39,7 @@ use tako_rs_core::body::TakoBody;
 use tako_rs_core::conn_info::ConnInfo;
 use tako_rs_core::conn_info::PeerAddr;
 use tako_rs_core::conn_info::Transport;
+use tako_rs_core::disconnect::DisconnectToken;
 use tako_rs_core::router::Router;
 use tako_rs_core::types::BoxError;
 use tokio::sync::Semaphore;
@@ -113,8 +114,15 @@ async fn run(
   let listener = VsockListener::bind(VsockAddr::new(cid, port))?;
   let router = Arc::new(router);
 
+  router.print_routes_and_exit_if_requested();
+
   #[cfg(feature = "plugins")]
-  router.setup_plugins_once();
+  {
+    router.initialize_plugins().await?;
+    router.start_plugins(&tako_rs_core::plugins::RouterHandle::from_arc(
+      router.clone(),
+    ));
+  }
 
   tracing::info!("Tako vsock HTTP listening on cid={cid} port={port}");
 
@@ -161,7 +169,10 @@ async fn run(
 
         join_set.spawn(async move {
           let peer_label = format!("vsock:{}:{}", peer.cid(), peer.port());
+          let conn_disconnect = DisconnectToken::new();
+          let _closed = conn_disconnect.guard();
           let svc = service_fn(move |mut req| {
+            let disconnect = conn_disconnect.child();
             let router = router.clone();
             let peer_label = peer_label.clone();
             async move {
@@ -172,7 +183,10 @@ async fn run(
                 tls: None,
               };
               req.extensions_mut().insert(conn_info);
+              req.extensions_mut().insert(disconnect.clone());
+              let guard = disconnect.guard();
               let response = router.dispatch(req.map(TakoBody::incoming)).await;
+              guard.disarm();
               Ok::<_, Infallible>(response)
             }
           });
@@ -9,6 +9,9 @@
 /// Server-Sent Events (SSE) support for real-time communication.
 pub mod sse;
 
+/// Closing SSE / WebSocket connections when their session expires.
+pub mod session_expiry;
+
 /// File streaming utilities for serving files.
 #[cfg(feature = "file-stream")]
 #[cfg_attr(docsrs, doc(cfg(feature = "file-stream")))]
@@ -26,6 +29,16 @@ pub mod ws;
 #[cfg_attr(docsrs, doc(cfg(feature = "compio-ws")))]
 pub mod ws_compio;
 
+/// MQTT-over-WebSocket bridge to the signal arbiter.
+#[cfg(all(feature = "mqtt", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "mqtt")))]
+pub mod mqtt;
+
+/// Socket.IO-compatible protocol adapter.
+#[cfg(all(feature = "socketio", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "socketio")))]
+pub mod socketio;
+
 /// WebTransport server support over QUIC.
 #[cfg(all(feature = "webtransport", not(feature = "compio")))]
 #[cfg_attr(docsrs, doc(cfg(feature = "webtransport")))]
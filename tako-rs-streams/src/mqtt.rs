@@ -0,0 +1,586 @@
+//! MQTT-over-WebSocket bridge to the signal arbiter.
+//!
+//! [`MqttBridge`] is a minimal MQTT 3.1.1 broker endpoint for browser and
+//! IoT clients (mqtt.js, Paho) that connect over WebSocket with the `mqtt`
+//! subprotocol. Instead of keeping its own topic tree it relays through a
+//! [`SignalArbiter`]:
+//!
+//! - a client `PUBLISH` to `sensors/kitchen` emits the signal
+//!   `mqtt/sensors/kitchen` (the prefix is configurable with
+//!   [`MqttBridge::topic_prefix`]), carrying the payload under
+//!   [`PAYLOAD_KEY`] and the client id under [`CLIENT_ID_KEY`];
+//! - a client `SUBSCRIBE` to `sensors/+` receives every signal under the
+//!   prefix whose topic matches, so server code can push to dashboards with a
+//!   plain `arbiter.emit(Signal::new("mqtt/sensors/kitchen").meta("payload", "21.5"))`.
+//!
+//! [`MqttAuth`] hooks decide who may connect, publish, and subscribe.
+//!
+//! Delivery to clients is QoS 0; incoming QoS 1 and 2 publishes are
+//! acknowledged once emitted. Retained messages and persistent sessions are
+//! not supported. The last will is emitted when a client drops without
+//! sending `DISCONNECT`.
+//!
+//! [`MqttBridge`]: crate::mqtt::MqttBridge
+//! [`MqttBridge::topic_prefix`]: crate::mqtt::MqttBridge::topic_prefix
+//! [`PAYLOAD_KEY`]: crate::mqtt::PAYLOAD_KEY
+//! [`CLIENT_ID_KEY`]: crate::mqtt::CLIENT_ID_KEY
+//! [`MqttAuth`]: crate::mqtt::MqttAuth
+//! [`SignalArbiter`]: tako_rs_core::signals::SignalArbiter
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use tako::Method;
+//! use tako::mqtt::MqttBridge;
+//! use tako::router::Router;
+//! use tako::signals::app_signals;
+//! use tako::types::Request;
+//!
+//! let bridge = MqttBridge::new(app_signals().clone());
+//! let mut router = Router::new();
+//! router.route(Method::GET, "/mqtt", move |req: Request| {
+//!     let bridge = bridge.clone();
+//!     async move { bridge.upgrade(req) }
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+use bytes::BytesMut;
+use futures_util::SinkExt;
+use futures_util::StreamExt;
+use http::HeaderMap;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::signals::Signal;
+use tako_rs_core::signals::SignalArbiter;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::ws::TakoWs;
+
+mod codec;
+
+use codec::Packet;
+
+/// Signal metadata key holding a UTF-8 payload.
+pub const PAYLOAD_KEY: &str = "payload";
+/// Signal metadata key holding a base64-encoded payload that is not UTF-8.
+pub const PAYLOAD_BASE64_KEY: &str = "payload_base64";
+/// Signal metadata key holding the publishing client's id.
+pub const CLIENT_ID_KEY: &str = "mqtt.client_id";
+
+/// Identity presented in the client's `CONNECT`.
+#[derive(Debug, Clone)]
+pub struct MqttClient {
+  /// MQTT client identifier; generated when the client sent an empty one.
+  pub client_id: String,
+  /// `CONNECT` user name.
+  pub username: Option<String>,
+  /// `CONNECT` password.
+  pub password: Option<Bytes>,
+  /// Headers of the WebSocket upgrade request (cookies, `Authorization`, …).
+  pub headers: HeaderMap,
+}
+
+/// Authorization hooks. Every method defaults to allowing the action.
+#[async_trait]
+pub trait MqttAuth: Send + Sync + 'static {
+  /// Accepts or refuses a connection (`CONNACK` "not authorized").
+  async fn connect(&self, client: &MqttClient) -> bool {
+    let _ = client;
+    true
+  }
+
+  /// Allows `client` to publish to `topic`. Refused publishes are dropped.
+  async fn publish(&self, client: &MqttClient, topic: &str) -> bool {
+    let _ = (client, topic);
+    true
+  }
+
+  /// Allows `client` to subscribe to `filter`. Refused filters get a
+  /// failure code in `SUBACK`.
+  async fn subscribe(&self, client: &MqttClient, filter: &str) -> bool {
+    let _ = (client, filter);
+    true
+  }
+}
+
+/// [`MqttAuth`] that allows everything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllowAll;
+
+impl MqttAuth for AllowAll {}
+
+/// WebSocket endpoint relaying MQTT publish/subscribe to a signal arbiter.
+#[derive(Clone)]
+pub struct MqttBridge {
+  arbiter: SignalArbiter,
+  auth: Arc<dyn MqttAuth>,
+  prefix: Arc<str>,
+  max_packet_size: usize,
+  connect_timeout: Duration,
+}
+
+impl fmt::Debug for MqttBridge {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("MqttBridge")
+      .field("prefix", &self.prefix)
+      .field("max_packet_size", &self.max_packet_size)
+      .finish_non_exhaustive()
+  }
+}
+
+impl MqttBridge {
+  /// Bridges to `arbiter` with the `mqtt/` topic prefix and no
+  /// authorization.
+  pub fn new(arbiter: SignalArbiter) -> Self {
+    Self {
+      arbiter,
+      auth: Arc::new(AllowAll),
+      prefix: "mqtt/".into(),
+      max_packet_size: 256 * 1024,
+      connect_timeout: Duration::from_secs(10),
+    }
+  }
+
+  /// Installs authorization hooks.
+  pub fn auth(mut self, auth: impl MqttAuth) -> Self {
+    self.auth = Arc::new(auth);
+    self
+  }
+
+  /// Prefix joining MQTT topics to signal ids (default `mqtt/`). Clients only
+  /// see signals under it; an empty prefix exposes every signal.
+  pub fn topic_prefix(mut self, prefix: impl Into<String>) -> Self {
+    self.prefix = prefix.into().into();
+    self
+  }
+
+  /// Largest accepted MQTT packet, in bytes (default 256 KiB).
+  pub fn max_packet_size(mut self, bytes: usize) -> Self {
+    self.max_packet_size = bytes;
+    self
+  }
+
+  /// How long a new connection may take to send `CONNECT` (default 10 s).
+  pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+    self.connect_timeout = timeout;
+    self
+  }
+
+  /// Upgrades `req` to a WebSocket speaking the `mqtt` subprotocol.
+  pub fn upgrade(&self, req: Request) -> Response {
+    let bridge = self.clone();
+    let headers = req.headers().clone();
+    TakoWs::new(
+      req,
+      move |ws| async move { bridge.serve(ws, headers).await },
+    )
+    .protocols(["mqtt"])
+    .max_message_size(self.max_packet_size)
+    .into_response()
+  }
+
+  /// Runs one MQTT session over an established WebSocket.
+  pub async fn serve<IO>(&self, ws: WebSocketStream<IO>, headers: HeaderMap)
+  where
+    IO: AsyncRead + AsyncWrite + Unpin + Send,
+  {
+    let (mut sink, mut stream) = ws.split();
+    let mut buf = BytesMut::new();
+    let max = self.max_packet_size;
+
+    let connect = tokio::time::timeout(self.connect_timeout, async {
+      loop {
+        match codec::decode(&mut buf, max) {
+          Ok(Some(packet)) => return Some(packet),
+          Ok(None) => {}
+          Err(e) => {
+            tracing::debug!("MQTT: invalid CONNECT: {e}");
+            return None;
+          }
+        }
+        match stream.next().await {
+          Some(Ok(Message::Binary(data))) => buf.extend_from_slice(&data),
+          Some(Ok(Message::Ping(_) | Message::Pong(_))) => {}
+          _ => return None,
+        }
+      }
+    })
+    .await;
+    let Ok(Some(Packet::Connect {
+      protocol_level,
+      keep_alive,
+      client_id,
+      username,
+      password,
+      will,
+    })) = connect
+    else {
+      return;
+    };
+
+    if protocol_level != codec::PROTOCOL_LEVEL {
+      let _ = sink
+        .send(Message::Binary(codec::connack(codec::CONNACK_BAD_PROTOCOL)))
+        .await;
+      return;
+    }
+    if client_id.len() > 256 {
+      let _ = sink
+        .send(Message::Binary(codec::connack(
+          codec::CONNACK_BAD_CLIENT_ID,
+        )))
+        .await;
+      return;
+    }
+    let client = MqttClient {
+      client_id: if client_id.is_empty() {
+        uuid::Uuid::new_v4().simple().to_string()
+      } else {
+        client_id
+      },
+      username,
+      password,
+      headers,
+    };
+    if !self.auth.connect(&client).await {
+      let _ = sink
+        .send(Message::Binary(codec::connack(
+          codec::CONNACK_NOT_AUTHORIZED,
+        )))
+        .await;
+      return;
+    }
+    if sink.send(Message::Binary(codec::connack(0))).await.is_err() {
+      return;
+    }
+
+    // MQTT 3.1.1 §3.1.2.10: drop the client after 1.5 × keep-alive of silence.
+    let idle_limit = (keep_alive > 0).then(|| Duration::from_millis(u64::from(keep_alive) * 1500));
+    let mut deadline = idle_limit.map(|d| tokio::time::Instant::now() + d);
+    let (tx, mut outgoing) = mpsc::channel::<Bytes>(256);
+    let mut subscriptions: HashMap<String, AbortHandle> = HashMap::new();
+    let mut graceful = false;
+
+    'session: loop {
+      loop {
+        let packet = match codec::decode(&mut buf, max) {
+          Ok(Some(packet)) => packet,
+          Ok(None) => break,
+          Err(e) => {
+            tracing::debug!(client_id = %client.client_id, "MQTT: {e}");
+            break 'session;
+          }
+        };
+        let reply = match packet {
+          Packet::Publish {
+            topic,
+            payload,
+            qos,
+            packet_id,
+          } => {
+            if topic.is_empty() || topic.contains(['+', '#']) {
+              break 'session;
+            }
+            if self.auth.publish(&client, &topic).await {
+              self.emit(&client, &topic, &payload).await;
+            } else {
+              tracing::debug!(client_id = %client.client_id, %topic, "MQTT publish refused");
+            }
+            match (qos, packet_id) {
+              (1, Some(id)) => Some(codec::puback(id)),
+              (2, Some(id)) => Some(codec::pubrec(id)),
+              _ => None,
+            }
+          }
+          Packet::PubRel(id) => Some(codec::pubcomp(id)),
+          Packet::Subscribe { packet_id, filters } => {
+            let mut codes = Vec::with_capacity(filters.len());
+            for filter in filters {
+              if codec::valid_filter(&filter) && self.auth.subscribe(&client, &filter).await {
+                let handle = self.forward(filter.clone(), tx.clone());
+                if let Some(old) = subscriptions.insert(filter, handle) {
+                  old.abort();
+                }
+                codes.push(0);
+              } else {
+                codes.push(codec::SUBACK_FAILURE);
+              }
+            }
+            Some(codec::suback(packet_id, &codes))
+          }
+          Packet::Unsubscribe { packet_id, filters } => {
+            for filter in filters {
+              if let Some(handle) = subscriptions.remove(&filter) {
+                handle.abort();
+              }
+            }
+            Some(codec::unsuback(packet_id))
+          }
+          Packet::PingReq => Some(codec::pingresp()),
+          Packet::PubAck => None,
+          Packet::Disconnect => {
+            graceful = true;
+            break 'session;
+          }
+          // A second CONNECT is a protocol violation.
+          Packet::Connect { .. } => break 'session,
+        };
+        if let Some(reply) = reply
+          && sink.send(Message::Binary(reply)).await.is_err()
+        {
+          break 'session;
+        }
+      }
+
+      tokio::select! {
+        msg = stream.next() => match msg {
+          Some(Ok(Message::Binary(data))) => {
+            buf.extend_from_slice(&data);
+            deadline = idle_limit.map(|d| tokio::time::Instant::now() + d);
+          }
+          Some(Ok(Message::Ping(_) | Message::Pong(_))) => {}
+          _ => break,
+        },
+        Some(publish) = outgoing.recv() => {
+          if sink.send(Message::Binary(publish)).await.is_err() {
+            break;
+          }
+        }
+        () = sleep_until(deadline) => {
+          tracing::debug!(client_id = %client.client_id, "MQTT keep-alive expired");
+          break;
+        }
+      }
+    }
+
+    for handle in subscriptions.into_values() {
+      handle.abort();
+    }
+    if !graceful
+      && let Some(will) = will
+      && self.auth.publish(&client, &will.topic).await
+    {
+      self.emit(&client, &will.topic, &will.payload).await;
+    }
+    let _ = sink.close().await;
+  }
+
+  async fn emit(&self, client: &MqttClient, topic: &str, payload: &[u8]) {
+    let signal = Signal::new(format!("{}{topic}", self.prefix));
+    let signal = match std::str::from_utf8(payload) {
+      Ok(text) => signal.meta(PAYLOAD_KEY, text),
+      Err(_) => signal.meta(PAYLOAD_BASE64_KEY, STANDARD.encode(payload)),
+    };
+    self
+      .arbiter
+      .emit(signal.meta(CLIENT_ID_KEY, client.client_id.clone()))
+      .await;
+  }
+
+  /// Spawns a task relaying matching signals to the session at `QoS 0`
+  /// publishes.
+  fn forward(&self, filter: String, tx: mpsc::Sender<Bytes>) -> AbortHandle {
+    let literal = match filter.find(['+', '#']) {
+      Some(i) => filter[..i].trim_end_matches('/'),
+      None => &filter,
+    };
+    let id = format!("{}{literal}", self.prefix);
+    let mut rx = if literal.len() == filter.len() {
+      self.arbiter.subscribe(id)
+    } else {
+      self.arbiter.subscribe_prefix(id)
+    };
+    let prefix = self.prefix.clone();
+    tokio::spawn(async move {
+      loop {
+        match rx.recv().await {
+          Ok(signal) => {
+            let Some(topic) = signal.id.strip_prefix(&*prefix) else {
+              continue;
+            };
+            if !codec::topic_matches(&filter, topic) {
+              continue;
+            }
+            if tx
+              .send(codec::publish(topic, &signal_payload(&signal)))
+              .await
+              .is_err()
+            {
+              break;
+            }
+          }
+          Err(broadcast::error::RecvError::Lagged(n)) => {
+            tracing::debug!(%filter, "MQTT subscriber lagged by {n} signals");
+          }
+          Err(broadcast::error::RecvError::Closed) => break,
+        }
+      }
+    })
+    .abort_handle()
+  }
+}
+
+async fn sleep_until(deadline: Option<tokio::time::Instant>) {
+  match deadline {
+    Some(deadline) => tokio::time::sleep_until(deadline).await,
+    None => std::future::pending().await,
+  }
+}
+
+/// Payload published to clients: [`PAYLOAD_KEY`], else the decoded
+/// [`PAYLOAD_BASE64_KEY`], else the metadata as a JSON object.
+fn signal_payload(signal: &Signal) -> Vec<u8> {
+  if let Some(payload) = signal.metadata.get(PAYLOAD_KEY) {
+    return payload.clone().into_bytes();
+  }
+  if let Some(encoded) = signal.metadata.get(PAYLOAD_BASE64_KEY)
+    && let Ok(payload) = STANDARD.decode(encoded)
+  {
+    return payload;
+  }
+  serde_json::to_vec(&signal.metadata).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use tokio_tungstenite::tungstenite::protocol::Role;
+
+  use super::*;
+
+  struct OnlyAlice;
+
+  #[async_trait]
+  impl MqttAuth for OnlyAlice {
+    async fn connect(&self, client: &MqttClient) -> bool {
+      client.username.as_deref() == Some("alice")
+    }
+
+    async fn subscribe(&self, _client: &MqttClient, filter: &str) -> bool {
+      !filter.starts_with("admin")
+    }
+  }
+
+  fn connect_packet(username: &str) -> Vec<u8> {
+    let mut body = vec![0, 4];
+    body.extend_from_slice(b"MQTT");
+    body.extend_from_slice(&[4, 0x82, 0, 0]);
+    for field in ["dash", username] {
+      body.extend_from_slice(&(field.len() as u16).to_be_bytes());
+      body.extend_from_slice(field.as_bytes());
+    }
+    let mut packet = vec![0x10, body.len() as u8];
+    packet.extend_from_slice(&body);
+    packet
+  }
+
+  fn subscribe_packet(id: u16, filters: &[&str]) -> Vec<u8> {
+    let mut body = id.to_be_bytes().to_vec();
+    for filter in filters {
+      body.extend_from_slice(&(filter.len() as u16).to_be_bytes());
+      body.extend_from_slice(filter.as_bytes());
+      body.push(0);
+    }
+    let mut packet = vec![0x82, body.len() as u8];
+    packet.extend_from_slice(&body);
+    packet
+  }
+
+  async fn client(bridge: &MqttBridge) -> WebSocketStream<tokio::io::DuplexStream> {
+    let (server_io, client_io) = tokio::io::duplex(64 * 1024);
+    let bridge = bridge.clone();
+    tokio::spawn(async move {
+      let ws = WebSocketStream::from_raw_socket(server_io, Role::Server, None).await;
+      bridge.serve(ws, HeaderMap::new()).await;
+    });
+    WebSocketStream::from_raw_socket(client_io, Role::Client, None).await
+  }
+
+  async fn next_binary(ws: &mut WebSocketStream<tokio::io::DuplexStream>) -> Bytes {
+    loop {
+      if let Some(Ok(Message::Binary(data))) = ws.next().await {
+        return data;
+      }
+    }
+  }
+
+  #[tokio::test]
+  async fn relays_publish_and_subscribe_through_the_arbiter() {
+    let arbiter = SignalArbiter::new();
+    let bridge = MqttBridge::new(arbiter.clone()).auth(OnlyAlice);
+
+    let mut mallory = client(&bridge).await;
+    mallory
+      .send(Message::Binary(connect_packet("mallory").into()))
+      .await
+      .unwrap();
+    assert_eq!(&next_binary(&mut mallory).await[..], &[0x20, 2, 0, 5]);
+
+    let mut alice = client(&bridge).await;
+    alice
+      .send(Message::Binary(connect_packet("alice").into()))
+      .await
+      .unwrap();
+    assert_eq!(&next_binary(&mut alice).await[..], &[0x20, 2, 0, 0]);
+    alice
+      .send(Message::Binary(
+        subscribe_packet(1, &["sensors/+", "admin/#"]).into(),
+      ))
+      .await
+      .unwrap();
+    assert_eq!(
+      &next_binary(&mut alice).await[..],
+      &[0x90, 4, 0, 1, 0, 0x80]
+    );
+
+    arbiter
+      .emit(Signal::new("mqtt/sensors/kitchen").meta(PAYLOAD_KEY, "21.5"))
+      .await;
+    arbiter
+      .emit(Signal::new("mqtt/sensors/kitchen/deep").meta(PAYLOAD_KEY, "x"))
+      .await;
+    arbiter
+      .emit(Signal::new("mqtt/sensors/hall").meta(PAYLOAD_KEY, "19"))
+      .await;
+    assert_eq!(
+      next_binary(&mut alice).await,
+      codec::publish("sensors/kitchen", b"21.5")
+    );
+    assert_eq!(
+      next_binary(&mut alice).await,
+      codec::publish("sensors/hall", b"19")
+    );
+
+    let mut published = arbiter.subscribe("mqtt/lights/on");
+    let mut publish = codec::publish("lights/on", b"1").to_vec();
+    publish[0] |= 0x02;
+    publish[1] += 2;
+    publish.splice(13..13, [0, 7]);
+    alice.send(Message::Binary(publish.into())).await.unwrap();
+    assert_eq!(&next_binary(&mut alice).await[..], &[0x40, 2, 0, 7]);
+    let signal = published.recv().await.unwrap();
+    assert_eq!(
+      signal.metadata.get(PAYLOAD_KEY).map(String::as_str),
+      Some("1")
+    );
+    assert_eq!(
+      signal.metadata.get(CLIENT_ID_KEY).map(String::as_str),
+      Some("dash")
+    );
+  }
+}
@@ -0,0 +1,375 @@
+//! MQTT 3.1.1 control packet framing (the subset a broker needs).
+
+use bytes::Buf;
+use bytes::BufMut;
+use bytes::Bytes;
+use bytes::BytesMut;
+
+/// Protocol level of MQTT 3.1.1.
+pub(crate) const PROTOCOL_LEVEL: u8 = 4;
+
+/// `CONNACK` return code: unacceptable protocol version.
+pub(crate) const CONNACK_BAD_PROTOCOL: u8 = 1;
+/// `CONNACK` return code: identifier rejected.
+pub(crate) const CONNACK_BAD_CLIENT_ID: u8 = 2;
+/// `CONNACK` return code: not authorized.
+pub(crate) const CONNACK_NOT_AUTHORIZED: u8 = 5;
+/// `SUBACK` return code for a refused filter.
+pub(crate) const SUBACK_FAILURE: u8 = 0x80;
+
+/// Last-will message registered on `CONNECT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Will {
+  pub topic: String,
+  pub payload: Bytes,
+}
+
+/// A decoded client → server packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Packet {
+  Connect {
+    protocol_level: u8,
+    keep_alive: u16,
+    client_id: String,
+    username: Option<String>,
+    password: Option<Bytes>,
+    will: Option<Will>,
+  },
+  Publish {
+    topic: String,
+    payload: Bytes,
+    qos: u8,
+    packet_id: Option<u16>,
+  },
+  PubAck,
+  PubRel(u16),
+  Subscribe {
+    packet_id: u16,
+    filters: Vec<String>,
+  },
+  Unsubscribe {
+    packet_id: u16,
+    filters: Vec<String>,
+  },
+  PingReq,
+  Disconnect,
+}
+
+fn read_u16(buf: &mut Bytes) -> Result<u16, String> {
+  if buf.remaining() < 2 {
+    return Err("truncated packet".to_string());
+  }
+  Ok(buf.get_u16())
+}
+
+fn read_bytes(buf: &mut Bytes) -> Result<Bytes, String> {
+  let len = read_u16(buf)? as usize;
+  if buf.remaining() < len {
+    return Err("truncated packet".to_string());
+  }
+  Ok(buf.split_to(len))
+}
+
+fn read_string(buf: &mut Bytes) -> Result<String, String> {
+  String::from_utf8(read_bytes(buf)?.to_vec()).map_err(|_| "invalid UTF-8 string".to_string())
+}
+
+/// Decodes one packet from the front of `buf`. Returns `Ok(None)` while the
+/// packet is incomplete (WebSocket frames may split or batch packets).
+pub(crate) fn decode(buf: &mut BytesMut, max_packet_size: usize) -> Result<Option<Packet>, String> {
+  if buf.is_empty() {
+    return Ok(None);
+  }
+  let mut len = 0usize;
+  let mut header_len = 1;
+  loop {
+    let Some(&byte) = buf.get(header_len) else {
+      return Ok(None);
+    };
+    len |= usize::from(byte & 0x7f) << (7 * (header_len - 1));
+    header_len += 1;
+    if byte & 0x80 == 0 {
+      break;
+    }
+    if header_len > 4 {
+      return Err("malformed remaining length".to_string());
+    }
+  }
+  if len > max_packet_size {
+    return Err(format!("packet of {len} bytes exceeds the limit"));
+  }
+  if buf.len() < header_len + len {
+    return Ok(None);
+  }
+
+  let first = buf[0];
+  buf.advance(header_len);
+  let mut body = buf.split_to(len).freeze();
+  let packet = match first >> 4 {
+    1 => decode_connect(&mut body)?,
+    3 => {
+      let qos = (first >> 1) & 0x03;
+      if qos == 3 {
+        return Err("invalid QoS".to_string());
+      }
+      let topic = read_string(&mut body)?;
+      let packet_id = if qos > 0 {
+        Some(read_u16(&mut body)?)
+      } else {
+        None
+      };
+      Packet::Publish {
+        topic,
+        payload: body,
+        qos,
+        packet_id,
+      }
+    }
+    4 | 5 | 7 => Packet::PubAck,
+    6 => Packet::PubRel(read_u16(&mut body)?),
+    8 => {
+      let packet_id = read_u16(&mut body)?;
+      let mut filters = Vec::new();
+      while body.has_remaining() {
+        filters.push(read_string(&mut body)?);
+        if !body.has_remaining() {
+          return Err("missing requested QoS".to_string());
+        }
+        body.advance(1);
+      }
+      if filters.is_empty() {
+        return Err("SUBSCRIBE without filters".to_string());
+      }
+      Packet::Subscribe { packet_id, filters }
+    }
+    10 => {
+      let packet_id = read_u16(&mut body)?;
+      let mut filters = Vec::new();
+      while body.has_remaining() {
+        filters.push(read_string(&mut body)?);
+      }
+      Packet::Unsubscribe { packet_id, filters }
+    }
+    12 => Packet::PingReq,
+    14 => Packet::Disconnect,
+    kind => return Err(format!("unexpected packet type {kind}")),
+  };
+  Ok(Some(packet))
+}
+
+fn decode_connect(body: &mut Bytes) -> Result<Packet, String> {
+  let protocol = read_string(body)?;
+  if protocol != "MQTT" && protocol != "MQIsdp" {
+    return Err(format!("unknown protocol name {protocol:?}"));
+  }
+  if body.remaining() < 4 {
+    return Err("truncated CONNECT".to_string());
+  }
+  let protocol_level = body.get_u8();
+  let flags = body.get_u8();
+  let keep_alive = body.get_u16();
+  if protocol_level != PROTOCOL_LEVEL {
+    // The rest of the packet may use another layout; the caller answers
+    // with `CONNACK_BAD_PROTOCOL` and closes.
+    return Ok(Packet::Connect {
+      protocol_level,
+      keep_alive,
+      client_id: String::new(),
+      username: None,
+      password: None,
+      will: None,
+    });
+  }
+  let client_id = read_string(body)?;
+  let will = if flags & 0x04 != 0 {
+    Some(Will {
+      topic: read_string(body)?,
+      payload: read_bytes(body)?,
+    })
+  } else {
+    None
+  };
+  let username = if flags & 0x80 != 0 {
+    Some(read_string(body)?)
+  } else {
+    None
+  };
+  let password = if flags & 0x40 != 0 {
+    Some(read_bytes(body)?)
+  } else {
+    None
+  };
+  Ok(Packet::Connect {
+    protocol_level,
+    keep_alive,
+    client_id,
+    username,
+    password,
+    will,
+  })
+}
+
+fn frame(kind: u8, body: &[u8]) -> Bytes {
+  let mut out = BytesMut::with_capacity(body.len() + 5);
+  out.put_u8(kind);
+  let mut len = body.len();
+  loop {
+    let mut byte = (len % 128) as u8;
+    len /= 128;
+    if len > 0 {
+      byte |= 0x80;
+    }
+    out.put_u8(byte);
+    if len == 0 {
+      break;
+    }
+  }
+  out.put_slice(body);
+  out.freeze()
+}
+
+pub(crate) fn connack(return_code: u8) -> Bytes {
+  frame(0x20, &[0, return_code])
+}
+
+/// An at-most-once (`QoS 0`) `PUBLISH`.
+pub(crate) fn publish(topic: &str, payload: &[u8]) -> Bytes {
+  let mut body = Vec::with_capacity(topic.len() + payload.len() + 2);
+  body.extend_from_slice(&(topic.len() as u16).to_be_bytes());
+  body.extend_from_slice(topic.as_bytes());
+  body.extend_from_slice(payload);
+  frame(0x30, &body)
+}
+
+pub(crate) fn puback(packet_id: u16) -> Bytes {
+  frame(0x40, &packet_id.to_be_bytes())
+}
+
+pub(crate) fn pubrec(packet_id: u16) -> Bytes {
+  frame(0x50, &packet_id.to_be_bytes())
+}
+
+pub(crate) fn pubcomp(packet_id: u16) -> Bytes {
+  frame(0x70, &packet_id.to_be_bytes())
+}
+
+pub(crate) fn suback(packet_id: u16, return_codes: &[u8]) -> Bytes {
+  let mut body = packet_id.to_be_bytes().to_vec();
+  body.extend_from_slice(return_codes);
+  frame(0x90, &body)
+}
+
+pub(crate) fn unsuback(packet_id: u16) -> Bytes {
+  frame(0xb0, &packet_id.to_be_bytes())
+}
+
+pub(crate) fn pingresp() -> Bytes {
+  frame(0xd0, &[])
+}
+
+/// Whether `filter` is a valid topic filter (`+` and `#` occupy a whole
+/// level, `#` only the last).
+pub(crate) fn valid_filter(filter: &str) -> bool {
+  if filter.is_empty() {
+    return false;
+  }
+  let levels: Vec<&str> = filter.split('/').collect();
+  levels.iter().enumerate().all(|(i, level)| match *level {
+    "#" => i == levels.len() - 1,
+    "+" => true,
+    level => !level.contains(['+', '#']),
+  })
+}
+
+/// Matches a topic name against a filter. Wildcards at the first level do
+/// not match `$`-prefixed system topics.
+pub(crate) fn topic_matches(filter: &str, topic: &str) -> bool {
+  if topic.starts_with('$') && (filter.starts_with('+') || filter.starts_with('#')) {
+    return false;
+  }
+  let mut topic_levels = topic.split('/');
+  for level in filter.split('/') {
+    match level {
+      "#" => return true,
+      "+" => {
+        if topic_levels.next().is_none() {
+          return false;
+        }
+      }
+      exact => {
+        if topic_levels.next() != Some(exact) {
+          return false;
+        }
+      }
+    }
+  }
+  topic_levels.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decodes_split_and_batched_packets() {
+    let mut connect = vec![0x10, 0];
+    let mut body = Vec::new();
+    body.extend_from_slice(&[0, 4]);
+    body.extend_from_slice(b"MQTT");
+    body.extend_from_slice(&[4, 0xc2, 0, 60]);
+    for field in [&b"dash"[..], b"alice", b"secret"] {
+      body.extend_from_slice(&(field.len() as u16).to_be_bytes());
+      body.extend_from_slice(field);
+    }
+    connect[1] = body.len() as u8;
+    connect.extend_from_slice(&body);
+
+    let mut buf = BytesMut::from(&connect[..5]);
+    assert_eq!(decode(&mut buf, 1024), Ok(None));
+    buf.extend_from_slice(&connect[5..]);
+    buf.extend_from_slice(&publish("a/b", b"hi"));
+    buf.extend_from_slice(&[0xc0, 0]);
+
+    let Ok(Some(Packet::Connect {
+      client_id,
+      username,
+      password,
+      keep_alive,
+      ..
+    })) = decode(&mut buf, 1024)
+    else {
+      panic!("expected CONNECT");
+    };
+    assert_eq!(client_id, "dash");
+    assert_eq!(username.as_deref(), Some("alice"));
+    assert_eq!(password.as_deref(), Some(&b"secret"[..]));
+    assert_eq!(keep_alive, 60);
+    assert_eq!(
+      decode(&mut buf, 1024),
+      Ok(Some(Packet::Publish {
+        topic: "a/b".to_string(),
+        payload: Bytes::from_static(b"hi"),
+        qos: 0,
+        packet_id: None,
+      }))
+    );
+    assert_eq!(decode(&mut buf, 1024), Ok(Some(Packet::PingReq)));
+    assert!(buf.is_empty());
+
+    let mut big = BytesMut::from(&publish("t", &[0; 300])[..]);
+    assert!(decode(&mut big, 128).is_err());
+  }
+
+  #[test]
+  fn matches_topic_filters() {
+    assert!(topic_matches("sensors/+/temp", "sensors/kitchen/temp"));
+    assert!(!topic_matches("sensors/+/temp", "sensors/kitchen/humidity"));
+    assert!(topic_matches("sensors/#", "sensors"));
+    assert!(topic_matches("sensors/#", "sensors/kitchen/temp"));
+    assert!(!topic_matches("#", "$SYS/uptime"));
+    assert!(!topic_matches("a/b", "a/b/c"));
+    assert!(valid_filter("a/+/#"));
+    assert!(!valid_filter("a/#/b"));
+    assert!(!valid_filter("a/b+"));
+  }
+}
@@ -0,0 +1,166 @@
+//! Ending long-lived SSE / WebSocket connections when their session ends.
+//!
+//! Authentication runs once, when the connection is opened; a stream that
+//! stays up for hours keeps delivering data after the session or token that
+//! admitted it has expired or been revoked. A [`SessionExpiry`] attached with
+//! [`Sse::session_expiry`](crate::sse::Sse::session_expiry) or
+//! `TakoWs::session_expiry` closes the connection at a fixed expiry time
+//! (e.g. a JWT `exp` claim), after a periodic revalidation check fails, or
+//! at whichever comes first.
+//!
+//! SSE streams simply end, so the browser's reconnect goes back through the
+//! authentication middleware. WebSocket connections are closed with
+//! `1008 Policy Violation` and the reason `"session expired"`.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//! use tako::session_expiry::SessionExpiry;
+//!
+//! let expiry = SessionExpiry::at_unix(claims.exp).revalidate_every(
+//!   Duration::from_secs(60),
+//!   move || {
+//!     let sessions = sessions.clone();
+//!     let sid = sid.clone();
+//!     async move { sessions.is_active(&sid).await }
+//!   },
+//! );
+//! Sse::new(events).session_expiry(expiry);
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use futures_util::future::BoxFuture;
+
+type Revalidate = Arc<dyn Fn() -> BoxFuture<'static, bool> + Send + Sync>;
+
+/// When a long-lived connection stops being authorized.
+///
+/// Combine a fixed deadline with a periodic check; the connection ends at
+/// whichever fires first. A `SessionExpiry` with neither never fires.
+#[derive(Clone, Default)]
+pub struct SessionExpiry {
+  expires_at: Option<SystemTime>,
+  revalidate: Option<(Duration, Revalidate)>,
+}
+
+impl fmt::Debug for SessionExpiry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SessionExpiry")
+      .field("expires_at", &self.expires_at)
+      .field(
+        "revalidate_every",
+        &self.revalidate.as_ref().map(|(d, _)| d),
+      )
+      .finish()
+  }
+}
+
+impl SessionExpiry {
+  /// Expires at a wall-clock time.
+  pub fn at(expires_at: SystemTime) -> Self {
+    Self {
+      expires_at: Some(expires_at),
+      revalidate: None,
+    }
+  }
+
+  /// Expires at a Unix timestamp in seconds, the format of a JWT `exp` claim.
+  pub fn at_unix(secs: u64) -> Self {
+    Self::at(UNIX_EPOCH + Duration::from_secs(secs))
+  }
+
+  /// Expires `ttl` from now.
+  pub fn after(ttl: Duration) -> Self {
+    Self::at(SystemTime::now() + ttl)
+  }
+
+  /// Calls `check` every `interval` and expires as soon as it returns
+  /// `false` — e.g. when the session was revoked on logout.
+  pub fn revalidate_every<F, Fut>(mut self, interval: Duration, check: F) -> Self
+  where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = bool> + Send + 'static,
+  {
+    self.revalidate = Some((interval, Arc::new(move || Box::pin(check()))));
+    self
+  }
+
+  /// Resolves once the session has expired; never resolves when neither a
+  /// deadline nor a check is configured.
+  pub async fn expired(self) {
+    let deadline = async {
+      match self.expires_at {
+        Some(at) => {
+          let left = at.duration_since(SystemTime::now()).unwrap_or_default();
+          tokio::time::sleep(left).await;
+        }
+        None => std::future::pending().await,
+      }
+    };
+    let revoked = async {
+      match self.revalidate {
+        Some((interval, check)) => {
+          let mut ticks =
+            tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+          loop {
+            ticks.tick().await;
+            if !check().await {
+              break;
+            }
+          }
+        }
+        None => std::future::pending().await,
+      }
+    };
+    tokio::select! {
+      () = deadline => {}
+      () = revoked => {}
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+  use std::time::Duration;
+
+  use super::SessionExpiry;
+
+  #[tokio::test]
+  async fn expires_at_deadline_or_failed_revalidation() {
+    let past = SessionExpiry::at_unix(0);
+    tokio::time::timeout(Duration::from_millis(1), past.expired())
+      .await
+      .expect("past deadline fires immediately");
+
+    let checks = Arc::new(AtomicUsize::new(0));
+    let counter = checks.clone();
+    let revoked = SessionExpiry::after(Duration::from_secs(3600)).revalidate_every(
+      Duration::from_millis(5),
+      move || {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        async move { n < 2 }
+      },
+    );
+    tokio::time::timeout(Duration::from_secs(1), revoked.expired())
+      .await
+      .expect("third check revokes the session");
+    assert_eq!(checks.load(Ordering::SeqCst), 3);
+
+    let never = SessionExpiry::default().expired();
+    assert!(
+      tokio::time::timeout(Duration::from_millis(20), never)
+        .await
+        .is_err()
+    );
+  }
+}
@@ -0,0 +1,1059 @@
+//! Socket.IO-compatible protocol adapter.
+//!
+//! Speaks Engine.IO v4 / Socket.IO v5, the protocol of the `socket.io-client`
+//! 3.x and 4.x releases, so an existing frontend can move to a Tako backend
+//! without changing its client code. Both transports are supported: HTTP
+//! long-polling and WebSocket (on top of [`TakoWs`](crate::ws::TakoWs)),
+//! including the client's polling → WebSocket upgrade.
+//!
+//! Each namespace gets a connection handler that receives a [`Socket`] and
+//! pulls [`Event`]s from it, the same shape as a [`TakoWs`](crate::ws::TakoWs)
+//! handler. Sockets can join rooms; [`SocketIo::of`], [`Socket::to`], and
+//! [`Socket::broadcast`] emit to many sockets at once.
+//!
+//! [`Socket`]: crate::socketio::Socket
+//! [`Event`]: crate::socketio::Event
+//! [`SocketIo::of`]: crate::socketio::SocketIo::of
+//! [`Socket::to`]: crate::socketio::Socket::to
+//! [`Socket::broadcast`]: crate::socketio::Socket::broadcast
+//!
+//! Binary events and acks (packet types 5 and 6, sent when an event carries a
+//! `Blob`/`ArrayBuffer`) are not supported and are dropped.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use tako::Method;
+//! use tako::router::Router;
+//! use tako::socketio::SocketIo;
+//! use tako::types::Request;
+//!
+//! let io = SocketIo::builder()
+//!     .namespace("/", |socket| async move {
+//!         socket.join("lobby");
+//!         while let Some(event) = socket.recv().await {
+//!             if event.name == "chat" {
+//!                 socket.to("lobby").emit_args("chat", event.args);
+//!                 if let Some(ack) = event.ack {
+//!                     let _ = ack.send(&"delivered");
+//!                 }
+//!             }
+//!         }
+//!     })
+//!     .build();
+//!
+//! let mut router = Router::new();
+//! for method in [Method::GET, Method::POST] {
+//!     let io = io.clone();
+//!     router.route(method, "/socket.io/", move |req: Request| {
+//!         let io = io.clone();
+//!         async move { io.handle(req).await }
+//!     });
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use http::HeaderMap;
+use serde::Serialize;
+use serde_json::Value;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+
+mod engine;
+mod packet;
+
+use engine::EngineSession;
+use packet::EnginePacket;
+use packet::Packet;
+use packet::PacketKind;
+
+/// Errors returned when emitting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SocketIoError {
+  /// The payload could not be serialized to JSON.
+  Serialize(String),
+  /// The socket has disconnected.
+  Disconnected,
+  /// The client did not acknowledge in time.
+  AckTimeout,
+}
+
+impl fmt::Display for SocketIoError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Serialize(e) => write!(f, "failed to serialize payload: {e}"),
+      Self::Disconnected => f.write_str("socket disconnected"),
+      Self::AckTimeout => f.write_str("acknowledgement timed out"),
+    }
+  }
+}
+
+impl std::error::Error for SocketIoError {}
+
+type ConnectHandler = Arc<dyn Fn(Socket) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Builder for [`SocketIo`].
+pub struct SocketIoBuilder {
+  ping_interval: Duration,
+  ping_timeout: Duration,
+  max_payload: usize,
+  namespaces: HashMap<String, ConnectHandler>,
+}
+
+impl Default for SocketIoBuilder {
+  fn default() -> Self {
+    Self {
+      ping_interval: Duration::from_secs(25),
+      ping_timeout: Duration::from_secs(20),
+      max_payload: 1_000_000,
+      namespaces: HashMap::new(),
+    }
+  }
+}
+
+impl SocketIoBuilder {
+  /// Interval between server pings (default 25 s, as in socket.io).
+  pub fn ping_interval(mut self, interval: Duration) -> Self {
+    self.ping_interval = interval;
+    self
+  }
+
+  /// How long to wait for the client's pong before dropping the connection
+  /// (default 20 s).
+  pub fn ping_timeout(mut self, timeout: Duration) -> Self {
+    self.ping_timeout = timeout;
+    self
+  }
+
+  /// Largest accepted long-polling request body, in bytes (default 1 MB).
+  pub fn max_payload(mut self, bytes: usize) -> Self {
+    self.max_payload = bytes;
+    self
+  }
+
+  /// Accepts connections to namespace `name` (`"/"` is the default
+  /// namespace) and runs `handler` for each. Connections to unregistered
+  /// namespaces are refused.
+  pub fn namespace<F, Fut>(mut self, name: impl Into<String>, handler: F) -> Self
+  where
+    F: Fn(Socket) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    let handler: ConnectHandler = Arc::new(move |socket| Box::pin(handler(socket)));
+    self.namespaces.insert(name.into(), handler);
+    self
+  }
+
+  /// Finishes the server.
+  pub fn build(self) -> SocketIo {
+    SocketIo {
+      inner: Arc::new(Inner {
+        ping_interval: self.ping_interval,
+        ping_timeout: self.ping_timeout,
+        max_payload: self.max_payload,
+        namespaces: self.namespaces,
+        engines: Mutex::new(HashMap::new()),
+        sockets: Mutex::new(HashMap::new()),
+      }),
+    }
+  }
+}
+
+/// A Socket.IO server. Cheap to clone; mount [`handle`](Self::handle) on
+/// `GET` and `POST` of the socket.io path.
+#[derive(Clone)]
+pub struct SocketIo {
+  inner: Arc<Inner>,
+}
+
+impl fmt::Debug for SocketIo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SocketIo")
+      .field("namespaces", &self.inner.namespaces.keys())
+      .finish_non_exhaustive()
+  }
+}
+
+impl SocketIo {
+  /// Starts a builder with socket.io's default timings.
+  pub fn builder() -> SocketIoBuilder {
+    SocketIoBuilder::default()
+  }
+
+  /// Serves one Engine.IO request: a polling handshake, poll, or post, or a
+  /// WebSocket upgrade.
+  pub async fn handle(&self, req: Request) -> Response {
+    engine::handle(&self.inner, req).await
+  }
+
+  /// Emits to every socket connected to namespace `ns`; narrow with
+  /// [`BroadcastOperator::to`].
+  pub fn of(&self, ns: &str) -> BroadcastOperator {
+    BroadcastOperator::new(self.inner.clone(), ns, None)
+  }
+
+  /// Sockets currently connected to namespace `ns`.
+  pub fn sockets(&self, ns: &str) -> Vec<Socket> {
+    self
+      .inner
+      .sockets
+      .lock()
+      .expect("socket registry poisoned")
+      .get(ns)
+      .map(|sockets| sockets.values().cloned().collect())
+      .unwrap_or_default()
+  }
+}
+
+pub(crate) struct Inner {
+  ping_interval: Duration,
+  ping_timeout: Duration,
+  max_payload: usize,
+  namespaces: HashMap<String, ConnectHandler>,
+  engines: Mutex<HashMap<String, Arc<EngineSession>>>,
+  /// namespace → socket id → socket.
+  sockets: Mutex<HashMap<String, HashMap<String, Socket>>>,
+}
+
+impl Inner {
+  fn on_message(self: &Arc<Self>, engine: &Arc<EngineSession>, raw: &str) {
+    let packet = match Packet::decode(raw) {
+      Ok(packet) => packet,
+      Err(e) => {
+        tracing::debug!(sid = %engine.sid, "dropping socket.io packet: {e}");
+        return;
+      }
+    };
+    match packet.kind {
+      PacketKind::Connect => self.connect(engine, packet),
+      PacketKind::Disconnect => {
+        let socket = engine.socket(&packet.namespace);
+        if let Some(socket) = socket {
+          self.remove_socket(&socket);
+        }
+      }
+      PacketKind::Event => {
+        if let Some(socket) = engine.socket(&packet.namespace) {
+          socket.dispatch(packet);
+        }
+      }
+      PacketKind::Ack => {
+        if let Some(socket) = engine.socket(&packet.namespace) {
+          socket.resolve_ack(packet);
+        }
+      }
+      PacketKind::ConnectError => {}
+    }
+  }
+
+  fn connect(self: &Arc<Self>, engine: &Arc<EngineSession>, packet: Packet) {
+    let ns = packet.namespace;
+    let Some(handler) = self.namespaces.get(&ns).cloned() else {
+      engine.send_packet(&Packet::new(
+        PacketKind::ConnectError,
+        &ns,
+        Some(serde_json::json!({ "message": "Invalid namespace" })),
+      ));
+      return;
+    };
+    if engine.socket(&ns).is_some() {
+      return;
+    }
+
+    let (events_tx, events_rx) = mpsc::unbounded_channel();
+    let socket = Socket {
+      inner: Arc::new(SocketInner {
+        id: new_id(),
+        namespace: ns.clone(),
+        auth: packet.data.unwrap_or(Value::Null),
+        engine: engine.clone(),
+        io: self.clone(),
+        events_tx: Mutex::new(Some(events_tx)),
+        events_rx: tokio::sync::Mutex::new(events_rx),
+        acks: Mutex::new(HashMap::new()),
+        next_ack: AtomicU64::new(0),
+        rooms: Mutex::new(HashSet::new()),
+      }),
+    };
+    engine
+      .sockets
+      .lock()
+      .expect("engine sockets poisoned")
+      .insert(ns.clone(), socket.clone());
+    self
+      .sockets
+      .lock()
+      .expect("socket registry poisoned")
+      .entry(ns.clone())
+      .or_default()
+      .insert(socket.id().to_string(), socket.clone());
+
+    engine.send_packet(&Packet::new(
+      PacketKind::Connect,
+      &ns,
+      Some(serde_json::json!({ "sid": socket.id() })),
+    ));
+    tokio::spawn(handler(socket));
+  }
+
+  fn remove_socket(&self, socket: &Socket) {
+    let ns = socket.namespace();
+    socket
+      .inner
+      .engine
+      .sockets
+      .lock()
+      .expect("engine sockets poisoned")
+      .remove(ns);
+    if let Some(sockets) = self
+      .sockets
+      .lock()
+      .expect("socket registry poisoned")
+      .get_mut(ns)
+    {
+      sockets.remove(socket.id());
+    }
+    socket.close_local();
+  }
+
+  fn close_engine(&self, engine: &EngineSession) {
+    if engine.closed.is_cancelled() {
+      return;
+    }
+    engine.closed.cancel();
+    self
+      .engines
+      .lock()
+      .expect("engine registry poisoned")
+      .remove(&engine.sid);
+    let sockets: Vec<Socket> = engine
+      .sockets
+      .lock()
+      .expect("engine sockets poisoned")
+      .drain()
+      .map(|(_, socket)| socket)
+      .collect();
+    for socket in sockets {
+      self.remove_socket(&socket);
+    }
+  }
+}
+
+fn new_id() -> String {
+  uuid::Uuid::new_v4().simple().to_string()
+}
+
+fn to_value<T: Serialize + ?Sized>(data: &T) -> Result<Value, SocketIoError> {
+  serde_json::to_value(data).map_err(|e| SocketIoError::Serialize(e.to_string()))
+}
+
+/// A client connected to one namespace.
+#[derive(Clone)]
+pub struct Socket {
+  inner: Arc<SocketInner>,
+}
+
+struct SocketInner {
+  id: String,
+  namespace: String,
+  auth: Value,
+  engine: Arc<EngineSession>,
+  io: Arc<Inner>,
+  events_tx: Mutex<Option<mpsc::UnboundedSender<Event>>>,
+  events_rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<Event>>,
+  acks: Mutex<HashMap<u64, oneshot::Sender<Vec<Value>>>>,
+  next_ack: AtomicU64,
+  rooms: Mutex<HashSet<String>>,
+}
+
+impl fmt::Debug for Socket {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Socket")
+      .field("id", &self.inner.id)
+      .field("namespace", &self.inner.namespace)
+      .finish_non_exhaustive()
+  }
+}
+
+impl Socket {
+  /// Socket id, sent to the client as `socket.id`. Every socket is
+  /// implicitly in a room with this name.
+  pub fn id(&self) -> &str {
+    &self.inner.id
+  }
+
+  /// Namespace the socket connected to.
+  pub fn namespace(&self) -> &str {
+    &self.inner.namespace
+  }
+
+  /// The client's `auth` option, or `Null`.
+  pub fn auth(&self) -> &Value {
+    &self.inner.auth
+  }
+
+  /// Headers of the request that opened the underlying connection.
+  pub fn headers(&self) -> &HeaderMap {
+    &self.inner.engine.headers
+  }
+
+  /// Whether the socket is still connected.
+  pub fn is_connected(&self) -> bool {
+    self
+      .inner
+      .events_tx
+      .lock()
+      .expect("socket poisoned")
+      .is_some()
+  }
+
+  /// Waits for the next event from the client. Returns `None` once the
+  /// socket has disconnected.
+  pub async fn recv(&self) -> Option<Event> {
+    self.inner.events_rx.lock().await.recv().await
+  }
+
+  /// Emits `event` with one argument.
+  ///
+  /// # Errors
+  ///
+  /// Fails when `data` cannot be serialized or the socket has disconnected.
+  pub fn emit<T: Serialize + ?Sized>(&self, event: &str, data: &T) -> Result<(), SocketIoError> {
+    self.emit_packet(event, vec![to_value(data)?], None)
+  }
+
+  /// Emits `event` with several arguments, for `socket.on("e", (a, b) => …)`
+  /// listeners.
+  ///
+  /// # Errors
+  ///
+  /// Fails when the socket has disconnected.
+  pub fn emit_args(&self, event: &str, args: Vec<Value>) -> Result<(), SocketIoError> {
+    self.emit_packet(event, args, None)
+  }
+
+  /// Emits `event` and waits up to `timeout` for the client's
+  /// acknowledgement, returning its arguments.
+  ///
+  /// # Errors
+  ///
+  /// Fails when `data` cannot be serialized, the socket disconnects, or the
+  /// client does not acknowledge in time.
+  pub async fn emit_with_ack<T: Serialize + ?Sized>(
+    &self,
+    event: &str,
+    data: &T,
+    timeout: Duration,
+  ) -> Result<Vec<Value>, SocketIoError> {
+    let data = to_value(data)?;
+    let id = self.inner.next_ack.fetch_add(1, Ordering::Relaxed);
+    let (tx, rx) = oneshot::channel();
+    self
+      .inner
+      .acks
+      .lock()
+      .expect("socket poisoned")
+      .insert(id, tx);
+    if let Err(e) = self.emit_packet(event, vec![data], Some(id)) {
+      self.inner.acks.lock().expect("socket poisoned").remove(&id);
+      return Err(e);
+    }
+    match tokio::time::timeout(timeout, rx).await {
+      Ok(Ok(args)) => Ok(args),
+      Ok(Err(_)) => Err(SocketIoError::Disconnected),
+      Err(_) => {
+        self.inner.acks.lock().expect("socket poisoned").remove(&id);
+        Err(SocketIoError::AckTimeout)
+      }
+    }
+  }
+
+  /// Adds the socket to `room`.
+  pub fn join(&self, room: impl Into<String>) {
+    self
+      .inner
+      .rooms
+      .lock()
+      .expect("socket poisoned")
+      .insert(room.into());
+  }
+
+  /// Removes the socket from `room`.
+  pub fn leave(&self, room: &str) {
+    self
+      .inner
+      .rooms
+      .lock()
+      .expect("socket poisoned")
+      .remove(room);
+  }
+
+  /// Rooms the socket has joined (not including its own id).
+  pub fn rooms(&self) -> Vec<String> {
+    self
+      .inner
+      .rooms
+      .lock()
+      .expect("socket poisoned")
+      .iter()
+      .cloned()
+      .collect()
+  }
+
+  fn in_room(&self, room: &str) -> bool {
+    room == self.inner.id
+      || self
+        .inner
+        .rooms
+        .lock()
+        .expect("socket poisoned")
+        .contains(room)
+  }
+
+  /// Emits to the other sockets in `room`.
+  pub fn to(&self, room: impl Into<String>) -> BroadcastOperator {
+    self.broadcast().to(room)
+  }
+
+  /// Emits to every other socket in this namespace.
+  pub fn broadcast(&self) -> BroadcastOperator {
+    BroadcastOperator::new(
+      self.inner.io.clone(),
+      &self.inner.namespace,
+      Some(self.inner.id.clone()),
+    )
+  }
+
+  /// Disconnects the socket from its namespace. The underlying connection
+  /// stays open for other namespaces.
+  pub fn disconnect(&self) {
+    if self.is_connected() {
+      self.inner.engine.send_packet(&Packet::new(
+        PacketKind::Disconnect,
+        &self.inner.namespace,
+        None,
+      ));
+      self.inner.io.remove_socket(self);
+    }
+  }
+
+  fn emit_packet(
+    &self,
+    event: &str,
+    args: Vec<Value>,
+    id: Option<u64>,
+  ) -> Result<(), SocketIoError> {
+    if !self.is_connected() {
+      return Err(SocketIoError::Disconnected);
+    }
+    let mut packet = event_packet(&self.inner.namespace, event, args);
+    packet.id = id;
+    self.inner.engine.send_packet(&packet);
+    Ok(())
+  }
+
+  fn dispatch(&self, packet: Packet) {
+    let Some(Value::Array(mut args)) = packet.data else {
+      return;
+    };
+    if args.is_empty() {
+      return;
+    }
+    let Value::String(name) = args.remove(0) else {
+      return;
+    };
+    let event = Event {
+      name,
+      args,
+      ack: packet.id.map(|id| Ack {
+        engine: self.inner.engine.clone(),
+        namespace: self.inner.namespace.clone(),
+        id,
+      }),
+    };
+    if let Some(tx) = self
+      .inner
+      .events_tx
+      .lock()
+      .expect("socket poisoned")
+      .as_ref()
+    {
+      let _ = tx.send(event);
+    }
+  }
+
+  fn resolve_ack(&self, packet: Packet) {
+    let Some(id) = packet.id else { return };
+    let args = match packet.data {
+      Some(Value::Array(args)) => args,
+      Some(other) => vec![other],
+      None => Vec::new(),
+    };
+    if let Some(tx) = self.inner.acks.lock().expect("socket poisoned").remove(&id) {
+      let _ = tx.send(args);
+    }
+  }
+
+  fn close_local(&self) {
+    self.inner.events_tx.lock().expect("socket poisoned").take();
+    self.inner.acks.lock().expect("socket poisoned").clear();
+  }
+}
+
+fn event_packet(ns: &str, event: &str, args: Vec<Value>) -> Packet {
+  let mut data = Vec::with_capacity(args.len() + 1);
+  data.push(Value::String(event.to_string()));
+  data.extend(args);
+  Packet::new(PacketKind::Event, ns, Some(Value::Array(data)))
+}
+
+/// An event received from the client.
+#[derive(Debug)]
+pub struct Event {
+  /// Event name, the first argument of the client's `emit`.
+  pub name: String,
+  /// Remaining arguments.
+  pub args: Vec<Value>,
+  /// Present when the client passed an acknowledgement callback.
+  pub ack: Option<Ack>,
+}
+
+impl Event {
+  /// Deserializes the first argument.
+  ///
+  /// # Errors
+  ///
+  /// Fails when there is no argument or it does not match `T`.
+  pub fn data<T: serde::de::DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+    serde_json::from_value(self.args.first().cloned().unwrap_or(Value::Null))
+  }
+}
+
+/// Replies to an event's acknowledgement callback. Dropping it without
+/// sending leaves the client's callback uncalled.
+pub struct Ack {
+  engine: Arc<EngineSession>,
+  namespace: String,
+  id: u64,
+}
+
+impl fmt::Debug for Ack {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Ack")
+      .field("id", &self.id)
+      .finish_non_exhaustive()
+  }
+}
+
+impl Ack {
+  /// Calls the client's callback with `data`.
+  ///
+  /// # Errors
+  ///
+  /// Fails when `data` cannot be serialized.
+  pub fn send<T: Serialize + ?Sized>(self, data: &T) -> Result<(), SocketIoError> {
+    let data = to_value(data)?;
+    self.send_args(vec![data]);
+    Ok(())
+  }
+
+  /// Calls the client's callback with several arguments.
+  pub fn send_args(self, args: Vec<Value>) {
+    self.engine.send_packet(
+      &Packet::new(PacketKind::Ack, &self.namespace, Some(Value::Array(args))).with_id(self.id),
+    );
+  }
+}
+
+/// Emits to a set of sockets in one namespace.
+#[derive(Clone)]
+pub struct BroadcastOperator {
+  io: Arc<Inner>,
+  namespace: String,
+  rooms: Vec<String>,
+  except: Option<String>,
+}
+
+impl fmt::Debug for BroadcastOperator {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("BroadcastOperator")
+      .field("namespace", &self.namespace)
+      .field("rooms", &self.rooms)
+      .finish_non_exhaustive()
+  }
+}
+
+impl BroadcastOperator {
+  fn new(io: Arc<Inner>, namespace: &str, except: Option<String>) -> Self {
+    Self {
+      io,
+      namespace: namespace.to_string(),
+      rooms: Vec::new(),
+      except,
+    }
+  }
+
+  /// Restricts the emit to sockets in `room`. Calling it again adds rooms;
+  /// a socket in any of them receives the event once.
+  pub fn to(mut self, room: impl Into<String>) -> Self {
+    self.rooms.push(room.into());
+    self
+  }
+
+  /// Emits `event` with one argument to every matching socket.
+  ///
+  /// # Errors
+  ///
+  /// Fails when `data` cannot be serialized.
+  pub fn emit<T: Serialize + ?Sized>(&self, event: &str, data: &T) -> Result<(), SocketIoError> {
+    self.emit_args(event, vec![to_value(data)?]);
+    Ok(())
+  }
+
+  /// Emits `event` with several arguments to every matching socket.
+  pub fn emit_args(&self, event: &str, args: Vec<Value>) {
+    let encoded = EnginePacket::Message(event_packet(&self.namespace, event, args).encode());
+    let targets: Vec<Socket> = self
+      .io
+      .sockets
+      .lock()
+      .expect("socket registry poisoned")
+      .get(&self.namespace)
+      .map(|sockets| sockets.values().cloned().collect())
+      .unwrap_or_default();
+    for socket in targets {
+      if self.except.as_deref() == Some(socket.id()) {
+        continue;
+      }
+      if !self.rooms.is_empty() && !self.rooms.iter().any(|room| socket.in_room(room)) {
+        continue;
+      }
+      socket.inner.engine.send(encoded.clone());
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use futures_util::SinkExt;
+  use futures_util::StreamExt;
+  use http::StatusCode;
+  use http_body_util::BodyExt;
+  use hyper_util::rt::TokioIo;
+  use tako_rs_core::body::TakoBody;
+  use tokio_tungstenite::tungstenite::Message;
+
+  use super::*;
+
+  async fn send(
+    io: &SocketIo,
+    method: http::Method,
+    uri: &str,
+    body: &str,
+  ) -> (StatusCode, String) {
+    let req = http::Request::builder()
+      .method(method)
+      .uri(uri)
+      .body(TakoBody::from(body.to_string()))
+      .unwrap();
+    let res = io.handle(req).await;
+    let status = res.status();
+    let bytes = res.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+  }
+
+  async fn request(io: &SocketIo, method: http::Method, query: &str, body: &str) -> String {
+    let uri = format!("/socket.io/?EIO=4&transport=polling{query}");
+    send(io, method, &uri, body).await.1
+  }
+
+  /// Runs the polling handshake and returns the `&sid=…` query suffix.
+  async fn open(io: &SocketIo) -> String {
+    let open = request(io, http::Method::GET, "", "").await;
+    let handshake: Value = serde_json::from_str(open.strip_prefix('0').unwrap()).unwrap();
+    format!("&sid={}", handshake["sid"].as_str().unwrap())
+  }
+
+  /// Polls until `n` packets have arrived.
+  async fn poll_packets(io: &SocketIo, sid: &str, n: usize) -> Vec<String> {
+    let mut received = Vec::new();
+    while received.len() < n {
+      let payload = request(io, http::Method::GET, sid, "").await;
+      received.extend(payload.split('\x1e').map(str::to_string));
+    }
+    received
+  }
+
+  /// Serves `io` over HTTP/1.1 with upgrades, for the WebSocket transport.
+  async fn serve(io: SocketIo) -> std::net::SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+      while let Ok((stream, _)) = listener.accept().await {
+        let io = io.clone();
+        let service =
+          hyper::service::service_fn(move |req: http::Request<hyper::body::Incoming>| {
+            let io = io.clone();
+            async move {
+              Ok::<_, std::convert::Infallible>(io.handle(req.map(TakoBody::incoming)).await)
+            }
+          });
+        tokio::spawn(
+          hyper::server::conn::http1::Builder::new()
+            .serve_connection(TokioIo::new(stream), service)
+            .with_upgrades(),
+        );
+      }
+    });
+    addr
+  }
+
+  #[tokio::test]
+  async fn serves_events_and_acks_over_long_polling() {
+    let io = SocketIo::builder()
+      .namespace("/", |socket: Socket| async move {
+        socket.join("echo");
+        while let Some(event) = socket.recv().await {
+          socket.to("echo").emit_args(&event.name, event.args.clone());
+          if let Some(ack) = event.ack {
+            ack.send(&event.args[0]).unwrap();
+          }
+        }
+      })
+      .build();
+
+    let open = request(&io, http::Method::GET, "", "").await;
+    let handshake: Value = serde_json::from_str(open.strip_prefix('0').unwrap()).unwrap();
+    assert_eq!(handshake["upgrades"], serde_json::json!(["websocket"]));
+    let sid = format!("&sid={}", handshake["sid"].as_str().unwrap());
+
+    assert_eq!(
+      request(&io, http::Method::POST, &sid, "40\x1e40/nope,").await,
+      "ok"
+    );
+    let mut received = Vec::new();
+    while received.len() < 2 {
+      let payload = request(&io, http::Method::GET, &sid, "").await;
+      received.extend(payload.split('\x1e').map(str::to_string));
+    }
+    assert!(received[0].starts_with("40{\"sid\":"));
+    assert_eq!(received[1], "44/nope,{\"message\":\"Invalid namespace\"}");
+    assert_eq!(io.sockets("/").len(), 1);
+
+    request(&io, http::Method::POST, &sid, "427[\"echo\",\"hi\"]").await;
+    let ack = request(&io, http::Method::GET, &sid, "").await;
+    assert_eq!(ack, "437[\"hi\"]");
+
+    io.of("/").to("echo").emit("news", &1).unwrap();
+    assert_eq!(
+      request(&io, http::Method::GET, &sid, "").await,
+      "42[\"news\",1]"
+    );
+
+    request(&io, http::Method::POST, &sid, "1").await;
+    assert!(io.sockets("/").is_empty());
+    assert!(
+      request(&io, http::Method::GET, &sid, "")
+        .await
+        .contains("Session ID unknown")
+    );
+  }
+
+  #[tokio::test]
+  async fn polling_handshake_advertises_settings_and_rejects_bad_requests() {
+    let io = SocketIo::builder()
+      .ping_interval(Duration::from_millis(300))
+      .ping_timeout(Duration::from_millis(200))
+      .max_payload(64)
+      .namespace("/", |_socket: Socket| async {})
+      .build();
+
+    let (status, body) = send(
+      &io,
+      http::Method::GET,
+      "/socket.io/?EIO=4&transport=polling",
+      "",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let handshake: Value = serde_json::from_str(body.strip_prefix('0').unwrap()).unwrap();
+    assert_eq!(handshake["sid"].as_str().unwrap().len(), 32);
+    assert_eq!(handshake["upgrades"], serde_json::json!(["websocket"]));
+    assert_eq!(handshake["pingInterval"], 300);
+    assert_eq!(handshake["pingTimeout"], 200);
+    assert_eq!(handshake["maxPayload"], 64);
+
+    for (method, uri, code) in [
+      (http::Method::GET, "/socket.io/?EIO=3&transport=polling", 5),
+      (http::Method::GET, "/socket.io/?EIO=4&transport=flash", 0),
+      (http::Method::POST, "/socket.io/?EIO=4&transport=polling", 2),
+      (
+        http::Method::GET,
+        "/socket.io/?EIO=4&transport=polling&sid=missing",
+        1,
+      ),
+    ] {
+      let (status, body) = send(&io, method, uri, "").await;
+      assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+      let body: Value = serde_json::from_str(&body).unwrap();
+      assert_eq!(body["code"], code, "{uri}");
+    }
+
+    // An oversized post closes the session.
+    let sid = open(&io).await;
+    let (status, _) = send(
+      &io,
+      http::Method::POST,
+      &format!("/socket.io/?EIO=4&transport=polling{sid}"),
+      &"4".repeat(65),
+    )
+    .await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(
+      request(&io, http::Method::GET, &sid, "")
+        .await
+        .contains("Session ID unknown")
+    );
+  }
+
+  #[tokio::test]
+  async fn evicts_sessions_that_miss_a_pong() {
+    let io = SocketIo::builder()
+      .ping_interval(Duration::from_millis(50))
+      .ping_timeout(Duration::from_millis(200))
+      .namespace("/", |_socket: Socket| async {})
+      .build();
+    let sid = open(&io).await;
+    request(&io, http::Method::POST, &sid, "40").await;
+    assert!(poll_packets(&io, &sid, 1).await[0].starts_with("40"));
+
+    // Answered ping: the session survives.
+    assert_eq!(request(&io, http::Method::GET, &sid, "").await, "2");
+    assert_eq!(request(&io, http::Method::POST, &sid, "3").await, "ok");
+    assert_eq!(request(&io, http::Method::GET, &sid, "").await, "2");
+    assert_eq!(io.sockets("/").len(), 1);
+
+    // Unanswered ping: the pending poll ends with a close and the session
+    // and its sockets are gone.
+    let closed = tokio::time::timeout(
+      Duration::from_secs(5),
+      request(&io, http::Method::GET, &sid, ""),
+    )
+    .await
+    .unwrap();
+    assert_eq!(closed, "1");
+    assert!(io.sockets("/").is_empty());
+    assert!(
+      request(&io, http::Method::GET, &sid, "")
+        .await
+        .contains("Session ID unknown")
+    );
+  }
+
+  #[tokio::test]
+  async fn server_emits_resolve_with_the_client_ack() {
+    let (results_tx, mut results) = mpsc::unbounded_channel();
+    let io = SocketIo::builder()
+      .namespace("/", move |socket: Socket| {
+        let results_tx = results_tx.clone();
+        async move {
+          let answered = socket
+            .emit_with_ack("question", &"ping?", Duration::from_secs(5))
+            .await;
+          let _ = results_tx.send(answered);
+          let ignored = socket
+            .emit_with_ack("question", &"again?", Duration::from_millis(50))
+            .await;
+          let _ = results_tx.send(ignored);
+        }
+      })
+      .build();
+    let sid = open(&io).await;
+    request(&io, http::Method::POST, &sid, "40").await;
+
+    let received = poll_packets(&io, &sid, 2).await;
+    assert!(received[0].starts_with("40"));
+    assert_eq!(received[1], "420[\"question\",\"ping?\"]");
+    request(&io, http::Method::POST, &sid, "430[\"pong!\",2]").await;
+    assert_eq!(
+      results.recv().await.unwrap(),
+      Ok(vec![Value::from("pong!"), Value::from(2)])
+    );
+
+    assert_eq!(
+      poll_packets(&io, &sid, 1).await,
+      ["421[\"question\",\"again?\"]"]
+    );
+    assert_eq!(
+      results.recv().await.unwrap(),
+      Err(SocketIoError::AckTimeout)
+    );
+    // A late ack for the expired id is dropped.
+    assert_eq!(
+      request(&io, http::Method::POST, &sid, "431[\"late\"]").await,
+      "ok"
+    );
+  }
+
+  #[tokio::test]
+  async fn upgrades_polling_sessions_to_websocket() {
+    let io = SocketIo::builder()
+      .namespace("/", |socket: Socket| async move {
+        while let Some(event) = socket.recv().await {
+          if let Some(ack) = event.ack {
+            ack.send_args(event.args);
+          }
+        }
+      })
+      .build();
+    let addr = serve(io.clone()).await;
+    let sid = open(&io).await;
+    request(&io, http::Method::POST, &sid, "40").await;
+    assert!(poll_packets(&io, &sid, 1).await[0].starts_with("40"));
+
+    // The poll in flight during the upgrade is completed with a `noop`.
+    let pending = tokio::spawn({
+      let io = io.clone();
+      let sid = sid.clone();
+      async move { request(&io, http::Method::GET, &sid, "").await }
+    });
+    let url = format!("ws://{addr}/socket.io/?EIO=4&transport=websocket{sid}");
+    let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    ws.send(Message::text("2probe")).await.unwrap();
+    let reply = ws.next().await.unwrap().unwrap();
+    assert_eq!(reply.to_text().unwrap(), "3probe");
+    ws.send(Message::text("5")).await.unwrap();
+    assert_eq!(pending.await.unwrap(), "6");
+
+    // The socket now talks over the WebSocket; polling is refused.
+    ws.send(Message::text("421[\"echo\",\"hi\"]"))
+      .await
+      .unwrap();
+    let reply = ws.next().await.unwrap().unwrap();
+    assert_eq!(reply.to_text().unwrap(), "431[\"hi\"]");
+    io.of("/").emit("news", &1).unwrap();
+    let reply = ws.next().await.unwrap().unwrap();
+    assert_eq!(reply.to_text().unwrap(), "42[\"news\",1]");
+    assert!(
+      request(&io, http::Method::GET, &sid, "")
+        .await
+        .contains("Bad request")
+    );
+
+    // A second upgrade of the same session is refused.
+    let url = format!("ws://{addr}/socket.io/?EIO=4&transport=websocket{sid}");
+    assert!(tokio_tungstenite::connect_async(url).await.is_err());
+  }
+}
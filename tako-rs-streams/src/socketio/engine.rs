@@ -0,0 +1,359 @@
+//! Engine.IO sessions and their two transports.
+//!
+//! Outgoing packets go into a per-session channel. Whichever transport is
+//! active holds the receiving end: a long-poll `GET` locks it for one
+//! response, an upgraded WebSocket locks it for good. On upgrade the
+//! session queues a `noop`, which completes the poll in flight and hands the
+//! channel over to the WebSocket.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use futures_util::SinkExt;
+use futures_util::StreamExt;
+use http::HeaderMap;
+use http::HeaderValue;
+use http::Method;
+use http::StatusCode;
+use http::header;
+use http_body_util::BodyExt;
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+use tokio::sync::Notify;
+use tokio::sync::mpsc;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+
+use super::Inner;
+use super::Socket;
+use super::new_id;
+use super::packet::EnginePacket;
+use super::packet::Packet;
+use super::packet::RECORD_SEPARATOR;
+use super::packet::encode_payload;
+use crate::ws::TakoWs;
+
+/// One Engine.IO connection, shared by the transports serving it.
+pub(crate) struct EngineSession {
+  pub(crate) sid: String,
+  pub(crate) headers: HeaderMap,
+  outbox: mpsc::UnboundedSender<EnginePacket>,
+  inbox: tokio::sync::Mutex<mpsc::UnboundedReceiver<EnginePacket>>,
+  pong: Notify,
+  pub(crate) closed: CancellationToken,
+  upgraded: AtomicBool,
+  /// namespace → socket.
+  pub(crate) sockets: Mutex<HashMap<String, Socket>>,
+}
+
+impl EngineSession {
+  pub(crate) fn send(&self, packet: EnginePacket) {
+    let _ = self.outbox.send(packet);
+  }
+
+  pub(crate) fn send_packet(&self, packet: &Packet) {
+    self.send(EnginePacket::Message(packet.encode()));
+  }
+
+  pub(crate) fn socket(&self, ns: &str) -> Option<Socket> {
+    self
+      .sockets
+      .lock()
+      .expect("engine sockets poisoned")
+      .get(ns)
+      .cloned()
+  }
+}
+
+pub(crate) async fn handle(io: &Arc<Inner>, req: Request) -> Response {
+  let mut eio = None;
+  let mut transport = None;
+  let mut sid = None;
+  for (key, value) in url::form_urlencoded::parse(req.uri().query().unwrap_or("").as_bytes()) {
+    match &*key {
+      "EIO" => eio = Some(value.into_owned()),
+      "transport" => transport = Some(value.into_owned()),
+      "sid" => sid = Some(value.into_owned()),
+      _ => {}
+    }
+  }
+  if eio.as_deref() != Some("4") {
+    return error(5, "Unsupported protocol version");
+  }
+
+  match transport.as_deref() {
+    Some("polling") => {
+      let Some(sid) = sid else {
+        if req.method() != Method::GET {
+          return error(2, "Bad handshake method");
+        }
+        let (_, open) = open_session(io, req.headers().clone(), true);
+        return text(EnginePacket::Open(open).encode());
+      };
+      let Some(engine) = lookup(io, &sid) else {
+        return error(1, "Session ID unknown");
+      };
+      match *req.method() {
+        Method::GET => poll(io, &engine).await,
+        Method::POST => post(io, &engine, req).await,
+        _ => error(3, "Bad request"),
+      }
+    }
+    Some("websocket") => {
+      let engine = match sid {
+        Some(sid) => match lookup(io, &sid) {
+          Some(engine) if !engine.upgraded.load(Ordering::SeqCst) => Some(engine),
+          Some(_) => return error(3, "Bad request"),
+          None => return error(1, "Session ID unknown"),
+        },
+        None => None,
+      };
+      let io = io.clone();
+      let headers = req.headers().clone();
+      TakoWs::new(req, move |ws| run_websocket(io, ws, engine, headers)).into_response()
+    }
+    _ => error(0, "Transport unknown"),
+  }
+}
+
+fn lookup(io: &Inner, sid: &str) -> Option<Arc<EngineSession>> {
+  io.engines
+    .lock()
+    .expect("engine registry poisoned")
+    .get(sid)
+    .cloned()
+}
+
+/// Registers a session and starts its heartbeat. Returns the handshake JSON.
+fn open_session(
+  io: &Arc<Inner>,
+  headers: HeaderMap,
+  upgrades: bool,
+) -> (Arc<EngineSession>, String) {
+  let (outbox, inbox) = mpsc::unbounded_channel();
+  let engine = Arc::new(EngineSession {
+    sid: new_id(),
+    headers,
+    outbox,
+    inbox: tokio::sync::Mutex::new(inbox),
+    pong: Notify::new(),
+    closed: CancellationToken::new(),
+    upgraded: AtomicBool::new(false),
+    sockets: Mutex::new(HashMap::new()),
+  });
+  io.engines
+    .lock()
+    .expect("engine registry poisoned")
+    .insert(engine.sid.clone(), engine.clone());
+
+  let handshake = serde_json::json!({
+    "sid": engine.sid,
+    "upgrades": if upgrades { vec!["websocket"] } else { Vec::new() },
+    "pingInterval": io.ping_interval.as_millis() as u64,
+    "pingTimeout": io.ping_timeout.as_millis() as u64,
+    "maxPayload": io.max_payload,
+  });
+
+  let heartbeat_io = io.clone();
+  let heartbeat_engine = engine.clone();
+  tokio::spawn(async move { heartbeat(&heartbeat_io, &heartbeat_engine).await });
+  (engine, handshake.to_string())
+}
+
+/// Pings every `ping_interval` and closes the session when a pong does not
+/// arrive within `ping_timeout`.
+async fn heartbeat(io: &Inner, engine: &EngineSession) {
+  loop {
+    tokio::select! {
+      () = engine.closed.cancelled() => return,
+      () = tokio::time::sleep(io.ping_interval) => {}
+    }
+    engine.send(EnginePacket::Ping(String::new()));
+    tokio::select! {
+      () = engine.closed.cancelled() => return,
+      () = engine.pong.notified() => {}
+      () = tokio::time::sleep(io.ping_timeout) => {
+        tracing::debug!(sid = %engine.sid, "socket.io ping timeout");
+        io.close_engine(engine);
+        return;
+      }
+    }
+  }
+}
+
+fn on_packet(io: &Arc<Inner>, engine: &Arc<EngineSession>, packet: EnginePacket) {
+  match packet {
+    EnginePacket::Close => io.close_engine(engine),
+    EnginePacket::Ping(data) => engine.send(EnginePacket::Pong(data)),
+    EnginePacket::Pong(_) => engine.pong.notify_one(),
+    EnginePacket::Message(raw) => io.on_message(engine, &raw),
+    EnginePacket::Open(_) | EnginePacket::Upgrade | EnginePacket::Noop => {}
+  }
+}
+
+/// Long-poll `GET`: waits for at least one packet, then flushes the queue.
+async fn poll(io: &Inner, engine: &EngineSession) -> Response {
+  if engine.upgraded.load(Ordering::SeqCst) {
+    return error(3, "Bad request");
+  }
+  // A second concurrent poll is a protocol violation; socket.io closes the
+  // session.
+  let Ok(mut inbox) = engine.inbox.try_lock() else {
+    io.close_engine(engine);
+    return error(3, "Bad request");
+  };
+  let mut packets = Vec::new();
+  tokio::select! {
+    packet = inbox.recv() => packets.extend(packet),
+    () = engine.closed.cancelled() => {}
+  }
+  while let Ok(packet) = inbox.try_recv() {
+    packets.push(packet);
+  }
+  if packets.is_empty() {
+    packets.push(EnginePacket::Close);
+  }
+  text(encode_payload(&packets))
+}
+
+/// Long-poll `POST`: one or more client packets.
+async fn post(io: &Arc<Inner>, engine: &Arc<EngineSession>, req: Request) -> Response {
+  let limited = http_body_util::Limited::new(req.into_body(), io.max_payload);
+  let body = match limited.collect().await {
+    Ok(collected) => collected.to_bytes(),
+    Err(e) => {
+      if e
+        .downcast_ref::<http_body_util::LengthLimitError>()
+        .is_some()
+      {
+        io.close_engine(engine);
+        return (StatusCode::PAYLOAD_TOO_LARGE, "payload too large").into_response();
+      }
+      return error(3, "Bad request");
+    }
+  };
+  let Ok(payload) = std::str::from_utf8(&body) else {
+    return error(3, "Bad request");
+  };
+  for raw in payload.split(RECORD_SEPARATOR) {
+    if let Some(packet) = EnginePacket::decode(raw) {
+      on_packet(io, engine, packet);
+    } else {
+      // `b<base64>` binary packets and garbage.
+      tracing::debug!(sid = %engine.sid, "dropping engine.io packet");
+    }
+  }
+  text("ok".to_string())
+}
+
+/// Answers the client's `2probe` with `3probe` and waits for `5`.
+async fn probe(ws: &mut WebSocketStream<TokioIo<Upgraded>>) -> bool {
+  while let Some(msg) = ws.next().await {
+    let Ok(Message::Text(raw)) = msg else {
+      return false;
+    };
+    match EnginePacket::decode(raw.as_str()) {
+      Some(EnginePacket::Ping(data)) if data == "probe" => {
+        if ws
+          .send(Message::text(EnginePacket::Pong(data).encode()))
+          .await
+          .is_err()
+        {
+          return false;
+        }
+      }
+      Some(EnginePacket::Upgrade) => return true,
+      _ => return false,
+    }
+  }
+  false
+}
+
+async fn run_websocket(
+  io: Arc<Inner>,
+  mut ws: WebSocketStream<TokioIo<Upgraded>>,
+  engine: Option<Arc<EngineSession>>,
+  headers: HeaderMap,
+) {
+  let engine = if let Some(engine) = engine {
+    let probed = tokio::time::timeout(io.ping_timeout, probe(&mut ws))
+      .await
+      .unwrap_or(false);
+    if !probed {
+      return;
+    }
+    engine.upgraded.store(true, Ordering::SeqCst);
+    engine.send(EnginePacket::Noop);
+    engine
+  } else {
+    let (engine, open) = open_session(&io, headers, false);
+    if ws
+      .send(Message::text(EnginePacket::Open(open).encode()))
+      .await
+      .is_err()
+    {
+      io.close_engine(&engine);
+      return;
+    }
+    engine
+  };
+
+  let (mut sink, mut stream) = ws.split();
+  let mut inbox = engine.inbox.lock().await;
+  loop {
+    tokio::select! {
+      out = inbox.recv() => {
+        let Some(out) = out else { break };
+        let close = out == EnginePacket::Close;
+        if sink.send(Message::text(out.encode())).await.is_err() || close {
+          break;
+        }
+      }
+      msg = stream.next() => match msg {
+        Some(Ok(Message::Text(raw))) => {
+          if let Some(packet) = EnginePacket::decode(raw.as_str()) {
+            on_packet(&io, &engine, packet);
+          }
+        }
+        Some(Ok(Message::Binary(_))) => {
+          tracing::debug!(sid = %engine.sid, "dropping binary socket.io frame");
+        }
+        Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+        Some(Ok(_)) => {}
+      },
+      () = engine.closed.cancelled() => break,
+    }
+  }
+  drop(inbox);
+  io.close_engine(&engine);
+  let _ = sink.close().await;
+}
+
+fn text(body: String) -> Response {
+  let mut res = Response::new(TakoBody::from(body));
+  res.headers_mut().insert(
+    header::CONTENT_TYPE,
+    HeaderValue::from_static("text/plain; charset=UTF-8"),
+  );
+  res
+}
+
+/// Engine.IO error body, as returned by the reference server.
+fn error(code: u8, message: &str) -> Response {
+  let body = serde_json::json!({ "code": code, "message": message }).to_string();
+  let mut res = Response::new(TakoBody::from(body));
+  *res.status_mut() = StatusCode::BAD_REQUEST;
+  res.headers_mut().insert(
+    header::CONTENT_TYPE,
+    HeaderValue::from_static("application/json"),
+  );
+  res
+}
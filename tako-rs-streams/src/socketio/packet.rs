@@ -0,0 +1,229 @@
+//! Engine.IO v4 and Socket.IO v5 packet framing.
+
+use serde_json::Value;
+
+/// Separates packets in a long-polling payload.
+pub(crate) const RECORD_SEPARATOR: char = '\x1e';
+
+/// One Engine.IO packet (text only).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum EnginePacket {
+  /// Handshake JSON sent by the server.
+  Open(String),
+  Close,
+  Ping(String),
+  Pong(String),
+  /// A Socket.IO packet.
+  Message(String),
+  Upgrade,
+  Noop,
+}
+
+impl EnginePacket {
+  pub(crate) fn encode(&self) -> String {
+    match self {
+      Self::Open(data) => format!("0{data}"),
+      Self::Close => "1".to_string(),
+      Self::Ping(data) => format!("2{data}"),
+      Self::Pong(data) => format!("3{data}"),
+      Self::Message(data) => format!("4{data}"),
+      Self::Upgrade => "5".to_string(),
+      Self::Noop => "6".to_string(),
+    }
+  }
+
+  pub(crate) fn decode(raw: &str) -> Option<Self> {
+    let kind = raw.chars().next()?;
+    let data = &raw[kind.len_utf8()..];
+    Some(match kind {
+      '0' => Self::Open(data.to_string()),
+      '1' => Self::Close,
+      '2' => Self::Ping(data.to_string()),
+      '3' => Self::Pong(data.to_string()),
+      '4' => Self::Message(data.to_string()),
+      '5' => Self::Upgrade,
+      '6' => Self::Noop,
+      _ => return None,
+    })
+  }
+}
+
+/// Encodes packets into a long-polling payload.
+pub(crate) fn encode_payload(packets: &[EnginePacket]) -> String {
+  let mut out = String::new();
+  for (i, packet) in packets.iter().enumerate() {
+    if i > 0 {
+      out.push(RECORD_SEPARATOR);
+    }
+    out.push_str(&packet.encode());
+  }
+  out
+}
+
+/// Socket.IO packet type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PacketKind {
+  Connect,
+  Disconnect,
+  Event,
+  Ack,
+  ConnectError,
+}
+
+impl PacketKind {
+  fn code(self) -> u8 {
+    match self {
+      Self::Connect => 0,
+      Self::Disconnect => 1,
+      Self::Event => 2,
+      Self::Ack => 3,
+      Self::ConnectError => 4,
+    }
+  }
+}
+
+/// One Socket.IO packet:
+/// `<type>[<namespace>,][<ack id>][<JSON data>]`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Packet {
+  pub kind: PacketKind,
+  pub namespace: String,
+  pub id: Option<u64>,
+  pub data: Option<Value>,
+}
+
+impl Packet {
+  pub(crate) fn new(kind: PacketKind, namespace: &str, data: Option<Value>) -> Self {
+    Self {
+      kind,
+      namespace: namespace.to_string(),
+      id: None,
+      data,
+    }
+  }
+
+  pub(crate) fn with_id(mut self, id: u64) -> Self {
+    self.id = Some(id);
+    self
+  }
+
+  pub(crate) fn encode(&self) -> String {
+    let mut out = self.kind.code().to_string();
+    if self.namespace != "/" {
+      out.push_str(&self.namespace);
+      out.push(',');
+    }
+    if let Some(id) = self.id {
+      out.push_str(&id.to_string());
+    }
+    if let Some(data) = &self.data {
+      out.push_str(&data.to_string());
+    }
+    out
+  }
+
+  /// Parses a packet. Binary packets (types 5 and 6) are rejected: their
+  /// attachments travel as separate binary frames, which this adapter does
+  /// not reassemble.
+  pub(crate) fn decode(raw: &str) -> Result<Self, String> {
+    let kind = match raw.as_bytes().first() {
+      Some(b'0') => PacketKind::Connect,
+      Some(b'1') => PacketKind::Disconnect,
+      Some(b'2') => PacketKind::Event,
+      Some(b'3') => PacketKind::Ack,
+      Some(b'4') => PacketKind::ConnectError,
+      Some(b'5' | b'6') => return Err("binary packets are not supported".to_string()),
+      _ => return Err(format!("unknown packet type in {raw:?}")),
+    };
+    let mut rest = &raw[1..];
+
+    let mut namespace = "/";
+    if rest.starts_with('/') {
+      if let Some(i) = rest.find(',') {
+        namespace = &rest[..i];
+        rest = &rest[i + 1..];
+      } else {
+        namespace = rest;
+        rest = "";
+      }
+    }
+
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let id = if digits > 0 {
+      Some(
+        rest[..digits]
+          .parse()
+          .map_err(|_| format!("invalid ack id in {raw:?}"))?,
+      )
+    } else {
+      None
+    };
+    rest = &rest[digits..];
+
+    let data = if rest.is_empty() {
+      None
+    } else {
+      Some(serde_json::from_str(rest).map_err(|e| format!("invalid packet data: {e}"))?)
+    };
+
+    Ok(Self {
+      kind,
+      namespace: namespace.to_string(),
+      id,
+      data,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn round_trips_socket_io_packets() {
+    let cases = [
+      ("0", Packet::new(PacketKind::Connect, "/", None)),
+      (
+        "0/admin,{\"token\":\"x\"}",
+        Packet::new(PacketKind::Connect, "/admin", Some(json!({"token": "x"}))),
+      ),
+      (
+        "2[\"chat\",\"hi\"]",
+        Packet::new(PacketKind::Event, "/", Some(json!(["chat", "hi"]))),
+      ),
+      (
+        "3/admin,12[\"ok\"]",
+        Packet::new(PacketKind::Ack, "/admin", Some(json!(["ok"]))).with_id(12),
+      ),
+      (
+        "1/admin,",
+        Packet::new(PacketKind::Disconnect, "/admin", None),
+      ),
+    ];
+    for (raw, packet) in cases {
+      assert_eq!(Packet::decode(raw).unwrap(), packet, "{raw}");
+      assert_eq!(Packet::decode(&packet.encode()).unwrap(), packet);
+    }
+    assert!(Packet::decode("51-[\"file\",{\"_placeholder\":true,\"num\":0}]").is_err());
+  }
+
+  #[test]
+  fn frames_polling_payloads() {
+    let payload = encode_payload(&[
+      EnginePacket::Message("2[\"a\"]".to_string()),
+      EnginePacket::Ping(String::new()),
+    ]);
+    assert_eq!(payload, "42[\"a\"]\x1e2");
+    let packets: Vec<_> = payload
+      .split(RECORD_SEPARATOR)
+      .filter_map(EnginePacket::decode)
+      .collect();
+    assert_eq!(packets[1], EnginePacket::Ping(String::new()));
+    assert_eq!(
+      EnginePacket::decode("3probe"),
+      Some(EnginePacket::Pong("probe".to_string()))
+    );
+  }
+}
@@ -2,11 +2,15 @@
 //! [EventSource](https://html.spec.whatwg.org/multipage/server-sent-events.html)
 //! specification.
 //!
-//! `Sse::new(stream)` keeps the original raw-bytes path (legacy: each `Bytes`
-//! is wrapped as `data: …\n\n`). `Sse::events(stream)` is the v2 structured
-//! API: each item is an [`SseEvent`](crate::sse::SseEvent) with `event:`, `id:`, `retry:`, and/or
-//! comment fields. A configurable [`Sse::keep_alive`](crate::sse::Sse::keep_alive) periodically interleaves
+//! `Sse::new(stream)` accepts a stream of [`Event`](crate::sse::Event)s built
+//! with chained setters (`event:`, `id:`, `retry:`, JSON `data:`, comments).
+//! It also keeps the original raw-bytes path (legacy: each `Bytes` is wrapped
+//! as `data: …\n\n`). `Sse::events(stream)` is the v2 structured API over
+//! [`SseEvent`](crate::sse::SseEvent). A configurable
+//! [`Sse::keep_alive`](crate::sse::Sse::keep_alive) periodically interleaves
 //! comment frames so reverse proxies do not idle-close the connection.
+//! With the `sse-compression` feature, [`EventCompression`] gzips or
+//! deflates large `data:` payloads for clients that opt in.
 //!
 //! Additional defaults:
 //! - `Cache-Control: no-cache, no-store, must-revalidate`
@@ -17,7 +21,7 @@
 //!
 //! ```rust,ignore
 //! use std::time::Duration;
-//! use tako::sse::{Sse, SseEvent};
+//! use tako::sse::{Event, Sse, SseEvent};
 //! use tokio_stream::StreamExt as _;
 //! use futures_util::stream;
 //!
@@ -28,13 +32,33 @@
 //! ]);
 //!
 //! Sse::events(events).keep_alive(Duration::from_secs(15));
+//!
+//! // Chained builder with JSON payloads
+//! let update = Event::default()
+//!   .event("update")
+//!   .id("42")
+//!   .retry(5000)
+//!   .json_data(&serde_json::json!({ "count": 3 }))?;
+//! Sse::new(stream::iter([update])).keep_alive(Duration::from_secs(15));
 //! ```
 
+#[cfg(feature = "sse-compression")]
+mod compress;
 mod event;
 mod stream;
 
+#[cfg(feature = "sse-compression")]
+pub use compress::ACCEPT_EVENT_ENCODING;
+#[cfg(feature = "sse-compression")]
+pub use compress::EVENT_ENCODING_PARAM;
+#[cfg(feature = "sse-compression")]
+pub use compress::EventCompression;
+#[cfg(feature = "sse-compression")]
+pub use compress::EventEncoding;
+pub use event::Event;
 pub use event::SseEvent;
 pub use stream::Sse;
 pub use stream::SseEvents;
+pub use stream::SseFrame;
 pub use stream::last_event_id;
 pub use stream::last_event_id_bytes;
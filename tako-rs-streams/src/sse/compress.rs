@@ -0,0 +1,159 @@
+use std::io::Write as _;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use flate2::write::ZlibEncoder;
+use tako_rs_core::types::Request;
+
+use super::SseEvent;
+
+/// Request header listing the event encodings a client can decode, most
+/// preferred first, e.g. `Accept-Event-Encoding: gzip, deflate`.
+pub const ACCEPT_EVENT_ENCODING: &str = "accept-event-encoding";
+
+/// Query parameter with the same meaning as [`ACCEPT_EVENT_ENCODING`], for
+/// `EventSource`, which cannot send custom headers.
+pub const EVENT_ENCODING_PARAM: &str = "event_encoding";
+
+/// Compression applied to an event's `data:` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventEncoding {
+  /// gzip (RFC 1952).
+  Gzip,
+  /// zlib-wrapped deflate (RFC 1950), what `DecompressionStream("deflate")`
+  /// expects.
+  Deflate,
+}
+
+impl EventEncoding {
+  /// The token naming the encoding on the wire.
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Gzip => "gzip",
+      Self::Deflate => "deflate",
+    }
+  }
+
+  fn parse(token: &str) -> Option<Self> {
+    match token.trim() {
+      t if t.eq_ignore_ascii_case("gzip") => Some(Self::Gzip),
+      t if t.eq_ignore_ascii_case("deflate") => Some(Self::Deflate),
+      _ => None,
+    }
+  }
+
+  fn compress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+    match self {
+      Self::Gzip => {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        encoder.finish()
+      }
+      Self::Deflate => {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        encoder.finish()
+      }
+    }
+  }
+}
+
+/// Per-event compression of large `data:` payloads.
+///
+/// SSE responses are usually left alone by compression middleware, which
+/// would have to buffer them; large JSON events then cost their full size.
+/// With compression, every event whose data is at least the threshold is
+/// sent as
+///
+/// ```text
+/// data: gzip;base64,H4sIAAAAAAAA...
+/// ```
+///
+/// — the compressed payload, base64-encoded to stay valid SSE text. Smaller
+/// events, comments and other fields are untouched, and an event is only
+/// rewritten when the result is actually shorter.
+///
+/// Clients opt in, so no event reaches a client that cannot decode it: the
+/// handler calls [`EventCompression::negotiate`], which honours the
+/// [`ACCEPT_EVENT_ENCODING`] header or the [`EVENT_ENCODING_PARAM`] query
+/// parameter. In the browser, decode with `DecompressionStream`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use tako::sse::{EventCompression, Sse};
+///
+/// async fn feed(req: Request) -> impl Responder {
+///     // new EventSource("/feed?event_encoding=gzip")
+///     Sse::new(updates()).compress(EventCompression::negotiate(&req))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct EventCompression {
+  encoding: EventEncoding,
+  threshold: usize,
+}
+
+impl EventCompression {
+  /// Compresses with `encoding` regardless of what the client sent.
+  pub fn new(encoding: EventEncoding) -> Self {
+    Self {
+      encoding,
+      threshold: 1024,
+    }
+  }
+
+  /// The first encoding the client accepts, or `None` when it did not opt
+  /// in. The [`ACCEPT_EVENT_ENCODING`] header wins over the query parameter.
+  pub fn negotiate(req: &Request) -> Option<Self> {
+    let offered = req
+      .headers()
+      .get(ACCEPT_EVENT_ENCODING)
+      .and_then(|v| v.to_str().ok())
+      .map(str::to_string)
+      .or_else(|| {
+        url::form_urlencoded::parse(req.uri().query()?.as_bytes())
+          .find(|(name, _)| name == EVENT_ENCODING_PARAM)
+          .map(|(_, value)| value.into_owned())
+      })?;
+    offered
+      .split(',')
+      .find_map(EventEncoding::parse)
+      .map(Self::new)
+  }
+
+  /// Minimum data size in bytes worth compressing (default 1 KiB).
+  pub fn threshold(mut self, bytes: usize) -> Self {
+    self.threshold = bytes;
+    self
+  }
+
+  /// The negotiated encoding.
+  pub fn encoding(&self) -> EventEncoding {
+    self.encoding
+  }
+
+  /// Compresses `event`'s data when it is large enough to benefit.
+  pub fn apply(&self, mut event: SseEvent) -> SseEvent {
+    let Some(data) = event.data.as_deref() else {
+      return event;
+    };
+    if data.len() < self.threshold {
+      return event;
+    }
+    let Ok(compressed) = self.encoding.compress(data.as_bytes()) else {
+      return event;
+    };
+    let encoded = format!(
+      "{};base64,{}",
+      self.encoding.as_str(),
+      STANDARD.encode(compressed)
+    );
+    if encoded.len() < data.len() {
+      event.data = Some(encoded);
+    }
+    event
+  }
+}
@@ -98,6 +98,80 @@ impl SseEvent {
   }
 }
 
+/// Chainable SSE event builder.
+///
+/// Every setter takes and returns the event, so an event reads as one
+/// expression. Encodes exactly like [`SseEvent`], including the CR/LF
+/// sanitizing of single-line fields; pass a stream of `Event`s to
+/// [`Sse::new`](crate::sse::Sse::new).
+///
+/// ```rust,ignore
+/// use tako::sse::Event;
+///
+/// let ev = Event::default()
+///   .event("update")
+///   .id("42")
+///   .retry(5000)
+///   .json_data(&serde_json::json!({ "count": 3 }))?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Event(SseEvent);
+
+impl Event {
+  /// Set the `data:` payload; multi-line strings become multiple fields.
+  pub fn data(mut self, d: impl Into<String>) -> Self {
+    self.0.data = Some(d.into());
+    self
+  }
+
+  /// Set the `data:` payload to `value` serialized as JSON.
+  pub fn json_data<T: serde::Serialize + ?Sized>(mut self, value: &T) -> serde_json::Result<Self> {
+    self.0.data = Some(serde_json::to_string(value)?);
+    Ok(self)
+  }
+
+  /// Set the `event:` field.
+  pub fn event(mut self, e: impl Into<String>) -> Self {
+    self.0.event = Some(e.into());
+    self
+  }
+
+  /// Set the `id:` field.
+  pub fn id(mut self, i: impl Into<String>) -> Self {
+    self.0.id = Some(i.into());
+    self
+  }
+
+  /// Set the `retry:` reconnection hint, in milliseconds.
+  pub fn retry(mut self, ms: u64) -> Self {
+    self.0.retry_ms = Some(ms);
+    self
+  }
+
+  /// Set a `:` comment, ignored by `EventSource` handlers.
+  pub fn comment(mut self, c: impl Into<String>) -> Self {
+    self.0.comment = Some(c.into());
+    self
+  }
+
+  /// Encode as a single SSE wire frame.
+  pub fn encode(&self) -> Bytes {
+    self.0.encode()
+  }
+}
+
+impl From<Event> for SseEvent {
+  fn from(event: Event) -> Self {
+    event.0
+  }
+}
+
+impl From<SseEvent> for Event {
+  fn from(event: SseEvent) -> Self {
+    Self(event)
+  }
+}
+
 /// Replace SSE-control characters with a space so single-line fields cannot
 /// smuggle extra `event:` / `id:` lines.
 fn sanitize_single_line(s: &str) -> String {
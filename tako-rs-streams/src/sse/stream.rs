@@ -16,7 +16,11 @@ use tako_rs_core::body::TakoBody;
 use tako_rs_core::responder::Responder;
 use tako_rs_core::types::Response;
 
+use super::Event;
 use super::SseEvent;
+#[cfg(feature = "sse-compression")]
+use super::compress::EventCompression;
+use crate::session_expiry::SessionExpiry;
 
 const PREFIX: &[u8] = b"data: ";
 const SUFFIX: &[u8] = b"\n\n";
@@ -29,30 +33,99 @@ const KEEPALIVE_FRAME: &[u8] = b":keepalive\n\n";
 pub struct Sse<S> {
   pub(crate) stream: S,
   pub(crate) keepalive: Option<Duration>,
+  pub(crate) session_expiry: Option<SessionExpiry>,
+  #[cfg(feature = "sse-compression")]
+  pub(crate) compression: Option<EventCompression>,
+}
+
+/// An item [`Sse::new`] can stream.
+///
+/// Raw [`Bytes`] keep the legacy `data: …\n\n` wrapping; [`Event`] and
+/// [`SseEvent`] are encoded with all their fields.
+pub trait SseFrame {
+  /// Encodes the item as one SSE wire frame.
+  fn into_frame(self) -> Bytes;
+
+  /// Encodes the item with its data compressed per `compression`. Raw
+  /// frames are already encoded and pass through unchanged.
+  #[cfg(feature = "sse-compression")]
+  fn into_compressed_frame(self, _compression: &EventCompression) -> Bytes
+  where
+    Self: Sized,
+  {
+    self.into_frame()
+  }
+}
+
+impl SseFrame for Bytes {
+  fn into_frame(self) -> Bytes {
+    let mut buf = BytesMut::with_capacity(PS_LEN + self.len());
+    buf.extend_from_slice(PREFIX);
+    buf.extend_from_slice(&self);
+    buf.extend_from_slice(SUFFIX);
+    buf.freeze()
+  }
+}
+
+impl SseFrame for SseEvent {
+  fn into_frame(self) -> Bytes {
+    self.encode()
+  }
+
+  #[cfg(feature = "sse-compression")]
+  fn into_compressed_frame(self, compression: &EventCompression) -> Bytes {
+    compression.apply(self).encode()
+  }
+}
+
+impl SseFrame for Event {
+  fn into_frame(self) -> Bytes {
+    self.encode()
+  }
+
+  #[cfg(feature = "sse-compression")]
+  fn into_compressed_frame(self, compression: &EventCompression) -> Bytes {
+    SseEvent::from(self).into_compressed_frame(compression)
+  }
+}
+
+fn encode_frame<T: SseFrame>(
+  item: T,
+  #[cfg(feature = "sse-compression")] compression: Option<&EventCompression>,
+) -> Bytes {
+  #[cfg(feature = "sse-compression")]
+  if let Some(compression) = compression {
+    return item.into_compressed_frame(compression);
+  }
+  item.into_frame()
 }
 
 impl<S> Sse<S>
 where
-  S: Stream<Item = Bytes> + Send + 'static,
+  S: Stream + Send + 'static,
+  S::Item: SseFrame,
 {
-  /// Legacy constructor — wraps each `Bytes` as `data: …\n\n` (W3C minimum).
+  /// Builds an SSE responder from a stream of [`Event`]s (or [`SseEvent`]s,
+  /// or raw [`Bytes`]).
+  ///
+  /// Raw `Bytes` use the legacy wrapping — each item becomes
+  /// `data: …\n\n` (W3C minimum).
   ///
-  /// **⚠️ Security note (STR-5):** this raw-bytes wrapper does NOT sanitize
+  /// **⚠️ Security note (STR-5):** the raw-bytes wrapper does NOT sanitize
   /// embedded `\n` / `\r` / `\r\n` sequences. A message containing
   /// `\n\nevent:click\n\n` is interpreted by the browser as **two separate
   /// SSE events** — a synthetic event/field injection if the message comes
-  /// from untrusted input. The structured [`Sse::events`] path is safe
-  /// (every line is rebuilt with strict `data:`/`event:` prefixes and CR is
-  /// stripped). Use `Sse::events` for any message that could carry
-  /// caller-controlled bytes; reserve `Sse::new` for already-encoded raw
-  /// SSE chunks the caller produced.
-  ///
-  /// For richer events (`event:`, `id:`, `retry:`, comments) use
-  /// [`Sse::events`] which accepts a stream of [`SseEvent`].
+  /// from untrusted input. Events are safe (every line is rebuilt with
+  /// strict `data:`/`event:` prefixes and CR is stripped). Stream `Event`s
+  /// for any message that could carry caller-controlled bytes; reserve raw
+  /// `Bytes` for already-encoded SSE chunks the caller produced.
   pub fn new(stream: S) -> Self {
     Self {
       stream,
       keepalive: None,
+      session_expiry: None,
+      #[cfg(feature = "sse-compression")]
+      compression: None,
     }
   }
 }
@@ -63,20 +136,43 @@ impl<S> Sse<S> {
     self.keepalive = Some(period);
     self
   }
+
+  /// End the stream once `expiry` fires, so the client's reconnect has to
+  /// authenticate again.
+  pub fn session_expiry(mut self, expiry: SessionExpiry) -> Self {
+    self.session_expiry = Some(expiry);
+    self
+  }
+
+  /// Compress large event payloads, typically with the result of
+  /// [`EventCompression::negotiate`]; `None` leaves events as they are.
+  #[cfg(feature = "sse-compression")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "sse-compression")))]
+  pub fn compress(mut self, compression: Option<EventCompression>) -> Self {
+    self.compression = compression;
+    self
+  }
 }
 
 impl<S> Responder for Sse<S>
 where
-  S: Stream<Item = Bytes> + Send + 'static,
+  S: Stream + Send + 'static,
+  S::Item: SseFrame,
 {
   fn into_response(self) -> Response {
-    let mapped = self.stream.map(|msg| {
-      let mut buf = BytesMut::with_capacity(PS_LEN + msg.len());
-      buf.extend_from_slice(PREFIX);
-      buf.extend_from_slice(&msg);
-      buf.extend_from_slice(SUFFIX);
-      Ok::<_, Infallible>(http_body::Frame::data(Bytes::from(buf)))
-    });
+    #[cfg(feature = "sse-compression")]
+    let compression = self.compression;
+    let mapped = self
+      .stream
+      .map(move |item| {
+        let frame = encode_frame(
+          item,
+          #[cfg(feature = "sse-compression")]
+          compression.as_ref(),
+        );
+        Ok::<_, Infallible>(http_body::Frame::data(frame))
+      })
+      .take_until(until_expired(self.session_expiry));
 
     let body = if let Some(period) = self.keepalive {
       let stream = KeepAliveStream::new(mapped, period, Bytes::from_static(KEEPALIVE_FRAME));
@@ -93,6 +189,9 @@ where
 pub struct SseEvents<S> {
   stream: S,
   keepalive: Option<Duration>,
+  session_expiry: Option<SessionExpiry>,
+  #[cfg(feature = "sse-compression")]
+  compression: Option<EventCompression>,
 }
 
 impl<S> Sse<S> {
@@ -104,6 +203,9 @@ impl<S> Sse<S> {
     SseEvents {
       stream,
       keepalive: None,
+      session_expiry: None,
+      #[cfg(feature = "sse-compression")]
+      compression: None,
     }
   }
 }
@@ -114,6 +216,20 @@ impl<S> SseEvents<S> {
     self.keepalive = Some(period);
     self
   }
+
+  /// End the stream once `expiry` fires. See [`Sse::session_expiry`].
+  pub fn session_expiry(mut self, expiry: SessionExpiry) -> Self {
+    self.session_expiry = Some(expiry);
+    self
+  }
+
+  /// Compress large event payloads. See [`Sse::compress`].
+  #[cfg(feature = "sse-compression")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "sse-compression")))]
+  pub fn compress(mut self, compression: Option<EventCompression>) -> Self {
+    self.compression = compression;
+    self
+  }
 }
 
 impl<S> Responder for SseEvents<S>
@@ -121,9 +237,19 @@ where
   S: Stream<Item = SseEvent> + Send + 'static,
 {
   fn into_response(self) -> Response {
+    #[cfg(feature = "sse-compression")]
+    let compression = self.compression;
     let mapped = self
       .stream
-      .map(|ev| Ok::<_, Infallible>(http_body::Frame::data(ev.encode())));
+      .map(move |ev| {
+        let frame = encode_frame(
+          ev,
+          #[cfg(feature = "sse-compression")]
+          compression.as_ref(),
+        );
+        Ok::<_, Infallible>(http_body::Frame::data(frame))
+      })
+      .take_until(until_expired(self.session_expiry));
 
     let body = if let Some(period) = self.keepalive {
       let stream = KeepAliveStream::new(mapped, period, Bytes::from_static(KEEPALIVE_FRAME));
@@ -136,6 +262,13 @@ where
   }
 }
 
+async fn until_expired(expiry: Option<SessionExpiry>) {
+  match expiry {
+    Some(e) => e.expired().await,
+    None => std::future::pending().await,
+  }
+}
+
 fn build_sse_response(body: TakoBody) -> Response {
   http::Response::builder()
     .status(StatusCode::OK)
@@ -5,9 +5,13 @@
 //! and a canonicalize + prefix-check guard against path traversal.
 //!
 //! `ServeFile` serves a single file.
+//!
+//! `AssetManifest` hashes a static directory for Subresource Integrity so
+//! templates can emit `integrity=` attributes, and serves the hashes as JSON.
 
 mod dir;
 mod file;
+mod manifest;
 mod serve;
 
 pub use dir::PrecompressedPolicy;
@@ -15,3 +19,7 @@ pub use dir::ServeDir;
 pub use dir::ServeDirBuilder;
 pub use file::ServeFile;
 pub use file::ServeFileBuilder;
+pub use manifest::Asset;
+pub use manifest::AssetManifest;
+pub use manifest::AssetManifestBuilder;
+pub use manifest::SriAlgorithm;
@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use http::StatusCode;
+use http::header;
+use sha2::Digest;
+use sha2::Sha256;
+use sha2::Sha384;
+use sha2::Sha512;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::responder::Responder;
+use tako_rs_core::types::Request;
+
+/// Hash function used for `integrity` values.
+///
+/// The W3C Subresource Integrity spec allows SHA-256, SHA-384 and SHA-512;
+/// SHA-384 is the conventional default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SriAlgorithm {
+  /// `sha256-…`
+  Sha256,
+  /// `sha384-…`
+  #[default]
+  Sha384,
+  /// `sha512-…`
+  Sha512,
+}
+
+impl SriAlgorithm {
+  /// Computes the `integrity` attribute value for `bytes`.
+  pub fn integrity(self, bytes: &[u8]) -> String {
+    let (prefix, digest) = match self {
+      Self::Sha256 => ("sha256", Sha256::digest(bytes).to_vec()),
+      Self::Sha384 => ("sha384", Sha384::digest(bytes).to_vec()),
+      Self::Sha512 => ("sha512", Sha512::digest(bytes).to_vec()),
+    };
+    format!("{prefix}-{}", STANDARD.encode(digest))
+  }
+}
+
+/// One fingerprinted file in an [`AssetManifest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Asset {
+  /// Public URL the asset is served from (`url_prefix` + relative path).
+  pub url: String,
+  /// SRI value, e.g. `sha384-oqVuAfXRKap7fdgcCY5uykM6+R9GqQ8K/uxy9rx7HNQlGYl1kPzQho1wx4JwY8wC`.
+  pub integrity: String,
+  /// File size in bytes.
+  pub size: u64,
+}
+
+/// Subresource Integrity hashes for every file under a static directory.
+///
+/// Built once at startup by walking the directory that a
+/// [`ServeDir`](super::ServeDir) serves. Keys are paths relative to that
+/// directory with `/` separators (`js/app.js`). Precompressed `.br` / `.gz`
+/// sidecars are skipped when their identity file exists, since browsers
+/// verify the decoded bytes. Symlinks are not followed.
+///
+/// The manifest is cheap to clone, so it can live in router state and be
+/// handed to templates, which use [`AssetManifest::script_tag`],
+/// [`AssetManifest::stylesheet_tag`] or [`AssetManifest::integrity`] to emit
+/// `integrity=` attributes. [`AssetManifest::handle`] serves it as JSON for
+/// client-side bundlers and deploy checks.
+#[derive(Debug, Clone)]
+pub struct AssetManifest {
+  inner: Arc<ManifestInner>,
+}
+
+#[derive(Debug)]
+struct ManifestInner {
+  algorithm: SriAlgorithm,
+  assets: BTreeMap<String, Asset>,
+}
+
+/// Builder for configuring an `AssetManifest`.
+#[must_use]
+pub struct AssetManifestBuilder {
+  base_dir: PathBuf,
+  url_prefix: String,
+  algorithm: SriAlgorithm,
+}
+
+impl AssetManifestBuilder {
+  /// Creates a new builder hashing files under `base_dir`.
+  #[inline]
+  pub fn new<P: Into<PathBuf>>(base_dir: P) -> Self {
+    Self {
+      base_dir: base_dir.into(),
+      url_prefix: "/".into(),
+      algorithm: SriAlgorithm::default(),
+    }
+  }
+
+  /// URL prefix the directory is mounted under (defaults to `/`).
+  #[inline]
+  pub fn url_prefix(mut self, prefix: impl Into<String>) -> Self {
+    self.url_prefix = prefix.into();
+    self
+  }
+
+  /// Hash function for `integrity` values (defaults to SHA-384).
+  #[inline]
+  pub fn algorithm(mut self, algorithm: SriAlgorithm) -> Self {
+    self.algorithm = algorithm;
+    self
+  }
+
+  /// Walks the directory and hashes every regular file.
+  pub fn build(self) -> io::Result<AssetManifest> {
+    let mut files = Vec::new();
+    collect_files(&self.base_dir, &mut files)?;
+
+    let prefix = self.url_prefix.trim_end_matches('/');
+    let mut assets = BTreeMap::new();
+    for path in files {
+      if is_redundant_sidecar(&path) {
+        continue;
+      }
+      let Ok(rel) = path.strip_prefix(&self.base_dir) else {
+        continue;
+      };
+      let key = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+      let bytes = std::fs::read(&path)?;
+      assets.insert(
+        key.clone(),
+        Asset {
+          url: format!("{prefix}/{key}"),
+          integrity: self.algorithm.integrity(&bytes),
+          size: bytes.len() as u64,
+        },
+      );
+    }
+
+    Ok(AssetManifest {
+      inner: Arc::new(ManifestInner {
+        algorithm: self.algorithm,
+        assets,
+      }),
+    })
+  }
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+  for entry in std::fs::read_dir(dir)? {
+    let entry = entry?;
+    let file_type = entry.file_type()?;
+    if file_type.is_dir() {
+      collect_files(&entry.path(), out)?;
+    } else if file_type.is_file() {
+      out.push(entry.path());
+    }
+  }
+  Ok(())
+}
+
+fn is_redundant_sidecar(path: &Path) -> bool {
+  matches!(path.extension().and_then(|e| e.to_str()), Some("br" | "gz"))
+    && path.with_extension("").is_file()
+}
+
+fn escape_attr(value: &str) -> String {
+  let mut out = String::with_capacity(value.len());
+  for c in value.chars() {
+    match c {
+      '&' => out.push_str("&amp;"),
+      '"' => out.push_str("&quot;"),
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+impl AssetManifest {
+  /// Creates a new builder for configuring an `AssetManifest`.
+  pub fn builder<P: Into<PathBuf>>(base_dir: P) -> AssetManifestBuilder {
+    AssetManifestBuilder::new(base_dir)
+  }
+
+  /// Hash function the manifest was built with.
+  pub fn algorithm(&self) -> SriAlgorithm {
+    self.inner.algorithm
+  }
+
+  /// Looks up an asset by its relative path (a leading `/` is ignored).
+  pub fn get(&self, path: &str) -> Option<&Asset> {
+    self.inner.assets.get(path.trim_start_matches('/'))
+  }
+
+  /// SRI value for `path`, if the asset exists.
+  pub fn integrity(&self, path: &str) -> Option<&str> {
+    self.get(path).map(|a| a.integrity.as_str())
+  }
+
+  /// Iterates assets in path order.
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &Asset)> {
+    self.inner.assets.iter().map(|(k, v)| (k.as_str(), v))
+  }
+
+  /// Number of assets in the manifest.
+  pub fn len(&self) -> usize {
+    self.inner.assets.len()
+  }
+
+  /// Returns `true` when the directory held no files.
+  pub fn is_empty(&self) -> bool {
+    self.inner.assets.is_empty()
+  }
+
+  /// `<script>` tag with `src`, `integrity` and `crossorigin` set.
+  pub fn script_tag(&self, path: &str) -> Option<String> {
+    let asset = self.get(path)?;
+    Some(format!(
+      r#"<script src="{}" integrity="{}" crossorigin="anonymous"></script>"#,
+      escape_attr(&asset.url),
+      asset.integrity
+    ))
+  }
+
+  /// `<link rel="stylesheet">` tag with `href`, `integrity` and `crossorigin` set.
+  pub fn stylesheet_tag(&self, path: &str) -> Option<String> {
+    let asset = self.get(path)?;
+    Some(format!(
+      r#"<link rel="stylesheet" href="{}" integrity="{}" crossorigin="anonymous">"#,
+      escape_attr(&asset.url),
+      asset.integrity
+    ))
+  }
+
+  /// Manifest as a JSON object keyed by relative path.
+  pub fn to_json(&self) -> serde_json::Value {
+    let map = self
+      .inner
+      .assets
+      .iter()
+      .map(|(k, a)| {
+        (
+          k.clone(),
+          serde_json::json!({
+            "url": a.url,
+            "integrity": a.integrity,
+            "size": a.size,
+          }),
+        )
+      })
+      .collect::<serde_json::Map<_, _>>();
+    serde_json::Value::Object(map)
+  }
+
+  /// Handles an HTTP request by returning the manifest as JSON.
+  ///
+  /// Mount this on a single route such as `/assets-manifest.json`.
+  pub async fn handle(&self, _req: Request) -> impl Responder {
+    http::Response::builder()
+      .status(StatusCode::OK)
+      .header(header::CONTENT_TYPE, "application/json")
+      .header(header::CACHE_CONTROL, "no-cache")
+      .body(TakoBody::from(self.to_json().to_string()))
+      .unwrap()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::AssetManifest;
+  use super::SriAlgorithm;
+
+  #[test]
+  fn sri_matches_known_vector() {
+    // `echo -n "alert('Hello, world.');" | openssl dgst -sha384 -binary | base64`
+    assert_eq!(
+      SriAlgorithm::Sha384.integrity(b"alert('Hello, world.');"),
+      "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO"
+    );
+  }
+
+  #[test]
+  fn manifest_skips_sidecars_and_renders_tags() {
+    let root = std::env::temp_dir().join(format!("tako-sri-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("js")).unwrap();
+    std::fs::write(root.join("js/app.js"), "alert('Hello, world.');").unwrap();
+    std::fs::write(root.join("js/app.js.br"), "compressed").unwrap();
+    std::fs::write(root.join("site.css"), "body{}").unwrap();
+
+    let manifest = AssetManifest::builder(&root)
+      .url_prefix("/static/")
+      .build()
+      .unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(manifest.len(), 2);
+    assert!(manifest.get("js/app.js.br").is_none());
+    assert_eq!(
+      manifest.script_tag("/js/app.js").unwrap(),
+      "<script src=\"/static/js/app.js\" integrity=\"sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO\" crossorigin=\"anonymous\"></script>"
+    );
+    let json = manifest.to_json();
+    assert_eq!(json["site.css"]["url"], "/static/site.css");
+    assert_eq!(json["site.css"]["size"], 6);
+  }
+}
@@ -11,7 +11,9 @@
 //! the WebTransport API; only QUIC peers that speak the same private framing
 //! can.
 //!
-//! The W3C-compliant CONNECT handshake is a follow-up roadmap item. For now:
+//! The W3C-compliant handshake lives in the HTTP/3 server instead:
+//! `tako::server_h3::webtransport` (set `ServerConfig::h3_webtransport`).
+//! Otherwise:
 //!
 //! - Use this module when you want a private QUIC tunnel between Tako-aware
 //!   peers (server-to-server, custom client).
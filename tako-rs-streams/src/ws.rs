@@ -8,17 +8,36 @@
 //! - origin allow-list (rejects mismatching `Origin` with `403`)
 //! - upgrade timeout (drops leaked tasks when the client never finishes the upgrade)
 //! - configurable initial `WebSocketConfig` (forwarded to tokio-tungstenite)
+//! - keep-alive (`ping_interval`, `pong_timeout`, `idle_timeout`)
 //!
-//! Application-level keep-alive (`ping_interval` / `pong_timeout`) is exposed
-//! as a [`WsKeepAlive`](crate::ws::WsKeepAlive) config value the handler can read; the framework
-//! itself does not run the ping loop because the handler owns the stream.
+//! Keep-alive is driven by the framework. Because the handler owns its
+//! stream, enabling any keep-alive option puts an in-process relay between
+//! the client socket and the handler: the relay forwards data frames both
+//! ways, sends pings on the configured interval, answers and swallows
+//! control frames, and closes the client connection with `1001 Going Away`
+//! once a deadline passes. The handler still receives a plain
+//! `WebSocketStream`; it sees the end of the stream when the relay gives up.
+//!
+//! Attaching a [`WsRegistry`] tracks the connection centrally so it can be
+//! enumerated and closed from outside the handler (see [`registry`]), and
+//! a [`SessionExpiry`] closes it once the session that opened it ends. Both
+//! run through the same relay.
+//!
+//! [`WsProxy`] forwards a connection to an upstream WebSocket server instead
+//! of handling it locally (see [`proxy`]).
 
+use std::convert::Infallible;
 use std::future::Future;
+use std::sync::Mutex;
 use std::time::Duration;
 
 use base64::Engine as _;
 use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
 use futures_util::FutureExt;
+use futures_util::SinkExt;
+use futures_util::StreamExt;
+use futures_util::future::BoxFuture;
 use http::HeaderValue;
 use http::StatusCode;
 use http::header;
@@ -27,19 +46,36 @@ use hyper_util::rt::TokioIo;
 use sha1::Digest;
 use sha1::Sha1;
 use tako_rs_core::body::TakoBody;
+use tako_rs_core::conn_info::ConnInfo;
 use tako_rs_core::responder::Responder;
 use tako_rs_core::types::Request;
 use tako_rs_core::types::Response;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
 use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
 use tokio_tungstenite::tungstenite::protocol::Role;
 use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+use crate::session_expiry::SessionExpiry;
+
+pub mod proxy;
+pub mod registry;
 
-/// Application-level keep-alive hints attached to the `TakoWs` builder.
+pub use proxy::WsProxy;
+pub use registry::WsConnectionId;
+pub use registry::WsConnectionInfo;
+pub use registry::WsRegistry;
+
+/// Server-driven keep-alive settings for the `TakoWs` builder.
 ///
-/// The framework does not drive these intervals itself — they're surfaced
-/// to the handler via request extensions so handlers can implement their
-/// own ping logic. For unconditional disconnection of an idle peer, prefer
-/// the `max_lifetime` cap on the builder.
+/// Pings go out every `ping_interval`; when `pong_timeout` is also set, a
+/// peer that sends nothing back within that window after a ping is
+/// disconnected. See the module docs for how the relay works.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct WsKeepAlive {
   /// Period between server-initiated pings; `None` disables.
@@ -48,6 +84,10 @@ pub struct WsKeepAlive {
   pub pong_timeout: Option<Duration>,
 }
 
+/// Buffer size of the in-process pipe between the keep-alive relay and the
+/// handler's stream.
+const RELAY_PIPE_CAPACITY: usize = 64 * 1024;
+
 /// WebSocket connection handler with upgrade protocol support.
 #[doc(alias = "websocket")]
 #[doc(alias = "ws")]
@@ -64,12 +104,18 @@ where
   allowed_origins: Option<Vec<String>>,
   upgrade_timeout: Option<Duration>,
   keep_alive: WsKeepAlive,
+  /// Close the connection when the peer sends nothing (data or control
+  /// frames) for this long.
+  idle_timeout: Option<Duration>,
   /// Hard cap on how long a single WebSocket conversation may live after a
   /// successful upgrade. When set, the handler future is wrapped in
   /// `tokio::time::timeout(max_lifetime, …)`; expiry drops the connection.
   /// Defends against slowloris-style holders that never send data after
   /// upgrade.
   max_lifetime: Option<Duration>,
+  registry: Option<WsRegistry>,
+  subject: Option<String>,
+  session_expiry: Option<SessionExpiry>,
 }
 
 impl<H, Fut> TakoWs<H, Fut>
@@ -88,7 +134,11 @@ where
       allowed_origins: None,
       upgrade_timeout: None,
       keep_alive: WsKeepAlive::default(),
+      idle_timeout: None,
       max_lifetime: None,
+      registry: None,
+      subject: None,
+      session_expiry: None,
     }
   }
 
@@ -137,12 +187,48 @@ where
     self
   }
 
-  /// Configure server-initiated keep-alive hints.
+  /// Configure server-initiated keep-alive.
   pub fn keep_alive(mut self, k: WsKeepAlive) -> Self {
     self.keep_alive = k;
     self
   }
 
+  /// Send a ping to the client every `d`.
+  pub fn ping_interval(mut self, d: Duration) -> Self {
+    self.keep_alive.ping_interval = Some(d);
+    self
+  }
+
+  /// Disconnect when no frame arrives within `d` of a ping.
+  pub fn pong_timeout(mut self, d: Duration) -> Self {
+    self.keep_alive.pong_timeout = Some(d);
+    self
+  }
+
+  /// Disconnect when the client sends nothing at all for `d`.
+  pub fn idle_timeout(mut self, d: Duration) -> Self {
+    self.idle_timeout = Some(d);
+    self
+  }
+
+  /// Track the connection in `registry` once the upgrade completes.
+  pub fn registry(mut self, registry: WsRegistry) -> Self {
+    self.registry = Some(registry);
+    self
+  }
+
+  /// Record the authenticated subject (e.g. user id) in the registry entry.
+  pub fn subject(mut self, subject: impl Into<String>) -> Self {
+    self.subject = Some(subject.into());
+    self
+  }
+
+  /// Close the connection with `1008 Policy Violation` once `expiry` fires.
+  pub fn session_expiry(mut self, expiry: SessionExpiry) -> Self {
+    self.session_expiry = Some(expiry);
+    self
+  }
+
   fn websocket_config(&self) -> Option<WebSocketConfig> {
     if self.max_frame_size.is_none() && self.max_message_size.is_none() {
       return None;
@@ -244,10 +330,25 @@ where
     let selected_proto = self.negotiate_subprotocol(self.request.headers());
     let upgrade_timeout = self.upgrade_timeout;
     let max_lifetime = self.max_lifetime;
+    let keep_alive = KeepAlivePolicy {
+      ping_interval: self.keep_alive.ping_interval,
+      pong_timeout: self.keep_alive.pong_timeout,
+      idle_timeout: self.idle_timeout,
+    };
 
     let TakoWs {
-      request, handler, ..
+      request,
+      handler,
+      registry,
+      subject,
+      session_expiry,
+      ..
     } = self;
+    let path = request.uri().path().to_string();
+    let peer = request
+      .extensions()
+      .get::<ConnInfo>()
+      .map(|info| info.peer.clone());
     let (parts, body) = request.into_parts();
     let req = http::Request::from_parts(parts, body);
 
@@ -258,12 +359,7 @@ where
         .expect("valid bad request response");
     };
 
-    let accept = {
-      let mut sha1 = Sha1::new();
-      sha1.update(key.as_bytes());
-      sha1.update(b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11");
-      STANDARD.encode(sha1.finalize())
-    };
+    let accept = accept_key(key.as_bytes());
 
     let mut builder = http::Response::builder()
       .status(StatusCode::SWITCHING_PROTOCOLS)
@@ -292,14 +388,51 @@ where
         };
         let upgraded = TokioIo::new(upgraded);
         let ws = WebSocketStream::from_raw_socket(upgraded, Role::Server, ws_config).await;
-        let handler_fut = std::panic::AssertUnwindSafe(handler(ws)).catch_unwind();
+        let (registration, close_rx) = match registry {
+          Some(r) => {
+            let (registration, close_rx) = r.register(path, subject, peer);
+            (Some(registration), Some(close_rx))
+          }
+          None => (None, None),
+        };
+        let close = close_signal(close_rx, session_expiry);
+        #[cfg(feature = "signals")]
+        if let Some(r) = &registration {
+          let info = &r.info;
+          tako_rs_core::signals::transport::emit_ws_connected(
+            &info.id.to_string(),
+            &info.path,
+            info.subject.as_deref(),
+          )
+          .await;
+        }
+        let conversation = async move {
+          if keep_alive.is_enabled() || close.is_some() {
+            run_with_keep_alive(ws, handler, keep_alive, close).await;
+          } else {
+            let _ = std::panic::AssertUnwindSafe(handler(ws))
+              .catch_unwind()
+              .await;
+          }
+        };
         match max_lifetime {
           Some(d) => {
-            let _ = tokio::time::timeout(d, handler_fut).await;
-          }
-          None => {
-            let _ = handler_fut.await;
+            let _ = tokio::time::timeout(d, conversation).await;
           }
+          None => conversation.await,
+        }
+        if let Some(registration) = registration {
+          let info = registration.info.clone();
+          drop(registration);
+          #[cfg(feature = "signals")]
+          tako_rs_core::signals::transport::emit_ws_disconnected(
+            &info.id.to_string(),
+            &info.path,
+            info.subject.as_deref(),
+          )
+          .await;
+          #[cfg(not(feature = "signals"))]
+          let _ = info;
         }
       });
     }
@@ -308,9 +441,369 @@ where
   }
 }
 
+/// `Sec-WebSocket-Accept` value for a client's `Sec-WebSocket-Key`.
+pub(crate) fn accept_key(key: &[u8]) -> String {
+  let mut sha1 = Sha1::new();
+  sha1.update(key);
+  sha1.update(b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11");
+  STANDARD.encode(sha1.finalize())
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct KeepAlivePolicy {
+  ping_interval: Option<Duration>,
+  pong_timeout: Option<Duration>,
+  idle_timeout: Option<Duration>,
+}
+
+impl KeepAlivePolicy {
+  fn is_enabled(&self) -> bool {
+    self.ping_interval.is_some() || self.idle_timeout.is_some()
+  }
+}
+
+/// Server-side reason to close a connection: a registry close or an expired
+/// session.
+type CloseSignal = BoxFuture<'static, CloseFrame>;
+
+/// Merges the registry's close request and the session expiry into one
+/// [`CloseSignal`]; `None` when neither is configured.
+fn close_signal(
+  close_rx: Option<oneshot::Receiver<CloseFrame>>,
+  session_expiry: Option<SessionExpiry>,
+) -> Option<CloseSignal> {
+  if close_rx.is_none() && session_expiry.is_none() {
+    return None;
+  }
+  Some(Box::pin(async move {
+    let requested = async {
+      match close_rx {
+        // The sender only goes away together with the registry entry.
+        Some(rx) => match rx.await {
+          Ok(frame) => frame,
+          Err(_) => std::future::pending().await,
+        },
+        None => std::future::pending().await,
+      }
+    };
+    let expired = async {
+      match session_expiry {
+        Some(expiry) => {
+          expiry.expired().await;
+          CloseFrame {
+            code: CloseCode::Policy,
+            reason: "session expired".into(),
+          }
+        }
+        None => std::future::pending().await,
+      }
+    };
+    tokio::select! {
+      frame = requested => frame,
+      frame = expired => frame,
+    }
+  }))
+}
+
+/// Hands `handler` one end of an in-process relay and pumps frames between
+/// the other end and the client until either side goes away or `close`
+/// fires.
+async fn run_with_keep_alive<S, H, Fut>(
+  client: WebSocketStream<S>,
+  handler: H,
+  policy: KeepAlivePolicy,
+  close: Option<CloseSignal>,
+) where
+  S: AsyncRead + AsyncWrite + Unpin,
+  H: FnOnce(WebSocketStream<TokioIo<Upgraded>>) -> Fut,
+  Fut: Future<Output = ()>,
+{
+  let Some((handler_io, relay_io)) = upgraded_pipe().await else {
+    tracing::warn!("WebSocket keep-alive relay setup failed; closing connection");
+    return;
+  };
+  let handler_ws =
+    WebSocketStream::from_raw_socket(TokioIo::new(handler_io), Role::Server, None).await;
+  let relay_ws = WebSocketStream::from_raw_socket(TokioIo::new(relay_io), Role::Client, None).await;
+  let handler_fut = std::panic::AssertUnwindSafe(handler(handler_ws)).catch_unwind();
+  let _ = tokio::join!(handler_fut, relay(client, relay_ws, policy, close));
+}
+
+/// Builds a connected pair of `Upgraded` IOs by running an HTTP/1.1 upgrade
+/// over an in-memory duplex. `Upgraded` has no public constructor, and the
+/// handler's stream type is fixed to wrap one.
+async fn upgraded_pipe() -> Option<(Upgraded, Upgraded)> {
+  let (server_io, client_io) = tokio::io::duplex(RELAY_PIPE_CAPACITY);
+
+  let (tx, rx) = tokio::sync::oneshot::channel();
+  let tx = Mutex::new(Some(tx));
+  let service = hyper::service::service_fn(move |mut req: http::Request<hyper::body::Incoming>| {
+    if let Some(tx) = tx.lock().ok().and_then(|mut t| t.take()) {
+      let _ = tx.send(hyper::upgrade::on(&mut req));
+    }
+    async {
+      Ok::<_, Infallible>(
+        http::Response::builder()
+          .status(StatusCode::SWITCHING_PROTOCOLS)
+          .header(header::UPGRADE, "tako-relay")
+          .header(header::CONNECTION, "upgrade")
+          .body(http_body_util::Empty::<Bytes>::new())
+          .expect("valid relay upgrade response"),
+      )
+    }
+  });
+  tokio::spawn(
+    hyper::server::conn::http1::Builder::new()
+      .serve_connection(TokioIo::new(server_io), service)
+      .with_upgrades(),
+  );
+
+  let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(client_io))
+    .await
+    .ok()?;
+  tokio::spawn(conn.with_upgrades());
+  let req = http::Request::builder()
+    .uri("/")
+    .header(header::HOST, "relay")
+    .header(header::UPGRADE, "tako-relay")
+    .header(header::CONNECTION, "upgrade")
+    .body(http_body_util::Empty::<Bytes>::new())
+    .ok()?;
+  let resp = sender.send_request(req).await.ok()?;
+  let client_side = hyper::upgrade::on(resp).await.ok()?;
+  let server_side = rx.await.ok()?.await.ok()?;
+  Some((server_side, client_side))
+}
+
+async fn tick(interval: &mut Option<tokio::time::Interval>) {
+  match interval {
+    Some(i) => {
+      i.tick().await;
+    }
+    None => std::future::pending().await,
+  }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+  match deadline {
+    Some(d) => tokio::time::sleep_until(d).await,
+    None => std::future::pending().await,
+  }
+}
+
+async fn close_requested(close: &mut Option<CloseSignal>) -> CloseFrame {
+  match close {
+    Some(c) => c.await,
+    None => std::future::pending().await,
+  }
+}
+
+/// Forwards data frames between `client` and `handler`, pinging the client
+/// and closing it once the idle or pong deadline passes or `close` fires.
+async fn relay<C, R>(
+  mut client: WebSocketStream<C>,
+  mut handler: WebSocketStream<R>,
+  policy: KeepAlivePolicy,
+  mut close: Option<CloseSignal>,
+) where
+  C: AsyncRead + AsyncWrite + Unpin,
+  R: AsyncRead + AsyncWrite + Unpin,
+{
+  let start = Instant::now();
+  let mut ping = policy
+    .ping_interval
+    .map(|p| tokio::time::interval_at(start + p, p));
+  let mut last_seen = start;
+  let mut pong_deadline: Option<Instant> = None;
+
+  loop {
+    let idle_deadline = policy.idle_timeout.map(|d| last_seen + d);
+    let deadline = match (idle_deadline, pong_deadline) {
+      (Some(a), Some(b)) => Some(a.min(b)),
+      (a, b) => a.or(b),
+    };
+
+    tokio::select! {
+      msg = client.next() => {
+        let Some(Ok(msg)) = msg else { break };
+        last_seen = Instant::now();
+        pong_deadline = None;
+        match msg {
+          // tungstenite queues the pong reply itself; flushing sends it.
+          Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {
+            if client.flush().await.is_err() {
+              break;
+            }
+          }
+          other => {
+            if handler.send(other).await.is_err() {
+              let _ = client.close(None).await;
+              break;
+            }
+          }
+        }
+      }
+      msg = handler.next() => match msg {
+        Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => {}
+        Some(Ok(msg)) => {
+          if client.send(msg).await.is_err() {
+            break;
+          }
+        }
+        _ => {
+          let _ = client.close(None).await;
+          break;
+        }
+      },
+      () = tick(&mut ping) => {
+        if client.send(Message::Ping(Bytes::new())).await.is_err() {
+          break;
+        }
+        if pong_deadline.is_none() {
+          pong_deadline = policy.pong_timeout.map(|t| Instant::now() + t);
+        }
+      }
+      () = sleep_until(deadline) => {
+        let _ = client
+          .close(Some(CloseFrame {
+            code: CloseCode::Away,
+            reason: "keep-alive timeout".into(),
+          }))
+          .await;
+        break;
+      }
+      frame = close_requested(&mut close) => {
+        let _ = client.close(Some(frame)).await;
+        let _ = handler.close(None).await;
+        break;
+      }
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
+  use std::time::Duration;
+
+  use futures_util::SinkExt;
+  use futures_util::StreamExt;
+  use tokio_tungstenite::WebSocketStream;
+  use tokio_tungstenite::tungstenite::Message;
+  use tokio_tungstenite::tungstenite::protocol::Role;
+
+  use super::CloseSignal;
+  use super::KeepAlivePolicy;
+  use super::WsRegistry;
+  use super::close_signal;
   use super::normalize_origin;
+  use super::run_with_keep_alive;
+  use crate::session_expiry::SessionExpiry;
+
+  async fn keep_alive_pair(
+    policy: KeepAlivePolicy,
+    close: Option<CloseSignal>,
+  ) -> WebSocketStream<tokio::io::DuplexStream> {
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+      let server = WebSocketStream::from_raw_socket(server_io, Role::Server, None).await;
+      run_with_keep_alive(
+        server,
+        |mut ws| async move {
+          while let Some(Ok(msg)) = ws.next().await {
+            if msg.is_text() && ws.send(msg).await.is_err() {
+              break;
+            }
+          }
+        },
+        policy,
+        close,
+      )
+      .await;
+    });
+    WebSocketStream::from_raw_socket(client_io, Role::Client, None).await
+  }
+
+  #[tokio::test]
+  async fn keep_alive_relays_messages_and_pings() {
+    let mut client = keep_alive_pair(
+      KeepAlivePolicy {
+        ping_interval: Some(Duration::from_millis(20)),
+        pong_timeout: None,
+        idle_timeout: None,
+      },
+      None,
+    )
+    .await;
+
+    client.send(Message::text("hello")).await.unwrap();
+    let mut saw_echo = false;
+    let mut saw_ping = false;
+    while !(saw_echo && saw_ping) {
+      match client.next().await.unwrap().unwrap() {
+        Message::Text(t) => {
+          assert_eq!(t.as_str(), "hello");
+          saw_echo = true;
+        }
+        Message::Ping(_) => saw_ping = true,
+        other => panic!("unexpected frame: {other:?}"),
+      }
+    }
+  }
+
+  #[tokio::test]
+  async fn keep_alive_closes_silent_peers() {
+    let mut client = keep_alive_pair(
+      KeepAlivePolicy {
+        ping_interval: None,
+        pong_timeout: None,
+        idle_timeout: Some(Duration::from_millis(30)),
+      },
+      None,
+    )
+    .await;
+
+    match client.next().await.unwrap().unwrap() {
+      Message::Close(Some(frame)) => assert_eq!(u16::from(frame.code), 1001),
+      other => panic!("expected close, got {other:?}"),
+    }
+  }
+
+  #[tokio::test]
+  async fn registry_close_reaches_the_client() {
+    let registry = WsRegistry::new();
+    let (registration, close_rx) = registry.register("/ws".into(), Some("alice".into()), None);
+    let mut client = keep_alive_pair(
+      KeepAlivePolicy::default(),
+      close_signal(Some(close_rx), None),
+    )
+    .await;
+
+    assert_eq!(registry.close_subject("alice", 4001, "logged out"), 1);
+    match client.next().await.unwrap().unwrap() {
+      Message::Close(Some(frame)) => {
+        assert_eq!(u16::from(frame.code), 4001);
+        assert_eq!(frame.reason.as_str(), "logged out");
+      }
+      other => panic!("expected close, got {other:?}"),
+    }
+    drop(registration);
+    assert!(registry.is_empty());
+  }
+
+  #[tokio::test]
+  async fn session_expiry_closes_with_policy_violation() {
+    let expiry = SessionExpiry::after(Duration::from_millis(20));
+    let mut client =
+      keep_alive_pair(KeepAlivePolicy::default(), close_signal(None, Some(expiry))).await;
+
+    match client.next().await.unwrap().unwrap() {
+      Message::Close(Some(frame)) => {
+        assert_eq!(u16::from(frame.code), 1008);
+        assert_eq!(frame.reason.as_str(), "session expired");
+      }
+      other => panic!("expected close, got {other:?}"),
+    }
+  }
 
   #[test]
   fn normalize_origin_lowercases_scheme_and_host() {
@@ -0,0 +1,485 @@
+//! Reverse-proxying WebSocket connections to an upstream server.
+//!
+//! [`WsProxy::forward`] opens the upstream connection first, then answers the
+//! client's upgrade and bridges frames both ways until either side closes.
+//! Connecting before the `101` means upstream refusals reach the client as
+//! ordinary HTTP responses (`401`, `404`, ...), an unreachable upstream
+//! answers `502`, and a handshake that takes too long answers `504`.
+//!
+//! The client's `Sec-WebSocket-Protocol` offer is passed upstream and the
+//! subprotocol the upstream picks is echoed back, so both ends agree on it.
+//! Other end-to-end headers (cookies, `Authorization`, `Origin`) are
+//! forwarded as well and the peer address is appended to `X-Forwarded-For`.
+//!
+//! Text, binary and close frames are relayed as-is, including close codes
+//! and reasons. Pings are answered on each hop rather than forwarded. When
+//! one side drops without a close handshake, the other is closed with
+//! `1001 Going Away`.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use http::Method;
+//! use tako::router::Router;
+//! use tako::types::Request;
+//! use tako::ws::WsProxy;
+//!
+//! let chat = WsProxy::new("ws://chat-backend:9000/socket");
+//! let mut router = Router::new();
+//! router.route(Method::GET, "/chat", move |req: Request| {
+//!   let chat = chat.clone();
+//!   async move { chat.forward(req).await }
+//! });
+//! ```
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use futures_util::Sink;
+use futures_util::SinkExt;
+use futures_util::Stream;
+use futures_util::StreamExt;
+use http::HeaderMap;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header;
+use hyper_util::rt::TokioIo;
+use tako_rs_core::body::TakoBody;
+use tako_rs_core::conn_info::ConnInfo;
+use tako_rs_core::types::Request;
+use tako_rs_core::types::Response;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::net::TcpStream;
+use tokio_tungstenite::MaybeTlsStream;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Error as WsError;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+use super::accept_key;
+
+type UpstreamStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Headers that belong to one hop or to the handshake itself and are never
+/// copied to the upstream request.
+const NOT_FORWARDED: [&str; 13] = [
+  "connection",
+  "keep-alive",
+  "proxy-connection",
+  "proxy-authenticate",
+  "proxy-authorization",
+  "te",
+  "trailer",
+  "transfer-encoding",
+  "upgrade",
+  "host",
+  "sec-websocket-key",
+  "sec-websocket-version",
+  "sec-websocket-extensions",
+];
+
+/// Forwards WebSocket upgrades to a fixed upstream URL.
+#[derive(Debug, Clone)]
+pub struct WsProxy {
+  upstream: String,
+  connect_timeout: Duration,
+  max_frame_size: Option<usize>,
+  max_message_size: Option<usize>,
+}
+
+impl WsProxy {
+  /// Proxies to `upstream`, a `ws://` or `wss://` URL. The inbound request
+  /// path is not appended; route each upstream endpoint separately.
+  ///
+  /// `wss://` upstreams need tokio-tungstenite built with one of its TLS
+  /// features.
+  pub fn new(upstream: impl Into<String>) -> Self {
+    Self {
+      upstream: upstream.into(),
+      connect_timeout: Duration::from_secs(10),
+      max_frame_size: None,
+      max_message_size: None,
+    }
+  }
+
+  /// Time allowed for the upstream TCP connect and handshake (default 10s).
+  pub fn connect_timeout(mut self, d: Duration) -> Self {
+    self.connect_timeout = d;
+    self
+  }
+
+  /// Caps the size of a single frame on both legs.
+  pub fn max_frame_size(mut self, n: usize) -> Self {
+    self.max_frame_size = Some(n);
+    self
+  }
+
+  /// Caps the size of a reassembled message on both legs.
+  pub fn max_message_size(mut self, n: usize) -> Self {
+    self.max_message_size = Some(n);
+    self
+  }
+
+  fn websocket_config(&self) -> Option<WebSocketConfig> {
+    if self.max_frame_size.is_none() && self.max_message_size.is_none() {
+      return None;
+    }
+    let mut cfg = WebSocketConfig::default();
+    if let Some(n) = self.max_frame_size {
+      cfg.max_frame_size = Some(n);
+    }
+    if let Some(n) = self.max_message_size {
+      cfg.max_message_size = Some(n);
+    }
+    Some(cfg)
+  }
+
+  /// Connects upstream and answers the client's upgrade, then bridges the
+  /// two connections in a background task.
+  pub async fn forward(&self, req: Request) -> Response {
+    let Some(key) = req.headers().get(header::SEC_WEBSOCKET_KEY) else {
+      return text(StatusCode::BAD_REQUEST, "Missing Sec-WebSocket-Key");
+    };
+    let accept = accept_key(key.as_bytes());
+    let peer = req
+      .extensions()
+      .get::<ConnInfo>()
+      .and_then(|info| info.peer.as_socket())
+      .map(std::net::SocketAddr::ip);
+
+    let (upstream, protocol) = match self.connect(req.headers(), peer).await {
+      Ok(connected) => connected,
+      Err(resp) => return resp,
+    };
+
+    let mut builder = http::Response::builder()
+      .status(StatusCode::SWITCHING_PROTOCOLS)
+      .header(header::UPGRADE, "websocket")
+      .header(header::CONNECTION, "Upgrade")
+      .header(header::SEC_WEBSOCKET_ACCEPT, accept);
+    if let Some(p) = protocol {
+      builder = builder.header(header::SEC_WEBSOCKET_PROTOCOL, p);
+    }
+    let response = builder
+      .body(TakoBody::empty())
+      .expect("valid WebSocket upgrade response");
+
+    if let Some(on_upgrade) = req.extensions().get::<hyper::upgrade::OnUpgrade>().cloned() {
+      let config = self.websocket_config();
+      tokio::spawn(async move {
+        let Ok(upgraded) = on_upgrade.await else {
+          return;
+        };
+        let client =
+          WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, config).await;
+        bridge(client, upstream).await;
+      });
+    }
+
+    response
+  }
+
+  /// Opens the upstream connection, returning it with the subprotocol the
+  /// upstream selected, or the response to send the client instead.
+  async fn connect(
+    &self,
+    headers: &HeaderMap,
+    peer: Option<IpAddr>,
+  ) -> Result<(UpstreamStream, Option<HeaderValue>), Response> {
+    let upstream = self.upstream.as_str();
+    let mut request = match upstream.into_client_request() {
+      Ok(r) => r,
+      Err(e) => {
+        tracing::error!(upstream, error = %e, "ws proxy: invalid upstream URL");
+        return Err(text(StatusCode::BAD_GATEWAY, "bad gateway"));
+      }
+    };
+
+    let listed: Vec<String> = headers
+      .get_all(header::CONNECTION)
+      .iter()
+      .filter_map(|v| v.to_str().ok())
+      .flat_map(|v| v.split(','))
+      .map(|name| name.trim().to_ascii_lowercase())
+      .collect();
+    let out = request.headers_mut();
+    for (name, value) in headers {
+      let name_str = name.as_str();
+      if NOT_FORWARDED.contains(&name_str) || listed.iter().any(|l| l == name_str) {
+        continue;
+      }
+      out.append(name.clone(), value.clone());
+    }
+    if let Some(ip) = peer {
+      let forwarded = match headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) {
+        Some(prior) => format!("{prior}, {ip}"),
+        None => ip.to_string(),
+      };
+      if let Ok(v) = HeaderValue::from_str(&forwarded) {
+        out.insert("x-forwarded-for", v);
+      }
+    }
+
+    let connect =
+      tokio_tungstenite::connect_async_with_config(request, self.websocket_config(), false);
+    match tokio::time::timeout(self.connect_timeout, connect).await {
+      Ok(Ok((ws, resp))) => {
+        let protocol = resp.headers().get(header::SEC_WEBSOCKET_PROTOCOL).cloned();
+        Ok((ws, protocol))
+      }
+      Ok(Err(WsError::Http(resp))) => {
+        let (parts, body) = resp.into_parts();
+        let mut out = http::Response::new(TakoBody::from(body.unwrap_or_default()));
+        *out.status_mut() = parts.status;
+        if let Some(ct) = parts.headers.get(header::CONTENT_TYPE) {
+          out.headers_mut().insert(header::CONTENT_TYPE, ct.clone());
+        }
+        Err(out)
+      }
+      Ok(Err(e)) => {
+        tracing::warn!(upstream, error = %e, "ws proxy: upstream connect failed");
+        Err(text(StatusCode::BAD_GATEWAY, "bad gateway"))
+      }
+      Err(_) => {
+        tracing::warn!(upstream, "ws proxy: upstream handshake timed out");
+        Err(text(StatusCode::GATEWAY_TIMEOUT, "gateway timeout"))
+      }
+    }
+  }
+}
+
+fn text(status: StatusCode, body: &'static str) -> Response {
+  http::Response::builder()
+    .status(status)
+    .body(TakoBody::from(body))
+    .expect("valid ws proxy response")
+}
+
+fn going_away() -> Message {
+  Message::Close(Some(CloseFrame {
+    code: CloseCode::Away,
+    reason: "".into(),
+  }))
+}
+
+/// Relays frames between the two connections until one side closes, then
+/// finishes both close handshakes.
+async fn bridge<C, U>(client: WebSocketStream<C>, upstream: WebSocketStream<U>)
+where
+  C: AsyncRead + AsyncWrite + Unpin,
+  U: AsyncRead + AsyncWrite + Unpin,
+{
+  let (mut client_tx, mut client_rx) = client.split();
+  let (mut upstream_tx, mut upstream_rx) = upstream.split();
+
+  tokio::select! {
+    closed = pump(&mut client_rx, &mut upstream_tx) => {
+      if !closed {
+        let _ = upstream_tx.send(going_away()).await;
+      }
+    }
+    closed = pump(&mut upstream_rx, &mut client_tx) => {
+      if !closed {
+        let _ = client_tx.send(going_away()).await;
+      }
+    }
+  }
+  // Flushes the close reply tungstenite queued for whichever side closed.
+  let _ = client_tx.close().await;
+  let _ = upstream_tx.close().await;
+}
+
+/// Forwards data frames from `rx` to `tx`. Returns `true` once a close frame
+/// was relayed, `false` when `rx` ended without one.
+async fn pump<R, T>(rx: &mut R, tx: &mut T) -> bool
+where
+  R: Stream<Item = Result<Message, WsError>> + Unpin,
+  T: Sink<Message, Error = WsError> + Unpin,
+{
+  while let Some(Ok(msg)) = rx.next().await {
+    match msg {
+      Message::Text(_) | Message::Binary(_) => {
+        if tx.send(msg).await.is_err() {
+          return false;
+        }
+      }
+      Message::Close(frame) => {
+        let _ = tx.send(Message::Close(frame)).await;
+        return true;
+      }
+      Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
+    }
+  }
+  false
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::SocketAddr;
+
+  use tokio::net::TcpListener;
+  use tokio::sync::mpsc;
+  use tokio_tungstenite::tungstenite::handshake::server::ErrorResponse;
+  use tokio_tungstenite::tungstenite::handshake::server::Request as HandshakeRequest;
+  use tokio_tungstenite::tungstenite::handshake::server::Response as HandshakeResponse;
+
+  use super::*;
+
+  /// Echo server that picks `chat.v2` when offered, refuses requests without
+  /// a cookie, closes with `4000 done` on "bye", and reports the close codes
+  /// it receives.
+  async fn upstream() -> (SocketAddr, mpsc::UnboundedReceiver<u16>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (codes_tx, codes_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+      while let Ok((stream, _)) = listener.accept().await {
+        let codes_tx = codes_tx.clone();
+        tokio::spawn(async move {
+          #[allow(clippy::result_large_err)]
+          let callback = |req: &HandshakeRequest, mut resp: HandshakeResponse| {
+            if !req.headers().contains_key(header::COOKIE) {
+              let mut refusal = ErrorResponse::new(Some("no session".into()));
+              *refusal.status_mut() = StatusCode::UNAUTHORIZED;
+              return Err(refusal);
+            }
+            let offered = req
+              .headers()
+              .get(header::SEC_WEBSOCKET_PROTOCOL)
+              .and_then(|v| v.to_str().ok())
+              .unwrap_or("");
+            if offered.split(',').any(|p| p.trim() == "chat.v2") {
+              resp.headers_mut().insert(
+                header::SEC_WEBSOCKET_PROTOCOL,
+                HeaderValue::from_static("chat.v2"),
+              );
+            }
+            Ok(resp)
+          };
+          let Ok(mut ws) = tokio_tungstenite::accept_hdr_async(stream, callback).await else {
+            return;
+          };
+          while let Some(Ok(msg)) = ws.next().await {
+            match msg {
+              Message::Text(t) if t.as_str() == "bye" => {
+                let _ = ws
+                  .close(Some(CloseFrame {
+                    code: CloseCode::Library(4000),
+                    reason: "done".into(),
+                  }))
+                  .await;
+              }
+              Message::Text(_) | Message::Binary(_) => {
+                let _ = ws.send(msg).await;
+              }
+              Message::Close(Some(frame)) => {
+                let _ = codes_tx.send(u16::from(frame.code));
+              }
+              _ => {}
+            }
+          }
+        });
+      }
+    });
+    (addr, codes_rx)
+  }
+
+  fn upgrade_request(cookie: bool) -> Request {
+    let mut builder = http::Request::builder()
+      .uri("/chat")
+      .header(header::HOST, "proxy")
+      .header(header::CONNECTION, "Upgrade")
+      .header(header::UPGRADE, "websocket")
+      .header(header::SEC_WEBSOCKET_VERSION, "13")
+      .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+      .header(header::SEC_WEBSOCKET_PROTOCOL, "chat.v1, chat.v2");
+    if cookie {
+      builder = builder.header(header::COOKIE, "session=1");
+    }
+    builder.body(TakoBody::empty()).unwrap()
+  }
+
+  /// Bridges an upstream connection to the returned client over an
+  /// in-memory pipe.
+  async fn bridged(proxy: &WsProxy) -> WebSocketStream<tokio::io::DuplexStream> {
+    let (upstream, protocol) = proxy
+      .connect(upgrade_request(true).headers(), None)
+      .await
+      .ok()
+      .unwrap();
+    assert_eq!(protocol.unwrap(), "chat.v2");
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+      let server = WebSocketStream::from_raw_socket(server_io, Role::Server, None).await;
+      bridge(server, upstream).await;
+    });
+    WebSocketStream::from_raw_socket(client_io, Role::Client, None).await
+  }
+
+  #[tokio::test]
+  async fn forward_echoes_subprotocol_and_relays_refusals() {
+    let (addr, _) = upstream().await;
+    let proxy = WsProxy::new(format!("ws://{addr}/socket"));
+
+    let resp = proxy.forward(upgrade_request(true)).await;
+    assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+    assert_eq!(resp.headers()[header::SEC_WEBSOCKET_PROTOCOL], "chat.v2");
+    assert_eq!(
+      resp.headers()[header::SEC_WEBSOCKET_ACCEPT],
+      "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+
+    let resp = proxy.forward(upgrade_request(false)).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let closed = WsProxy::new("ws://127.0.0.1:1/socket");
+    let resp = closed.forward(upgrade_request(true)).await;
+    assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+  }
+
+  #[tokio::test]
+  async fn relays_messages_and_upstream_close_codes() {
+    let (addr, _) = upstream().await;
+    let mut client = bridged(&WsProxy::new(format!("ws://{addr}/socket"))).await;
+
+    client.send(Message::text("hello")).await.unwrap();
+    match client.next().await.unwrap().unwrap() {
+      Message::Text(t) => assert_eq!(t.as_str(), "hello"),
+      other => panic!("unexpected frame: {other:?}"),
+    }
+    client.send(Message::binary(vec![1, 2, 3])).await.unwrap();
+    match client.next().await.unwrap().unwrap() {
+      Message::Binary(b) => assert_eq!(&b[..], &[1, 2, 3]),
+      other => panic!("unexpected frame: {other:?}"),
+    }
+
+    client.send(Message::text("bye")).await.unwrap();
+    match client.next().await.unwrap().unwrap() {
+      Message::Close(Some(frame)) => {
+        assert_eq!(u16::from(frame.code), 4000);
+        assert_eq!(frame.reason.as_str(), "done");
+      }
+      other => panic!("expected close, got {other:?}"),
+    }
+  }
+
+  #[tokio::test]
+  async fn relays_client_close_codes() {
+    let (addr, mut codes) = upstream().await;
+    let mut client = bridged(&WsProxy::new(format!("ws://{addr}/socket"))).await;
+
+    client
+      .close(Some(CloseFrame {
+        code: CloseCode::Library(4100),
+        reason: "leaving".into(),
+      }))
+      .await
+      .unwrap();
+    assert_eq!(codes.recv().await, Some(4100));
+  }
+}
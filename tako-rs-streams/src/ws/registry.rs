@@ -0,0 +1,247 @@
+//! Central registry of live WebSocket connections.
+//!
+//! A [`WsRegistry`] attached to [`TakoWs`](crate::ws::TakoWs) records every
+//! upgraded connection with an id, the request path, an optional auth
+//! subject and the connect time, and removes it when the conversation ends.
+//! [`WsRegistry::close`] sends a close frame to one connection — e.g. to kick
+//! a user on logout — and the handler sees the end of its stream.
+//!
+//! Tracked connections run behind the same in-process relay as keep-alive
+//! (see the [module docs](crate::ws)), which is what lets the registry close
+//! a socket the handler owns. With the `signals` feature, `ws.connected` and
+//! `ws.disconnected` are emitted on the app arbiter.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::SystemTime;
+
+use tako_rs_core::conn_info::PeerAddr;
+use tokio::sync::oneshot;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+/// Identifier of a tracked connection, unique within its registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WsConnectionId(u64);
+
+impl WsConnectionId {
+  /// The numeric id.
+  pub fn get(self) -> u64 {
+    self.0
+  }
+}
+
+impl fmt::Display for WsConnectionId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.0.fmt(f)
+  }
+}
+
+/// Metadata recorded for a tracked connection.
+#[derive(Debug, Clone)]
+pub struct WsConnectionInfo {
+  /// Registry-assigned id.
+  pub id: WsConnectionId,
+  /// Path of the upgrade request.
+  pub path: String,
+  /// Authenticated subject set with [`TakoWs::subject`](crate::ws::TakoWs::subject).
+  pub subject: Option<String>,
+  /// Remote peer, when the transport recorded one.
+  pub peer: Option<PeerAddr>,
+  /// When the upgrade completed.
+  pub connected_at: SystemTime,
+}
+
+struct Entry {
+  info: WsConnectionInfo,
+  close: Option<oneshot::Sender<CloseFrame>>,
+}
+
+#[derive(Default)]
+struct Inner {
+  next_id: AtomicU64,
+  connections: Mutex<HashMap<WsConnectionId, Entry>>,
+}
+
+/// Shared table of live WebSocket connections.
+///
+/// Cheap to clone; every clone sees the same connections.
+///
+/// ```rust,ignore
+/// let registry = WsRegistry::new();
+///
+/// router.get("/ws", {
+///   let registry = registry.clone();
+///   move |req: Request| {
+///     let registry = registry.clone();
+///     async move {
+///       let user = current_user(&req);
+///       TakoWs::new(req, chat).registry(registry).subject(user)
+///     }
+///   }
+/// });
+///
+/// // On logout:
+/// registry.close_subject(&user, 4001, "logged out");
+/// ```
+#[derive(Clone, Default)]
+pub struct WsRegistry {
+  inner: Arc<Inner>,
+}
+
+impl fmt::Debug for WsRegistry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("WsRegistry")
+      .field("connections", &self.len())
+      .finish()
+  }
+}
+
+impl WsRegistry {
+  /// Creates an empty registry.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Number of live connections.
+  pub fn len(&self) -> usize {
+    self.lock().len()
+  }
+
+  /// Returns `true` when no connection is tracked.
+  pub fn is_empty(&self) -> bool {
+    self.lock().is_empty()
+  }
+
+  /// Snapshot of every live connection, oldest first.
+  pub fn connections(&self) -> Vec<WsConnectionInfo> {
+    let mut all: Vec<_> = self.lock().values().map(|e| e.info.clone()).collect();
+    all.sort_by_key(|info| info.id);
+    all
+  }
+
+  /// Metadata of one connection.
+  pub fn get(&self, id: WsConnectionId) -> Option<WsConnectionInfo> {
+    self.lock().get(&id).map(|e| e.info.clone())
+  }
+
+  /// Live connections of one subject, oldest first.
+  pub fn by_subject(&self, subject: &str) -> Vec<WsConnectionInfo> {
+    let mut matching: Vec<_> = self
+      .lock()
+      .values()
+      .filter(|e| e.info.subject.as_deref() == Some(subject))
+      .map(|e| e.info.clone())
+      .collect();
+    matching.sort_by_key(|info| info.id);
+    matching
+  }
+
+  /// Closes a connection with the given close code and reason.
+  ///
+  /// Returns `false` when the id is unknown or a close was already
+  /// requested. The entry disappears once the connection has shut down.
+  pub fn close(&self, id: WsConnectionId, code: u16, reason: impl Into<String>) -> bool {
+    let Some(tx) = self.lock().get_mut(&id).and_then(|e| e.close.take()) else {
+      return false;
+    };
+    tx.send(CloseFrame {
+      code: CloseCode::from(code),
+      reason: reason.into().into(),
+    })
+    .is_ok()
+  }
+
+  /// Closes every connection of `subject`; returns how many were closed.
+  pub fn close_subject(&self, subject: &str, code: u16, reason: impl Into<String>) -> usize {
+    let reason = reason.into();
+    self
+      .by_subject(subject)
+      .into_iter()
+      .filter(|info| self.close(info.id, code, reason.clone()))
+      .count()
+  }
+
+  /// Tracks a new connection until the returned guard drops.
+  pub(crate) fn register(
+    &self,
+    path: String,
+    subject: Option<String>,
+    peer: Option<PeerAddr>,
+  ) -> (WsRegistration, oneshot::Receiver<CloseFrame>) {
+    let id = WsConnectionId(self.inner.next_id.fetch_add(1, Ordering::Relaxed));
+    let (tx, rx) = oneshot::channel();
+    let info = WsConnectionInfo {
+      id,
+      path,
+      subject,
+      peer,
+      connected_at: SystemTime::now(),
+    };
+    self.lock().insert(
+      id,
+      Entry {
+        info: info.clone(),
+        close: Some(tx),
+      },
+    );
+    let registration = WsRegistration {
+      registry: self.clone(),
+      info,
+    };
+    (registration, rx)
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<WsConnectionId, Entry>> {
+    self
+      .inner
+      .connections
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+/// One tracked connection; removes its entry on drop.
+pub(crate) struct WsRegistration {
+  registry: WsRegistry,
+  pub(crate) info: WsConnectionInfo,
+}
+
+impl Drop for WsRegistration {
+  fn drop(&mut self) {
+    self.registry.lock().remove(&self.info.id);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::WsRegistry;
+
+  #[test]
+  fn tracks_closes_and_forgets_connections() {
+    let registry = WsRegistry::new();
+    let (alice, mut alice_rx) = registry.register("/chat".into(), Some("alice".into()), None);
+    let (bob, _bob_rx) = registry.register("/chat".into(), Some("bob".into()), None);
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.connections()[0].id, alice.info.id);
+    assert_eq!(registry.by_subject("bob")[0].id, bob.info.id);
+
+    assert_eq!(registry.close_subject("alice", 4001, "logged out"), 1);
+    let frame = alice_rx.try_recv().unwrap();
+    assert_eq!(u16::from(frame.code), 4001);
+    assert_eq!(frame.reason.as_str(), "logged out");
+    // A second close for the same connection is a no-op.
+    assert!(!registry.close(alice.info.id, 1000, ""));
+
+    drop(alice);
+    assert_eq!(registry.len(), 1);
+    assert!(registry.get(bob.info.id).is_some());
+    drop(bob);
+    assert!(registry.is_empty());
+  }
+}
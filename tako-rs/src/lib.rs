@@ -23,6 +23,7 @@
 //! All public types stay reachable at the original `tako::*` paths.
 
 pub use tako_rs_core::Bytes;
+pub use tako_rs_core::Error;
 pub use tako_rs_core::Full;
 pub use tako_rs_core::Method;
 pub use tako_rs_core::NOT_FOUND;
@@ -54,8 +55,14 @@ pub use tako_rs_core::body;
 #[cfg(all(feature = "client", not(feature = "compio")))]
 #[cfg_attr(docsrs, doc(cfg(all(feature = "client", not(feature = "compio")))))]
 pub use tako_rs_core::client;
+pub use tako_rs_core::clock;
+pub use tako_rs_core::codegen;
+pub use tako_rs_core::conditional;
 pub use tako_rs_core::config;
 pub use tako_rs_core::conn_info;
+pub use tako_rs_core::disconnect;
+pub use tako_rs_core::error;
+pub use tako_rs_core::export;
 #[cfg(feature = "graphiql")]
 #[cfg_attr(docsrs, doc(cfg(feature = "graphiql")))]
 pub use tako_rs_core::graphiql;
@@ -72,22 +79,50 @@ pub use tako_rs_core::problem;
 pub use tako_rs_core::queue;
 pub use tako_rs_core::redirect;
 pub use tako_rs_core::responder;
+pub use tako_rs_core::robots;
 pub use tako_rs_core::route;
 pub use tako_rs_core::router;
 pub use tako_rs_core::router_state;
+#[cfg(feature = "schemars")]
+#[cfg_attr(docsrs, doc(cfg(feature = "schemars")))]
+pub use tako_rs_core::schemas;
 #[cfg(feature = "signals")]
 #[cfg_attr(docsrs, doc(cfg(feature = "signals")))]
 pub use tako_rs_core::signals;
+#[cfg(feature = "simulation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "simulation")))]
+pub use tako_rs_core::sim;
+pub use tako_rs_core::sitemap;
 pub use tako_rs_core::state;
 #[cfg(feature = "tako-tracing")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tako-tracing")))]
 pub use tako_rs_core::tracing;
 pub use tako_rs_core::types;
+#[cfg(feature = "watch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "watch")))]
+pub use tako_rs_core::watch;
+pub use tako_rs_core::well_known;
+#[cfg(any(feature = "password", feature = "totp", feature = "magic-link"))]
+#[cfg_attr(
+  docsrs,
+  doc(cfg(any(feature = "password", feature = "totp", feature = "magic-link")))
+)]
+pub use tako_rs_plugins::auth;
+#[cfg(all(feature = "email", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "email")))]
+pub use tako_rs_plugins::email;
+#[cfg(all(feature = "gateway", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "gateway")))]
+pub use tako_rs_plugins::gateway;
+#[cfg(feature = "images")]
+#[cfg_attr(docsrs, doc(cfg(feature = "images")))]
+pub use tako_rs_plugins::images;
 pub use tako_rs_server::AcceptBackoff;
 #[cfg(feature = "compio")]
 pub use tako_rs_server::CompioServer;
 #[cfg(feature = "compio")]
 pub use tako_rs_server::CompioServerBuilder;
+pub use tako_rs_server::PerIpLimit;
 #[cfg(not(feature = "compio"))]
 pub use tako_rs_server::Server;
 #[cfg(not(feature = "compio"))]
@@ -96,6 +131,9 @@ pub use tako_rs_server::ServerConfig;
 pub use tako_rs_server::ServerHandle;
 pub use tako_rs_server::TlsCert;
 pub use tako_rs_server::bind_with_port_fallback;
+#[cfg(feature = "tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+pub use tako_rs_server::build_rustls_server_config;
 #[cfg(not(any(feature = "compio", feature = "compio-tls", feature = "compio-ws")))]
 pub use tako_rs_server::proxy_protocol;
 pub use tako_rs_server::serve;
@@ -123,6 +161,12 @@ pub use tako_rs_server::serve_h3_with_shutdown;
 #[cfg(all(feature = "http3", not(feature = "compio")))]
 #[cfg_attr(docsrs, doc(cfg(feature = "http3")))]
 pub use tako_rs_server::serve_h3_with_shutdown_and_config;
+#[cfg(all(feature = "http3", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "http3")))]
+pub use tako_rs_server::serve_quic;
+#[cfg(all(feature = "http3", not(feature = "compio")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "http3")))]
+pub use tako_rs_server::serve_quic_with_shutdown_and_config;
 #[cfg(any(
   all(
     feature = "tls",
@@ -180,9 +224,19 @@ pub use tako_rs_server::server_udp;
   not(any(feature = "compio", feature = "compio-tls", feature = "compio-ws"))
 ))]
 pub use tako_rs_server::server_unix;
+#[cfg(feature = "socket-activation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "socket-activation")))]
+pub use tako_rs_server::socket_activation;
 #[cfg(feature = "file-stream")]
 #[cfg_attr(docsrs, doc(cfg(feature = "file-stream")))]
 pub use tako_rs_streams::file_stream;
+pub use tako_rs_streams::session_expiry;
+#[cfg(all(
+  feature = "socketio",
+  not(any(feature = "compio", feature = "compio-ws"))
+))]
+#[cfg_attr(docsrs, doc(cfg(feature = "socketio")))]
+pub use tako_rs_streams::socketio;
 pub use tako_rs_streams::sse;
 pub use tako_rs_streams::r#static;
 #[cfg(all(feature = "webtransport", not(feature = "compio")))]
@@ -198,6 +252,7 @@ pub use tako_rs_streams::ws_compio;
 pub mod extractors {
   pub use tako_rs_core::extractors::FromRequest;
   pub use tako_rs_core::extractors::FromRequestParts;
+  pub use tako_rs_core::extractors::body_limit;
   #[doc(hidden)]
   pub use tako_rs_core::extractors::is_json_content_type;
   pub use tako_rs_core::extractors::json;
@@ -209,16 +264,23 @@ pub mod extractors {
   pub use tako_rs_extractors::basic;
   pub use tako_rs_extractors::bearer;
   pub use tako_rs_extractors::bytes;
+  #[cfg(feature = "cbor")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+  pub use tako_rs_extractors::cbor;
   pub use tako_rs_extractors::connect_info;
   pub use tako_rs_extractors::content_length_limit;
   pub use tako_rs_extractors::cookie_jar;
   pub use tako_rs_extractors::cookie_key_expansion;
   pub use tako_rs_extractors::cookie_private;
   pub use tako_rs_extractors::cookie_signed;
+  pub use tako_rs_extractors::disconnected;
   pub use tako_rs_extractors::extension;
   pub use tako_rs_extractors::form;
   pub use tako_rs_extractors::header_map;
   pub use tako_rs_extractors::ipaddr;
+  #[cfg(feature = "jwe")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "jwe")))]
+  pub use tako_rs_extractors::jwe;
   pub use tako_rs_extractors::jwt;
   pub use tako_rs_extractors::matched_path;
   #[cfg(feature = "multipart")]
@@ -230,10 +292,16 @@ pub mod extractors {
   pub use tako_rs_extractors::protobuf;
   pub use tako_rs_extractors::query;
   pub use tako_rs_extractors::query_multi;
+  #[cfg(feature = "query-nested")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "query-nested")))]
+  pub use tako_rs_extractors::query_nested;
   #[cfg(feature = "simd")]
   #[cfg_attr(docsrs, doc(cfg(feature = "simd")))]
   pub use tako_rs_extractors::simdjson;
   pub use tako_rs_extractors::state;
+  #[cfg(feature = "charset")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "charset")))]
+  pub use tako_rs_extractors::text;
   #[cfg(feature = "typed-header")]
   #[cfg_attr(docsrs, doc(cfg(feature = "typed-header")))]
   pub use tako_rs_extractors::typed_header;
@@ -247,13 +315,18 @@ pub mod extractors {
 pub mod middleware {
   pub use tako_rs_core::middleware::IntoMiddleware;
   pub use tako_rs_core::middleware::Next;
+  pub use tako_rs_core::middleware::priority;
   pub use tako_rs_plugins::middleware::access_log;
   pub use tako_rs_plugins::middleware::api_key_auth;
+  pub use tako_rs_plugins::middleware::api_version;
   pub use tako_rs_plugins::middleware::basic_auth;
   pub use tako_rs_plugins::middleware::bearer_auth;
+  pub use tako_rs_plugins::middleware::body_inspector;
   pub use tako_rs_plugins::middleware::body_limit;
+  pub use tako_rs_plugins::middleware::chaos;
   pub use tako_rs_plugins::middleware::circuit_breaker;
   pub use tako_rs_plugins::middleware::csrf;
+  pub use tako_rs_plugins::middleware::degrade;
   pub use tako_rs_plugins::middleware::etag;
   pub use tako_rs_plugins::middleware::healthcheck;
   #[cfg(feature = "hmac-signature")]
@@ -262,14 +335,24 @@ pub mod middleware {
   #[cfg(feature = "ip-filter")]
   #[cfg_attr(docsrs, doc(cfg(feature = "ip-filter")))]
   pub use tako_rs_plugins::middleware::ip_filter;
+  pub use tako_rs_plugins::middleware::journal;
   #[cfg(feature = "json-schema")]
   #[cfg_attr(docsrs, doc(cfg(feature = "json-schema")))]
   pub use tako_rs_plugins::middleware::json_schema;
   pub use tako_rs_plugins::middleware::jwt_auth;
+  pub use tako_rs_plugins::middleware::login_throttle;
   pub use tako_rs_plugins::middleware::problem_json;
+  pub use tako_rs_plugins::middleware::profiling;
   pub use tako_rs_plugins::middleware::request_id;
+  pub use tako_rs_plugins::middleware::request_schema;
+  #[cfg(feature = "response-signature")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "response-signature")))]
+  pub use tako_rs_plugins::middleware::response_signature;
   pub use tako_rs_plugins::middleware::security_headers;
   pub use tako_rs_plugins::middleware::session;
+  #[cfg(feature = "signed-url")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "signed-url")))]
+  pub use tako_rs_plugins::middleware::signed_url;
   pub use tako_rs_plugins::middleware::tenant;
   pub use tako_rs_plugins::middleware::timeout;
   pub use tako_rs_plugins::middleware::traceparent;
@@ -284,17 +367,22 @@ pub mod stores {
 #[cfg(feature = "plugins")]
 #[cfg_attr(docsrs, doc(cfg(feature = "plugins")))]
 pub mod plugins {
+  pub use tako_rs_core::plugins::PluginScope;
+  pub use tako_rs_core::plugins::PluginSetupError;
+  pub use tako_rs_core::plugins::PluginStatus;
+  pub use tako_rs_core::plugins::RouterHandle;
   pub use tako_rs_core::plugins::TakoPlugin;
+  #[cfg(not(feature = "compio"))]
+  pub use tako_rs_plugins::plugins::batch;
+  pub use tako_rs_plugins::plugins::cache;
   pub use tako_rs_plugins::plugins::compression;
   pub use tako_rs_plugins::plugins::cors;
   pub use tako_rs_plugins::plugins::idempotency;
-  #[cfg(any(feature = "metrics-prometheus", feature = "metrics-opentelemetry"))]
-  #[cfg_attr(
-    docsrs,
-    doc(cfg(any(feature = "metrics-prometheus", feature = "metrics-opentelemetry")))
-  )]
   pub use tako_rs_plugins::plugins::metrics;
+  pub use tako_rs_plugins::plugins::quota;
   pub use tako_rs_plugins::plugins::rate_limiter;
+  pub use tako_rs_plugins::plugins::security_headers;
+  pub use tako_rs_plugins::plugins::tus;
 }
 
 #[cfg(feature = "zero-copy-extractors")]
@@ -333,6 +421,7 @@ pub mod prelude {
   pub use tako_rs_core::extractors::params::Params;
   pub use tako_rs_core::middleware::IntoMiddleware;
   pub use tako_rs_core::middleware::Next;
+  pub use tako_rs_core::middleware::priority;
   pub use tako_rs_core::responder::Responder;
   pub use tako_rs_core::router::Router;
   pub use tako_rs_core::types::Request;
@@ -257,6 +257,27 @@ async fn form_wrong_content_type() {
   assert!(matches!(result, Err(FormError::InvalidContentType)));
 }
 
+#[tokio::test]
+async fn form_respects_body_limit() {
+  use tako::extractors::body_limit::RequestBodyLimit;
+  use tako::extractors::form::Form;
+  use tako::extractors::form::FormError;
+
+  let mut req = http::Request::builder()
+    .method(Method::POST)
+    .uri("/login")
+    .header("content-type", "application/x-www-form-urlencoded")
+    .body(TakoBody::from("username=alice&password=secret"))
+    .unwrap();
+  req.extensions_mut().insert(RequestBodyLimit(8));
+
+  let result = Form::<LoginForm>::from_request(&mut req).await;
+  assert!(matches!(result, Err(FormError::PayloadTooLarge(8))));
+  let resp = FormError::PayloadTooLarge(8).into_response();
+  assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+  assert!(body_str(resp).await.contains("payload_too_large"));
+}
+
 #[tokio::test]
 async fn form_deserialization_error() {
   use tako::extractors::form::Form;
@@ -331,3 +352,390 @@ async fn accept_quality_ordering() {
   let types = accept.types();
   assert_eq!(types, vec!["application/json", "text/html"]);
 }
+
+#[tokio::test]
+async fn cookie_jar_emits_set_cookie_for_changes_only() {
+  use tako::extractors::cookie_jar::Cookie;
+  use tako::extractors::cookie_jar::CookieJar;
+  use tako::extractors::cookie_jar::SameSite;
+
+  let (mut parts, ()) = http::Request::builder()
+    .header("cookie", "theme=dark; stale=1")
+    .body(())
+    .unwrap()
+    .into_parts();
+
+  let mut jar = CookieJar::from_request_parts(&mut parts).await.unwrap();
+  assert_eq!(jar.get("theme").map(Cookie::value), Some("dark"));
+  jar.add(
+    Cookie::build(("session", "abc"))
+      .path("/")
+      .secure(true)
+      .http_only(true)
+      .same_site(SameSite::Strict),
+  );
+  jar.remove("stale");
+
+  let resp = jar.respond((StatusCode::CREATED, "ok"));
+  assert_eq!(resp.status(), StatusCode::CREATED);
+  let set: Vec<&str> = resp
+    .headers()
+    .get_all("set-cookie")
+    .iter()
+    .map(|v| v.to_str().unwrap())
+    .collect();
+  assert_eq!(set.len(), 2);
+  let session = set.iter().find(|v| v.starts_with("session=abc")).unwrap();
+  for attr in ["HttpOnly", "SameSite=Strict", "Secure", "Path=/"] {
+    assert!(session.contains(attr), "{session} lacks {attr}");
+  }
+  assert!(
+    set
+      .iter()
+      .any(|v| v.starts_with("stale=") && v.contains("Max-Age=0"))
+  );
+}
+
+#[tokio::test]
+async fn cookie_private_round_trips_through_set_cookie() {
+  use tako::extractors::cookie_jar::Cookie;
+  use tako::extractors::cookie_private::CookiePrivate;
+  use tako::extractors::cookie_signed::Key;
+
+  let key = Key::generate();
+  let mut private = CookiePrivate::new(key.clone());
+  private.add(Cookie::build(("user", "42")).http_only(true));
+  let resp = private.into_response();
+  let set = resp.headers().get("set-cookie").unwrap().to_str().unwrap();
+  let pair = set.split(';').next().unwrap().to_string();
+  let (name, value) = pair.split_once('=').unwrap();
+  assert_eq!(name, "user");
+  assert_ne!(value, "42");
+  let headers = {
+    let mut h = http::HeaderMap::new();
+    h.insert("cookie", pair.parse().unwrap());
+    h
+  };
+  let read = CookiePrivate::from_headers(&headers, key);
+  assert_eq!(read.get_value("user").as_deref(), Some("42"));
+}
+
+#[cfg(feature = "multipart")]
+#[tokio::test]
+async fn typed_multipart_respects_route_body_limit() {
+  use tako::extractors::body_limit::RouteBodyLimit;
+  use tako::extractors::multipart::TakoTypedMultipart;
+  use tako::extractors::multipart::TypedMultipartError;
+  use tako::extractors::multipart::UploadedFile;
+
+  #[derive(Deserialize)]
+  struct Note {
+    #[allow(dead_code)]
+    text: String,
+  }
+
+  let body = format!(
+    "--X\r\nContent-Disposition: form-data; name=\"text\"\r\n\r\n{}\r\n--X--\r\n",
+    "a".repeat(256)
+  );
+  let mut req = http::Request::builder()
+    .method(Method::POST)
+    .uri("/notes")
+    .header("content-type", "multipart/form-data; boundary=X")
+    .body(TakoBody::from(body))
+    .unwrap();
+  req.extensions_mut().insert(RouteBodyLimit(64));
+
+  let result = TakoTypedMultipart::<Note, UploadedFile>::from_request(&mut req).await;
+  assert!(matches!(
+    result,
+    Err(TypedMultipartError::PayloadTooLarge(64))
+  ));
+}
+
+#[cfg(feature = "multipart")]
+#[tokio::test]
+async fn uploads_are_deduplicated_in_the_content_store() {
+  use tako::extractors::multipart::MultipartConfig;
+  use tako::extractors::multipart::TakoTypedMultipart;
+  use tako::extractors::multipart::UploadedFile;
+
+  #[derive(Deserialize)]
+  struct Form {
+    file: UploadedFile,
+  }
+
+  let dir = std::env::temp_dir().join(format!("tako-cas-{}", std::process::id()));
+  tako::state::set_state(MultipartConfig::new().content_store(&dir));
+
+  let upload = |name: &str| {
+    let body = format!(
+      "--X\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{name}\"\r\n\r\nhello\r\n--X--\r\n"
+    );
+    http::Request::builder()
+      .method(Method::POST)
+      .uri("/files")
+      .header("content-type", "multipart/form-data; boundary=X")
+      .body(TakoBody::from(body))
+      .unwrap()
+  };
+
+  let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+  let mut req = upload("a.txt");
+  let first = TakoTypedMultipart::<Form, UploadedFile>::from_request(&mut req)
+    .await
+    .unwrap()
+    .data
+    .file;
+  assert_eq!(first.sha256.as_deref(), Some(digest));
+  assert!(!first.deduplicated);
+  assert_eq!(first.path, dir.join("2c").join(digest));
+
+  let mut req = upload("b.txt");
+  let second = TakoTypedMultipart::<Form, UploadedFile>::from_request(&mut req)
+    .await
+    .unwrap()
+    .data
+    .file;
+  assert!(second.deduplicated);
+  assert_eq!(second.file_name.as_deref(), Some("b.txt"));
+  assert_eq!(second.path, first.path);
+  assert_eq!(std::fs::read(&second.path).unwrap(), b"hello");
+  // The duplicate's temp file is gone: only the shard directory remains.
+  assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+  std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "jwe")]
+#[tokio::test]
+async fn jwe_round_trips_and_rotates_keys() {
+  use tako::extractors::jwe::Jwe;
+  use tako::extractors::jwe::JweError;
+  use tako::extractors::jwe::JweKey;
+  use tako::extractors::jwe::JweKeys;
+  use tako::extractors::jwe::JweResponse;
+
+  #[derive(Debug, Deserialize, serde::Serialize, PartialEq)]
+  struct Transfer {
+    amount: u64,
+  }
+
+  let old = JweKey::new("2025", &[7u8; 16]).unwrap();
+  let new = JweKey::new("2026", &[9u8; 32]).unwrap();
+  let client = JweKeys::new(old.clone());
+  let server = JweKeys::new(new).with_previous(old);
+
+  let token = client.encrypt(br#"{"amount":42}"#).unwrap();
+  let mut req = http::Request::builder()
+    .method(Method::POST)
+    .header("content-type", "application/jose")
+    .body(TakoBody::from(token))
+    .unwrap();
+  req.extensions_mut().insert(server.clone());
+  let Jwe(t) = Jwe::<Transfer>::from_request(&mut req).await.unwrap();
+  assert_eq!(t, Transfer { amount: 42 });
+
+  let resp = JweResponse::new(server.clone(), Transfer { amount: 7 }).into_response();
+  assert_eq!(resp.headers()["content-type"], "application/jose");
+  let body = body_str(resp).await;
+  assert!(body.starts_with("ey") && !body.contains("amount"));
+  assert_eq!(server.decrypt(&body).unwrap(), br#"{"amount":7}"#);
+  assert_eq!(client.decrypt(&body).unwrap_err(), JweError::UnknownKey);
+
+  // Flip the first ciphertext character: unlike the tag's last character it
+  // carries six full bits, so the edit always stays valid base64url.
+  let mut segments: Vec<String> = body.split('.').map(str::to_owned).collect();
+  let first = if segments[3].starts_with('A') {
+    "B"
+  } else {
+    "A"
+  };
+  segments[3].replace_range(..1, first);
+  let tampered = segments.join(".");
+  assert_eq!(
+    server.decrypt(&tampered).unwrap_err(),
+    JweError::DecryptionFailed
+  );
+}
+
+#[cfg(feature = "charset")]
+#[tokio::test]
+async fn text_decodes_the_declared_charset() {
+  use tako::extractors::text::Text;
+  use tako::extractors::text::TextError;
+  use tako::extractors::text::TextResponse;
+
+  async fn extract(content_type: &str, body: Vec<u8>) -> Result<String, TextError> {
+    let mut req = http::Request::builder()
+      .method(Method::POST)
+      .header("content-type", content_type)
+      .body(TakoBody::from(body))
+      .unwrap();
+    Text::from_request(&mut req).await.map(|Text(text)| text)
+  }
+
+  // "café" in ISO-8859-1 is not valid UTF-8.
+  let latin1 = b"caf\xe9".to_vec();
+  assert_eq!(
+    extract("text/plain; charset=\"ISO-8859-1\"", latin1.clone()).await,
+    Ok("café".to_string())
+  );
+  assert_eq!(
+    extract("text/plain", latin1).await,
+    Err(TextError::InvalidEncoding("UTF-8"))
+  );
+
+  let utf16le: Vec<u8> = "héllo".encode_utf16().flat_map(u16::to_le_bytes).collect();
+  assert_eq!(
+    extract("text/plain; charset=utf-16le", utf16le.clone()).await,
+    Ok("héllo".to_string())
+  );
+  // A byte-order mark wins over a missing or wrong declaration.
+  let with_bom = [&[0xff, 0xfe][..], &utf16le].concat();
+  assert_eq!(
+    extract("text/plain; charset=utf-8", with_bom).await,
+    Ok("héllo".to_string())
+  );
+
+  let err = extract("text/plain; charset=klingon", b"x".to_vec())
+    .await
+    .unwrap_err();
+  assert_eq!(err, TextError::UnsupportedCharset("klingon".to_string()));
+  assert_eq!(
+    err.into_response().status(),
+    StatusCode::UNSUPPORTED_MEDIA_TYPE
+  );
+
+  let resp = TextResponse::new("café €")
+    .charset("latin1")
+    .into_response();
+  assert_eq!(
+    resp.headers()["content-type"],
+    "text/plain; charset=windows-1252"
+  );
+  let body = resp.into_body().collect().await.unwrap().to_bytes();
+  assert_eq!(&body[..], b"caf\xe9 \x80");
+
+  let resp = TextResponse::new("<p>ok</p>")
+    .mime("text/html")
+    .into_response();
+  assert_eq!(resp.headers()["content-type"], "text/html; charset=utf-8");
+}
+
+#[cfg(feature = "cbor")]
+#[tokio::test]
+async fn cbor_round_trips_and_honors_the_body_limit() {
+  use tako::extractors::body_limit::RouteBodyLimit;
+  use tako::extractors::cbor::Cbor;
+  use tako::extractors::cbor::CborError;
+
+  let user = TestUser {
+    name: "sensor-7".to_string(),
+    age: 3,
+  };
+  let resp = Cbor(&user).into_response();
+  assert_eq!(resp.headers()["content-type"], "application/cbor");
+  let encoded = resp.into_body().collect().await.unwrap().to_bytes();
+
+  let request = |content_type: &str| {
+    http::Request::builder()
+      .method(Method::POST)
+      .header("content-type", content_type)
+      .body(TakoBody::from(encoded.clone()))
+      .unwrap()
+  };
+
+  let Cbor(decoded) = Cbor::<TestUser>::from_request(&mut request("application/cbor"))
+    .await
+    .unwrap();
+  assert_eq!(decoded, user);
+  assert!(
+    Cbor::<TestUser>::from_request(&mut request("application/senml+cbor"))
+      .await
+      .is_ok()
+  );
+  assert_eq!(
+    Cbor::<TestUser>::from_request(&mut request("application/json"))
+      .await
+      .err(),
+    Some(CborError::InvalidContentType)
+  );
+
+  let mut limited = request("application/cbor");
+  limited.extensions_mut().insert(RouteBodyLimit(4));
+  let err = Cbor::<TestUser>::from_request(&mut limited)
+    .await
+    .err()
+    .unwrap();
+  assert_eq!(err, CborError::PayloadTooLarge(4));
+  assert_eq!(err.into_response().status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+  let mut garbage = http::Request::builder()
+    .header("content-type", "application/cbor")
+    .body(TakoBody::from(vec![0xff, 0x00]))
+    .unwrap();
+  assert!(matches!(
+    Cbor::<TestUser>::from_request(&mut garbage).await,
+    Err(CborError::DeserializationError(_))
+  ));
+}
+
+#[cfg(feature = "query-nested")]
+#[tokio::test]
+async fn query_nested_parses_bracketed_arrays_and_maps() {
+  use std::collections::HashMap;
+
+  use tako::extractors::query::QueryError;
+  use tako::extractors::query_nested::QueryNested;
+
+  #[derive(Debug, Deserialize, PartialEq)]
+  struct Range {
+    gte: Option<u32>,
+    lte: Option<u32>,
+  }
+
+  #[derive(Debug, Deserialize)]
+  struct Search {
+    tags: Vec<String>,
+    filter: HashMap<String, Range>,
+  }
+
+  let request = |uri: &str| {
+    http::Request::builder()
+      .uri(uri)
+      .body(TakoBody::empty())
+      .unwrap()
+  };
+
+  let QueryNested(search) = QueryNested::<Search>::from_request(&mut request(
+    "/users?tags[]=a&tags[]=b&filter[age][gte]=10&filter[score][lte]=99",
+  ))
+  .await
+  .unwrap();
+  assert_eq!(search.tags, vec!["a", "b"]);
+  assert_eq!(
+    search.filter["age"],
+    Range {
+      gte: Some(10),
+      lte: None
+    }
+  );
+  assert_eq!(search.filter["score"].lte, Some(99));
+
+  let QueryNested(search) = QueryNested::<Search>::from_request(&mut request(
+    "/users?tags%5B0%5D=x&filter%5Bage%5D%5Blte%5D=5",
+  ))
+  .await
+  .unwrap();
+  assert_eq!(search.tags, vec!["x"]);
+  assert_eq!(search.filter["age"].lte, Some(5));
+
+  assert!(matches!(
+    QueryNested::<Search>::from_request(&mut request("/users?tags[]=a&filter[age][gte]=old")).await,
+    Err(QueryError::DeserializationError(_))
+  ));
+  assert!(matches!(
+    QueryNested::<Search>::from_request(&mut request("/users")).await,
+    Err(QueryError::MissingQueryString)
+  ));
+}
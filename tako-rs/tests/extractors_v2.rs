@@ -39,6 +39,46 @@ async fn path_t_tuple() {
   assert_eq!(id, 42);
 }
 
+#[tokio::test]
+async fn path_t_tuple_follows_route_segment_order() {
+  use http_body_util::BodyExt;
+
+  async fn show(Path((org, id)): Path<(String, u64)>) -> String {
+    format!("{org}:{id}")
+  }
+
+  async fn too_many(Path((org, id, _)): Path<(String, u64, u64)>) -> String {
+    format!("{org}:{id}")
+  }
+
+  let mut router = tako::router::Router::new();
+  router.route(http::Method::GET, "/orgs/{org}/users/{id}", show);
+  router.route(http::Method::GET, "/teams/{org}/{id}", too_many);
+
+  let body = |res: tako::types::Response| async move {
+    let bytes = res.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(bytes.to_vec()).unwrap()
+  };
+
+  let res = router.dispatch(req_with_uri("/orgs/acme/users/7")).await;
+  assert_eq!(res.status(), http::StatusCode::OK);
+  assert_eq!(body(res).await, "acme:7");
+
+  let res = router
+    .dispatch(req_with_uri("/orgs/acme/users/seven"))
+    .await;
+  assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+  assert!(body(res).await.contains("path parameter `id` (segment 2)"));
+
+  let res = router.dispatch(req_with_uri("/teams/acme/7")).await;
+  assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+  assert!(
+    body(res)
+      .await
+      .contains("expected tuple of 3 path parameters, got 2 (captured: [org, id])")
+  );
+}
+
 #[tokio::test]
 async fn path_t_struct() {
   #[derive(serde::Deserialize)]
@@ -161,3 +201,23 @@ async fn params_extractor_matches_path_extractor_for_struct() {
   assert_eq!(p1.a, 1);
   assert_eq!(p1.b, "two");
 }
+
+#[tokio::test]
+async fn disconnected_fires_with_the_connection_token() {
+  use tako::disconnect::DisconnectToken;
+  use tako::extractors::disconnected::Disconnected;
+
+  let conn = DisconnectToken::new();
+  let mut req = req_with_uri("/report");
+  req.extensions_mut().insert(conn.child());
+  let disconnected = Disconnected::from_request(&mut req).await.unwrap();
+  assert!(!disconnected.is_disconnected());
+  conn.disconnect();
+  disconnected.clone().await;
+  assert!(disconnected.is_disconnected());
+
+  // Hand-built requests carry no token and never fire.
+  let mut bare = req_with_uri("/report");
+  let never = Disconnected::from_request(&mut bare).await.unwrap();
+  assert!(!never.is_disconnected());
+}
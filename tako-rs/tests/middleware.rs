@@ -119,6 +119,27 @@ async fn api_key_with_verify() {
   assert_eq!(resp2.status(), StatusCode::UNAUTHORIZED);
 }
 
+#[tokio::test]
+async fn middleware_when_skips_unmatched_requests() {
+  use tako::middleware::api_key_auth::ApiKeyAuth;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/healthz", |_req: Request| async { "ok" });
+  router.route(Method::GET, "/api", |_req: Request| async { "ok" });
+  router.route(Method::OPTIONS, "/api", |_req: Request| async { "ok" });
+  router.middleware_when(
+    |req| req.uri().path() != "/healthz" && req.method() != Method::OPTIONS,
+    ApiKeyAuth::new("secret-key").into_middleware(),
+  );
+
+  let resp = router.dispatch(make_req(Method::GET, "/healthz")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  let resp = router.dispatch(make_req(Method::OPTIONS, "/api")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  let resp = router.dispatch(make_req(Method::GET, "/api")).await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
 #[tokio::test]
 async fn basic_auth_valid() {
   use tako::middleware::basic_auth::BasicAuth;
@@ -382,6 +403,150 @@ async fn body_limit_runtime_reject() {
   assert_eq!(body_str(resp).await, "Body exceeds allowed size");
 }
 
+#[tokio::test]
+async fn body_limit_json_error_and_route_override() {
+  use tako::extractors::FromRequest;
+  use tako::extractors::json::Json;
+  use tako::middleware::body_limit::BodyLimit;
+  use tako::responder::Responder;
+
+  async fn echo(mut req: Request) -> tako::types::Response {
+    match Json::<serde_json::Value>::from_request(&mut req).await {
+      Ok(Json(v)) => Json(v).into_response(),
+      Err(e) => e.into_response(),
+    }
+  }
+
+  let mut router = Router::new();
+  router.route(Method::POST, "/small", echo);
+  router.route(Method::POST, "/large", echo).body_limit(1024);
+  router.middleware(BodyLimit::new(16).into_middleware());
+
+  let payload = r#"{"message":"well over sixteen bytes"}"#;
+
+  // No Content-Length: the extractor trips the limit mid-stream.
+  let mut req = make_req_with_body(Method::POST, "/small", payload);
+  req
+    .headers_mut()
+    .insert("content-type", "application/json".parse().unwrap());
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+  assert_eq!(
+    resp.headers().get("content-type").unwrap(),
+    "application/json"
+  );
+  let body: serde_json::Value = serde_json::from_str(&body_str(resp).await).unwrap();
+  assert_eq!(body["error"], "payload_too_large");
+  assert_eq!(body["limit"], 16);
+
+  let mut req = make_req_with_body(Method::POST, "/large", payload);
+  req
+    .headers_mut()
+    .insert("content-type", "application/json".parse().unwrap());
+  req
+    .headers_mut()
+    .insert("content-length", payload.len().into());
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn body_inspector_reports_sizes() {
+  use std::sync::Arc;
+  use std::sync::atomic::AtomicU64;
+  use std::sync::atomic::Ordering;
+
+  use tako::middleware::body_inspector::BodyInspector;
+
+  let req_bytes = Arc::new(AtomicU64::new(0));
+  let resp_bytes = Arc::new(AtomicU64::new(0));
+  let mut router = Router::new();
+  router.route(Method::POST, "/echo", |req: Request| async move {
+    let bytes = req.into_body().collect().await.unwrap().to_bytes();
+    TakoBody::from(bytes.repeat(2))
+  });
+  let (r, w) = (Arc::clone(&req_bytes), Arc::clone(&resp_bytes));
+  router.middleware(
+    BodyInspector::new()
+      .on_request_size(move |n| r.store(n, Ordering::SeqCst))
+      .on_response_size(move |n| w.store(n, Ordering::SeqCst))
+      .into_middleware(),
+  );
+
+  let resp = router
+    .dispatch(make_req_with_body(Method::POST, "/echo", "hello"))
+    .await;
+  assert_eq!(body_str(resp).await, "hellohello");
+  assert_eq!(req_bytes.load(Ordering::SeqCst), 5);
+  assert_eq!(resp_bytes.load(Ordering::SeqCst), 10);
+}
+
+#[tokio::test]
+async fn body_inspector_enforces_streamed_budgets() {
+  use futures_util::stream;
+  use tako::body::BodyBudgetExceeded;
+  use tako::middleware::body_inspector::BodyInspector;
+
+  let mut router = Router::new();
+  router.route(Method::POST, "/upload", |req: Request| async move {
+    match req.into_body().collect().await {
+      Ok(_) => StatusCode::OK,
+      Err(e) if e.is::<BodyBudgetExceeded>() => StatusCode::PAYLOAD_TOO_LARGE,
+      Err(_) => StatusCode::BAD_REQUEST,
+    }
+  });
+  router.route(Method::GET, "/stream", |_req: Request| async move {
+    let chunks = (0..4).map(|_| Ok::<_, std::io::Error>(bytes::Bytes::from_static(b"chunk")));
+    TakoBody::from_stream(stream::iter(chunks))
+  });
+  router.middleware(
+    BodyInspector::new()
+      .request_budget(4)
+      .response_budget(12)
+      .into_middleware(),
+  );
+
+  // No Content-Length, so the budget has to trip inside the body stream.
+  let resp = router
+    .dispatch(make_req_with_body(Method::POST, "/upload", "too large"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+  let resp = router.dispatch(make_req(Method::GET, "/stream")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert!(resp.into_body().collect().await.is_err());
+}
+
+#[tokio::test]
+async fn body_inspector_rejects_known_sizes_up_front() {
+  use tako::middleware::body_inspector::BodyInspector;
+
+  let mut router = Router::new();
+  router.route(Method::POST, "/upload", |_req: Request| async {
+    "x".repeat(64)
+  });
+  router.middleware(
+    BodyInspector::new()
+      .request_budget(10)
+      .response_budget(32)
+      .into_middleware(),
+  );
+
+  let mut req = make_req_with_body(Method::POST, "/upload", "this body is too large");
+  req
+    .headers_mut()
+    .insert("content-length", "22".parse().unwrap());
+  assert_eq!(
+    router.dispatch(req).await.status(),
+    StatusCode::PAYLOAD_TOO_LARGE
+  );
+
+  let resp = router
+    .dispatch(make_req_with_body(Method::POST, "/upload", "ok"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
 #[tokio::test]
 async fn security_headers_default() {
   use tako::middleware::security_headers::SecurityHeaders;
@@ -445,6 +610,176 @@ async fn security_headers_custom_frame_options() {
   assert_eq!(resp.headers().get("x-frame-options").unwrap(), "SAMEORIGIN");
 }
 
+#[tokio::test]
+async fn security_headers_csp_nonce_reaches_handler() {
+  use tako::extractors::FromRequest;
+  use tako::middleware::security_headers::CspNonce;
+  use tako::middleware::security_headers::SecurityHeaders;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |mut req: Request| async move {
+    let nonce = CspNonce::from_request(&mut req).await.unwrap();
+    format!("<script {}></script>", nonce.attr())
+  });
+  router.middleware(
+    SecurityHeaders::new()
+      .csp_with_nonce("script-src 'nonce-{nonce}'")
+      .into_middleware(),
+  );
+
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  let csp = resp
+    .headers()
+    .get("content-security-policy")
+    .unwrap()
+    .to_str()
+    .unwrap()
+    .to_string();
+  let nonce = csp
+    .strip_prefix("script-src 'nonce-")
+    .and_then(|rest| rest.strip_suffix('\''))
+    .unwrap()
+    .to_string();
+  assert_eq!(
+    body_str(resp).await,
+    format!("<script nonce=\"{nonce}\"></script>")
+  );
+
+  let again = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_ne!(
+    again.headers().get("content-security-policy").unwrap(),
+    csp.as_str()
+  );
+
+  // A nonce chosen by an outer layer is kept.
+  let mut req = make_req(Method::GET, "/");
+  req.extensions_mut().insert(CspNonce("outer".to_string()));
+  let resp = router.dispatch(req).await;
+  assert_eq!(
+    resp.headers().get("content-security-policy").unwrap(),
+    "script-src 'nonce-outer'"
+  );
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn security_headers_plugin_covers_every_route() {
+  use tako::middleware::security_headers::SecurityHeaders;
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::security_headers::SecurityHeadersPlugin;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/a", |_req: Request| async { "a" });
+  router.route(Method::GET, "/b", |_req: Request| async { "b" });
+  SecurityHeadersPlugin::new(
+    SecurityHeaders::new()
+      .hsts(true)
+      .permissions_policy("camera=()"),
+  )
+  .setup(&router)
+  .unwrap();
+
+  for uri in ["/a", "/b", "/missing"] {
+    let resp = router.dispatch(make_req(Method::GET, uri)).await;
+    assert_eq!(resp.headers()["x-content-type-options"], "nosniff");
+    assert_eq!(
+      resp.headers()["strict-transport-security"],
+      "max-age=31536000; includeSubDomains"
+    );
+    assert_eq!(resp.headers()["permissions-policy"], "camera=()");
+  }
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn plugin_settings_load_from_config_and_validate() {
+  use serde::Deserialize;
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::cache;
+  use tako::plugins::compression;
+  use tako::plugins::cors;
+  use tako::plugins::idempotency;
+  use tako::plugins::rate_limiter;
+  use tako::plugins::security_headers;
+
+  #[derive(Deserialize)]
+  struct AppConfig {
+    compression: compression::Settings,
+    cors: cors::Settings,
+    rate_limiter: rate_limiter::Settings,
+    idempotency: idempotency::Settings,
+    cache: cache::Settings,
+    security_headers: security_headers::Settings,
+  }
+
+  let cfg: AppConfig = serde_json::from_value(serde_json::json!({
+    "compression": { "encodings": ["br", "gzip"], "min_size": 512 },
+    "cors": { "origins": ["https://app.example.com"], "allow_credentials": true },
+    "rate_limiter": { "max_requests": 5, "algorithm": "sliding_window" },
+    "idempotency": { "methods": ["post", "patch"], "scope": "key_only" },
+    "cache": { "ttl_secs": 30, "status_header": "" },
+    "security_headers": { "hsts": true, "permissions_policy": "camera=()" },
+  }))
+  .unwrap();
+
+  cfg.compression.build().unwrap();
+  cfg.rate_limiter.build().unwrap();
+  cfg.idempotency.build().unwrap();
+  cfg.cache.build().unwrap();
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |_req: Request| async { "ok" });
+  cfg.cors.build().unwrap().setup(&router).unwrap();
+  cfg
+    .security_headers
+    .build()
+    .unwrap()
+    .setup(&router)
+    .unwrap();
+  let req = http::Request::builder()
+    .uri("/")
+    .header("origin", "https://app.example.com")
+    .body(TakoBody::empty())
+    .unwrap();
+  let resp = router.dispatch(req).await;
+  assert_eq!(
+    resp.headers()["access-control-allow-origin"],
+    "https://app.example.com"
+  );
+  assert_eq!(resp.headers()["permissions-policy"], "camera=()");
+
+  let err = serde_json::from_str::<compression::Settings>(r#"{"gzip_level": 12}"#)
+    .unwrap()
+    .build()
+    .err()
+    .unwrap();
+  assert_eq!((err.plugin, err.field), ("compression", "gzip_level"));
+  let err = serde_json::from_str::<cors::Settings>(r#"{"allow_credentials": true}"#)
+    .unwrap()
+    .build()
+    .err()
+    .unwrap();
+  assert_eq!(err.field, "allow_credentials");
+  let err = serde_json::from_str::<rate_limiter::Settings>(r#"{"refill_rate": 0}"#)
+    .unwrap()
+    .build()
+    .err()
+    .unwrap();
+  assert_eq!(
+    err.to_string(),
+    "rate_limiter.refill_rate: must be greater than 0"
+  );
+  let err = serde_json::from_str::<security_headers::Settings>(
+    r#"{"hsts_preload": true, "hsts_max_age": 60}"#,
+  )
+  .unwrap()
+  .build()
+  .err()
+  .unwrap();
+  assert_eq!(err.field, "hsts_preload");
+  assert!(serde_json::from_str::<cache::Settings>(r#"{"ttl": 30}"#).is_err());
+}
+
 #[tokio::test]
 async fn request_id_generated() {
   use tako::middleware::request_id::RequestId;
@@ -865,6 +1200,163 @@ async fn traceparent_propagates_inbound() {
   assert_eq!(body_str(resp).await, "0123456789abcdef0123456789abcdef");
 }
 
+#[tokio::test]
+async fn traceparent_accepts_b3_and_jaeger_with_baggage() {
+  use tako::middleware::traceparent::Baggage;
+  use tako::middleware::traceparent::Propagator;
+  use tako::middleware::traceparent::TraceContext;
+  use tako::middleware::traceparent::Traceparent;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |req: Request| async move {
+    let ctx = req.extensions().get::<TraceContext>().unwrap();
+    let baggage = req.extensions().get::<Baggage>().unwrap();
+    format!(
+      "{} {} {} {}",
+      ctx.trace_id,
+      ctx.parent_id.clone().unwrap_or_default(),
+      u8::from(ctx.sampled()),
+      baggage.get("tenant").unwrap_or("-")
+    )
+  });
+  router.middleware(
+    Traceparent::new()
+      .propagators([Propagator::W3c, Propagator::B3, Propagator::Jaeger])
+      .into_middleware(),
+  );
+
+  let mut req = make_req(Method::GET, "/");
+  let headers = req.headers_mut();
+  headers.insert("x-b3-traceid", "463ac35c9f6413ad".parse().unwrap());
+  headers.insert("x-b3-spanid", "a2fb4a1d1a96d312".parse().unwrap());
+  headers.insert("x-b3-sampled", "1".parse().unwrap());
+  headers.insert(
+    "baggage",
+    "tenant=acme%20corp;ttl=1,user=7".parse().unwrap(),
+  );
+  assert_eq!(
+    body_str(router.dispatch(req).await).await,
+    "0000000000000000463ac35c9f6413ad a2fb4a1d1a96d312 1 acme corp"
+  );
+
+  let mut req = make_req(Method::GET, "/");
+  let headers = req.headers_mut();
+  headers.insert(
+    "uber-trace-id",
+    "4bf92f3577b34da6a3ce929d0e0e4736%3A00f067aa0ba902b7%3A0%3A0"
+      .parse()
+      .unwrap(),
+  );
+  headers.insert("uberctx-tenant", "globex".parse().unwrap());
+  assert_eq!(
+    body_str(router.dispatch(req).await).await,
+    "4bf92f3577b34da6a3ce929d0e0e4736 00f067aa0ba902b7 0 globex"
+  );
+}
+
+#[tokio::test]
+async fn traceparent_injects_outbound_headers() {
+  use tako::middleware::traceparent::Propagator;
+  use tako::middleware::traceparent::Traceparent;
+  use tako::middleware::traceparent::inject;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |req: Request| async move {
+    let mut headers = http::HeaderMap::new();
+    inject(
+      &req,
+      &mut headers,
+      &[Propagator::W3c, Propagator::B3Single, Propagator::Jaeger],
+    );
+    let get = |name: &str| headers[name].to_str().unwrap().to_string();
+    let span = get("traceparent")[36..52].to_string();
+    assert_eq!(
+      get("traceparent"),
+      format!("00-0123456789abcdef0123456789abcdef-{span}-01")
+    );
+    assert_eq!(
+      get("b3"),
+      format!("0123456789abcdef0123456789abcdef-{span}-1")
+    );
+    assert_eq!(
+      get("uber-trace-id"),
+      format!("0123456789abcdef0123456789abcdef:{span}:0:1")
+    );
+    assert_eq!(get("baggage"), "user=alice%40example.com");
+    assert_eq!(get("uberctx-user"), "alice%40example.com");
+    "ok"
+  });
+  router.middleware(Traceparent::new().into_middleware());
+
+  let mut req = make_req(Method::GET, "/");
+  req.headers_mut().insert(
+    "traceparent",
+    "00-0123456789abcdef0123456789abcdef-0011223344556677-01"
+      .parse()
+      .unwrap(),
+  );
+  req
+    .headers_mut()
+    .insert("baggage", "user=alice%40example.com".parse().unwrap());
+  assert_eq!(body_str(router.dispatch(req).await).await, "ok");
+}
+
+#[tokio::test]
+async fn profiling_endpoint_is_token_gated_and_clamped() {
+  use std::time::Duration;
+
+  use tako::middleware::profiling::Profile;
+  use tako::middleware::profiling::ProfileError;
+  use tako::middleware::profiling::ProfileFormat;
+  use tako::middleware::profiling::Profiling;
+
+  let fake = |duration: Duration, format: ProfileFormat| async move {
+    let body = format!("{}s", duration.as_secs());
+    Ok::<_, ProfileError>(match format {
+      ProfileFormat::Pprof => Profile::pprof(body),
+      ProfileFormat::Flamegraph => Profile::flamegraph(body),
+    })
+  };
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |_req: Request| async { "app" });
+  router.middleware(
+    Profiling::new(fake, "s3cret")
+      .max_seconds(5)
+      .into_middleware(),
+  );
+  let authed = |uri: &str| {
+    let mut req = make_req(Method::GET, uri);
+    req
+      .headers_mut()
+      .insert("authorization", "Bearer s3cret".parse().unwrap());
+    req
+  };
+
+  let resp = router
+    .dispatch(make_req(Method::GET, "/debug/pprof/profile"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+  let resp = router
+    .dispatch(authed("/debug/pprof/profile?seconds=120&format=flamegraph"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["content-type"], "image/svg+xml");
+  assert_eq!(body_str(resp).await, "5s");
+
+  let resp = router
+    .dispatch(authed("/debug/pprof/profile?seconds=0"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+  let resp = router.dispatch(authed("/debug/pprof/heap")).await;
+  assert_eq!(resp.status(), StatusCode::NOT_IMPLEMENTED);
+
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(body_str(resp).await, "app");
+}
+
 #[tokio::test]
 async fn problem_json_rewrites_text_404() {
   use tako::middleware::problem_json::ProblemJson;
@@ -1121,20 +1613,161 @@ async fn compression_opt_out_of_crime_mitigation() {
   );
 }
 
+#[cfg(feature = "plugins")]
 #[tokio::test]
-async fn tenant_invalid_ids_rejected() {
-  use tako::middleware::tenant::Tenant;
-  use tako::middleware::tenant::TenantMiddleware;
+async fn compression_skips_partial_content() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::compression::CompressionBuilder;
 
   let mut router = Router::new();
-  router.route(Method::GET, "/", |req: Request| async move {
-    req
-      .extensions()
-      .get::<Tenant>()
-      .map_or_else(|| "no-tenant".into(), |t| t.0.clone())
-  });
-  router.middleware(
-    TenantMiddleware::from_header(http::HeaderName::from_static("x-tenant-id")).into_middleware(),
+  router.route(Method::GET, "/", |_req: Request| async move {
+    let payload = "x".repeat(2048);
+    http::Response::builder()
+      .status(http::StatusCode::PARTIAL_CONTENT)
+      .header("content-type", "text/plain")
+      .header("content-range", "bytes 0-2047/8192")
+      .body(TakoBody::from(payload))
+      .unwrap()
+  });
+  CompressionBuilder::new()
+    .enable_gzip(true)
+    .min_size(512)
+    .build()
+    .setup(&router)
+    .unwrap();
+
+  let mut req = make_req(Method::GET, "/");
+  req
+    .headers_mut()
+    .insert("accept-encoding", "gzip".parse().unwrap());
+  let resp = router.dispatch(req).await;
+  assert!(
+    !resp.headers().contains_key("content-encoding"),
+    "range responses must keep their identity byte offsets"
+  );
+  assert_eq!(body_str(resp).await.len(), 2048);
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn compression_mirrors_get_headers_on_head_and_304() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::compression::BodilessMode;
+  use tako::plugins::compression::CompressionBuilder;
+
+  fn page() -> tako::types::Response {
+    http::Response::builder()
+      .header("content-type", "text/html")
+      .body(TakoBody::from("<p>hello</p>".repeat(200)))
+      .unwrap()
+  }
+  fn gzip_req(method: Method, uri: &str) -> Request {
+    let mut req = make_req(method, uri);
+    req
+      .headers_mut()
+      .insert("accept-encoding", "gzip".parse().unwrap());
+    req
+  }
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/page", |_req: Request| async move { page() });
+  router.route(Method::HEAD, "/page", |_req: Request| async move { page() });
+  router.route(Method::GET, "/cached", |_req: Request| async move {
+    http::Response::builder()
+      .status(StatusCode::NOT_MODIFIED)
+      .header("content-type", "text/html")
+      .header("etag", "\"v1\"")
+      .body(TakoBody::empty())
+      .unwrap()
+  });
+  let plugin = CompressionBuilder::new().enable_gzip(true).build();
+  plugin.setup(&router).unwrap();
+
+  let get = router.dispatch(gzip_req(Method::GET, "/page")).await;
+  let get_len = get.into_body().collect().await.unwrap().to_bytes().len();
+
+  let head = router.dispatch(gzip_req(Method::HEAD, "/page")).await;
+  assert_eq!(head.headers()["content-encoding"], "gzip");
+  assert_eq!(head.headers()["vary"], "Accept-Encoding");
+  assert_eq!(
+    head.headers()["content-length"],
+    get_len.to_string().as_str()
+  );
+  assert!(body_str(head).await.is_empty());
+
+  let not_modified = router.dispatch(gzip_req(Method::GET, "/cached")).await;
+  assert_eq!(not_modified.status(), StatusCode::NOT_MODIFIED);
+  assert_eq!(not_modified.headers()["vary"], "Accept-Encoding");
+  assert!(!not_modified.headers().contains_key("content-encoding"));
+
+  let mut router = Router::new();
+  router.route(Method::HEAD, "/page", |_req: Request| async move { page() });
+  CompressionBuilder::new()
+    .bodiless(BodilessMode::Skip)
+    .build()
+    .setup(&router)
+    .unwrap();
+  let head = router.dispatch(gzip_req(Method::HEAD, "/page")).await;
+  assert!(!head.headers().contains_key("content-encoding"));
+  assert!(!head.headers().contains_key("vary"));
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn compression_skip_when_predicate() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::compression::CompressionBuilder;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/signed", |_req: Request| async move {
+    http::Response::builder()
+      .header("content-type", "text/plain")
+      .header("x-signature", "abc")
+      .body(TakoBody::from("x".repeat(2048)))
+      .unwrap()
+  });
+  router.route(Method::GET, "/plain", |_req: Request| async move {
+    http::Response::builder()
+      .header("content-type", "text/plain")
+      .body(TakoBody::from("x".repeat(2048)))
+      .unwrap()
+  });
+  CompressionBuilder::new()
+    .enable_gzip(true)
+    .min_size(512)
+    .skip_when(|_, headers| headers.contains_key("x-signature"))
+    .build()
+    .setup(&router)
+    .unwrap();
+
+  for (path, compressed) in [("/signed", false), ("/plain", true)] {
+    let mut req = make_req(Method::GET, path);
+    req
+      .headers_mut()
+      .insert("accept-encoding", "gzip".parse().unwrap());
+    let resp = router.dispatch(req).await;
+    assert_eq!(
+      resp.headers().contains_key("content-encoding"),
+      compressed,
+      "{path}"
+    );
+  }
+}
+
+#[tokio::test]
+async fn tenant_invalid_ids_rejected() {
+  use tako::middleware::tenant::Tenant;
+  use tako::middleware::tenant::TenantMiddleware;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |req: Request| async move {
+    req
+      .extensions()
+      .get::<Tenant>()
+      .map_or_else(|| "no-tenant".into(), |t| t.0.clone())
+  });
+  router.middleware(
+    TenantMiddleware::from_header(http::HeaderName::from_static("x-tenant-id")).into_middleware(),
   );
 
   for malicious in &["..", "../etc/passwd", "a/b", "with space", ""] {
@@ -1262,3 +1895,1444 @@ async fn problem_json_passes_through_json() {
   // Body untouched (the existing JSON authority wins).
   assert_eq!(body_str(resp).await, r#"{"foo":"bar"}"#);
 }
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn tus_upload_can_be_resumed() {
+  use std::sync::Arc;
+  use std::sync::atomic::AtomicBool;
+  use std::sync::atomic::Ordering;
+
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::tus::TusBuilder;
+  use tako::stores::memory::MemoryTusStore;
+
+  fn tus_req(method: Method, uri: &str, body: &'static str) -> Request {
+    http::Request::builder()
+      .method(method)
+      .uri(uri)
+      .header("tus-resumable", "1.0.0")
+      .header("content-type", "application/offset+octet-stream")
+      .body(TakoBody::from(body))
+      .unwrap()
+  }
+
+  let store = MemoryTusStore::new();
+  let done = Arc::new(AtomicBool::new(false));
+  let router = Router::new();
+  let flag = Arc::clone(&done);
+  TusBuilder::new()
+    .max_size(1024)
+    .store(store.clone())
+    .on_complete(move |_| flag.store(true, Ordering::SeqCst))
+    .build()
+    .setup(&router)
+    .unwrap();
+
+  let resp = router.dispatch(make_req(Method::OPTIONS, "/files")).await;
+  assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+  assert_eq!(resp.headers()["tus-max-size"], "1024");
+
+  // Requests that do not speak the protocol version are refused.
+  let resp = router.dispatch(make_req(Method::POST, "/files")).await;
+  assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+  let mut req = tus_req(Method::POST, "/files", "");
+  req
+    .headers_mut()
+    .insert("upload-length", "11".parse().unwrap());
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.status(), StatusCode::CREATED);
+  assert!(resp.headers().contains_key("upload-expires"));
+  let location = resp.headers()["location"].to_str().unwrap().to_string();
+  let id = location.trim_start_matches("/files/").to_string();
+
+  let mut req = tus_req(Method::PATCH, &location, "hello ");
+  req
+    .headers_mut()
+    .insert("upload-offset", "0".parse().unwrap());
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+  assert_eq!(resp.headers()["upload-offset"], "6");
+
+  // A client that lost track of the offset asks for it and resumes there.
+  let resp = router.dispatch(tus_req(Method::HEAD, &location, "")).await;
+  assert_eq!(resp.headers()["upload-offset"], "6");
+  assert_eq!(resp.headers()["upload-length"], "11");
+
+  let mut stale = tus_req(Method::PATCH, &location, "hello ");
+  stale
+    .headers_mut()
+    .insert("upload-offset", "0".parse().unwrap());
+  assert_eq!(router.dispatch(stale).await.status(), StatusCode::CONFLICT);
+
+  let mut req = tus_req(Method::PATCH, &location, "world");
+  req
+    .headers_mut()
+    .insert("upload-offset", "6".parse().unwrap());
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.headers()["upload-offset"], "11");
+  assert!(done.load(Ordering::SeqCst));
+  assert_eq!(&store.data(&id).await.unwrap()[..], b"hello world");
+
+  let resp = router
+    .dispatch(tus_req(Method::DELETE, &location, ""))
+    .await;
+  assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+  let resp = router.dispatch(tus_req(Method::HEAD, &location, "")).await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn tus_rejects_oversized_uploads() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::tus::TusBuilder;
+
+  let router = Router::new();
+  TusBuilder::new()
+    .max_size(4)
+    .build()
+    .setup(&router)
+    .unwrap();
+
+  let req = http::Request::builder()
+    .method(Method::POST)
+    .uri("/files")
+    .header("tus-resumable", "1.0.0")
+    .header("upload-length", "5")
+    .body(TakoBody::empty())
+    .unwrap();
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[cfg(feature = "plugins")]
+fn counting_cache_router(builder: tako::plugins::cache::CacheBuilder, fail_after: usize) -> Router {
+  use std::sync::Arc;
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+
+  use tako::plugins::TakoPlugin;
+  use tako::responder::Responder;
+
+  let mut router = Router::new();
+  let hits = Arc::new(AtomicUsize::new(0));
+  router.route(Method::GET, "/report", move |_req: Request| {
+    let hits = hits.clone();
+    async move {
+      let n = hits.fetch_add(1, Ordering::SeqCst) + 1;
+      if n > fail_after {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "down").into_response();
+      }
+      format!("v{n}").into_response()
+    }
+  });
+  builder.build().setup(&router).unwrap();
+  router
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn cache_serves_fresh_hits_and_revalidates_stale_entries() {
+  use std::time::Duration;
+
+  use tako::plugins::cache::CacheBuilder;
+
+  let router = counting_cache_router(CacheBuilder::new(), usize::MAX);
+  let resp = router.dispatch(make_req(Method::GET, "/report")).await;
+  assert_eq!(resp.headers()["x-cache"], "MISS");
+  assert_eq!(body_str(resp).await, "v1");
+  let resp = router.dispatch(make_req(Method::GET, "/report")).await;
+  assert_eq!(resp.headers()["x-cache"], "HIT");
+  assert_eq!(body_str(resp).await, "v1");
+
+  // Zero freshness: every later hit is stale and served immediately while a
+  // single background refresh replaces the entry.
+  let router = counting_cache_router(
+    CacheBuilder::new()
+      .ttl(Duration::ZERO)
+      .stale_while_revalidate(Duration::from_secs(60)),
+    usize::MAX,
+  );
+  let _ = router.dispatch(make_req(Method::GET, "/report")).await;
+  let resp = router.dispatch(make_req(Method::GET, "/report")).await;
+  assert_eq!(resp.headers()["x-cache"], "STALE");
+  assert_eq!(body_str(resp).await, "v1");
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  let resp = router.dispatch(make_req(Method::GET, "/report")).await;
+  assert_eq!(resp.headers()["x-cache"], "STALE");
+  assert_eq!(body_str(resp).await, "v2");
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn cache_serves_stale_entry_when_handler_fails() {
+  use std::time::Duration;
+
+  use tako::plugins::cache::CacheBuilder;
+
+  let builder = CacheBuilder::new()
+    .ttl(Duration::ZERO)
+    .stale_if_error(Duration::from_secs(60));
+  let router = counting_cache_router(builder, 1);
+  assert_eq!(
+    body_str(router.dispatch(make_req(Method::GET, "/report")).await).await,
+    "v1"
+  );
+  let resp = router.dispatch(make_req(Method::GET, "/report")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["x-cache"], "STALE");
+  assert_eq!(body_str(resp).await, "v1");
+
+  let router = counting_cache_router(CacheBuilder::new().ttl(Duration::ZERO), 1);
+  let _ = router.dispatch(make_req(Method::GET, "/report")).await;
+  let resp = router.dispatch(make_req(Method::GET, "/report")).await;
+  assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn cache_warmers_prepopulate_entries() {
+  use std::sync::Arc;
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+  use std::time::Duration;
+
+  use tako::plugins::RouterHandle;
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::cache::CacheBuilder;
+  use tako::plugins::cache::CacheWarmer;
+
+  let mut router = Router::new();
+  let hits = Arc::new(AtomicUsize::new(0));
+  let counter = hits.clone();
+  router.route(Method::GET, "/pricing", move |_req: Request| {
+    let counter = counter.clone();
+    async move { format!("v{}", counter.fetch_add(1, Ordering::SeqCst) + 1) }
+  });
+  let cache = CacheBuilder::new()
+    .warmer(CacheWarmer::paths(Duration::from_secs(3600), ["/pricing"]))
+    .build();
+  cache.setup(&router).unwrap();
+
+  assert_eq!(cache.warm(&router).await, 1);
+  let resp = router.dispatch(make_req(Method::GET, "/pricing")).await;
+  assert_eq!(resp.headers()["x-cache"], "HIT");
+  assert_eq!(body_str(resp).await, "v1");
+
+  // Warming replaces entries that are still fresh.
+  cache.warm(&router).await;
+  let resp = router.dispatch(make_req(Method::GET, "/pricing")).await;
+  assert_eq!(body_str(resp).await, "v2");
+
+  // Scheduled warmers fire as soon as the server hands over its router.
+  let router: &'static Router = Box::leak(Box::new(router));
+  cache.on_start(RouterHandle::from_static(router));
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  assert_eq!(hits.load(Ordering::SeqCst), 3);
+  let resp = router.dispatch(make_req(Method::GET, "/pricing")).await;
+  assert_eq!(body_str(resp).await, "v3");
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn cache_invalidations_purge_the_edge() {
+  use std::sync::Arc;
+  use std::sync::Mutex;
+  use std::time::Duration;
+
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::cache::CacheBuilder;
+  use tako::plugins::cache::EdgePurger;
+  use tako::plugins::cache::Purge;
+  use tako::plugins::cache::PurgeError;
+
+  #[derive(Clone, Default)]
+  struct Recorder {
+    purges: Arc<Mutex<Vec<Purge>>>,
+    fail: Arc<Mutex<bool>>,
+  }
+
+  #[async_trait::async_trait]
+  impl EdgePurger for Recorder {
+    fn name(&self) -> &'static str {
+      "recorder"
+    }
+    async fn purge(&self, purge: &Purge) -> Result<(), PurgeError> {
+      self.purges.lock().unwrap().push(purge.clone());
+      if *self.fail.lock().unwrap() {
+        return Err(PurgeError::Status(
+          StatusCode::FORBIDDEN,
+          "bad token".into(),
+        ));
+      }
+      Ok(())
+    }
+  }
+
+  let edge = Recorder::default();
+  let mut router = Router::new();
+  router.route(Method::GET, "/posts/{id}", |_req: Request| async { "post" });
+  router.route(Method::PUT, "/posts/{id}", |_req: Request| async {
+    "saved"
+  });
+  let cache = CacheBuilder::new()
+    .edge_purger(edge.clone())
+    .invalidate_on_write(true)
+    .build();
+  cache.setup(&router).unwrap();
+  let cached = |uri: &'static str| {
+    let router = &router;
+    async move { router.dispatch(make_req(Method::GET, uri)).await.headers()["x-cache"].clone() }
+  };
+
+  cached("/posts/1").await;
+  cached("/posts/1?page=2").await;
+  cached("/posts/2").await;
+  assert_eq!(cached("/posts/1").await, "HIT");
+  assert_eq!(cache.invalidate(["/posts/1"]).await.unwrap(), 2);
+  assert_eq!(cached("/posts/1?page=2").await, "MISS");
+  assert_eq!(cached("/posts/2").await, "HIT");
+
+  // A successful write drops the path before the response returns and
+  // purges the edge in the background.
+  let resp = router.dispatch(make_req(Method::PUT, "/posts/2")).await;
+  assert_eq!(body_str(resp).await, "saved");
+  assert_eq!(cached("/posts/2").await, "MISS");
+  tokio::time::sleep(Duration::from_millis(50)).await;
+
+  *edge.fail.lock().unwrap() = true;
+  let err = cache.invalidate_all().await.unwrap_err();
+  assert!(err.to_string().contains("bad token"));
+  assert_eq!(cached("/posts/2").await, "MISS");
+  assert_eq!(
+    *edge.purges.lock().unwrap(),
+    [
+      Purge::Paths(vec!["/posts/1".into()]),
+      Purge::Paths(vec!["/posts/2".into()]),
+      Purge::All,
+    ]
+  );
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn cache_answers_matching_etags_with_304() {
+  use tako::plugins::cache::CacheBuilder;
+
+  let router = counting_cache_router(CacheBuilder::new(), usize::MAX);
+  let resp = router.dispatch(make_req(Method::GET, "/report")).await;
+  let etag = resp.headers()["etag"].clone();
+  assert!(etag.to_str().unwrap().starts_with("W/\""));
+
+  let conditional = |tag: http::HeaderValue| {
+    http::Request::builder()
+      .method(Method::GET)
+      .uri("/report")
+      .header("if-none-match", tag)
+      .body(TakoBody::empty())
+      .unwrap()
+  };
+  let resp = router.dispatch(conditional(etag.clone())).await;
+  assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+  assert_eq!(resp.headers()["x-cache"], "HIT");
+  assert_eq!(resp.headers()["etag"], etag);
+  assert_eq!(body_str(resp).await, "");
+
+  let other = http::HeaderValue::from_static("\"other\"");
+  let resp = router.dispatch(conditional(other)).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(body_str(resp).await, "v1");
+
+  let router = counting_cache_router(CacheBuilder::new().etag(false), usize::MAX);
+  let resp = router.dispatch(make_req(Method::GET, "/report")).await;
+  assert!(resp.headers().get("etag").is_none());
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn cache_partitions_by_principal_and_skips_authorization() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::cache::CacheBuilder;
+
+  let user = |req: &Request| {
+    req
+      .headers()
+      .get("x-user")
+      .and_then(|v| v.to_str().ok())
+      .map(str::to_string)
+  };
+  let build = |cache: CacheBuilder| {
+    let mut router = Router::new();
+    router.route(Method::GET, "/me", move |req: Request| async move {
+      let name = user(&req).unwrap_or_else(|| "anonymous".into());
+      http::Response::builder()
+        .header("cache-control", "private, max-age=60")
+        .body(TakoBody::from(format!("hello {name}")))
+        .unwrap()
+    });
+    router.route(Method::GET, "/feed", |_req: Request| async { "feed" });
+    cache.build().setup(&router).unwrap();
+    router
+  };
+  let req = |user: Option<&str>, auth: bool, uri: &str| {
+    let mut req = http::Request::builder().method(Method::GET).uri(uri);
+    if let Some(user) = user {
+      req = req.header("x-user", user);
+    }
+    if auth {
+      req = req.header("authorization", "Bearer t");
+    }
+    req.body(TakoBody::empty()).unwrap()
+  };
+
+  // Authorization bypasses the cache unless opted in.
+  let router = build(CacheBuilder::new());
+  let resp = router.dispatch(req(None, true, "/feed")).await;
+  assert!(resp.headers().get("x-cache").is_none());
+  assert_eq!(
+    router.dispatch(req(None, false, "/feed")).await.headers()["x-cache"],
+    "MISS"
+  );
+
+  let router = build(
+    CacheBuilder::new()
+      .cache_authorized(true)
+      .partition_by(user),
+  );
+  assert_eq!(
+    router
+      .dispatch(req(Some("alice"), true, "/me"))
+      .await
+      .headers()["x-cache"],
+    "MISS"
+  );
+  let resp = router.dispatch(req(Some("alice"), true, "/me")).await;
+  assert_eq!(resp.headers()["x-cache"], "HIT");
+  assert_eq!(body_str(resp).await, "hello alice");
+  let resp = router.dispatch(req(Some("bob"), true, "/me")).await;
+  assert_eq!(resp.headers()["x-cache"], "MISS");
+  assert_eq!(body_str(resp).await, "hello bob");
+  // Private responses stay out of the shared partition.
+  router.dispatch(req(None, false, "/me")).await;
+  assert_eq!(
+    router.dispatch(req(None, false, "/me")).await.headers()["x-cache"],
+    "MISS"
+  );
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn http_metrics_serve_prometheus_text() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::metrics::HttpMetricsBuilder;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/users/{id}", |_req: Request| async {
+    "alice"
+  });
+  HttpMetricsBuilder::new()
+    .namespace("app")
+    .size_buckets(vec![10.0, 1.0])
+    .build()
+    .setup(&router)
+    .unwrap();
+
+  for uri in ["/users/1", "/users/2", "/nope"] {
+    body_str(router.dispatch(make_req(Method::GET, uri)).await).await;
+  }
+  let resp = router.dispatch(make_req(Method::GET, "/metrics")).await;
+  assert!(
+    resp.headers()["content-type"]
+      .to_str()
+      .unwrap()
+      .starts_with("text/plain; version=0.0.4")
+  );
+  let text = body_str(resp).await;
+  let users = r#"method="GET",route="/users/{id}",status="2xx""#;
+  for line in [
+    "# TYPE app_http_requests_total counter".to_string(),
+    format!("app_http_requests_total{{{users}}} 2"),
+    r#"app_http_requests_total{method="GET",route="unmatched",status="4xx"} 1"#.to_string(),
+    format!("app_http_request_duration_seconds_count{{{users}}} 2"),
+    format!("app_http_response_size_bytes_bucket{{{users},le=\"1\"}} 0"),
+    format!("app_http_response_size_bytes_bucket{{{users},le=\"10\"}} 2"),
+    format!("app_http_response_size_bytes_sum{{{users}}} 10"),
+    "app_http_requests_in_flight 0".to_string(),
+  ] {
+    assert!(
+      text.lines().any(|l| l == line),
+      "missing `{line}` in:\n{text}"
+    );
+  }
+  assert!(!text.contains("/metrics"));
+}
+
+#[cfg(feature = "gateway")]
+const GATEWAY_TOML: &str = r#"
+[cors]
+origins = ["https://app.example.com"]
+
+[[routes]]
+path = "/health"
+response = { body = "ok", content_type = "text/plain", headers = { x-served-by = "gateway" } }
+
+[[routes]]
+path = "/teapot"
+methods = ["get", "post"]
+response = { status = 418 }
+rate_limit = { requests_per_minute = 60, burst = 1 }
+
+[[routes]]
+path = "/down/{*rest}"
+methods = ["GET", "POST"]
+proxy = { upstream = "http://127.0.0.1:1", timeout_ms = 500, max_body_bytes = 4 }
+"#;
+
+#[cfg(feature = "gateway")]
+#[tokio::test]
+async fn gateway_config_registers_routes_and_plugins() {
+  use std::net::SocketAddr;
+
+  use tako::gateway::GatewayConfig;
+
+  let mut router = Router::new();
+  GatewayConfig::from_toml_str(GATEWAY_TOML)
+    .unwrap()
+    .apply(&mut router)
+    .unwrap();
+  router.setup_plugins_once();
+
+  let mut req = make_req(Method::GET, "/health");
+  req
+    .headers_mut()
+    .insert("origin", "https://app.example.com".parse().unwrap());
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["x-served-by"], "gateway");
+  assert_eq!(resp.headers()["content-type"], "text/plain");
+  assert_eq!(
+    resp.headers()["access-control-allow-origin"],
+    "https://app.example.com"
+  );
+  assert_eq!(body_str(resp).await, "ok");
+
+  let peer: SocketAddr = "10.0.0.7:4000".parse().unwrap();
+  let mut statuses = Vec::new();
+  for method in [Method::GET, Method::POST] {
+    let mut req = make_req(method, "/teapot");
+    req.extensions_mut().insert(peer);
+    statuses.push(router.dispatch(req).await.status());
+  }
+  assert_eq!(
+    statuses,
+    [StatusCode::IM_A_TEAPOT, StatusCode::TOO_MANY_REQUESTS]
+  );
+
+  let resp = router.dispatch(make_req(Method::GET, "/down/x?y=1")).await;
+  assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+
+  // Over the route's body cap: refused before the upstream is contacted,
+  // whether or not the length is advertised.
+  let resp = router
+    .dispatch(make_req_with_body(Method::POST, "/down/x", "too large"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+  let mut req = make_req(Method::POST, "/down/x");
+  req
+    .headers_mut()
+    .insert("content-length", "1048576".parse().unwrap());
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+  let resp = router
+    .dispatch(make_req_with_body(Method::POST, "/down/x", "ok"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+}
+
+#[cfg(feature = "gateway")]
+#[tokio::test]
+async fn gateway_config_rejects_invalid_routes_before_registering() {
+  use tako::gateway::GatewayConfig;
+  use tako::gateway::GatewayError;
+
+  let cfg = GatewayConfig::from_yaml_str(
+    r#"
+routes:
+  - path: /ok
+    response: { body: fine }
+  - path: /both
+    response: { body: x }
+    proxy: { upstream: "http://127.0.0.1:9000" }
+"#,
+  )
+  .unwrap();
+  let mut router = Router::new();
+  let err = cfg.apply(&mut router).unwrap_err();
+  assert!(matches!(&err, GatewayError::Invalid { at, .. } if at == "routes[1]"));
+
+  let resp = router.dispatch(make_req(Method::GET, "/ok")).await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn batch_dispatches_each_item_through_the_router() {
+  use http_body_util::BodyExt;
+  use tako::extractors::json::Json;
+  use tako::plugins::RouterHandle;
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::batch::BatchBuilder;
+  use tako::responder::Responder;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/users/me", |_req: Request| async {
+    Json(serde_json::json!({ "name": "Ada" })).into_response()
+  });
+  router.route(Method::POST, "/echo", |req: Request| async move {
+    let ct = req.headers()["content-type"].to_str().unwrap().to_string();
+    let body = req.into_body().collect().await.unwrap().to_bytes();
+    format!("{ct} {}", String::from_utf8_lossy(&body))
+  });
+  // Router-level auth middleware must see every sub-request.
+  router.middleware(|req: Request, next: tako::middleware::Next| async move {
+    if req.headers().contains_key("authorization") {
+      next.run(req).await
+    } else {
+      http::Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .body(TakoBody::empty())
+        .unwrap()
+    }
+  });
+  let batch = BatchBuilder::new().max_items(4).build();
+  router.plugin(batch.clone());
+  router.setup_plugins_once();
+  let router: &'static Router = Box::leak(Box::new(router));
+
+  let batch_req = |body: &str| {
+    let mut req = make_req_with_body(Method::POST, "/batch", body);
+    req
+      .headers_mut()
+      .insert("authorization", "Bearer t".parse().unwrap());
+    req
+  };
+  let body = r#"{"requests": [
+    {"id": "me", "path": "/users/me"},
+    {"id": 2, "method": "post", "path": "/echo", "body": {"a": 1}},
+    {"id": "bad", "path": "users"},
+    {"path": "/batch", "method": "POST"}
+  ]}"#;
+  let too_many = r#"{"requests": [
+    {"path": "/a"}, {"path": "/b"}, {"path": "/c"}, {"path": "/d"}, {"path": "/e"}
+  ]}"#;
+
+  let resp = router.dispatch(batch_req(body)).await;
+  assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+  batch.on_start(RouterHandle::from_static(router));
+  let resp = router.dispatch(batch_req(too_many)).await;
+  assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+  let resp = router.dispatch(batch_req(body)).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["content-type"], "application/json");
+  let out: serde_json::Value = serde_json::from_str(&body_str(resp).await).unwrap();
+  let items = out["responses"].as_array().unwrap();
+  assert_eq!(items.len(), 4);
+
+  assert_eq!(items[0]["id"], "me");
+  assert_eq!(items[0]["status"], 200);
+  assert_eq!(items[0]["headers"]["content-type"], "application/json");
+  assert_eq!(items[0]["body"]["name"], "Ada");
+
+  assert_eq!(items[1]["id"], 2);
+  assert_eq!(items[1]["body"], r#"application/json {"a":1}"#);
+
+  assert_eq!(items[2]["id"], "bad");
+  assert_eq!(items[2]["status"], 400);
+  assert!(items[3].get("id").is_none());
+  assert_eq!(items[3]["status"], 400);
+
+  // Without credentials the outer request is rejected by the same middleware.
+  let resp = router
+    .dispatch(make_req_with_body(Method::POST, "/batch", body))
+    .await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn quota_counts_per_key_and_rejects_over_limit() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::quota::QuotaBuilder;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/api", |_req: Request| async { "ok" });
+  QuotaBuilder::new()
+    .per_day(2)
+    .key_header(http::header::HeaderName::from_static("x-api-key"))
+    .build()
+    .setup(&router)
+    .unwrap();
+
+  let req = |key: Option<&str>| {
+    let mut req = make_req(Method::GET, "/api");
+    if let Some(key) = key {
+      req.headers_mut().insert("x-api-key", key.parse().unwrap());
+    }
+    req
+  };
+
+  let resp = router.dispatch(req(Some("a"))).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["x-quota-limit"], "2");
+  assert_eq!(resp.headers()["x-quota-remaining"], "1");
+  let reset: u64 = resp.headers()["x-quota-reset"]
+    .to_str()
+    .unwrap()
+    .parse()
+    .unwrap();
+  assert!((1..=86_400).contains(&reset));
+
+  let resp = router.dispatch(req(Some("a"))).await;
+  assert_eq!(resp.headers()["x-quota-remaining"], "0");
+  let resp = router.dispatch(req(Some("a"))).await;
+  assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+  assert_eq!(resp.headers()["x-quota-remaining"], "0");
+  assert!(resp.headers().contains_key("retry-after"));
+
+  // Quotas are per key; unkeyed requests pass without accounting.
+  let resp = router.dispatch(req(Some("b"))).await;
+  assert_eq!(resp.headers()["x-quota-remaining"], "1");
+  let resp = router.dispatch(req(None)).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert!(!resp.headers().contains_key("x-quota-limit"));
+}
+
+#[tokio::test]
+async fn login_throttle_locks_an_account_after_failed_attempts() {
+  use std::time::Duration;
+  use std::time::Instant;
+
+  use tako::middleware::basic_auth::BasicAuth;
+  use tako::middleware::login_throttle::LoginThrottle;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/login", |_req: Request| async { "welcome" });
+  router.middleware(
+    LoginThrottle::new()
+      .account_threshold(2)
+      .base_lockout(Duration::from_secs(60))
+      .min_response_time(Duration::from_millis(50))
+      .into_middleware(),
+  );
+  router.middleware(BasicAuth::single("admin", "password").into_middleware());
+
+  let login = |creds: &str| {
+    let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, creds);
+    let mut req = make_req(Method::GET, "/login");
+    req
+      .headers_mut()
+      .insert("authorization", format!("Basic {encoded}").parse().unwrap());
+    req
+  };
+
+  let started = Instant::now();
+  let resp = router.dispatch(login("admin:wrong")).await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+  assert!(started.elapsed() >= Duration::from_millis(50));
+
+  let resp = router.dispatch(login("admin:wrong")).await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+  // Locked: even the right password is turned away before it is checked.
+  let resp = router.dispatch(login("admin:password")).await;
+  assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+  let retry: u64 = resp.headers()["retry-after"]
+    .to_str()
+    .unwrap()
+    .parse()
+    .unwrap();
+  assert!((59..=60).contains(&retry));
+
+  // Other accounts are unaffected.
+  let resp = router.dispatch(login("bob:guess")).await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[cfg(feature = "password")]
+#[tokio::test]
+async fn form_login_stores_the_user_in_the_session() {
+  use std::sync::Arc;
+  use std::sync::Mutex;
+
+  use tako::auth::form_login::FormLogin;
+  use tako::auth::password::PasswordPolicy;
+  use tako::middleware::session::SessionMiddleware;
+
+  let old = PasswordPolicy::new().memory_kib(64).iterations(1);
+  let stored = Arc::new(Mutex::new(old.hash("hunter2").unwrap()));
+  let lookup = stored.clone();
+  let rehash = stored.clone();
+  let login = FormLogin::new(move |user: String| {
+    let hash = (user == "alice").then(|| lookup.lock().unwrap().clone());
+    async move { hash }
+  })
+  .policy(old.iterations(2))
+  .on_rehash(move |_user, hash| {
+    *rehash.lock().unwrap() = hash;
+    async {}
+  })
+  .success_redirect("/me");
+
+  let mut router = Router::new();
+  router.middleware(SessionMiddleware::new().into_middleware());
+  let l = login.clone();
+  router.route(Method::POST, "/login", move |req: Request| {
+    let l = l.clone();
+    async move { l.login(req).await }
+  });
+  router.route(Method::GET, "/me", move |req: Request| {
+    let user = login.current_user(&req);
+    async move { user.unwrap_or_default() }
+  });
+
+  let form = |body: &str| {
+    let mut req = make_req_with_body(Method::POST, "/login", body);
+    req.headers_mut().insert(
+      "content-type",
+      "application/x-www-form-urlencoded".parse().unwrap(),
+    );
+    req
+  };
+
+  let resp = router.dispatch(form("username=alice&password=wrong")).await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+  let resp = router
+    .dispatch(form("username=mallory&password=hunter2"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+  let resp = router
+    .dispatch(form("username=alice&password=hunter2"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+  assert_eq!(resp.headers()["location"], "/me");
+  // The hash made under the weaker policy was replaced.
+  assert!(stored.lock().unwrap().contains("t=2"));
+
+  let cookie = resp.headers()["set-cookie"].to_str().unwrap();
+  let session = cookie.split(';').next().unwrap().to_string();
+  let mut req = make_req(Method::GET, "/me");
+  req.headers_mut().insert("cookie", session.parse().unwrap());
+  assert_eq!(body_str(router.dispatch(req).await).await, "alice");
+}
+
+#[cfg(feature = "totp")]
+#[tokio::test]
+async fn require_two_factor_admits_sessions_after_a_valid_code() {
+  use tako::auth::totp::RequireTwoFactor;
+  use tako::auth::totp::Totp;
+  use tako::auth::totp::mark_verified;
+  use tako::middleware::session::Session;
+  use tako::middleware::session::SessionMiddleware;
+
+  let totp = Totp::generate();
+  let code = totp.current_code();
+
+  let mut router = Router::new();
+  router.middleware(SessionMiddleware::new().into_middleware());
+  router.route(Method::POST, "/2fa", move |req: Request| {
+    let totp = totp.clone();
+    async move {
+      let session = req.extensions().get::<Session>().cloned().unwrap();
+      let code = body_str(http::Response::new(req.into_body())).await;
+      if totp.verify(&code) {
+        mark_verified(&session);
+        StatusCode::NO_CONTENT
+      } else {
+        StatusCode::UNAUTHORIZED
+      }
+    }
+  });
+  router
+    .route(Method::GET, "/vault", |_req: Request| async { "secret" })
+    .middleware(RequireTwoFactor::new().redirect("/2fa").into_middleware());
+
+  let resp = router.dispatch(make_req(Method::GET, "/vault")).await;
+  assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+  assert_eq!(resp.headers()["location"], "/2fa");
+
+  let resp = router
+    .dispatch(make_req_with_body(Method::POST, "/2fa", "000000x"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+  let resp = router
+    .dispatch(make_req_with_body(Method::POST, "/2fa", &code))
+    .await;
+  assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+  let cookie = resp.headers()["set-cookie"].to_str().unwrap();
+  let session = cookie.split(';').next().unwrap().to_string();
+
+  let mut req = make_req(Method::GET, "/vault");
+  req.headers_mut().insert("cookie", session.parse().unwrap());
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(body_str(resp).await, "secret");
+}
+
+#[cfg(feature = "magic-link")]
+#[tokio::test]
+async fn magic_link_logs_in_once() {
+  use tako::auth::magic_link::MagicLinks;
+  use tako::auth::magic_link::PURPOSE_LOGIN;
+  use tako::auth::magic_link::PURPOSE_VERIFY_EMAIL;
+  use tako::middleware::session::Session;
+  use tako::middleware::session::SessionMiddleware;
+
+  let links = MagicLinks::new(b"test secret".to_vec()).success_redirect("/me");
+  let url = links.link("/magic", PURPOSE_LOGIN, "alice");
+  let verify_url = links.link("/magic", PURPOSE_VERIFY_EMAIL, "alice");
+
+  let mut router = Router::new();
+  router.middleware(SessionMiddleware::new().into_middleware());
+  router.route(Method::GET, "/magic", move |req: Request| {
+    let links = links.clone();
+    async move { links.login(req).await }
+  });
+  router.route(Method::GET, "/me", |req: Request| async move {
+    let session = req.extensions().get::<Session>().unwrap();
+    session.get::<String>("user").unwrap_or_default()
+  });
+
+  let resp = router.dispatch(make_req(Method::GET, &verify_url)).await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+  let resp = router.dispatch(make_req(Method::GET, &url)).await;
+  assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+  assert_eq!(resp.headers()["location"], "/me");
+  let cookie = resp.headers()["set-cookie"].to_str().unwrap();
+  let session = cookie.split(';').next().unwrap().to_string();
+
+  let mut req = make_req(Method::GET, "/me");
+  req.headers_mut().insert("cookie", session.parse().unwrap());
+  assert_eq!(body_str(router.dispatch(req).await).await, "alice");
+
+  // The same link does not work twice.
+  let resp = router.dispatch(make_req(Method::GET, &url)).await;
+  assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn api_versioning_migrates_pinned_payloads_both_ways() {
+  use tako::middleware::api_version::ApiVersion;
+  use tako::middleware::api_version::ApiVersioning;
+  use tako::middleware::api_version::VersionChange;
+
+  let mut router = Router::new();
+  router.route(Method::POST, "/users", |mut req: Request| async move {
+    let version = req.extensions().get::<ApiVersion>().unwrap().0.clone();
+    let bytes = req.body_mut().collect().await.unwrap().to_bytes();
+    let mut user: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert!(
+      user.get("name").is_none(),
+      "handler only sees the new shape"
+    );
+    user["seen_as"] = version.into();
+    http::Response::builder()
+      .header("content-type", "application/json")
+      .body(TakoBody::from(user.to_string()))
+      .unwrap()
+  });
+  router.middleware(
+    ApiVersioning::new("2024-01-01")
+      .change(
+        VersionChange::new("2024-06-01")
+          .request(|body| {
+            let name = body["name"].take();
+            let (first, last) = name
+              .as_str()
+              .unwrap_or("")
+              .split_once(' ')
+              .unwrap_or_default();
+            body["first_name"] = first.into();
+            body["last_name"] = last.into();
+            body.as_object_mut().unwrap().remove("name");
+          })
+          .response(|body| {
+            let obj = body.as_object_mut().unwrap();
+            let first = obj.remove("first_name").unwrap();
+            let last = obj.remove("last_name").unwrap();
+            obj.insert(
+              "name".into(),
+              format!("{} {}", first.as_str().unwrap(), last.as_str().unwrap()).into(),
+            );
+          }),
+      )
+      .into_middleware(),
+  );
+
+  let post = |version: Option<&str>, body: &str| {
+    let mut req = make_req_with_body(Method::POST, "/users", body);
+    req
+      .headers_mut()
+      .insert("content-type", "application/json".parse().unwrap());
+    if let Some(v) = version {
+      req.headers_mut().insert("api-version", v.parse().unwrap());
+    }
+    req
+  };
+
+  let resp = router
+    .dispatch(post(Some("2024-01-01"), r#"{"name":"Ada Lovelace"}"#))
+    .await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["api-version"], "2024-01-01");
+  let body: serde_json::Value = serde_json::from_str(&body_str(resp).await).unwrap();
+  assert_eq!(
+    body,
+    serde_json::json!({ "name": "Ada Lovelace", "seen_as": "2024-01-01" })
+  );
+
+  // No header: the latest version, passed through untouched.
+  let resp = router
+    .dispatch(post(None, r#"{"first_name":"Ada","last_name":"Lovelace"}"#))
+    .await;
+  assert_eq!(resp.headers()["api-version"], "2024-06-01");
+  let body: serde_json::Value = serde_json::from_str(&body_str(resp).await).unwrap();
+  assert_eq!(body["first_name"], "Ada");
+  assert_eq!(body["seen_as"], "2024-06-01");
+
+  let resp = router.dispatch(post(Some("2019-01-01"), "{}")).await;
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn request_schema_rejects_with_every_violation() {
+  use tako::middleware::request_schema::Field;
+  use tako::middleware::request_schema::RequestSchema;
+
+  let mut router = Router::new();
+  router
+    .route(Method::POST, "/reports", |_req: Request| async { "ok" })
+    .middleware(
+      RequestSchema::new()
+        .header(Field::required("x-tenant-id"))
+        .query(Field::required("page").integer())
+        .query(Field::optional("order").one_of(["asc", "desc"]))
+        .content_type("application/json")
+        .into_middleware(),
+    );
+
+  let mut req = make_req_with_body(Method::POST, "/reports?page=2&order=desc", "{}");
+  req
+    .headers_mut()
+    .insert("x-tenant-id", "acme".parse().unwrap());
+  req.headers_mut().insert(
+    "content-type",
+    "application/json; charset=utf-8".parse().unwrap(),
+  );
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+
+  let mut req = make_req_with_body(Method::POST, "/reports?page=two&order=up", "x");
+  req
+    .headers_mut()
+    .insert("content-type", "text/plain".parse().unwrap());
+  let resp = router.dispatch(req).await;
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  assert_eq!(resp.headers()["content-type"], "application/problem+json");
+  let body: serde_json::Value = serde_json::from_str(&body_str(resp).await).unwrap();
+  assert_eq!(
+    body["errors"],
+    serde_json::json!([
+      "missing required header `x-tenant-id`",
+      "query parameter `page` must be an integer",
+      "query parameter `order` must be one of asc, desc",
+      "content type must be one of application/json",
+    ])
+  );
+}
+
+#[cfg(feature = "response-signature")]
+#[tokio::test]
+async fn response_signature_signs_status_type_and_digest() {
+  use tako::middleware::response_signature::ResponseSignature;
+  use tako::middleware::response_signature::SigningKey;
+
+  let signer =
+    || ResponseSignature::new(SigningKey::ed25519_from_seed("api-key", &[7u8; 32]).unwrap());
+  let mut router = Router::new();
+  router.route(Method::GET, "/report", |_req: Request| async {
+    http::Response::builder()
+      .header("content-type", "application/json")
+      .body(TakoBody::from(r#"{"total":42}"#))
+      .unwrap()
+  });
+  router.middleware(signer().into_middleware());
+
+  let resp = router.dispatch(make_req(Method::GET, "/report")).await;
+  let (mut parts, body) = resp.into_parts();
+  let body = body.collect().await.unwrap().to_bytes();
+
+  assert_eq!(
+    parts.headers["content-digest"],
+    "sha-256=:uxRTx/7I38fJ0sQBNiBffO9qHZu2Ph7oXAOPBOcwuqg=:"
+  );
+  let input = parts.headers["signature-input"].to_str().unwrap();
+  assert!(input.starts_with(r#"sig1=("@status" "content-type" "content-digest");created="#));
+  assert!(input.ends_with(r#";keyid="api-key";alg="ed25519""#));
+  assert!(signer().verify(&parts, &body));
+  assert!(!signer().verify(&parts, br#"{"total":43}"#));
+
+  parts.status = StatusCode::CREATED;
+  assert!(!signer().verify(&parts, &body));
+}
+
+#[cfg(feature = "signed-url")]
+#[tokio::test]
+async fn signed_url_gates_downloads() {
+  use std::time::Duration;
+  use std::time::UNIX_EPOCH;
+
+  use tako::middleware::signed_url::SignedClaims;
+  use tako::middleware::signed_url::SignedUrls;
+
+  let urls = SignedUrls::new(b"download secret".to_vec());
+  let mut router = Router::new();
+  router
+    .route(Method::GET, "/files/{name}", |req: Request| async move {
+      let claims = req.extensions().get::<SignedClaims>().unwrap();
+      format!("for {}", claims.get("user").unwrap())
+    })
+    .middleware(urls.clone().into_middleware());
+
+  let link = urls.sign_with(
+    "/files/a.pdf",
+    &[("user", "alice")],
+    Duration::from_secs(60),
+  );
+  let resp = router.dispatch(make_req(Method::GET, &link)).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(body_str(resp).await, "for alice");
+
+  let other = link.replacen("a.pdf", "b.pdf", 1);
+  let resp = router.dispatch(make_req(Method::GET, &other)).await;
+  assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+  let resp = router.dispatch(make_req(Method::GET, "/files/a.pdf")).await;
+  assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+  let stale = urls.sign_until(
+    "/files/a.pdf",
+    &[("user", "alice")],
+    UNIX_EPOCH + Duration::from_secs(60),
+  );
+  let resp = router.dispatch(make_req(Method::GET, &stale)).await;
+  assert_eq!(resp.status(), StatusCode::GONE);
+}
+
+#[tokio::test]
+async fn chaos_faults_only_fire_when_requested() {
+  use std::time::Duration;
+
+  use tako::middleware::chaos::Chaos;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |_req: Request| async { "app" });
+  router.middleware(
+    Chaos::new()
+      .latency(1.0, Duration::from_millis(20), Duration::from_millis(20))
+      .error(1.0, StatusCode::SERVICE_UNAVAILABLE)
+      .into_middleware(),
+  );
+  let chaotic = |faults: &str| {
+    let mut req = make_req(Method::GET, "/");
+    req.headers_mut().insert("x-chaos", faults.parse().unwrap());
+    req
+  };
+
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert!(!resp.headers().contains_key("x-chaos-fault"));
+
+  let started = std::time::Instant::now();
+  let resp = router.dispatch(chaotic("latency")).await;
+  assert!(started.elapsed() >= Duration::from_millis(20));
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["x-chaos-fault"], "latency");
+  assert_eq!(body_str(resp).await, "app");
+
+  let resp = router.dispatch(chaotic("all")).await;
+  assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+  assert_eq!(resp.headers()["x-chaos-fault"], "error");
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |_req: Request| async { "app" });
+  router.middleware(Chaos::new().always().drop_connection(1.0).into_middleware());
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert!(resp.into_body().collect().await.is_err());
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn rate_limiter_keys_by_extractor() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::rate_limiter::ApiKeyHeader;
+  use tako::plugins::rate_limiter::RateLimiterBuilder;
+  use tako::plugins::rate_limiter::RouteAndIp;
+  use tako::plugins::rate_limiter::UnkeyedBehavior;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |_req: Request| async { "ok" });
+  RateLimiterBuilder::new()
+    .requests_per_minute(1)
+    .key(ApiKeyHeader::default())
+    .on_unkeyed(UnkeyedBehavior::Reject)
+    .build()
+    .setup(&router)
+    .unwrap();
+  let with_key = |key: &str| {
+    let mut req = make_req(Method::GET, "/");
+    req.headers_mut().insert("x-api-key", key.parse().unwrap());
+    req
+  };
+
+  let resp = router.dispatch(with_key("a")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["ratelimit-policy"], "1;w=60");
+  let resp = router.dispatch(with_key("a")).await;
+  assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+  assert_eq!(resp.headers()["ratelimit-remaining"], "0");
+  assert!(resp.headers().contains_key("retry-after"));
+  let resp = router.dispatch(with_key("b")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/a", |_req: Request| async { "a" });
+  router.route(Method::GET, "/b", |_req: Request| async { "b" });
+  RateLimiterBuilder::new()
+    .requests_per_minute(1)
+    .key(RouteAndIp)
+    .build()
+    .setup(&router)
+    .unwrap();
+  let from_peer = |uri: &str| {
+    let mut req = make_req(Method::GET, uri);
+    req
+      .extensions_mut()
+      .insert(std::net::SocketAddr::from(([203, 0, 113, 7], 40000)));
+    req
+  };
+  assert_eq!(
+    router.dispatch(from_peer("/a")).await.status(),
+    StatusCode::OK
+  );
+  assert_eq!(
+    router.dispatch(from_peer("/b")).await.status(),
+    StatusCode::OK
+  );
+  assert_eq!(
+    router.dispatch(from_peer("/a")).await.status(),
+    StatusCode::TOO_MANY_REQUESTS
+  );
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn rate_limiter_consults_a_custom_store() {
+  use std::sync::Arc;
+  use std::sync::Mutex;
+
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::rate_limiter::RateLimiterBuilder;
+  use tako::stores::RateLimitSnapshot;
+  use tako::stores::RateLimitStore;
+
+  /// Allows each key once, as a shared store would across instances.
+  #[derive(Clone, Default)]
+  struct OncePerKey(Arc<Mutex<Vec<String>>>);
+
+  #[async_trait::async_trait]
+  impl RateLimitStore for OncePerKey {
+    async fn consume(&self, key: &str, _cost: u32) -> Result<RateLimitSnapshot, RateLimitSnapshot> {
+      let mut seen = self.0.lock().unwrap();
+      let first = !seen.iter().any(|k| k == key);
+      seen.push(key.to_string());
+      let snapshot = RateLimitSnapshot {
+        limit: 1,
+        remaining: 0,
+        reset_secs: 30,
+        retry_after_secs: 30,
+      };
+      if first { Ok(snapshot) } else { Err(snapshot) }
+    }
+  }
+
+  let store = OncePerKey::default();
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |_req: Request| async { "ok" });
+  RateLimiterBuilder::new()
+    .requests_per_second(100)
+    .key_fn(|_req| Some("shared".to_string()))
+    .store(store.clone())
+    .build()
+    .setup(&router)
+    .unwrap();
+
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["ratelimit-limit"], "1");
+  assert!(!resp.headers().contains_key("ratelimit-policy"));
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+  assert_eq!(resp.headers()["retry-after"], "30");
+  assert_eq!(*store.0.lock().unwrap(), ["shared", "shared"]);
+}
+
+#[tokio::test]
+async fn degrade_serves_fallback_on_5xx() {
+  use tako::middleware::degrade::Degrade;
+
+  let mut router = Router::new();
+  router
+    .route(Method::GET, "/recs", |_req: Request| async {
+      (StatusCode::INTERNAL_SERVER_ERROR, "boom")
+    })
+    .middleware(Degrade::json(serde_json::json!({ "items": [] })).into_middleware());
+
+  let resp = router.dispatch(make_req(Method::GET, "/recs")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["x-degraded"], "error");
+  assert_eq!(resp.headers()["cache-control"], "no-store");
+  assert_eq!(resp.headers()["content-type"], "application/json");
+  assert_eq!(body_str(resp).await, r#"{"items":[]}"#);
+}
+
+#[tokio::test]
+async fn degrade_replays_the_last_good_copy() {
+  use std::sync::Arc;
+  use std::sync::atomic::AtomicBool;
+  use std::sync::atomic::Ordering;
+
+  use tako::middleware::degrade::Degrade;
+
+  let failing = Arc::new(AtomicBool::new(false));
+  let mut router = Router::new();
+  let flag = failing.clone();
+  router
+    .route(Method::GET, "/feed", move |_req: Request| {
+      let flag = flag.clone();
+      async move {
+        if flag.load(Ordering::SeqCst) {
+          (StatusCode::BAD_GATEWAY, "upstream down")
+        } else {
+          (StatusCode::OK, "fresh")
+        }
+      }
+    })
+    .middleware(Degrade::last_good_only().into_middleware());
+
+  let resp = router.dispatch(make_req(Method::GET, "/feed")).await;
+  assert!(resp.headers().get("x-degraded").is_none());
+  assert_eq!(body_str(resp).await, "fresh");
+
+  failing.store(true, Ordering::SeqCst);
+  let resp = router.dispatch(make_req(Method::GET, "/feed")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["x-degraded"], "error");
+  assert!(resp.headers().contains_key("age"));
+  assert_eq!(body_str(resp).await, "fresh");
+
+  // No copy for another URL: the failure passes through.
+  let resp = router.dispatch(make_req(Method::GET, "/feed?page=2")).await;
+  assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+}
+
+#[tokio::test]
+async fn journal_records_writes_before_the_handler_and_replays_them() {
+  use std::io::Write as _;
+
+  use tako::middleware::journal::Journal;
+  use tako::middleware::journal::Journaled;
+
+  let path = std::env::temp_dir().join(format!("tako-journal-{}.log", std::process::id()));
+  let _ = std::fs::remove_file(&path);
+
+  let build = |journal: Journal| {
+    let mut router = Router::new();
+    router.post("/readings", |req: Request| async move {
+      let journaled = req.extensions().get::<Journaled>().copied().unwrap();
+      let body = req.into_body().collect().await.unwrap().to_bytes();
+      format!(
+        "{} {} {}",
+        journaled.seq,
+        journaled.replayed,
+        String::from_utf8_lossy(&body)
+      )
+    });
+    router.get("/readings", |req: Request| async move {
+      req.extensions().get::<Journaled>().is_none().to_string()
+    });
+    router.middleware(journal.into_middleware());
+    router
+  };
+
+  let router = build(Journal::open(&path).unwrap());
+  let mut req = make_req_with_body(Method::POST, "/readings?site=7", "t=21.5");
+  req
+    .headers_mut()
+    .insert("authorization", "Bearer secret".parse().unwrap());
+  req
+    .headers_mut()
+    .insert("content-type", "text/plain".parse().unwrap());
+  assert_eq!(body_str(router.dispatch(req).await).await, "1 false t=21.5");
+  let req = make_req_with_body(Method::POST, "/readings", "t=22.0");
+  assert_eq!(body_str(router.dispatch(req).await).await, "2 false t=22.0");
+  let req = make_req(Method::GET, "/readings");
+  assert_eq!(body_str(router.dispatch(req).await).await, "true");
+
+  let entries = Journal::read(&path).unwrap();
+  assert_eq!(entries.len(), 2);
+  assert_eq!(entries[0].uri, "/readings?site=7");
+  assert_eq!(&entries[0].body[..], b"t=21.5");
+  assert!(
+    entries[0]
+      .headers
+      .contains(&("content-type".to_string(), "text/plain".to_string()))
+  );
+  assert!(
+    entries[0]
+      .headers
+      .iter()
+      .all(|(name, _)| name != "authorization")
+  );
+
+  // A crash mid-write leaves a torn record; reopening cuts it off and
+  // continues the numbering.
+  std::fs::OpenOptions::new()
+    .append(true)
+    .open(&path)
+    .unwrap()
+    .write_all(b"{\"seq\":3,\"meth")
+    .unwrap();
+  assert_eq!(Journal::read(&path).unwrap().len(), 2);
+  let router = build(Journal::open(&path).unwrap());
+  let req = make_req_with_body(Method::POST, "/readings", "t=23.1");
+  assert_eq!(body_str(router.dispatch(req).await).await, "3 false t=23.1");
+  let entries = Journal::read(&path).unwrap();
+  assert_eq!(entries.iter().map(|e| e.seq).collect::<Vec<_>>(), [1, 2, 3]);
+
+  // Replayed requests carry their record's sequence number and are not
+  // journaled again.
+  let resp = router.dispatch(entries[0].to_request()).await;
+  assert_eq!(body_str(resp).await, "1 true t=21.5");
+  assert_eq!(Journal::read(&path).unwrap().len(), 3);
+
+  std::fs::remove_file(&path).unwrap();
+}
@@ -387,3 +387,134 @@ async fn exponential_backoff_retry() {
 
   queue.shutdown(Duration::from_secs(1)).await;
 }
+
+#[cfg(all(feature = "email", not(feature = "compio")))]
+#[tokio::test]
+async fn mailer_extractor_delivers_through_the_queue() {
+  use http_body_util::BodyExt;
+  use tako::Method;
+  use tako::body::TakoBody;
+  use tako::email::Mailer;
+  use tako::email::MemoryTransport;
+  use tako::email::Message;
+  use tako::router::Router;
+
+  let outbox = MemoryTransport::new();
+  let queue = Queue::new();
+  let mailer = Mailer::new(outbox.clone(), "Tako <noreply@example.com>").with_queue(&queue);
+  queue.start();
+
+  let mut router = Router::new();
+  router.with_state(mailer);
+  router.route(Method::POST, "/signup", |mailer: Mailer| async move {
+    let msg = Message::new()
+      .to("alice@example.com")
+      .subject("Welcome")
+      .text("Thanks for signing up.");
+    match mailer.enqueue(msg).await {
+      Ok(()) => "queued",
+      Err(_) => "failed",
+    }
+  });
+
+  let req = http::Request::builder()
+    .method(Method::POST)
+    .uri("/signup")
+    .body(TakoBody::empty())
+    .unwrap();
+  let resp = router.dispatch(req).await;
+  let body = resp.into_body().collect().await.unwrap().to_bytes();
+  assert_eq!(&body[..], b"queued");
+
+  tokio::time::sleep(Duration::from_millis(300)).await;
+  let sent = outbox.messages();
+  assert_eq!(sent.len(), 1);
+  assert_eq!(sent[0].from.as_deref(), Some("Tako <noreply@example.com>"));
+  assert_eq!(sent[0].subject, "Welcome");
+
+  queue.shutdown(Duration::from_secs(1)).await;
+}
+
+#[cfg(not(feature = "compio"))]
+#[tokio::test]
+async fn accepted_job_reports_status_and_progress() {
+  use http_body_util::BodyExt;
+  use tako::Method;
+  use tako::body::TakoBody;
+  use tako::queue::AcceptedJobs;
+  use tako::router::Router;
+  use tako::types::Request;
+
+  let queue = Queue::new();
+  let jobs = AcceptedJobs::new(queue.clone());
+  let (release_tx, release_rx) = tokio::sync::watch::channel(false);
+  jobs.register("square", move |job| {
+    let mut release = release_rx.clone();
+    async move {
+      let n: u64 = job.deserialize()?;
+      job.progress(40);
+      job.message("crunching");
+      let _ = release.wait_for(|go| *go).await;
+      Ok(n * n)
+    }
+  });
+  queue.start();
+
+  let mut router = Router::new();
+  jobs.routes(&mut router);
+  router.route(Method::POST, "/square", {
+    let jobs = jobs.clone();
+    move |_req: Request| {
+      let jobs = jobs.clone();
+      async move { jobs.accept("square", &7u64).await }
+    }
+  });
+
+  let get = |uri: &str| {
+    http::Request::builder()
+      .method(Method::GET)
+      .uri(uri)
+      .body(TakoBody::empty())
+      .unwrap()
+  };
+  let json = |resp: tako::types::Response| async move {
+    let body = resp.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+  };
+
+  let post = http::Request::builder()
+    .method(Method::POST)
+    .uri("/square")
+    .body(TakoBody::empty())
+    .unwrap();
+  let resp = router.dispatch(post).await;
+  assert_eq!(resp.status(), http::StatusCode::ACCEPTED);
+  let location = resp.headers()["location"].to_str().unwrap().to_string();
+  assert!(location.starts_with("/jobs/"));
+  assert_eq!(json(resp).await["status_url"], location.as_str());
+
+  tokio::time::sleep(Duration::from_millis(100)).await;
+  let resp = router.dispatch(get(&location)).await;
+  assert!(resp.headers().contains_key("retry-after"));
+  let running = json(resp).await;
+  assert_eq!(running["status"], "running");
+  assert_eq!(running["progress"], 40);
+  assert_eq!(running["message"], "crunching");
+
+  let events = router.dispatch(get(&format!("{location}/events"))).await;
+  assert_eq!(events.headers()["content-type"], "text/event-stream");
+  release_tx.send(true).unwrap();
+  let stream = events.into_body().collect().await.unwrap().to_bytes();
+  let stream = std::str::from_utf8(&stream).unwrap();
+  assert!(stream.starts_with("event: progress\ndata: {"));
+  assert!(stream.contains(r#""status":"succeeded""#));
+
+  let done = json(router.dispatch(get(&location)).await).await;
+  assert_eq!(done["status"], "succeeded");
+  assert_eq!(done["result"], 49);
+
+  let resp = router.dispatch(get("/jobs/unknown")).await;
+  assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+  queue.shutdown(Duration::from_secs(1)).await;
+}
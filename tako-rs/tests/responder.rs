@@ -95,3 +95,218 @@ async fn response_passthrough() {
   assert_eq!(resp.headers().get("x-custom").unwrap(), "yes");
   assert_eq!(body_str(resp).await, "created");
 }
+
+#[tokio::test]
+async fn cached_json_answers_matching_if_none_match_with_304() {
+  use http::Method;
+  use tako::conditional::CachedJson;
+  use tako::router::Router;
+
+  let mut router = Router::new();
+  let catalog = || async { CachedJson(serde_json::json!({ "items": ["tea"] })) };
+  router.route(Method::GET, "/catalog", catalog);
+  router.route(Method::POST, "/catalog", catalog);
+
+  let req = |method: Method, inm: Option<&str>| {
+    let mut builder = http::Request::builder().method(method).uri("/catalog");
+    if let Some(inm) = inm {
+      builder = builder.header("if-none-match", inm);
+    }
+    builder.body(TakoBody::empty()).unwrap()
+  };
+
+  let resp = router.dispatch(req(Method::GET, None)).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["content-type"], "application/json");
+  let etag = resp.headers()["etag"].to_str().unwrap().to_string();
+  assert!(etag.starts_with('"'));
+  assert_eq!(body_str(resp).await, r#"{"items":["tea"]}"#);
+
+  let resp = router.dispatch(req(Method::GET, Some(&etag))).await;
+  assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+  assert_eq!(resp.headers()["etag"], etag.as_str());
+  assert_eq!(body_str(resp).await, "");
+
+  let weak = format!("\"stale\", W/{etag}");
+  let resp = router.dispatch(req(Method::GET, Some(&weak))).await;
+  assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+  let resp = router.dispatch(req(Method::GET, Some("\"stale\""))).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+
+  // Unsafe methods never get a 304.
+  let resp = router.dispatch(req(Method::POST, Some(&etag))).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn json_lines_streams_one_document_per_line() {
+  use futures_util::stream;
+  use tako::responder::JsonLines;
+
+  let rows = stream::iter([
+    serde_json::json!({"id": 1}),
+    serde_json::json!({"id": 2, "tags": ["a\nb"]}),
+  ]);
+  let resp = JsonLines::new(rows).into_response();
+  assert_eq!(
+    resp.headers().get("content-type").unwrap(),
+    "application/x-ndjson"
+  );
+
+  let mut body = resp.into_body();
+  let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+  assert_eq!(&first[..], b"{\"id\":1}\n");
+  let second = body.frame().await.unwrap().unwrap().into_data().unwrap();
+  assert_eq!(&second[..], b"{\"id\":2,\"tags\":[\"a\\nb\"]}\n");
+  assert!(body.frame().await.is_none());
+
+  let rows = stream::iter([Ok(1), Err(std::io::Error::other("cursor lost"))]);
+  let mut body = JsonLines::try_new(rows).into_response().into_body();
+  let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+  assert_eq!(&first[..], b"1\n");
+  assert!(body.frame().await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn cache_control_composes_with_any_responder() {
+  use std::time::Duration;
+  use tako::responder::CacheControl;
+
+  let cache = CacheControl::new()
+    .public()
+    .max_age(Duration::from_secs(60))
+    .s_maxage(Duration::from_secs(600))
+    .stale_while_revalidate(Duration::from_secs(30));
+  let resp = (cache, (StatusCode::CREATED, "made")).into_response();
+  assert_eq!(resp.status(), StatusCode::CREATED);
+  assert_eq!(
+    resp.headers()["cache-control"],
+    "public, max-age=60, s-maxage=600, stale-while-revalidate=30"
+  );
+  assert_eq!(body_str(resp).await, "made");
+
+  // `private` replaces `public`; the builder's value replaces the inner one.
+  let mut headers = http::HeaderMap::new();
+  headers.insert("cache-control", "max-age=1".parse().unwrap());
+  CacheControl::new()
+    .public()
+    .private()
+    .no_store()
+    .apply_to(&mut headers);
+  let resp = (StatusCode::OK, headers).into_response();
+  assert_eq!(resp.headers()["cache-control"], "private, no-store");
+  assert_eq!(CacheControl::new().to_string(), "");
+}
+
+#[tokio::test]
+async fn combinators_tweak_status_headers_and_body() {
+  let resp = "ok"
+    .with_status(201)
+    .with_header("x-id", 7_u64)
+    .with_header(http::header::CACHE_CONTROL, "no-store");
+  assert_eq!(resp.status(), StatusCode::CREATED);
+  assert_eq!(resp.headers()["x-id"], "7");
+  assert_eq!(resp.headers()["cache-control"], "no-store");
+
+  let resp = (StatusCode::NOT_FOUND, "gone")
+    .with_status(1000)
+    .with_header("bad header", "x")
+    .map_body(|_| TakoBody::from("replaced"));
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  assert!(!resp.headers().contains_key("bad header"));
+  assert_eq!(body_str(resp).await, "replaced");
+}
+
+#[tokio::test]
+async fn multipart_mixed_frames_each_part() {
+  use futures_util::stream;
+  use http::HeaderName;
+  use http::HeaderValue;
+  use tako::responder::MultipartMixed;
+  use tako::responder::Part;
+
+  let streamed = TakoBody::from_stream(stream::iter([
+    Ok::<_, std::io::Error>(bytes::Bytes::from_static(b"a,")),
+    Ok(bytes::Bytes::from_static(b"b")),
+  ]));
+  let resp = MultipartMixed::new([
+    Part::json(&serde_json::json!({"id": 1})).unwrap().header(
+      HeaderName::from_static("content-id"),
+      HeaderValue::from_static("<user>"),
+    ),
+    Part::new("text/csv", streamed),
+  ])
+  .boundary("b0und")
+  .into_response();
+  assert_eq!(
+    resp.headers().get("content-type").unwrap(),
+    "multipart/mixed; boundary=\"b0und\""
+  );
+  let body = resp.into_body().collect().await.unwrap().to_bytes();
+  assert_eq!(
+    &body[..],
+    b"--b0und\r\ncontent-type: application/json\r\ncontent-id: <user>\r\n\r\n{\"id\":1}\r\n\
+      --b0und\r\ncontent-type: text/csv\r\n\r\na,b\r\n\
+      --b0und--\r\n"
+  );
+
+  let first = MultipartMixed::new([]).into_response();
+  let second = MultipartMixed::new([]).into_response();
+  assert_ne!(
+    first.headers().get("content-type"),
+    second.headers().get("content-type")
+  );
+}
+
+#[derive(Debug)]
+enum AccountError {
+  NotFound(u64),
+  Ledger,
+}
+
+impl std::fmt::Display for AccountError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::NotFound(id) => write!(f, "no account {id}"),
+      Self::Ledger => f.write_str("ledger disk full"),
+    }
+  }
+}
+
+impl std::error::Error for AccountError {}
+
+impl tako::error::IntoStatus for AccountError {
+  fn status(&self) -> StatusCode {
+    match self {
+      Self::NotFound(_) => StatusCode::NOT_FOUND,
+      Self::Ledger => StatusCode::SERVICE_UNAVAILABLE,
+    }
+  }
+}
+
+#[tokio::test]
+async fn result_renders_any_responder_error_and_mapped_statuses() {
+  let plain: Result<&'static str, (StatusCode, &'static str)> =
+    Err((StatusCode::FORBIDDEN, "nope"));
+  let resp = plain.into_response();
+  assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+  assert_eq!(body_str(resp).await, "nope");
+
+  let ok: Result<&'static str, AccountError> = Ok("42");
+  assert_eq!(body_str(ok.into_response()).await, "42");
+
+  let missing: Result<&'static str, AccountError> = Err(AccountError::NotFound(7));
+  let resp = missing.into_response();
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  let error = resp.extensions().get::<tako::Error>().unwrap();
+  assert_eq!(error.message(), "no account 7");
+  assert!(body_str(resp).await.contains("no account 7"));
+
+  let down: Result<&'static str, AccountError> = Err(AccountError::Ledger);
+  let resp = down.into_response();
+  assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+  let body = body_str(resp).await;
+  assert!(body.contains("Service Unavailable"));
+  assert!(!body.contains("disk full"));
+}
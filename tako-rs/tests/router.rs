@@ -73,6 +73,33 @@ async fn custom_fallback() {
   assert_eq!(body_str(resp).await, "Custom 404");
 }
 
+#[tokio::test]
+async fn custom_method_not_allowed() {
+  use tako::router::AllowedMethods;
+
+  let mut router = Router::new();
+  router.route(Method::GET, "/hello", |_req: Request| async { "Hello" });
+  router.route(Method::PUT, "/hello", |_req: Request| async { "Put" });
+  router.fallback(|_req: Request| async { (StatusCode::NOT_FOUND, "Custom 404") });
+  router.method_not_allowed(|req: Request| async move {
+    let allowed = req.extensions().get::<AllowedMethods>().unwrap();
+    (
+      StatusCode::METHOD_NOT_ALLOWED,
+      format!("{} methods", allowed.0.len()),
+    )
+  });
+
+  let resp = router.dispatch(make_req(Method::POST, "/hello")).await;
+  assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+  let allow = resp.headers().get(http::header::ALLOW).unwrap();
+  assert!(allow.to_str().unwrap().contains("GET"));
+  assert_eq!(body_str(resp).await, "2 methods");
+
+  // Unknown paths still reach the fallback.
+  let resp = router.dispatch(make_req(Method::POST, "/nope")).await;
+  assert_eq!(body_str(resp).await, "Custom 404");
+}
+
 #[tokio::test]
 async fn tsr_redirect() {
   let mut router = Router::new();
@@ -186,6 +213,240 @@ async fn error_handler_ignores_non_5xx() {
   assert_eq!(body_str(resp).await, "ok");
 }
 
+#[tokio::test]
+async fn tako_error_renders_json_envelope() {
+  let mut router = Router::new();
+  router.get("/missing", |_req: Request| async {
+    Err::<&str, _>(tako::Error::not_found("no user 7").with_details(serde_json::json!({"id": 7})))
+  });
+  router.get("/broken", |_req: Request| async {
+    let n: u32 = "nope".parse()?;
+    Ok::<_, tako::Error>(n.to_string())
+  });
+
+  let resp = router.dispatch(make_req(Method::GET, "/missing")).await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  let body: serde_json::Value = serde_json::from_str(&body_str(resp).await).unwrap();
+  assert_eq!(
+    body,
+    serde_json::json!({"error": {
+      "kind": "not_found", "status": 404, "message": "no user 7", "details": {"id": 7}
+    }})
+  );
+
+  // `?` on a std error becomes a 500 that doesn't leak the cause.
+  let resp = router.dispatch(make_req(Method::GET, "/broken")).await;
+  assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+  let error = resp.extensions().get::<tako::Error>().unwrap();
+  assert_eq!(error.kind(), tako::error::ErrorKind::Internal);
+  assert!(
+    error
+      .source()
+      .unwrap()
+      .to_string()
+      .contains("invalid digit")
+  );
+  assert!(!body_str(resp).await.contains("invalid digit"));
+}
+
+#[tokio::test]
+async fn error_renderer_covers_rejections_and_anyhow() {
+  use tako::error::ErrorKind;
+  use tako::extractors::params::Params;
+  use tako::responder::Responder;
+
+  #[derive(serde::Deserialize)]
+  struct UserPath {
+    id: u64,
+  }
+
+  let mut router = Router::new();
+  router.get("/users/{id}", |Params(p): Params<UserPath>| async move {
+    p.id.to_string()
+  });
+  router.get("/anyhow", |_req: Request| async {
+    Err::<&str, _>(anyhow::anyhow!("db down"))
+  });
+  router.get("/conflict", |_req: Request| async {
+    Err::<&str, _>(tako::Error::conflict("taken"))
+  });
+  router.error_renderer(|err| match err.kind() {
+    ErrorKind::Conflict => None,
+    kind => Some(
+      (
+        err.status(),
+        format!("{kind}|{}|{}", err.is_rejection(), err.status().as_u16()),
+      )
+        .into_response(),
+    ),
+  });
+
+  let resp = router.dispatch(make_req(Method::GET, "/users/abc")).await;
+  assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  assert!(
+    resp
+      .extensions()
+      .get::<tako::Error>()
+      .unwrap()
+      .is_rejection()
+  );
+  assert_eq!(body_str(resp).await, "bad_request|true|400");
+
+  let resp = router.dispatch(make_req(Method::GET, "/anyhow")).await;
+  assert_eq!(body_str(resp).await, "internal|false|500");
+
+  // `None` keeps the default envelope.
+  let resp = router.dispatch(make_req(Method::GET, "/conflict")).await;
+  assert_eq!(resp.status(), StatusCode::CONFLICT);
+  assert!(body_str(resp).await.contains("\"kind\":\"conflict\""));
+
+  // Untagged responses, like the router's own 404, are left alone.
+  let resp = router.dispatch(make_req(Method::GET, "/nowhere")).await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  assert!(resp.extensions().get::<tako::Error>().is_none());
+}
+
+#[tokio::test]
+async fn app_error_carries_its_code() {
+  use tako::error::codes;
+  use tako::error::codes::AppError;
+  use tako::error::codes::ErrorCode;
+
+  const CARD_DECLINED: ErrorCode = ErrorCode::new(
+    "card_declined",
+    StatusCode::PAYMENT_REQUIRED,
+    "The card was declined",
+  )
+  .docs("https://docs.example.com/errors/card_declined");
+  codes::register([CARD_DECLINED]);
+
+  let mut router = Router::new();
+  router.get("/pay", |_req: Request| async {
+    Err::<&str, _>(AppError::new(CARD_DECLINED).details(serde_json::json!({"retry": false})))
+  });
+  router.get("/checkout", |_req: Request| async {
+    let declined: Result<(), AppError> =
+      Err(AppError::new(CARD_DECLINED).message("insufficient funds"));
+    declined?;
+    Ok::<_, tako::Error>("paid")
+  });
+
+  let resp = router.dispatch(make_req(Method::GET, "/pay")).await;
+  assert_eq!(resp.status(), StatusCode::PAYMENT_REQUIRED);
+  let error = resp.extensions().get::<tako::Error>().unwrap();
+  assert_eq!(error.code(), Some(CARD_DECLINED));
+  let body: serde_json::Value = serde_json::from_str(&body_str(resp).await).unwrap();
+  assert_eq!(
+    body,
+    serde_json::json!({"error": {
+      "kind": "error", "status": 402, "message": "The card was declined",
+      "code": "card_declined", "docs": "https://docs.example.com/errors/card_declined",
+      "details": {"retry": false}
+    }})
+  );
+
+  let resp = router.dispatch(make_req(Method::GET, "/checkout")).await;
+  assert!(
+    body_str(resp)
+      .await
+      .contains("\"message\":\"insufficient funds\"")
+  );
+
+  assert_eq!(codes::lookup("card_declined"), Some(CARD_DECLINED));
+  let schema = codes::schema();
+  assert!(
+    schema["description"]
+      .as_str()
+      .unwrap()
+      .contains("| `card_declined` | 402 | [The card was declined](https://docs.example.com/errors/card_declined) |")
+  );
+}
+
+#[tokio::test]
+async fn static_response_serves_a_fresh_copy_per_request() {
+  let mut headers = http::HeaderMap::new();
+  headers.insert("content-type", "text/plain".parse().unwrap());
+  headers.insert("x-build", "abc123".parse().unwrap());
+
+  let mut router = Router::new();
+  router.static_response(
+    Method::GET,
+    "/version",
+    StatusCode::OK,
+    headers.clone(),
+    "1.4.2",
+  );
+  router
+    .static_response(Method::GET, "/banner", StatusCode::ACCEPTED, headers, "hi")
+    .middleware(|req, next| async move {
+      let mut resp = next.run(req).await;
+      resp
+        .headers_mut()
+        .insert("x-route", "banner".parse().unwrap());
+      resp
+    });
+
+  for _ in 0..2 {
+    let resp = router.dispatch(make_req(Method::GET, "/version")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["x-build"], "abc123");
+    assert_eq!(body_str(resp).await, "1.4.2");
+  }
+
+  let resp = router.dispatch(make_req(Method::GET, "/banner")).await;
+  assert_eq!(resp.status(), StatusCode::ACCEPTED);
+  assert_eq!(resp.headers()["x-route"], "banner");
+  assert_eq!(resp.headers()["content-type"], "text/plain");
+  assert_eq!(body_str(resp).await, "hi");
+
+  let resp = router.dispatch(make_req(Method::POST, "/version")).await;
+  assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn check_reload_diffs_routes_and_vetoes_unexpected_removals() {
+  let handler = |_req: Request| async { "ok" };
+  let mut old = Router::new();
+  old.get("/users", handler);
+  old.get("/legacy", handler);
+  old.get("/reports", handler);
+  old.post("/orders", handler);
+
+  let mut new = Router::new();
+  new.get("/users", handler).timeout(Duration::from_secs(5));
+  new.get("/health", handler);
+  new.post("/orders", handler);
+  new.middleware(|req, next| async move { next.run(req).await });
+
+  let diff = old.diff(&new);
+  assert_eq!(diff.added, vec![(Method::GET, "/health".to_string())]);
+  assert_eq!(
+    diff.removed,
+    vec![
+      (Method::GET, "/legacy".to_string()),
+      (Method::GET, "/reports".to_string()),
+    ]
+  );
+  assert_eq!(diff.changed.len(), 1);
+  assert_eq!(diff.changed[0].path, "/users");
+  assert_eq!(diff.changed[0].details, vec!["timeout none -> 5s"]);
+  assert_eq!(diff.middleware, Some((0, 1)));
+  assert!(diff.to_string().contains("- GET /legacy"));
+
+  let vetoed = old
+    .check_reload(&new, &[(Method::GET, "/legacy")])
+    .await
+    .unwrap_err();
+  assert_eq!(
+    vetoed.unexpected,
+    vec![(Method::GET, "/reports".to_string())]
+  );
+
+  let allowed = [(Method::GET, "/legacy"), (Method::GET, "/reports")];
+  assert!(old.check_reload(&new, &allowed).await.is_ok());
+  assert!(old.diff(&old).is_empty());
+}
+
 #[tokio::test]
 async fn merge_routers() {
   let mut sub = Router::new();
@@ -219,6 +480,35 @@ async fn multiple_routes_different_methods() {
   assert_eq!(body_str(resp_post).await, "post");
 }
 
+#[tokio::test]
+async fn path_builder_registers_methods_and_shares_middleware() {
+  let mut router = Router::new();
+  let routes = router
+    .path("/users/{id}")
+    .get(|_req: Request| async { "show" })
+    .put(|_req: Request| async { "update" })
+    .delete(|_req: Request| async { "remove" })
+    .middleware(|req: Request, next: tako::middleware::Next| async move {
+      let mut resp = next.run(req).await;
+      resp.headers_mut().insert("x-users", "1".parse().unwrap());
+      resp
+    })
+    .routes()
+    .len();
+  assert_eq!(routes, 3);
+
+  let resp = router.dispatch(make_req(Method::PUT, "/users/7")).await;
+  assert_eq!(resp.headers().get("x-users").unwrap(), "1");
+  assert_eq!(body_str(resp).await, "update");
+
+  let resp = router.dispatch(make_req(Method::POST, "/users/7")).await;
+  assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+  assert_eq!(
+    resp.headers().get(http::header::ALLOW).unwrap(),
+    "GET, PUT, DELETE"
+  );
+}
+
 #[tokio::test]
 async fn route_level_middleware_runs() {
   let mut router = Router::new();
@@ -595,6 +885,209 @@ async fn nest_does_not_double_stack_middleware_on_re_nest() {
   );
 }
 
+#[cfg(feature = "plugins")]
+#[derive(Clone)]
+struct LabelPlugin {
+  label: &'static str,
+  events: Arc<Mutex<Vec<&'static str>>>,
+}
+
+#[cfg(feature = "plugins")]
+impl TakoPlugin for LabelPlugin {
+  fn name(&self) -> &'static str {
+    "label-plugin"
+  }
+
+  fn setup(&self, router: &TakoPluginRouter) -> anyhow::Result<()> {
+    let label = self.label;
+    let events = Arc::clone(&self.events);
+    router.middleware(move |req: Request, next: tako::middleware::Next| {
+      events.lock().unwrap().push(label);
+      next.run(req)
+    });
+    Ok(())
+  }
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn nest_carries_child_plugins_onto_child_routes_only() {
+  let events = Arc::new(Mutex::new(Vec::<&'static str>::new()));
+  let log = |label: &'static str| {
+    let events = Arc::clone(&events);
+    move |req: Request, next: tako::middleware::Next| {
+      events.lock().unwrap().push(label);
+      next.run(req)
+    }
+  };
+  let plugin = |label: &'static str| LabelPlugin {
+    label,
+    events: Arc::clone(&events),
+  };
+
+  let mut users = Router::new();
+  users
+    .get("/{id}", |_req: Request| async { "user" })
+    .plugin(plugin("route-plugin"));
+  users.middleware(log("users-global"));
+  users.plugin(plugin("users-plugin"));
+
+  let mut api = Router::new();
+  api.middleware(log("api-global"));
+  api.plugin(plugin("api-plugin"));
+  api.nest("/users", users);
+
+  let mut root = Router::new();
+  root.get("/health", |_req: Request| async { "ok" });
+  root.nest("/api", api);
+  root.setup_plugins_once();
+
+  let resp = root.dispatch(make_req(Method::GET, "/api/users/7")).await;
+  assert_eq!(body_str(resp).await, "user");
+  assert_eq!(
+    std::mem::take(&mut *events.lock().unwrap()),
+    [
+      "api-global",
+      "api-plugin",
+      "users-global",
+      "users-plugin",
+      "route-plugin"
+    ]
+  );
+
+  let resp = root.dispatch(make_req(Method::GET, "/health")).await;
+  assert_eq!(body_str(resp).await, "ok");
+  assert!(events.lock().unwrap().is_empty());
+}
+
+#[cfg(feature = "plugins")]
+#[derive(Clone)]
+struct FailingPlugin;
+
+#[cfg(feature = "plugins")]
+impl TakoPlugin for FailingPlugin {
+  fn name(&self) -> &'static str {
+    "failing-plugin"
+  }
+
+  fn setup(&self, _router: &TakoPluginRouter) -> anyhow::Result<()> {
+    anyhow::bail!("missing secret")
+  }
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn plugin_status_reports_failures_and_blocks_startup() {
+  use tako::plugins::PluginScope;
+
+  let events = Arc::new(Mutex::new(Vec::<&'static str>::new()));
+  let mut child = Router::new();
+  child.get("/", |_req: Request| async { "child" });
+  child.plugin(FailingPlugin);
+
+  let mut router = Router::new();
+  router.plugin(LabelPlugin {
+    label: "label",
+    events: Arc::clone(&events),
+  });
+  router.nest("/child", child);
+  router.fail_on_plugin_error(true);
+  assert!(router.plugin_status().is_empty());
+
+  let err = router.initialize_plugins().await.unwrap_err();
+  assert_eq!(err.failed.len(), 1);
+  assert!(err.to_string().contains("failing-plugin (missing secret)"));
+
+  let status = router.plugin_status();
+  assert_eq!(status.len(), 2);
+  assert!(status[0].is_active() && status[0].scope == PluginScope::Router);
+  assert_eq!(status[1].name, "failing-plugin");
+  assert_eq!(status[1].scope, PluginScope::Nested);
+  assert_eq!(status[1].error.as_deref(), Some("missing secret"));
+
+  // Lenient by default: the failure is only recorded.
+  let mut lenient = Router::new();
+  lenient.plugin(FailingPlugin);
+  lenient.initialize_plugins().await.unwrap();
+  assert!(!lenient.plugin_status()[0].is_active());
+}
+
+#[cfg(feature = "plugins")]
+#[derive(Clone)]
+struct PriorityPlugin {
+  label: &'static str,
+  priority: i32,
+  events: Arc<Mutex<Vec<&'static str>>>,
+}
+
+#[cfg(feature = "plugins")]
+impl TakoPlugin for PriorityPlugin {
+  fn name(&self) -> &'static str {
+    self.label
+  }
+
+  fn priority(&self) -> i32 {
+    self.priority
+  }
+
+  fn setup(&self, router: &TakoPluginRouter) -> anyhow::Result<()> {
+    let label = self.label;
+    let events = Arc::clone(&self.events);
+    router.middleware(move |req: Request, next: tako::middleware::Next| {
+      events.lock().unwrap().push(label);
+      next.run(req)
+    });
+    Ok(())
+  }
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn middleware_and_plugins_run_in_priority_order() {
+  use tako::middleware::priority;
+
+  let events = Arc::new(Mutex::new(Vec::<&'static str>::new()));
+  let log = |label: &'static str| {
+    let events = Arc::clone(&events);
+    move |req: Request, next: tako::middleware::Next| {
+      events.lock().unwrap().push(label);
+      next.run(req)
+    }
+  };
+  let plugin = |label: &'static str, priority: i32| PriorityPlugin {
+    label,
+    priority,
+    events: Arc::clone(&events),
+  };
+
+  let mut router = Router::new();
+  router.get("/", |_req: Request| async { "ok" });
+  router.middleware(log("auth"));
+  router.middleware_with_priority(priority::LAST, log("last"));
+  router.middleware(log("etag"));
+  router.middleware_with_priority(priority::FIRST, log("first"));
+  router.plugin(plugin("compression", priority::COMPRESSION));
+  router.plugin(plugin("late-plugin", 10));
+  router.plugin(plugin("cors", priority::CORS));
+  router.setup_plugins_once();
+
+  router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(
+    *events.lock().unwrap(),
+    [
+      "first",
+      "cors",
+      "compression",
+      "auth",
+      "etag",
+      "late-plugin",
+      "last"
+    ]
+  );
+  let names: Vec<_> = router.plugin_status().iter().map(|s| s.name).collect();
+  assert_eq!(names, ["cors", "compression", "late-plugin"]);
+}
+
 #[tokio::test]
 async fn with_state_isolates_two_routers_in_same_process() {
   // Each router holds its own `String` state, distinct from the other and
@@ -621,6 +1114,45 @@ async fn with_state_isolates_two_routers_in_same_process() {
   assert_eq!(body_str(resp_b).await, "router-b");
 }
 
+#[tokio::test]
+async fn nested_router_state_shadows_parent_for_its_routes_only() {
+  use tako::extractors::state::State;
+
+  #[derive(Clone)]
+  struct Db(&'static str);
+
+  async fn describe(
+    State(s): State<String>,
+    State(db): State<Db>,
+  ) -> impl tako::responder::Responder {
+    format!("{}:{}", s, db.0)
+  }
+
+  let mut admin = Router::new();
+  admin.with_state::<String>("admin".to_string());
+  admin.get("/whoami", describe);
+
+  let mut api = Router::new();
+  api.get("/whoami", describe);
+  api.nest("/admin", admin);
+
+  let mut root = Router::new();
+  root.get("/whoami", describe);
+  root.nest("/api", api);
+  // Inserted after nesting: still visible through the parent link.
+  root.with_state::<String>("root".to_string());
+  root.with_state(Db("main"));
+
+  for (path, expected) in [
+    ("/whoami", "root:main"),
+    ("/api/whoami", "root:main"),
+    ("/api/admin/whoami", "admin:main"),
+  ] {
+    let resp = root.dispatch(make_req(Method::GET, path)).await;
+    assert_eq!(body_str(resp).await, expected, "{path}");
+  }
+}
+
 #[derive(Clone)]
 struct GlobalOnly(&'static str);
 
@@ -665,3 +1197,666 @@ async fn scope_groups_routes_under_prefix() {
   assert_eq!(resp.status(), StatusCode::OK);
   assert_eq!(body_str(resp).await, "dashboard");
 }
+
+#[tokio::test]
+async fn scope_middleware_applies_to_scoped_routes_only() {
+  fn tag(
+    log: &Arc<Mutex<Vec<&'static str>>>,
+    name: &'static str,
+  ) -> impl Fn(
+    Request,
+    tako::middleware::Next,
+  ) -> std::pin::Pin<Box<dyn std::future::Future<Output = tako::types::Response> + Send>>
+  + Clone
+  + Send
+  + Sync
+  + 'static {
+    let log = Arc::clone(log);
+    move |req, next| {
+      log.lock().unwrap().push(name);
+      Box::pin(next.run(req))
+    }
+  }
+
+  let log = Arc::new(Mutex::new(Vec::new()));
+  let mut router = Router::new();
+  router.middleware(tag(&log, "global"));
+  router.get("/public", |_req: Request| async { "public" });
+  router.scope("/admin", |r| {
+    r.get("/stats", |_req: Request| async { "stats" })
+      .middleware(tag(&log, "route"));
+    r.middleware(tag(&log, "admin"));
+    r.scope("/audit", |r2| {
+      r2.middleware(tag(&log, "audit"));
+      r2.get("/log", |_req: Request| async { "log" });
+    });
+  });
+  router.get("/after", |_req: Request| async { "after" });
+
+  let take = |log: &Arc<Mutex<Vec<&'static str>>>| std::mem::take(&mut *log.lock().unwrap());
+
+  router.dispatch(make_req(Method::GET, "/public")).await;
+  assert_eq!(take(&log), ["global"]);
+  router.dispatch(make_req(Method::GET, "/after")).await;
+  assert_eq!(take(&log), ["global"]);
+  router.dispatch(make_req(Method::GET, "/admin/stats")).await;
+  assert_eq!(take(&log), ["global", "admin", "route"]);
+  let resp = router
+    .dispatch(make_req(Method::GET, "/admin/audit/log"))
+    .await;
+  assert_eq!(body_str(resp).await, "log");
+  assert_eq!(take(&log), ["global", "admin", "audit"]);
+}
+
+#[tokio::test]
+async fn static_export_writes_get_routes() {
+  use tako::export::StaticExport;
+  use tako::extractors::params::Params;
+
+  #[derive(serde::Deserialize)]
+  struct Post {
+    slug: String,
+  }
+
+  let mut router = Router::new();
+  router.get("/", |_req: Request| async { "home" });
+  router.get("/feed.xml", |_req: Request| async { "<rss/>" });
+  router.get("/posts/{slug}", |Params(p): Params<Post>| async move {
+    format!("post {}", p.slug)
+  });
+  router.get("/users/{id}", |_req: Request| async { "user" });
+  router.get("/broken", |_req: Request| async {
+    StatusCode::INTERNAL_SERVER_ERROR
+  });
+  router.post("/form", |_req: Request| async { "posted" });
+
+  let out = std::env::temp_dir().join(format!("tako-export-{}", std::process::id()));
+  let report = StaticExport::new(&out)
+    .params(
+      "/posts/{slug}",
+      [
+        [("slug", "hello")],
+        [("slug", "world")],
+        [("slug", "what? no")],
+      ],
+    )
+    .run(&router)
+    .await
+    .unwrap();
+
+  let read = |p: &str| std::fs::read_to_string(out.join(p)).unwrap();
+  assert_eq!(read("index.html"), "home");
+  assert_eq!(read("feed.xml"), "<rss/>");
+  assert_eq!(read("posts/hello/index.html"), "post hello");
+  assert_eq!(read("posts/world/index.html"), "post world");
+  assert!(out.join("posts/what? no/index.html").is_file());
+  assert_eq!(report.written.len(), 5);
+  assert_eq!(report.skipped, vec!["/users/{id}".to_string()]);
+  assert_eq!(
+    report.failed,
+    vec![("/broken".to_string(), StatusCode::INTERNAL_SERVER_ERROR)]
+  );
+  std::fs::remove_dir_all(&out).unwrap();
+}
+
+#[test]
+fn route_table_lists_routes_sorted_with_nest_prefixes() {
+  let mut child = Router::new();
+  child.get("/users/{id}", |_req: Request| async { "user" });
+  child.delete("/users/{id}", |_req: Request| async { "gone" });
+
+  let mut router = Router::new();
+  router.post("/login", |_req: Request| async { "ok" });
+  router.get("/", |_req: Request| async { "home" });
+  router.nest("/api", child);
+
+  let table: Vec<(String, String)> = router
+    .route_table()
+    .into_iter()
+    .map(|(m, p)| (m.to_string(), p))
+    .collect();
+  let expect = [
+    ("GET", "/"),
+    ("DELETE", "/api/users/{id}"),
+    ("GET", "/api/users/{id}"),
+    ("POST", "/login"),
+  ];
+  assert_eq!(table, expect.map(|(m, p)| (m.to_string(), p.to_string())));
+}
+
+#[cfg(feature = "vespera")]
+#[test]
+fn openapi_metadata_is_derived_from_extractors() {
+  use serde::Deserialize;
+  use tako::extractors::json::Json;
+  use tako::extractors::path::Path;
+  use tako::extractors::query::Query;
+  use tako::openapi::OpenApiParameter;
+  use tako::openapi::ParameterLocation;
+
+  #[derive(Deserialize)]
+  struct UserId {
+    #[allow(dead_code)]
+    id: u64,
+  }
+
+  #[allow(dead_code)]
+  #[derive(Deserialize)]
+  struct Notify {
+    email: Option<bool>,
+  }
+
+  #[allow(dead_code)]
+  #[derive(Deserialize)]
+  struct Patch {
+    name: String,
+    age: u32,
+  }
+
+  let mut router = Router::new();
+  router
+    .route(
+      Method::PUT,
+      "/users/{id}",
+      |_: Path<UserId>, _: Query<Notify>, _: Json<Patch>| async { "ok" },
+    )
+    .parameter(OpenApiParameter {
+      name: "email".into(),
+      location: ParameterLocation::Query,
+      description: Some("Send a notification email".into()),
+      required: false,
+      kind: None,
+    });
+
+  let (_, _, openapi) = router.collect_openapi_routes().remove(0);
+  let params: Vec<_> = openapi
+    .parameters
+    .iter()
+    .map(|p| (p.name.as_str(), p.location.clone(), p.required))
+    .collect();
+  assert_eq!(
+    params,
+    [
+      ("id", ParameterLocation::Path, true),
+      ("email", ParameterLocation::Query, false),
+    ]
+  );
+  // The explicit parameter replaced the derived one.
+  assert!(openapi.parameters[1].description.is_some());
+
+  let body = openapi.request_body.expect("json body");
+  assert_eq!(body.content_type, "application/json");
+  let props: Vec<_> = body
+    .schema_properties
+    .iter()
+    .map(|p| (p.name.as_str(), p.property_type.as_str()))
+    .collect();
+  assert_eq!(props, [("name", "string"), ("age", "integer")]);
+}
+
+#[cfg(feature = "schemars")]
+#[tokio::test]
+async fn schema_registry_serves_json_schemas() {
+  use tako::extractors::json::Json;
+  use tako::schemas::SchemaRegistry;
+
+  #[allow(dead_code)]
+  #[derive(schemars::JsonSchema)]
+  struct Order {
+    /// Units ordered.
+    quantity: u32,
+    note: Option<String>,
+  }
+
+  assert_eq!(
+    Json::<Order>::json_schema().as_value()["properties"]["quantity"]["description"],
+    "Units ordered."
+  );
+
+  let mut router = Router::new();
+  let registry = SchemaRegistry::new();
+  registry.mount(&mut router, "/schemas/");
+  // Registered after mounting: still served.
+  registry.register::<Order>();
+
+  let resp = router.dispatch(make_req(Method::GET, "/schemas")).await;
+  let all: serde_json::Value = serde_json::from_str(&body_str(resp).await).unwrap();
+  assert_eq!(all["Order"]["required"], serde_json::json!(["quantity"]));
+
+  let resp = router
+    .dispatch(make_req(Method::GET, "/schemas/Order"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  let order: serde_json::Value = serde_json::from_str(&body_str(resp).await).unwrap();
+  assert_eq!(order["title"], "Order");
+
+  let resp = router
+    .dispatch(make_req(Method::GET, "/schemas/Missing"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(all(feature = "schemars", feature = "vespera"))]
+#[test]
+fn openapi_request_body_from_json_schema() {
+  use tako::extractors::json::Json;
+
+  #[allow(dead_code)]
+  #[derive(serde::Deserialize, schemars::JsonSchema)]
+  struct Signup {
+    /// Login e-mail.
+    email: String,
+    age: Option<u8>,
+  }
+
+  let mut router = Router::new();
+  router
+    .route(Method::POST, "/signup", |_: Json<Signup>| async { "ok" })
+    .request_schema::<Signup>();
+
+  let (_, _, openapi) = router.collect_openapi_routes().remove(0);
+  let body = openapi.request_body.expect("json body");
+  let props: Vec<_> = body
+    .schema_properties
+    .iter()
+    .map(|p| {
+      (
+        p.name.as_str(),
+        p.property_type.as_str(),
+        p.description.as_deref(),
+        p.required,
+      )
+    })
+    .collect();
+  assert_eq!(
+    props,
+    [
+      ("age", "integer", None, false),
+      ("email", "string", Some("Login e-mail."), true),
+    ]
+  );
+}
+
+#[test]
+fn generates_typed_clients_from_the_route_table() {
+  use tako::codegen::ClientLanguage;
+  use tako::codegen::generate_client;
+
+  let mut router = Router::new();
+  router.get("/users/{id}", |_: Request| async { "user" });
+  router.post("/users", |_: Request| async { "created" });
+  router.get("/assets/{*path}", |_: Request| async { "asset" });
+
+  let ts = generate_client(&router, ClientLanguage::TypeScript);
+  assert!(ts.contains("export class ApiClient {"));
+  assert!(ts.contains(
+    "getUsersById<T = unknown>(id: string | number): Promise<T> {\n    return this.request<T>(\"GET\", `/users/${encodeURIComponent(String(id))}`);"
+  ));
+  assert!(ts.contains("postUsers<T = unknown>(body?: unknown): Promise<T> {"));
+  assert!(ts.contains("`/assets/${encodeURI(String(path))}`"));
+
+  let rs = generate_client(&router, ClientLanguage::Rust);
+  assert!(rs.contains("pub struct ApiClient {"));
+  assert!(rs.contains(
+    "pub async fn get_users_by_id(&self, id: impl std::fmt::Display) -> Result<http::Response<Bytes>, Error> {"
+  ));
+  assert!(rs.contains("format!(\"/assets/{}\", encode(&path.to_string(), true))"));
+}
+
+#[cfg(feature = "vespera")]
+#[test]
+fn generated_clients_type_query_and_body_from_extractors() {
+  use serde::Deserialize;
+  use tako::codegen::ClientLanguage;
+  use tako::codegen::generate_client;
+  use tako::extractors::json::Json;
+  use tako::extractors::query::Query;
+
+  #[allow(dead_code)]
+  #[derive(Deserialize)]
+  struct Notify {
+    email: Option<bool>,
+    retries: u8,
+  }
+
+  #[allow(dead_code)]
+  #[derive(Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  struct CreateUser {
+    user_name: String,
+    age: u32,
+    nickname: Option<String>,
+  }
+
+  let mut router = Router::new();
+  router
+    .route(
+      Method::POST,
+      "/users",
+      |_: Query<Notify>, _: Json<CreateUser>| async { "ok" },
+    )
+    .operation_id("createUser")
+    .summary("Create a user");
+
+  let ts = generate_client(&router, ClientLanguage::TypeScript);
+  assert!(
+    ts.contains("export interface CreateUserQuery {\n  email?: boolean;\n  retries: number;\n}")
+  );
+  assert!(ts.contains(
+    "export interface CreateUserBody {\n  userName: string;\n  age: number;\n  nickname?: string;\n}"
+  ));
+  assert!(ts.contains(
+    "createUser<T = unknown>(query: CreateUserQuery, body: CreateUserBody): Promise<T> {"
+  ));
+  assert!(ts.contains("   * Create a user\n"));
+
+  let rs = generate_client(&router, ClientLanguage::Rust);
+  assert!(
+    rs.contains("  #[serde(rename = \"userName\")]\n  pub user_name: String,\n  pub age: i64,")
+  );
+  assert!(rs.contains("  pub nickname: Option<String>,"));
+  assert!(rs.contains("pub email: Option<bool>,\n  pub retries: i64,"));
+  assert!(rs.contains("pairs.push((\"retries\", query.retries.to_string()));"));
+  assert!(
+    rs.contains("pub async fn create_user(&self, query: &CreateUserQuery, body: &CreateUserBody)")
+  );
+}
+
+#[tokio::test]
+async fn deprecated_route_emits_lifecycle_headers() {
+  use std::time::SystemTime;
+
+  let since = SystemTime::UNIX_EPOCH + Duration::from_secs(1_767_225_600);
+  let mut router = Router::new();
+  router
+    .get("/v1/users", |_: Request| async {
+      http::Response::builder()
+        .header(http::header::LINK, "</v2/users>; rel=\"successor-version\"")
+        .body(TakoBody::empty())
+        .unwrap()
+    })
+    .deprecated(
+      since,
+      Some(since + Duration::from_secs(86_400)),
+      Some("https://example.com/migrate"),
+    );
+  router.get("/v2/users", |_: Request| async { "ok" });
+
+  let resp = router.dispatch(make_req(Method::GET, "/v1/users")).await;
+  let headers = resp.headers();
+  assert_eq!(headers["deprecation"], "@1767225600");
+  assert_eq!(headers["sunset"], "Fri, 02 Jan 2026 00:00:00 GMT");
+  let links: Vec<_> = headers.get_all(http::header::LINK).iter().collect();
+  assert_eq!(
+    links,
+    [
+      "</v2/users>; rel=\"successor-version\"",
+      "<https://example.com/migrate>; rel=\"deprecation\"",
+    ]
+  );
+
+  let resp = router.dispatch(make_req(Method::GET, "/v2/users")).await;
+  assert!(!resp.headers().contains_key("deprecation"));
+
+  #[cfg(feature = "vespera")]
+  {
+    let openapi = router.collect_openapi_routes();
+    let (_, _, v1) = openapi.iter().find(|(_, p, _)| p == "/v1/users").unwrap();
+    assert!(v1.deprecated);
+  }
+}
+
+#[tokio::test]
+async fn blocking_route_keeps_the_reactor_free() {
+  use std::time::Instant;
+
+  use tako::route::blocking_pool_stats;
+
+  let mut router = Router::new();
+  router
+    .route(Method::GET, "/report", |_req: Request| async {
+      std::thread::sleep(Duration::from_millis(200));
+      "report"
+    })
+    .blocking();
+  router.route(Method::GET, "/ping", |_req: Request| async { "pong" });
+  let router = Arc::new(router);
+
+  let slow = tokio::spawn({
+    let router = router.clone();
+    async move { body_str(router.dispatch(make_req(Method::GET, "/report")).await).await }
+  });
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  assert!(blocking_pool_stats().running >= 1);
+
+  // The test runtime has a single worker; a sleeping handler on it would
+  // hold this request until the report finished.
+  let started = Instant::now();
+  let resp = router.dispatch(make_req(Method::GET, "/ping")).await;
+  assert_eq!(body_str(resp).await, "pong");
+  assert!(started.elapsed() < Duration::from_millis(100));
+
+  assert_eq!(slow.await.unwrap(), "report");
+  assert!(blocking_pool_stats().completed >= 1);
+}
+
+#[tokio::test]
+async fn smoke_tests_run_route_examples() {
+  use serde_json::json;
+  use tako::extractors::json::Json;
+  use tako::extractors::params::Params;
+  use tako::responder::Responder;
+  use tako::route::RouteExample;
+
+  #[derive(serde::Deserialize)]
+  struct UserPath {
+    id: u64,
+  }
+
+  let mut router = Router::new();
+  router
+    .get("/users/{id}", |Params(p): Params<UserPath>| async move {
+      if p.id == 1 {
+        Json(json!({ "id": 1, "name": "alice", "created": "2026-01-01" })).into_response()
+      } else {
+        StatusCode::NOT_FOUND.into_response()
+      }
+    })
+    .example(
+      RouteExample::new("existing user")
+        .param("id", "1")
+        .expect_json(json!({ "name": "alice" })),
+    )
+    .example(
+      RouteExample::new("unknown user")
+        .param("id", "2")
+        .expect_status(StatusCode::NOT_FOUND),
+    )
+    .example(RouteExample::new("no id"));
+  router
+    .route(Method::POST, "/echo", |req: Request| async move {
+      let ct = req.headers()[http::header::CONTENT_TYPE].clone();
+      let body = req.into_body().collect().await.unwrap().to_bytes();
+      http::Response::builder()
+        .status(StatusCode::CREATED)
+        .header(http::header::CONTENT_TYPE, ct)
+        .body(TakoBody::from(body))
+        .unwrap()
+    })
+    .example(
+      RouteExample::new("echoes json")
+        .json(json!({ "n": 1 }))
+        .expect_status(StatusCode::CREATED)
+        .expect_header("content-type", "application/json")
+        .expect_json(json!({ "n": 1 })),
+    )
+    .example(
+      RouteExample::new("wrong text")
+        .text("hi")
+        .expect_text("bye"),
+    );
+
+  let report = router.run_smoke_tests().await;
+  assert_eq!(report.passed, 3, "{report}");
+  assert!(!report.is_success());
+  let failures: Vec<_> = report
+    .failures
+    .iter()
+    .map(|f| (f.path.as_str(), f.example.as_str(), f.problems.clone()))
+    .collect();
+  assert_eq!(
+    failures,
+    [
+      (
+        "/echo",
+        "wrong text",
+        vec![
+          "status: expected 200, got 201".to_string(),
+          "body: expected `bye`, got `hi`".to_string(),
+        ]
+      ),
+      (
+        "/users/{id}",
+        "no id",
+        vec!["path parameter `id` has no value".to_string()]
+      ),
+    ]
+  );
+  assert!(
+    report
+      .to_string()
+      .starts_with("3 example(s) passed, 2 failed")
+  );
+}
+
+#[tokio::test]
+async fn robots_and_sitemap_cover_the_route_table() {
+  use futures_util::stream;
+  use tako::robots::Robots;
+  use tako::sitemap::ChangeFreq;
+  use tako::sitemap::Sitemap;
+  use tako::sitemap::SitemapUrl;
+
+  let mut router = Router::new();
+  for path in ["/", "/about", "/posts/{slug}", "/feed.xml", "/admin/users"] {
+    router.get(path, |_req: Request| async { "page" });
+  }
+  Robots::new()
+    .disallow("/admin/")
+    .sitemap("https://example.com/sitemap.xml")
+    .mount(&mut router);
+  Sitemap::new("https://example.com/")
+    .exclude("/admin/*")
+    .source(|| {
+      stream::iter([
+        SitemapUrl::new("/about").changefreq(ChangeFreq::Yearly),
+        SitemapUrl::new("/posts/a&b")
+          .lastmod("2026-10-01")
+          .priority(0.8),
+      ])
+    })
+    .urls_per_file(2)
+    .mount(&mut router);
+
+  let resp = router.dispatch(make_req(Method::GET, "/robots.txt")).await;
+  assert_eq!(resp.headers()["cache-control"], "public, max-age=3600");
+  let robots = body_str(resp).await;
+  assert!(robots.contains("Disallow: /admin/\n"));
+  assert!(robots.ends_with("Sitemap: https://example.com/sitemap.xml\n"));
+
+  let resp = router.dispatch(make_req(Method::GET, "/sitemap.xml")).await;
+  assert_eq!(
+    resp.headers()["content-type"],
+    "application/xml; charset=utf-8"
+  );
+  let index = body_str(resp).await;
+  assert!(index.contains("<sitemapindex"));
+  assert!(index.contains("<loc>https://example.com/sitemap.xml?page=2</loc>"));
+  assert!(!index.contains("page=3"));
+
+  let page = |n: u32| {
+    let router = &router;
+    async move {
+      body_str(
+        router
+          .dispatch(make_req(Method::GET, &format!("/sitemap.xml?page={n}")))
+          .await,
+      )
+      .await
+    }
+  };
+  let first = page(1).await;
+  assert!(first.contains("<url><loc>https://example.com/</loc></url>"));
+  assert!(
+    first
+      .contains("<url><loc>https://example.com/about</loc><changefreq>yearly</changefreq></url>")
+  );
+  assert!(page(2).await.contains(
+    "<url><loc>https://example.com/posts/a&amp;b</loc><lastmod>2026-10-01</lastmod>\
+     <priority>0.8</priority></url>"
+  ));
+  let all = first + &page(2).await;
+  assert!(!all.contains("/admin") && !all.contains("feed.xml") && !all.contains("{slug}"));
+  let resp = router
+    .dispatch(make_req(Method::GET, "/sitemap.xml?page=3"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn well_known_documents_carry_their_content_types() {
+  use tako::well_known::SecurityTxt;
+  use tako::well_known::WellKnown;
+
+  let mut router = Router::new();
+  let routes = WellKnown::new()
+    .security_txt(SecurityTxt::new(
+      "mailto:security@example.com",
+      "2027-10-01T00:00:00Z",
+    ))
+    .change_password("/account/password")
+    .assetlinks(serde_json::json!([{ "relation": ["delegate_permission/common.handle_all_urls"] }]))
+    .document(
+      "/.well-known/apple-app-site-association",
+      "application/json",
+      "{}",
+    )
+    .max_age(Duration::from_secs(600))
+    .mount(&mut router);
+  assert_eq!(routes.len(), 4);
+
+  let resp = router
+    .dispatch(make_req(Method::GET, "/.well-known/security.txt"))
+    .await;
+  assert_eq!(resp.headers()["content-type"], "text/plain; charset=utf-8");
+  assert_eq!(resp.headers()["cache-control"], "public, max-age=600");
+  assert!(
+    body_str(resp)
+      .await
+      .starts_with("Contact: mailto:security@example.com\n")
+  );
+
+  let resp = router
+    .dispatch(make_req(Method::GET, "/.well-known/change-password"))
+    .await;
+  assert_eq!(resp.status(), StatusCode::FOUND);
+  assert_eq!(resp.headers()["location"], "/account/password");
+
+  let resp = router
+    .dispatch(make_req(Method::GET, "/.well-known/assetlinks.json"))
+    .await;
+  assert_eq!(resp.headers()["content-type"], "application/json");
+  let links: serde_json::Value = serde_json::from_str(&body_str(resp).await).unwrap();
+  assert_eq!(
+    links[0]["relation"][0],
+    "delegate_permission/common.handle_all_urls"
+  );
+
+  let resp = router
+    .dispatch(make_req(
+      Method::GET,
+      "/.well-known/apple-app-site-association",
+    ))
+    .await;
+  assert_eq!(resp.status(), StatusCode::OK);
+}
@@ -168,3 +168,191 @@ async fn server_builder_propagates_405_with_allow() {
 
   handle.shutdown(Duration::from_secs(2)).await;
 }
+
+// Hyper stops polling a sized body after its last frame, and never polls an
+// empty one, so the size callback cannot wait for the trailing `None`.
+#[tokio::test]
+async fn body_inspector_reports_response_sizes_on_a_live_connection() {
+  use std::sync::Arc;
+  use std::sync::Mutex;
+
+  use tako::middleware::IntoMiddleware;
+  use tako::middleware::body_inspector::BodyInspector;
+
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+
+  let sizes = Arc::new(Mutex::new(Vec::new()));
+  let mut router = Router::new();
+  router.get("/ping", hello);
+  router.get("/empty", |_req: Request| async { "" });
+  let sink = Arc::clone(&sizes);
+  router.middleware(
+    BodyInspector::new()
+      .on_response_size(move |n| sink.lock().unwrap().push(n))
+      .into_middleware(),
+  );
+
+  let handle = Server::builder().build().spawn_http(listener, router);
+  tokio::time::sleep(Duration::from_millis(50)).await;
+
+  // Keep-alive: the connection stays open, so only the body's own end
+  // signal can trigger the callback.
+  let mut stream = TcpStream::connect(&addr).await.unwrap();
+  for (path, tail, expected) in [("/ping", "\r\n\r\nok", 2), ("/empty", "\r\n\r\n", 0)] {
+    stream
+      .write_all(format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").as_bytes())
+      .await
+      .unwrap();
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 512];
+    while !buf.ends_with(tail.as_bytes()) {
+      let n = stream.read(&mut chunk).await.unwrap();
+      assert!(n > 0, "connection closed early");
+      buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let reported = tokio::time::timeout(Duration::from_secs(2), async {
+      loop {
+        if let Some(n) = sizes.lock().unwrap().pop() {
+          break n;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+      }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("size callback never ran for {path}"));
+    assert_eq!(reported, expected);
+  }
+  assert!(sizes.lock().unwrap().is_empty(), "callback ran twice");
+  drop(stream);
+
+  handle.shutdown(Duration::from_secs(2)).await;
+}
+
+#[cfg(feature = "http2")]
+#[tokio::test]
+async fn server_builder_h2c_serves_http2_and_http1_on_one_port() {
+  use http_body_util::BodyExt;
+  use http_body_util::Empty;
+  use hyper_util::rt::TokioExecutor;
+  use hyper_util::rt::TokioIo;
+
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+
+  let mut router = Router::new();
+  router.get("/ping", |req: Request| async move {
+    format!("{:?}", req.version())
+  });
+
+  let handle = Server::builder()
+    .h2c(true)
+    .build()
+    .spawn_http(listener, router);
+  tokio::time::sleep(Duration::from_millis(50)).await;
+
+  // Prior knowledge: the client opens with the HTTP/2 preface.
+  let stream = TcpStream::connect(addr).await.unwrap();
+  let (mut sender, conn) =
+    hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+      .await
+      .unwrap();
+  tokio::spawn(conn);
+  let req = http::Request::builder()
+    .uri(format!("http://{addr}/ping"))
+    .body(Empty::<bytes::Bytes>::new())
+    .unwrap();
+  let resp = sender.send_request(req).await.unwrap();
+  assert_eq!(resp.version(), http::Version::HTTP_2);
+  let body = resp.into_body().collect().await.unwrap().to_bytes();
+  assert_eq!(&body[..], b"HTTP/2.0");
+  drop(sender);
+
+  let status_line = fetch_status_line(&addr).await;
+  assert!(
+    status_line.starts_with("HTTP/1.1 200"),
+    "status line was {status_line:?}"
+  );
+
+  handle.shutdown(Duration::from_secs(2)).await;
+}
+
+#[tokio::test]
+async fn server_builder_advertises_h3_via_alt_svc() {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+
+  let mut router = Router::new();
+  router.get("/ping", hello);
+  router.get("/custom", |_req: Request| async {
+    http::Response::builder()
+      .header(http::header::ALT_SVC, "clear")
+      .body(TakoBody::empty())
+      .unwrap()
+  });
+
+  let handle = Server::builder()
+    .advertise_h3(8443, Duration::from_secs(3600))
+    .build()
+    .spawn_http(listener, router);
+  tokio::time::sleep(Duration::from_millis(50)).await;
+
+  let fetch = |path: &'static str| async move {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let req = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+    stream.write_all(req.as_bytes()).await.unwrap();
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).await.unwrap();
+    String::from_utf8_lossy(&buf).to_lowercase()
+  };
+  assert!(
+    fetch("/ping")
+      .await
+      .contains("alt-svc: h3=\":8443\"; ma=3600\r\n")
+  );
+  // A handler's own Alt-Svc wins.
+  assert!(fetch("/custom").await.contains("alt-svc: clear\r\n"));
+
+  handle.shutdown(Duration::from_secs(2)).await;
+}
+
+#[tokio::test]
+async fn server_builder_max_connections_defers_accept() {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+
+  let mut router = Router::new();
+  router.get("/ping", hello);
+
+  let handle = Server::builder()
+    .max_connections(1)
+    .header_read_timeout(None)
+    .tcp_nodelay(true)
+    .tcp_keepalive(Duration::from_secs(60))
+    .build()
+    .spawn_http(listener, router);
+  tokio::time::sleep(Duration::from_millis(50)).await;
+
+  // An idle connection holds the only slot.
+  let idle = TcpStream::connect(addr).await.unwrap();
+  tokio::time::sleep(Duration::from_millis(50)).await;
+
+  let mut waiting = TcpStream::connect(addr).await.unwrap();
+  waiting
+    .write_all(b"GET /ping HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+    .await
+    .unwrap();
+  let mut buf = Vec::new();
+  let early = tokio::time::timeout(Duration::from_millis(200), waiting.read_to_end(&mut buf)).await;
+  assert!(early.is_err(), "second connection was served past the cap");
+
+  drop(idle);
+  tokio::time::timeout(Duration::from_secs(2), waiting.read_to_end(&mut buf))
+    .await
+    .expect("served once the slot frees up")
+    .unwrap();
+  assert!(String::from_utf8_lossy(&buf).starts_with("HTTP/1.1 200 OK"));
+
+  handle.shutdown(Duration::from_secs(2)).await;
+}
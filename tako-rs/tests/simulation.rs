@@ -0,0 +1,208 @@
+//! Time-dependent behaviour driven by `sim::Simulation`. Each test advances
+//! a paused clock instead of sleeping, so none of them take wall time.
+#![cfg(all(feature = "simulation", not(feature = "compio")))]
+#![cfg_attr(not(feature = "plugins"), allow(unused_imports, dead_code))]
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use http::Method;
+use http::StatusCode;
+use tako::body::TakoBody;
+use tako::queue::Job;
+use tako::queue::Queue;
+use tako::router::Router;
+use tako::sim::Simulation;
+use tako::types::Request;
+
+fn make_req(method: Method, uri: &str) -> Request {
+  let mut req = http::Request::builder()
+    .method(method)
+    .uri(uri)
+    .body(TakoBody::empty())
+    .unwrap();
+  req
+    .extensions_mut()
+    .insert(std::net::SocketAddr::from(([203, 0, 113, 7], 40000)));
+  req
+}
+
+#[tokio::test]
+async fn clock_follows_the_simulation() {
+  let sim = Simulation::start();
+  let wall = sim.system_now();
+  let start = tako::clock::now();
+
+  sim.advance(Duration::from_secs(3600)).await;
+  assert_eq!(sim.elapsed(), Duration::from_secs(3600));
+  assert_eq!(tako::clock::now() - start, Duration::from_secs(3600));
+  let moved = sim.system_now().duration_since(wall).unwrap();
+  assert!(moved >= Duration::from_secs(3599) && moved <= Duration::from_secs(3601));
+}
+
+#[tokio::test]
+async fn delayed_jobs_run_when_the_clock_reaches_them() {
+  let sim = Simulation::start();
+  let runs = Arc::new(AtomicU32::new(0));
+  let queue = Queue::new();
+  let r = runs.clone();
+  queue.register("remind", move |_job: Job| {
+    let r = r.clone();
+    async move {
+      r.fetch_add(1, Ordering::SeqCst);
+      Ok(())
+    }
+  });
+  queue.start();
+  queue
+    .push_delayed("remind", &(), Duration::from_secs(24 * 3600))
+    .await
+    .unwrap();
+
+  sim
+    .run_for(Duration::from_secs(23 * 3600), Duration::from_secs(60))
+    .await;
+  assert_eq!(runs.load(Ordering::SeqCst), 0);
+
+  sim
+    .run_for(Duration::from_secs(3600), Duration::from_secs(60))
+    .await;
+  sim
+    .run_for(Duration::from_millis(500), Duration::from_millis(100))
+    .await;
+  assert_eq!(runs.load(Ordering::SeqCst), 1);
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn rate_limit_refills_on_simulated_time() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::rate_limiter::RateLimiterBuilder;
+
+  let sim = Simulation::start();
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |_req: Request| async { "ok" });
+  RateLimiterBuilder::new()
+    .requests_per_minute(2)
+    .build()
+    .setup(&router)
+    .unwrap();
+
+  for _ in 0..2 {
+    let resp = router.dispatch(make_req(Method::GET, "/")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+  }
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+  sim.advance(Duration::from_secs(60)).await;
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn sliding_window_weights_the_previous_window() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::rate_limiter::Algorithm;
+  use tako::plugins::rate_limiter::RateLimiterBuilder;
+
+  let sim = Simulation::start();
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |_req: Request| async { "ok" });
+  RateLimiterBuilder::new()
+    .requests_per_minute(4)
+    .algorithm(Algorithm::SlidingWindow)
+    .build()
+    .setup(&router)
+    .unwrap();
+
+  for _ in 0..4 {
+    let resp = router.dispatch(make_req(Method::GET, "/")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+  }
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+  assert_eq!(resp.headers()["retry-after"], "75");
+
+  // A fresh fixed window, but the previous one still covers all of the
+  // sliding window.
+  sim.advance(Duration::from_secs(60)).await;
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+  assert_eq!(resp.headers()["retry-after"], "15");
+
+  sim.advance(Duration::from_secs(15)).await;
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+  assert_eq!(resp.headers()["ratelimit-remaining"], "0");
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn gcra_allows_the_burst_then_paces() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::rate_limiter::Algorithm;
+  use tako::plugins::rate_limiter::RateLimiterBuilder;
+
+  let sim = Simulation::start();
+  let mut router = Router::new();
+  router.route(Method::GET, "/", |_req: Request| async { "ok" });
+  RateLimiterBuilder::new()
+    .requests_per_minute(2)
+    .algorithm(Algorithm::Gcra)
+    .build()
+    .setup(&router)
+    .unwrap();
+
+  for _ in 0..2 {
+    let resp = router.dispatch(make_req(Method::GET, "/")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+  }
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+  assert_eq!(resp.headers()["retry-after"], "30");
+
+  sim.advance(Duration::from_secs(30)).await;
+  let resp = router.dispatch(make_req(Method::GET, "/")).await;
+  assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[cfg(feature = "plugins")]
+#[tokio::test]
+async fn idempotency_keys_expire_on_simulated_time() {
+  use tako::plugins::TakoPlugin;
+  use tako::plugins::idempotency::IdempotencyBuilder;
+
+  let sim = Simulation::start();
+  let calls = Arc::new(AtomicU32::new(0));
+  let mut router = Router::new();
+  let c = calls.clone();
+  router.route(Method::POST, "/orders", move |_req: Request| {
+    let c = c.clone();
+    async move { c.fetch_add(1, Ordering::SeqCst).to_string() }
+  });
+  IdempotencyBuilder::new()
+    .ttl_secs(3600)
+    .build()
+    .setup(&router)
+    .unwrap();
+  let post = || {
+    let mut req = make_req(Method::POST, "/orders");
+    req
+      .headers_mut()
+      .insert("idempotency-key", "order-1".parse().unwrap());
+    req
+  };
+
+  router.dispatch(post()).await;
+  sim.advance(Duration::from_secs(3599)).await;
+  router.dispatch(post()).await;
+  assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+  sim.advance(Duration::from_secs(2)).await;
+  router.dispatch(post()).await;
+  assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
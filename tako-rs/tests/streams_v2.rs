@@ -47,3 +47,140 @@ fn sse_event_data_with_newline_splits() {
   assert!(s.contains("data: line1\n"));
   assert!(s.contains("data: line2\n"));
 }
+
+#[test]
+fn sse_event_builder_chains_every_field() {
+  use tako::sse::Event;
+
+  let ev = Event::default()
+    .event("update")
+    .id("42")
+    .retry(5000)
+    .comment("v1")
+    .json_data(&serde_json::json!({ "count": 3 }))
+    .unwrap();
+  let bytes = ev.encode();
+  let s = std::str::from_utf8(&bytes).unwrap();
+  assert_eq!(
+    s,
+    ": v1\nevent: update\nid: 42\nretry: 5000\ndata: {\"count\":3}\n\n"
+  );
+}
+
+#[tokio::test]
+async fn sse_new_streams_events_with_keep_alive() {
+  use futures_util::StreamExt;
+  use futures_util::stream;
+  use http_body_util::BodyExt;
+  use tako::responder::Responder;
+  use tako::sse::Event;
+  use tako::sse::Sse;
+
+  let events = stream::iter([Event::default().event("tick").data("1")]).chain(stream::pending());
+  let resp = Sse::new(events)
+    .keep_alive(Duration::from_millis(10))
+    .into_response();
+  assert_eq!(
+    resp.headers().get("content-type").unwrap(),
+    "text/event-stream"
+  );
+
+  let mut body = resp.into_body();
+  let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+  assert_eq!(&first[..], b"event: tick\ndata: 1\n\n");
+  let ping = tokio::time::timeout(Duration::from_secs(5), body.frame())
+    .await
+    .expect("keep-alive comment within the period")
+    .unwrap()
+    .unwrap()
+    .into_data()
+    .unwrap();
+  assert_eq!(&ping[..], b":keepalive\n\n");
+}
+
+#[tokio::test]
+async fn sse_stream_ends_when_session_expires() {
+  use futures_util::StreamExt;
+  use futures_util::stream;
+  use http_body_util::BodyExt;
+  use tako::responder::Responder;
+  use tako::session_expiry::SessionExpiry;
+  use tako::sse::Event;
+  use tako::sse::Sse;
+
+  let events = stream::iter([Event::default().data("hi")]).chain(stream::pending());
+  let resp = Sse::new(events)
+    .session_expiry(SessionExpiry::after(Duration::from_millis(20)))
+    .into_response();
+
+  let mut body = resp.into_body();
+  let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+  assert_eq!(&first[..], b"data: hi\n\n");
+  let end = tokio::time::timeout(Duration::from_secs(5), body.frame())
+    .await
+    .expect("stream ends at expiry");
+  assert!(end.is_none());
+}
+
+#[cfg(feature = "sse-compression")]
+#[tokio::test]
+async fn sse_compresses_large_events_for_opted_in_clients() {
+  use std::io::Read as _;
+
+  use base64::Engine as _;
+  use futures_util::stream;
+  use http_body_util::BodyExt;
+  use tako::body::TakoBody;
+  use tako::responder::Responder;
+  use tako::sse::Event;
+  use tako::sse::EventCompression;
+  use tako::sse::EventEncoding;
+  use tako::sse::Sse;
+
+  let req = |uri: &str| {
+    http::Request::builder()
+      .uri(uri)
+      .body(TakoBody::empty())
+      .unwrap()
+  };
+  assert!(EventCompression::negotiate(&req("/feed")).is_none());
+  assert!(EventCompression::negotiate(&req("/feed?event_encoding=br")).is_none());
+  let negotiated =
+    EventCompression::negotiate(&req("/feed?event_encoding=br,deflate,gzip")).unwrap();
+  assert_eq!(negotiated.encoding(), EventEncoding::Deflate);
+  let mut with_header = req("/feed?event_encoding=deflate");
+  with_header
+    .headers_mut()
+    .insert("accept-event-encoding", "gzip".parse().unwrap());
+  let compression = EventCompression::negotiate(&with_header).unwrap();
+  assert_eq!(compression.encoding(), EventEncoding::Gzip);
+
+  let large = serde_json::json!({ "rows": vec!["the same row again"; 200] }).to_string();
+  let events = stream::iter([
+    Event::default().event("small").data("{\"ok\":true}"),
+    Event::default().event("large").data(large.clone()),
+  ]);
+  let mut body = Sse::new(events)
+    .compress(Some(compression.threshold(256)))
+    .into_response()
+    .into_body();
+
+  let small = body.frame().await.unwrap().unwrap().into_data().unwrap();
+  assert_eq!(&small[..], b"event: small\ndata: {\"ok\":true}\n\n");
+
+  let frame = body.frame().await.unwrap().unwrap().into_data().unwrap();
+  assert!(frame.len() < large.len() / 4);
+  let frame = std::str::from_utf8(&frame).unwrap();
+  let encoded = frame
+    .strip_prefix("event: large\ndata: gzip;base64,")
+    .and_then(|rest| rest.strip_suffix("\n\n"))
+    .expect("compressed data field");
+  let gzipped = base64::engine::general_purpose::STANDARD
+    .decode(encoded)
+    .unwrap();
+  let mut decoded = String::new();
+  flate2::read::GzDecoder::new(&gzipped[..])
+    .read_to_string(&mut decoded)
+    .unwrap();
+  assert_eq!(decoded, large);
+}
@@ -0,0 +1,86 @@
+//! Upload scanning. Lives in its own binary because the scanner is read from
+//! global state.
+#![cfg(feature = "multipart")]
+
+use http::Method;
+use http::StatusCode;
+use serde::Deserialize;
+use tako::body::TakoBody;
+use tako::extractors::FromRequest;
+use tako::extractors::multipart::MultipartConfig;
+use tako::extractors::multipart::ScanTarget;
+use tako::extractors::multipart::ScanVerdict;
+use tako::extractors::multipart::TakoTypedMultipart;
+use tako::extractors::multipart::TypedMultipartError;
+use tako::extractors::multipart::UploadedFile;
+use tako::responder::Responder;
+
+#[derive(Deserialize)]
+struct Form {
+  file: UploadedFile,
+}
+
+fn upload(content: &str) -> tako::types::Request {
+  let body = format!(
+    "--X\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\r\n{content}\r\n--X--\r\n"
+  );
+  http::Request::builder()
+    .method(Method::POST)
+    .uri("/files")
+    .header("content-type", "multipart/form-data; boundary=X")
+    .body(TakoBody::from(body))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn infected_uploads_are_rejected_and_quarantined() {
+  let quarantine = std::env::temp_dir().join(format!("tako-quarantine-{}", std::process::id()));
+  tako::state::set_state(
+    MultipartConfig::new()
+      .scanner(|target: ScanTarget| async move {
+        let bytes = target.bytes().await?;
+        if bytes.as_ref() == b"boom" {
+          Ok(ScanVerdict::Infected("Test-Signature".to_string()))
+        } else if bytes.as_ref() == b"offline" {
+          Err(anyhow::anyhow!("scanner offline"))
+        } else {
+          Ok(ScanVerdict::Clean)
+        }
+      })
+      .quarantine_dir(&quarantine),
+  );
+
+  let mut req = upload("hello");
+  let clean = TakoTypedMultipart::<Form, UploadedFile>::from_request(&mut req)
+    .await
+    .unwrap()
+    .data
+    .file;
+  assert_eq!(clean.size, 5);
+
+  let mut req = upload("boom");
+  let Err(err) = TakoTypedMultipart::<Form, UploadedFile>::from_request(&mut req).await else {
+    panic!("infected upload accepted");
+  };
+  assert!(matches!(&err, TypedMultipartError::Infected(sig) if sig == "Test-Signature"));
+  assert_eq!(
+    err.into_response().status(),
+    StatusCode::UNPROCESSABLE_ENTITY
+  );
+  let quarantined: Vec<_> = std::fs::read_dir(&quarantine)
+    .unwrap()
+    .map(|e| std::fs::read(e.unwrap().path()).unwrap())
+    .collect();
+  assert_eq!(quarantined, vec![b"boom".to_vec()]);
+
+  // Scanner errors fail closed.
+  let mut req = upload("offline");
+  let Err(err) = TakoTypedMultipart::<Form, UploadedFile>::from_request(&mut req).await else {
+    panic!("unscanned upload accepted");
+  };
+  assert_eq!(
+    err.into_response().status(),
+    StatusCode::SERVICE_UNAVAILABLE
+  );
+  std::fs::remove_dir_all(&quarantine).unwrap();
+}